@@ -0,0 +1,105 @@
+//! Generators of large synthetic programs, and of goals against them
+//! whose solutions are known.
+
+use std::fmt::Write;
+
+/// The shape of a generated program:
+///
+/// - `structs` structs `S0`, `S1`, ..., each of which implements every
+///   trait;
+/// - `traits` traits `T0`, `T1`, ..., each of which has the next one as
+///   a supertrait, so that the where clauses of `T0` form a chain of
+///   `traits` bounds;
+/// - a struct `Wrap<T>`, which implements each trait when `T` does, and
+///   so takes `depth` steps to prove a trait for when nested `depth`
+///   times;
+/// - a struct `Unimplemented`, which implements no trait.
+#[derive(Copy, Clone, Debug)]
+pub struct Workload {
+    pub structs: usize,
+    pub traits: usize,
+    pub depth: usize,
+}
+
+impl Workload {
+    pub fn program(&self) -> String {
+        let mut program = String::new();
+        for t in 0..self.traits {
+            if t + 1 < self.traits {
+                writeln!(program, "trait T{} where Self: T{} {{ }}", t, t + 1).unwrap();
+            } else {
+                writeln!(program, "trait T{} {{ }}", t).unwrap();
+            }
+        }
+        for s in 0..self.structs {
+            writeln!(program, "struct S{} {{ }}", s).unwrap();
+            for t in 0..self.traits {
+                writeln!(program, "impl T{} for S{} {{ }}", t, s).unwrap();
+            }
+        }
+        writeln!(program, "struct Wrap<T> {{ }}").unwrap();
+        for t in 0..self.traits {
+            writeln!(program, "impl<T> T{} for Wrap<T> where T: T{} {{ }}", t, t).unwrap();
+        }
+        writeln!(program, "struct Unimplemented {{ }}").unwrap();
+        program
+    }
+
+    /// Representative goals against the program, with the solutions that
+    /// they have.
+    pub fn goals(&self) -> Vec<Goal> {
+        let last = self.traits - 1;
+        vec![
+            Goal {
+                name: "nested",
+                text: format!("{}: T0", self.wrap("S0")),
+                expected: "Unique",
+            },
+            Goal {
+                name: "nested-unimplemented",
+                text: format!("{}: T0", self.wrap("Unimplemented")),
+                expected: "No possible solution",
+            },
+            Goal {
+                name: "supertraits",
+                text: format!("forall<T> {{ if (T: T0) {{ T: T{} }} }}", last),
+                expected: "Unique",
+            },
+            Goal {
+                name: "search",
+                text: format!("exists<T> {{ T: T{} }}", last),
+                expected: "Ambiguous",
+            },
+        ]
+    }
+
+    /// A conjunction of `count` goals that hold and share no variables,
+    /// each like the "nested" goal of `goals` for a struct of its own, as
+    /// far as there are structs.
+    #[cfg_attr(not(feature = "parallel"), allow(dead_code))]
+    pub fn independent_goals(&self, count: usize) -> String {
+        let goals: Vec<_> = (0..count.min(self.structs))
+            .map(|s| format!("{}: T0", self.wrap(&format!("S{}", s))))
+            .collect();
+        goals.join(", ")
+    }
+
+    /// `ty` within `depth` `Wrap`s.
+    fn wrap(&self, ty: &str) -> String {
+        format!(
+            "{}{}{}",
+            "Wrap<".repeat(self.depth),
+            ty,
+            ">".repeat(self.depth)
+        )
+    }
+}
+
+/// A goal of a workload.
+pub struct Goal {
+    pub name: &'static str,
+    pub text: String,
+
+    /// The start of the solution of the goal, as it is displayed.
+    pub expected: &'static str,
+}
@@ -0,0 +1,155 @@
+//! Benchmarks of lowering and solving, on generated programs of
+//! increasing size; see `generate`. Run them with `cargo bench`, and
+//! compare them across changes with `cargo bench -- --save-baseline` and
+//! `--baseline`. The benchmarks of solving goals in parallel need
+//! `--features parallel`.
+
+mod generate;
+
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::interner::ChalkIr;
+use chalk_integration::query::LoweringDatabase;
+use chalk_integration::tls;
+use chalk_solve::ext::*;
+use chalk_solve::{RustIrDatabase, SolverChoice};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use generate::Workload;
+
+// The recursive solver gives up on goals whose size is 30 or more, so
+// the `depth` of a workload stays below that.
+const WORKLOADS: &[(&str, Workload)] = &[
+    (
+        "small",
+        Workload {
+            structs: 10,
+            traits: 5,
+            depth: 8,
+        },
+    ),
+    (
+        "large",
+        Workload {
+            structs: 40,
+            traits: 20,
+            depth: 24,
+        },
+    ),
+];
+
+fn solvers() -> Vec<(&'static str, SolverChoice)> {
+    vec![
+        // The default limits of the solvers are too small for the most
+        // nested goals.
+        ("slg", SolverChoice::slg(64, None)),
+        ("recursive", SolverChoice::recursive_with_overflow_depth(64)),
+    ]
+}
+
+fn lowering(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lowering");
+    // Checking the large workload takes seconds.
+    group.sample_size(10);
+    for (name, workload) in WORKLOADS {
+        let program = workload.program();
+        group.bench_with_input(BenchmarkId::new("lower", name), &program, |b, program| {
+            b.iter(|| {
+                let db = ChalkDatabase::with(program, SolverChoice::default());
+                db.program_ir().unwrap()
+            })
+        });
+        // Checking includes the well-formedness and coherence checks,
+        // which solve goals of their own.
+        group.bench_with_input(BenchmarkId::new("check", name), &program, |b, program| {
+            b.iter(|| {
+                let db = ChalkDatabase::with(program, SolverChoice::default());
+                db.checked_program().unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn solving(c: &mut Criterion) {
+    for (name, workload) in WORKLOADS {
+        let mut group = c.benchmark_group(format!("solving/{}", name));
+        for (solver_name, solver_choice) in solvers() {
+            let db = ChalkDatabase::with(&workload.program(), solver_choice);
+            let program = db.checked_program().unwrap();
+            tls::set_current_program(&program, || {
+                for goal in workload.goals() {
+                    let lowered = db
+                        .parse_and_lower_goal(&goal.text)
+                        .unwrap()
+                        .into_peeled_goal(db.interner());
+
+                    // Check the solution once, so that a change that breaks
+                    // the solver is not mistaken for one that speeds it up.
+                    let solution = match db.solve(&lowered) {
+                        Some(solution) => solution.display(&ChalkIr).to_string(),
+                        None => "No possible solution".to_string(),
+                    };
+                    assert!(
+                        solution.starts_with(goal.expected),
+                        "`{}` with the {} solver: {}",
+                        goal.text,
+                        solver_name,
+                        solution
+                    );
+
+                    // Each iteration uses a new solver, as a solver caches
+                    // the solutions of the goals it solved.
+                    let id = BenchmarkId::new(goal.name, solver_name);
+                    group.bench_with_input(id, &lowered, |b, lowered| {
+                        b.iter(|| solver_choice.into_solver().solve(&db, lowered))
+                    });
+                }
+            });
+        }
+        group.finish();
+    }
+}
+
+/// Solving a conjunction of independent goals of each workload with a
+/// single solver, and with `solve_parallel`, which solves each of them on
+/// a thread of its own. The threads intern types at the same time, so
+/// the difference also shows how much they contend on the type table of
+/// the interner.
+#[cfg(feature = "parallel")]
+fn parallel(c: &mut Criterion) {
+    let solver_choice = SolverChoice::recursive_with_overflow_depth(64);
+    let mut group = c.benchmark_group("parallel");
+    for (name, workload) in WORKLOADS {
+        let db = ChalkDatabase::with(&workload.program(), solver_choice);
+        let program = db.checked_program().unwrap();
+        tls::set_current_program(&program, || {
+            let goal = workload.independent_goals(8);
+            let lowered = db
+                .parse_and_lower_goal(&goal)
+                .unwrap()
+                .into_peeled_goal(db.interner());
+            let solution = solver_choice.solve_parallel(&*program, &lowered);
+            assert!(
+                solution.as_ref().map_or(false, |s| s.is_unique()),
+                "`{}`: {:?}",
+                goal,
+                solution
+            );
+
+            let id = BenchmarkId::new("sequential", name);
+            group.bench_with_input(id, &lowered, |b, lowered| {
+                b.iter(|| solver_choice.into_solver().solve(&db, lowered))
+            });
+            let id = BenchmarkId::new("parallel", name);
+            group.bench_with_input(id, &lowered, |b, lowered| {
+                b.iter(|| solver_choice.solve_parallel(&*program, lowered))
+            });
+        });
+    }
+    group.finish();
+}
+
+#[cfg(not(feature = "parallel"))]
+criterion_group!(benches, lowering, solving);
+#[cfg(feature = "parallel")]
+criterion_group!(benches, lowering, solving, parallel);
+criterion_main!(benches);
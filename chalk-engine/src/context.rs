@@ -76,6 +76,9 @@ pub trait Context: Clone + Debug {
     /// (but not verified).
     type RegionConstraint: Clone + Debug;
 
+    /// A region constraint, without the environment it was found in.
+    type Constraint: Debug;
+
     /// Represents a goal along with an environment.
     type GoalInEnvironment: Debug + Clone + Eq + Hash;
 
@@ -129,6 +132,13 @@ pub trait Context: Clone + Debug {
         goal: Self::Goal,
     ) -> Self::GoalInEnvironment;
 
+    /// Given an environment and a constraint, glue them together to
+    /// create a `RegionConstraint`.
+    fn constraint_in_environment(
+        environment: &Self::Environment,
+        constraint: Self::Constraint,
+    ) -> Self::RegionConstraint;
+
     /// Extracts the inner normalized substitution from a canonical ex-clause.
     fn inference_normalized_subst_from_ex_clause(
         canon_ex_clause: &Self::CanonicalExClause,
@@ -251,6 +261,9 @@ pub trait ContextOps<C: Context>: Sized + Clone + Debug + AggregateOps<C> {
     /// Upcast this domain goal into a more general goal.
     fn into_goal(&self, domain_goal: C::DomainGoal) -> C::Goal;
 
+    /// Creates a goal that holds if any of `goals` holds.
+    fn into_any_goal(&self, goals: Vec<C::Goal>) -> C::Goal;
+
     fn is_trivial_substitution(
         &self,
         u_canon: &C::UCanonicalGoalInEnvironment,
@@ -266,6 +279,36 @@ pub trait ContextOps<C: Context>: Sized + Clone + Debug + AggregateOps<C> {
     /// `HhGoal`, but the goals contained within are left as context
     /// goals.
     fn into_hh_goal(&self, goal: C::Goal) -> HhGoal<C>;
+
+    /// Reports a step taken by the solver, so that it can be traced.
+    /// Does nothing by default.
+    fn on_event(&self, _event: SolverEvent<'_, C>) {}
+}
+
+/// A step taken by the solver, reported with `ContextOps::on_event`.
+#[derive(Debug)]
+pub enum SolverEvent<'a, C: Context> {
+    /// A table was created for `goal`, with `depth` tables already on
+    /// the stack.
+    TableCreated {
+        goal: &'a C::UCanonicalGoalInEnvironment,
+        depth: usize,
+    },
+
+    /// The existing table for `goal` was found, so the answers found
+    /// for it are reused.
+    TableReused {
+        goal: &'a C::UCanonicalGoalInEnvironment,
+    },
+
+    /// `clause` was resolved against `goal`, the goal of a new table.
+    /// If `unified` is false, the consequence of `clause` does not
+    /// unify with the goal.
+    ClauseTried {
+        goal: &'a C::UCanonicalGoalInEnvironment,
+        clause: &'a C::ProgramClause,
+        unified: bool,
+    },
 }
 
 /// Methods for combining solutions to yield an aggregate solution.
@@ -332,6 +375,19 @@ pub trait UnificationOps<C: Context> {
         constraints: Vec<C::RegionConstraint>,
     ) -> C::CanonicalConstrainedSubst;
 
+    /// Removes the placeholders of universes `num_universes` and up,
+    /// which were introduced while solving a goal with `num_universes`
+    /// universes, from the region constraints of one of its answers.
+    /// Returns `None` if the constraints cannot hold for every value of
+    /// those placeholders.
+    // Used by: logic
+    fn resolve_escaping_placeholders(
+        &mut self,
+        interner: &C::Interner,
+        num_universes: usize,
+        constraints: Vec<C::RegionConstraint>,
+    ) -> Option<Vec<C::RegionConstraint>>;
+
     // Used by: logic
     fn canonicalize_answer_subst(
         &mut self,
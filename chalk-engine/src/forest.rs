@@ -3,6 +3,7 @@ use crate::logic::RootSearchFail;
 use crate::table::AnswerIndex;
 use crate::tables::Tables;
 use crate::{TableIndex, TimeStamp};
+use rustc_hash::FxHashSet;
 use std::fmt::Display;
 
 pub struct Forest<C: Context> {
@@ -35,6 +36,27 @@ impl<C: Context> Forest<C> {
         &self.context
     }
 
+    /// Discards the tables of the goals for which `affected` is true,
+    /// and of the goals that depend on them, directly or not, keeping
+    /// the other tables and their answers. This is used when the program
+    /// changes in a way that only affects some goals. It must not be
+    /// called while a goal is being solved.
+    pub fn invalidate(&mut self, affected: impl Fn(&C::UCanonicalGoalInEnvironment) -> bool) {
+        let mut worklist: Vec<_> = self
+            .tables
+            .indices()
+            .filter(|&table| affected(&self.tables[table].table_goal))
+            .collect();
+        let mut removed = FxHashSet::default();
+        while let Some(table) = worklist.pop() {
+            if removed.insert(table) {
+                worklist.extend(self.tables[table].dependents());
+            }
+        }
+        debug!("invalidate: removing {} tables", removed.len());
+        self.tables.remove(&removed);
+    }
+
     // Gets the next clock TimeStamp. This will never decrease.
     pub(crate) fn increment_clock(&mut self) -> TimeStamp {
         self.clock.increment();
@@ -50,7 +72,7 @@ impl<C: Context> Forest<C> {
         context: &'f impl ContextOps<C>,
         goal: &C::UCanonicalGoalInEnvironment,
     ) -> impl AnswerStream<C> + 'f {
-        let table = self.get_or_create_table_for_ucanonical_goal(context, goal.clone());
+        let table = self.get_or_create_table_for_ucanonical_goal(context, goal.clone(), 0);
         let answer = AnswerIndex::ZERO;
         ForestSolver {
             forest: self,
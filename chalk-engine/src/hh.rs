@@ -9,8 +9,10 @@ pub enum HhGoal<C: Context> {
     Exists(C::BindersGoal),
     Implies(C::ProgramClauses, C::Goal),
     All(Vec<C::Goal>),
+    Any(Vec<C::Goal>),
     Not(C::Goal),
     Unify(C::Variance, C::Parameter, C::Parameter),
+    Constraint(C::Constraint),
     DomainGoal(C::DomainGoal),
 
     /// Indicates something that cannot be proven to be true or false
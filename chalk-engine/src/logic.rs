@@ -1,5 +1,6 @@
 use crate::context::{
-    Context, ContextOps, Floundered, InferenceTable, ResolventOps, TruncateOps, UnificationOps,
+    Context, ContextOps, Floundered, InferenceTable, ResolventOps, SolverEvent, TruncateOps,
+    UnificationOps,
 };
 use crate::fallible::NoSolution;
 use crate::forest::Forest;
@@ -176,6 +177,7 @@ impl<C: Context> Forest<C> {
         context: &impl ContextOps<C>,
         infer: &mut dyn InferenceTable<C>,
         subgoal: &Literal<C>,
+        depth: usize,
     ) -> Option<(TableIndex, C::UniverseMap)> {
         debug_heading!("get_or_create_table_for_subgoal(subgoal={:?})", subgoal);
 
@@ -192,14 +194,17 @@ impl<C: Context> Forest<C> {
         debug!("ucanonical_subgoal={:?}", ucanonical_subgoal);
         debug!("universe_map={:?}", universe_map);
 
-        let table = self.get_or_create_table_for_ucanonical_goal(context, ucanonical_subgoal);
+        let table =
+            self.get_or_create_table_for_ucanonical_goal(context, ucanonical_subgoal, depth);
 
         Some((table, universe_map))
     }
 
     /// Given a u-canonical goal, searches for an existing table. If
     /// one is found, it is returned, but otherwise a new table is
-    /// created (and populated with its initial set of strands).
+    /// created (and populated with its initial set of strands). `depth`
+    /// is the number of tables on the stack, which is only reported to
+    /// `ContextOps::on_event`.
     ///
     /// In terms of the NFTD paper, creating a new table corresponds
     /// to the *New Subgoal* step as well as the *Program Clause
@@ -208,11 +213,13 @@ impl<C: Context> Forest<C> {
         &mut self,
         context: &impl ContextOps<C>,
         goal: C::UCanonicalGoalInEnvironment,
+        depth: usize,
     ) -> TableIndex {
         debug_heading!("get_or_create_table_for_ucanonical_goal({:?})", goal);
 
         if let Some(table) = self.tables.index_of(&goal) {
             debug!("found existing table {:?}", table);
+            context.on_event(SolverEvent::TableReused { goal: &goal });
             return table;
         }
 
@@ -221,6 +228,7 @@ impl<C: Context> Forest<C> {
             self.tables.next_index(),
             goal
         );
+        context.on_event(SolverEvent::TableCreated { goal: &goal, depth });
         let coinductive_goal = context.is_coinductive(&goal);
         let table = self.tables.insert(goal, coinductive_goal);
         self.push_initial_strands(context, table);
@@ -231,9 +239,10 @@ impl<C: Context> Forest<C> {
     /// create the initial set of strands. If the table represents a
     /// domain goal, these strands are created from the program
     /// clauses as well as the clauses found in the environment.  If
-    /// the table represents a non-domain goal, such as `for<T> G`
-    /// etc, then `simplify_hh_goal` is invoked to create a strand
-    /// that breaks the goal down.
+    /// the table represents a disjunction, there is one strand per
+    /// alternative. If the table represents another non-domain goal,
+    /// such as `for<T> G` etc, then `simplify_hh_goal` is invoked to
+    /// create a strand that breaks the goal down.
     ///
     /// In terms of the NFTD paper, this corresponds to the *Program
     /// Clause Resolution* step being applied eagerly, as many times
@@ -262,13 +271,19 @@ impl<C: Context> Forest<C> {
                         for clause in clauses {
                             info!("program clause = {:#?}", clause);
                             let mut infer = infer.clone();
-                            if let Ok(resolvent) = infer.resolvent_clause(
+                            let resolvent = infer.resolvent_clause(
                                 context.interner(),
                                 &environment,
                                 &domain_goal,
                                 &subst,
                                 &clause,
-                            ) {
+                            );
+                            context.on_event(SolverEvent::ClauseTried {
+                                goal: &table_ref.table_goal,
+                                clause: &clause,
+                                unified: resolvent.is_ok(),
+                            });
+                            if let Ok(resolvent) = resolvent {
                                 info!("pushing initial strand with ex-clause: {:#?}", &resolvent,);
                                 let strand = Strand {
                                     infer,
@@ -288,6 +303,32 @@ impl<C: Context> Forest<C> {
                 }
             }
 
+            HhGoal::Any(subgoals) => {
+                for subgoal in subgoals {
+                    let mut infer = infer.clone();
+                    if let Ok(ex_clause) = Self::simplify_hh_goal(
+                        context,
+                        &mut infer,
+                        subst.clone(),
+                        environment.clone(),
+                        context.into_hh_goal(subgoal),
+                    ) {
+                        info!(
+                            "pushing initial strand with ex-clause: {:#?}",
+                            infer.debug_ex_clause(context.interner(), &ex_clause),
+                        );
+                        let strand = Strand {
+                            infer,
+                            ex_clause,
+                            selected_subgoal: None,
+                            last_pursued_time: TimeStamp::default(),
+                        };
+                        let canonical_strand = Self::canonicalize_strand(context, strand);
+                        table_ref.enqueue_strand(canonical_strand);
+                    }
+                }
+            }
+
             hh_goal => {
                 // `canonical_goal` is an HH goal. We can simplify it
                 // into a series of *literals*, all of which must be
@@ -1251,8 +1292,11 @@ impl<'forest, C: Context + 'forest, CO: ContextOps<C> + 'forest> SolveState<'for
                     self.context,
                     &mut strand.infer,
                     &strand.ex_clause.subgoals[subgoal_index],
+                    self.stack.len(),
                 ) {
                     Some((subgoal_table, universe_map)) => {
+                        let table = self.stack.top().table;
+                        self.forest.tables[subgoal_table].add_dependent(table);
                         strand.selected_subgoal = Some(SelectedSubgoal {
                             subgoal_index,
                             subgoal_table,
@@ -1338,6 +1382,17 @@ impl<'forest, C: Context + 'forest, CO: ContextOps<C> + 'forest> SolveState<'for
             return None;
         }
 
+        // The placeholders introduced by `forall` goals while solving
+        // this table's goal must not escape into its answer; if the
+        // constraints on them cannot be expressed without them, this
+        // strand is not an answer after all.
+        let num_universes = C::num_universes(&self.forest.tables[table].table_goal);
+        let constraints = infer.resolve_escaping_placeholders(
+            self.context.interner(),
+            num_universes,
+            constraints,
+        )?;
+
         let subst = infer.canonicalize_answer_subst(
             self.context.interner(),
             subst,
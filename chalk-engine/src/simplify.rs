@@ -49,6 +49,22 @@ impl<C: Context> Forest<C> {
                         pending_goals.push((environment.clone(), context.into_hh_goal(subgoal)));
                     }
                 }
+                HhGoal::Any(mut subgoals) => {
+                    if subgoals.len() == 1 {
+                        let subgoal = subgoals.pop().unwrap();
+                        pending_goals.push((environment, context.into_hh_goal(subgoal)));
+                    } else {
+                        // Each alternative needs a strand of its own, so the
+                        // disjunction becomes a subgoal with its own table;
+                        // see `push_initial_strands`.
+                        ex_clause
+                            .subgoals
+                            .push(Literal::Positive(C::goal_in_environment(
+                                &environment,
+                                context.into_any_goal(subgoals),
+                            )));
+                    }
+                }
                 HhGoal::Not(subgoal) => {
                     ex_clause
                         .subgoals
@@ -65,6 +81,11 @@ impl<C: Context> Forest<C> {
                     &b,
                     &mut ex_clause,
                 )?,
+                HhGoal::Constraint(constraint) => {
+                    ex_clause
+                        .constraints
+                        .push(C::constraint_in_environment(&environment, constraint));
+                }
                 HhGoal::DomainGoal(domain_goal) => {
                     ex_clause
                         .subgoals
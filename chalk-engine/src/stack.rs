@@ -47,6 +47,10 @@ impl<C: Context> Stack<C> {
         self.stack.is_empty()
     }
 
+    pub(super) fn len(&self) -> usize {
+        self.stack.len()
+    }
+
     /// Searches the stack to see if `table` is active. If so, returns
     /// its stack index.
     pub(super) fn is_active(&self, table: TableIndex) -> Option<StackIndex> {
@@ -1,7 +1,7 @@
 use crate::context::Context;
 use crate::strand::CanonicalStrand;
-use crate::Answer;
-use rustc_hash::FxHashMap;
+use crate::{Answer, TableIndex};
+use rustc_hash::{FxHashMap, FxHashSet};
 use std::collections::hash_map::Entry;
 use std::collections::VecDeque;
 use std::mem;
@@ -38,6 +38,10 @@ pub(crate) struct Table<C: Context> {
     /// Stores the active strands that we can "pull on" to find more
     /// answers.
     strands: VecDeque<CanonicalStrand<C>>,
+
+    /// The tables that selected this table as a subgoal, and whose
+    /// answers may thus depend on its answers.
+    dependents: FxHashSet<TableIndex>,
 }
 
 index_struct! {
@@ -58,7 +62,34 @@ impl<C: Context> Table<C> {
             floundered: false,
             answers_hash: FxHashMap::default(),
             strands: VecDeque::new(),
+            dependents: FxHashSet::default(),
+        }
+    }
+
+    /// Records that `table` selected this table as a subgoal.
+    pub(crate) fn add_dependent(&mut self, table: TableIndex) {
+        self.dependents.insert(table);
+    }
+
+    pub(crate) fn dependents(&self) -> impl Iterator<Item = TableIndex> + '_ {
+        self.dependents.iter().cloned()
+    }
+
+    /// Gives the tables referred to by this table the indices given by
+    /// `renumber`, which is `None` for the tables that were removed. The
+    /// subgoals selected by its strands must not have been removed.
+    pub(crate) fn renumber(&mut self, renumber: impl Fn(TableIndex) -> Option<TableIndex>) {
+        for strand in &mut self.strands {
+            if let Some(selected_subgoal) = &mut strand.selected_subgoal {
+                selected_subgoal.subgoal_table = renumber(selected_subgoal.subgoal_table)
+                    .expect("selected subgoal table was removed");
+            }
         }
+        self.dependents = self
+            .dependents
+            .iter()
+            .filter_map(|&t| renumber(t))
+            .collect();
     }
 
     /// Push a strand to the back of the queue of strands to be processed.
@@ -1,7 +1,8 @@
 use crate::context::Context;
 use crate::table::Table;
 use crate::TableIndex;
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::mem;
 use std::ops::{Index, IndexMut};
 
 /// See `Forest`.
@@ -43,6 +44,35 @@ impl<C: Context> Tables<C> {
     pub(super) fn index_of(&self, literal: &C::UCanonicalGoalInEnvironment) -> Option<TableIndex> {
         self.table_indices.get(literal).cloned()
     }
+
+    pub(super) fn indices(&self) -> impl Iterator<Item = TableIndex> {
+        (0..self.tables.len()).map(|value| TableIndex { value })
+    }
+
+    /// Removes the tables in `removed`, and renumbers the others. The
+    /// strands of the other tables must not have selected a removed
+    /// table as a subgoal.
+    pub(super) fn remove(&mut self, removed: &FxHashSet<TableIndex>) {
+        let mut new_indices = vec![None; self.tables.len()];
+        let mut tables = vec![];
+        for (index, table) in self.indices().zip(mem::take(&mut self.tables)) {
+            if !removed.contains(&index) {
+                new_indices[index.value] = Some(TableIndex {
+                    value: tables.len(),
+                });
+                tables.push(table);
+            }
+        }
+        for table in &mut tables {
+            table.renumber(|index| new_indices[index.value]);
+        }
+        self.table_indices = tables
+            .iter()
+            .enumerate()
+            .map(|(value, table)| (table.table_goal.clone(), TableIndex { value }))
+            .collect();
+        self.tables = tables;
+    }
 }
 
 impl<C: Context> Index<TableIndex> for Tables<C> {
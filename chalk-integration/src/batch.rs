@@ -0,0 +1,282 @@
+//! Batch goal files: a `.chalk` program interleaved with `goal { ... }`
+//! blocks, each followed on the same line by the outcome we expect from
+//! the solver:
+//!
+//! ```notrust
+//! struct Foo { }
+//! trait Bar { }
+//! impl Bar for Foo { }
+//!
+//! goal { Foo: Bar } //~ UNIQUE
+//! goal { exists<T> { T: Bar } } //~ UNIQUE [?0 := Foo]
+//! goal { forall<T> { T: Bar } } //~ NO SOLUTION
+//! ```
+//!
+//! The expectation is one of `UNIQUE` (optionally followed by the
+//! expected substitution), `AMBIGUOUS` or `NO SOLUTION`. Everything
+//! outside of the goal blocks is the program, so goals may refer to
+//! named `goal name { .. }` definitions.
+
+use crate::db::ChalkDatabase;
+use crate::error::ChalkError;
+use crate::interner::ChalkIr;
+use crate::query::LoweringDatabase;
+use crate::tls;
+use chalk_solve::ext::*;
+use chalk_solve::{RustIrDatabase, Solution, SolverChoice};
+use std::fmt;
+
+/// The outcome a batch goal is expected to have.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expectation {
+    /// `//~ UNIQUE`, optionally with the expected substitution.
+    Unique(Option<String>),
+
+    /// `//~ AMBIGUOUS`
+    Ambiguous,
+
+    /// `//~ NO SOLUTION`
+    NoSolution,
+}
+
+impl Expectation {
+    fn parse(text: &str) -> Option<Expectation> {
+        match text {
+            "UNIQUE" => Some(Expectation::Unique(None)),
+            "AMBIGUOUS" => Some(Expectation::Ambiguous),
+            "NO SOLUTION" => Some(Expectation::NoSolution),
+            _ if text.starts_with("UNIQUE ") => Some(Expectation::Unique(Some(
+                text["UNIQUE ".len()..].trim().to_string(),
+            ))),
+            _ => None,
+        }
+    }
+
+    fn is_met_by(&self, solution: &Option<Solution<ChalkIr>>) -> bool {
+        match (self, solution) {
+            (Expectation::Unique(None), Some(Solution::Unique(_))) => true,
+            (Expectation::Unique(Some(subst)), Some(solution @ Solution::Unique(_))) => {
+                // Compare the printed forms, ignoring whitespace.
+                let strip = |s: &str| s.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+                strip(&solution.display(&ChalkIr).to_string())
+                    .contains(&strip(&format!("substitution {}", subst)))
+            }
+            (Expectation::Ambiguous, Some(Solution::Ambig(_))) => true,
+            (Expectation::NoSolution, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Expectation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expectation::Unique(None) => write!(f, "a unique solution"),
+            Expectation::Unique(Some(subst)) => {
+                write!(f, "a unique solution with substitution {}", subst)
+            }
+            Expectation::Ambiguous => write!(f, "an ambiguous solution"),
+            Expectation::NoSolution => write!(f, "no solution"),
+        }
+    }
+}
+
+/// A `goal { ... } //~ EXPECTATION` block of a batch file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BatchGoal {
+    /// The line of the file that the block starts on, counting from 1.
+    pub line: usize,
+
+    /// The goal, without the surrounding `goal { }`.
+    pub text: String,
+
+    pub expected: Expectation,
+}
+
+/// A batch file, split into its program and its goals. The goal blocks
+/// are blanked out of the program, which is otherwise the file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Batch {
+    pub program_text: String,
+    pub goals: Vec<BatchGoal>,
+}
+
+impl Batch {
+    /// Splits `text` into the program and the annotated goal blocks.
+    pub fn parse(text: &str) -> Result<Batch, ChalkError> {
+        let mut program_text = String::new();
+        let mut goals = vec![];
+        let mut rest = text;
+        while let Some(start) = find_goal_block(rest) {
+            program_text.push_str(&rest[..start]);
+            let line = text[..text.len() - rest.len() + start]
+                .matches('\n')
+                .count()
+                + 1;
+
+            let block = &rest[start..];
+            let open = block.find('{').unwrap();
+            let close = matching_brace(block, open)
+                .ok_or_else(|| batch_error(line, "unterminated goal block"))?;
+            let goal_text = block[open + 1..close].trim().to_string();
+
+            let after = &block[close + 1..];
+            let end_of_line = after.find('\n').unwrap_or(after.len());
+            let annotation = after[..end_of_line].trim();
+            if !annotation.starts_with("//~") {
+                Err(batch_error(line, "goal is missing a `//~` expectation"))?;
+            }
+            let annotation = annotation["//~".len()..].trim();
+            let expected = Expectation::parse(annotation).ok_or_else(|| {
+                batch_error(line, &format!("unrecognized expectation `{}`", annotation))
+            })?;
+
+            goals.push(BatchGoal {
+                line,
+                text: goal_text,
+                expected,
+            });
+
+            // The block and its expectation are blanked out rather than
+            // removed, so that positions in the program are those in the
+            // file.
+            let end = rest.len() - after.len() + end_of_line;
+            program_text.extend(
+                rest[start..end]
+                    .chars()
+                    .map(|c| if c == '\n' { '\n' } else { ' ' }),
+            );
+            rest = &after[end_of_line..];
+        }
+        program_text.push_str(rest);
+
+        Ok(Batch {
+            program_text,
+            goals,
+        })
+    }
+}
+
+/// A batch goal whose solution did not match its expectation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    pub goal: BatchGoal,
+
+    /// The printed solution that the solver found instead.
+    pub actual: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}: goal `{}`: expected {}, found `{}`",
+            self.goal.line, self.goal.text, self.goal.expected, self.actual
+        )
+    }
+}
+
+/// The outcome of solving the goals of a batch file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BatchReport {
+    /// The number of goals in the file.
+    pub goals: usize,
+
+    /// The goals whose solution did not match their expectation.
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl BatchReport {
+    /// True if every goal met its expectation.
+    pub fn passed(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+impl fmt::Display for BatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let failed = self.mismatches.len();
+        write!(
+            f,
+            "{} goals, {} passed, {} failed",
+            self.goals,
+            self.goals - failed,
+            failed
+        )
+    }
+}
+
+/// Solves every goal of the batch file `text`, reporting those whose
+/// solution does not match their expectation.
+pub fn run_batch(text: &str, solver_choice: SolverChoice) -> Result<BatchReport, ChalkError> {
+    let batch = Batch::parse(text)?;
+    let db = ChalkDatabase::with(&batch.program_text, solver_choice);
+    let program = db.checked_program()?;
+    let goals = batch.goals.len();
+    tls::set_current_program(&program, || {
+        let mut mismatches = vec![];
+        for goal in batch.goals {
+            let lowered = db
+                .parse_and_lower_goal(&goal.text)
+                .map_err(|e| batch_error(goal.line, &e.to_string()))?;
+            let solution = db.solve(&lowered.into_peeled_goal(db.interner()));
+            if !goal.expected.is_met_by(&solution) {
+                let actual = match &solution {
+                    Some(solution) => solution.display(&ChalkIr).to_string(),
+                    None => "No possible solution".to_string(),
+                };
+                mismatches.push(Mismatch { goal, actual });
+            }
+        }
+        Ok(BatchReport { goals, mismatches })
+    })
+}
+
+fn batch_error(line: usize, message: &str) -> ChalkError {
+    let error: Box<dyn std::error::Error> = format!("line {}: {}", line, message).into();
+    error.into()
+}
+
+/// Finds the next `goal {` in `text`, skipping comments and named
+/// `goal name { .. }` definitions, which belong to the program.
+fn find_goal_block(text: &str) -> Option<usize> {
+    let mut offset = 0;
+    while let Some(index) = text[offset..].find("goal") {
+        let start = offset + index;
+        offset = start + "goal".len();
+
+        let line_start = text[..start].rfind('\n').map_or(0, |i| i + 1);
+        let in_comment = text[line_start..start].contains("//");
+        let is_word_start = text[..start]
+            .chars()
+            .last()
+            .map_or(true, |c| !c.is_alphanumeric() && c != '_');
+        if !in_comment && is_word_start && text[offset..].trim_start().starts_with('{') {
+            return Some(start);
+        }
+    }
+    None
+}
+
+/// Finds the `}` closing the `{` at `open`, skipping comments.
+fn matching_brace(text: &str, open: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut in_comment = false;
+    let mut chars = text.char_indices().skip(open).peekable();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\n' => in_comment = false,
+            _ if in_comment => {}
+            '/' if chars.peek().map(|&(_, c)| c) == Some('/') => in_comment = true,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
@@ -0,0 +1,279 @@
+//! Building a `Program` directly, for embedders that already have their
+//! own representation of items and would rather not print them as
+//! source only for `chalk_parse` to read them back.
+//!
+//! ```
+//! use chalk_integration::builder::ProgramBuilder;
+//!
+//! // struct Vec<T> { }
+//! // struct Foo { }
+//! // trait Clone { }
+//! // impl<T> Clone for Vec<T> where T: Clone { }
+//! let program = ProgramBuilder::new()
+//!     .struct_("Vec", 1)
+//!     .struct_("Foo", 0)
+//!     .trait_("Clone", 0)
+//!     .impl_("Clone", 1, |b, params| {
+//!         let vec = b.ty("Vec", &[params[0].clone()]);
+//!         (vec![vec], vec![b.implemented("Clone", &[params[0].clone()])])
+//!     })
+//!     .build();
+//! assert_eq!(program.impl_data.len(), 1);
+//! ```
+
+use crate::interner::ChalkIr;
+use crate::program::Program;
+use crate::{Identifier, RawId, TypeKind, TypeSort};
+use chalk_ir::fold::shift::Shift;
+use chalk_ir::{
+    ApplicationTy, Binders, BoundVar, DebruijnIndex, ImplId, ParameterKind, ParameterKinds,
+    QuantifiedWhereClause, StructId, Substitution, TraitId, TraitRef, Ty, TyData, TypeName,
+    WhereClause,
+};
+use chalk_rust_ir::{
+    Constness, ImplDatum, ImplDatumBound, ImplType, Polarity, Safety, StructDatum,
+    StructDatumBound, StructFlags, TraitDatum, TraitDatumBound, TraitFlags,
+};
+use std::iter;
+use std::sync::Arc;
+
+/// Builds a `Program` item by item. Items are named as in the surface
+/// syntax, and must be declared before they are named. Only type
+/// parameters are supported, and structs have neither fields nor where
+/// clauses; programs that need more than that are still written in the
+/// surface syntax.
+#[derive(Debug)]
+pub struct ProgramBuilder {
+    program: Program,
+    next_id: u32,
+}
+
+impl ProgramBuilder {
+    pub fn new() -> Self {
+        ProgramBuilder {
+            program: Program {
+                struct_ids: Default::default(),
+                struct_kinds: Default::default(),
+                trait_ids: Default::default(),
+                trait_kinds: Default::default(),
+                struct_data: Default::default(),
+                impl_data: Default::default(),
+                impls_by_trait: Default::default(),
+                inherent_impl_data: Default::default(),
+                associated_ty_values: Default::default(),
+                opaque_ty_ids: Default::default(),
+                opaque_ty_kinds: Default::default(),
+                opaque_ty_data: Default::default(),
+                trait_data: Default::default(),
+                well_known_traits: Default::default(),
+                associated_ty_data: Default::default(),
+                predicate_ids: Default::default(),
+                predicate_kinds: Default::default(),
+                method_predicate_ids: Default::default(),
+                goal_defns: Default::default(),
+                custom_clauses: Default::default(),
+                method_clauses: Default::default(),
+            },
+            next_id: 0,
+        }
+    }
+
+    fn next_id(&mut self) -> RawId {
+        let index = self.next_id;
+        self.next_id += 1;
+        RawId { index }
+    }
+
+    /// Declares `struct name<T0, .., Tn> { }`, with `num_params` type
+    /// parameters.
+    pub fn struct_(mut self, name: &str, num_params: usize) -> Self {
+        let id = StructId(self.next_id());
+        let name = Identifier::from(name);
+        let binders = type_parameters(num_params);
+
+        self.program.struct_ids.insert(name.clone(), id);
+        self.program.struct_kinds.insert(
+            id,
+            TypeKind {
+                sort: TypeSort::Struct,
+                name,
+                binders: Binders::new(binders.clone(), crate::Unit),
+            },
+        );
+        self.program.struct_data.insert(
+            id,
+            Arc::new(StructDatum {
+                id,
+                binders: Binders::new(
+                    binders,
+                    StructDatumBound {
+                        fields: vec![],
+                        where_clauses: vec![],
+                    },
+                ),
+                flags: StructFlags {
+                    upstream: false,
+                    fundamental: false,
+                    union: false,
+                    manually_drop: false,
+                    extern_type: false,
+                },
+            }),
+        );
+        self
+    }
+
+    /// Declares `trait name<T0, .., Tn> { }`, with `num_params` type
+    /// parameters besides `Self`.
+    pub fn trait_(mut self, name: &str, num_params: usize) -> Self {
+        let id = TraitId(self.next_id());
+        let name = Identifier::from(name);
+
+        self.program.trait_ids.insert(name.clone(), id);
+        self.program.trait_kinds.insert(
+            id,
+            TypeKind {
+                sort: TypeSort::Trait,
+                name,
+                // for the purposes of the *type*, ignore `Self`:
+                binders: Binders::new(type_parameters(num_params), crate::Unit),
+            },
+        );
+        self.program.trait_data.insert(
+            id,
+            Arc::new(TraitDatum {
+                id,
+                binders: Binders::new(
+                    type_parameters(num_params + 1),
+                    TraitDatumBound {
+                        where_clauses: vec![],
+                    },
+                ),
+                flags: TraitFlags {
+                    auto: false,
+                    marker: false,
+                    upstream: false,
+                    fundamental: false,
+                    non_enumerable: false,
+                    coinductive: false,
+                },
+                safety: Safety::Safe,
+                associated_ty_ids: vec![],
+                methods: vec![],
+                well_known: None,
+            }),
+        );
+        self
+    }
+
+    /// Declares `impl<T0, .., Tn> Trait<..> for .. where .. { }`, with
+    /// `num_params` type parameters. `header` is given the builder and
+    /// the types of the parameters, and returns the arguments of the
+    /// trait, starting with the `Self` type, and the where clauses.
+    ///
+    /// Panics if there is no trait `trait_name`.
+    pub fn impl_(
+        mut self,
+        trait_name: &str,
+        num_params: usize,
+        header: impl FnOnce(
+            &Self,
+            &[Ty<ChalkIr>],
+        ) -> (Vec<Ty<ChalkIr>>, Vec<QuantifiedWhereClause<ChalkIr>>),
+    ) -> Self {
+        let interner = &ChalkIr;
+        let trait_id = self.trait_id(trait_name);
+        let params: Vec<_> = (0..num_params)
+            .map(|i| TyData::BoundVar(BoundVar::new(DebruijnIndex::INNERMOST, i)).intern(interner))
+            .collect();
+        let (args, where_clauses) = header(&self, &params);
+
+        let id = ImplId(self.next_id());
+        self.program.add_impl(
+            id,
+            Arc::new(ImplDatum {
+                polarity: Polarity::Positive,
+                safety: Safety::Safe,
+                constness: Constness::NotConst,
+                binders: Binders::new(
+                    type_parameters(num_params),
+                    ImplDatumBound {
+                        trait_ref: TraitRef {
+                            trait_id,
+                            substitution: Substitution::from(interner, args),
+                        },
+                        where_clauses,
+                    },
+                ),
+                impl_type: ImplType::Local,
+                associated_ty_value_ids: vec![],
+                methods: vec![],
+                priority: 0,
+            }),
+            vec![],
+        );
+        self
+    }
+
+    /// The type `name<args..>`.
+    ///
+    /// Panics if there is no struct `name`.
+    pub fn ty(&self, name: &str, args: &[Ty<ChalkIr>]) -> Ty<ChalkIr> {
+        let interner = &ChalkIr;
+        let id = match self.program.struct_ids.get(&Identifier::from(name)) {
+            Some(&id) => id,
+            None => panic!("no struct named `{}`", name),
+        };
+        TyData::Apply(ApplicationTy {
+            name: TypeName::Struct(id),
+            substitution: Substitution::from(interner, args.iter().cloned()),
+        })
+        .intern(interner)
+    }
+
+    /// The where clause `args[0]: Trait<args[1..]>`, for an impl whose
+    /// parameters appear in `args`.
+    ///
+    /// Panics if there is no trait `trait_name`.
+    pub fn implemented(
+        &self,
+        trait_name: &str,
+        args: &[Ty<ChalkIr>],
+    ) -> QuantifiedWhereClause<ChalkIr> {
+        let interner = &ChalkIr;
+        let trait_ref = TraitRef {
+            trait_id: self.trait_id(trait_name),
+            substitution: Substitution::from(interner, args.iter().cloned()),
+        };
+        // The where clause has a binder of its own, which the
+        // parameters of the impl are outside of.
+        Binders::empty(
+            interner,
+            WhereClause::Implemented(trait_ref).shifted_in(interner),
+        )
+    }
+
+    fn trait_id(&self, name: &str) -> TraitId<ChalkIr> {
+        match self.program.trait_ids.get(&Identifier::from(name)) {
+            Some(&id) => id,
+            None => panic!("no trait named `{}`", name),
+        }
+    }
+
+    pub fn build(self) -> Program {
+        self.program
+    }
+}
+
+impl Default for ProgramBuilder {
+    fn default() -> Self {
+        ProgramBuilder::new()
+    }
+}
+
+fn type_parameters(num_params: usize) -> ParameterKinds<ChalkIr> {
+    ParameterKinds::from(
+        &ChalkIr,
+        iter::repeat(ParameterKind::Ty(())).take(num_params),
+    )
+}
@@ -1,6 +1,7 @@
 use crate::error::ChalkError;
 use crate::interner::ChalkIr;
-use crate::lowering::LowerGoal;
+use crate::lowering::{LowerGoal, LowerMethodProbe};
+use crate::method::{self, MethodPick};
 use crate::program::Program;
 use crate::query::{Lowering, LoweringDatabase};
 use crate::tls;
@@ -26,10 +27,20 @@ use chalk_rust_ir::OpaqueTyDatum;
 use chalk_rust_ir::StructDatum;
 use chalk_rust_ir::TraitDatum;
 use chalk_rust_ir::WellKnownTrait;
+use chalk_solve::clauses::order::ClauseOrder;
+use chalk_solve::clauses::provider::ProgramClauseProvider;
+use chalk_solve::explain::Explanation;
+use chalk_solve::proof_tree::ProofTree;
+use chalk_solve::trace::TraceSink;
 use chalk_solve::RustIrDatabase;
 use chalk_solve::Solution;
 use chalk_solve::SolverChoice;
+use chalk_solve::SolverStats;
+use chalk_solve::{AnswerCacheStats, Budget, BudgetExhausted, CancellationToken};
 use salsa::Database;
+use std::fs;
+use std::io;
+use std::path::Path;
 use std::sync::Arc;
 
 #[salsa::database(Lowering)]
@@ -52,14 +63,44 @@ impl ChalkDatabase {
         db
     }
 
+    /// Creates a database whose program is the concatenation of the given
+    /// `.chalk` files, in order. This lets a shared prelude of definitions
+    /// be reused across several programs.
+    pub fn with_files<P: AsRef<Path>>(
+        paths: &[P],
+        solver_choice: SolverChoice,
+    ) -> io::Result<Self> {
+        let mut program_text = String::new();
+        for path in paths {
+            program_text.push_str(&fs::read_to_string(path)?);
+            program_text.push('\n');
+        }
+        Ok(ChalkDatabase::with(&program_text, solver_choice))
+    }
+
     pub fn with_program<R>(&self, op: impl FnOnce(&Program) -> R) -> R {
         let program = &self.checked_program().unwrap();
         tls::set_current_program(&program, || op(&program))
     }
 
+    /// Parses and lowers the goal `text`. Its syntax and lowering errors
+    /// are at their positions in `text`, like those of the program.
     pub fn parse_and_lower_goal(&self, text: &str) -> Result<Goal<ChalkIr>, ChalkError> {
         let program = self.checked_program()?;
-        Ok(chalk_parse::parse_goal(text)?.lower(&*program)?)
+        chalk_parse::parse_goal(text)
+            .map_err(|error| ChalkError::parsing(error, text))?
+            .lower(&*program)
+            .map_err(|error| ChalkError::lowering(vec![error], text))
+    }
+
+    /// Resolves a method probe like `Foo<u32>.bar()` against the
+    /// inherent impls of the program.
+    pub fn probe_method(&self, text: &str) -> Result<MethodPick, ChalkError> {
+        let program = self.checked_program()?;
+        let probe = chalk_parse::parse_method_probe(text)?.lower(&*program)?;
+        tls::set_current_program(&program, || {
+            Ok(method::probe_method(self, &program, &probe)?)
+        })
     }
 
     pub fn solve(
@@ -80,6 +121,96 @@ impl ChalkDatabase {
         let solution = solver.lock().unwrap().solve_multiple(self, goal, f);
         solution
     }
+
+    pub fn solve_cancellable(
+        &self,
+        goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>,
+        token: &CancellationToken,
+    ) -> Option<Solution<ChalkIr>> {
+        let solver = self.solver();
+        let solution = solver.lock().unwrap().solve_cancellable(self, goal, token);
+        solution
+    }
+
+    pub fn solve_with_budget(
+        &self,
+        goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>,
+        budget: Budget,
+    ) -> Result<Option<Solution<ChalkIr>>, BudgetExhausted> {
+        let solver = self.solver();
+        let solution = solver.lock().unwrap().solve_with_budget(self, goal, budget);
+        solution
+    }
+
+    pub fn solve_with_proof_tree(
+        &self,
+        goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>,
+    ) -> Option<ProofTree<ChalkIr>> {
+        let solver = self.solver();
+        let tree = solver.lock().unwrap().solve_with_proof_tree(self, goal);
+        tree
+    }
+
+    /// Explains why `goal` has no solution, if it has none; see
+    /// `Solver::explain_failure`.
+    pub fn explain_failure(
+        &self,
+        goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>,
+    ) -> Option<Explanation<ChalkIr>> {
+        let solver = self.solver();
+        let explanation = solver.lock().unwrap().explain_failure(self, goal);
+        explanation
+    }
+
+    /// Installs `sink` to receive the steps taken by the current solver;
+    /// see `Solver::set_trace_sink`.
+    pub fn set_trace_sink(&self, sink: Option<Box<dyn TraceSink<ChalkIr>>>) {
+        self.solver().lock().unwrap().set_trace_sink(sink);
+    }
+
+    /// Caches the solutions of the goals solved by the current solver;
+    /// see `Solver::enable_answer_cache`.
+    pub fn enable_answer_cache(&self) {
+        self.solver().lock().unwrap().enable_answer_cache();
+    }
+
+    /// How often the answer cache of the current solver had the solution
+    /// of a goal, if it was enabled.
+    pub fn answer_cache_stats(&self) -> Option<AnswerCacheStats> {
+        self.solver().lock().unwrap().answer_cache_stats()
+    }
+
+    /// How much work the current solver did to solve the last goal; see
+    /// `Solver::last_stats`.
+    pub fn last_stats(&self) -> SolverStats {
+        self.solver().lock().unwrap().last_stats()
+    }
+
+    /// The goals that the current solver was solving when it gave up on
+    /// one because of its overflow depth, while solving the last goal;
+    /// see `Solver::last_overflow`.
+    pub fn last_overflow(&self) -> Option<Vec<UCanonical<InEnvironment<Goal<ChalkIr>>>>> {
+        self.solver()
+            .lock()
+            .unwrap()
+            .last_overflow()
+            .map(|stack| stack.to_vec())
+    }
+
+    /// Sets how the current solver generates the program clauses for a
+    /// goal; see `Solver::set_clause_provider`.
+    pub fn set_clause_provider(&self, clause_provider: Arc<dyn ProgramClauseProvider<ChalkIr>>) {
+        self.solver()
+            .lock()
+            .unwrap()
+            .set_clause_provider(clause_provider);
+    }
+
+    /// Sets the order in which the current solver tries the program
+    /// clauses for a goal; see `Solver::set_clause_order`.
+    pub fn set_clause_order(&self, clause_order: Arc<dyn ClauseOrder<ChalkIr>>) {
+        self.solver().lock().unwrap().set_clause_order(clause_order);
+    }
 }
 
 impl RustIrDatabase<ChalkIr> for ChalkDatabase {
@@ -0,0 +1,99 @@
+//! Diagnostics: errors rendered the way rustc renders them, with the
+//! line of the program that an error is about and its span underlined:
+//!
+//! ```notrust
+//! error: invalid type name `Quux`
+//!  --> test.chalk:6:14
+//!   |
+//! 6 | impl Bar for Quux { }
+//!   |              ^^^^ not a type in scope
+//! ```
+
+use crate::error::RustIrError;
+use chalk_parse::ast::Span;
+use chalk_parse::SyntaxError;
+use std::fmt::Write;
+
+/// An error message, with the span of the program that it is about and
+/// a label for it, if it is about a particular place in the program.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Option<Span>,
+    pub label: Option<String>,
+}
+
+impl Diagnostic {
+    /// The diagnostic of the error `error` in lowering a program.
+    pub fn lowering(error: &RustIrError) -> Self {
+        Diagnostic {
+            message: error.to_string(),
+            span: Some(error.span()),
+            label: Some(error.label().to_string()),
+        }
+    }
+
+    /// The diagnostic of the syntax error `error`, labelled with the
+    /// tokens that were expected. Without a span, the expected tokens
+    /// are part of the message instead.
+    pub fn syntax(error: &SyntaxError) -> Self {
+        match error.span {
+            Some(span) => Diagnostic {
+                message: format!("parse error: {}", error.message),
+                span: Some(span),
+                label: error.expected_label(),
+            },
+            None => Diagnostic {
+                message: error.to_string(),
+                span: None,
+                label: None,
+            },
+        }
+    }
+
+    /// Renders the diagnostic against `text`, the text of the file `file`
+    /// that its span is in. The span is underlined on the line it starts
+    /// on, up to the end of that line; a diagnostic without a span, or
+    /// with one that is not in `text`, is rendered without a snippet.
+    pub fn render(&self, file: &str, text: &str) -> String {
+        let mut out = String::new();
+        writeln!(out, "error: {}", self.message).unwrap();
+        let span = match self
+            .span
+            .filter(|span| text.get(span.lo..span.hi).is_some())
+        {
+            Some(span) => span,
+            None => {
+                writeln!(out, " --> {}", file).unwrap();
+                return out;
+            }
+        };
+
+        let (line, column) = span.line_column(text);
+        let line_start = text[..span.lo].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = text[span.lo..]
+            .find('\n')
+            .map_or(text.len(), |i| span.lo + i);
+        let source_line = text[line_start..line_end].trim_end_matches('\r');
+
+        // The underline is indented with the whitespace of the line
+        // before the span, so that tabs line it up as they do the line.
+        let indent: String = text[line_start..span.lo]
+            .chars()
+            .map(|c| if c.is_whitespace() { c } else { ' ' })
+            .collect();
+        let width = text[span.lo..span.hi.min(line_end)].chars().count().max(1);
+
+        let number = line.to_string();
+        let gutter = " ".repeat(number.len());
+        writeln!(out, "{}--> {}:{}:{}", gutter, file, line, column).unwrap();
+        writeln!(out, "{} |", gutter).unwrap();
+        writeln!(out, "{} | {}", number, source_line).unwrap();
+        write!(out, "{} | {}{}", gutter, indent, "^".repeat(width)).unwrap();
+        match &self.label {
+            Some(label) => writeln!(out, " {}", label).unwrap(),
+            None => writeln!(out).unwrap(),
+        }
+        out
+    }
+}
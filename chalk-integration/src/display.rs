@@ -0,0 +1,1366 @@
+//! Renders the IR back into the surface syntax of chalk programs, with
+//! items called by the names they were declared with. Unlike the
+//! `Debug` output, this names the variables of binders, so that
+//! `forall<T> { T: Clone }` is rendered as `forall<T0> { T0: Clone }`
+//! rather than as `ForAll<type> { Implemented(^0.0: Clone) }`.
+//!
+//! Values are rendered with `Program::display`.
+//!
+//! A whole program is rendered with `Program::display_source`, as a
+//! program that lowers back to it.
+//!
+//! Inference variables are rendered as `?0` and placeholders as `!1_0`,
+//! as in the `Debug` output, which have no surface syntax. So do the
+//! variables of binders outside of the rendered value, such as those of
+//! a `Canonical`, which are rendered as `?0` as well.
+
+use crate::interner::ChalkIr;
+use crate::program::Program;
+use crate::Identifier;
+use chalk_ir::interner::HasInterner;
+use chalk_ir::*;
+use chalk_rust_ir::{
+    ImplType, InlineBound, MethodDatum, QuantifiedInlineBound, Receiver, Safety, TraitBound,
+    WellKnownTrait,
+};
+use chalk_solve::split::Split;
+use chalk_solve::RustIrDatabase;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::iter;
+
+/// A value that can be rendered in the surface syntax.
+pub trait RenderAsSurface {
+    fn render(&self, renderer: &mut Renderer<'_, '_>) -> fmt::Result;
+}
+
+/// Displays a value in the surface syntax; see `Program::display`.
+pub struct SurfaceDisplay<'a, T> {
+    program: &'a Program,
+    value: &'a T,
+}
+
+impl<T: RenderAsSurface> fmt::Display for SurfaceDisplay<'_, T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value
+            .render(&mut Renderer::new(self.program, fmt, false))
+    }
+}
+
+/// Displays a whole program in the surface syntax; see
+/// `Program::display_source`.
+pub struct SourceDisplay<'a> {
+    program: &'a Program,
+}
+
+impl fmt::Display for SourceDisplay<'_> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut renderer = Renderer::new(self.program, fmt, true);
+        // Parameters shadow the items of the same name, so their names
+        // must differ from those of the items at the top level.
+        let program = self.program;
+        let names: Vec<_> = program
+            .struct_kinds
+            .values()
+            .chain(program.trait_kinds.values())
+            .chain(program.opaque_ty_kinds.values())
+            .chain(program.predicate_kinds.values())
+            .map(|kind| &kind.name)
+            .collect();
+        while names.iter().any(|name| {
+            name.starts_with(renderer.ty_prefix.as_str())
+                && name[renderer.ty_prefix.len()..]
+                    .chars()
+                    .all(|c| c.is_ascii_digit())
+        }) {
+            renderer.ty_prefix.push('_');
+        }
+        renderer.program_source()
+    }
+}
+
+impl Program {
+    /// Displays `value` in the surface syntax, with the names the items
+    /// of this program were declared with.
+    pub fn display<'a, T: RenderAsSurface>(&'a self, value: &'a T) -> SurfaceDisplay<'a, T> {
+        SurfaceDisplay {
+            program: self,
+            value,
+        }
+    }
+
+    /// Displays this program in the surface syntax, one item per line,
+    /// as a program that lowers back to this one, down to the ids of
+    /// its items.
+    ///
+    /// The names of the parameters and of the fields of structs are not
+    /// kept by lowering, so they are made up, as are the forms of the
+    /// items that lower alike: an opaque type is rendered as an `opaque
+    /// type`, except where a separate `hidden type` item is needed for
+    /// the ids to come out the same.
+    pub fn display_source(&self) -> SourceDisplay<'_> {
+        SourceDisplay { program: self }
+    }
+}
+
+/// Writes values in the surface syntax, keeping track of the names of
+/// the variables of the binders it is in.
+pub struct Renderer<'a, 'f> {
+    program: &'a Program,
+    interner: &'a ChalkIr,
+    fmt: &'a mut fmt::Formatter<'f>,
+
+    /// The names of the variables of the binders being rendered, the
+    /// innermost binder last.
+    binders: Vec<Vec<String>>,
+
+    /// Variables are numbered across the whole value, so that the names
+    /// of inner binders never shadow those of outer ones.
+    next_ty: usize,
+    next_lifetime: usize,
+
+    /// Type variables are named like `T0`, with this prefix.
+    ty_prefix: String,
+
+    /// Whether values are rendered as the source that lowers to them,
+    /// rather than one node at a time: lowering adds a where clause
+    /// `T: Trait` to each `T: Trait<Item = U>` and `T: ~const Trait`,
+    /// which the source leaves implied, and reverses the conditions of
+    /// clauses.
+    source: bool,
+}
+
+impl<'a, 'f> Renderer<'a, 'f> {
+    fn new(program: &'a Program, fmt: &'a mut fmt::Formatter<'f>, source: bool) -> Self {
+        Renderer {
+            program,
+            interner: program.interner(),
+            fmt,
+            binders: vec![],
+            next_ty: 0,
+            next_lifetime: 0,
+            ty_prefix: "T".to_string(),
+            source,
+        }
+    }
+}
+
+impl Renderer<'_, '_> {
+    /// Names the variables of `binders` and renders them like
+    /// `<T0, 'a0>`, then renders `op` with those names in scope.
+    fn in_binders(
+        &mut self,
+        binders: &ParameterKinds<ChalkIr>,
+        op: impl FnOnce(&mut Self) -> fmt::Result,
+    ) -> fmt::Result {
+        let names = self.name_kinds(binders.iter(self.interner));
+        write!(self.fmt, "<{}>", names.join(", "))?;
+        self.binders.push(names);
+        let result = op(self);
+        self.binders.pop();
+        result
+    }
+
+    /// Names variables of the kinds `kinds`, like `T0` and `'a0`.
+    fn name_kinds<'k>(
+        &mut self,
+        kinds: impl IntoIterator<Item = &'k ParameterKind<()>>,
+    ) -> Vec<String> {
+        kinds
+            .into_iter()
+            .map(|kind| match kind {
+                ParameterKind::Ty(()) => {
+                    self.next_ty += 1;
+                    format!("{}{}", self.ty_prefix, self.next_ty - 1)
+                }
+                ParameterKind::Lifetime(()) => {
+                    self.next_lifetime += 1;
+                    format!("'a{}", self.next_lifetime - 1)
+                }
+            })
+            .collect()
+    }
+
+    /// Like `in_binders`, but with the names of `binders` given.
+    fn in_named_binders(
+        &mut self,
+        names: Vec<String>,
+        op: impl FnOnce(&mut Self) -> fmt::Result,
+    ) -> fmt::Result {
+        self.binders.push(names);
+        let result = op(self);
+        self.binders.pop();
+        result
+    }
+
+    fn bound_var(&mut self, bound_var: BoundVar, prefix: &str) -> fmt::Result {
+        let depth = bound_var.debruijn.depth() as usize;
+        if depth < self.binders.len() {
+            let names = &self.binders[self.binders.len() - 1 - depth];
+            write!(self.fmt, "{}", names[bound_var.index])
+        } else if depth == self.binders.len() {
+            write!(self.fmt, "{}?{}", prefix, bound_var.index)
+        } else {
+            write!(
+                self.fmt,
+                "{}^{}.{}",
+                prefix,
+                depth - self.binders.len(),
+                bound_var.index
+            )
+        }
+    }
+
+    /// Renders `values` separated by `separator`.
+    fn list<'v, T: RenderAsSurface + 'v>(
+        &mut self,
+        values: impl IntoIterator<Item = &'v T>,
+        separator: &str,
+    ) -> fmt::Result {
+        for (index, value) in values.into_iter().enumerate() {
+            if index > 0 {
+                write!(self.fmt, "{}", separator)?;
+            }
+            value.render(self)?;
+        }
+        Ok(())
+    }
+
+    /// Renders `parameters` like `<A, B>`, or nothing if there are none.
+    fn angle(&mut self, parameters: &[Parameter<ChalkIr>]) -> fmt::Result {
+        if parameters.is_empty() {
+            return Ok(());
+        }
+        write!(self.fmt, "<")?;
+        self.list(parameters, ", ")?;
+        write!(self.fmt, ">")
+    }
+
+    /// Renders a goal where the grammar only allows a single goal, as
+    /// in the conditions of a clause, so that a conjunction is
+    /// parenthesized.
+    fn goal1(&mut self, goal: &Goal<ChalkIr>) -> fmt::Result {
+        match goal.data(self.interner) {
+            GoalData::All(goals)
+                if goals.len(self.interner) > 1
+                    && self.implied_goals(goals.as_slice(self.interner)).is_none() =>
+            {
+                write!(self.fmt, "(")?;
+                goal.render(self)?;
+                write!(self.fmt, ")")
+            }
+            _ => goal.render(self),
+        }
+    }
+
+    fn trait_ref(&mut self, trait_ref: &TraitRef<ChalkIr>, colon: &str) -> fmt::Result {
+        let parameters = trait_ref.substitution.parameters(self.interner);
+        parameters[0].render(self)?;
+        let name = self.trait_name(trait_ref.trait_id);
+        write!(self.fmt, "{}{}", colon, name)?;
+        self.angle(&parameters[1..])
+    }
+
+    fn projection(&mut self, projection: &ProjectionTy<ChalkIr>) -> fmt::Result {
+        let (associated_ty_data, trait_params, other_params) =
+            self.program.split_projection(projection);
+        write!(self.fmt, "<")?;
+        trait_params[0].render(self)?;
+        let name = self.trait_name(associated_ty_data.trait_id);
+        write!(self.fmt, " as {}", name)?;
+        self.angle(&trait_params[1..])?;
+        write!(self.fmt, ">::{}", associated_ty_data.name)?;
+        self.angle(other_params)
+    }
+
+    /// Renders an alias equality bound like `Trait<A, Item = T>`, without
+    /// the self type, as in the bounds of `dyn` types. The alias
+    /// equalities in `more` are on the same trait reference and are
+    /// rendered as more bindings, like `Trait<A, Item = T, Rest = U>`.
+    fn alias_eq_bound(
+        &mut self,
+        alias_eq: &AliasEq<ChalkIr>,
+        more: &[&AliasEq<ChalkIr>],
+    ) -> fmt::Result {
+        let projection = match &alias_eq.alias {
+            AliasTy::Projection(projection) => projection,
+            AliasTy::Opaque(_) => return alias_eq.render(self),
+        };
+        let (associated_ty_data, trait_params, _) = self.program.split_projection(projection);
+        let name = self.trait_name(associated_ty_data.trait_id);
+        write!(self.fmt, "{}<", name)?;
+        for parameter in &trait_params[1..] {
+            parameter.render(self)?;
+            write!(self.fmt, ", ")?;
+        }
+        for (index, alias_eq) in iter::once(alias_eq).chain(more.iter().copied()).enumerate() {
+            if index > 0 {
+                write!(self.fmt, ", ")?;
+            }
+            if let AliasTy::Projection(projection) = &alias_eq.alias {
+                let (associated_ty_data, _, other_params) =
+                    self.program.split_projection(projection);
+                write!(self.fmt, "{}", associated_ty_data.name)?;
+                self.angle(other_params)?;
+            }
+            write!(self.fmt, " = ")?;
+            alias_eq.ty.render(self)?;
+        }
+        write!(self.fmt, ">")
+    }
+
+    /// Renders an alias equality like `T: Trait<Item = U>`, with the
+    /// alias equalities in `more` as more bindings, as in `alias_eq_bound`.
+    fn alias_eq(&mut self, alias_eq: &AliasEq<ChalkIr>, more: &[&AliasEq<ChalkIr>]) -> fmt::Result {
+        if let AliasTy::Projection(projection) = &alias_eq.alias {
+            let (_, trait_params, _) = self.program.split_projection(projection);
+            trait_params[0].render(self)?;
+            write!(self.fmt, ": ")?;
+        }
+        self.alias_eq_bound(alias_eq, more)
+    }
+
+    fn dyn_bound(&mut self, bound: &QuantifiedWhereClause<ChalkIr>) -> fmt::Result {
+        let render = |renderer: &mut Self| match bound.skip_binders() {
+            WhereClause::Implemented(trait_ref) | WhereClause::ConstImplemented(trait_ref) => {
+                let parameters = trait_ref.substitution.parameters(renderer.interner);
+                let name = renderer.trait_name(trait_ref.trait_id);
+                write!(renderer.fmt, "{}", name)?;
+                renderer.angle(&parameters[1..])
+            }
+            WhereClause::AliasEq(alias_eq) => renderer.alias_eq_bound(alias_eq, &[]),
+        };
+        if bound.binders.is_empty(self.interner) {
+            self.in_named_binders(vec![], render)
+        } else {
+            write!(self.fmt, "forall")?;
+            self.in_binders(&bound.binders, |renderer| {
+                write!(renderer.fmt, " ")?;
+                render(renderer)
+            })
+        }
+    }
+
+    /// Renders the bounds of a `dyn` or opaque type, separated by `+`.
+    fn bounds(&mut self, bounds: &[QuantifiedWhereClause<ChalkIr>]) -> fmt::Result {
+        let mut index = 0;
+        while index < bounds.len() {
+            if index > 0 {
+                write!(self.fmt, " + ")?;
+            }
+            // The bound `Trait<Item = U>` lowers to `Trait` followed by
+            // the alias equality.
+            if self.source
+                && bounds
+                    .get(index + 1)
+                    .map_or(false, |next| self.implies_bound(&bounds[index], next))
+            {
+                index += 1;
+            }
+            self.dyn_bound(&bounds[index])?;
+            index += 1;
+        }
+        Ok(())
+    }
+
+    /// Whether `bound` is the bound `Trait` that lowering adds before
+    /// the alias equality `next` on an associated type of `Trait`.
+    fn implies_bound(
+        &self,
+        bound: &QuantifiedWhereClause<ChalkIr>,
+        next: &QuantifiedWhereClause<ChalkIr>,
+    ) -> bool {
+        bound.binders == next.binders
+            && match (bound.skip_binders(), next.skip_binders()) {
+                (WhereClause::Implemented(trait_ref), WhereClause::AliasEq(alias_eq)) => {
+                    self.implied_trait_ref(&WhereClause::AliasEq(alias_eq.clone()))
+                        .as_ref()
+                        == Some(trait_ref)
+                }
+                _ => false,
+            }
+    }
+
+    /// The trait reference `T: Trait` that lowering implies along with
+    /// `where_clause`, if it is `T: Trait<Item = U>` or `T: ~const Trait`.
+    fn implied_trait_ref(&self, where_clause: &WhereClause<ChalkIr>) -> Option<TraitRef<ChalkIr>> {
+        match where_clause {
+            WhereClause::AliasEq(AliasEq {
+                alias: AliasTy::Projection(projection),
+                ..
+            }) => Some(self.program.trait_ref_from_projection(projection)),
+            WhereClause::ConstImplemented(trait_ref) => Some(trait_ref.clone()),
+            _ => None,
+        }
+    }
+
+    /// Returns the trait reference that lowering implies along with
+    /// `where_clause`, under the same binders.
+    fn implied_where_clause(
+        &self,
+        where_clause: &QuantifiedWhereClause<ChalkIr>,
+    ) -> Option<QuantifiedWhereClause<ChalkIr>> {
+        self.implied_trait_ref(where_clause.skip_binders())
+            .map(|trait_ref| where_clause.map_ref(|_| WhereClause::Implemented(trait_ref)))
+    }
+
+    /// In source mode, if `goals` are a where clause and the trait
+    /// reference it implies, as a where clause lowers to in a goal,
+    /// returns the where clause alone.
+    fn implied_goals<'g>(&self, goals: &'g [Goal<ChalkIr>]) -> Option<&'g Goal<ChalkIr>> {
+        if !self.source {
+            return None;
+        }
+        match goals {
+            [first, second] => match (first.data(self.interner), second.data(self.interner)) {
+                (
+                    GoalData::DomainGoal(DomainGoal::Holds(where_clause)),
+                    GoalData::DomainGoal(DomainGoal::Holds(WhereClause::Implemented(trait_ref))),
+                ) if self.implied_trait_ref(where_clause).as_ref() == Some(trait_ref) => {
+                    Some(first)
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The clauses of `clauses` to render: in source mode, those that
+    /// lowering does not imply along with the clause before them. In the
+    /// hypotheses of an `if` goal, the implied clause is `FromEnv(T:
+    /// Trait)` rather than `T: Trait`.
+    fn source_clauses<'c>(
+        &self,
+        clauses: &'c [ProgramClause<ChalkIr>],
+    ) -> Vec<&'c ProgramClause<ChalkIr>> {
+        let mut rendered = vec![];
+        let mut index = 0;
+        while index < clauses.len() {
+            rendered.push(&clauses[index]);
+            let implied = self.source
+                && clauses
+                    .get(index + 1)
+                    .map_or(false, |next| self.implies_clause(&clauses[index], next));
+            index += if implied { 2 } else { 1 };
+        }
+        rendered
+    }
+
+    fn implies_clause(
+        &self,
+        clause: &ProgramClause<ChalkIr>,
+        next: &ProgramClause<ChalkIr>,
+    ) -> bool {
+        let (implication, next) = match (clause.data(self.interner), next.data(self.interner)) {
+            (ProgramClauseData::Implies(implication), ProgramClauseData::Implies(next)) => {
+                (implication, next)
+            }
+            (ProgramClauseData::ForAll(implication), ProgramClauseData::ForAll(next))
+                if implication.binders == next.binders =>
+            {
+                (implication.skip_binders(), next.skip_binders())
+            }
+            _ => return false,
+        };
+        let trait_ref = match &implication.consequence {
+            DomainGoal::Holds(where_clause) => self.implied_trait_ref(where_clause),
+            _ => None,
+        };
+        match trait_ref {
+            Some(trait_ref) => {
+                implication.conditions == next.conditions
+                    && implication.priority == next.priority
+                    && (next.consequence
+                        == DomainGoal::Holds(WhereClause::Implemented(trait_ref.clone()))
+                        || next.consequence == DomainGoal::FromEnv(FromEnv::Trait(trait_ref)))
+            }
+            None => false,
+        }
+    }
+
+    fn trait_name(&self, trait_id: TraitId<ChalkIr>) -> String {
+        self.program.trait_kinds[&trait_id].name.to_string()
+    }
+}
+
+impl<T: RenderAsSurface> RenderAsSurface for &T {
+    fn render(&self, renderer: &mut Renderer<'_, '_>) -> fmt::Result {
+        (**self).render(renderer)
+    }
+}
+
+impl RenderAsSurface for Parameter<ChalkIr> {
+    fn render(&self, renderer: &mut Renderer<'_, '_>) -> fmt::Result {
+        match self.data(renderer.interner) {
+            ParameterKind::Ty(ty) => ty.render(renderer),
+            ParameterKind::Lifetime(lifetime) => lifetime.render(renderer),
+        }
+    }
+}
+
+impl RenderAsSurface for Lifetime<ChalkIr> {
+    fn render(&self, renderer: &mut Renderer<'_, '_>) -> fmt::Result {
+        match self.data(renderer.interner) {
+            LifetimeData::BoundVar(bound_var) => renderer.bound_var(*bound_var, "'"),
+            LifetimeData::InferenceVar(var) => write!(renderer.fmt, "'{:?}", var),
+            LifetimeData::Placeholder(index) => write!(renderer.fmt, "'{:?}", index),
+            LifetimeData::Static => write!(renderer.fmt, "'static"),
+            LifetimeData::Phantom(..) => unreachable!(),
+        }
+    }
+}
+
+impl RenderAsSurface for Ty<ChalkIr> {
+    fn render(&self, renderer: &mut Renderer<'_, '_>) -> fmt::Result {
+        let interner = renderer.interner;
+        match self.data(interner) {
+            TyData::Apply(apply) => apply.render(renderer),
+            TyData::Placeholder(index) => write!(renderer.fmt, "{:?}", index),
+            TyData::Dyn(dyn_ty) => {
+                write!(renderer.fmt, "dyn ")?;
+                renderer.in_named_binders(vec!["Self".to_string()], |renderer| {
+                    renderer.bounds(dyn_ty.bounds.skip_binders().as_slice(interner))
+                })
+            }
+            TyData::Alias(alias) => alias.render(renderer),
+            TyData::Function(function) => {
+                if !function.binders.is_empty(interner) {
+                    write!(renderer.fmt, "for")?;
+                    renderer.in_binders(&function.binders, |renderer| {
+                        write!(renderer.fmt, " fn(")?;
+                        renderer.list(function.substitution.iter(interner), ", ")?;
+                        write!(renderer.fmt, ")")
+                    })
+                } else {
+                    renderer.in_named_binders(vec![], |renderer| {
+                        write!(renderer.fmt, "fn(")?;
+                        renderer.list(function.substitution.iter(interner), ", ")?;
+                        write!(renderer.fmt, ")")
+                    })
+                }
+            }
+            TyData::BoundVar(bound_var) => renderer.bound_var(*bound_var, ""),
+            TyData::InferenceVar(var) => write!(renderer.fmt, "{:?}", var),
+        }
+    }
+}
+
+impl RenderAsSurface for ApplicationTy<ChalkIr> {
+    fn render(&self, renderer: &mut Renderer<'_, '_>) -> fmt::Result {
+        let parameters = self.substitution.parameters(renderer.interner);
+        match self.name {
+            TypeName::Struct(id) => {
+                write!(renderer.fmt, "{}", renderer.program.struct_kinds[&id].name)?;
+                renderer.angle(parameters)
+            }
+            TypeName::AssociatedType(associated_ty_id) => renderer.projection(&ProjectionTy {
+                associated_ty_id,
+                substitution: self.substitution.clone(),
+            }),
+            TypeName::Scalar(scalar) => write!(renderer.fmt, "{}", scalar_name(scalar)),
+            TypeName::Tuple(arity) => {
+                write!(renderer.fmt, "(")?;
+                renderer.list(parameters, ", ")?;
+                if arity == 1 {
+                    write!(renderer.fmt, ",")?;
+                }
+                write!(renderer.fmt, ")")
+            }
+            TypeName::Ref(mutability) => {
+                write!(renderer.fmt, "&")?;
+                parameters[0].render(renderer)?;
+                if let Mutability::Mut = mutability {
+                    write!(renderer.fmt, " mut")?;
+                }
+                write!(renderer.fmt, " ")?;
+                parameters[1].render(renderer)
+            }
+            TypeName::OpaqueType(id) => {
+                write!(
+                    renderer.fmt,
+                    "{}",
+                    renderer.program.opaque_ty_kinds[&id].name
+                )?;
+                renderer.angle(parameters)
+            }
+            TypeName::Error => write!(renderer.fmt, "{{error}}"),
+        }
+    }
+}
+
+fn scalar_name(scalar: Scalar) -> &'static str {
+    match scalar {
+        Scalar::Bool => "bool",
+        Scalar::Char => "char",
+        Scalar::Int(IntTy::Isize) => "isize",
+        Scalar::Int(IntTy::I8) => "i8",
+        Scalar::Int(IntTy::I16) => "i16",
+        Scalar::Int(IntTy::I32) => "i32",
+        Scalar::Int(IntTy::I64) => "i64",
+        Scalar::Int(IntTy::I128) => "i128",
+        Scalar::Uint(UintTy::Usize) => "usize",
+        Scalar::Uint(UintTy::U8) => "u8",
+        Scalar::Uint(UintTy::U16) => "u16",
+        Scalar::Uint(UintTy::U32) => "u32",
+        Scalar::Uint(UintTy::U64) => "u64",
+        Scalar::Uint(UintTy::U128) => "u128",
+        Scalar::Float(FloatTy::F32) => "f32",
+        Scalar::Float(FloatTy::F64) => "f64",
+    }
+}
+
+impl RenderAsSurface for AliasTy<ChalkIr> {
+    fn render(&self, renderer: &mut Renderer<'_, '_>) -> fmt::Result {
+        match self {
+            AliasTy::Projection(projection) => renderer.projection(projection),
+            AliasTy::Opaque(opaque) => {
+                let name = &renderer.program.opaque_ty_kinds[&opaque.opaque_ty_id].name;
+                write!(renderer.fmt, "{}", name)?;
+                renderer.angle(opaque.substitution.parameters(renderer.interner))
+            }
+        }
+    }
+}
+
+impl RenderAsSurface for TraitRef<ChalkIr> {
+    fn render(&self, renderer: &mut Renderer<'_, '_>) -> fmt::Result {
+        renderer.trait_ref(self, ": ")
+    }
+}
+
+impl RenderAsSurface for AliasEq<ChalkIr> {
+    fn render(&self, renderer: &mut Renderer<'_, '_>) -> fmt::Result {
+        match &self.alias {
+            AliasTy::Projection(_) => renderer.alias_eq(self, &[]),
+            AliasTy::Opaque(_) => {
+                self.alias.render(renderer)?;
+                write!(renderer.fmt, " = ")?;
+                self.ty.render(renderer)
+            }
+        }
+    }
+}
+
+impl RenderAsSurface for WhereClause<ChalkIr> {
+    fn render(&self, renderer: &mut Renderer<'_, '_>) -> fmt::Result {
+        match self {
+            WhereClause::Implemented(trait_ref) => trait_ref.render(renderer),
+            WhereClause::AliasEq(alias_eq) => alias_eq.render(renderer),
+            WhereClause::ConstImplemented(trait_ref) => renderer.trait_ref(trait_ref, ": ~const "),
+        }
+    }
+}
+
+impl<T> RenderAsSurface for Binders<T>
+where
+    T: RenderAsSurface + HasInterner<Interner = ChalkIr>,
+{
+    fn render(&self, renderer: &mut Renderer<'_, '_>) -> fmt::Result {
+        if self.binders.is_empty(renderer.interner) {
+            return renderer
+                .in_named_binders(vec![], |renderer| self.skip_binders().render(renderer));
+        }
+        write!(renderer.fmt, "forall")?;
+        renderer.in_binders(&self.binders, |renderer| {
+            write!(renderer.fmt, " {{ ")?;
+            self.skip_binders().render(renderer)?;
+            write!(renderer.fmt, " }}")
+        })
+    }
+}
+
+impl RenderAsSurface for DomainGoal<ChalkIr> {
+    fn render(&self, renderer: &mut Renderer<'_, '_>) -> fmt::Result {
+        match self {
+            DomainGoal::Holds(where_clause) => where_clause.render(renderer),
+            DomainGoal::WellFormed(WellFormed::Trait(trait_ref)) => {
+                write!(renderer.fmt, "WellFormed(")?;
+                trait_ref.render(renderer)?;
+                write!(renderer.fmt, ")")
+            }
+            DomainGoal::WellFormed(WellFormed::Ty(ty)) => {
+                write!(renderer.fmt, "WellFormed(")?;
+                ty.render(renderer)?;
+                write!(renderer.fmt, ")")
+            }
+            DomainGoal::FromEnv(FromEnv::Trait(trait_ref)) => {
+                write!(renderer.fmt, "FromEnv(")?;
+                trait_ref.render(renderer)?;
+                write!(renderer.fmt, ")")
+            }
+            DomainGoal::FromEnv(FromEnv::Ty(ty)) => {
+                write!(renderer.fmt, "FromEnv(")?;
+                ty.render(renderer)?;
+                write!(renderer.fmt, ")")
+            }
+            DomainGoal::Normalize(normalize) => {
+                write!(renderer.fmt, "Normalize(")?;
+                normalize.alias.render(renderer)?;
+                write!(renderer.fmt, " -> ")?;
+                normalize.ty.render(renderer)?;
+                write!(renderer.fmt, ")")
+            }
+            DomainGoal::IsLocal(ty) => {
+                write!(renderer.fmt, "IsLocal(")?;
+                ty.render(renderer)?;
+                write!(renderer.fmt, ")")
+            }
+            DomainGoal::IsUpstream(ty) => {
+                write!(renderer.fmt, "IsUpstream(")?;
+                ty.render(renderer)?;
+                write!(renderer.fmt, ")")
+            }
+            DomainGoal::IsFullyVisible(ty) => {
+                write!(renderer.fmt, "IsFullyVisible(")?;
+                ty.render(renderer)?;
+                write!(renderer.fmt, ")")
+            }
+            DomainGoal::LocalImplAllowed(trait_ref) => {
+                write!(renderer.fmt, "LocalImplAllowed(")?;
+                trait_ref.render(renderer)?;
+                write!(renderer.fmt, ")")
+            }
+            DomainGoal::Compatible(()) => write!(renderer.fmt, "Compatible"),
+            DomainGoal::DownstreamType(ty) => {
+                write!(renderer.fmt, "DownstreamType(")?;
+                ty.render(renderer)?;
+                write!(renderer.fmt, ")")
+            }
+            DomainGoal::Reveal(()) => write!(renderer.fmt, "Reveal"),
+            DomainGoal::Predicate(predicate_ref) => {
+                let predicate_id = predicate_ref.predicate_id;
+                if let Some(name) = renderer.program.method_name(predicate_id) {
+                    let parameters = predicate_ref.substitution.parameters(renderer.interner);
+                    parameters[0].render(renderer)?;
+                    return write!(renderer.fmt, ".{}()", name);
+                }
+                let name = &renderer.program.predicate_kinds[&predicate_id].name;
+                write!(renderer.fmt, "{}(", name)?;
+                let parameters = predicate_ref.substitution.parameters(renderer.interner);
+                renderer.list(parameters, ", ")?;
+                write!(renderer.fmt, ")")
+            }
+        }
+    }
+}
+
+impl RenderAsSurface for ProgramClauseImplication<ChalkIr> {
+    fn render(&self, renderer: &mut Renderer<'_, '_>) -> fmt::Result {
+        self.consequence.render(renderer)?;
+        let mut conditions: Vec<_> = self.conditions.iter(renderer.interner).collect();
+        // Lowering reverses the conditions; see `LowerClause`.
+        if renderer.source {
+            conditions.reverse();
+        }
+        for (index, condition) in conditions.into_iter().enumerate() {
+            write!(renderer.fmt, "{}", if index == 0 { " if " } else { ", " })?;
+            renderer.goal1(condition)?;
+        }
+        Ok(())
+    }
+}
+
+impl RenderAsSurface for ProgramClause<ChalkIr> {
+    fn render(&self, renderer: &mut Renderer<'_, '_>) -> fmt::Result {
+        match self.data(renderer.interner) {
+            ProgramClauseData::Implies(implication) => implication.render(renderer),
+            ProgramClauseData::ForAll(implication) => implication.render(renderer),
+        }
+    }
+}
+
+impl RenderAsSurface for Goal<ChalkIr> {
+    fn render(&self, renderer: &mut Renderer<'_, '_>) -> fmt::Result {
+        let interner = renderer.interner;
+        match self.data(interner) {
+            GoalData::Quantified(kind, subgoal) => {
+                match kind {
+                    QuantifierKind::ForAll => write!(renderer.fmt, "forall")?,
+                    QuantifierKind::Exists => write!(renderer.fmt, "exists")?,
+                }
+                renderer.in_binders(&subgoal.binders, |renderer| {
+                    write!(renderer.fmt, " {{ ")?;
+                    subgoal.skip_binders().render(renderer)?;
+                    write!(renderer.fmt, " }}")
+                })
+            }
+            GoalData::Implies(clauses, goal) => {
+                write!(renderer.fmt, "if (")?;
+                renderer.list(renderer.source_clauses(clauses.as_slice(interner)), "; ")?;
+                write!(renderer.fmt, ") {{ ")?;
+                goal.render(renderer)?;
+                write!(renderer.fmt, " }}")
+            }
+            GoalData::All(goals) => {
+                let goals = goals.as_slice(interner);
+                if let Some(goal) = renderer.implied_goals(goals) {
+                    return goal.render(renderer);
+                }
+                if goals.is_empty() {
+                    return write!(renderer.fmt, "true");
+                }
+                for (index, goal) in goals.iter().enumerate() {
+                    if index > 0 {
+                        write!(renderer.fmt, ", ")?;
+                    }
+                    renderer.goal1(goal)?;
+                }
+                Ok(())
+            }
+            GoalData::Any(goals) => match goals.as_slice(interner) {
+                [] => write!(renderer.fmt, "false"),
+                [goal] => goal.render(renderer),
+                goals => {
+                    write!(renderer.fmt, "or {{ ")?;
+                    renderer.list(goals, "; ")?;
+                    write!(renderer.fmt, " }}")
+                }
+            },
+            GoalData::Not(goal) => {
+                write!(renderer.fmt, "not {{ ")?;
+                goal.render(renderer)?;
+                write!(renderer.fmt, " }}")
+            }
+            GoalData::EqGoal(EqGoal { a, b }) => {
+                a.render(renderer)?;
+                write!(renderer.fmt, " = ")?;
+                b.render(renderer)
+            }
+            GoalData::Constraint(Constraint::Outlives(a, b)) => {
+                a.render(renderer)?;
+                write!(renderer.fmt, ": ")?;
+                b.render(renderer)
+            }
+            GoalData::Constraint(Constraint::TypeOutlives(ty, lifetime)) => {
+                ty.render(renderer)?;
+                write!(renderer.fmt, ": ")?;
+                lifetime.render(renderer)
+            }
+            GoalData::DomainGoal(domain_goal) => domain_goal.render(renderer),
+            GoalData::CannotProve(()) => write!(renderer.fmt, "CannotProve"),
+        }
+    }
+}
+
+impl<G: RenderAsSurface + HasInterner<Interner = ChalkIr>> RenderAsSurface for InEnvironment<G> {
+    /// Renders the goal in the clauses of its environment, like
+    /// `if (T: Clone) { Vec<T>: Clone }`.
+    fn render(&self, renderer: &mut Renderer<'_, '_>) -> fmt::Result {
+        let clauses = self.environment.clauses.as_slice(renderer.interner);
+        if clauses.is_empty() {
+            return self.goal.render(renderer);
+        }
+        write!(renderer.fmt, "if (")?;
+        renderer.list(clauses, "; ")?;
+        write!(renderer.fmt, ") {{ ")?;
+        self.goal.render(renderer)?;
+        write!(renderer.fmt, " }}")
+    }
+}
+
+/// An item of a program, as `Program::display_source` renders it.
+enum SourceItem<'p> {
+    Struct(StructId<ChalkIr>),
+    Trait(TraitId<ChalkIr>),
+    Impl(ImplId<ChalkIr>),
+    InherentImpl(ImplId<ChalkIr>),
+    /// An opaque type, and whether its hidden type is a separate item.
+    OpaqueTy(OpaqueTyId<ChalkIr>, bool),
+    HiddenTy(OpaqueTyId<ChalkIr>),
+    Predicate(PredicateId<ChalkIr>),
+    Clause(&'p ProgramClause<ChalkIr>),
+    Goal(&'p Identifier, &'p Goal<ChalkIr>),
+}
+
+impl Renderer<'_, '_> {
+    /// Renders the items of the program in the order of their ids. The
+    /// items that have no ids of their own, that is clauses, goals and
+    /// hidden types, still take up one, so they are rendered in the
+    /// gaps between the others, in the order they were lowered in.
+    fn program_source(&mut self) -> fmt::Result {
+        let program = self.program;
+        let mut items = BTreeMap::new();
+        for &id in program.struct_data.keys() {
+            items.insert(id.0.index, SourceItem::Struct(id));
+        }
+        for &id in program.trait_data.keys() {
+            items.insert(id.0.index, SourceItem::Trait(id));
+        }
+        for &id in program.impl_data.keys() {
+            items.insert(id.0.index, SourceItem::Impl(id));
+        }
+        for &id in program.inherent_impl_data.keys() {
+            items.insert(id.0.index, SourceItem::InherentImpl(id));
+        }
+        for &id in program.opaque_ty_data.keys() {
+            items.insert(id.0.index, SourceItem::OpaqueTy(id, false));
+        }
+        for &id in program.predicate_kinds.keys() {
+            items.insert(id.0.index, SourceItem::Predicate(id));
+        }
+
+        let mut unnumbered: VecDeque<_> = self
+            .source_clauses(&program.custom_clauses)
+            .into_iter()
+            .map(SourceItem::Clause)
+            .chain(
+                program
+                    .goal_defns
+                    .iter()
+                    .map(|(name, goal)| SourceItem::Goal(name, goal)),
+            )
+            .collect();
+
+        // The associated types and values are numbered after the items,
+        // so the first of them gives the number of items.
+        let item_count = program
+            .associated_ty_data
+            .keys()
+            .map(|id| id.0.index)
+            .chain(program.associated_ty_values.keys().map(|id| id.0.index))
+            .min()
+            .unwrap_or_else(|| items.keys().next_back().map_or(0, |&index| index + 1));
+        let gaps = item_count as usize - items.len();
+        let hidden_tys: Vec<_> = program
+            .opaque_ty_data
+            .keys()
+            .take(gaps.saturating_sub(unnumbered.len()))
+            .cloned()
+            .collect();
+        for id in hidden_tys {
+            items.insert(id.0.index, SourceItem::OpaqueTy(id, true));
+            unnumbered.push_back(SourceItem::HiddenTy(id));
+        }
+
+        let mut index = 0;
+        while !items.is_empty() || !unnumbered.is_empty() {
+            let item = match items.remove(&index) {
+                Some(item) => Some(item),
+                None => unnumbered.pop_front(),
+            };
+            if let Some(item) = item {
+                self.next_ty = 0;
+                self.next_lifetime = 0;
+                self.item_source(&item)?;
+                writeln!(self.fmt)?;
+            }
+            index += 1;
+        }
+        Ok(())
+    }
+
+    /// Renders `item`, within the modules it was declared in, if it has
+    /// a name. Items are referred to by the paths from the top level, and
+    /// impls and clauses are rendered at the top level.
+    fn item_source(&mut self, item: &SourceItem<'_>) -> fmt::Result {
+        let program = self.program;
+        let path = match *item {
+            SourceItem::Struct(id) => Some(&program.struct_kinds[&id].name),
+            SourceItem::Trait(id) => Some(&program.trait_kinds[&id].name),
+            SourceItem::OpaqueTy(id, _) | SourceItem::HiddenTy(id) => {
+                Some(&program.opaque_ty_kinds[&id].name)
+            }
+            SourceItem::Predicate(id) => Some(&program.predicate_kinds[&id].name),
+            SourceItem::Goal(name, _) => Some(name),
+            SourceItem::Impl(_) | SourceItem::InherentImpl(_) | SourceItem::Clause(_) => None,
+        };
+        let mut modules: Vec<_> = path.map_or(vec![], |path| path.split("::").collect());
+        let name = modules.pop().unwrap_or("");
+        for module in &modules {
+            write!(self.fmt, "mod {} {{ ", module)?;
+        }
+        match *item {
+            SourceItem::Struct(id) => self.struct_source(id, name)?,
+            SourceItem::Trait(id) => self.trait_source(id, name)?,
+            SourceItem::Impl(id) => self.impl_source(id)?,
+            SourceItem::InherentImpl(id) => self.inherent_impl_source(id)?,
+            SourceItem::OpaqueTy(id, split) => self.opaque_ty_source(id, name, split)?,
+            SourceItem::HiddenTy(id) => self.hidden_ty_source(id, name)?,
+            SourceItem::Predicate(id) => {
+                let kinds = &program.predicate_kinds[&id].binders.binders;
+                let kinds: Vec<_> = kinds
+                    .iter(self.interner)
+                    .map(|kind| match kind {
+                        ParameterKind::Ty(()) => "type",
+                        ParameterKind::Lifetime(()) => "lifetime",
+                    })
+                    .collect();
+                write!(self.fmt, "predicate {}({});", name, kinds.join(", "))?;
+            }
+            SourceItem::Clause(clause) => self.clause_source(clause)?,
+            SourceItem::Goal(_, goal) => {
+                write!(self.fmt, "goal {} {{ ", name)?;
+                self.in_named_binders(vec![], |renderer| goal.render(renderer))?;
+                write!(self.fmt, " }}")?;
+            }
+        }
+        for _ in &modules {
+            write!(self.fmt, " }}")?;
+        }
+        Ok(())
+    }
+
+    fn struct_source(&mut self, id: StructId<ChalkIr>, name: &str) -> fmt::Result {
+        let datum = &self.program.struct_data[&id];
+        let flags = &datum.flags;
+        self.attributes(&[
+            ("upstream", flags.upstream),
+            ("fundamental", flags.fundamental),
+            ("manually_drop", flags.manually_drop),
+        ])?;
+        if flags.extern_type {
+            return write!(self.fmt, "extern type {};", name);
+        }
+        let keyword = if flags.union { "union" } else { "struct" };
+        write!(self.fmt, "{} {}", keyword, name)?;
+        let names = self.name_kinds(datum.binders.binders.iter(self.interner));
+        self.parameters(&names)?;
+        self.in_named_binders(names, |renderer| {
+            let bound = datum.binders.skip_binders();
+            renderer.where_clauses(&bound.where_clauses)?;
+            if bound.fields.is_empty() {
+                return write!(renderer.fmt, " {{ }}");
+            }
+            write!(renderer.fmt, " {{ ")?;
+            for (index, field) in bound.fields.iter().enumerate() {
+                if index > 0 {
+                    write!(renderer.fmt, ", ")?;
+                }
+                write!(renderer.fmt, "f{}: ", index)?;
+                field.render(renderer)?;
+            }
+            write!(renderer.fmt, " }}")
+        })
+    }
+
+    fn trait_source(&mut self, id: TraitId<ChalkIr>, name: &str) -> fmt::Result {
+        let program = self.program;
+        let datum = &program.trait_data[&id];
+        let flags = &datum.flags;
+        self.attributes(&[
+            ("auto", flags.auto),
+            ("marker", flags.marker),
+            ("upstream", flags.upstream),
+            ("fundamental", flags.fundamental),
+            ("non_enumerable", flags.non_enumerable),
+            ("coinductive", flags.coinductive),
+        ])?;
+        if let Some(well_known) = datum.well_known {
+            let lang = match well_known {
+                WellKnownTrait::SizedTrait => "sized",
+                WellKnownTrait::CopyTrait => "copy",
+                WellKnownTrait::CloneTrait => "clone",
+                WellKnownTrait::DropTrait => "drop",
+                WellKnownTrait::DerefTrait => "deref",
+            };
+            write!(self.fmt, "#[lang({})] ", lang)?;
+        }
+        if let Safety::Unsafe = datum.safety {
+            write!(self.fmt, "unsafe ")?;
+        }
+        write!(self.fmt, "trait {}", name)?;
+
+        // The first parameter of a trait is its `Self` type.
+        let kinds = datum.binders.binders.iter(self.interner).skip(1);
+        let names: Vec<_> = iter::once("Self".to_string())
+            .chain(self.name_kinds(kinds))
+            .collect();
+        self.parameters(&names[1..])?;
+        self.in_named_binders(names.clone(), |renderer| {
+            renderer.where_clauses(&datum.binders.skip_binders().where_clauses)
+        })?;
+
+        write!(self.fmt, " {{")?;
+        for assoc_ty_id in &datum.associated_ty_ids {
+            let assoc_ty = &program.associated_ty_data[assoc_ty_id];
+            let kinds = assoc_ty.binders.binders.iter(self.interner);
+            let own = kinds.len() - names.len();
+            let mut assoc_names = self.name_kinds(kinds.take(own));
+            write!(self.fmt, " type {}", assoc_ty.name)?;
+            self.parameters(&assoc_names)?;
+            assoc_names.extend(names.iter().cloned());
+            self.in_named_binders(assoc_names, |renderer| {
+                let bound = assoc_ty.binders.skip_binders();
+                for (index, inline_bound) in bound.bounds.iter().enumerate() {
+                    write!(renderer.fmt, "{}", if index == 0 { ": " } else { " + " })?;
+                    renderer.inline_bound(inline_bound)?;
+                }
+                renderer.where_clauses(&bound.where_clauses)?;
+                write!(renderer.fmt, ";")
+            })?;
+        }
+        for method in &datum.methods {
+            write!(self.fmt, " ")?;
+            self.method_source(method, &names)?;
+        }
+        write!(self.fmt, " }}")
+    }
+
+    fn impl_source(&mut self, id: ImplId<ChalkIr>) -> fmt::Result {
+        let program = self.program;
+        let datum = &program.impl_data[&id];
+        self.attributes(&[("upstream", datum.impl_type == ImplType::External)])?;
+        if datum.priority != 0 {
+            write!(self.fmt, "#[priority({})] ", datum.priority)?;
+        }
+        if let Safety::Unsafe = datum.safety {
+            write!(self.fmt, "unsafe ")?;
+        }
+        write!(self.fmt, "impl")?;
+        let names = self.name_kinds(datum.binders.binders.iter(self.interner));
+        self.parameters(&names)?;
+        write!(self.fmt, " ")?;
+        if datum.is_const() {
+            write!(self.fmt, "const ")?;
+        }
+        if !datum.is_positive() {
+            write!(self.fmt, "!")?;
+        }
+        self.in_named_binders(names.clone(), |renderer| {
+            let bound = datum.binders.skip_binders();
+            let parameters = bound.trait_ref.substitution.parameters(renderer.interner);
+            let name = renderer.trait_name(bound.trait_ref.trait_id);
+            write!(renderer.fmt, "{}", name)?;
+            renderer.angle(&parameters[1..])?;
+            write!(renderer.fmt, " for ")?;
+            parameters[0].render(renderer)?;
+            renderer.where_clauses(&bound.where_clauses)
+        })?;
+
+        write!(self.fmt, " {{")?;
+        for atv_id in &datum.associated_ty_value_ids {
+            let atv = &program.associated_ty_values[atv_id];
+            let assoc_ty = &program.associated_ty_data[&atv.associated_ty_id];
+            let kinds = atv.value.binders.iter(self.interner);
+            let own = kinds.len() - names.len();
+            let mut atv_names = self.name_kinds(kinds.take(own));
+            write!(self.fmt, " type {}", assoc_ty.name)?;
+            self.parameters(&atv_names)?;
+            atv_names.extend(names.iter().cloned());
+            self.in_named_binders(atv_names, |renderer| {
+                write!(renderer.fmt, " = ")?;
+                atv.value.skip_binders().ty.render(renderer)?;
+                write!(renderer.fmt, ";")
+            })?;
+        }
+        for method in &datum.methods {
+            write!(self.fmt, " ")?;
+            self.method_source(method, &names)?;
+        }
+        write!(self.fmt, " }}")
+    }
+
+    fn inherent_impl_source(&mut self, id: ImplId<ChalkIr>) -> fmt::Result {
+        let datum = &self.program.inherent_impl_data[&id];
+        write!(self.fmt, "impl")?;
+        let names = self.name_kinds(datum.binders.binders.iter(self.interner));
+        self.parameters(&names)?;
+        write!(self.fmt, " ")?;
+        self.in_named_binders(names.clone(), |renderer| {
+            let bound = datum.binders.skip_binders();
+            bound.self_ty.render(renderer)?;
+            renderer.where_clauses(&bound.where_clauses)
+        })?;
+        write!(self.fmt, " {{")?;
+        for method in &datum.methods {
+            write!(self.fmt, " ")?;
+            self.method_source(method, &names)?;
+        }
+        write!(self.fmt, " }}")
+    }
+
+    /// Renders a method of a trait or impl whose parameters are named
+    /// `outer`.
+    fn method_source(&mut self, method: &MethodDatum<ChalkIr>, outer: &[String]) -> fmt::Result {
+        let kinds = method.binders.binders.iter(self.interner);
+        let own = kinds.len() - outer.len();
+        let mut names = self.name_kinds(kinds.take(own));
+        write!(self.fmt, "fn {}", method.name)?;
+        self.parameters(&names)?;
+        names.extend(outer.iter().cloned());
+        self.in_named_binders(names, |renderer| {
+            let sig = method.binders.skip_binders();
+            write!(renderer.fmt, "(")?;
+            if let Some(receiver) = method.receiver {
+                let receiver = match receiver {
+                    Receiver::Value => "self",
+                    Receiver::Ref => "&self",
+                    Receiver::RefMut => "&mut self",
+                };
+                write!(renderer.fmt, "{}", receiver)?;
+                if !sig.inputs.is_empty() {
+                    write!(renderer.fmt, ", ")?;
+                }
+            }
+            renderer.list(&sig.inputs, ", ")?;
+            write!(renderer.fmt, ") -> ")?;
+            sig.output.render(renderer)?;
+            renderer.where_clauses(&sig.where_clauses)?;
+            write!(renderer.fmt, ";")
+        })
+    }
+
+    /// Renders an opaque type as `opaque type Foo: Bounds = Hidden;`, or,
+    /// if `split`, as `type Foo = impl Bounds;` without its hidden type.
+    fn opaque_ty_source(
+        &mut self,
+        id: OpaqueTyId<ChalkIr>,
+        name: &str,
+        split: bool,
+    ) -> fmt::Result {
+        let datum = &self.program.opaque_ty_data[&id];
+        if split {
+            write!(self.fmt, "type {}", name)?;
+        } else {
+            write!(self.fmt, "opaque type {}", name)?;
+        }
+        let names = self.name_kinds(datum.bound.binders.iter(self.interner));
+        self.parameters(&names)?;
+        self.in_named_binders(names, |renderer| {
+            let bound = datum.bound.skip_binders();
+            write!(renderer.fmt, "{}", if split { " = impl " } else { ": " })?;
+            renderer.in_named_binders(vec!["Self".to_string()], |renderer| {
+                renderer.bounds(bound.bounds.skip_binders())
+            })?;
+            if !split {
+                write!(renderer.fmt, " = ")?;
+                bound.hidden_ty.render(renderer)?;
+            }
+            write!(renderer.fmt, ";")
+        })
+    }
+
+    fn hidden_ty_source(&mut self, id: OpaqueTyId<ChalkIr>, name: &str) -> fmt::Result {
+        let datum = &self.program.opaque_ty_data[&id];
+        write!(self.fmt, "hidden type {}", name)?;
+        let names = self.name_kinds(datum.bound.binders.iter(self.interner));
+        self.parameters(&names)?;
+        self.in_named_binders(names, |renderer| {
+            write!(renderer.fmt, " = ")?;
+            datum.bound.skip_binders().hidden_ty.render(renderer)?;
+            write!(renderer.fmt, ";")
+        })
+    }
+
+    fn clause_source(&mut self, clause: &ProgramClause<ChalkIr>) -> fmt::Result {
+        let implication = match clause.data(self.interner) {
+            ProgramClauseData::ForAll(implication) => implication.as_ref(),
+            ProgramClauseData::Implies(implication) => {
+                Binders::new(ParameterKinds::new(self.interner), implication)
+            }
+        };
+        write!(self.fmt, "forall")?;
+        let names = self.name_kinds(implication.binders.iter(self.interner));
+        self.parameters(&names)?;
+        self.in_named_binders(names, |renderer| {
+            write!(renderer.fmt, " {{ ")?;
+            implication.skip_binders().render(renderer)?;
+            write!(renderer.fmt, " }}")
+        })
+    }
+
+    /// Renders the attributes of `attributes` that are set, like
+    /// `#[upstream] `.
+    fn attributes(&mut self, attributes: &[(&str, bool)]) -> fmt::Result {
+        for &(name, set) in attributes {
+            if set {
+                write!(self.fmt, "#[{}] ", name)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Renders the parameters named `names` like `<T0, 'a0>`, or nothing
+    /// if there are none.
+    fn parameters(&mut self, names: &[String]) -> fmt::Result {
+        if names.is_empty() {
+            return Ok(());
+        }
+        write!(self.fmt, "<{}>", names.join(", "))
+    }
+
+    /// Renders the where clauses of an item like ` where A, B`, leaving
+    /// out those that lowering implies. A clause like
+    /// `T: Trait<A = X, B = Y>` lowers to the alias equality on `A`, the
+    /// trait reference it implies and then the one on `B`, so an alias
+    /// equality without its implied trait reference after it is rendered
+    /// as another binding of the one before it.
+    fn where_clauses(&mut self, where_clauses: &[QuantifiedWhereClause<ChalkIr>]) -> fmt::Result {
+        let mut index = 0;
+        while let Some(where_clause) = where_clauses.get(index) {
+            write!(self.fmt, "{}", if index == 0 { " where " } else { ", " })?;
+            let implied = self.implied_where_clause(where_clause);
+            index += 1;
+            let mut more = vec![];
+            if implied.is_some() && where_clauses.get(index) == implied.as_ref() {
+                index += 1;
+                while let Some(next) = where_clauses.get(index) {
+                    match next.skip_binders() {
+                        WhereClause::AliasEq(alias_eq)
+                            if self.implied_where_clause(next) == implied
+                                && where_clauses.get(index + 1) != implied.as_ref() =>
+                        {
+                            more.push(alias_eq);
+                            index += 1;
+                        }
+                        _ => break,
+                    }
+                }
+            }
+            let render = |renderer: &mut Self| match where_clause.skip_binders() {
+                WhereClause::AliasEq(alias_eq) if !more.is_empty() => {
+                    renderer.alias_eq(alias_eq, &more)
+                }
+                where_clause => where_clause.render(renderer),
+            };
+            if where_clause.binders.is_empty(self.interner) {
+                self.in_named_binders(vec![], render)?;
+            } else {
+                write!(self.fmt, "forall")?;
+                self.in_binders(&where_clause.binders, |renderer| {
+                    write!(renderer.fmt, " ")?;
+                    render(renderer)
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Renders a bound of an associated type, like `Trait<Item = U>`.
+    fn inline_bound(&mut self, bound: &QuantifiedInlineBound<ChalkIr>) -> fmt::Result {
+        let render = |renderer: &mut Self| match bound.skip_binders() {
+            InlineBound::TraitBound(trait_bound) => renderer.trait_bound(trait_bound),
+            InlineBound::AliasEqBound(alias_eq_bound) => {
+                let trait_bound = &alias_eq_bound.trait_bound;
+                let name = renderer.trait_name(trait_bound.trait_id);
+                write!(renderer.fmt, "{}<", name)?;
+                for parameter in &trait_bound.args_no_self {
+                    parameter.render(renderer)?;
+                    write!(renderer.fmt, ", ")?;
+                }
+                let assoc_ty =
+                    &renderer.program.associated_ty_data[&alias_eq_bound.associated_ty_id];
+                write!(renderer.fmt, "{}", assoc_ty.name)?;
+                renderer.angle(&alias_eq_bound.parameters)?;
+                write!(renderer.fmt, " = ")?;
+                alias_eq_bound.value.render(renderer)?;
+                write!(renderer.fmt, ">")
+            }
+        };
+        if bound.binders.is_empty(self.interner) {
+            self.in_named_binders(vec![], render)
+        } else {
+            write!(self.fmt, "forall")?;
+            self.in_binders(&bound.binders, |renderer| {
+                write!(renderer.fmt, " ")?;
+                render(renderer)
+            })
+        }
+    }
+
+    fn trait_bound(&mut self, trait_bound: &TraitBound<ChalkIr>) -> fmt::Result {
+        let name = self.trait_name(trait_bound.trait_id);
+        write!(self.fmt, "{}", name)?;
+        self.angle(&trait_bound.args_no_self)
+    }
+}
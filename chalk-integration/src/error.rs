@@ -1,6 +1,10 @@
+use crate::diagnostic::Diagnostic;
 use crate::interner::ChalkIr;
-use chalk_parse::ast::{Identifier, Kind};
+use crate::method::MethodError;
+use chalk_parse::ast::{Identifier, Kind, Span};
+use chalk_parse::SyntaxError;
 use chalk_solve::coherence::CoherenceError;
+use chalk_solve::stratification::StratificationError;
 use chalk_solve::wf::WfError;
 
 /// Wrapper type for the various errors that can occur during chalk
@@ -8,39 +12,142 @@ use chalk_solve::wf::WfError;
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct ChalkError {
     /// For now, we just convert the error into a string, which makes
-    /// it trivially hashable etc.
+    /// it trivially hashable etc. A program that does not lower may have
+    /// several errors, one on each line.
     error_text: String,
+
+    /// The errors, with where in the program text they are, if that is
+    /// known.
+    diagnostics: Vec<Diagnostic>,
+
+    /// The line and column of the first error, both counted from 1, if
+    /// it is known.
+    position: Option<(usize, usize)>,
 }
 
-impl From<Box<dyn std::error::Error>> for ChalkError {
-    fn from(value: Box<dyn std::error::Error>) -> Self {
+impl ChalkError {
+    /// An error that is not about a particular place in the program.
+    fn message(error_text: String) -> Self {
+        ChalkError {
+            diagnostics: vec![Diagnostic {
+                message: error_text.clone(),
+                span: None,
+                label: None,
+            }],
+            error_text,
+            position: None,
+        }
+    }
+
+    /// The errors `errors` in lowering the program `text`, each at the
+    /// position in `text` of what it is about.
+    pub fn lowering(errors: Vec<RustIrError>, text: &str) -> Self {
+        let diagnostics: Vec<_> = errors.iter().map(Diagnostic::lowering).collect();
+        let messages: Vec<_> = errors.iter().map(|error| error.to_string()).collect();
+        ChalkError {
+            error_text: messages.join("\n"),
+            position: diagnostics[0].span.map(|span| span.line_column(text)),
+            diagnostics,
+        }
+    }
+
+    /// The errors `errors` in checking the well-formedness of the impls,
+    /// which are not about a particular place in the program.
+    pub fn well_formedness(errors: Vec<WfError<ChalkIr>>) -> Self {
+        let messages: Vec<_> = errors.iter().map(|error| error.to_string()).collect();
+        ChalkError {
+            error_text: messages.join("\n"),
+            diagnostics: messages
+                .into_iter()
+                .map(|message| Diagnostic {
+                    message,
+                    span: None,
+                    label: None,
+                })
+                .collect(),
+            position: None,
+        }
+    }
+
+    /// The syntax error `error` in parsing the program `text`, at the
+    /// position in `text` where the parser gave up, if that is known.
+    pub fn syntax(error: &SyntaxError, text: &str) -> Self {
         ChalkError {
-            error_text: value.to_string(),
+            error_text: error.to_string(),
+            diagnostics: vec![Diagnostic::syntax(error)],
+            position: error.span.map(|span| span.line_column(text)),
         }
     }
+
+    /// The error `error` in parsing `text`: a syntax error is at the
+    /// position in `text` where the parser gave up, if that is known.
+    pub fn parsing(error: Box<dyn std::error::Error>, text: &str) -> Self {
+        match error.downcast::<SyntaxError>() {
+            Ok(error) => ChalkError::syntax(&error, text),
+            Err(error) => error.into(),
+        }
+    }
+
+    /// The line and column in the program text of what the error is
+    /// about, both counted from 1, if it is known. Only syntax errors
+    /// and errors in lowering have one; of several, this is that of the
+    /// first.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        self.position
+    }
+
+    /// The errors, as diagnostics: there is more than one only when a
+    /// program has several errors in lowering or ill-formed impls.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Renders the errors as diagnostics against `text`, the text of the
+    /// file `file`, with an empty line between them; see
+    /// `Diagnostic::render`.
+    pub fn render(&self, file: &str, text: &str) -> String {
+        let rendered: Vec<_> = self
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.render(file, text))
+            .collect();
+        rendered.join("\n")
+    }
+}
+
+impl From<Box<dyn std::error::Error>> for ChalkError {
+    fn from(value: Box<dyn std::error::Error>) -> Self {
+        ChalkError::message(value.to_string())
+    }
 }
 
 impl From<WfError<ChalkIr>> for ChalkError {
     fn from(value: WfError<ChalkIr>) -> Self {
-        ChalkError {
-            error_text: value.to_string(),
-        }
+        ChalkError::message(value.to_string())
+    }
+}
+
+impl From<MethodError> for ChalkError {
+    fn from(value: MethodError) -> Self {
+        ChalkError::message(value.to_string())
     }
 }
 
 impl From<CoherenceError<ChalkIr>> for ChalkError {
     fn from(value: CoherenceError<ChalkIr>) -> Self {
-        ChalkError {
-            error_text: value.to_string(),
-        }
+        ChalkError::message(value.to_string())
+    }
+}
+
+impl From<StratificationError<ChalkIr>> for ChalkError {
+    fn from(value: StratificationError<ChalkIr>) -> Self {
+        ChalkError::message(value.to_string())
     }
 }
 
 impl From<RustIrError> for ChalkError {
     fn from(value: RustIrError) -> Self {
-        ChalkError {
-            error_text: value.to_string(),
-        }
+        ChalkError::message(value.to_string())
     }
 }
 
@@ -55,16 +162,29 @@ impl std::error::Error for ChalkError {}
 #[derive(Debug)]
 pub enum RustIrError {
     InvalidTypeName(Identifier),
-    InvalidLifetimeName(Identifier),
+    InvalidLifetimeName {
+        name: Identifier,
+        in_scope: Vec<crate::Identifier>,
+    },
+    InvalidPredicateName(Identifier),
+    InvalidGoalName(Identifier),
+    AnonymousLifetimeNotAllowed(Span),
     NotTrait(Identifier),
     NotStruct(Identifier),
-    DuplicateOrShadowedParameters,
+    DuplicateOrShadowedParameters(Span),
+    DuplicateParameterName(Identifier),
+    ReservedName(Identifier),
     AutoTraitAssociatedTypes(Identifier),
     AutoTraitParameters(Identifier),
     AutoTraitWhereClauses(Identifier),
     InvalidFundamentalTypesParameters(Identifier),
     NegativeImplAssociatedValues(Identifier),
     MissingAssociatedType(Identifier),
+    UnresolvedAssociatedType(Identifier),
+    AmbiguousAssociatedType {
+        name: Identifier,
+        candidates: Vec<Identifier>,
+    },
     IncorrectNumberOfTypeParameters {
         identifier: Identifier,
         expected: usize,
@@ -91,13 +211,148 @@ pub enum RustIrError {
         actual: Kind,
     },
     CannotApplyTypeParameter(Identifier),
+    UnknownAttribute(Identifier),
+    UnknownLangItem(Identifier),
+    InvalidAttributeArguments(Identifier),
+    NotOpaqueType(Identifier),
+    MissingHiddenType(Identifier),
+    DuplicateHiddenType(Identifier),
+    HiddenTypeParameterMismatch(Identifier),
+    RecursiveStruct(Identifier),
+    DuplicateMethod(Identifier),
+    AutoTraitMethods(Identifier),
+    NegativeImplMethods(Identifier),
+    UnsafeNegativeImpl(Identifier),
+    ConstNegativeImpl(Identifier),
+    SafeImplOfUnsafeTrait(Identifier),
+    UnsafeImplOfSafeTrait(Identifier),
+}
+
+impl RustIrError {
+    /// Where in the program the error is: the span of the name that it
+    /// is about or, for a parameter that is declared twice, that of what
+    /// declares it. A name that came from a template is in its body or in
+    /// an argument of the `expand`.
+    pub fn span(&self) -> Span {
+        match self {
+            RustIrError::AnonymousLifetimeNotAllowed(span)
+            | RustIrError::DuplicateOrShadowedParameters(span) => *span,
+            RustIrError::InvalidTypeName(identifier)
+            | RustIrError::InvalidLifetimeName {
+                name: identifier, ..
+            }
+            | RustIrError::InvalidPredicateName(identifier)
+            | RustIrError::InvalidGoalName(identifier)
+            | RustIrError::NotTrait(identifier)
+            | RustIrError::NotStruct(identifier)
+            | RustIrError::DuplicateParameterName(identifier)
+            | RustIrError::ReservedName(identifier)
+            | RustIrError::AutoTraitAssociatedTypes(identifier)
+            | RustIrError::AutoTraitParameters(identifier)
+            | RustIrError::AutoTraitWhereClauses(identifier)
+            | RustIrError::InvalidFundamentalTypesParameters(identifier)
+            | RustIrError::NegativeImplAssociatedValues(identifier)
+            | RustIrError::MissingAssociatedType(identifier)
+            | RustIrError::UnresolvedAssociatedType(identifier)
+            | RustIrError::AmbiguousAssociatedType {
+                name: identifier, ..
+            }
+            | RustIrError::IncorrectNumberOfTypeParameters { identifier, .. }
+            | RustIrError::IncorrectNumberOfAssociatedTypeParameters { identifier, .. }
+            | RustIrError::IncorrectParameterKind { identifier, .. }
+            | RustIrError::IncorrectTraitParameterKind { identifier, .. }
+            | RustIrError::IncorrectAssociatedTypeParameterKind { identifier, .. }
+            | RustIrError::CannotApplyTypeParameter(identifier)
+            | RustIrError::UnknownAttribute(identifier)
+            | RustIrError::UnknownLangItem(identifier)
+            | RustIrError::InvalidAttributeArguments(identifier)
+            | RustIrError::NotOpaqueType(identifier)
+            | RustIrError::MissingHiddenType(identifier)
+            | RustIrError::DuplicateHiddenType(identifier)
+            | RustIrError::HiddenTypeParameterMismatch(identifier)
+            | RustIrError::RecursiveStruct(identifier)
+            | RustIrError::DuplicateMethod(identifier)
+            | RustIrError::AutoTraitMethods(identifier)
+            | RustIrError::NegativeImplMethods(identifier)
+            | RustIrError::UnsafeNegativeImpl(identifier)
+            | RustIrError::ConstNegativeImpl(identifier)
+            | RustIrError::SafeImplOfUnsafeTrait(identifier)
+            | RustIrError::UnsafeImplOfSafeTrait(identifier) => identifier.span,
+        }
+    }
+
+    /// A short description of what the span of the error is, to label it
+    /// with in a diagnostic; the message says the rest.
+    pub fn label(&self) -> &'static str {
+        match self {
+            RustIrError::InvalidTypeName(_) => "not a type in scope",
+            RustIrError::InvalidLifetimeName { .. } => "not a lifetime in scope",
+            RustIrError::InvalidPredicateName(_) => "not a predicate in scope",
+            RustIrError::InvalidGoalName(_) => "not a goal in scope",
+            RustIrError::AnonymousLifetimeNotAllowed(_) => "lifetime needs a name",
+            RustIrError::NotTrait(_) => "not a trait",
+            RustIrError::NotStruct(_) => "not a struct",
+            RustIrError::DuplicateOrShadowedParameters(_)
+            | RustIrError::DuplicateParameterName(_) => "declared more than once",
+            RustIrError::ReservedName(_) => "reserved name",
+            RustIrError::AutoTraitAssociatedTypes(_)
+            | RustIrError::AutoTraitParameters(_)
+            | RustIrError::AutoTraitWhereClauses(_)
+            | RustIrError::AutoTraitMethods(_) => "auto trait",
+            RustIrError::InvalidFundamentalTypesParameters(_) => "fundamental type",
+            RustIrError::NegativeImplAssociatedValues(_)
+            | RustIrError::NegativeImplMethods(_)
+            | RustIrError::UnsafeNegativeImpl(_)
+            | RustIrError::ConstNegativeImpl(_) => "negative impl of this trait",
+            RustIrError::MissingAssociatedType(_) => "not an associated type of the trait",
+            RustIrError::UnresolvedAssociatedType(_) => "not declared by any bound",
+            RustIrError::AmbiguousAssociatedType { .. } => "ambiguous associated type",
+            RustIrError::IncorrectNumberOfTypeParameters { .. }
+            | RustIrError::IncorrectNumberOfAssociatedTypeParameters { .. } => {
+                "wrong number of parameters"
+            }
+            RustIrError::IncorrectParameterKind { .. }
+            | RustIrError::IncorrectTraitParameterKind { .. }
+            | RustIrError::IncorrectAssociatedTypeParameterKind { .. } => "wrong kind of parameter",
+            RustIrError::CannotApplyTypeParameter(_) => "type parameter",
+            RustIrError::UnknownAttribute(_) => "unknown attribute",
+            RustIrError::UnknownLangItem(_) => "unknown lang item",
+            RustIrError::InvalidAttributeArguments(_) => "invalid arguments",
+            RustIrError::NotOpaqueType(_) => "not an opaque type",
+            RustIrError::MissingHiddenType(_) => "opaque type without a hidden type",
+            RustIrError::DuplicateHiddenType(_) => "hidden type defined again",
+            RustIrError::HiddenTypeParameterMismatch(_) => "parameters differ",
+            RustIrError::RecursiveStruct(_) => "recursive without indirection",
+            RustIrError::DuplicateMethod(_) => "defined more than once",
+            RustIrError::SafeImplOfUnsafeTrait(_) => "unsafe trait",
+            RustIrError::UnsafeImplOfSafeTrait(_) => "safe trait",
+        }
+    }
 }
 
 impl std::fmt::Display for RustIrError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             RustIrError::InvalidTypeName(name) => write!(f, "invalid type name `{}`", name),
-            RustIrError::InvalidLifetimeName(name) => write!(f, "invalid lifetime name `{}`", name),
+            RustIrError::InvalidLifetimeName { name, in_scope } => {
+                write!(f, "invalid lifetime name `{}`", name)?;
+                let in_scope: Vec<_> = in_scope
+                    .iter()
+                    .map(|lifetime| format!("`{}`", lifetime))
+                    .collect();
+                match in_scope.as_slice() {
+                    [] => write!(f, "; no lifetimes are in scope"),
+                    [lifetime] => write!(f, "; the lifetime in scope is {}", lifetime),
+                    _ => write!(f, "; the lifetimes in scope are {}", in_scope.join(", ")),
+                }
+            }
+            RustIrError::InvalidPredicateName(name) => {
+                write!(f, "invalid predicate name `{}`", name)
+            }
+            RustIrError::InvalidGoalName(name) => write!(f, "invalid goal name `{}`", name),
+            RustIrError::AnonymousLifetimeNotAllowed(_) => {
+                write!(f, "anonymous lifetimes are not allowed here")
+            }
             RustIrError::NotTrait(name) => write!(
                 f,
                 "expected a trait, found `{}`, which is not a trait",
@@ -108,9 +363,15 @@ impl std::fmt::Display for RustIrError {
                 "expected a struct, found `{}`, which is not a struct",
                 name
             ),
-            RustIrError::DuplicateOrShadowedParameters => {
+            RustIrError::DuplicateOrShadowedParameters(_) => {
                 write!(f, "duplicate or shadowed parameters")
             }
+            RustIrError::DuplicateParameterName(name) => {
+                write!(f, "duplicate parameter name `{}`", name)
+            }
+            RustIrError::ReservedName(name) => {
+                write!(f, "`{}` is a reserved name and cannot be defined", name)
+            }
             RustIrError::AutoTraitAssociatedTypes(name) => {
                 write!(f, "auto trait `{}` cannot define associated types", name)
             }
@@ -133,6 +394,21 @@ impl std::fmt::Display for RustIrError {
             RustIrError::MissingAssociatedType(name) => {
                 write!(f, "no associated type `{}` defined in trait", name)
             }
+            RustIrError::UnresolvedAssociatedType(name) => {
+                write!(f, "no bound in scope declares associated type `{}`", name)
+            }
+            RustIrError::AmbiguousAssociatedType { name, candidates } => {
+                let candidates: Vec<_> = candidates
+                    .iter()
+                    .map(|candidate| format!("`{}`", candidate))
+                    .collect();
+                write!(
+                    f,
+                    "ambiguous associated type `{}`, which could come from {}",
+                    name,
+                    candidates.join(", ")
+                )
+            }
             RustIrError::IncorrectNumberOfTypeParameters {
                 identifier,
                 expected,
@@ -181,6 +457,57 @@ impl std::fmt::Display for RustIrError {
             RustIrError::CannotApplyTypeParameter(name) => {
                 write!(f, "cannot apply type parameter `{}`", name)
             }
+            RustIrError::UnknownAttribute(name) => write!(f, "unknown attribute `{}`", name),
+            RustIrError::UnknownLangItem(name) => write!(f, "unknown lang item `{}`", name),
+            RustIrError::InvalidAttributeArguments(name) => {
+                write!(f, "invalid arguments for attribute `{}`", name)
+            }
+            RustIrError::NotOpaqueType(name) => write!(
+                f,
+                "expected an opaque type, found `{}`, which is not an opaque type",
+                name
+            ),
+            RustIrError::MissingHiddenType(name) => {
+                write!(f, "no hidden type defined for opaque type `{}`", name)
+            }
+            RustIrError::DuplicateHiddenType(name) => {
+                write!(f, "hidden type for opaque type `{}` defined more than once", name)
+            }
+            RustIrError::HiddenTypeParameterMismatch(name) => write!(
+                f,
+                "hidden type for opaque type `{}` must declare the same parameters as the opaque type",
+                name
+            ),
+            RustIrError::RecursiveStruct(name) => write!(
+                f,
+                "recursive struct `{}` has infinite size; insert some indirection to make it representable",
+                name
+            ),
+            RustIrError::DuplicateMethod(name) => {
+                write!(f, "duplicate definitions with name `{}`", name)
+            }
+            RustIrError::AutoTraitMethods(name) => {
+                write!(f, "auto trait `{}` cannot define methods", name)
+            }
+            RustIrError::NegativeImplMethods(name) => write!(
+                f,
+                "negative impl for trait `{}` cannot define methods",
+                name
+            ),
+            RustIrError::UnsafeNegativeImpl(name) => {
+                write!(f, "negative impl for trait `{}` cannot be unsafe", name)
+            }
+            RustIrError::ConstNegativeImpl(name) => {
+                write!(f, "negative impl for trait `{}` cannot be const", name)
+            }
+            RustIrError::SafeImplOfUnsafeTrait(name) => write!(
+                f,
+                "the trait `{}` requires an `unsafe impl` declaration",
+                name
+            ),
+            RustIrError::UnsafeImplOfSafeTrait(name) => {
+                write!(f, "implementing the trait `{}` is not unsafe", name)
+            }
         }
     }
 }
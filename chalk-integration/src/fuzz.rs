@@ -0,0 +1,554 @@
+//! Structured generation of programs and goals for fuzzing, with the
+//! `arbitrary` crate: a `FuzzProgram` is built from the fuzzer's bytes as
+//! a well-formed AST, so that the fuzzer spends its time in lowering and
+//! solving rather than on programs that do not parse.
+//!
+//! The programs only use the items, types and goals that every solver
+//! supports: structs, traits with associated types and where clauses,
+//! impls, and scalar and tuple types.
+
+use crate::db::ChalkDatabase;
+use crate::interner::ChalkIr;
+use crate::lowering::LowerGoal;
+use crate::program::Program as LoweredProgram;
+use crate::query::LoweringDatabase;
+use crate::tls;
+use arbitrary::{Arbitrary, Result, Unstructured};
+use chalk_parse::ast::*;
+use chalk_solve::ext::*;
+use chalk_solve::{Budget, RustIrDatabase, Solution, SolverChoice};
+use std::sync::Arc;
+use string_cache::DefaultAtom as Atom;
+
+/// The number of steps each solver may take for a goal.
+const MAX_STEPS: usize = 2_000;
+
+/// A program and goals against it.
+#[derive(Clone, Debug)]
+pub struct FuzzProgram {
+    pub program: Program,
+    pub goals: Vec<Goal>,
+}
+
+impl<'a> Arbitrary<'a> for FuzzProgram {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Generator::new(u).program()
+    }
+}
+
+impl FuzzProgram {
+    /// The text of the program.
+    pub fn text(&self) -> String {
+        chalk_parse::print_program(&self.program)
+    }
+
+    /// Checks that the program parses and lowers, and that the lowered
+    /// program round trips; see `check_source_round_trip`.
+    ///
+    /// The coherence and well-formedness checks that `checked_program`
+    /// runs after lowering are left out: their goals are solved without a
+    /// budget, and so may not terminate on a generated program.
+    pub fn check_lowering(&self) -> (ChalkDatabase, Arc<LoweredProgram>) {
+        let text = self.text();
+        if let Err(error) = chalk_parse::parse_program(&text) {
+            panic!("generated program does not parse: {}\n{}", error, text);
+        }
+        let db = ChalkDatabase::with(&text, SolverChoice::default());
+        match db.program_ir() {
+            Ok(program) => {
+                check_source_round_trip(&text, &program);
+                (db, program)
+            }
+            Err(error) => panic!("generated program does not lower: {}\n{}", error, text),
+        }
+    }
+
+    /// Lowers the program as `check_lowering` does, then solves each goal
+    /// with the SLG and the recursive solver, checking that they do not
+    /// contradict each other: a goal may be ambiguous for one solver and
+    /// not for the other, but not proven by one and disproven by the
+    /// other.
+    pub fn check_solvers(&self) {
+        let (db, program) = self.check_lowering();
+        tls::set_current_program(&program, || {
+            for goal in &self.goals {
+                let text = chalk_parse::print_goal(goal);
+                let goal = match goal.lower(&*program) {
+                    Ok(goal) => goal.into_peeled_goal(db.interner()),
+                    Err(error) => panic!("generated goal does not lower: {}\n{}", error, text),
+                };
+                let budget = Budget {
+                    max_steps: Some(MAX_STEPS),
+                    max_duration: None,
+                };
+                let solver_choices = [SolverChoice::slg_default(), SolverChoice::recursive()];
+                let outcomes: Vec<_> = solver_choices
+                    .iter()
+                    .map(|solver_choice| {
+                        let solution = solver_choice
+                            .into_solver()
+                            .solve_with_budget(&db, &goal, budget);
+                        (solver_choice, solution.ok().and_then(definite))
+                    })
+                    .collect();
+                if let [(_, Some(slg)), (_, Some(recursive))] = &outcomes[..] {
+                    assert_eq!(
+                        slg,
+                        recursive,
+                        "the solvers disagree on `{}`: {:?}\n{}",
+                        text,
+                        outcomes,
+                        self.text()
+                    );
+                }
+            }
+        });
+    }
+}
+
+/// Checks that `program`, lowered from `text`, lowers back to itself
+/// from its rendering with `Program::display_source`.
+pub fn check_source_round_trip(text: &str, program: &LoweredProgram) {
+    let source = program.display_source().to_string();
+    let db = ChalkDatabase::with(&source, SolverChoice::default());
+    let relowered = match db.program_ir() {
+        Ok(relowered) => relowered,
+        Err(error) => panic!(
+            "rendered program does not lower: {}\n{}\nrendered from:\n{}",
+            error, source, text
+        ),
+    };
+    if *relowered == *program {
+        return;
+    }
+    let expected = format!("{:#?}", program);
+    let actual = format!("{:#?}", relowered);
+    let line = expected
+        .lines()
+        .zip(actual.lines())
+        .position(|(expected, actual)| expected != actual)
+        .unwrap_or(0);
+    let context = |text: &str| {
+        let lines: Vec<_> = text.lines().collect();
+        lines[line.saturating_sub(5)..(line + 1).min(lines.len())].join("\n")
+    };
+    panic!(
+        "rendered program lowers differently:\n{}\nexpected:\n{}\nactual:\n{}\n\
+         rendered from:\n{}",
+        source,
+        context(&expected),
+        context(&actual),
+        text
+    );
+}
+
+/// Whether a goal was proven, if it was proven or disproven.
+fn definite(solution: Option<Solution<ChalkIr>>) -> Option<bool> {
+    match solution {
+        Some(Solution::Unique(_)) => Some(true),
+        Some(Solution::Ambig(_)) => None,
+        None => Some(false),
+    }
+}
+
+struct TraitInfo {
+    name: String,
+    parameters: usize,
+    assoc_tys: Vec<String>,
+}
+
+struct Generator<'a, 'u> {
+    u: &'u mut Unstructured<'a>,
+    structs: Vec<(String, usize)>,
+    traits: Vec<TraitInfo>,
+
+    /// The number of structs that types may use. The fields of a struct
+    /// only use the structs before it, as a struct cannot contain itself.
+    usable_structs: usize,
+}
+
+impl<'a, 'u> Generator<'a, 'u> {
+    fn new(u: &'u mut Unstructured<'a>) -> Self {
+        Generator {
+            u,
+            structs: vec![],
+            traits: vec![],
+            usable_structs: 0,
+        }
+    }
+
+    fn program(mut self) -> Result<FuzzProgram> {
+        // Names are declared before the items are generated, so that items
+        // can refer to the items after them.
+        for s in 0..self.u.int_in_range(1..=4)? {
+            let parameters = self.u.int_in_range(0..=2)?;
+            self.structs.push((format!("S{}", s), parameters));
+        }
+        for t in 0..self.u.int_in_range(1..=4)? {
+            let parameters = self.u.int_in_range(0..=1)?;
+            let assoc_tys = (0..self.u.int_in_range(0..=1)?)
+                .map(|a| format!("A{}", a))
+                .collect();
+            self.traits.push(TraitInfo {
+                name: format!("T{}", t),
+                parameters,
+                assoc_tys,
+            });
+        }
+
+        let mut items = vec![];
+        for s in 0..self.structs.len() {
+            self.usable_structs = s;
+            items.push(Item::StructDefn(self.struct_defn(s)?));
+        }
+        self.usable_structs = self.structs.len();
+        for t in 0..self.traits.len() {
+            items.push(Item::TraitDefn(self.trait_defn(t)?));
+        }
+        for _ in 0..self.u.int_in_range(0..=8)? {
+            items.push(Item::Impl(self.impl_defn()?));
+        }
+
+        let mut goals = vec![];
+        for _ in 0..self.u.int_in_range(1..=4)? {
+            goals.push(self.goal()?);
+        }
+        Ok(FuzzProgram {
+            program: Program { items },
+            goals,
+        })
+    }
+
+    fn struct_defn(&mut self, index: usize) -> Result<StructDefn> {
+        let (name, parameters) = self.structs[index].clone();
+        let scope = parameter_names("P", parameters);
+        let mut fields = vec![];
+        for f in 0..self.u.int_in_range(0..=2)? {
+            fields.push(Field {
+                name: identifier(&format!("f{}", f)),
+                ty: self.ty(&scope, 2)?,
+            });
+        }
+        Ok(StructDefn {
+            name: identifier(&name),
+            parameter_kinds: parameter_kinds(&scope),
+            where_clauses: self.where_clauses(&scope)?,
+            fields,
+            flags: StructFlags {
+                upstream: false,
+                fundamental: false,
+                union: false,
+                manually_drop: false,
+                extern_type: false,
+            },
+            attributes: vec![],
+            span: no_span(),
+        })
+    }
+
+    fn trait_defn(&mut self, index: usize) -> Result<TraitDefn> {
+        let name = self.traits[index].name.clone();
+        let scope = parameter_names("P", self.traits[index].parameters);
+        let mut where_clauses = vec![];
+        if self.u.ratio(1, 3)? {
+            let self_ty = Ty::Id {
+                name: identifier("Self"),
+                span: no_span(),
+            };
+            where_clauses.push(self.where_clause(self_ty, &scope)?);
+        }
+        where_clauses.extend(self.where_clauses(&scope)?);
+        let mut assoc_ty_defns = vec![];
+        for assoc_ty in self.traits[index].assoc_tys.clone() {
+            let mut bounds = vec![];
+            if self.u.ratio(1, 3)? {
+                let trait_ref = self.trait_ref(
+                    Ty::Tuple {
+                        types: vec![],
+                        span: no_span(),
+                    },
+                    &scope,
+                )?;
+                bounds.push(QuantifiedInlineBound {
+                    parameter_kinds: vec![],
+                    bound: InlineBound::TraitBound(TraitBound {
+                        trait_name: trait_ref.trait_name,
+                        args_no_self: trait_ref.args[1..].to_vec(),
+                    }),
+                    span: no_span(),
+                });
+            }
+            assoc_ty_defns.push(AssocTyDefn {
+                name: identifier(&assoc_ty),
+                parameter_kinds: vec![],
+                bounds,
+                where_clauses: vec![],
+            });
+        }
+        Ok(TraitDefn {
+            name: identifier(&name),
+            parameter_kinds: parameter_kinds(&scope),
+            where_clauses,
+            assoc_ty_defns,
+            methods: vec![],
+            flags: TraitFlags {
+                auto: false,
+                marker: false,
+                upstream: false,
+                fundamental: false,
+                non_enumerable: false,
+                coinductive: false,
+            },
+            safety: Safety::Safe,
+            well_known: None,
+            attributes: vec![],
+            span: no_span(),
+        })
+    }
+
+    fn impl_defn(&mut self) -> Result<Impl> {
+        let scope = parameter_names("I", self.u.int_in_range(0..=2)?);
+        let self_ty = self.ty(&scope, 2)?;
+        let trait_ref = self.trait_ref(self_ty, &scope)?;
+        let assoc_tys = self
+            .traits
+            .iter()
+            .find(|t| *t.name == *trait_ref.trait_name.str)
+            .unwrap()
+            .assoc_tys
+            .clone();
+        let mut assoc_ty_values = vec![];
+        for assoc_ty in assoc_tys {
+            assoc_ty_values.push(AssocTyValue {
+                name: identifier(&assoc_ty),
+                parameter_kinds: vec![],
+                value: self.ty(&scope, 2)?,
+                default: false,
+            });
+        }
+        Ok(Impl {
+            parameter_kinds: parameter_kinds(&scope),
+            trait_ref,
+            polarity: Polarity::Positive,
+            safety: Safety::Safe,
+            constness: Constness::NotConst,
+            where_clauses: self.where_clauses(&scope)?,
+            assoc_ty_values,
+            methods: vec![],
+            impl_type: ImplType::Local,
+            attributes: vec![],
+            span: no_span(),
+        })
+    }
+
+    fn goal(&mut self) -> Result<Goal> {
+        let x = vec!["X".to_string()];
+        let x_ty = Ty::Id {
+            name: identifier("X"),
+            span: no_span(),
+        };
+        Ok(match self.u.int_in_range(0..=4)? {
+            0 => {
+                let self_ty = self.ty(&[], 2)?;
+                self.implemented(self_ty, &[])?
+            }
+            1 => Goal::Exists(parameter_kinds(&x), Box::new(self.implemented(x_ty, &x)?)),
+            2 => {
+                let hypothesis = match self.where_clause(x_ty.clone(), &x)?.where_clause {
+                    WhereClause::Implemented { trait_ref } => trait_ref,
+                    _ => unreachable!(),
+                };
+                let self_ty = self.ty(&x, 2)?;
+                let goal = self.implemented(self_ty, &x)?;
+                let clause = Clause {
+                    parameter_kinds: vec![],
+                    consequence: DomainGoal::Holds {
+                        where_clause: WhereClause::Implemented {
+                            trait_ref: hypothesis,
+                        },
+                    },
+                    conditions: vec![],
+                    span: no_span(),
+                };
+                Goal::ForAll(
+                    parameter_kinds(&x),
+                    Box::new(Goal::Implies(vec![clause], Box::new(goal))),
+                )
+            }
+            3 => {
+                let self_ty = self.ty(&[], 2)?;
+                Goal::Not(Box::new(self.implemented(self_ty, &[])?))
+            }
+            _ => {
+                // `exists<X> { <Ty as Trait>::A = X }`, or a trait goal if
+                // no trait has an associated type.
+                let with_assoc_ty: Vec<_> = (0..self.traits.len())
+                    .filter(|&t| !self.traits[t].assoc_tys.is_empty())
+                    .collect();
+                if with_assoc_ty.is_empty() {
+                    let self_ty = self.ty(&[], 2)?;
+                    return self.implemented(self_ty, &[]);
+                }
+                let t = *self.u.choose(&with_assoc_ty)?;
+                let self_ty = self.ty(&[], 2)?;
+                let mut args = vec![Parameter::Ty(self_ty)];
+                for _ in 0..self.traits[t].parameters {
+                    args.push(Parameter::Ty(self.ty(&[], 1)?));
+                }
+                let projection = ProjectionTy {
+                    trait_ref: TraitRef {
+                        trait_name: identifier(&self.traits[t].name),
+                        args,
+                    },
+                    name: identifier(&self.traits[t].assoc_tys[0]),
+                    args: vec![],
+                };
+                Goal::Exists(
+                    parameter_kinds(&x),
+                    Box::new(Goal::Leaf(LeafGoal::UnifyTys {
+                        a: Ty::Projection {
+                            proj: projection,
+                            span: no_span(),
+                        },
+                        b: x_ty,
+                    })),
+                )
+            }
+        })
+    }
+
+    fn implemented(&mut self, self_ty: Ty, scope: &[String]) -> Result<Goal> {
+        let trait_ref = self.trait_ref(self_ty, scope)?;
+        Ok(Goal::Leaf(LeafGoal::DomainGoal {
+            goal: DomainGoal::Holds {
+                where_clause: WhereClause::Implemented { trait_ref },
+            },
+        }))
+    }
+
+    /// Up to two where clauses on the parameters in `scope`.
+    fn where_clauses(&mut self, scope: &[String]) -> Result<Vec<QuantifiedWhereClause>> {
+        let mut where_clauses = vec![];
+        if scope.is_empty() {
+            return Ok(where_clauses);
+        }
+        for _ in 0..self.u.int_in_range(0..=2)? {
+            let parameter = self.u.choose(scope)?;
+            let self_ty = Ty::Id {
+                name: identifier(parameter),
+                span: no_span(),
+            };
+            where_clauses.push(self.where_clause(self_ty, scope)?);
+        }
+        Ok(where_clauses)
+    }
+
+    fn where_clause(&mut self, self_ty: Ty, scope: &[String]) -> Result<QuantifiedWhereClause> {
+        Ok(QuantifiedWhereClause {
+            parameter_kinds: vec![],
+            where_clause: WhereClause::Implemented {
+                trait_ref: self.trait_ref(self_ty, scope)?,
+            },
+            span: no_span(),
+        })
+    }
+
+    fn trait_ref(&mut self, self_ty: Ty, scope: &[String]) -> Result<TraitRef> {
+        let t = self.u.int_in_range(0..=self.traits.len() - 1)?;
+        let mut args = vec![Parameter::Ty(self_ty)];
+        for _ in 0..self.traits[t].parameters {
+            args.push(Parameter::Ty(self.ty(scope, 1)?));
+        }
+        Ok(TraitRef {
+            trait_name: identifier(&self.traits[t].name),
+            args,
+        })
+    }
+
+    /// A type whose parameters are in `scope`, nested at most `depth`
+    /// deep.
+    fn ty(&mut self, scope: &[String], depth: usize) -> Result<Ty> {
+        let choice = self.u.int_in_range(0..=3)?;
+        Ok(match choice {
+            0 if !scope.is_empty() => Ty::Id {
+                name: identifier(self.u.choose(scope)?),
+                span: no_span(),
+            },
+            _ if self.usable_structs == 0 => Ty::Scalar {
+                ty: ScalarType::Bool,
+                span: no_span(),
+            },
+            1 if depth > 0 => {
+                let types = if self.u.arbitrary()? {
+                    vec![
+                        Box::new(self.ty(scope, depth - 1)?),
+                        Box::new(self.ty(scope, depth - 1)?),
+                    ]
+                } else {
+                    vec![]
+                };
+                Ty::Tuple {
+                    types,
+                    span: no_span(),
+                }
+            }
+            2 => Ty::Scalar {
+                ty: *self
+                    .u
+                    .choose(&[ScalarType::Bool, ScalarType::Uint(UintTy::U32)])?,
+                span: no_span(),
+            },
+            _ => {
+                let s = self.u.int_in_range(0..=self.usable_structs - 1)?;
+                let (name, parameters) = self.structs[s].clone();
+                let mut args = vec![];
+                for _ in 0..parameters {
+                    let arg = if depth > 0 {
+                        self.ty(scope, depth - 1)?
+                    } else {
+                        Ty::Scalar {
+                            ty: ScalarType::Bool,
+                            span: no_span(),
+                        }
+                    };
+                    args.push(Parameter::Ty(arg));
+                }
+                if args.is_empty() {
+                    Ty::Id {
+                        name: identifier(&name),
+                        span: no_span(),
+                    }
+                } else {
+                    Ty::Apply {
+                        name: identifier(&name),
+                        args,
+                        span: no_span(),
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Generated programs have no text, so nothing in them has a span.
+fn no_span() -> Span {
+    Span::new(0, 0)
+}
+
+fn identifier(name: &str) -> Identifier {
+    Identifier {
+        str: Atom::from(name),
+        span: no_span(),
+    }
+}
+
+fn parameter_names(prefix: &str, count: usize) -> Vec<String> {
+    (0..count).map(|i| format!("{}{}", prefix, i)).collect()
+}
+
+fn parameter_kinds(names: &[String]) -> Vec<ParameterKind> {
+    names
+        .iter()
+        .map(|name| ParameterKind::Ty(identifier(name)))
+        .collect()
+}
@@ -1,22 +1,30 @@
 use crate::tls;
-use chalk_ir::interner::{HasInterner, Interner};
+use chalk_ir::flags::TypeFlags;
+use chalk_ir::interner::{HasInterner, Interner, SerdeInterner};
 use chalk_ir::{
-    AliasTy, ApplicationTy, AssocTypeId, CanonicalVarKinds, Goals, Lifetime, OpaqueTy, OpaqueTyId,
-    ParameterKinds, ProgramClauseImplication, ProgramClauses, ProjectionTy, QuantifiedWhereClauses,
-    SeparatorTraitRef, Substitution, TraitId, Ty,
+    AliasTy, ApplicationTy, AssocTypeId, CanonicalVarKinds, Goals, ImplId, Lifetime, OpaqueTy,
+    OpaqueTyId, ParameterKinds, PredicateId, PredicateRef, ProgramClauseImplication,
+    ProgramClauses, ProjectionTy, QuantifiedWhereClauses, SeparatorTraitRef, Substitution, TraitId,
+    Ty,
 };
 use chalk_ir::{
     Goal, GoalData, LifetimeData, Parameter, ParameterData, ParameterKind, ProgramClause,
     ProgramClauseData, QuantifiedWhereClause, StructId, TyData, UniverseIndex,
 };
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Borrow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
 use std::fmt;
 use std::fmt::Debug;
-use std::sync::Arc;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
 use string_cache::DefaultAtom;
 
 pub type Identifier = DefaultAtom;
 
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct RawId {
     pub index: u32,
 }
@@ -28,12 +36,134 @@ impl Debug for RawId {
 }
 
 /// The default "interner" and the only interner used by chalk
-/// itself. In this interner, no interning actually occurs.
+/// itself. In this interner, only types are actually interned, see
+/// `InternedTy`.
 #[derive(Debug, Copy, Clone, Hash, PartialOrd, Ord, PartialEq, Eq)]
 pub struct ChalkIr;
 
+/// A type interned by `ChalkIr`. Equal types are interned only once, so
+/// they share an allocation, and are compared and hashed by address
+/// rather than by walking them. The flags of the type are computed
+/// when it is interned.
+#[derive(Clone)]
+pub struct InternedTy(Arc<TyEntry>);
+
+struct TyEntry {
+    data: TyData<ChalkIr>,
+    flags: TypeFlags,
+}
+
+impl PartialEq for InternedTy {
+    fn eq(&self, other: &InternedTy) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for InternedTy {}
+
+impl Hash for InternedTy {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (&*self.0 as *const TyEntry).hash(state)
+    }
+}
+
+impl Debug for InternedTy {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&self.0.data, fmt)
+    }
+}
+
+impl Serialize for InternedTy {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.data.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for InternedTy {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(ChalkIr.intern_ty(TyData::deserialize(deserializer)?))
+    }
+}
+
+/// An entry of the `TypeTable`, which is looked up by the data of the
+/// type.
+struct TableEntry(Arc<TyEntry>);
+
+impl PartialEq for TableEntry {
+    fn eq(&self, other: &TableEntry) -> bool {
+        self.0.data == other.0.data
+    }
+}
+
+impl Eq for TableEntry {}
+
+impl Hash for TableEntry {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.data.hash(state)
+    }
+}
+
+impl Borrow<TyData<ChalkIr>> for TableEntry {
+    fn borrow(&self) -> &TyData<ChalkIr> {
+        &self.0.data
+    }
+}
+
+/// The types interned so far, in one shard of `TYPE_TABLE`. Since the
+/// types within a type are interned before it, hashing and comparing a
+/// type only looks at its immediate contents.
+struct TypeTable {
+    types: HashSet<TableEntry>,
+
+    /// The table is pruned of the types no longer used outside of it
+    /// when it grows to this many types.
+    prune_at: usize,
+}
+
+/// The number of shards of `TYPE_TABLE`.
+const TYPE_TABLE_SHARDS: usize = 16;
+
+lazy_static! {
+    /// The types interned so far, split by hash into shards, each behind
+    /// a lock of its own, so that threads solving goals in parallel only
+    /// contend when they intern types of the same shard at once.
+    static ref TYPE_TABLE: Vec<Mutex<TypeTable>> = (0..TYPE_TABLE_SHARDS)
+        .map(|_| {
+            Mutex::new(TypeTable {
+                types: HashSet::new(),
+                prune_at: 1024,
+            })
+        })
+        .collect();
+}
+
+/// The shard of `TYPE_TABLE` that `data` is interned in.
+fn type_table_shard(data: &TyData<ChalkIr>) -> &'static Mutex<TypeTable> {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    &TYPE_TABLE[hasher.finish() as usize % TYPE_TABLE_SHARDS]
+}
+
+impl TypeTable {
+    fn intern(&mut self, data: TyData<ChalkIr>, flags: TypeFlags) -> Arc<TyEntry> {
+        if let Some(interned) = self.types.get(&data) {
+            return interned.0.clone();
+        }
+        if self.types.len() >= self.prune_at {
+            // Each prune frees the types that became unused since the
+            // last, and the table is let grow to twice the size of what
+            // is left, so that pruning takes amortized constant time.
+            self.types.retain(|entry| Arc::strong_count(&entry.0) > 1);
+            self.prune_at = (2 * self.types.len()).max(1024);
+        }
+        let interned = Arc::new(TyEntry { data, flags });
+        self.types.insert(TableEntry(interned.clone()));
+        interned
+    }
+}
+
 impl Interner for ChalkIr {
-    type InternedType = Arc<TyData<ChalkIr>>;
+    type InternedType = InternedTy;
     type InternedLifetime = LifetimeData<ChalkIr>;
     type InternedParameter = ParameterData<ChalkIr>;
     type InternedGoal = Arc<GoalData<ChalkIr>>;
@@ -68,6 +198,10 @@ impl Interner for ChalkIr {
         tls::with_current_program(|prog| Some(prog?.debug_assoc_type_id(id, fmt)))
     }
 
+    fn debug_impl_id(id: ImplId<ChalkIr>, fmt: &mut fmt::Formatter<'_>) -> Option<fmt::Result> {
+        tls::with_current_program(|prog| Some(prog?.debug_impl_id(id, fmt)))
+    }
+
     fn debug_opaque_ty_id(
         id: OpaqueTyId<ChalkIr>,
         fmt: &mut fmt::Formatter<'_>,
@@ -75,6 +209,13 @@ impl Interner for ChalkIr {
         tls::with_current_program(|prog| Some(prog?.debug_opaque_ty_id(id, fmt)))
     }
 
+    fn debug_predicate_id(
+        id: PredicateId<ChalkIr>,
+        fmt: &mut fmt::Formatter<'_>,
+    ) -> Option<fmt::Result> {
+        tls::with_current_program(|prog| Some(prog?.debug_predicate_id(id, fmt)))
+    }
+
     fn debug_alias(alias: &AliasTy<ChalkIr>, fmt: &mut fmt::Formatter<'_>) -> Option<fmt::Result> {
         tls::with_current_program(|prog| Some(prog?.debug_alias(alias, fmt)))
     }
@@ -173,6 +314,13 @@ impl Interner for ChalkIr {
         tls::with_current_program(|prog| Some(prog?.debug_application_ty(application_ty, fmt)))
     }
 
+    fn debug_predicate_ref(
+        predicate_ref: &PredicateRef<ChalkIr>,
+        fmt: &mut fmt::Formatter<'_>,
+    ) -> Option<fmt::Result> {
+        tls::with_current_program(|prog| Some(prog?.debug_predicate_ref(predicate_ref, fmt)))
+    }
+
     fn debug_substitution(
         substitution: &Substitution<ChalkIr>,
         fmt: &mut fmt::Formatter<'_>,
@@ -196,12 +344,18 @@ impl Interner for ChalkIr {
         tls::with_current_program(|prog| Some(prog?.debug_quantified_where_clauses(clauses, fmt)))
     }
 
-    fn intern_ty(&self, ty: TyData<ChalkIr>) -> Arc<TyData<ChalkIr>> {
-        Arc::new(ty)
+    fn intern_ty(&self, ty: TyData<ChalkIr>) -> InternedTy {
+        let flags = ty.compute_flags(self);
+        let shard = type_table_shard(&ty);
+        InternedTy(shard.lock().unwrap().intern(ty, flags))
     }
 
-    fn ty_data<'a>(&self, ty: &'a Arc<TyData<ChalkIr>>) -> &'a TyData<Self> {
-        ty
+    fn ty_data<'a>(&self, ty: &'a InternedTy) -> &'a TyData<Self> {
+        &ty.0.data
+    }
+
+    fn ty_flags(&self, ty: &InternedTy) -> TypeFlags {
+        ty.0.flags
     }
 
     fn intern_lifetime(&self, lifetime: LifetimeData<ChalkIr>) -> LifetimeData<ChalkIr> {
@@ -323,6 +477,47 @@ impl Interner for ChalkIr {
     }
 }
 
+/// Implements the methods of `SerdeInterner` for the interned types
+/// given, which all implement `Serialize` and `Deserialize`.
+macro_rules! forward_to_serde {
+    ($($interned:ident => $serialize:ident, $deserialize:ident;)*) => {
+        $(
+            fn $serialize<S: Serializer>(
+                value: &Self::$interned,
+                serializer: S,
+            ) -> Result<S::Ok, S::Error> {
+                value.serialize(serializer)
+            }
+
+            fn $deserialize<'de, D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<Self::$interned, D::Error> {
+                Deserialize::deserialize(deserializer)
+            }
+        )*
+    };
+}
+
+impl SerdeInterner for ChalkIr {
+    forward_to_serde! {
+        InternedType => serialize_ty, deserialize_ty;
+        InternedLifetime => serialize_lifetime, deserialize_lifetime;
+        InternedParameter => serialize_parameter, deserialize_parameter;
+        InternedGoal => serialize_goal, deserialize_goal;
+        InternedGoals => serialize_goals, deserialize_goals;
+        InternedSubstitution => serialize_substitution, deserialize_substitution;
+        InternedProgramClause => serialize_program_clause, deserialize_program_clause;
+        InternedProgramClauses => serialize_program_clauses, deserialize_program_clauses;
+        InternedQuantifiedWhereClauses
+            => serialize_quantified_where_clauses, deserialize_quantified_where_clauses;
+        InternedParameterKinds => serialize_parameter_kinds, deserialize_parameter_kinds;
+        InternedCanonicalVarKinds
+            => serialize_canonical_var_kinds, deserialize_canonical_var_kinds;
+        DefId => serialize_def_id, deserialize_def_id;
+        Identifier => serialize_identifier, deserialize_identifier;
+    }
+}
+
 impl HasInterner for ChalkIr {
     type Interner = ChalkIr;
 }
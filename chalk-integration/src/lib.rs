@@ -3,37 +3,52 @@
 
 #[macro_use]
 extern crate chalk_macros;
+#[macro_use]
+extern crate lazy_static;
 
+pub mod batch;
+pub mod builder;
 pub mod db;
+pub mod diagnostic;
+pub mod display;
 pub mod error;
+#[cfg(feature = "arbitrary")]
+pub mod fuzz;
 pub mod interner;
 pub mod lowering;
+pub mod lsp;
+pub mod method;
 pub mod program;
 pub mod program_environment;
 pub mod query;
+pub mod reduce;
+pub mod report;
 pub mod tls;
+pub mod validate;
 
 use chalk_ir::interner::HasInterner;
 use chalk_ir::Binders;
 use interner::ChalkIr;
+use serde::{Deserialize, Serialize};
 
 pub use interner::{Identifier, RawId};
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum TypeSort {
     Struct,
     Trait,
     Opaque,
+    Predicate,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Unit;
 
 impl HasInterner for Unit {
     type Interner = ChalkIr;
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct TypeKind {
     pub sort: TypeSort,
     pub name: Identifier,
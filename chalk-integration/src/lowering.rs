@@ -3,26 +3,34 @@ use chalk_ir::cast::{Cast, Caster};
 use chalk_ir::interner::HasInterner;
 use chalk_ir::{
     self, AssocTypeId, BoundVar, ClausePriority, DebruijnIndex, ImplId, OpaqueTyId, ParameterKinds,
-    QuantifiedWhereClauses, StructId, Substitution, ToParameter, TraitId,
+    PredicateId, QuantifiedWhereClauses, StructId, Substitution, TraitId,
 };
 use chalk_parse::ast::*;
 use chalk_rust_ir as rust_ir;
 use chalk_rust_ir::{
     Anonymize, AssociatedTyValueId, IntoWhereClauses, OpaqueTyDatum, OpaqueTyDatumBound,
 };
-use std::collections::BTreeMap;
+use petgraph::algo::tarjan_scc;
+use petgraph::Graph;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
 use std::sync::Arc;
 use string_cache::DefaultAtom as Atom;
 
 use crate::error::RustIrError;
+use crate::method;
 use crate::program::Program as LoweredProgram;
 use crate::{Identifier as Ident, RawId, TypeKind, TypeSort};
 
 type StructIds = BTreeMap<Ident, chalk_ir::StructId<ChalkIr>>;
 type TraitIds = BTreeMap<Ident, chalk_ir::TraitId<ChalkIr>>;
 type OpaqueTyIds = BTreeMap<Ident, chalk_ir::OpaqueTyId<ChalkIr>>;
+type PredicateIds = BTreeMap<Ident, chalk_ir::PredicateId<ChalkIr>>;
 type StructKinds = BTreeMap<chalk_ir::StructId<ChalkIr>, TypeKind>;
 type TraitKinds = BTreeMap<chalk_ir::TraitId<ChalkIr>, TypeKind>;
+type PredicateKinds = BTreeMap<chalk_ir::PredicateId<ChalkIr>, TypeKind>;
+type MethodPredicateIds = BTreeMap<Ident, chalk_ir::PredicateId<ChalkIr>>;
+type GoalDefns = BTreeMap<Ident, chalk_ir::Goal<ChalkIr>>;
 type AssociatedTyLookups = BTreeMap<(chalk_ir::TraitId<ChalkIr>, Ident), AssociatedTyLookup>;
 type AssociatedTyValueIds =
     BTreeMap<(chalk_ir::ImplId<ChalkIr>, Ident), AssociatedTyValueId<ChalkIr>>;
@@ -37,7 +45,15 @@ struct Env<'k> {
     trait_ids: &'k TraitIds,
     trait_kinds: &'k TraitKinds,
     opaque_ty_ids: &'k OpaqueTyIds,
+    predicate_ids: &'k PredicateIds,
+    predicate_kinds: &'k PredicateKinds,
+    method_predicate_ids: &'k MethodPredicateIds,
+    goal_defns: &'k GoalDefns,
     associated_ty_lookups: &'k AssociatedTyLookups,
+    /// Path of the module containing the item being lowered (e.g. `a::b`),
+    /// or the empty string at the top level. Names are resolved relative to
+    /// this module first, and then relative to each enclosing module.
+    module: &'k str,
     /// Parameter identifiers are used as keys, therefore
     /// all identifiers in an environment must be unique (no shadowing).
     parameter_map: ParameterMap,
@@ -81,7 +97,808 @@ enum LifetimeLookup {
 const SELF: &str = "Self";
 const FIXME_SELF: &str = "__FIXME_SELF__";
 
+/// Qualifies `name` with the path of the module it is defined in.
+fn qualify(module: &str, name: &Ident) -> Ident {
+    if module.is_empty() {
+        name.clone()
+    } else {
+        Atom::from(format!("{}::{}", module, name))
+    }
+}
+
+const STRUCT_ATTRIBUTES: &[&str] = &["upstream", "fundamental", "manually_drop"];
+const TRAIT_ATTRIBUTES: &[&str] = &[
+    "auto",
+    "marker",
+    "upstream",
+    "fundamental",
+    "non_enumerable",
+    "coinductive",
+    "lang",
+];
+const IMPL_ATTRIBUTES: &[&str] = &["upstream", "priority"];
+const INHERENT_IMPL_ATTRIBUTES: &[&str] = &[];
+
+/// Checks that every attribute on an item is one of `known`. Only
+/// `#[lang(..)]` and `#[priority(..)]` take an argument, which must
+/// respectively name a well-known trait and be a number.
+fn check_attributes(attributes: &[Attribute], known: &[&str]) -> LowerResult<()> {
+    for attr in attributes {
+        if !known.iter().any(|name| attr.is(name)) {
+            Err(RustIrError::UnknownAttribute(attr.name.clone()))?;
+        }
+        if attr.is("lang") {
+            match attr.args.as_slice() {
+                [arg] if WellKnownTrait::from_name(&arg.str).is_some() => {}
+                [arg] => Err(RustIrError::UnknownLangItem(arg.clone()))?,
+                _ => Err(RustIrError::InvalidAttributeArguments(attr.name.clone()))?,
+            }
+        } else if attr.is("priority") {
+            match attr.args.as_slice() {
+                [arg] if arg.str.parse::<u32>().is_ok() => {}
+                _ => Err(RustIrError::InvalidAttributeArguments(attr.name.clone()))?,
+            }
+        } else if !attr.args.is_empty() {
+            Err(RustIrError::InvalidAttributeArguments(attr.name.clone()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Names that lowering gives a meaning of its own, and that therefore
+/// cannot be used to name items or parameters.
+const RESERVED_NAMES: &[&str] = &[SELF, FIXME_SELF];
+
+fn check_name(name: &Identifier) -> LowerResult<()> {
+    if RESERVED_NAMES.contains(&&*name.str) {
+        Err(RustIrError::ReservedName(name.clone()))?;
+    }
+    Ok(())
+}
+
+/// Checks that no two methods of a trait or impl share a name, and that
+/// each method's parameter names are distinct.
+fn check_methods(methods: &[MethodDefn]) -> LowerResult<()> {
+    let mut names = BTreeSet::new();
+    for method in methods {
+        method.check_parameter_names()?;
+        if !names.insert(&method.name.str) {
+            Err(RustIrError::DuplicateMethod(method.name.clone()))?;
+        }
+    }
+    Ok(())
+}
+
+/// An item of a program other than a `mod` block, as produced by
+/// `flatten_items`, so that lowering never meets a `mod` block.
+#[derive(Clone, Debug)]
+enum FlatItem {
+    StructDefn(StructDefn),
+    TraitDefn(TraitDefn),
+    OpaqueTyDefn(OpaqueTyDefn),
+    HiddenTyDefn(HiddenTyDefn),
+    Impl(Impl),
+    InherentImpl(InherentImpl),
+    Clause(Clause),
+    PredicateDefn(PredicateDefn),
+    GoalDefn(GoalDefn),
+}
+
+/// Flattens nested `mod` blocks into a list of the items they contain,
+/// paired with the path of the module that defines them.
+fn flatten_items(module: &str, items: &[Item], out: &mut Vec<(Ident, FlatItem)>) {
+    for item in items {
+        let item = match item {
+            Item::ModDefn(defn) => {
+                let module = qualify(module, &defn.name.str);
+                flatten_items(&module, &defn.items, out);
+                continue;
+            }
+            Item::StructDefn(defn) => FlatItem::StructDefn(defn.clone()),
+            Item::TraitDefn(defn) => FlatItem::TraitDefn(defn.clone()),
+            Item::OpaqueTyDefn(defn) => FlatItem::OpaqueTyDefn(defn.clone()),
+            Item::HiddenTyDefn(defn) => FlatItem::HiddenTyDefn(defn.clone()),
+            Item::Impl(defn) => FlatItem::Impl(defn.clone()),
+            Item::InherentImpl(defn) => FlatItem::InherentImpl(defn.clone()),
+            Item::Clause(clause) => FlatItem::Clause(clause.clone()),
+            Item::PredicateDefn(defn) => FlatItem::PredicateDefn(defn.clone()),
+            Item::GoalDefn(defn) => FlatItem::GoalDefn(defn.clone()),
+        };
+        out.push((Atom::from(module), item));
+    }
+}
+
+/// A mutable walk over the types and lifetimes of an AST fragment, used to
+/// desugar items before they are lowered.
+trait AstRewriter: Sized {
+    fn rewrite_ty(&mut self, ty: &mut Ty) {
+        super_rewrite_ty(ty, self)
+    }
+
+    fn rewrite_lifetime(&mut self, _lifetime: &mut Lifetime) {}
+}
+
+trait RewriteAst {
+    fn rewrite_with<R: AstRewriter>(&mut self, rewriter: &mut R);
+}
+
+fn super_rewrite_ty<R: AstRewriter>(ty: &mut Ty, rewriter: &mut R) {
+    match ty {
+        Ty::Id { .. } | Ty::Scalar { .. } => {}
+        Ty::Dyn { bounds, .. } => bounds.rewrite_with(rewriter),
+        Ty::Apply { args, .. } => args.rewrite_with(rewriter),
+        Ty::Projection { proj, .. } => proj.rewrite_with(rewriter),
+        Ty::ForAll { ty, .. } => ty.rewrite_with(rewriter),
+        Ty::Tuple { types, .. } => types.rewrite_with(rewriter),
+        Ty::Ref { lifetime, ty, .. } => {
+            lifetime.rewrite_with(rewriter);
+            ty.rewrite_with(rewriter);
+        }
+    }
+}
+
+impl<T: RewriteAst> RewriteAst for Vec<T> {
+    fn rewrite_with<R: AstRewriter>(&mut self, rewriter: &mut R) {
+        for value in self {
+            value.rewrite_with(rewriter);
+        }
+    }
+}
+
+impl<T: RewriteAst> RewriteAst for Box<T> {
+    fn rewrite_with<R: AstRewriter>(&mut self, rewriter: &mut R) {
+        (**self).rewrite_with(rewriter)
+    }
+}
+
+impl<T: RewriteAst> RewriteAst for Option<T> {
+    fn rewrite_with<R: AstRewriter>(&mut self, rewriter: &mut R) {
+        if let Some(value) = self {
+            value.rewrite_with(rewriter);
+        }
+    }
+}
+
+impl RewriteAst for Ty {
+    fn rewrite_with<R: AstRewriter>(&mut self, rewriter: &mut R) {
+        rewriter.rewrite_ty(self)
+    }
+}
+
+impl RewriteAst for Lifetime {
+    fn rewrite_with<R: AstRewriter>(&mut self, rewriter: &mut R) {
+        rewriter.rewrite_lifetime(self)
+    }
+}
+
+impl RewriteAst for Parameter {
+    fn rewrite_with<R: AstRewriter>(&mut self, rewriter: &mut R) {
+        match self {
+            Parameter::Ty(ty) => ty.rewrite_with(rewriter),
+            Parameter::Lifetime(lifetime) => lifetime.rewrite_with(rewriter),
+        }
+    }
+}
+
+impl RewriteAst for ProjectionTy {
+    fn rewrite_with<R: AstRewriter>(&mut self, rewriter: &mut R) {
+        self.trait_ref.rewrite_with(rewriter);
+        self.args.rewrite_with(rewriter);
+    }
+}
+
+impl RewriteAst for TraitRef {
+    fn rewrite_with<R: AstRewriter>(&mut self, rewriter: &mut R) {
+        self.args.rewrite_with(rewriter)
+    }
+}
+
+impl RewriteAst for QuantifiedInlineBound {
+    fn rewrite_with<R: AstRewriter>(&mut self, rewriter: &mut R) {
+        match &mut self.bound {
+            InlineBound::TraitBound(bound) => bound.args_no_self.rewrite_with(rewriter),
+            InlineBound::AliasEqBound(bound) => {
+                bound.trait_bound.args_no_self.rewrite_with(rewriter);
+                bound.args.rewrite_with(rewriter);
+                bound.value.rewrite_with(rewriter);
+            }
+        }
+    }
+}
+
+impl RewriteAst for QuantifiedWhereClause {
+    fn rewrite_with<R: AstRewriter>(&mut self, rewriter: &mut R) {
+        self.where_clause.rewrite_with(rewriter)
+    }
+}
+
+impl RewriteAst for WhereClause {
+    fn rewrite_with<R: AstRewriter>(&mut self, rewriter: &mut R) {
+        match self {
+            WhereClause::Implemented { trait_ref }
+            | WhereClause::ConstImplemented { trait_ref } => trait_ref.rewrite_with(rewriter),
+            WhereClause::ProjectionEq { projection, ty } => {
+                projection.rewrite_with(rewriter);
+                ty.rewrite_with(rewriter);
+            }
+        }
+    }
+}
+
+impl RewriteAst for DomainGoal {
+    fn rewrite_with<R: AstRewriter>(&mut self, rewriter: &mut R) {
+        match self {
+            DomainGoal::Holds { where_clause } => where_clause.rewrite_with(rewriter),
+            DomainGoal::Normalize { projection, ty } => {
+                projection.rewrite_with(rewriter);
+                ty.rewrite_with(rewriter);
+            }
+            DomainGoal::TraitRefWellFormed { trait_ref }
+            | DomainGoal::TraitRefFromEnv { trait_ref }
+            | DomainGoal::LocalImplAllowed { trait_ref } => trait_ref.rewrite_with(rewriter),
+            DomainGoal::TyWellFormed { ty }
+            | DomainGoal::TyFromEnv { ty }
+            | DomainGoal::IsLocal { ty }
+            | DomainGoal::IsUpstream { ty }
+            | DomainGoal::IsFullyVisible { ty }
+            | DomainGoal::DownstreamType { ty } => ty.rewrite_with(rewriter),
+            DomainGoal::Predicate { args, .. } => args.rewrite_with(rewriter),
+            DomainGoal::Compatible | DomainGoal::Reveal => {}
+        }
+    }
+}
+
+impl RewriteAst for LeafGoal {
+    fn rewrite_with<R: AstRewriter>(&mut self, rewriter: &mut R) {
+        match self {
+            LeafGoal::DomainGoal { goal } => goal.rewrite_with(rewriter),
+            LeafGoal::UnifyTys { a, b } => {
+                a.rewrite_with(rewriter);
+                b.rewrite_with(rewriter);
+            }
+            LeafGoal::UnifyLifetimes { a, b } | LeafGoal::LifetimeOutlives { a, b } => {
+                a.rewrite_with(rewriter);
+                b.rewrite_with(rewriter);
+            }
+            LeafGoal::TypeOutlives { a, b } => {
+                a.rewrite_with(rewriter);
+                b.rewrite_with(rewriter);
+            }
+            LeafGoal::Method { probe } => probe.receiver.rewrite_with(rewriter),
+        }
+    }
+}
+
+impl RewriteAst for MethodDefn {
+    fn rewrite_with<R: AstRewriter>(&mut self, rewriter: &mut R) {
+        self.inputs.rewrite_with(rewriter);
+        self.output.rewrite_with(rewriter);
+        self.where_clauses.rewrite_with(rewriter);
+    }
+}
+
+/// Gives each anonymous lifetime (written `'_`, or left out of a reference
+/// type like `&T`) a fresh name that cannot clash with a user-written one,
+/// collecting the names so that they can be bound as lifetime parameters.
+#[derive(Default)]
+struct AnonymousLifetimes {
+    count: usize,
+    names: Vec<ParameterKind>,
+}
+
+impl AnonymousLifetimes {
+    /// Starts after the anonymous lifetimes named in the scope of `env`,
+    /// so that the names given to those of a goal never shadow them.
+    fn after(env: &Env) -> Self {
+        let count = env
+            .parameter_map
+            .keys()
+            .filter_map(|kind| match kind {
+                chalk_ir::ParameterKind::Lifetime(name) => {
+                    name.strip_prefix("'_#")?.parse::<usize>().ok()
+                }
+                _ => None,
+            })
+            .max()
+            .map_or(0, |index| index + 1);
+        AnonymousLifetimes {
+            count,
+            names: vec![],
+        }
+    }
+
+    /// Takes the names collected so far; the count keeps going, so that
+    /// names introduced in nested binders never shadow outer ones.
+    fn take(&mut self) -> Vec<ParameterKind> {
+        std::mem::take(&mut self.names)
+    }
+
+    /// Anonymous lifetimes in a signature become lifetime parameters of
+    /// the method, as with `fn get(&self, &K) -> &V`.
+    fn name_in_methods(&mut self, methods: &mut Vec<MethodDefn>) {
+        for method in methods {
+            method.rewrite_with(self);
+            method.parameter_kinds.extend(self.take());
+        }
+    }
+
+    /// Anonymous lifetimes in the where clauses of a trait or struct are
+    /// bound by the where clause they appear in, so `where &'_ T: Foo`
+    /// means `where forall<'a> { &'a T: Foo }`.
+    fn name_in_where_clauses(&mut self, where_clauses: &mut Vec<QuantifiedWhereClause>) {
+        for where_clause in where_clauses {
+            where_clause.where_clause.rewrite_with(self);
+            where_clause.parameter_kinds.extend(self.take());
+        }
+    }
+
+    /// Anonymous lifetimes in an impl header become lifetime parameters
+    /// of the impl, as with `impl Foo for &u32`, and those in the
+    /// consequence of a custom clause become parameters of the clause.
+    /// Anywhere else other than in where clauses and method signatures,
+    /// such as in struct fields, they are rejected during lowering.
+    fn name_in_item(&mut self, item: &mut FlatItem) {
+        match item {
+            FlatItem::StructDefn(defn) => self.name_in_where_clauses(&mut defn.where_clauses),
+            FlatItem::TraitDefn(defn) => {
+                self.name_in_where_clauses(&mut defn.where_clauses);
+                self.name_in_methods(&mut defn.methods);
+            }
+            FlatItem::Impl(defn) => {
+                defn.trait_ref.rewrite_with(self);
+                defn.where_clauses.rewrite_with(self);
+                defn.parameter_kinds.extend(self.take());
+                self.name_in_methods(&mut defn.methods);
+            }
+            FlatItem::InherentImpl(defn) => {
+                defn.self_ty.rewrite_with(self);
+                defn.where_clauses.rewrite_with(self);
+                defn.parameter_kinds.extend(self.take());
+                self.name_in_methods(&mut defn.methods);
+            }
+            FlatItem::Clause(clause) => {
+                clause.consequence.rewrite_with(self);
+                clause.parameter_kinds.extend(self.take());
+            }
+            _ => {}
+        }
+    }
+}
+
+impl AstRewriter for AnonymousLifetimes {
+    fn rewrite_lifetime(&mut self, lifetime: &mut Lifetime) {
+        if let Lifetime::Anonymous { span } = *lifetime {
+            let name = Identifier {
+                str: Atom::from(format!("'_#{}", self.count)),
+                span,
+            };
+            self.count += 1;
+            self.names.push(ParameterKind::Lifetime(name.clone()));
+            *lifetime = Lifetime::Id { name };
+        }
+    }
+}
+
+/// Replaces `Self` with the self type of the enclosing impl.
+struct ImplSelfTy<'a>(&'a Ty);
+
+impl ImplSelfTy<'_> {
+    /// `Self` may be used in the where clauses, associated type values and
+    /// method signatures of an impl, but not in its header.
+    fn resolve_in_item(item: &mut FlatItem) {
+        match item {
+            FlatItem::Impl(defn) => {
+                let self_ty = match defn.trait_ref.args.first() {
+                    Some(Parameter::Ty(ty)) => ty.clone(),
+                    _ => return,
+                };
+                let rewriter = &mut ImplSelfTy(&self_ty);
+                defn.where_clauses.rewrite_with(rewriter);
+                for assoc_ty_value in &mut defn.assoc_ty_values {
+                    assoc_ty_value.value.rewrite_with(rewriter);
+                }
+                defn.methods.rewrite_with(rewriter);
+            }
+            FlatItem::InherentImpl(defn) => {
+                let self_ty = defn.self_ty.clone();
+                let rewriter = &mut ImplSelfTy(&self_ty);
+                defn.where_clauses.rewrite_with(rewriter);
+                defn.methods.rewrite_with(rewriter);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl AstRewriter for ImplSelfTy<'_> {
+    fn rewrite_ty(&mut self, ty: &mut Ty) {
+        match ty {
+            Ty::Id { name, .. } if &*name.str == SELF => *ty = self.0.clone(),
+            _ => super_rewrite_ty(ty, self),
+        }
+    }
+}
+
+/// Replaces anonymous lifetimes with `'static` in the receiver of a method
+/// probe: lifetimes take no part in picking a method, so any will do.
+struct ErasedLifetimes;
+
+impl AstRewriter for ErasedLifetimes {
+    fn rewrite_lifetime(&mut self, lifetime: &mut Lifetime) {
+        if let Lifetime::Anonymous { .. } = lifetime {
+            *lifetime = Lifetime::Static;
+        }
+    }
+}
+
+/// Desugars an item before it is lowered: anonymous lifetimes are named and
+/// bound, then `Self` is resolved inside impls.
+fn desugar_item(mut item: FlatItem) -> FlatItem {
+    AnonymousLifetimes::default().name_in_item(&mut item);
+    ImplSelfTy::resolve_in_item(&mut item);
+    item
+}
+
+/// Rewrites shorthand projections like `T::Item` into `<T as Trait>::Item`,
+/// where `Trait` is the one trait among the bounds on `T` in scope that
+/// declares an associated type `Item`.
+struct ShorthandProjections<'a, 'k> {
+    env: &'a Env<'k>,
+    /// Type parameters in scope, including `Self` where it is one.
+    parameters: Vec<Ident>,
+    /// Trait bounds in scope, keyed by the name of the type they bound.
+    bounds: Vec<(Ident, TraitRef)>,
+    error: Option<RustIrError>,
+}
+
+impl<'a, 'k> ShorthandProjections<'a, 'k> {
+    fn new(env: &'a Env<'k>) -> Self {
+        ShorthandProjections {
+            env,
+            parameters: vec![],
+            bounds: vec![],
+            error: None,
+        }
+    }
+
+    /// Brings the parameters and the where clauses of an item into scope
+    /// for the duration of `op`.
+    fn in_scope(
+        &mut self,
+        parameter_kinds: &[ParameterKind],
+        where_clauses: &[QuantifiedWhereClause],
+        op: impl FnOnce(&mut Self),
+    ) {
+        let (num_parameters, num_bounds) = (self.parameters.len(), self.bounds.len());
+        self.parameters
+            .extend(parameter_kinds.iter().filter_map(|kind| match kind {
+                ParameterKind::Ty(name) => Some(name.str.clone()),
+                ParameterKind::Lifetime(_) => None,
+            }));
+        self.bounds.extend(
+            where_clauses
+                .iter()
+                .filter_map(|qwc| match &qwc.where_clause {
+                    WhereClause::Implemented { trait_ref }
+                    | WhereClause::ProjectionEq {
+                        projection: ProjectionTy { trait_ref, .. },
+                        ..
+                    } => match trait_ref.args.first() {
+                        Some(Parameter::Ty(Ty::Id { name, .. })) => {
+                            Some((name.str.clone(), trait_ref.clone()))
+                        }
+                        _ => None,
+                    },
+                    _ => None,
+                }),
+        );
+        op(self);
+        self.parameters.truncate(num_parameters);
+        self.bounds.truncate(num_bounds);
+    }
+
+    fn rewrite_methods(&mut self, methods: &mut Vec<MethodDefn>) {
+        for method in methods {
+            let (parameter_kinds, where_clauses) =
+                (method.parameter_kinds.clone(), method.where_clauses.clone());
+            self.in_scope(&parameter_kinds, &where_clauses, |this| {
+                method.rewrite_with(this)
+            });
+        }
+    }
+
+    fn rewrite_item(&mut self, item: &mut FlatItem) {
+        let self_param = |span| {
+            ParameterKind::Ty(Identifier {
+                str: Atom::from(SELF),
+                span,
+            })
+        };
+        match item {
+            FlatItem::StructDefn(defn) => {
+                let (parameter_kinds, where_clauses) =
+                    (defn.parameter_kinds.clone(), defn.where_clauses.clone());
+                self.in_scope(&parameter_kinds, &where_clauses, |this| {
+                    for field in &mut defn.fields {
+                        field.ty.rewrite_with(this);
+                    }
+                    defn.where_clauses.rewrite_with(this);
+                });
+            }
+            FlatItem::TraitDefn(defn) => {
+                // Inside a trait, `Self` is bounded by the trait itself.
+                let mut parameter_kinds = vec![self_param(defn.span)];
+                parameter_kinds.extend(defn.parameter_kinds.iter().cloned());
+                let mut args = vec![Parameter::Ty(Ty::Id {
+                    name: Identifier {
+                        str: Atom::from(SELF),
+                        span: defn.name.span,
+                    },
+                    span: defn.name.span,
+                })];
+                args.extend(defn.parameter_kinds.iter().map(|kind| match kind {
+                    ParameterKind::Ty(name) => Parameter::Ty(Ty::Id {
+                        name: name.clone(),
+                        span: name.span,
+                    }),
+                    ParameterKind::Lifetime(name) => {
+                        Parameter::Lifetime(Lifetime::Id { name: name.clone() })
+                    }
+                }));
+                let trait_ref = TraitRef {
+                    trait_name: defn.name.clone(),
+                    args,
+                };
+                let where_clauses = defn.where_clauses.clone();
+                self.bounds.push((Atom::from(SELF), trait_ref));
+                self.in_scope(&parameter_kinds, &where_clauses, |this| {
+                    defn.where_clauses.rewrite_with(this);
+                    for assoc_ty_defn in &mut defn.assoc_ty_defns {
+                        let (parameter_kinds, where_clauses) = (
+                            assoc_ty_defn.parameter_kinds.clone(),
+                            assoc_ty_defn.where_clauses.clone(),
+                        );
+                        this.in_scope(&parameter_kinds, &where_clauses, |this| {
+                            assoc_ty_defn.bounds.rewrite_with(this);
+                            assoc_ty_defn.where_clauses.rewrite_with(this);
+                        });
+                    }
+                    this.rewrite_methods(&mut defn.methods);
+                });
+                self.bounds.pop();
+            }
+            FlatItem::Impl(defn) => {
+                // Inside an impl, `Self` is bounded by the implemented trait.
+                let mut parameter_kinds = vec![self_param(defn.span)];
+                parameter_kinds.extend(defn.parameter_kinds.iter().cloned());
+                let where_clauses = defn.where_clauses.clone();
+                self.bounds.push((Atom::from(SELF), defn.trait_ref.clone()));
+                self.in_scope(&parameter_kinds, &where_clauses, |this| {
+                    defn.where_clauses.rewrite_with(this);
+                    for assoc_ty_value in &mut defn.assoc_ty_values {
+                        let parameter_kinds = assoc_ty_value.parameter_kinds.clone();
+                        this.in_scope(&parameter_kinds, &[], |this| {
+                            assoc_ty_value.value.rewrite_with(this)
+                        });
+                    }
+                    this.rewrite_methods(&mut defn.methods);
+                });
+                self.bounds.pop();
+            }
+            FlatItem::InherentImpl(defn) => {
+                let (parameter_kinds, where_clauses) =
+                    (defn.parameter_kinds.clone(), defn.where_clauses.clone());
+                self.in_scope(&parameter_kinds, &where_clauses, |this| {
+                    defn.where_clauses.rewrite_with(this);
+                    this.rewrite_methods(&mut defn.methods);
+                });
+            }
+            _ => {}
+        }
+    }
+
+    /// Resolves `name` as a shorthand projection, if its prefix is a type
+    /// parameter in scope.
+    fn resolve(&self, name: &Identifier, args: &[Parameter]) -> LowerResult<Option<ProjectionTy>> {
+        let (prefix, assoc_name) = match name.str.rfind("::") {
+            Some(index) => (&name.str[..index], &name.str[index + 2..]),
+            None => return Ok(None),
+        };
+        if !self.parameters.iter().any(|param| &**param == prefix) {
+            return Ok(None);
+        }
+
+        let assoc_name = Atom::from(assoc_name);
+        let mut candidates: Vec<&TraitRef> = vec![];
+        for (bounded, trait_ref) in &self.bounds {
+            let declares_assoc_ty = self
+                .env
+                .resolve(self.env.trait_ids, &trait_ref.trait_name)
+                .map_or(false, |&trait_id| {
+                    self.env
+                        .associated_ty_lookups
+                        .contains_key(&(trait_id, assoc_name.clone()))
+                });
+            // `T: Iterator` and `T: Iterator<Item = U>` are the same bound,
+            // written in different places.
+            let written = trait_ref.to_string();
+            if &**bounded == prefix
+                && declares_assoc_ty
+                && !candidates.iter().any(|c| c.to_string() == written)
+            {
+                candidates.push(trait_ref);
+            }
+        }
+
+        match candidates.as_slice() {
+            [] => Err(RustIrError::UnresolvedAssociatedType(name.clone())),
+            [trait_ref] => Ok(Some(ProjectionTy {
+                trait_ref: (*trait_ref).clone(),
+                name: Identifier {
+                    str: assoc_name,
+                    span: name.span,
+                },
+                args: args.to_vec(),
+            })),
+            _ => Err(RustIrError::AmbiguousAssociatedType {
+                name: name.clone(),
+                candidates: candidates
+                    .iter()
+                    .map(|trait_ref| trait_ref.trait_name.clone())
+                    .collect(),
+            }),
+        }
+    }
+}
+
+impl AstRewriter for ShorthandProjections<'_, '_> {
+    fn rewrite_ty(&mut self, ty: &mut Ty) {
+        super_rewrite_ty(ty, self);
+        let resolved = match ty {
+            Ty::Id { name, .. } => self.resolve(name, &[]),
+            Ty::Apply { name, args, .. } => self.resolve(name, args),
+            _ => return,
+        };
+        match resolved {
+            Ok(Some(proj)) => {
+                let span = ty.span();
+                *ty = Ty::Projection { proj, span };
+            }
+            Ok(None) => {}
+            Err(error) => {
+                self.error.get_or_insert(error);
+            }
+        }
+    }
+}
+
+impl<'k> Env<'k> {
+    /// Rewrites the shorthand projections like `T::Item` in `item`; this
+    /// needs the traits to be known, so it happens just before lowering.
+    fn resolve_shorthand_projections(&self, item: &FlatItem) -> LowerResult<FlatItem> {
+        let mut item = item.clone();
+        let mut rewriter = ShorthandProjections::new(self);
+        rewriter.rewrite_item(&mut item);
+        match rewriter.error {
+            Some(error) => Err(error),
+            None => Ok(item),
+        }
+    }
+}
+
+/// Collects what `ty` contains without any indirection, i.e. directly or
+/// through the fields of the structs and tuples it contains: the structs go
+/// into `structs` and the type parameters of the enclosing struct into
+/// `params`. `by_value_params` tells, for each struct, which of its
+/// parameters are themselves held by value.
+fn by_value_contents(
+    by_value_params: &BTreeMap<StructId<ChalkIr>, Vec<bool>>,
+    ty: &chalk_ir::Ty<ChalkIr>,
+    structs: &mut Vec<StructId<ChalkIr>>,
+    params: &mut Vec<usize>,
+) {
+    let interner = &ChalkIr;
+    let application_ty = match ty.data(interner) {
+        chalk_ir::TyData::BoundVar(bv) if bv.debruijn == DebruijnIndex::INNERMOST => {
+            return params.push(bv.index);
+        }
+        chalk_ir::TyData::Apply(application_ty) => application_ty,
+        _ => return,
+    };
+    let substitution = application_ty.substitution.iter(interner);
+    match application_ty.name {
+        chalk_ir::TypeName::Struct(id) => {
+            structs.push(id);
+            for (parameter, &by_value) in substitution.zip(&by_value_params[&id]) {
+                if let Some(ty) = parameter.ty(interner).filter(|_| by_value) {
+                    by_value_contents(by_value_params, ty, structs, params);
+                }
+            }
+        }
+        chalk_ir::TypeName::Tuple(_) => {
+            for ty in substitution.filter_map(|p| p.ty(interner)) {
+                by_value_contents(by_value_params, ty, structs, params);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Finds the structs that contain themselves without any indirection, and so
+/// would have infinite size: those in a cycle of the graph of which structs
+/// contain which by value. Returns, for each of them, the index of its cycle.
+fn recursive_structs(
+    struct_data: &BTreeMap<StructId<ChalkIr>, Arc<rust_ir::StructDatum<ChalkIr>>>,
+) -> BTreeMap<StructId<ChalkIr>, usize> {
+    let interner = &ChalkIr;
+
+    // First find which parameters each struct holds by value; a parameter
+    // passed on by value to another struct counts once that one holds it.
+    let mut by_value_params: BTreeMap<_, _> = struct_data
+        .iter()
+        .map(|(&id, datum)| (id, vec![false; datum.binders.len(interner)]))
+        .collect();
+    loop {
+        let mut changed = false;
+        for (&id, datum) in struct_data {
+            let mut params = vec![];
+            for field in &datum.binders.skip_binders().fields {
+                by_value_contents(&by_value_params, field, &mut vec![], &mut params);
+            }
+            for index in params {
+                let by_value = &mut by_value_params.get_mut(&id).unwrap()[index];
+                changed |= !*by_value;
+                *by_value = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let mut graph = Graph::new();
+    let nodes: BTreeMap<_, _> = struct_data
+        .keys()
+        .map(|&id| (id, graph.add_node(id)))
+        .collect();
+    for (id, datum) in struct_data {
+        let mut structs = vec![];
+        for field in &datum.binders.skip_binders().fields {
+            by_value_contents(&by_value_params, field, &mut structs, &mut vec![]);
+        }
+        for target in structs {
+            graph.update_edge(nodes[id], nodes[&target], ());
+        }
+    }
+
+    tarjan_scc(&graph)
+        .into_iter()
+        .filter(|scc| scc.len() > 1 || graph.contains_edge(scc[0], scc[0]))
+        .enumerate()
+        .flat_map(|(cycle, scc)| scc.into_iter().map(move |node| (node, cycle)))
+        .map(|(node, cycle)| (graph[node], cycle))
+        .collect()
+}
+
 impl<'k> Env<'k> {
+    /// Looks `name` up in `map`, starting in the current module and walking
+    /// outwards through the enclosing modules up to the top level.
+    fn resolve<'m, V>(&self, map: &'m BTreeMap<Ident, V>, name: &Identifier) -> Option<&'m V> {
+        let mut module = self.module;
+        loop {
+            if let Some(v) = map.get(&qualify(module, &name.str)) {
+                return Some(v);
+            }
+            if module.is_empty() {
+                return None;
+            }
+            module = match module.rfind("::") {
+                Some(i) => &module[..i],
+                None => "",
+            };
+        }
+    }
+
     fn lookup_type(&self, name: &Identifier) -> LowerResult<TypeLookup> {
         if let Some(k) = self
             .parameter_map
@@ -90,14 +907,14 @@ impl<'k> Env<'k> {
             return Ok(TypeLookup::Parameter(*k));
         }
 
-        if let Some(id) = self.struct_ids.get(&name.str) {
+        if let Some(id) = self.resolve(self.struct_ids, name) {
             return Ok(TypeLookup::Struct(*id));
         }
 
-        if let Some(id) = self.opaque_ty_ids.get(&name.str) {
+        if let Some(id) = self.resolve(self.opaque_ty_ids, name) {
             return Ok(TypeLookup::Opaque(*id));
         }
-        if let Some(_) = self.trait_ids.get(&name.str) {
+        if self.resolve(self.trait_ids, name).is_some() {
             return Err(RustIrError::NotStruct(name.clone()));
         }
 
@@ -105,24 +922,38 @@ impl<'k> Env<'k> {
     }
 
     fn lookup_trait(&self, name: &Identifier) -> LowerResult<TraitId<ChalkIr>> {
-        if let Some(_) = self
+        if self
             .parameter_map
-            .get(&chalk_ir::ParameterKind::Ty(name.str.clone()))
+            .contains_key(&chalk_ir::ParameterKind::Ty(name.str.clone()))
         {
             return Err(RustIrError::NotTrait(name.clone()));
         }
 
-        if let Some(_) = self.struct_ids.get(&name.str) {
+        if self.resolve(self.struct_ids, name).is_some() {
             return Err(RustIrError::NotTrait(name.clone()));
         }
 
-        if let Some(id) = self.trait_ids.get(&name.str) {
+        if let Some(id) = self.resolve(self.trait_ids, name) {
             return Ok(*id);
         }
 
         Err(RustIrError::InvalidTypeName(name.clone()))
     }
 
+    fn lookup_predicate(&self, name: &Identifier) -> LowerResult<chalk_ir::PredicateId<ChalkIr>> {
+        match self.resolve(self.predicate_ids, name) {
+            Some(id) => Ok(*id),
+            None => Err(RustIrError::InvalidPredicateName(name.clone())),
+        }
+    }
+
+    fn lookup_goal(&self, name: &Identifier) -> LowerResult<chalk_ir::Goal<ChalkIr>> {
+        match self.resolve(self.goal_defns, name) {
+            Some(goal) => Ok(goal.clone()),
+            None => Err(RustIrError::InvalidGoalName(name.clone())),
+        }
+    }
+
     fn lookup_lifetime(&self, name: &Identifier) -> LowerResult<LifetimeLookup> {
         if let Some(k) = self
             .parameter_map
@@ -131,7 +962,24 @@ impl<'k> Env<'k> {
             return Ok(LifetimeLookup::Parameter(*k));
         }
 
-        Err(RustIrError::InvalidLifetimeName(name.clone()))
+        // The lifetimes in scope, in the order they are declared in: those
+        // of outer binders first. Those named for anonymous lifetimes are
+        // left out, as they cannot be named.
+        let mut in_scope: Vec<_> = self
+            .parameter_map
+            .iter()
+            .filter_map(|(kind, bound_var)| match kind {
+                chalk_ir::ParameterKind::Lifetime(lifetime) if !lifetime.starts_with("'_#") => {
+                    Some((bound_var, lifetime.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+        in_scope.sort_by_key(|(bound_var, _)| (Reverse(bound_var.debruijn), bound_var.index));
+        Err(RustIrError::InvalidLifetimeName {
+            name: name.clone(),
+            in_scope: in_scope.into_iter().map(|(_, lifetime)| lifetime).collect(),
+        })
     }
 
     fn struct_kind(&self, id: chalk_ir::StructId<ChalkIr>) -> &TypeKind {
@@ -142,10 +990,15 @@ impl<'k> Env<'k> {
         &self.trait_kinds[&id]
     }
 
+    fn predicate_kind(&self, id: chalk_ir::PredicateId<ChalkIr>) -> &TypeKind {
+        &self.predicate_kinds[&id]
+    }
+
     /// Introduces new parameters, shifting the indices of existing
     /// parameters to accommodate them. The indices of the new binders
-    /// will be assigned in order as they are iterated.
-    fn introduce<I>(&self, binders: I) -> LowerResult<Self>
+    /// will be assigned in order as they are iterated. `span` is that of
+    /// what declares them, where a duplicate or shadowed one is reported.
+    fn introduce<I>(&self, span: Span, binders: I) -> LowerResult<Self>
     where
         I: IntoIterator<Item = chalk_ir::ParameterKind<Ident>>,
         I::IntoIter: ExactSizeIterator,
@@ -162,7 +1015,7 @@ impl<'k> Env<'k> {
             .chain(binders)
             .collect();
         if parameter_map.len() != self.parameter_map.len() + len {
-            Err(RustIrError::DuplicateOrShadowedParameters)?;
+            Err(RustIrError::DuplicateOrShadowedParameters(span))?;
         }
         Ok(Env {
             parameter_map,
@@ -170,7 +1023,12 @@ impl<'k> Env<'k> {
         })
     }
 
-    fn in_binders<I, T, OP>(&self, binders: I, op: OP) -> LowerResult<chalk_ir::Binders<T>>
+    fn in_binders<I, T, OP>(
+        &self,
+        span: Span,
+        binders: I,
+        op: OP,
+    ) -> LowerResult<chalk_ir::Binders<T>>
     where
         I: IntoIterator<Item = chalk_ir::ParameterKind<Ident>>,
         I::IntoIter: ExactSizeIterator,
@@ -179,7 +1037,7 @@ impl<'k> Env<'k> {
     {
         let interner = &ChalkIr;
         let binders: Vec<_> = binders.into_iter().collect();
-        let env = self.introduce(binders.iter().cloned())?;
+        let env = self.introduce(span, binders.iter().cloned())?;
         Ok(chalk_ir::Binders::new(
             ParameterKinds::from(interner, binders.anonymize()),
             op(&env)?,
@@ -188,12 +1046,21 @@ impl<'k> Env<'k> {
 }
 
 pub(crate) trait LowerProgram {
-    /// Lowers from a Program AST to the internal IR for a program.
-    fn lower(&self) -> LowerResult<LoweredProgram>;
+    /// Lowers from a Program AST to the internal IR for a program. If the
+    /// program does not lower, all its errors are returned that do not
+    /// depend on one another.
+    fn lower(&self) -> Result<LoweredProgram, Vec<RustIrError>>;
+}
+
+/// Records the error of lowering an item, if there was one, and goes on.
+fn record(errors: &mut Vec<RustIrError>, result: LowerResult<()>) {
+    if let Err(error) = result {
+        errors.push(error);
+    }
 }
 
 impl LowerProgram for Program {
-    fn lower(&self) -> LowerResult<LoweredProgram> {
+    fn lower(&self) -> Result<LoweredProgram, Vec<RustIrError>> {
         let mut index = 0;
         let mut next_item_id = || -> RawId {
             let i = index;
@@ -201,248 +1068,467 @@ impl LowerProgram for Program {
             RawId { index: i }
         };
 
+        let mut items = Vec::new();
+        flatten_items("", &self.items, &mut items);
+        let items: Vec<_> = items
+            .into_iter()
+            .map(|(module, item)| (module, desugar_item(item)))
+            .collect();
+
         // Make a vector mapping each thing in `items` to an id,
         // based just on its position:
-        let raw_ids: Vec<_> = self.items.iter().map(|_| next_item_id()).collect();
+        let raw_ids: Vec<_> = items.iter().map(|_| next_item_id()).collect();
 
         // Create ids for associated type declarations and values
         let mut associated_ty_lookups = BTreeMap::new();
         let mut associated_ty_value_ids = BTreeMap::new();
-        for (item, &raw_id) in self.items.iter().zip(&raw_ids) {
-            match item {
-                Item::TraitDefn(d) => {
-                    if d.flags.auto && !d.assoc_ty_defns.is_empty() {
-                        Err(RustIrError::AutoTraitAssociatedTypes(d.name.clone()))?;
+        let mut method_predicate_ids = BTreeMap::new();
+        let mut opaque_ty_defns = BTreeMap::new();
+        let mut hidden_tys = BTreeMap::new();
+
+        // Each phase of lowering goes on past the items that have errors,
+        // so that the errors of the other items are found too, but the
+        // next phase only starts if there were none: it depends on what
+        // the items were lowered to.
+        let mut errors = vec![];
+        for ((module, item), &raw_id) in items.iter().zip(&raw_ids) {
+            let result = (|| -> LowerResult<()> {
+                match item {
+                    FlatItem::StructDefn(d) => {
+                        check_name(&d.name)?;
+                        check_attributes(&d.attributes, STRUCT_ATTRIBUTES)?;
+                        d.check_parameter_names()?;
                     }
-                    for defn in &d.assoc_ty_defns {
-                        let addl_parameter_kinds = defn.all_parameters();
-                        let lookup = AssociatedTyLookup {
-                            id: AssocTypeId(next_item_id()),
-                            addl_parameter_kinds: addl_parameter_kinds.anonymize(),
-                        };
-                        associated_ty_lookups
-                            .insert((TraitId(raw_id), defn.name.str.clone()), lookup);
+                    FlatItem::TraitDefn(d) => {
+                        check_name(&d.name)?;
+                        check_attributes(&d.attributes, TRAIT_ATTRIBUTES)?;
+                        d.check_parameter_names()?;
+                        if d.flags.auto && !d.assoc_ty_defns.is_empty() {
+                            Err(RustIrError::AutoTraitAssociatedTypes(d.name.clone()))?;
+                        }
+                        if d.flags.auto && !d.methods.is_empty() {
+                            Err(RustIrError::AutoTraitMethods(d.name.clone()))?;
+                        }
+                        check_methods(&d.methods)?;
+                        for defn in &d.assoc_ty_defns {
+                            check_name(&defn.name)?;
+                            defn.check_parameter_names()?;
+                            let addl_parameter_kinds = defn.all_parameters();
+                            let lookup = AssociatedTyLookup {
+                                id: AssocTypeId(next_item_id()),
+                                addl_parameter_kinds: addl_parameter_kinds.anonymize(),
+                            };
+                            associated_ty_lookups
+                                .insert((TraitId(raw_id), defn.name.str.clone()), lookup);
+                        }
+                    }
+
+                    FlatItem::Impl(d) => {
+                        check_attributes(&d.attributes, IMPL_ATTRIBUTES)?;
+                        d.check_parameter_names()?;
+                        for atv in &d.assoc_ty_values {
+                            atv.check_parameter_names()?;
+                            let atv_id = AssociatedTyValueId(next_item_id());
+                            associated_ty_value_ids
+                                .insert((ImplId(raw_id), atv.name.str.clone()), atv_id);
+                        }
+                        check_methods(&d.methods)?;
                     }
-                }
 
-                Item::Impl(d) => {
-                    for atv in &d.assoc_ty_values {
-                        let atv_id = AssociatedTyValueId(next_item_id());
-                        associated_ty_value_ids
-                            .insert((ImplId(raw_id), atv.name.str.clone()), atv_id);
+                    FlatItem::InherentImpl(d) => {
+                        check_attributes(&d.attributes, INHERENT_IMPL_ATTRIBUTES)?;
+                        d.check_parameter_names()?;
+                        check_methods(&d.methods)?;
+                        // Only methods can be called with method syntax,
+                        // so associated functions have no predicate.
+                        for method in d.methods.iter().filter(|m| m.receiver.is_some()) {
+                            method_predicate_ids
+                                .entry(method.name.str.clone())
+                                .or_insert_with(|| PredicateId(next_item_id()));
+                        }
                     }
+
+                    FlatItem::OpaqueTyDefn(d) => {
+                        check_name(&d.identifier)?;
+                        d.check_parameter_names()?;
+                        opaque_ty_defns.insert(qualify(module, &d.identifier.str), d);
+                    }
+                    FlatItem::HiddenTyDefn(d) => {
+                        d.check_parameter_names()?;
+                        if hidden_tys
+                            .insert(qualify(module, &d.identifier.str), d)
+                            .is_some()
+                        {
+                            Err(RustIrError::DuplicateHiddenType(d.identifier.clone()))?;
+                        }
+                    }
+                    FlatItem::Clause(d) => d.check_parameter_names()?,
+                    FlatItem::PredicateDefn(d) => check_name(&d.name)?,
+                    FlatItem::GoalDefn(d) => check_name(&d.name)?,
                 }
+                Ok(())
+            })();
+            record(&mut errors, result);
+        }
 
-                _ => {}
-            }
+        // Pair each `hidden type` item with the `type Foo = impl Bar;` it defines.
+        for (name, hidden_ty) in &hidden_tys {
+            let result = (|| -> LowerResult<()> {
+                match opaque_ty_defns.get(name) {
+                    Some(opaque_ty) if opaque_ty.ty.is_some() => Err(
+                        RustIrError::DuplicateHiddenType(hidden_ty.identifier.clone()),
+                    )?,
+                    Some(opaque_ty) => {
+                        if opaque_ty.all_parameters() != hidden_ty.all_parameters() {
+                            Err(RustIrError::HiddenTypeParameterMismatch(
+                                hidden_ty.identifier.clone(),
+                            ))?;
+                        }
+                    }
+                    None => Err(RustIrError::NotOpaqueType(hidden_ty.identifier.clone()))?,
+                }
+                Ok(())
+            })();
+            record(&mut errors, result);
+        }
+        if !errors.is_empty() {
+            return Err(errors);
         }
 
         let mut struct_ids = BTreeMap::new();
         let mut trait_ids = BTreeMap::new();
         let mut opaque_ty_ids = BTreeMap::new();
+        let mut predicate_ids = BTreeMap::new();
         let mut struct_kinds = BTreeMap::new();
         let mut trait_kinds = BTreeMap::new();
         let mut opaque_ty_kinds = BTreeMap::new();
-        for (item, &raw_id) in self.items.iter().zip(&raw_ids) {
-            match item {
-                Item::StructDefn(defn) => {
-                    let type_kind = defn.lower_type_kind()?;
-                    let id = StructId(raw_id);
-                    struct_ids.insert(type_kind.name.clone(), id);
-                    struct_kinds.insert(id, type_kind);
-                }
-                Item::TraitDefn(defn) => {
-                    let type_kind = defn.lower_type_kind()?;
-                    let id = TraitId(raw_id);
-                    trait_ids.insert(type_kind.name.clone(), id);
-                    trait_kinds.insert(id, type_kind);
+        let mut predicate_kinds = BTreeMap::new();
+        for ((module, item), &raw_id) in items.iter().zip(&raw_ids) {
+            let result = (|| -> LowerResult<()> {
+                match item {
+                    FlatItem::StructDefn(defn) => {
+                        let mut type_kind = defn.lower_type_kind()?;
+                        type_kind.name = qualify(module, &type_kind.name);
+                        let id = StructId(raw_id);
+                        struct_ids.insert(type_kind.name.clone(), id);
+                        struct_kinds.insert(id, type_kind);
+                    }
+                    FlatItem::TraitDefn(defn) => {
+                        let mut type_kind = defn.lower_type_kind()?;
+                        type_kind.name = qualify(module, &type_kind.name);
+                        let id = TraitId(raw_id);
+                        trait_ids.insert(type_kind.name.clone(), id);
+                        trait_kinds.insert(id, type_kind);
+                    }
+                    FlatItem::OpaqueTyDefn(defn) => {
+                        let mut type_kind = defn.lower_type_kind()?;
+                        type_kind.name = qualify(module, &type_kind.name);
+                        let id = OpaqueTyId(raw_id);
+                        opaque_ty_ids.insert(type_kind.name.clone(), id);
+                        opaque_ty_kinds.insert(id, type_kind);
+                    }
+                    FlatItem::PredicateDefn(defn) => {
+                        let mut type_kind = defn.lower_type_kind()?;
+                        type_kind.name = qualify(module, &type_kind.name);
+                        let id = PredicateId(raw_id);
+                        predicate_ids.insert(type_kind.name.clone(), id);
+                        predicate_kinds.insert(id, type_kind);
+                    }
+                    FlatItem::Impl(_)
+                    | FlatItem::InherentImpl(_)
+                    | FlatItem::HiddenTyDefn(_)
+                    | FlatItem::Clause(_)
+                    | FlatItem::GoalDefn(_) => {}
                 }
-                Item::OpaqueTyDefn(defn) => {
-                    let type_kind = defn.lower_type_kind()?;
-                    let id = OpaqueTyId(raw_id);
-                    opaque_ty_ids.insert(defn.identifier.str.clone(), id);
-                    opaque_ty_kinds.insert(id, type_kind);
+                Ok(())
+            })();
+            record(&mut errors, result);
+        }
+
+        // A goal definition can only refer to the ones before it, so we
+        // can lower them in order, without worrying about cycles.
+        let mut goal_defns = BTreeMap::new();
+        for (module, item) in &items {
+            if let FlatItem::GoalDefn(defn) = item {
+                let env = Env {
+                    struct_ids: &struct_ids,
+                    struct_kinds: &struct_kinds,
+                    trait_ids: &trait_ids,
+                    trait_kinds: &trait_kinds,
+                    opaque_ty_ids: &opaque_ty_ids,
+                    predicate_ids: &predicate_ids,
+                    predicate_kinds: &predicate_kinds,
+                    method_predicate_ids: &method_predicate_ids,
+                    goal_defns: &goal_defns,
+                    associated_ty_lookups: &associated_ty_lookups,
+                    module,
+                    parameter_map: BTreeMap::new(),
+                };
+                match defn.goal.lower(&env) {
+                    Ok(goal) => {
+                        goal_defns.insert(qualify(module, &defn.name.str), goal);
+                    }
+                    // The goals after it may refer to it.
+                    Err(error) => {
+                        errors.push(error);
+                        break;
+                    }
                 }
-                Item::Impl(_) => continue,
-                Item::Clause(_) => continue,
-            };
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
         }
 
         let mut struct_data = BTreeMap::new();
         let mut trait_data = BTreeMap::new();
         let mut well_known_traits = BTreeMap::new();
         let mut impl_data = BTreeMap::new();
+        let mut inherent_impl_data = BTreeMap::new();
         let mut associated_ty_data = BTreeMap::new();
         let mut associated_ty_values = BTreeMap::new();
         let mut opaque_ty_data = BTreeMap::new();
         let mut custom_clauses = Vec::new();
-        for (item, &raw_id) in self.items.iter().zip(&raw_ids) {
+        for ((module, item), &raw_id) in items.iter().zip(&raw_ids) {
             let empty_env = Env {
                 struct_ids: &struct_ids,
                 struct_kinds: &struct_kinds,
                 trait_ids: &trait_ids,
                 trait_kinds: &trait_kinds,
                 opaque_ty_ids: &opaque_ty_ids,
+                predicate_ids: &predicate_ids,
+                predicate_kinds: &predicate_kinds,
+                method_predicate_ids: &method_predicate_ids,
+                goal_defns: &goal_defns,
                 associated_ty_lookups: &associated_ty_lookups,
+                module,
                 parameter_map: BTreeMap::new(),
             };
 
-            match *item {
-                Item::StructDefn(ref d) => {
-                    let struct_id = StructId(raw_id);
-                    struct_data.insert(struct_id, Arc::new(d.lower_struct(struct_id, &empty_env)?));
-                }
-                Item::TraitDefn(ref trait_defn) => {
-                    let trait_id = TraitId(raw_id);
-                    let trait_datum = trait_defn.lower_trait(trait_id, &empty_env)?;
-
-                    if let Some(well_known) = trait_datum.well_known {
-                        well_known_traits.insert(well_known, trait_id);
+            let result = (|| -> LowerResult<()> {
+                let item = &empty_env.resolve_shorthand_projections(item)?;
+                match *item {
+                    FlatItem::StructDefn(ref d) => {
+                        let struct_id = StructId(raw_id);
+                        struct_data
+                            .insert(struct_id, Arc::new(d.lower_struct(struct_id, &empty_env)?));
                     }
-
-                    trait_data.insert(trait_id, Arc::new(trait_datum));
-
-                    for assoc_ty_defn in &trait_defn.assoc_ty_defns {
-                        let lookup =
-                            &associated_ty_lookups[&(trait_id, assoc_ty_defn.name.str.clone())];
-
-                        // The parameters in scope for the associated
-                        // type definitions are *both* those from the
-                        // trait *and* those from the associated type
-                        // itself.
-                        //
-                        // Insert the associated type parameters first
-                        // into the list so that they are given the
-                        // indices starting from 0. This corresponds
-                        // to the "de bruijn" convention where "more
-                        // inner" sets of parameters get the lower
-                        // indices:
-                        //
-                        // e.g., in this example, the indices would be
-                        // assigned `[A0, A1, T0, T1]`:
-                        //
-                        // ```
-                        // trait Foo<T0, T1> {
-                        //     type Bar<A0, A1>;
-                        // }
-                        // ```
-                        let mut parameter_kinds = assoc_ty_defn.all_parameters();
-                        parameter_kinds.extend(trait_defn.all_parameters());
-
-                        let binders = empty_env.in_binders(parameter_kinds, |env| {
-                            Ok(rust_ir::AssociatedTyDatumBound {
-                                bounds: assoc_ty_defn.bounds.lower(&env)?,
-                                where_clauses: assoc_ty_defn.where_clauses.lower(&env)?,
-                            })
-                        })?;
-
-                        associated_ty_data.insert(
-                            lookup.id,
-                            Arc::new(rust_ir::AssociatedTyDatum {
-                                trait_id: TraitId(raw_id),
-                                id: lookup.id,
-                                name: assoc_ty_defn.name.str.clone(),
-                                binders: binders,
-                            }),
+                    FlatItem::TraitDefn(ref trait_defn) => {
+                        let trait_id = TraitId(raw_id);
+                        let trait_datum = trait_defn.lower_trait(trait_id, &empty_env)?;
+
+                        if let Some(well_known) = trait_datum.well_known {
+                            well_known_traits.insert(well_known, trait_id);
+                        }
+
+                        trait_data.insert(trait_id, Arc::new(trait_datum));
+
+                        for assoc_ty_defn in &trait_defn.assoc_ty_defns {
+                            let lookup =
+                                &associated_ty_lookups[&(trait_id, assoc_ty_defn.name.str.clone())];
+
+                            // The parameters in scope for the associated
+                            // type definitions are *both* those from the
+                            // trait *and* those from the associated type
+                            // itself.
+                            //
+                            // Insert the associated type parameters first
+                            // into the list so that they are given the
+                            // indices starting from 0. This corresponds
+                            // to the "de bruijn" convention where "more
+                            // inner" sets of parameters get the lower
+                            // indices:
+                            //
+                            // e.g., in this example, the indices would be
+                            // assigned `[A0, A1, T0, T1]`:
+                            //
+                            // ```
+                            // trait Foo<T0, T1> {
+                            //     type Bar<A0, A1>;
+                            // }
+                            // ```
+                            let mut parameter_kinds = assoc_ty_defn.all_parameters();
+                            parameter_kinds.extend(trait_defn.all_parameters());
+
+                            let span = assoc_ty_defn.name.span;
+                            let binders = empty_env.in_binders(span, parameter_kinds, |env| {
+                                Ok(rust_ir::AssociatedTyDatumBound {
+                                    bounds: assoc_ty_defn.bounds.lower(&env)?,
+                                    where_clauses: assoc_ty_defn.where_clauses.lower(&env)?,
+                                })
+                            })?;
+
+                            associated_ty_data.insert(
+                                lookup.id,
+                                Arc::new(rust_ir::AssociatedTyDatum {
+                                    trait_id: TraitId(raw_id),
+                                    id: lookup.id,
+                                    name: assoc_ty_defn.name.str.clone(),
+                                    binders,
+                                }),
+                            );
+                        }
+                    }
+                    FlatItem::Impl(ref impl_defn) => {
+                        let impl_id = ImplId(raw_id);
+                        let impl_datum = Arc::new(impl_defn.lower_impl(
+                            &empty_env,
+                            impl_id,
+                            &associated_ty_value_ids,
+                        )?);
+                        impl_data.insert(impl_id, impl_datum.clone());
+                        let trait_id = impl_datum.trait_id();
+
+                        for atv in &impl_defn.assoc_ty_values {
+                            let atv_id = associated_ty_value_ids[&(impl_id, atv.name.str.clone())];
+                            let lookup = &associated_ty_lookups[&(trait_id, atv.name.str.clone())];
+
+                            // The parameters in scope for the associated
+                            // type definitions are *both* those from the
+                            // impl *and* those from the associated type
+                            // itself. As in the "trait" case above, we begin
+                            // with the parameters from the impl.
+                            let mut parameter_kinds = atv.all_parameters();
+                            parameter_kinds.extend(impl_defn.all_parameters());
+
+                            let value =
+                                empty_env.in_binders(atv.name.span, parameter_kinds, |env| {
+                                    Ok(rust_ir::AssociatedTyValueBound {
+                                        ty: atv.value.lower(env)?,
+                                    })
+                                })?;
+
+                            associated_ty_values.insert(
+                                atv_id,
+                                Arc::new(rust_ir::AssociatedTyValue {
+                                    impl_id,
+                                    associated_ty_id: lookup.id,
+                                    value,
+                                }),
+                            );
+                        }
+                    }
+                    FlatItem::InherentImpl(ref impl_defn) => {
+                        inherent_impl_data.insert(
+                            ImplId(raw_id),
+                            Arc::new(impl_defn.lower_inherent_impl(&empty_env)?),
                         );
                     }
+                    FlatItem::Clause(ref clause) => {
+                        custom_clauses.extend(clause.lower_clause(&empty_env)?);
+                    }
+                    FlatItem::HiddenTyDefn(_)
+                    | FlatItem::PredicateDefn(_)
+                    | FlatItem::GoalDefn(_) => {}
+                    FlatItem::OpaqueTyDefn(ref opaque_ty) => {
+                        let name = qualify(module, &opaque_ty.identifier.str);
+                        let ty = match (&opaque_ty.ty, hidden_tys.get(&name)) {
+                            (Some(ty), None) => ty,
+                            (None, Some(hidden_ty)) => &hidden_ty.ty,
+                            (None, None) => {
+                                Err(RustIrError::MissingHiddenType(opaque_ty.identifier.clone()))?
+                            }
+                            (Some(_), Some(_)) => unreachable!(),
+                        };
+                        if let Some(&opaque_ty_id) = opaque_ty_ids.get(&name) {
+                            let parameter_kinds = opaque_ty
+                                .parameter_kinds
+                                .iter()
+                                .map(|k| k.lower())
+                                .collect::<Vec<_>>();
+
+                            // Introduce the parameters declared on the opaque type definition.
+                            // So if we have `type Foo<P1..Pn> = impl Trait<T1..Tn>`, this would introduce `P1..Pn`
+                            let span = opaque_ty.span;
+                            let binders = empty_env.in_binders(span, parameter_kinds, |env| {
+                                let hidden_ty = ty.lower(&env)?;
+
+                                // Introduce a variable to represent the hidden "self type". This will be used in the bounds.
+                                // So the `impl Trait<T1..Tn>` will be lowered to `exists<Self> { Self: Trait<T1..Tn> }`.
+                                let bounds: chalk_ir::Binders<Vec<chalk_ir::Binders<_>>> = env
+                                    .in_binders(
+                                        span,
+                                        Some(chalk_ir::ParameterKind::Ty(Atom::from(FIXME_SELF))),
+                                        |env1| {
+                                            let interner = env1.interner();
+                                            Ok(opaque_ty
+                                                .bounds
+                                                .lower(&env1)?
+                                                .iter()
+                                                .flat_map(|qil| {
+                                                    // Instantiate the bounds with the innermost bound variable, which represents Self, as the self type.
+                                                    qil.into_where_clauses(
+                                                        interner,
+                                                        chalk_ir::TyData::BoundVar(BoundVar::new(
+                                                            DebruijnIndex::INNERMOST,
+                                                            0,
+                                                        ))
+                                                        .intern(interner),
+                                                    )
+                                                })
+                                                .collect())
+                                        },
+                                    )?;
+
+                                Ok(OpaqueTyDatumBound { hidden_ty, bounds })
+                            })?;
+
+                            opaque_ty_data.insert(
+                                opaque_ty_id,
+                                Arc::new(OpaqueTyDatum {
+                                    opaque_ty_id,
+                                    bound: binders,
+                                }),
+                            );
+                        }
+                    }
                 }
-                Item::Impl(ref impl_defn) => {
-                    let impl_id = ImplId(raw_id);
-                    let impl_datum = Arc::new(impl_defn.lower_impl(
-                        &empty_env,
-                        impl_id,
-                        &associated_ty_value_ids,
-                    )?);
-                    impl_data.insert(impl_id, impl_datum.clone());
-                    let trait_id = impl_datum.trait_id();
-
-                    for atv in &impl_defn.assoc_ty_values {
-                        let atv_id = associated_ty_value_ids[&(impl_id, atv.name.str.clone())];
-                        let lookup = &associated_ty_lookups[&(trait_id, atv.name.str.clone())];
-
-                        // The parameters in scope for the associated
-                        // type definitions are *both* those from the
-                        // impl *and* those from the associated type
-                        // itself. As in the "trait" case above, we begin
-                        // with the parameters from the impl.
-                        let mut parameter_kinds = atv.all_parameters();
-                        parameter_kinds.extend(impl_defn.all_parameters());
-
-                        let value = empty_env.in_binders(parameter_kinds, |env| {
-                            Ok(rust_ir::AssociatedTyValueBound {
-                                ty: atv.value.lower(env)?,
-                            })
-                        })?;
+                Ok(())
+            })();
+            record(&mut errors, result);
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
 
-                        associated_ty_values.insert(
-                            atv_id,
-                            Arc::new(rust_ir::AssociatedTyValue {
-                                impl_id,
-                                associated_ty_id: lookup.id,
-                                value,
-                            }),
-                        );
+        // A struct that contains itself by value would have infinite size.
+        // Each cycle is reported once, at the first struct in it.
+        let recursive = recursive_structs(&struct_data);
+        let mut reported = BTreeSet::new();
+        for ((_, item), &raw_id) in items.iter().zip(&raw_ids) {
+            if let FlatItem::StructDefn(d) = item {
+                if let Some(&cycle) = recursive.get(&StructId(raw_id)) {
+                    if reported.insert(cycle) {
+                        errors.push(RustIrError::RecursiveStruct(d.name.clone()));
                     }
                 }
-                Item::Clause(ref clause) => {
-                    custom_clauses.extend(clause.lower_clause(&empty_env)?);
-                }
-                Item::OpaqueTyDefn(ref opaque_ty) => {
-                    if let Some(&opaque_ty_id) = opaque_ty_ids.get(&opaque_ty.identifier.str) {
-                        let parameter_kinds = opaque_ty
-                            .parameter_kinds
-                            .iter()
-                            .map(|k| k.lower())
-                            .collect::<Vec<_>>();
-
-                        // Introduce the parameters declared on the opaque type definition.
-                        // So if we have `type Foo<P1..Pn> = impl Trait<T1..Tn>`, this would introduce `P1..Pn`
-                        let binders = empty_env.in_binders(parameter_kinds, |env| {
-                            let hidden_ty = opaque_ty.ty.lower(&env)?;
-
-                            // Introduce a variable to represent the hidden "self type". This will be used in the bounds.
-                            // So the `impl Trait<T1..Tn>` will be lowered to `exists<Self> { Self: Trait<T1..Tn> }`.
-                            let bounds: chalk_ir::Binders<Vec<chalk_ir::Binders<_>>> = env
-                                .in_binders(
-                                    Some(chalk_ir::ParameterKind::Ty(Atom::from(FIXME_SELF))),
-                                    |env1| {
-                                        let interner = env1.interner();
-                                        Ok(opaque_ty
-                                            .bounds
-                                            .lower(&env1)?
-                                            .iter()
-                                            .flat_map(|qil| {
-                                                // Instantiate the bounds with the innermost bound variable, which represents Self, as the self type.
-                                                qil.into_where_clauses(
-                                                    interner,
-                                                    chalk_ir::TyData::BoundVar(BoundVar::new(
-                                                        DebruijnIndex::INNERMOST,
-                                                        0,
-                                                    ))
-                                                    .intern(interner),
-                                                )
-                                            })
-                                            .collect())
-                                    },
-                                )?;
-
-                            Ok(OpaqueTyDatumBound { hidden_ty, bounds })
-                        })?;
+            }
+        }
 
-                        opaque_ty_data.insert(
-                            opaque_ty_id,
-                            Arc::new(OpaqueTyDatum {
-                                opaque_ty_id,
-                                bound: binders,
-                            }),
-                        );
-                    }
+        // Positive impls must be `unsafe` exactly when their trait is.
+        for ((_, item), &raw_id) in items.iter().zip(&raw_ids) {
+            if let FlatItem::Impl(d) = item {
+                let impl_datum = &impl_data[&ImplId(raw_id)];
+                if !impl_datum.is_positive() {
+                    continue;
+                }
+                let trait_datum = &trait_data[&impl_datum.trait_id()];
+                match (impl_datum.safety, trait_datum.safety) {
+                    (rust_ir::Safety::Safe, rust_ir::Safety::Unsafe) => errors.push(
+                        RustIrError::SafeImplOfUnsafeTrait(d.trait_ref.trait_name.clone()),
+                    ),
+                    (rust_ir::Safety::Unsafe, rust_ir::Safety::Safe) => errors.push(
+                        RustIrError::UnsafeImplOfSafeTrait(d.trait_ref.trait_name.clone()),
+                    ),
+                    _ => {}
                 }
             }
         }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
 
-        let program = LoweredProgram {
+        let mut program = LoweredProgram {
             struct_ids,
             trait_ids,
             struct_kinds,
@@ -450,14 +1536,22 @@ impl LowerProgram for Program {
             struct_data,
             trait_data,
             well_known_traits,
+            impls_by_trait: LoweredProgram::index_impls(&impl_data),
             impl_data,
+            inherent_impl_data,
             associated_ty_values,
             associated_ty_data,
             opaque_ty_ids,
             opaque_ty_kinds,
             opaque_ty_data,
+            predicate_ids,
+            predicate_kinds,
+            method_predicate_ids,
+            goal_defns,
             custom_clauses,
+            method_clauses: vec![],
         };
+        program.method_clauses = crate::method::method_clauses(&program);
 
         Ok(program)
     }
@@ -496,28 +1590,35 @@ trait LowerParameterMap {
         // probably just kind of messed up right now. That's ok.
     }
 
-    fn parameter_refs(&self) -> Vec<chalk_ir::Parameter<ChalkIr>> {
-        self.all_parameters()
-            .anonymize()
-            .iter()
-            .zip(0..)
-            .map(|p| p.to_parameter(self.interner()))
-            .collect()
+    /// Checks that no two declared parameters share a name; otherwise
+    /// the parameter map would silently collapse them into a single entry.
+    /// Parameters named `Self` would likewise shadow the synthetic one.
+    fn check_parameter_names(&self) -> LowerResult<()> {
+        let mut seen = BTreeSet::new();
+        for parameter in self.declared_parameters() {
+            let name = match parameter {
+                ParameterKind::Ty(n) | ParameterKind::Lifetime(n) => n,
+            };
+            check_name(name)?;
+            if !seen.insert(&name.str) {
+                Err(RustIrError::DuplicateParameterName(name.clone()))?;
+            }
+        }
+        Ok(())
     }
+}
 
-    fn parameter_map(&self) -> ParameterMap {
-        self.all_parameters()
-            .into_iter()
-            .zip((0..).map(|i| BoundVar::new(DebruijnIndex::INNERMOST, i)))
-            .collect()
+impl LowerParameterMap for StructDefn {
+    fn synthetic_parameters(&self) -> Option<chalk_ir::ParameterKind<Ident>> {
+        None
     }
 
-    fn interner(&self) -> &ChalkIr {
-        &ChalkIr
+    fn declared_parameters(&self) -> &[ParameterKind] {
+        &self.parameter_kinds
     }
 }
 
-impl LowerParameterMap for StructDefn {
+impl LowerParameterMap for Impl {
     fn synthetic_parameters(&self) -> Option<chalk_ir::ParameterKind<Ident>> {
         None
     }
@@ -527,7 +1628,17 @@ impl LowerParameterMap for StructDefn {
     }
 }
 
-impl LowerParameterMap for Impl {
+impl LowerParameterMap for MethodDefn {
+    fn synthetic_parameters(&self) -> Option<chalk_ir::ParameterKind<Ident>> {
+        None
+    }
+
+    fn declared_parameters(&self) -> &[ParameterKind] {
+        &self.parameter_kinds
+    }
+}
+
+impl LowerParameterMap for InherentImpl {
     fn synthetic_parameters(&self) -> Option<chalk_ir::ParameterKind<Ident>> {
         None
     }
@@ -567,6 +1678,26 @@ impl LowerParameterMap for TraitDefn {
     }
 }
 
+impl LowerParameterMap for OpaqueTyDefn {
+    fn synthetic_parameters(&self) -> Option<chalk_ir::ParameterKind<Ident>> {
+        None
+    }
+
+    fn declared_parameters(&self) -> &[ParameterKind] {
+        &self.parameter_kinds
+    }
+}
+
+impl LowerParameterMap for HiddenTyDefn {
+    fn synthetic_parameters(&self) -> Option<chalk_ir::ParameterKind<Ident>> {
+        None
+    }
+
+    fn declared_parameters(&self) -> &[ParameterKind] {
+        &self.parameter_kinds
+    }
+}
+
 impl LowerParameterMap for Clause {
     fn synthetic_parameters(&self) -> Option<chalk_ir::ParameterKind<Ident>> {
         None
@@ -637,6 +1768,21 @@ impl LowerTypeKind for TraitDefn {
     }
 }
 
+impl LowerTypeKind for PredicateDefn {
+    fn lower_type_kind(&self) -> LowerResult<TypeKind> {
+        let interner = &ChalkIr;
+        let binders = self.parameter_kinds.iter().map(|kind| match kind {
+            Kind::Ty => chalk_ir::ParameterKind::Ty(()),
+            Kind::Lifetime => chalk_ir::ParameterKind::Lifetime(()),
+        });
+        Ok(TypeKind {
+            sort: TypeSort::Predicate,
+            name: self.name.str.clone(),
+            binders: chalk_ir::Binders::new(ParameterKinds::from(interner, binders), crate::Unit),
+        })
+    }
+}
+
 impl LowerTypeKind for OpaqueTyDefn {
     fn lower_type_kind(&self) -> LowerResult<TypeKind> {
         let interner = &ChalkIr;
@@ -664,26 +1810,65 @@ impl LowerWhereClauses for Impl {
     }
 }
 
+impl LowerWhereClauses for MethodDefn {
+    fn where_clauses(&self) -> &[QuantifiedWhereClause] {
+        &self.where_clauses
+    }
+}
+
+impl LowerWhereClauses for InherentImpl {
+    fn where_clauses(&self) -> &[QuantifiedWhereClause] {
+        &self.where_clauses
+    }
+}
+
 trait LowerWhereClauseVec {
     fn lower(&self, env: &Env) -> LowerResult<Vec<chalk_ir::QuantifiedWhereClause<ChalkIr>>>;
 }
 
 impl LowerWhereClauseVec for [QuantifiedWhereClause] {
     fn lower(&self, env: &Env) -> LowerResult<Vec<chalk_ir::QuantifiedWhereClause<ChalkIr>>> {
-        self.iter()
-            .flat_map(|wc| match wc.lower(env) {
-                Ok(v) => v.into_iter().map(Ok).collect(),
-                Err(e) => vec![Err(e)],
-            })
-            .collect()
+        let mut where_clauses = Vec::new();
+        let mut previous: Option<&QuantifiedWhereClause> = None;
+        for wc in self {
+            let mut lowered = wc.lower(env)?;
+            // `T: Foo<A = X, B = Y>` is parsed as one projection equality per
+            // binding, all with the same trait ref, and each implies `T: Foo`;
+            // that is only kept for the first of them.
+            if let (
+                Some(QuantifiedWhereClause {
+                    parameter_kinds,
+                    where_clause:
+                        WhereClause::ProjectionEq {
+                            projection: prev, ..
+                        },
+                    ..
+                }),
+                WhereClause::ProjectionEq { projection, .. },
+            ) = (previous, &wc.where_clause)
+            {
+                if *parameter_kinds == wc.parameter_kinds && prev.trait_ref == projection.trait_ref
+                {
+                    lowered.retain(|wc| {
+                        !matches!(wc.skip_binders(), chalk_ir::WhereClause::Implemented(_))
+                    });
+                }
+            }
+            where_clauses.extend(lowered);
+            previous = Some(wc);
+        }
+        Ok(where_clauses)
     }
 }
 
 trait LowerWhereClause<T> {
     /// Lower from an AST `where` clause to an internal IR.
     /// Some AST `where` clauses can lower to multiple ones, this is why we return a `Vec`.
-    /// As for now, this is the only the case for `where T: Foo<Item = U>` which lowers to
-    /// `Implemented(T: Foo)` and `ProjectionEq(<T as Foo>::Item = U)`.
+    /// This is the case for `where T: Foo<Item = U>` which lowers to
+    /// `Implemented(T: Foo)` and `ProjectionEq(<T as Foo>::Item = U)`, and for
+    /// `where T: ~const Foo`, which lowers to `ConstImplemented(T: Foo)` and
+    /// `Implemented(T: Foo)`, as a bound that holds in const contexts also
+    /// holds at runtime.
     fn lower(&self, env: &Env) -> LowerResult<Vec<T>>;
 }
 
@@ -700,6 +1885,13 @@ impl LowerWhereClause<chalk_ir::WhereClause<ChalkIr>> for WhereClause {
                 }),
                 chalk_ir::WhereClause::Implemented(projection.trait_ref.lower(env)?),
             ],
+            WhereClause::ConstImplemented { trait_ref } => {
+                let trait_ref = trait_ref.lower(env)?;
+                vec![
+                    chalk_ir::WhereClause::ConstImplemented(trait_ref.clone()),
+                    chalk_ir::WhereClause::Implemented(trait_ref),
+                ]
+            }
         };
         Ok(where_clauses)
     }
@@ -707,7 +1899,9 @@ impl LowerWhereClause<chalk_ir::WhereClause<ChalkIr>> for WhereClause {
 impl LowerWhereClause<chalk_ir::QuantifiedWhereClause<ChalkIr>> for QuantifiedWhereClause {
     fn lower(&self, env: &Env) -> LowerResult<Vec<chalk_ir::QuantifiedWhereClause<ChalkIr>>> {
         let parameter_kinds = self.parameter_kinds.iter().map(|pk| pk.lower());
-        let binders = env.in_binders(parameter_kinds, |env| Ok(self.where_clause.lower(env)?))?;
+        let binders = env.in_binders(self.span, parameter_kinds, |env| {
+            Ok(self.where_clause.lower(env)?)
+        })?;
         Ok(binders.into_iter().collect())
     }
 }
@@ -758,6 +1952,36 @@ impl LowerDomainGoal for DomainGoal {
                 vec![chalk_ir::DomainGoal::DownstreamType(ty.lower(env)?)]
             }
             DomainGoal::Reveal => vec![chalk_ir::DomainGoal::Reveal(())],
+            DomainGoal::Predicate { name, args } => {
+                let predicate_id = env.lookup_predicate(name)?;
+                let k = env.predicate_kind(predicate_id);
+                if k.binders.len(interner) != args.len() {
+                    Err(RustIrError::IncorrectNumberOfTypeParameters {
+                        identifier: name.clone(),
+                        expected: k.binders.len(interner),
+                        actual: args.len(),
+                    })?;
+                }
+
+                for (binder, arg) in k.binders.binders.iter(interner).zip(args) {
+                    if binder.kind() != arg.kind() {
+                        Err(RustIrError::IncorrectParameterKind {
+                            identifier: name.clone(),
+                            expected: binder.kind(),
+                            actual: arg.kind(),
+                        })?;
+                    }
+                }
+
+                let substitution = chalk_ir::Substitution::from_fallible(
+                    interner,
+                    args.iter().map(|a| a.lower(env)),
+                )?;
+                vec![chalk_ir::DomainGoal::Predicate(chalk_ir::PredicateRef {
+                    predicate_id,
+                    substitution,
+                })]
+            }
         };
         Ok(goals)
     }
@@ -784,6 +2008,26 @@ impl LowerLeafGoal for LeafGoal {
                 b: b.lower(env)?.cast(interner),
             }
             .cast::<chalk_ir::Goal<ChalkIr>>(interner),
+            LeafGoal::TypeOutlives { a, b } => {
+                chalk_ir::Constraint::TypeOutlives(a.lower(env)?, b.lower(env)?).cast(interner)
+            }
+            LeafGoal::LifetimeOutlives { a, b } => {
+                chalk_ir::Constraint::Outlives(a.lower(env)?, b.lower(env)?).cast(interner)
+            }
+            // A method that no inherent impl defines can't be called on
+            // any type, so the goal is the empty disjunction, which never
+            // holds.
+            LeafGoal::Method { probe } => match env.method_predicate_ids.get(&probe.method.str) {
+                Some(&predicate_id) => chalk_ir::DomainGoal::Predicate(chalk_ir::PredicateRef {
+                    predicate_id,
+                    substitution: chalk_ir::Substitution::from1(
+                        interner,
+                        probe.receiver.lower(env)?,
+                    ),
+                })
+                .cast(interner),
+                None => chalk_ir::GoalData::Any(chalk_ir::Goals::new(interner)).intern(interner),
+            },
         })
     }
 }
@@ -808,7 +2052,7 @@ impl LowerStructDefn for StructDefn {
             ))?;
         }
 
-        let binders = env.in_binders(self.all_parameters(), |env| {
+        let binders = env.in_binders(self.span, self.all_parameters(), |env| {
             let fields: LowerResult<_> = self.fields.iter().map(|f| f.ty.lower(env)).collect();
             let where_clauses = self.lower_where_clauses(env)?;
 
@@ -821,6 +2065,9 @@ impl LowerStructDefn for StructDefn {
         let flags = rust_ir::StructFlags {
             upstream: self.flags.upstream,
             fundamental: self.flags.fundamental,
+            union: self.flags.union,
+            manually_drop: self.flags.manually_drop,
+            extern_type: self.flags.extern_type,
         };
 
         Ok(rust_ir::StructDatum {
@@ -962,7 +2209,9 @@ trait LowerQuantifiedInlineBound {
 impl LowerQuantifiedInlineBound for QuantifiedInlineBound {
     fn lower(&self, env: &Env) -> LowerResult<rust_ir::QuantifiedInlineBound<ChalkIr>> {
         let parameter_kinds = self.parameter_kinds.iter().map(|pk| pk.lower());
-        let binders = env.in_binders(parameter_kinds, |env| Ok(self.bound.lower(env)?))?;
+        let binders = env.in_binders(self.span, parameter_kinds, |env| {
+            Ok(self.bound.lower(env)?)
+        })?;
         Ok(binders)
     }
 }
@@ -990,6 +2239,32 @@ impl LowerPolarity for Polarity {
     }
 }
 
+trait LowerSafety {
+    fn lower(&self) -> rust_ir::Safety;
+}
+
+impl LowerSafety for Safety {
+    fn lower(&self) -> rust_ir::Safety {
+        match *self {
+            Safety::Safe => rust_ir::Safety::Safe,
+            Safety::Unsafe => rust_ir::Safety::Unsafe,
+        }
+    }
+}
+
+trait LowerConstness {
+    fn lower(&self) -> rust_ir::Constness;
+}
+
+impl LowerConstness for Constness {
+    fn lower(&self) -> rust_ir::Constness {
+        match *self {
+            Constness::NotConst => rust_ir::Constness::NotConst,
+            Constness::Const => rust_ir::Constness::Const,
+        }
+    }
+}
+
 trait LowerImplType {
     fn lower(&self) -> rust_ir::ImplType;
 }
@@ -1003,6 +2278,20 @@ impl LowerImplType for ImplType {
     }
 }
 
+trait LowerReceiver {
+    fn lower(&self) -> rust_ir::Receiver;
+}
+
+impl LowerReceiver for Receiver {
+    fn lower(&self) -> rust_ir::Receiver {
+        match self {
+            Receiver::Value => rust_ir::Receiver::Value,
+            Receiver::Ref => rust_ir::Receiver::Ref,
+            Receiver::RefMut => rust_ir::Receiver::RefMut,
+        }
+    }
+}
+
 trait LowerTraitFlags {
     fn lower(&self) -> rust_ir::TraitFlags;
 }
@@ -1083,7 +2372,7 @@ impl LowerTy for Ty {
     fn lower(&self, env: &Env) -> LowerResult<chalk_ir::Ty<ChalkIr>> {
         let interner = env.interner();
         match self {
-            Ty::Id { name } => match env.lookup_type(name)? {
+            Ty::Id { name, .. } => match env.lookup_type(name)? {
                 TypeLookup::Struct(id) => {
                     let k = env.struct_kind(id);
                     if k.binders.len(interner) > 0 {
@@ -1110,8 +2399,9 @@ impl LowerTy for Ty {
                 .intern(interner)),
             },
 
-            Ty::Dyn { ref bounds } => Ok(chalk_ir::TyData::Dyn(chalk_ir::DynTy {
+            Ty::Dyn { ref bounds, span } => Ok(chalk_ir::TyData::Dyn(chalk_ir::DynTy {
                 bounds: env.in_binders(
+                    *span,
                     // FIXME: Figure out a proper name for this type parameter
                     Some(chalk_ir::ParameterKind::Ty(Atom::from(FIXME_SELF))),
                     |env| {
@@ -1133,7 +2423,7 @@ impl LowerTy for Ty {
             })
             .intern(interner)),
 
-            Ty::Apply { name, ref args } => {
+            Ty::Apply { name, ref args, .. } => {
                 let id = match env.lookup_type(name)? {
                     TypeLookup::Struct(id) => id,
                     TypeLookup::Parameter(_) | TypeLookup::Opaque(_) => {
@@ -1172,31 +2462,35 @@ impl LowerTy for Ty {
                 .intern(interner))
             }
 
-            Ty::Projection { ref proj } => Ok(chalk_ir::TyData::Alias(
+            Ty::Projection { ref proj, .. } => Ok(chalk_ir::TyData::Alias(
                 chalk_ir::AliasTy::Projection(proj.lower(env)?),
             )
             .intern(interner)),
 
             Ty::ForAll {
-                ref lifetime_names,
+                ref parameter_kinds,
                 ref ty,
+                span,
             } => {
-                let quantified_env = env.introduce(
-                    lifetime_names
-                        .iter()
-                        .map(|id| chalk_ir::ParameterKind::Lifetime(id.str.clone())),
+                let quantified = env.in_binders(
+                    *span,
+                    parameter_kinds.iter().map(|pk| pk.lower()),
+                    |quantified_env| {
+                        Ok(Substitution::from(
+                            interner,
+                            Some(ty.lower(quantified_env)?.cast(interner)),
+                        ))
+                    },
                 )?;
+                let (binders, substitution) = quantified.into();
 
                 let function = chalk_ir::Fn {
-                    num_binders: lifetime_names.len(),
-                    substitution: Substitution::from(
-                        interner,
-                        Some(ty.lower(&quantified_env)?.cast(interner)),
-                    ),
+                    binders,
+                    substitution,
                 };
                 Ok(chalk_ir::TyData::Function(function).intern(interner))
             }
-            Ty::Tuple { ref types } => Ok(chalk_ir::TyData::Apply(chalk_ir::ApplicationTy {
+            Ty::Tuple { ref types, .. } => Ok(chalk_ir::TyData::Apply(chalk_ir::ApplicationTy {
                 name: chalk_ir::TypeName::Tuple(types.len()),
                 substitution: chalk_ir::Substitution::from_fallible(
                     interner,
@@ -1205,11 +2499,31 @@ impl LowerTy for Ty {
             })
             .intern(interner)),
 
-            Ty::Scalar { ty } => Ok(chalk_ir::TyData::Apply(chalk_ir::ApplicationTy {
+            Ty::Scalar { ty, .. } => Ok(chalk_ir::TyData::Apply(chalk_ir::ApplicationTy {
                 name: chalk_ir::TypeName::Scalar(ast_scalar_to_chalk_scalar(ty.clone())),
                 substitution: chalk_ir::Substitution::empty(interner),
             })
             .intern(interner)),
+
+            Ty::Ref {
+                mutability,
+                ref lifetime,
+                ref ty,
+                ..
+            } => Ok(chalk_ir::TyData::Apply(chalk_ir::ApplicationTy {
+                name: chalk_ir::TypeName::Ref(match mutability {
+                    Mutability::Mut => chalk_ir::Mutability::Mut,
+                    Mutability::Not => chalk_ir::Mutability::Not,
+                }),
+                substitution: chalk_ir::Substitution::from(
+                    interner,
+                    vec![
+                        lifetime.lower(env)?.cast(interner),
+                        ty.lower(env)?.cast(interner),
+                    ],
+                ),
+            })
+            .intern(interner)),
         }
     }
 }
@@ -1241,6 +2555,8 @@ impl LowerLifetime for Lifetime {
                     Ok(chalk_ir::LifetimeData::BoundVar(d).intern(interner))
                 }
             },
+            Lifetime::Static => Ok(chalk_ir::LifetimeData::Static.intern(interner)),
+            Lifetime::Anonymous { span } => Err(RustIrError::AnonymousLifetimeNotAllowed(*span)),
         }
     }
 }
@@ -1264,7 +2580,20 @@ impl LowerImpl for Impl {
         debug_heading!("LowerImpl::lower_impl(impl_id={:?})", impl_id);
 
         let polarity = self.polarity.lower();
-        let binders = empty_env.in_binders(self.all_parameters(), |env| {
+        let safety = self.safety.lower();
+        if !polarity.is_positive() && safety == rust_ir::Safety::Unsafe {
+            Err(RustIrError::UnsafeNegativeImpl(
+                self.trait_ref.trait_name.clone(),
+            ))?;
+        }
+        let constness = self.constness.lower();
+        if !polarity.is_positive() && constness == rust_ir::Constness::Const {
+            Err(RustIrError::ConstNegativeImpl(
+                self.trait_ref.trait_name.clone(),
+            ))?;
+        }
+
+        let binders = empty_env.in_binders(self.span, self.all_parameters(), |env| {
             let trait_ref = self.trait_ref.lower(env)?;
             debug!("trait_ref = {:?}", trait_ref);
 
@@ -1293,11 +2622,106 @@ impl LowerImpl for Impl {
 
         debug!("associated_ty_value_ids = {:?}", associated_ty_value_ids);
 
+        if !polarity.is_positive() && !self.methods.is_empty() {
+            Err(RustIrError::NegativeImplMethods(
+                self.trait_ref.trait_name.clone(),
+            ))?;
+        }
+
+        let methods = self
+            .methods
+            .iter()
+            .map(|method| method.lower_method(self.all_parameters(), empty_env))
+            .collect::<LowerResult<_>>()?;
+
+        // The argument was checked along with the other attributes.
+        let priority = self
+            .attributes
+            .iter()
+            .find(|attr| attr.is("priority"))
+            .map_or(0, |attr| attr.args[0].str.parse().unwrap());
+
         Ok(rust_ir::ImplDatum {
             polarity,
+            safety,
+            constness,
             binders,
             impl_type: self.impl_type.lower(),
             associated_ty_value_ids,
+            methods,
+            priority,
+        })
+    }
+}
+
+trait LowerInherentImpl {
+    fn lower_inherent_impl(
+        &self,
+        empty_env: &Env,
+    ) -> LowerResult<rust_ir::InherentImplDatum<ChalkIr>>;
+}
+
+impl LowerInherentImpl for InherentImpl {
+    fn lower_inherent_impl(
+        &self,
+        empty_env: &Env,
+    ) -> LowerResult<rust_ir::InherentImplDatum<ChalkIr>> {
+        let binders = empty_env.in_binders(self.span, self.all_parameters(), |env| {
+            Ok(rust_ir::InherentImplDatumBound {
+                self_ty: self.self_ty.lower(env)?,
+                where_clauses: self.lower_where_clauses(env)?,
+            })
+        })?;
+
+        let methods = self
+            .methods
+            .iter()
+            .map(|method| method.lower_method(self.all_parameters(), empty_env))
+            .collect::<LowerResult<_>>()?;
+
+        Ok(rust_ir::InherentImplDatum { binders, methods })
+    }
+}
+
+trait LowerMethod {
+    fn lower_method(
+        &self,
+        outer_parameters: Vec<chalk_ir::ParameterKind<Ident>>,
+        empty_env: &Env,
+    ) -> LowerResult<rust_ir::MethodDatum<ChalkIr>>;
+}
+
+impl LowerMethod for MethodDefn {
+    fn lower_method(
+        &self,
+        outer_parameters: Vec<chalk_ir::ParameterKind<Ident>>,
+        empty_env: &Env,
+    ) -> LowerResult<rust_ir::MethodDatum<ChalkIr>> {
+        // As for associated types, the method's own parameters come
+        // first, followed by those of the enclosing trait or impl.
+        let mut parameter_kinds = self.all_parameters();
+        parameter_kinds.extend(outer_parameters);
+
+        let binders = empty_env.in_binders(self.name.span, parameter_kinds, |env| {
+            let unit = Ty::Tuple {
+                types: vec![],
+                span: self.name.span,
+            };
+            Ok(rust_ir::FnSig {
+                inputs: self
+                    .inputs
+                    .iter()
+                    .map(|ty| ty.lower(env))
+                    .collect::<LowerResult<_>>()?,
+                output: self.output.as_ref().unwrap_or(&unit).lower(env)?,
+                where_clauses: self.lower_where_clauses(env)?,
+            })
+        })?;
+
+        Ok(rust_ir::MethodDatum {
+            name: self.name.str.clone(),
+            receiver: self.receiver.map(|r| r.lower()),
+            binders,
         })
     }
 }
@@ -1309,7 +2733,7 @@ trait LowerClause {
 impl LowerClause for Clause {
     fn lower_clause(&self, env: &Env) -> LowerResult<Vec<chalk_ir::ProgramClause<ChalkIr>>> {
         let interner = env.interner();
-        let implications = env.in_binders(self.all_parameters(), |env| {
+        let implications = env.in_binders(self.span, self.all_parameters(), |env| {
             let consequences: Vec<chalk_ir::DomainGoal<ChalkIr>> = self.consequence.lower(env)?;
 
             let conditions = chalk_ir::Goals::from_fallible(
@@ -1359,7 +2783,7 @@ impl LowerTrait for TraitDefn {
     ) -> LowerResult<rust_ir::TraitDatum<ChalkIr>> {
         let all_parameters = self.all_parameters();
         let all_parameters_len = all_parameters.len();
-        let binders = env.in_binders(all_parameters, |env| {
+        let binders = env.in_binders(self.span, all_parameters, |env| {
             if self.flags.auto {
                 if all_parameters_len > 1 {
                     Err(RustIrError::AutoTraitParameters(self.name.clone()))?;
@@ -1380,11 +2804,19 @@ impl LowerTrait for TraitDefn {
             .map(|defn| env.associated_ty_lookups[&(trait_id, defn.name.str.clone())].id)
             .collect();
 
+        let methods = self
+            .methods
+            .iter()
+            .map(|method| method.lower_method(self.all_parameters(), env))
+            .collect::<LowerResult<_>>()?;
+
         let trait_datum = rust_ir::TraitDatum {
             id: trait_id,
-            binders: binders,
+            binders,
             flags: self.flags.lower(),
+            safety: self.safety.lower(),
             associated_ty_ids,
+            methods,
             well_known: self.well_known.map(|t| t.lower()),
         };
 
@@ -1398,37 +2830,65 @@ pub trait LowerGoal<A> {
     fn lower(&self, arg: &A) -> LowerResult<chalk_ir::Goal<ChalkIr>>;
 }
 
+/// Runs `op` in an empty environment in which all the items of
+/// `program` can be named.
+fn with_program_env<R>(program: &LoweredProgram, op: impl FnOnce(&Env) -> R) -> R {
+    let interner = &ChalkIr;
+    let associated_ty_lookups: BTreeMap<_, _> = program
+        .associated_ty_data
+        .iter()
+        .map(|(&associated_ty_id, datum)| {
+            let trait_datum = &program.trait_data[&datum.trait_id];
+            let num_trait_params = trait_datum.binders.len(interner);
+            let num_addl_params = datum.binders.len(interner) - num_trait_params;
+            let addl_parameter_kinds =
+                datum.binders.binders.as_slice(interner)[..num_addl_params].to_owned();
+            let lookup = AssociatedTyLookup {
+                id: associated_ty_id,
+                addl_parameter_kinds,
+            };
+            ((datum.trait_id, datum.name.clone()), lookup)
+        })
+        .collect();
+
+    let env = Env {
+        struct_ids: &program.struct_ids,
+        trait_ids: &program.trait_ids,
+        opaque_ty_ids: &program.opaque_ty_ids,
+        predicate_ids: &program.predicate_ids,
+        predicate_kinds: &program.predicate_kinds,
+        method_predicate_ids: &program.method_predicate_ids,
+        goal_defns: &program.goal_defns,
+        struct_kinds: &program.struct_kinds,
+        trait_kinds: &program.trait_kinds,
+        associated_ty_lookups: &associated_ty_lookups,
+        module: "",
+        parameter_map: BTreeMap::new(),
+    };
+
+    op(&env)
+}
+
 impl LowerGoal<LoweredProgram> for Goal {
     fn lower(&self, program: &LoweredProgram) -> LowerResult<chalk_ir::Goal<ChalkIr>> {
-        let interner = &ChalkIr;
-        let associated_ty_lookups: BTreeMap<_, _> = program
-            .associated_ty_data
-            .iter()
-            .map(|(&associated_ty_id, datum)| {
-                let trait_datum = &program.trait_data[&datum.trait_id];
-                let num_trait_params = trait_datum.binders.len(interner);
-                let num_addl_params = datum.binders.len(interner) - num_trait_params;
-                let addl_parameter_kinds =
-                    datum.binders.binders.as_slice(interner)[..num_addl_params].to_owned();
-                let lookup = AssociatedTyLookup {
-                    id: associated_ty_id,
-                    addl_parameter_kinds,
-                };
-                ((datum.trait_id, datum.name.clone()), lookup)
-            })
-            .collect();
+        with_program_env(program, |env| self.lower(env))
+    }
+}
 
-        let env = Env {
-            struct_ids: &program.struct_ids,
-            trait_ids: &program.trait_ids,
-            opaque_ty_ids: &program.opaque_ty_ids,
-            struct_kinds: &program.struct_kinds,
-            trait_kinds: &program.trait_kinds,
-            associated_ty_lookups: &associated_ty_lookups,
-            parameter_map: BTreeMap::new(),
-        };
+pub trait LowerMethodProbe {
+    fn lower(&self, program: &LoweredProgram) -> LowerResult<method::MethodProbe>;
+}
 
-        self.lower(&env)
+impl LowerMethodProbe for MethodProbe {
+    fn lower(&self, program: &LoweredProgram) -> LowerResult<method::MethodProbe> {
+        let mut receiver = self.receiver.clone();
+        receiver.rewrite_with(&mut ErasedLifetimes);
+        with_program_env(program, |env| {
+            Ok(method::MethodProbe {
+                receiver: receiver.lower(env)?,
+                method: self.method.str.clone(),
+            })
+        })
     }
 }
 
@@ -1458,9 +2918,32 @@ impl<'k> LowerGoal<Env<'k>> for Goal {
                 )?;
                 Ok(chalk_ir::GoalData::All(goals).intern(interner))
             }
+            Goal::Or(g1, g2s) => {
+                let goals = chalk_ir::Goals::from_fallible(
+                    interner,
+                    Some(g1).into_iter().chain(g2s).map(|g| g.lower(env)),
+                )?;
+                Ok(chalk_ir::GoalData::Any(goals).intern(interner))
+            }
             Goal::Not(g) => Ok(chalk_ir::GoalData::Not(g.lower(env)?).intern(interner)),
+            Goal::Named(name) => env.lookup_goal(name),
             Goal::Compatible(g) => Ok(g.lower(env)?.compatible(interner)),
             Goal::Leaf(leaf) => {
+                // Anonymous lifetimes in a goal stand for some lifetime, so
+                // `&u32: Foo` means `exists<'a> { &'a u32: Foo }`.
+                let mut leaf = leaf.clone();
+                let mut lifetimes = AnonymousLifetimes::after(env);
+                leaf.rewrite_with(&mut lifetimes);
+                let parameter_kinds = lifetimes.take();
+                if !parameter_kinds.is_empty() {
+                    let goal = Goal::Leaf(leaf);
+                    return goal.lower_quantified(
+                        env,
+                        chalk_ir::QuantifierKind::Exists,
+                        &parameter_kinds,
+                    );
+                }
+
                 // A where clause can lower to multiple leaf goals; wrap these in Goal::And.
                 Ok(leaf.lower(env)?)
             }
@@ -1489,8 +2972,17 @@ impl LowerQuantifiedGoal for Goal {
             return self.lower(env);
         }
 
+        // A goal has no span of its own; a duplicate or shadowed parameter
+        // is reported at the list of parameters.
+        let span = |pk: &ParameterKind| match pk {
+            ParameterKind::Ty(name) | ParameterKind::Lifetime(name) => name.span,
+        };
+        let span = Span::new(
+            span(&parameter_kinds[0]).lo,
+            span(&parameter_kinds[parameter_kinds.len() - 1]).hi,
+        );
         let parameter_kinds = parameter_kinds.iter().map(|pk| pk.lower());
-        let subgoal = env.in_binders(parameter_kinds, |env| self.lower(env))?;
+        let subgoal = env.in_binders(span, parameter_kinds, |env| self.lower(env))?;
         Ok(chalk_ir::GoalData::Quantified(quantifier_kind, subgoal).intern(interner))
     }
 }
@@ -1506,6 +2998,7 @@ impl LowerWellKnownTrait for WellKnownTrait {
             Self::CopyTrait => rust_ir::WellKnownTrait::CopyTrait,
             Self::CloneTrait => rust_ir::WellKnownTrait::CloneTrait,
             Self::DropTrait => rust_ir::WellKnownTrait::DropTrait,
+            Self::DerefTrait => rust_ir::WellKnownTrait::DerefTrait,
         }
     }
 }
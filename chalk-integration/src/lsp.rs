@@ -0,0 +1,337 @@
+//! A language server for `.chalk` files, which speaks the Language Server
+//! Protocol over stdin and stdout (see `chalk lsp`). It reports syntax,
+//! lowering and coherence errors as diagnostics, finds the definitions of
+//! item names, and shows the lowered IR of the item under the cursor on
+//! hover.
+//!
+//! Only the parts of the protocol that this needs are implemented.
+//! Documents are synchronized in full on each change.
+
+use crate::db::ChalkDatabase;
+use crate::lowering::LowerProgram;
+use crate::query::LoweringDatabase;
+use crate::tls;
+use chalk_parse::ast::{Identifier, Item, Span};
+use chalk_parse::SyntaxError;
+use chalk_solve::SolverChoice;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+
+/// The state of the server: the text of each open document, by URI.
+#[derive(Default)]
+pub struct Server {
+    documents: BTreeMap<String, String>,
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Server::default()
+    }
+
+    /// Handles a message from the client, returning the messages to send
+    /// back: the response to a request, or the diagnostics of a document
+    /// that was opened or changed.
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let method = message["method"].as_str().unwrap_or("");
+        let params = &message["params"];
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "definitionProvider": true,
+                    "hoverProvider": true,
+                },
+                "serverInfo": { "name": "chalk" },
+            }),
+            "shutdown" => Value::Null,
+            "textDocument/didOpen" | "textDocument/didChange" => {
+                let text = if method == "textDocument/didOpen" {
+                    &params["textDocument"]["text"]
+                } else {
+                    &params["contentChanges"][0]["text"]
+                };
+                let text = text.as_str().unwrap_or("").to_string();
+                let diagnostics = diagnostics(&text);
+                self.documents.insert(uri.to_string(), text);
+                return vec![publish_diagnostics(uri, diagnostics)];
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                return vec![publish_diagnostics(uri, vec![])];
+            }
+            "textDocument/definition" => self
+                .word_at(params)
+                .and_then(|(text, word)| definition(uri, text, word))
+                .unwrap_or(Value::Null),
+            "textDocument/hover" => self
+                .word_at(params)
+                .and_then(|(text, word)| hover(text, word))
+                .unwrap_or(Value::Null),
+            _ => {
+                return match message.get("id") {
+                    Some(id) => vec![json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": {
+                            "code": -32601,
+                            "message": format!("unsupported method `{}`", method),
+                        },
+                    })],
+                    None => vec![],
+                };
+            }
+        };
+        match message.get("id") {
+            Some(id) => vec![json!({ "jsonrpc": "2.0", "id": id, "result": result })],
+            None => vec![],
+        }
+    }
+
+    /// The text of the document of a request about a position, and the
+    /// range of the word at the position.
+    fn word_at(&self, params: &Value) -> Option<(&str, (usize, usize))> {
+        let text = self
+            .documents
+            .get(params["textDocument"]["uri"].as_str()?)?;
+        let position = &params["position"];
+        let offset = offset(
+            text,
+            position["line"].as_u64()? as usize,
+            position["character"].as_u64()? as usize,
+        );
+
+        let is_word = |c: u8| c.is_ascii_alphanumeric() || c == b'_' || c == b':';
+        let bytes = text.as_bytes();
+        let mut start = offset;
+        while start > 0 && is_word(bytes[start - 1]) {
+            start -= 1;
+        }
+        let mut end = offset;
+        while end < bytes.len() && is_word(bytes[end]) {
+            end += 1;
+        }
+        while start < end && bytes[start] == b':' {
+            start += 1;
+        }
+        while end > start && bytes[end - 1] == b':' {
+            end -= 1;
+        }
+        if start == end {
+            None
+        } else {
+            Some((text.as_str(), (start, end)))
+        }
+    }
+}
+
+/// Runs a server that reads messages from `input` and writes messages to
+/// `output`, until the client sends `exit` or closes `input`.
+pub fn run(mut input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut server = Server::new();
+    while let Some(message) = read_message(&mut input)? {
+        if message["method"] == "exit" {
+            break;
+        }
+        for reply in server.handle(&message) {
+            write_message(&mut output, &reply)?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads a message, which is preceded by a `Content-Length` header.
+/// Returns `None` at the end of `input`.
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let invalid = |error: String| io::Error::new(io::ErrorKind::InvalidData, error);
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let header = "content-length:";
+        if line.to_ascii_lowercase().starts_with(header) {
+            let value = line[header.len()..].trim();
+            length = Some(value.parse::<usize>().map_err(|e| invalid(e.to_string()))?);
+        }
+    }
+    let length = length.ok_or_else(|| invalid("missing Content-Length header".to_string()))?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| invalid(e.to_string()))
+}
+
+/// Writes a message, preceded by its `Content-Length` header.
+pub fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Value>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+/// The errors in the program `text`. Only the first syntax error is
+/// found, as parsing does not go on after one; lowering finds all the
+/// errors that do not depend on one another.
+fn diagnostics(text: &str) -> Vec<Value> {
+    let diagnostic = |span: Option<Span>, message: String| {
+        let (lo, hi) = span.map_or((0, 0), |span| (span.lo, span.hi));
+        json!({
+            "range": range(text, lo, hi),
+            "severity": 1,
+            "source": "chalk",
+            "message": message,
+        })
+    };
+
+    let program = match chalk_parse::parse_program(text) {
+        Ok(program) => program,
+        Err(e) => {
+            let span = e.downcast_ref::<SyntaxError>().and_then(|e| e.span);
+            return vec![diagnostic(span, e.to_string())];
+        }
+    };
+    if let Err(errors) = program.lower() {
+        return errors
+            .iter()
+            .map(|e| diagnostic(Some(e.span()), e.to_string()))
+            .collect();
+    }
+
+    // The errors of the checks after lowering are not about a particular
+    // place in the program, so they are reported at the start of the
+    // document.
+    let db = ChalkDatabase::with(text, SolverChoice::default());
+    match db.checked_program() {
+        Ok(_) => vec![],
+        Err(e) => vec![diagnostic(None, e.to_string())],
+    }
+}
+
+/// The locations of the definitions of the item named by the word at
+/// `(start, end)`: those whose full name is the word or, if there are
+/// none, those whose name ends with the last segment of the word.
+fn definition(uri: &str, text: &str, (start, end): (usize, usize)) -> Option<Value> {
+    let word = &text[start..end];
+    let program = chalk_parse::parse_program(text).ok()?;
+    let mut definitions = vec![];
+    collect_definitions(&program.items, "", &mut definitions);
+
+    let last_segment = |name: &str| name.rsplit("::").next().unwrap().to_string();
+    let mut matches: Vec<_> = definitions
+        .iter()
+        .filter(|(name, _)| name == word)
+        .collect();
+    if matches.is_empty() {
+        matches = definitions
+            .iter()
+            .filter(|(name, _)| last_segment(name) == last_segment(word))
+            .collect();
+    }
+    if matches.is_empty() {
+        return None;
+    }
+    let locations: Vec<_> = matches
+        .into_iter()
+        .map(|(_, identifier)| {
+            json!({
+                "uri": uri,
+                "range": range(text, identifier.span.lo, identifier.span.hi),
+            })
+        })
+        .collect();
+    Some(Value::Array(locations))
+}
+
+/// Adds the named items of `items`, in a module with path `prefix`, to
+/// `definitions`, with their full names.
+fn collect_definitions(items: &[Item], prefix: &str, definitions: &mut Vec<(String, Identifier)>) {
+    let mut define = |identifier: &Identifier| {
+        definitions.push((format!("{}{}", prefix, identifier), identifier.clone()))
+    };
+    let mut modules = vec![];
+    for item in items {
+        match item {
+            Item::StructDefn(defn) => define(&defn.name),
+            Item::TraitDefn(defn) => {
+                define(&defn.name);
+                for assoc_ty in &defn.assoc_ty_defns {
+                    define(&assoc_ty.name);
+                }
+            }
+            Item::OpaqueTyDefn(defn) => define(&defn.identifier),
+            Item::PredicateDefn(defn) => define(&defn.name),
+            Item::GoalDefn(defn) => define(&defn.name),
+            Item::ModDefn(defn) => {
+                define(&defn.name);
+                modules.push(defn);
+            }
+            Item::HiddenTyDefn(_) | Item::Impl(_) | Item::InherentImpl(_) | Item::Clause(_) => {}
+        }
+    }
+    for module in modules {
+        let prefix = format!("{}{}::", prefix, module.name);
+        collect_definitions(&module.items, &prefix, definitions);
+    }
+}
+
+/// The lowered IR of the item named by the word at `(start, end)`.
+fn hover(text: &str, (start, end): (usize, usize)) -> Option<Value> {
+    let db = ChalkDatabase::with(text, SolverChoice::default());
+    let program = db.program_ir().ok()?;
+    let lowered = tls::set_current_program(&program, || program.lowered_item(&text[start..end]))?;
+    Some(json!({
+        "contents": { "kind": "markdown", "value": format!("```\n{}\n```", lowered.trim_end()) },
+        "range": range(text, start, end),
+    }))
+}
+
+fn range(text: &str, lo: usize, hi: usize) -> Value {
+    json!({ "start": position(text, lo), "end": position(text, hi) })
+}
+
+/// The position of the byte `offset` of `text`, whose `character` counts
+/// UTF-16 code units, as the protocol does.
+fn position(text: &str, offset: usize) -> Value {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    json!({
+        "line": before.matches('\n').count(),
+        "character": before[line_start..].encode_utf16().count(),
+    })
+}
+
+/// The byte offset of a position, or of the end of its line if the
+/// position is past it.
+fn offset(text: &str, line: usize, character: usize) -> usize {
+    let line_start = match line {
+        0 => 0,
+        _ => match text.match_indices('\n').nth(line - 1) {
+            Some((i, _)) => i + 1,
+            None => return text.len(),
+        },
+    };
+    let mut units = 0;
+    for (i, c) in text[line_start..].char_indices() {
+        if units >= character || c == '\n' {
+            return line_start + i;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
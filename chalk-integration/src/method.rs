@@ -0,0 +1,395 @@
+//! Method probing: given a receiver type and a method name, finds the
+//! inherent method that a call `receiver.name()` would resolve to.
+//!
+//! Goals can also ask whether a method can be called, as `Ty.name()`:
+//! such a goal lowers to a predicate of the method name, which holds by
+//! the clauses of `method_clauses`. Unlike the probe, the goal only asks
+//! whether a method applies at some autoderef step, so several methods
+//! that apply at the same step make it hold rather than ambiguous.
+//!
+//! This follows the shape of rustc's probing: starting from the
+//! receiver type, we look for a method at each autoderef step, first
+//! taking the receiver by value, then by autoref (`&`) and finally by
+//! mutable autoref (`&mut`). A method applies when its full receiver
+//! type (`Self`, `&Self` or `&mut Self`) is the adjusted type, so a
+//! `&self` method is found on `&Foo` without autoref, and on `Foo` with
+//! it. Whether an impl applies to the `Self` that this gives is decided
+//! by the trait solver, so the impl's where clauses are taken into
+//! account. Autoderef goes through references, and otherwise uses the
+//! `#[lang(deref)]` trait, if any, normalizing `<T as Deref>::Target` to
+//! find the next step.
+//!
+//! Lifetimes take no part in picking a method, so autoref borrows for
+//! `'static`, as do the references of the probe with elided lifetimes.
+
+use crate::db::ChalkDatabase;
+use crate::interner::ChalkIr;
+use crate::program::Program;
+use crate::Identifier;
+use chalk_ir::cast::Cast;
+use chalk_ir::fold::shift::Shift;
+use chalk_ir::{
+    AliasTy, ApplicationTy, AssocTypeId, Binders, BoundVar, ClausePriority, DebruijnIndex,
+    DomainGoal, EqGoal, Goal, Goals, ImplId, Lifetime, LifetimeData, Mutability, Normalize,
+    ParameterKind, ParameterKinds, PredicateRef, ProgramClause, ProgramClauseData,
+    ProgramClauseImplication, ProjectionTy, QuantifierKind, Substitution, Ty, TyData, TypeName,
+};
+use chalk_rust_ir::{InherentImplDatum, Receiver, WellKnownTrait};
+use chalk_solve::ext::*;
+use chalk_solve::Solution;
+use std::fmt;
+
+/// A lowered `Ty.name()` query.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MethodProbe {
+    pub receiver: Ty<ChalkIr>,
+    pub method: Identifier,
+}
+
+/// The method that a probe resolved to, along with the adjustments
+/// applied to the receiver to call it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MethodPick {
+    /// The inherent impl defining the method.
+    pub impl_id: ImplId<ChalkIr>,
+
+    /// The `Self` type of the method, which the impl applies to.
+    pub self_ty: Ty<ChalkIr>,
+
+    /// Number of autoderef steps taken.
+    pub autoderefs: usize,
+
+    /// The reference taken to the receiver after autoderef, if any.
+    pub autoref: Option<Mutability>,
+
+    /// How the method takes `self`.
+    pub receiver: Receiver,
+}
+
+impl fmt::Display for MethodPick {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let autoref = match self.autoref {
+            None => "none",
+            Some(Mutability::Not) => "&",
+            Some(Mutability::Mut) => "&mut",
+        };
+        write!(
+            f,
+            "impl for `{:?}`, autoderefs: {}, autoref: {}",
+            self.self_ty, self.autoderefs, autoref
+        )
+    }
+}
+
+#[derive(Debug)]
+pub enum MethodError {
+    /// No method with this name applies to the receiver type.
+    NoMatch(Identifier, Ty<ChalkIr>),
+
+    /// Several methods apply at the same step, with the same autoref.
+    Ambiguous(Identifier, Ty<ChalkIr>),
+}
+
+impl fmt::Display for MethodError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MethodError::NoMatch(name, ty) => {
+                write!(f, "no method named `{}` found for `{:?}`", name, ty)
+            }
+            MethodError::Ambiguous(name, ty) => write!(
+                f,
+                "multiple applicable methods named `{}` found for `{:?}`",
+                name, ty
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MethodError {}
+
+/// Resolves `probe` against the inherent impls of `program`.
+pub fn probe_method(
+    db: &ChalkDatabase,
+    program: &Program,
+    probe: &MethodProbe,
+) -> Result<MethodPick, MethodError> {
+    let interner = &ChalkIr;
+    let mut steps = vec![];
+    let mut self_ty = Some(probe.receiver.clone());
+    while let Some(ty) = self_ty {
+        // Stop on cycles such as `impl Deref for Foo { type Target = Foo; }`.
+        if steps.contains(&ty) {
+            break;
+        }
+
+        for &autoref in &[None, Some(Mutability::Not), Some(Mutability::Mut)] {
+            let adjusted = match autoref {
+                None => ty.clone(),
+                Some(mutability) => {
+                    let lifetime = LifetimeData::Static.intern(interner);
+                    reference(mutability, lifetime, ty.clone())
+                }
+            };
+            let mut picks = program
+                .inherent_impl_data
+                .iter()
+                .flat_map(|(&impl_id, datum)| {
+                    datum
+                        .methods
+                        .iter()
+                        .filter(|method| method.name == probe.method)
+                        .filter_map(move |method| Some((impl_id, datum, method.receiver?)))
+                })
+                .filter_map(|(impl_id, datum, receiver)| {
+                    let self_ty = receiver_self_ty(receiver, &adjusted)?;
+                    if impl_applies(db, datum, &self_ty) {
+                        Some((impl_id, self_ty, receiver))
+                    } else {
+                        None
+                    }
+                });
+            if let Some((impl_id, self_ty, receiver)) = picks.next() {
+                if picks.next().is_some() {
+                    return Err(MethodError::Ambiguous(probe.method.clone(), ty));
+                }
+                return Ok(MethodPick {
+                    impl_id,
+                    self_ty,
+                    autoderefs: steps.len(),
+                    autoref,
+                    receiver,
+                });
+            }
+        }
+
+        self_ty = deref(db, program, &ty);
+        steps.push(ty);
+    }
+
+    Err(MethodError::NoMatch(
+        probe.method.clone(),
+        probe.receiver.clone(),
+    ))
+}
+
+/// The reference type `&'a ty` or `&'a mut ty`.
+fn reference(mutability: Mutability, lifetime: Lifetime<ChalkIr>, ty: Ty<ChalkIr>) -> Ty<ChalkIr> {
+    let interner = &ChalkIr;
+    TyData::Apply(ApplicationTy {
+        name: TypeName::Ref(mutability),
+        substitution: Substitution::from(
+            interner,
+            vec![lifetime.cast(interner), ty.cast(interner)],
+        ),
+    })
+    .intern(interner)
+}
+
+/// The mutability and the referent type of `ty`, if it is a reference.
+fn referent(ty: &Ty<ChalkIr>) -> Option<(Mutability, Ty<ChalkIr>)> {
+    let interner = &ChalkIr;
+    match ty.data(interner) {
+        TyData::Apply(ApplicationTy {
+            name: TypeName::Ref(mutability),
+            substitution,
+        }) => Some((
+            *mutability,
+            substitution.at(interner, 1).assert_ty_ref(interner).clone(),
+        )),
+        _ => None,
+    }
+}
+
+/// The `Self` type that a method taking `self` as `receiver` must have
+/// for its receiver type (`Self`, `&Self` or `&mut Self`) to be `ty`.
+fn receiver_self_ty(receiver: Receiver, ty: &Ty<ChalkIr>) -> Option<Ty<ChalkIr>> {
+    let expected = match receiver {
+        Receiver::Value => return Some(ty.clone()),
+        Receiver::Ref => Mutability::Not,
+        Receiver::RefMut => Mutability::Mut,
+    };
+    match referent(ty)? {
+        (mutability, self_ty) if mutability == expected => Some(self_ty),
+        _ => None,
+    }
+}
+
+/// Checks whether the impl's self type unifies with `ty`, with its
+/// where clauses satisfied:
+///
+/// ```notrust
+/// exists<P0..Pn> { SelfTy = ty, WC0, .., WCm }
+/// ```
+fn impl_applies(db: &ChalkDatabase, datum: &InherentImplDatum<ChalkIr>, ty: &Ty<ChalkIr>) -> bool {
+    let interner = &ChalkIr;
+    let (binders, bound) = datum.binders.clone().into();
+    let eq_goal: Goal<ChalkIr> = EqGoal {
+        a: ty.shifted_in(interner).cast(interner),
+        b: bound.self_ty.cast(interner),
+    }
+    .cast(interner);
+    let goal = Goal::all(
+        interner,
+        Some(eq_goal)
+            .into_iter()
+            .chain(bound.where_clauses.into_iter().map(|wc| wc.cast(interner))),
+    )
+    .quantify(interner, QuantifierKind::Exists, binders);
+
+    db.solve(&goal.into_closed_goal(interner)).is_some()
+}
+
+/// The referent of `ty` if it is a reference, and otherwise normalizes
+/// `<ty as Deref>::Target`, if the program declares a `#[lang(deref)]`
+/// trait and the projection has a unique value.
+fn deref(db: &ChalkDatabase, program: &Program, ty: &Ty<ChalkIr>) -> Option<Ty<ChalkIr>> {
+    let interner = &ChalkIr;
+    if let Some((_, referent)) = referent(ty) {
+        return Some(referent);
+    }
+    let target_id = deref_target(program)?;
+    let alias = AliasTy::Projection(ProjectionTy {
+        associated_ty_id: target_id,
+        substitution: Substitution::from1(interner, ty.shifted_in(interner)),
+    });
+    let goal: Goal<ChalkIr> = Normalize {
+        alias,
+        ty: BoundVar::new(DebruijnIndex::INNERMOST, 0).to_ty(interner),
+    }
+    .cast(interner);
+    let goal = goal.quantify(
+        interner,
+        QuantifierKind::Exists,
+        ParameterKinds::from(interner, vec![ParameterKind::Ty(())]),
+    );
+
+    match db.solve(&goal.into_peeled_goal(interner))? {
+        Solution::Unique(solution) if solution.binders.is_empty(interner) => Some(
+            solution
+                .value
+                .subst
+                .at(interner, 0)
+                .assert_ty_ref(interner)
+                .clone(),
+        ),
+        _ => None,
+    }
+}
+
+/// The `Target` type of the `#[lang(deref)]` trait, if the program
+/// declares one.
+fn deref_target(program: &Program) -> Option<AssocTypeId<ChalkIr>> {
+    let deref_trait = *program.well_known_traits.get(&WellKnownTrait::DerefTrait)?;
+    let (&target_id, _) = program
+        .associated_ty_data
+        .iter()
+        .find(|(_, datum)| datum.trait_id == deref_trait && &*datum.name == "Target")?;
+    Some(target_id)
+}
+
+/// The clauses of the method predicates of `program`. For each method
+/// `name` that an inherent impl defines, the impl's self type has it
+/// when the impl's where clauses hold:
+///
+/// ```notrust
+/// forall<P0..Pn> { SelfTy.name() :- WC0, .., WCm }
+/// ```
+///
+/// and so does each reference to a type that has it:
+///
+/// ```notrust
+/// forall<'a, T> { (&'a T).name() :- T.name() }
+/// forall<'a, T> { (&'a mut T).name() :- T.name() }
+/// ```
+///
+/// and, if the program declares a `#[lang(deref)]` trait, so does each
+/// type that derefs to a type that has it:
+///
+/// ```notrust
+/// forall<T, U> { T.name() :- Normalize(<T as Deref>::Target -> U), U.name() }
+/// ```
+pub(crate) fn method_clauses(program: &Program) -> Vec<ProgramClause<ChalkIr>> {
+    let interner = &ChalkIr;
+    let method_goal = |name: &Identifier, ty: Ty<ChalkIr>| {
+        DomainGoal::Predicate(PredicateRef {
+            predicate_id: program.method_predicate_ids[name],
+            substitution: Substitution::from1(interner, ty),
+        })
+    };
+
+    let mut clauses = vec![];
+    for datum in program.inherent_impl_data.values() {
+        for method in datum.methods.iter().filter(|m| m.receiver.is_some()) {
+            let implication = datum.binders.map_ref(|bound| ProgramClauseImplication {
+                consequence: method_goal(&method.name, bound.self_ty.clone()),
+                conditions: Goals::from(
+                    interner,
+                    bound
+                        .where_clauses
+                        .iter()
+                        .map(|wc| wc.clone().cast::<Goal<ChalkIr>>(interner)),
+                ),
+                priority: ClausePriority::High,
+            });
+            clauses.push(ProgramClauseData::ForAll(implication).intern(interner));
+        }
+    }
+
+    let bound_var = |index| BoundVar::new(DebruijnIndex::INNERMOST, index);
+    let binders = ParameterKinds::from(
+        interner,
+        vec![ParameterKind::Lifetime(()), ParameterKind::Ty(())],
+    );
+    for name in program.method_predicate_ids.keys() {
+        for &mutability in &[Mutability::Not, Mutability::Mut] {
+            let ty = reference(
+                mutability,
+                bound_var(0).to_lifetime(interner),
+                bound_var(1).to_ty(interner),
+            );
+            let implication = ProgramClauseImplication {
+                consequence: method_goal(name, ty),
+                conditions: Goals::from(
+                    interner,
+                    vec![method_goal(name, bound_var(1).to_ty(interner))],
+                ),
+                priority: ClausePriority::High,
+            };
+            clauses.push(
+                ProgramClauseData::ForAll(Binders::new(binders.clone(), implication))
+                    .intern(interner),
+            );
+        }
+    }
+
+    if let Some(target_id) = deref_target(program) {
+        let ty = |index| bound_var(index).to_ty(interner);
+        let normalize: Goal<ChalkIr> = Normalize {
+            alias: AliasTy::Projection(ProjectionTy {
+                associated_ty_id: target_id,
+                substitution: Substitution::from1(interner, ty(0)),
+            }),
+            ty: ty(1),
+        }
+        .cast(interner);
+        let binders =
+            ParameterKinds::from(interner, vec![ParameterKind::Ty(()), ParameterKind::Ty(())]);
+        for name in program.method_predicate_ids.keys() {
+            // The conditions are solved from the last one, like those
+            // of lowered custom clauses, so normalizing comes first.
+            let implication = ProgramClauseImplication {
+                consequence: method_goal(name, ty(0)),
+                conditions: Goals::from(
+                    interner,
+                    vec![method_goal(name, ty(1)).cast(interner), normalize.clone()],
+                ),
+                priority: ClausePriority::High,
+            };
+            clauses.push(
+                ProgramClauseData::ForAll(Binders::new(binders.clone(), implication))
+                    .intern(interner),
+            );
+        }
+    }
+    clauses
+}
@@ -1,23 +1,27 @@
 use crate::interner::ChalkIr;
-use crate::{tls, Identifier, TypeKind};
+use crate::{tls, Identifier, RawId, TypeKind};
 use chalk_ir::could_match::CouldMatch;
 use chalk_ir::debug::Angle;
 use chalk_ir::{
     debug::SeparatorTraitRef, AliasTy, ApplicationTy, AssocTypeId, Goal, Goals, ImplId, Lifetime,
-    OpaqueTy, OpaqueTyId, Parameter, ProgramClause, ProgramClauseImplication, ProgramClauses,
-    ProjectionTy, StructId, Substitution, TraitId, Ty,
+    OpaqueTy, OpaqueTyId, Parameter, PredicateId, PredicateRef, ProgramClause,
+    ProgramClauseImplication, ProgramClauses, ProjectionTy, StructId, Substitution, TraitId, Ty,
 };
 use chalk_rust_ir::{
-    AssociatedTyDatum, AssociatedTyValue, AssociatedTyValueId, ImplDatum, ImplType, OpaqueTyDatum,
-    StructDatum, TraitDatum, WellKnownTrait,
+    AssociatedTyDatum, AssociatedTyValue, AssociatedTyValueId, ImplDatum, ImplType,
+    InherentImplDatum, OpaqueTyDatum, StructDatum, TraitDatum, WellKnownTrait,
 };
+use chalk_solve::clauses::ItemId;
 use chalk_solve::split::Split;
-use chalk_solve::RustIrDatabase;
-use std::collections::BTreeMap;
-use std::fmt;
+use chalk_solve::wf::{WfError, WfSolver};
+use chalk_solve::{RustIrDatabase, SolverChoice};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Write};
+use std::iter;
 use std::sync::Arc;
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Program {
     /// From struct name to item-id. Used during lowering only.
     pub struct_ids: BTreeMap<Identifier, StructId<ChalkIr>>,
@@ -37,6 +41,13 @@ pub struct Program {
     /// For each impl:
     pub impl_data: BTreeMap<ImplId<ChalkIr>, Arc<ImplDatum<ChalkIr>>>,
 
+    /// For each trait, the impls of it, kept up to date by `add_impl`
+    /// and `remove_impl`. See `Program::index_impls`.
+    pub impls_by_trait: BTreeMap<TraitId<ChalkIr>, BTreeSet<ImplId<ChalkIr>>>,
+
+    /// For each inherent impl `impl Foo { ... }`:
+    pub inherent_impl_data: BTreeMap<ImplId<ChalkIr>, Arc<InherentImplDatum<ChalkIr>>>,
+
     /// For each associated ty value `type Foo = XXX` found in an impl:
     pub associated_ty_values:
         BTreeMap<AssociatedTyValueId<ChalkIr>, Arc<AssociatedTyValue<ChalkIr>>>,
@@ -59,11 +70,92 @@ pub struct Program {
     /// For each associated ty declaration `type Foo` found in a trait:
     pub associated_ty_data: BTreeMap<AssocTypeId<ChalkIr>, Arc<AssociatedTyDatum<ChalkIr>>>,
 
+    /// From predicate name to item-id. Used during lowering only.
+    pub predicate_ids: BTreeMap<Identifier, PredicateId<ChalkIr>>,
+
+    /// For each user-declared predicate `predicate Foo(type)`:
+    pub predicate_kinds: BTreeMap<PredicateId<ChalkIr>, TypeKind>,
+
+    /// For each method name, the predicate that `Ty.name()` goals lower
+    /// to. It holds of the receiver types that a method of that name can
+    /// be called on, by the `method_clauses`.
+    pub method_predicate_ids: BTreeMap<Identifier, PredicateId<ChalkIr>>,
+
+    /// From goal name to the goal it defines. Used during lowering only.
+    pub goal_defns: BTreeMap<Identifier, Goal<ChalkIr>>,
+
     /// For each user-specified clause
     pub custom_clauses: Vec<ProgramClause<ChalkIr>>,
+
+    /// The clauses of the method predicates, derived from the inherent
+    /// impls; see `method::method_clauses`.
+    pub method_clauses: Vec<ProgramClause<ChalkIr>>,
 }
 
 impl Program {
+    /// The name of the method that `predicate_id` is the predicate of, if
+    /// it is that of a method.
+    pub fn method_name(&self, predicate_id: PredicateId<ChalkIr>) -> Option<&Identifier> {
+        self.method_predicate_ids
+            .iter()
+            .find(|&(_, &id)| id == predicate_id)
+            .map(|(name, _)| name)
+    }
+
+    /// Indexes the impls of `impl_data` by the trait they implement, to
+    /// fill in `impls_by_trait`.
+    pub fn index_impls(
+        impl_data: &BTreeMap<ImplId<ChalkIr>, Arc<ImplDatum<ChalkIr>>>,
+    ) -> BTreeMap<TraitId<ChalkIr>, BTreeSet<ImplId<ChalkIr>>> {
+        let mut impls_by_trait: BTreeMap<_, BTreeSet<_>> = BTreeMap::new();
+        for (&impl_id, impl_datum) in impl_data {
+            impls_by_trait
+                .entry(impl_datum.trait_id())
+                .or_default()
+                .insert(impl_id);
+        }
+        impls_by_trait
+    }
+
+    /// The impls of the trait `trait_id`, in the order they are
+    /// declared in.
+    pub fn impls_of_trait(
+        &self,
+        trait_id: TraitId<ChalkIr>,
+    ) -> impl Iterator<Item = ImplId<ChalkIr>> + '_ {
+        self.impls_by_trait
+            .get(&trait_id)
+            .into_iter()
+            .flat_map(|impl_ids| impl_ids.iter().cloned())
+    }
+
+    /// The trait that declares the associated type `assoc_ty_id`, if
+    /// there is such an associated type.
+    pub fn trait_of_assoc_ty(&self, assoc_ty_id: AssocTypeId<ChalkIr>) -> Option<TraitId<ChalkIr>> {
+        self.associated_ty_data
+            .get(&assoc_ty_id)
+            .map(|datum| datum.trait_id)
+    }
+
+    /// The name of the struct, trait, opaque type, predicate or
+    /// associated type with the id `item_id`, if there is one. Impls
+    /// have no names.
+    pub fn item_name(&self, item_id: RawId) -> Option<&Identifier> {
+        let kind = self
+            .struct_kinds
+            .get(&StructId(item_id))
+            .or_else(|| self.trait_kinds.get(&TraitId(item_id)))
+            .or_else(|| self.opaque_ty_kinds.get(&OpaqueTyId(item_id)))
+            .or_else(|| self.predicate_kinds.get(&PredicateId(item_id)));
+        match kind {
+            Some(kind) => Some(&kind.name),
+            None => self
+                .associated_ty_data
+                .get(&AssocTypeId(item_id))
+                .map(|datum| &datum.name),
+        }
+    }
+
     /// Returns the ids for all impls declared in this crate.
     pub(crate) fn local_impl_ids(&self) -> Vec<ImplId<ChalkIr>> {
         self.impl_data
@@ -72,6 +164,142 @@ impl Program {
             .map(|(&impl_id, _)| impl_id)
             .collect()
     }
+
+    /// Checks every impl for well-formedness: its trait ref must be
+    /// well-formed and it must satisfy the where clauses of the trait.
+    /// Rather than stopping at the first ill-formed impl, this returns
+    /// the failure for each impl that does not pass.
+    pub fn check_wf(
+        &self,
+        solver_choice: SolverChoice,
+    ) -> Vec<(ImplId<ChalkIr>, WfError<ChalkIr>)> {
+        let solver = WfSolver::new(self, solver_choice);
+        self.impl_data
+            .keys()
+            .filter_map(|&impl_id| {
+                solver
+                    .verify_trait_impl(impl_id)
+                    .err()
+                    .map(|error| (impl_id, error))
+            })
+            .collect()
+    }
+
+    /// Removes the impl `impl_id`, along with the values it gives for
+    /// associated types, returning them so that the impl can be added
+    /// back with `add_impl`. A solver that was used with this program
+    /// must then be told to invalidate the solutions that depend on the
+    /// impls of its trait, with `Solver::invalidate_traits`.
+    pub fn remove_impl(
+        &mut self,
+        impl_id: ImplId<ChalkIr>,
+    ) -> Option<(
+        Arc<ImplDatum<ChalkIr>>,
+        Vec<Arc<AssociatedTyValue<ChalkIr>>>,
+    )> {
+        let impl_datum = self.impl_data.remove(&impl_id)?;
+        if let Some(impl_ids) = self.impls_by_trait.get_mut(&impl_datum.trait_id()) {
+            impl_ids.remove(&impl_id);
+        }
+        let values = impl_datum
+            .associated_ty_value_ids
+            .iter()
+            .map(|id| self.associated_ty_values.remove(id).unwrap())
+            .collect();
+        Some((impl_datum, values))
+    }
+
+    /// Adds the impl `impl_datum` as `impl_id`, along with the values it
+    /// gives for associated types, in the order of its
+    /// `associated_ty_value_ids`, and returns the trait it implements.
+    /// As with `remove_impl`, a solver that was used with this program
+    /// must be told to invalidate the solutions for that trait.
+    pub fn add_impl(
+        &mut self,
+        impl_id: ImplId<ChalkIr>,
+        impl_datum: Arc<ImplDatum<ChalkIr>>,
+        values: Vec<Arc<AssociatedTyValue<ChalkIr>>>,
+    ) -> TraitId<ChalkIr> {
+        assert_eq!(impl_datum.associated_ty_value_ids.len(), values.len());
+        for (&id, value) in impl_datum.associated_ty_value_ids.iter().zip(values) {
+            self.associated_ty_values.insert(id, value);
+        }
+        let trait_id = impl_datum.trait_id();
+        self.impl_data.insert(impl_id, impl_datum);
+        self.impls_by_trait
+            .entry(trait_id)
+            .or_default()
+            .insert(impl_id);
+        trait_id
+    }
+
+    /// The items that the struct, trait or opaque type named `name`
+    /// consists of: the item itself, and for a trait, also its
+    /// associated types and its impls, which have no names of their
+    /// own. Empty if there is no item named `name`.
+    pub fn items_named(&self, name: &str) -> Vec<ItemId<ChalkIr>> {
+        let name = Identifier::from(name);
+        if let Some(&id) = self.struct_ids.get(&name) {
+            vec![ItemId::Struct(id)]
+        } else if let Some(&id) = self.trait_ids.get(&name) {
+            iter::once(ItemId::Trait(id))
+                .chain(
+                    self.trait_data[&id]
+                        .associated_ty_ids
+                        .iter()
+                        .map(|&assoc_ty_id| ItemId::AssociatedTy(assoc_ty_id)),
+                )
+                .chain(self.impls_of_trait(id).map(ItemId::Impl))
+                .collect()
+        } else if let Some(&id) = self.opaque_ty_ids.get(&name) {
+            vec![ItemId::OpaqueTy(id)]
+        } else {
+            vec![]
+        }
+    }
+
+    /// Renders the lowered IR of the items of `items_named(name)`, with
+    /// `Debug`: the kinds and data of the item and of its associated
+    /// types, and the data of its impls along with the values they give
+    /// the associated types. Returns `None` if there is no item named
+    /// `name`.
+    ///
+    /// Like all `Debug` output of the IR, this names items only while
+    /// the program is the current one; see `tls::set_current_program`.
+    pub fn lowered_item(&self, name: &str) -> Option<String> {
+        let items = self.items_named(name);
+        if items.is_empty() {
+            return None;
+        }
+        let mut out = String::new();
+        for item in items {
+            match item {
+                ItemId::Struct(id) => {
+                    writeln!(out, "{:#?}", self.struct_kinds[&id]).unwrap();
+                    writeln!(out, "{:#?}", self.struct_data[&id]).unwrap();
+                }
+                ItemId::Trait(id) => {
+                    writeln!(out, "{:#?}", self.trait_kinds[&id]).unwrap();
+                    writeln!(out, "{:#?}", self.trait_data[&id]).unwrap();
+                }
+                ItemId::AssociatedTy(id) => {
+                    writeln!(out, "{:#?}", self.associated_ty_data[&id]).unwrap();
+                }
+                ItemId::Impl(id) => {
+                    let impl_datum = &self.impl_data[&id];
+                    writeln!(out, "{:?} => {:#?}", id, impl_datum).unwrap();
+                    for value_id in &impl_datum.associated_ty_value_ids {
+                        writeln!(out, "{:#?}", self.associated_ty_values[value_id]).unwrap();
+                    }
+                }
+                ItemId::OpaqueTy(id) => {
+                    writeln!(out, "{:#?}", self.opaque_ty_kinds[&id]).unwrap();
+                    writeln!(out, "{:#?}", self.opaque_ty_data[&id]).unwrap();
+                }
+            }
+        }
+        Some(out)
+    }
 }
 
 impl tls::DebugContext for Program {
@@ -117,6 +345,24 @@ impl tls::DebugContext for Program {
         }
     }
 
+    fn debug_impl_id(
+        &self,
+        impl_id: ImplId<ChalkIr>,
+        fmt: &mut fmt::Formatter<'_>,
+    ) -> Result<(), fmt::Error> {
+        if let Some(d) = self.impl_data.get(&impl_id) {
+            write!(
+                fmt,
+                "impl({:?})",
+                d.binders.skip_binders().trait_ref.with_colon()
+            )
+        } else {
+            fmt.debug_struct("InvalidItemId")
+                .field("index", &impl_id.0)
+                .finish()
+        }
+    }
+
     fn debug_opaque_ty_id(
         &self,
         opaque_ty_id: OpaqueTyId<ChalkIr>,
@@ -131,6 +377,22 @@ impl tls::DebugContext for Program {
         }
     }
 
+    fn debug_predicate_id(
+        &self,
+        predicate_id: PredicateId<ChalkIr>,
+        fmt: &mut fmt::Formatter<'_>,
+    ) -> Result<(), fmt::Error> {
+        if let Some(k) = self.predicate_kinds.get(&predicate_id) {
+            write!(fmt, "{}", k.name)
+        } else if let Some(name) = self.method_name(predicate_id) {
+            write!(fmt, ".{}()", name)
+        } else {
+            fmt.debug_struct("InvalidItemId")
+                .field("index", &predicate_id.0)
+                .finish()
+        }
+    }
+
     fn debug_alias(
         &self,
         alias_ty: &AliasTy<ChalkIr>,
@@ -271,6 +533,23 @@ impl tls::DebugContext for Program {
         write!(fmt, "{:?}", application_ty.debug(interner))
     }
 
+    fn debug_predicate_ref(
+        &self,
+        predicate_ref: &PredicateRef<ChalkIr>,
+        fmt: &mut fmt::Formatter<'_>,
+    ) -> Result<(), fmt::Error> {
+        let interner = self.interner();
+        match self.method_name(predicate_ref.predicate_id) {
+            Some(name) => write!(
+                fmt,
+                "{:?}.{}()",
+                predicate_ref.substitution.at(interner, 0),
+                name
+            ),
+            None => write!(fmt, "{:?}", predicate_ref.debug(interner)),
+        }
+    }
+
     fn debug_substitution(
         &self,
         substitution: &Substitution<ChalkIr>,
@@ -301,7 +580,9 @@ impl tls::DebugContext for Program {
 
 impl RustIrDatabase<ChalkIr> for Program {
     fn custom_clauses(&self) -> Vec<ProgramClause<ChalkIr>> {
-        self.custom_clauses.clone()
+        let mut clauses = self.custom_clauses.clone();
+        clauses.extend(self.method_clauses.iter().cloned());
+        clauses
     }
 
     fn associated_ty_data(&self, ty: AssocTypeId<ChalkIr>) -> Arc<AssociatedTyDatum<ChalkIr>> {
@@ -337,30 +618,22 @@ impl RustIrDatabase<ChalkIr> for Program {
         parameters: &[Parameter<ChalkIr>],
     ) -> Vec<ImplId<ChalkIr>> {
         let interner = self.interner();
-        self.impl_data
-            .iter()
-            .filter(|(_, impl_datum)| {
-                let trait_ref = &impl_datum.binders.skip_binders().trait_ref;
-                trait_id == trait_ref.trait_id && {
-                    assert_eq!(trait_ref.substitution.len(interner), parameters.len());
-                    <[_] as CouldMatch<[_]>>::could_match(
-                        &parameters,
-                        interner,
-                        &trait_ref.substitution.parameters(interner),
-                    )
-                }
+        self.impls_of_trait(trait_id)
+            .filter(|impl_id| {
+                let trait_ref = &self.impl_data[impl_id].binders.skip_binders().trait_ref;
+                assert_eq!(trait_ref.substitution.len(interner), parameters.len());
+                <[_] as CouldMatch<[_]>>::could_match(
+                    &parameters,
+                    interner,
+                    &trait_ref.substitution.parameters(interner),
+                )
             })
-            .map(|(&impl_id, _)| impl_id)
             .collect()
     }
 
     fn local_impls_to_coherence_check(&self, trait_id: TraitId<ChalkIr>) -> Vec<ImplId<ChalkIr>> {
-        self.impl_data
-            .iter()
-            .filter(|(_, impl_datum)| {
-                impl_datum.trait_id() == trait_id && impl_datum.impl_type == ImplType::Local
-            })
-            .map(|(&impl_id, _)| impl_id)
+        self.impls_of_trait(trait_id)
+            .filter(|impl_id| self.impl_data[impl_id].impl_type == ImplType::Local)
             .collect()
     }
 
@@ -372,9 +645,8 @@ impl RustIrDatabase<ChalkIr> for Program {
         let interner = self.interner();
         // Look for an impl like `impl Send for Foo` where `Foo` is
         // the struct.  See `push_auto_trait_impls` for more.
-        self.impl_data.values().any(|impl_datum| {
-            impl_datum.trait_id() == auto_trait_id
-                && impl_datum.self_type_struct_id(interner) == Some(struct_id)
+        self.impls_of_trait(auto_trait_id).any(|impl_id| {
+            self.impl_data[&impl_id].self_type_struct_id(interner) == Some(struct_id)
         })
     }
 
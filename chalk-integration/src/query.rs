@@ -10,8 +10,10 @@ use crate::tls;
 use chalk_ir::TraitId;
 use chalk_solve::clauses::builder::ClauseBuilder;
 use chalk_solve::clauses::program_clauses::ToProgramClauses;
+use chalk_solve::clauses::{program_clauses_for_item, ItemId};
 use chalk_solve::coherence::orphan;
 use chalk_solve::coherence::{CoherenceSolver, SpecializationPriorities};
+use chalk_solve::stratification;
 use chalk_solve::wf;
 use chalk_solve::RustIrDatabase;
 use chalk_solve::Solver;
@@ -56,7 +58,12 @@ pub trait LoweringDatabase: RustIrDatabase<ChalkIr> {
 
 fn program_ir(db: &impl LoweringDatabase) -> Result<Arc<Program>, ChalkError> {
     let text = db.program_text();
-    Ok(Arc::new(chalk_parse::parse_program(&text)?.lower()?))
+    let program = chalk_parse::parse_program(&text)
+        .map_err(|error| ChalkError::parsing(error, &text))?
+        .lower()
+        .map_err(|error| ChalkError::lowering(error, &text))?;
+    debug_assert_eq!(program.validate(), vec![], "lowering produced invalid IR");
+    Ok(Arc::new(program))
 }
 
 fn orphan_check(db: &impl LoweringDatabase) -> Result<(), ChalkError> {
@@ -104,10 +111,15 @@ fn checked_program(db: &impl LoweringDatabase) -> Result<Arc<Program>, ChalkErro
             solver.verify_struct_decl(id)?;
         }
 
-        for &impl_id in program.impl_data.keys() {
-            solver.verify_trait_impl(impl_id)?;
+        let wf_errors = program.check_wf(db.solver_choice());
+        if !wf_errors.is_empty() {
+            let errors = wf_errors.into_iter().map(|(_, error)| error).collect();
+            return Err(ChalkError::well_formedness(errors));
         }
 
+        let environment = db.environment()?;
+        stratification::check_stratification(db.interner(), &environment.program_clauses)?;
+
         Ok(())
     })?;
 
@@ -121,7 +133,7 @@ fn environment(db: &impl LoweringDatabase) -> Result<Arc<ProgramEnvironment>, Ch
     // of the data above that always has the form:
     //
     //       forall P0...Pn. Something :- Conditions
-    let mut program_clauses = program.custom_clauses.clone();
+    let mut program_clauses = program.custom_clauses();
 
     let builder = &mut ClauseBuilder::new(db, &mut program_clauses);
 
@@ -150,17 +162,8 @@ fn environment(db: &impl LoweringDatabase) -> Result<Arc<ProgramEnvironment>, Ch
         }
     }
 
-    for datum in program.impl_data.values() {
-        // If we encounter a negative impl, do not generate any rule. Negative impls
-        // are currently just there to deactivate default impls for auto traits.
-        if datum.is_positive() {
-            datum.to_program_clauses(builder);
-            datum
-                .associated_ty_value_ids
-                .iter()
-                .map(|&atv_id| db.associated_ty_value(atv_id))
-                .for_each(|atv| atv.to_program_clauses(builder));
-        }
+    for &impl_id in program.impl_data.keys() {
+        program_clauses.extend(program_clauses_for_item(db, ItemId::Impl(impl_id)));
     }
 
     Ok(Arc::new(ProgramEnvironment::new(program_clauses)))
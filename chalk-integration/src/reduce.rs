@@ -0,0 +1,445 @@
+//! A reducer of failing test cases: given a program against which solving
+//! a goal panics or gives a wrong answer, `reduce` deletes items, where
+//! clauses and parameters of the program for as long as the failure
+//! persists, leaving a minimal reproduction of it.
+//!
+//! Deletions are made on the AST, one at a time. A deletion may leave a
+//! program that does not lower -- deleting a parameter that is still used,
+//! say -- but such a program does not fail the same way, and so the
+//! deletion is not kept.
+
+use crate::db::ChalkDatabase;
+use crate::interner::ChalkIr;
+use crate::query::LoweringDatabase;
+use crate::tls;
+use chalk_parse::ast::*;
+use chalk_solve::ext::*;
+use chalk_solve::{RustIrDatabase, SolverChoice};
+use std::panic::{self, AssertUnwindSafe};
+
+/// A way in which solving a goal against a program can fail.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Failure {
+    /// Lowering or checking the program, or solving the goal, panics.
+    Panic,
+
+    /// The goal is solved, but its solution, as the REPL prints it, does
+    /// not start with this text.
+    WrongSolution(String),
+}
+
+impl Failure {
+    /// Whether solving `goal` against `program` fails this way.
+    pub fn occurs(&self, program: &str, goal: &str, solver_choice: SolverChoice) -> bool {
+        let solution = panic::catch_unwind(AssertUnwindSafe(|| {
+            let db = ChalkDatabase::with(program, solver_choice);
+            let program = db.checked_program().ok()?;
+            tls::set_current_program(&program, || {
+                let goal = db.parse_and_lower_goal(goal).ok()?;
+                Some(match db.solve(&goal.into_peeled_goal(db.interner())) {
+                    Some(solution) => solution.display(&ChalkIr).to_string(),
+                    None => "No possible solution".to_string(),
+                })
+            })
+        }));
+        match (self, solution) {
+            (Failure::Panic, Err(_)) => true,
+            (Failure::WrongSolution(expected), Ok(Some(solution))) => {
+                !solution.starts_with(&expected[..])
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Reduces `program`, whose goal fails as `failure` says, with
+/// `solver_choice`; see `reduce`.
+pub fn reduce_failure(
+    program: &Program,
+    goal: &str,
+    failure: &Failure,
+    solver_choice: SolverChoice,
+) -> Program {
+    reduce(program, |program| {
+        let text = chalk_parse::print_program(program);
+        failure.occurs(&text, goal, solver_choice)
+    })
+}
+
+/// Deletes parts of `program` for as long as `fails` holds of what is
+/// left, and returns what is left, from which no single part can be
+/// deleted. `fails` is expected to hold of `program` itself.
+pub fn reduce(program: &Program, mut fails: impl FnMut(&Program) -> bool) -> Program {
+    let mut program = program.clone();
+    // The deletions are tried in order, resuming after each one that is
+    // kept, until a pass over all of them keeps none.
+    let mut start = 0;
+    loop {
+        let kept = candidates(&program)
+            .into_iter()
+            .enumerate()
+            .skip(start)
+            .find(|(_, candidate)| fails(candidate));
+        match kept {
+            Some((index, candidate)) => {
+                program = candidate;
+                start = index;
+            }
+            None if start == 0 => return program,
+            None => start = 0,
+        }
+    }
+}
+
+/// The programs that each delete one part of `program`, the largest
+/// parts first.
+fn candidates(program: &Program) -> Vec<Program> {
+    let mut candidates = item_list_candidates(&program.items);
+
+    for item in &program.items {
+        match item {
+            Item::StructDefn(defn) => {
+                for index in 0..defn.parameter_kinds.len() {
+                    candidates.push(delete_parameter(&program.items, &defn.name, index, 0));
+                }
+            }
+            Item::TraitDefn(defn) => {
+                for index in 0..defn.parameter_kinds.len() {
+                    // The first argument of a trait reference is the self
+                    // type.
+                    candidates.push(delete_parameter(&program.items, &defn.name, index, 1));
+                }
+                for assoc_ty in &defn.assoc_ty_defns {
+                    candidates.push(delete_assoc_ty(&program.items, &defn.name, &assoc_ty.name));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    candidates
+        .into_iter()
+        .map(|items| Program { items })
+        .collect()
+}
+
+fn item_list_candidates(items: &[Item]) -> Vec<Vec<Item>> {
+    let mut candidates = deletions(items);
+    for (index, item) in items.iter().enumerate() {
+        for reduced in item_candidates(item) {
+            let mut candidate = items.to_vec();
+            candidate[index] = reduced;
+            candidates.push(candidate);
+        }
+    }
+    candidates
+}
+
+fn item_candidates(item: &Item) -> Vec<Item> {
+    let mut candidates = vec![];
+    match item {
+        Item::StructDefn(defn) => {
+            for where_clauses in deletions(&defn.where_clauses) {
+                let mut defn = defn.clone();
+                defn.where_clauses = where_clauses;
+                candidates.push(Item::StructDefn(defn));
+            }
+            for fields in deletions(&defn.fields) {
+                let mut defn = defn.clone();
+                defn.fields = fields;
+                candidates.push(Item::StructDefn(defn));
+            }
+        }
+        Item::TraitDefn(defn) => {
+            for where_clauses in deletions(&defn.where_clauses) {
+                let mut defn = defn.clone();
+                defn.where_clauses = where_clauses;
+                candidates.push(Item::TraitDefn(defn));
+            }
+            for methods in deletions(&defn.methods) {
+                let mut defn = defn.clone();
+                defn.methods = methods;
+                candidates.push(Item::TraitDefn(defn));
+            }
+            for (index, assoc_ty) in defn.assoc_ty_defns.iter().enumerate() {
+                for bounds in deletions(&assoc_ty.bounds) {
+                    let mut defn = defn.clone();
+                    defn.assoc_ty_defns[index].bounds = bounds;
+                    candidates.push(Item::TraitDefn(defn));
+                }
+                for where_clauses in deletions(&assoc_ty.where_clauses) {
+                    let mut defn = defn.clone();
+                    defn.assoc_ty_defns[index].where_clauses = where_clauses;
+                    candidates.push(Item::TraitDefn(defn));
+                }
+            }
+        }
+        Item::Impl(imp) => {
+            for where_clauses in deletions(&imp.where_clauses) {
+                let mut imp = imp.clone();
+                imp.where_clauses = where_clauses;
+                candidates.push(Item::Impl(imp));
+            }
+            for parameter_kinds in deletions(&imp.parameter_kinds) {
+                let mut imp = imp.clone();
+                imp.parameter_kinds = parameter_kinds;
+                candidates.push(Item::Impl(imp));
+            }
+            for methods in deletions(&imp.methods) {
+                let mut imp = imp.clone();
+                imp.methods = methods;
+                candidates.push(Item::Impl(imp));
+            }
+        }
+        Item::InherentImpl(imp) => {
+            for where_clauses in deletions(&imp.where_clauses) {
+                let mut imp = imp.clone();
+                imp.where_clauses = where_clauses;
+                candidates.push(Item::InherentImpl(imp));
+            }
+            for parameter_kinds in deletions(&imp.parameter_kinds) {
+                let mut imp = imp.clone();
+                imp.parameter_kinds = parameter_kinds;
+                candidates.push(Item::InherentImpl(imp));
+            }
+            for methods in deletions(&imp.methods) {
+                let mut imp = imp.clone();
+                imp.methods = methods;
+                candidates.push(Item::InherentImpl(imp));
+            }
+        }
+        Item::Clause(clause) => {
+            for conditions in deletions(&clause.conditions) {
+                let mut clause = clause.clone();
+                clause.conditions = conditions;
+                candidates.push(Item::Clause(clause));
+            }
+        }
+        Item::ModDefn(defn) => {
+            for items in item_list_candidates(&defn.items) {
+                candidates.push(Item::ModDefn(ModDefn {
+                    name: defn.name.clone(),
+                    items,
+                    span: defn.span,
+                }));
+            }
+        }
+        Item::OpaqueTyDefn(_)
+        | Item::HiddenTyDefn(_)
+        | Item::PredicateDefn(_)
+        | Item::GoalDefn(_) => {}
+    }
+    candidates
+}
+
+/// The lists that each leave out one of `elements`.
+fn deletions<T: Clone>(elements: &[T]) -> Vec<Vec<T>> {
+    (0..elements.len())
+        .map(|index| {
+            let mut elements = elements.to_vec();
+            elements.remove(index);
+            elements
+        })
+        .collect()
+}
+
+/// Deletes the parameter `index` of the struct or trait `name`, and the
+/// argument for it wherever `name` is applied, which is at `index +
+/// offset` in its arguments.
+fn delete_parameter(items: &[Item], name: &Identifier, index: usize, offset: usize) -> Vec<Item> {
+    let mut items = items.to_vec();
+    for item in &mut items {
+        match item {
+            Item::StructDefn(defn) if defn.name.str == name.str => {
+                defn.parameter_kinds.remove(index);
+            }
+            Item::TraitDefn(defn) if defn.name.str == name.str => {
+                defn.parameter_kinds.remove(index);
+            }
+            _ => {}
+        }
+    }
+    let mut deleter = ArgumentDeleter {
+        name,
+        index: index + offset,
+    };
+    for item in &mut items {
+        deleter.item(item);
+    }
+    items
+}
+
+/// Deletes the associated type `assoc_ty` of the trait `trait_name`, and
+/// its values in the impls of the trait.
+fn delete_assoc_ty(items: &[Item], trait_name: &Identifier, assoc_ty: &Identifier) -> Vec<Item> {
+    let mut items = items.to_vec();
+    for item in &mut items {
+        match item {
+            Item::TraitDefn(defn) if defn.name.str == trait_name.str => {
+                defn.assoc_ty_defns
+                    .retain(|defn| defn.name.str != assoc_ty.str);
+            }
+            Item::Impl(imp) if imp.trait_ref.trait_name.str == trait_name.str => {
+                imp.assoc_ty_values
+                    .retain(|value| value.name.str != assoc_ty.str);
+            }
+            _ => {}
+        }
+    }
+    items
+}
+
+/// Deletes the argument at `index` wherever the struct or trait `name` is
+/// applied in the types, trait references and bounds of items. Goals and
+/// custom clauses are left as they are.
+struct ArgumentDeleter<'a> {
+    name: &'a Identifier,
+    index: usize,
+}
+
+impl ArgumentDeleter<'_> {
+    fn item(&mut self, item: &mut Item) {
+        match item {
+            Item::StructDefn(defn) => {
+                self.where_clauses(&mut defn.where_clauses);
+                for field in &mut defn.fields {
+                    self.ty(&mut field.ty);
+                }
+            }
+            Item::TraitDefn(defn) => {
+                self.where_clauses(&mut defn.where_clauses);
+                for assoc_ty in &mut defn.assoc_ty_defns {
+                    self.inline_bounds(&mut assoc_ty.bounds);
+                    self.where_clauses(&mut assoc_ty.where_clauses);
+                }
+                for method in &mut defn.methods {
+                    self.method(method);
+                }
+            }
+            Item::Impl(imp) => {
+                self.trait_ref(&mut imp.trait_ref);
+                self.where_clauses(&mut imp.where_clauses);
+                for value in &mut imp.assoc_ty_values {
+                    self.ty(&mut value.value);
+                }
+                for method in &mut imp.methods {
+                    self.method(method);
+                }
+            }
+            Item::InherentImpl(imp) => {
+                self.ty(&mut imp.self_ty);
+                self.where_clauses(&mut imp.where_clauses);
+                for method in &mut imp.methods {
+                    self.method(method);
+                }
+            }
+            Item::OpaqueTyDefn(defn) => {
+                if let Some(ty) = &mut defn.ty {
+                    self.ty(ty);
+                }
+                self.inline_bounds(&mut defn.bounds);
+            }
+            Item::HiddenTyDefn(defn) => self.ty(&mut defn.ty),
+            Item::ModDefn(defn) => {
+                for item in &mut defn.items {
+                    self.item(item);
+                }
+            }
+            Item::Clause(_) | Item::PredicateDefn(_) | Item::GoalDefn(_) => {}
+        }
+    }
+
+    fn method(&mut self, method: &mut MethodDefn) {
+        for input in &mut method.inputs {
+            self.ty(input);
+        }
+        if let Some(output) = &mut method.output {
+            self.ty(output);
+        }
+        self.where_clauses(&mut method.where_clauses);
+    }
+
+    fn where_clauses(&mut self, where_clauses: &mut [QuantifiedWhereClause]) {
+        for where_clause in where_clauses {
+            match &mut where_clause.where_clause {
+                WhereClause::Implemented { trait_ref }
+                | WhereClause::ConstImplemented { trait_ref } => self.trait_ref(trait_ref),
+                WhereClause::ProjectionEq { projection, ty } => {
+                    self.projection(projection);
+                    self.ty(ty);
+                }
+            }
+        }
+    }
+
+    fn inline_bounds(&mut self, bounds: &mut [QuantifiedInlineBound]) {
+        for bound in bounds {
+            match &mut bound.bound {
+                InlineBound::TraitBound(bound) => self.trait_bound(bound),
+                InlineBound::AliasEqBound(bound) => {
+                    self.trait_bound(&mut bound.trait_bound);
+                    self.parameters(&mut bound.args);
+                    self.ty(&mut bound.value);
+                }
+            }
+        }
+    }
+
+    fn trait_bound(&mut self, bound: &mut TraitBound) {
+        // The arguments of a bound leave out the self type.
+        if bound.trait_name.str == self.name.str
+            && self.index > 0
+            && self.index - 1 < bound.args_no_self.len()
+        {
+            bound.args_no_self.remove(self.index - 1);
+        }
+        self.parameters(&mut bound.args_no_self);
+    }
+
+    fn trait_ref(&mut self, trait_ref: &mut TraitRef) {
+        if trait_ref.trait_name.str == self.name.str && self.index < trait_ref.args.len() {
+            trait_ref.args.remove(self.index);
+        }
+        self.parameters(&mut trait_ref.args);
+    }
+
+    fn projection(&mut self, projection: &mut ProjectionTy) {
+        self.trait_ref(&mut projection.trait_ref);
+        self.parameters(&mut projection.args);
+    }
+
+    fn parameters(&mut self, parameters: &mut [Parameter]) {
+        for parameter in parameters {
+            if let Parameter::Ty(ty) = parameter {
+                self.ty(ty);
+            }
+        }
+    }
+
+    fn ty(&mut self, ty: &mut Ty) {
+        match ty {
+            Ty::Apply { name, args, span } => {
+                if name.str == self.name.str && self.index < args.len() {
+                    args.remove(self.index);
+                }
+                self.parameters(args);
+                // A struct with no arguments is named on its own.
+                if args.is_empty() {
+                    *ty = Ty::Id {
+                        name: name.clone(),
+                        span: *span,
+                    };
+                }
+            }
+            Ty::Dyn { bounds, .. } => self.inline_bounds(bounds),
+            Ty::Projection { proj, .. } => self.projection(proj),
+            Ty::ForAll { ty, .. } | Ty::Ref { ty, .. } => self.ty(ty),
+            Ty::Tuple { types, .. } => {
+                for ty in types {
+                    self.ty(ty);
+                }
+            }
+            Ty::Id { .. } | Ty::Scalar { .. } => {}
+        }
+    }
+}
@@ -0,0 +1,217 @@
+//! Machine-readable reports of what chalk found, for tools that would
+//! rather not scrape the text that the REPL prints. The reports are
+//! serialized with serde, as JSON by their `to_json` methods:
+//!
+//! ```notrust
+//! {"goal":"exists<T> { T: Clone }","solution":{"kind":"unique",
+//!  "substitution":[{"variable":"?0","value":"Foo"}],"constraints":[]}}
+//! {"goal":"Foo: Qux","error":"invalid type name `Qux`"}
+//! ```
+//!
+//! Types and lifetimes within a report are rendered as in the `Debug`
+//! output of the IR, which names items only while the program they are
+//! from is the current one; see `tls::set_current_program`.
+
+use crate::batch::{run_batch, Mismatch};
+use crate::db::ChalkDatabase;
+use crate::interner::ChalkIr;
+use crate::query::LoweringDatabase;
+use crate::tls;
+use chalk_ir::{Canonical, ConstrainedSubst, Substitution};
+use chalk_solve::ext::*;
+use chalk_solve::{Guidance, RustIrDatabase, Solution, SolverChoice};
+use serde::{Deserialize, Serialize};
+
+/// The value a solution gives to one of the existential variables of a
+/// goal, like `?0 := Foo`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Binding {
+    pub variable: String,
+    pub value: String,
+}
+
+/// A solution of a goal, or the lack of one.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SolutionReport {
+    /// The goal holds, with these values for its existential variables,
+    /// if these lifetime constraints hold.
+    Unique {
+        substitution: Vec<Binding>,
+        constraints: Vec<String>,
+    },
+
+    /// The goal may hold. The guidance is `definite`, `suggested` or
+    /// `unknown`; the substitution is empty if it is `unknown`.
+    Ambiguous {
+        guidance: String,
+        substitution: Vec<Binding>,
+    },
+
+    /// The goal does not hold.
+    NoSolution,
+}
+
+impl SolutionReport {
+    pub fn new(solution: &Option<Solution<ChalkIr>>) -> SolutionReport {
+        match solution {
+            Some(Solution::Unique(Canonical {
+                value: ConstrainedSubst { subst, constraints },
+                ..
+            })) => SolutionReport::Unique {
+                substitution: bindings(subst),
+                constraints: constraints
+                    .iter()
+                    .map(|constraint| format!("{:?}", constraint.goal))
+                    .collect(),
+            },
+            Some(Solution::Ambig(guidance)) => {
+                let (guidance, substitution) = match guidance {
+                    Guidance::Definite(subst) => ("definite", bindings(&subst.value)),
+                    Guidance::Suggested(subst) => ("suggested", bindings(&subst.value)),
+                    Guidance::Unknown => ("unknown", vec![]),
+                };
+                SolutionReport::Ambiguous {
+                    guidance: guidance.to_string(),
+                    substitution,
+                }
+            }
+            None => SolutionReport::NoSolution,
+        }
+    }
+}
+
+fn bindings(subst: &Substitution<ChalkIr>) -> Vec<Binding> {
+    subst
+        .iter(&ChalkIr)
+        .enumerate()
+        .map(|(index, value)| Binding {
+            variable: format!("?{}", index),
+            value: format!("{:?}", value),
+        })
+        .collect()
+}
+
+/// What came of solving a goal: its solution, or the error that kept it
+/// from being solved.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Solution(SolutionReport),
+    Error(String),
+}
+
+/// The report of solving a goal.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GoalReport {
+    /// The goal, as it was written.
+    pub goal: String,
+
+    #[serde(flatten)]
+    pub outcome: Outcome,
+}
+
+impl GoalReport {
+    /// Parses, lowers and solves `goal` against the program of `db`.
+    pub fn solve(db: &ChalkDatabase, goal: &str) -> GoalReport {
+        let outcome = match db.checked_program() {
+            Ok(program) => {
+                tls::set_current_program(&program, || match db.parse_and_lower_goal(goal) {
+                    Ok(lowered) => {
+                        let solution = db.solve(&lowered.into_peeled_goal(db.interner()));
+                        Outcome::Solution(SolutionReport::new(&solution))
+                    }
+                    Err(error) => Outcome::Error(error.to_string()),
+                })
+            }
+            Err(error) => Outcome::Error(error.to_string()),
+        };
+        GoalReport {
+            goal: goal.to_string(),
+            outcome,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+/// A goal of a batch file whose solution did not meet its expectation.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MismatchReport {
+    pub line: usize,
+    pub goal: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl From<&Mismatch> for MismatchReport {
+    fn from(mismatch: &Mismatch) -> MismatchReport {
+        MismatchReport {
+            line: mismatch.goal.line,
+            goal: mismatch.goal.text.clone(),
+            expected: mismatch.goal.expected.to_string(),
+            actual: mismatch.actual.clone(),
+        }
+    }
+}
+
+/// The report of checking a batch file; see `batch::run_batch`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CheckReport {
+    Checked {
+        file: String,
+        goals: usize,
+        passed: usize,
+        failed: usize,
+        mismatches: Vec<MismatchReport>,
+    },
+
+    /// The file could not be read, parsed or lowered. The line and
+    /// column of the error are given when it is about a particular place
+    /// in the file.
+    Error {
+        file: String,
+        error: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        line: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        column: Option<usize>,
+    },
+}
+
+impl CheckReport {
+    /// Checks the batch file `text`, read from `file`.
+    pub fn check(file: &str, text: &str, solver_choice: SolverChoice) -> CheckReport {
+        match run_batch(text, solver_choice) {
+            Ok(report) => CheckReport::Checked {
+                file: file.to_string(),
+                goals: report.goals,
+                passed: report.goals - report.mismatches.len(),
+                failed: report.mismatches.len(),
+                mismatches: report.mismatches.iter().map(MismatchReport::from).collect(),
+            },
+            Err(error) => CheckReport::Error {
+                file: file.to_string(),
+                error: error.to_string(),
+                line: error.position().map(|(line, _)| line),
+                column: error.position().map(|(_, column)| column),
+            },
+        }
+    }
+
+    pub fn error(file: &str, error: &dyn std::fmt::Display) -> CheckReport {
+        CheckReport::Error {
+            file: file.to_string(),
+            error: error.to_string(),
+            line: None,
+            column: None,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
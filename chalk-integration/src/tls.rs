@@ -1,9 +1,9 @@
 use crate::interner::ChalkIr;
 use chalk_ir::{
     debug::SeparatorTraitRef, AliasTy, ApplicationTy, AssocTypeId, CanonicalVarKinds, Goal, Goals,
-    Lifetime, OpaqueTy, OpaqueTyId, Parameter, ParameterKinds, ProgramClause,
-    ProgramClauseImplication, ProgramClauses, ProjectionTy, QuantifiedWhereClauses, StructId,
-    Substitution, TraitId, Ty,
+    ImplId, Lifetime, OpaqueTy, OpaqueTyId, Parameter, ParameterKinds, PredicateId, PredicateRef,
+    ProgramClause, ProgramClauseImplication, ProgramClauses, ProjectionTy, QuantifiedWhereClauses,
+    StructId, Substitution, TraitId, Ty,
 };
 use std::cell::RefCell;
 use std::fmt;
@@ -32,12 +32,24 @@ pub trait DebugContext {
         fmt: &mut fmt::Formatter<'_>,
     ) -> Result<(), fmt::Error>;
 
+    fn debug_impl_id(
+        &self,
+        id: ImplId<ChalkIr>,
+        fmt: &mut fmt::Formatter<'_>,
+    ) -> Result<(), fmt::Error>;
+
     fn debug_opaque_ty_id(
         &self,
         id: OpaqueTyId<ChalkIr>,
         fmt: &mut fmt::Formatter<'_>,
     ) -> Result<(), fmt::Error>;
 
+    fn debug_predicate_id(
+        &self,
+        id: PredicateId<ChalkIr>,
+        fmt: &mut fmt::Formatter<'_>,
+    ) -> Result<(), fmt::Error>;
+
     fn debug_alias(
         &self,
         alias: &AliasTy<ChalkIr>,
@@ -124,6 +136,12 @@ pub trait DebugContext {
         fmt: &mut fmt::Formatter<'_>,
     ) -> Result<(), fmt::Error>;
 
+    fn debug_predicate_ref(
+        &self,
+        predicate_ref: &PredicateRef<ChalkIr>,
+        fmt: &mut fmt::Formatter<'_>,
+    ) -> Result<(), fmt::Error>;
+
     fn debug_substitution(
         &self,
         substitution: &Substitution<ChalkIr>,
@@ -156,9 +174,10 @@ where
 {
     let p: Arc<dyn DebugContext> = p.clone();
     PROGRAM.with(|prog_cell| {
-        *prog_cell.borrow_mut() = Some(p);
+        // Restore the enclosing program afterwards, so that calls can nest.
+        let old = prog_cell.borrow_mut().replace(p);
         let r = op();
-        *prog_cell.borrow_mut() = None;
+        *prog_cell.borrow_mut() = old;
         r
     })
 }
@@ -0,0 +1,292 @@
+//! Checks that a lowered program is well-formed as IR: that its bound
+//! variables refer to binders that are in scope, and that its ids refer
+//! to items of the program, applied to as many parameters as they have.
+//! Ill-formed IR is a bug in whatever produced it, be it lowering or an
+//! embedder, and would otherwise make the solvers panic or answer
+//! wrongly far from its cause.
+
+use crate::interner::ChalkIr;
+use crate::program::Program;
+use crate::RawId;
+use chalk_ir::interner::HasInterner;
+use chalk_ir::visit::{SuperVisit, Visit, Visitor};
+use chalk_ir::{
+    AliasTy, BoundVar, DebruijnIndex, Substitution, TraitRef, Ty, TyData, TypeName, WhereClause,
+};
+use std::fmt;
+
+/// A way in which a program is ill-formed. `item` describes the item
+/// in which the problem was found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InvalidIr {
+    /// A bound variable that refers to a binder outside of its item.
+    EscapingBoundVar { item: String, var: BoundVar },
+
+    /// A bound variable that refers to a parameter its item does not
+    /// have.
+    BoundVarOutOfRange {
+        item: String,
+        var: BoundVar,
+        num_params: usize,
+    },
+
+    /// An id that is not the id of an item of the right sort.
+    UnknownId { item: String, id: RawId },
+
+    /// An item applied to the wrong number of parameters.
+    WrongNumberOfParameters {
+        item: String,
+        id: RawId,
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl fmt::Display for InvalidIr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidIr::EscapingBoundVar { item, var } => {
+                write!(f, "in {}: bound variable {:?} escapes the item", item, var)
+            }
+            InvalidIr::BoundVarOutOfRange {
+                item,
+                var,
+                num_params,
+            } => write!(
+                f,
+                "in {}: bound variable {:?} refers past the {} parameters of the item",
+                item, var, num_params
+            ),
+            InvalidIr::UnknownId { item, id } => write!(f, "in {}: unknown id {:?}", item, id),
+            InvalidIr::WrongNumberOfParameters {
+                item,
+                id,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "in {}: {:?} applied to {} parameters, but it has {}",
+                item, id, actual, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for InvalidIr {}
+
+impl Program {
+    /// Checks that this program is well-formed as IR, returning every
+    /// problem found. Lowering checks this in debug builds.
+    pub fn validate(&self) -> Vec<InvalidIr> {
+        let mut validator = Validator {
+            program: self,
+            item: String::new(),
+            num_params: None,
+            errors: vec![],
+        };
+
+        for (id, datum) in &self.struct_data {
+            validator.item(self.describe(id.0), &datum.binders);
+        }
+        for (id, datum) in &self.trait_data {
+            validator.item(self.describe(id.0), &datum.binders);
+            for method in &datum.methods {
+                validator.item(self.describe(id.0), &method.binders);
+            }
+        }
+        for (id, datum) in &self.impl_data {
+            let item = format!("impl {:?}", id.0);
+            validator.item(item.clone(), &datum.binders);
+            let trait_ref = &datum.binders.skip_binders().trait_ref;
+            validator.trait_ref(trait_ref);
+            for method in &datum.methods {
+                validator.item(item.clone(), &method.binders);
+            }
+        }
+        for (id, datum) in &self.inherent_impl_data {
+            let item = format!("impl {:?}", id.0);
+            validator.item(item.clone(), &datum.binders);
+            for method in &datum.methods {
+                validator.item(item.clone(), &method.binders);
+            }
+        }
+        for (id, datum) in &self.associated_ty_data {
+            validator.item(self.describe(id.0), &datum.binders);
+        }
+        for (id, value) in &self.associated_ty_values {
+            validator.item(format!("value {:?}", id.0), &value.value);
+        }
+        for (id, datum) in &self.opaque_ty_data {
+            validator.item(self.describe(id.0), &datum.bound);
+        }
+        for clause in self.custom_clauses.iter().chain(&self.method_clauses) {
+            validator.item = "custom clause".to_string();
+            validator.num_params = None;
+            clause.visit_with(&mut validator, DebruijnIndex::INNERMOST);
+        }
+
+        validator.errors
+    }
+
+    fn describe(&self, id: RawId) -> String {
+        match self.item_name(id) {
+            Some(name) => format!("`{}`", name),
+            None => format!("{:?}", id),
+        }
+    }
+}
+
+struct Validator<'p> {
+    program: &'p Program,
+
+    /// The item being checked.
+    item: String,
+
+    /// The number of parameters of the item being checked, if it has
+    /// binders.
+    num_params: Option<usize>,
+
+    errors: Vec<InvalidIr>,
+}
+
+impl Validator<'_> {
+    /// Checks the item `item`, whose parameters are bound by `binders`.
+    fn item<T>(&mut self, item: String, binders: &chalk_ir::Binders<T>)
+    where
+        T: HasInterner<Interner = ChalkIr> + Visit<ChalkIr>,
+    {
+        self.item = item;
+        self.num_params = Some(binders.len(&ChalkIr));
+        binders.visit_with(self, DebruijnIndex::INNERMOST);
+    }
+
+    fn bound_var(&mut self, var: BoundVar, outer_binder: DebruijnIndex) {
+        if !var.debruijn.within(outer_binder) {
+            self.errors.push(InvalidIr::EscapingBoundVar {
+                item: self.item.clone(),
+                var,
+            });
+            return;
+        }
+
+        // The binder of the item is the outermost one; the parameters
+        // of the binders within it are not checked.
+        let is_item_binder = var.debruijn.depth() + 1 == outer_binder.depth();
+        match self.num_params {
+            Some(num_params) if is_item_binder && var.index >= num_params => {
+                self.errors.push(InvalidIr::BoundVarOutOfRange {
+                    item: self.item.clone(),
+                    var,
+                    num_params,
+                })
+            }
+            _ => {}
+        }
+    }
+
+    fn arity(&mut self, id: RawId, expected: Option<usize>, substitution: &Substitution<ChalkIr>) {
+        let actual = substitution.len(&ChalkIr);
+        match expected {
+            None => self.errors.push(InvalidIr::UnknownId {
+                item: self.item.clone(),
+                id,
+            }),
+            Some(expected) if expected != actual => {
+                self.errors.push(InvalidIr::WrongNumberOfParameters {
+                    item: self.item.clone(),
+                    id,
+                    expected,
+                    actual,
+                })
+            }
+            Some(_) => {}
+        }
+    }
+
+    fn trait_ref(&mut self, trait_ref: &TraitRef<ChalkIr>) {
+        let expected = self
+            .program
+            .trait_data
+            .get(&trait_ref.trait_id)
+            .map(|datum| datum.binders.len(&ChalkIr));
+        self.arity(trait_ref.trait_id.0, expected, &trait_ref.substitution);
+    }
+
+    fn alias(&mut self, alias: &AliasTy<ChalkIr>) {
+        match alias {
+            AliasTy::Projection(projection) => {
+                let expected = self
+                    .program
+                    .associated_ty_data
+                    .get(&projection.associated_ty_id)
+                    .map(|datum| datum.binders.len(&ChalkIr));
+                self.arity(
+                    projection.associated_ty_id.0,
+                    expected,
+                    &projection.substitution,
+                );
+            }
+            AliasTy::Opaque(opaque) => {
+                let expected = self
+                    .program
+                    .opaque_ty_kinds
+                    .get(&opaque.opaque_ty_id)
+                    .map(|kind| kind.binders.len(&ChalkIr));
+                self.arity(opaque.opaque_ty_id.0, expected, &opaque.substitution);
+            }
+        }
+    }
+}
+
+impl<'i> Visitor<'i, ChalkIr> for Validator<'_> {
+    type Result = ();
+
+    fn as_dyn(&mut self) -> &mut dyn Visitor<'i, ChalkIr, Result = ()> {
+        self
+    }
+
+    fn visit_ty(&mut self, ty: &Ty<ChalkIr>, outer_binder: DebruijnIndex) {
+        match ty.data(&ChalkIr) {
+            TyData::BoundVar(var) => return self.bound_var(*var, outer_binder),
+            TyData::Apply(apply) => {
+                if let TypeName::Struct(id) = apply.name {
+                    let expected = self
+                        .program
+                        .struct_kinds
+                        .get(&id)
+                        .map(|kind| kind.binders.len(&ChalkIr));
+                    self.arity(id.0, expected, &apply.substitution);
+                }
+            }
+            TyData::Alias(alias) => self.alias(alias),
+            _ => {}
+        }
+        ty.super_visit_with(self, outer_binder)
+    }
+
+    fn visit_where_clause(
+        &mut self,
+        where_clause: &WhereClause<ChalkIr>,
+        outer_binder: DebruijnIndex,
+    ) {
+        match where_clause {
+            WhereClause::Implemented(trait_ref) | WhereClause::ConstImplemented(trait_ref) => {
+                self.trait_ref(trait_ref)
+            }
+            WhereClause::AliasEq(alias_eq) => self.alias(&alias_eq.alias),
+        }
+        where_clause.super_visit_with(self, outer_binder)
+    }
+
+    fn visit_free_var_lifetime(&mut self, var: BoundVar, outer_binder: DebruijnIndex) {
+        self.bound_var(var, outer_binder)
+    }
+
+    fn visit_bound_var_lifetime(&mut self, var: BoundVar, outer_binder: DebruijnIndex) {
+        self.bound_var(var, outer_binder)
+    }
+
+    fn interner(&self) -> &'i ChalkIr {
+        &ChalkIr
+    }
+}
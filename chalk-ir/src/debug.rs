@@ -21,6 +21,12 @@ impl<I: Interner> Debug for AssocTypeId<I> {
     }
 }
 
+impl<I: Interner> Debug for ImplId<I> {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        I::debug_impl_id(*self, fmt).unwrap_or_else(|| write!(fmt, "ImplId({:?})", self.0))
+    }
+}
+
 impl<I: Interner> Debug for Ty<I> {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
         I::debug_ty(self, fmt).unwrap_or_else(|| write!(fmt, "{:?}", self.interned))
@@ -124,6 +130,19 @@ impl<I: Interner> Debug for OpaqueTyId<I> {
     }
 }
 
+impl<I: Interner> Debug for PredicateId<I> {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        I::debug_predicate_id(*self, fmt)
+            .unwrap_or_else(|| write!(fmt, "PredicateId({:?})", self.0))
+    }
+}
+
+impl<I: Interner> Debug for PredicateRef<I> {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        I::debug_predicate_ref(self, fmt).unwrap_or_else(|| write!(fmt, "PredicateRef(?)"))
+    }
+}
+
 impl Display for UniverseIndex {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
         write!(fmt, "U{}", self.counter)
@@ -143,6 +162,8 @@ impl<I: Interner> Debug for TypeName<I> {
             TypeName::AssociatedType(assoc_ty) => write!(fmt, "{:?}", assoc_ty),
             TypeName::Scalar(scalar) => write!(fmt, "{:?}", scalar),
             TypeName::Tuple(arity) => write!(fmt, "{:?}", arity),
+            TypeName::Ref(Mutability::Not) => write!(fmt, "&"),
+            TypeName::Ref(Mutability::Mut) => write!(fmt, "&mut"),
             TypeName::OpaqueType(opaque_ty) => write!(fmt, "!{:?}", opaque_ty),
             TypeName::Error => write!(fmt, "{{error}}"),
         }
@@ -194,10 +215,10 @@ impl<I: Interner> Debug for Fn<I> {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
         // FIXME -- we should introduce some names or something here
         let Fn {
-            num_binders,
+            binders,
             substitution,
         } = self;
-        write!(fmt, "for<{}> {:?}", num_binders, substitution)
+        write!(fmt, "for{:?} {:?}", binders.debug(), substitution)
     }
 }
 
@@ -207,6 +228,7 @@ impl<I: Interner> Debug for LifetimeData<I> {
             LifetimeData::BoundVar(db) => write!(fmt, "'{:?}", db),
             LifetimeData::InferenceVar(var) => write!(fmt, "'{:?}", var),
             LifetimeData::Placeholder(index) => write!(fmt, "'{:?}", index),
+            LifetimeData::Static => write!(fmt, "'static"),
             LifetimeData::Phantom(..) => unreachable!(),
         }
     }
@@ -271,8 +293,10 @@ impl<I: Interner> Debug for GoalData<I> {
             ),
             GoalData::Implies(ref wc, ref g) => write!(fmt, "if ({:?}) {{ {:?} }}", wc, g),
             GoalData::All(ref goals) => write!(fmt, "all{:?}", goals),
+            GoalData::Any(ref goals) => write!(fmt, "any{:?}", goals),
             GoalData::Not(ref g) => write!(fmt, "not {{ {:?} }}", g),
             GoalData::EqGoal(ref wc) => write!(fmt, "{:?}", wc),
+            GoalData::Constraint(ref c) => write!(fmt, "{:?}", c),
             GoalData::DomainGoal(ref wc) => write!(fmt, "{:?}", wc),
             GoalData::CannotProve(()) => write!(fmt, r"¯\_(ツ)_/¯"),
         }
@@ -383,6 +407,41 @@ impl<I: Interner> ApplicationTy<I> {
     }
 }
 
+pub struct PredicateRefDebug<'a, I: Interner> {
+    predicate_ref: &'a PredicateRef<I>,
+    interner: &'a I,
+}
+
+impl<'a, I: Interner> Debug for PredicateRefDebug<'a, I> {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        let PredicateRefDebug {
+            predicate_ref,
+            interner,
+        } = self;
+        let PredicateRef {
+            predicate_id,
+            substitution,
+        } = predicate_ref;
+        write!(fmt, "{:?}(", predicate_id)?;
+        for (index, value) in substitution.iter(interner).enumerate() {
+            if index > 0 {
+                write!(fmt, ", ")?;
+            }
+            write!(fmt, "{:?}", value)?;
+        }
+        write!(fmt, ")")
+    }
+}
+
+impl<I: Interner> PredicateRef<I> {
+    pub fn debug<'a>(&'a self, interner: &'a I) -> PredicateRefDebug<'a, I> {
+        PredicateRefDebug {
+            predicate_ref: self,
+            interner,
+        }
+    }
+}
+
 pub struct SubstitutionDebug<'a, I: Interner> {
     substitution: &'a Substitution<I>,
     interner: &'a I,
@@ -588,6 +647,9 @@ impl<I: Interner> Debug for WhereClause<I> {
         match self {
             WhereClause::Implemented(tr) => write!(fmt, "Implemented({:?})", tr.with_colon()),
             WhereClause::AliasEq(a) => write!(fmt, "{:?}", a),
+            WhereClause::ConstImplemented(tr) => {
+                write!(fmt, "ConstImplemented({:?})", tr.with_colon())
+            }
         }
     }
 }
@@ -626,6 +688,7 @@ impl<I: Interner> Debug for DomainGoal<I> {
             DomainGoal::Compatible(_) => write!(fmt, "Compatible"),
             DomainGoal::DownstreamType(n) => write!(fmt, "DownstreamType({:?})", n),
             DomainGoal::Reveal(_) => write!(fmt, "Reveal"),
+            DomainGoal::Predicate(p) => write!(fmt, "{:?}", p),
         }
     }
 }
@@ -726,6 +789,7 @@ impl<I: Interner> Debug for Constraint<I> {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
         match self {
             Constraint::Outlives(a, b) => write!(fmt, "{:?}: {:?}", a, b),
+            Constraint::TypeOutlives(ty, lifetime) => write!(fmt, "{:?}: {:?}", ty, lifetime),
         }
     }
 }
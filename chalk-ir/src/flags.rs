@@ -0,0 +1,150 @@
+//! Flags summarizing what a type contains, so that folders and the
+//! solvers can skip the types that contain nothing they would change.
+
+use crate::visit::{Visit, VisitResult, Visitor};
+use crate::{
+    AliasTy, BoundVar, DebruijnIndex, InferenceVar, Interner, PlaceholderIndex, Ty, TyData,
+};
+use std::ops::BitOr;
+
+/// What a type contains. Interners can compute the flags of a type
+/// once, when it is interned; see `Interner::ty_flags`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TypeFlags {
+    bits: u8,
+}
+
+impl TypeFlags {
+    pub const EMPTY: TypeFlags = TypeFlags { bits: 0 };
+
+    /// Contains a type or lifetime inference variable.
+    pub const HAS_INFERENCE_VARS: TypeFlags = TypeFlags { bits: 1 << 0 };
+
+    /// Contains a bound type or lifetime variable, whether it is bound
+    /// within the type (as in `dyn for<'a> Foo<'a>`) or free.
+    pub const HAS_BOUND_VARS: TypeFlags = TypeFlags { bits: 1 << 1 };
+
+    /// Contains a placeholder type or lifetime.
+    pub const HAS_PLACEHOLDERS: TypeFlags = TypeFlags { bits: 1 << 2 };
+
+    /// Contains an associated type projection.
+    pub const HAS_PROJECTIONS: TypeFlags = TypeFlags { bits: 1 << 3 };
+
+    /// True if all of the flags in `other` are set.
+    pub fn contains(self, other: TypeFlags) -> bool {
+        self.bits & other.bits == other.bits
+    }
+
+    /// True if any of the flags in `other` is set.
+    pub fn intersects(self, other: TypeFlags) -> bool {
+        self.bits & other.bits != 0
+    }
+}
+
+impl BitOr for TypeFlags {
+    type Output = TypeFlags;
+
+    fn bitor(self, other: TypeFlags) -> TypeFlags {
+        TypeFlags {
+            bits: self.bits | other.bits,
+        }
+    }
+}
+
+impl VisitResult for TypeFlags {
+    fn new() -> Self {
+        TypeFlags::EMPTY
+    }
+
+    fn return_early(&self) -> bool {
+        false
+    }
+
+    fn combine(self, other: Self) -> Self {
+        self | other
+    }
+}
+
+impl<I: Interner> TyData<I> {
+    /// Computes the flags of this type from those of the types within
+    /// it, as given by `Interner::ty_flags`.
+    pub fn compute_flags(&self, interner: &I) -> TypeFlags {
+        let visitor = &mut FlagsVisitor { interner };
+        let flags = match self {
+            TyData::Alias(AliasTy::Projection(_)) => TypeFlags::HAS_PROJECTIONS,
+            _ => TypeFlags::EMPTY,
+        };
+        let contents = match self {
+            TyData::Apply(apply) => apply.visit_with(visitor, DebruijnIndex::INNERMOST),
+            TyData::Dyn(dyn_ty) => dyn_ty.visit_with(visitor, DebruijnIndex::INNERMOST),
+            TyData::Alias(alias) => alias.visit_with(visitor, DebruijnIndex::INNERMOST),
+            TyData::Function(fun) => fun.visit_with(visitor, DebruijnIndex::INNERMOST),
+            TyData::Placeholder(_) => TypeFlags::HAS_PLACEHOLDERS,
+            TyData::BoundVar(_) => TypeFlags::HAS_BOUND_VARS,
+            TyData::InferenceVar(_) => TypeFlags::HAS_INFERENCE_VARS,
+        };
+        flags | contents
+    }
+}
+
+impl<I: Interner> Ty<I> {
+    /// What this type contains.
+    pub fn flags(&self, interner: &I) -> TypeFlags {
+        interner.ty_flags(self.interned())
+    }
+}
+
+/// Combines the flags of the types and lifetimes within a type. The
+/// flags of the types come from the interner, rather than visiting
+/// them.
+struct FlagsVisitor<'i, I: Interner> {
+    interner: &'i I,
+}
+
+impl<'i, I: Interner> Visitor<'i, I> for FlagsVisitor<'i, I> {
+    type Result = TypeFlags;
+
+    fn as_dyn(&mut self) -> &mut dyn Visitor<'i, I, Result = Self::Result> {
+        self
+    }
+
+    fn visit_ty(&mut self, ty: &Ty<I>, _outer_binder: DebruijnIndex) -> TypeFlags {
+        ty.flags(self.interner)
+    }
+
+    fn visit_free_var_lifetime(
+        &mut self,
+        _bound_var: BoundVar,
+        _outer_binder: DebruijnIndex,
+    ) -> TypeFlags {
+        TypeFlags::HAS_BOUND_VARS
+    }
+
+    fn visit_bound_var_lifetime(
+        &mut self,
+        _bound_var: BoundVar,
+        _outer_binder: DebruijnIndex,
+    ) -> TypeFlags {
+        TypeFlags::HAS_BOUND_VARS
+    }
+
+    fn visit_free_placeholder_lifetime(
+        &mut self,
+        _universe: PlaceholderIndex,
+        _outer_binder: DebruijnIndex,
+    ) -> TypeFlags {
+        TypeFlags::HAS_PLACEHOLDERS
+    }
+
+    fn visit_inference_lifetime(
+        &mut self,
+        _var: InferenceVar,
+        _outer_binder: DebruijnIndex,
+    ) -> TypeFlags {
+        TypeFlags::HAS_INFERENCE_VARS
+    }
+
+    fn interner(&self) -> &'i I {
+        self.interner
+    }
+}
@@ -18,11 +18,13 @@ impl<I: Interner, TI: TargetInterner<I>> Fold<I, TI> for Fn<I> {
         TI: 'i,
     {
         let Fn {
-            num_binders,
+            binders,
             substitution,
         } = self;
         Ok(Fn {
-            num_binders: *num_binders,
+            binders: ParameterKinds {
+                interned: TI::transfer_parameter_kinds(binders.interned().clone()),
+            },
             substitution: substitution.fold_with(folder, outer_binder.shifted_in())?,
         })
     }
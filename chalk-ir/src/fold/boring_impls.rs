@@ -247,6 +247,7 @@ copy_fold!(UintTy);
 copy_fold!(IntTy);
 copy_fold!(FloatTy);
 copy_fold!(Scalar);
+copy_fold!(Mutability);
 copy_fold!(ClausePriority);
 
 #[macro_export]
@@ -276,6 +277,7 @@ id_fold!(StructId);
 id_fold!(TraitId);
 id_fold!(AssocTypeId);
 id_fold!(OpaqueTyId);
+id_fold!(PredicateId);
 
 impl<I: Interner, TI: TargetInterner<I>> SuperFold<I, TI> for ProgramClauseData<I> {
     fn super_fold_with<'i>(
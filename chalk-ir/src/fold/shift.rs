@@ -1,4 +1,5 @@
 use super::Fold;
+use crate::flags::TypeFlags;
 use crate::*;
 
 /// Methods for converting debruijn indices to move values into or out
@@ -74,6 +75,18 @@ impl<'i, I: Interner> Folder<'i, I> for Shifter<'i, I> {
         self
     }
 
+    fn fold_ty(&mut self, ty: &Ty<I>, outer_binder: DebruijnIndex) -> Fallible<Ty<I>> {
+        // Only bound variables are shifted.
+        if ty
+            .flags(self.interner())
+            .intersects(TypeFlags::HAS_BOUND_VARS)
+        {
+            ty.super_fold_with(self.as_dyn(), outer_binder)
+        } else {
+            Ok(ty.clone())
+        }
+    }
+
     fn fold_free_var_ty(
         &mut self,
         bound_var: BoundVar,
@@ -134,6 +147,18 @@ impl<'i, I: Interner> Folder<'i, I> for DownShifter<'i, I> {
         self
     }
 
+    fn fold_ty(&mut self, ty: &Ty<I>, outer_binder: DebruijnIndex) -> Fallible<Ty<I>> {
+        // Only bound variables are shifted.
+        if ty
+            .flags(self.interner())
+            .intersects(TypeFlags::HAS_BOUND_VARS)
+        {
+            ty.super_fold_with(self.as_dyn(), outer_binder)
+        } else {
+            Ok(ty.clone())
+        }
+    }
+
     fn fold_free_var_ty(
         &mut self,
         bound_var: BoundVar,
@@ -1,4 +1,5 @@
 use super::*;
+use crate::flags::TypeFlags;
 use crate::fold::shift::Shift;
 
 pub struct Subst<'s, 'i, I: Interner> {
@@ -28,6 +29,18 @@ impl<'i, I: Interner> Folder<'i, I> for Subst<'_, 'i, I> {
         self
     }
 
+    fn fold_ty(&mut self, ty: &Ty<I>, outer_binder: DebruijnIndex) -> Fallible<Ty<I>> {
+        // Only bound variables are substituted.
+        if ty
+            .flags(self.interner())
+            .intersects(TypeFlags::HAS_BOUND_VARS)
+        {
+            ty.super_fold_with(self.as_dyn(), outer_binder)
+        } else {
+            Ok(ty.clone())
+        }
+    }
+
     fn fold_free_var_ty(
         &mut self,
         bound_var: BoundVar,
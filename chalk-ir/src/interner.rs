@@ -1,3 +1,4 @@
+use crate::flags::TypeFlags;
 use crate::AliasTy;
 use crate::ApplicationTy;
 use crate::AssocTypeId;
@@ -5,6 +6,7 @@ use crate::CanonicalVarKinds;
 use crate::Goal;
 use crate::GoalData;
 use crate::Goals;
+use crate::ImplId;
 use crate::Lifetime;
 use crate::LifetimeData;
 use crate::OpaqueTy;
@@ -13,6 +15,8 @@ use crate::Parameter;
 use crate::ParameterData;
 use crate::ParameterKind;
 use crate::ParameterKinds;
+use crate::PredicateId;
+use crate::PredicateRef;
 use crate::ProgramClause;
 use crate::ProgramClauseData;
 use crate::ProgramClauseImplication;
@@ -29,7 +33,9 @@ use crate::TyData;
 use crate::UniverseIndex;
 use chalk_engine::context::Context;
 use chalk_engine::ExClause;
-use std::fmt::{self, Debug};
+#[cfg(feature = "serde")]
+use serde::{Deserializer, Serializer};
+use std::fmt::{self, Debug, Display};
 use std::hash::Hash;
 use std::marker::PhantomData;
 use std::sync::Arc;
@@ -147,7 +153,7 @@ pub trait Interner: Debug + Copy + Eq + Ord + Hash {
     /// The core "id" type used for struct-ids and the like.
     type DefId: Debug + Copy + Eq + Ord + Hash;
 
-    type Identifier: Debug + Clone + Eq + Hash;
+    type Identifier: Debug + Display + Clone + Eq + Hash;
 
     /// Prints the debug representation of a type-kind-id. To get good
     /// results, this requires inspecting TLS, and is difficult to
@@ -191,6 +197,15 @@ pub trait Interner: Debug + Copy + Eq + Ord + Hash {
         None
     }
 
+    /// Prints the debug representation of an impl-id. To get good
+    /// results, this requires inspecting TLS, and is difficult to
+    /// code without reference to a specific interner (and hence
+    /// fully known types).
+    #[allow(unused_variables)]
+    fn debug_impl_id(impl_id: ImplId<Self>, fmt: &mut fmt::Formatter<'_>) -> Option<fmt::Result> {
+        None
+    }
+
     /// Prints the debug representation of an opaque type. To get good
     /// results, this requires inspecting TLS, and is difficult to
     /// code without reference to a specific type-family (and hence
@@ -206,6 +221,21 @@ pub trait Interner: Debug + Copy + Eq + Ord + Hash {
         None
     }
 
+    /// Prints the debug representation of a predicate id. To get good
+    /// results, this requires inspecting TLS, and is difficult to
+    /// code without reference to a specific interner (and hence
+    /// fully known types).
+    ///
+    /// Returns `None` to fallback to the default debug output (e.g.,
+    /// if no info about current program is available from TLS).
+    #[allow(unused_variables)]
+    fn debug_predicate_id(
+        predicate_id: PredicateId<Self>,
+        fmt: &mut fmt::Formatter<'_>,
+    ) -> Option<fmt::Result> {
+        None
+    }
+
     /// Prints the debug representation of an alias. To get good
     /// results, this requires inspecting TLS, and is difficult to
     /// code without reference to a specific interner (and hence
@@ -419,6 +449,21 @@ pub trait Interner: Debug + Copy + Eq + Ord + Hash {
         None
     }
 
+    /// Prints the debug representation of a PredicateRef. To get good
+    /// results, this requires inspecting TLS, and is difficult to
+    /// code without reference to a specific interner (and hence
+    /// fully known types).
+    ///
+    /// Returns `None` to fallback to the default debug output (e.g.,
+    /// if no info about current program is available from TLS).
+    #[allow(unused_variables)]
+    fn debug_predicate_ref(
+        predicate_ref: &PredicateRef<Self>,
+        fmt: &mut fmt::Formatter<'_>,
+    ) -> Option<fmt::Result> {
+        None
+    }
+
     /// Prints the debug representation of a Substitution. To get good
     /// results, this requires inspecting TLS, and is difficult to
     /// code without reference to a specific interner (and hence
@@ -472,6 +517,13 @@ pub trait Interner: Debug + Copy + Eq + Ord + Hash {
     /// Lookup the `TyData` from an interned type.
     fn ty_data<'a>(&self, ty: &'a Self::InternedType) -> &'a TyData<Self>;
 
+    /// The flags of an interned type. By default, they are computed
+    /// from the data of the type each time; interners can instead
+    /// compute them once, in `intern_ty`.
+    fn ty_flags(&self, ty: &Self::InternedType) -> TypeFlags {
+        self.ty_data(ty).compute_flags(self)
+    }
+
     /// Create an "interned" lifetime from `lifetime`. This is not
     /// normally invoked directly; instead, you invoke
     /// `LifetimeData::intern` (which will ultimately call this
@@ -632,6 +684,53 @@ impl<I: Interner> TargetInterner<I> for I {
     }
 }
 
+/// Declares `SerdeInterner`, with a pair of methods to serialize and
+/// deserialize each of the given interned types.
+#[cfg(feature = "serde")]
+macro_rules! serde_interner {
+    ($($interned:ident => $serialize:ident, $deserialize:ident;)*) => {
+        /// An interner whose interned values can be serialized with serde,
+        /// which makes the IR types that use it serializable. The IR
+        /// types cannot require the interned types to implement
+        /// `Serialize` directly: those usually contain the IR types
+        /// themselves, as a `TyData<I>` does a `Ty<I>`, and rustc
+        /// overflows when proving such recursive bounds.
+        ///
+        /// An interner whose interned types implement `Serialize` and
+        /// `Deserialize` can implement every method by forwarding to them.
+        pub trait SerdeInterner: Interner {
+            $(
+                fn $serialize<S: Serializer>(
+                    value: &Self::$interned,
+                    serializer: S,
+                ) -> Result<S::Ok, S::Error>;
+
+                fn $deserialize<'de, D: Deserializer<'de>>(
+                    deserializer: D,
+                ) -> Result<Self::$interned, D::Error>;
+            )*
+        }
+    };
+}
+
+#[cfg(feature = "serde")]
+serde_interner! {
+    InternedType => serialize_ty, deserialize_ty;
+    InternedLifetime => serialize_lifetime, deserialize_lifetime;
+    InternedParameter => serialize_parameter, deserialize_parameter;
+    InternedGoal => serialize_goal, deserialize_goal;
+    InternedGoals => serialize_goals, deserialize_goals;
+    InternedSubstitution => serialize_substitution, deserialize_substitution;
+    InternedProgramClause => serialize_program_clause, deserialize_program_clause;
+    InternedProgramClauses => serialize_program_clauses, deserialize_program_clauses;
+    InternedQuantifiedWhereClauses
+        => serialize_quantified_where_clauses, deserialize_quantified_where_clauses;
+    InternedParameterKinds => serialize_parameter_kinds, deserialize_parameter_kinds;
+    InternedCanonicalVarKinds => serialize_canonical_var_kinds, deserialize_canonical_var_kinds;
+    DefId => serialize_def_id, deserialize_def_id;
+    Identifier => serialize_identifier, deserialize_identifier;
+}
+
 /// Implemented by types that have an associated interner (which
 /// are virtually all of the types in chalk-ir, for example).
 /// This lets us map from a type like `Ty<I>` to the parameter `I`.
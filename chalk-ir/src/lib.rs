@@ -4,17 +4,21 @@
 extern crate self as chalk_ir;
 
 use crate::cast::{Cast, CastTo};
+use crate::flags::TypeFlags;
 use crate::fold::shift::Shift;
 use crate::fold::{Fold, Folder, Subst, SuperFold};
 use crate::visit::{SuperVisit, Visit, VisitExt, VisitResult, Visitor};
 use chalk_derive::{Fold, HasInterner, SuperVisit, Visit, Zip};
 use chalk_engine::fallible::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::iter;
 use std::marker::PhantomData;
 
 pub use crate::debug::SeparatorTraitRef;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Void {}
 
 macro_rules! impl_debugs {
@@ -41,14 +45,23 @@ pub mod visit;
 pub mod cast;
 
 pub mod interner;
+#[cfg(feature = "serde")]
+use interner::SerdeInterner;
 use interner::{HasInterner, Interner};
 
 pub mod could_match;
 pub mod debug;
+pub mod flags;
+pub mod simplify;
 
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner)]
 /// The set of assumptions we've made so far, and the current number of
 /// universal (forall) quantifiers we're within.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct Environment<I: Interner> {
     pub clauses: ProgramClauses<I>,
 }
@@ -72,6 +85,14 @@ impl<I: Interner> Environment<I> {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "G: Serialize, G::Interner: SerdeInterner",
+        deserialize = "G: Deserialize<'de>, G::Interner: SerdeInterner"
+    ))
+)]
 pub struct InEnvironment<G: HasInterner> {
     pub environment: Environment<G::Interner>,
     pub goal: G,
@@ -102,6 +123,7 @@ impl<G: HasInterner> HasInterner for InEnvironment<G> {
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum IntTy {
     Isize,
     I8,
@@ -112,6 +134,7 @@ pub enum IntTy {
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum UintTy {
     Usize,
     U8,
@@ -122,12 +145,14 @@ pub enum UintTy {
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum FloatTy {
     F32,
     F64,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Scalar {
     Bool,
     Char,
@@ -136,7 +161,19 @@ pub enum Scalar {
     Float(FloatTy),
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Mutability {
+    Mut,
+    Not,
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Fold, Visit)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum TypeName<I: Interner> {
     /// a type like `Vec<T>`
     Struct(StructId<I>),
@@ -150,6 +187,10 @@ pub enum TypeName<I: Interner> {
     /// a tuple of the given arity
     Tuple(usize),
 
+    /// a reference like `&'a T` or `&'a mut T`; the substitution holds
+    /// the lifetime followed by the referent type
+    Ref(Mutability),
+
     /// a placeholder for opaque types like `impl Trait`
     OpaqueType(OpaqueTyId<I>),
 
@@ -170,6 +211,7 @@ impl<I: Interner> HasInterner for TypeName<I> {
 /// `Goal(!U1)` (the quantifier was moved to the environment and replaced with a universe index)
 /// See https://rustc-dev-guide.rust-lang.org/borrow_check/region_inference.html#placeholders-and-universes for more.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UniverseIndex {
     pub counter: usize,
 }
@@ -193,35 +235,152 @@ impl UniverseIndex {
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct StructId<I: Interner>(pub I::DefId);
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
+pub struct StructId<I: Interner>(
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "I::serialize_def_id",
+            deserialize_with = "I::deserialize_def_id"
+        )
+    )]
+    pub I::DefId,
+);
 
 /// The id of a trait definition; could be used to load the trait datum by
 /// invoking the [`trait_datum`] method.
 ///
 /// [`trait_datum`]: ../chalk_solve/trait.RustIrDatabase.html#tymethod.trait_datum
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct TraitId<I: Interner>(pub I::DefId);
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
+pub struct TraitId<I: Interner>(
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "I::serialize_def_id",
+            deserialize_with = "I::deserialize_def_id"
+        )
+    )]
+    pub I::DefId,
+);
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct ImplId<I: Interner>(pub I::DefId);
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
+pub struct ImplId<I: Interner>(
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "I::serialize_def_id",
+            deserialize_with = "I::deserialize_def_id"
+        )
+    )]
+    pub I::DefId,
+);
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct ClauseId<I: Interner>(pub I::DefId);
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
+pub struct ClauseId<I: Interner>(
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "I::serialize_def_id",
+            deserialize_with = "I::deserialize_def_id"
+        )
+    )]
+    pub I::DefId,
+);
 
 /// The id for the associated type member of a trait. The details of the type
 /// can be found by invoking the [`associated_ty_data`] method.
 ///
 /// [`associated_ty_data`]: ../chalk_solve/trait.RustIrDatabase.html#tymethod.associated_ty_data
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct AssocTypeId<I: Interner>(pub I::DefId);
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
+pub struct AssocTypeId<I: Interner>(
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "I::serialize_def_id",
+            deserialize_with = "I::deserialize_def_id"
+        )
+    )]
+    pub I::DefId,
+);
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct OpaqueTyId<I: Interner>(pub I::DefId);
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
+pub struct OpaqueTyId<I: Interner>(
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "I::serialize_def_id",
+            deserialize_with = "I::deserialize_def_id"
+        )
+    )]
+    pub I::DefId,
+);
+
+/// The id of a predicate: either a user-declared one, such as
+/// `predicate Reachable(type, type);`, or the one that the goals `Ty.name()`
+/// about a method `name` lower to. Predicates have no meaning of their own;
+/// they are defined entirely by the program clauses that mention them.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
+pub struct PredicateId<I: Interner>(
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "I::serialize_def_id",
+            deserialize_with = "I::deserialize_def_id"
+        )
+    )]
+    pub I::DefId,
+);
 
-impl_debugs!(ImplId, ClauseId);
+impl_debugs!(ClauseId);
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct Ty<I: Interner> {
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "I::serialize_ty",
+            deserialize_with = "I::deserialize_ty"
+        )
+    )]
     interned: I::InternedType,
 }
 
@@ -295,6 +454,11 @@ impl<I: Interner> Ty<I> {
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum TyData<I: Interner> {
     /// An "application" type is one that applies the set of type
     /// arguments to some base type. For example, `Vec<u32>` would be
@@ -368,6 +532,7 @@ impl<I: Interner> TyData<I> {
 /// they appear in the *innermost* binder enclosing the `...`. The
 /// indices identify the location *within* that binder.
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BoundVar {
     pub debruijn: DebruijnIndex,
     pub index: usize,
@@ -445,6 +610,7 @@ impl BoundVar {
 ///
 /// [de Bruijn index]: https://en.wikipedia.org/wiki/De_Bruijn_index
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DebruijnIndex {
     depth: u32,
 }
@@ -598,11 +764,17 @@ impl DebruijnIndex {
 /// a bound type with debruijn index 1 (i.e., skipping through one
 /// level of binder).
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct DynTy<I: Interner> {
     pub bounds: Binders<QuantifiedWhereClauses<I>>,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct InferenceVar {
     index: u32,
 }
@@ -627,16 +799,33 @@ impl InferenceVar {
     }
 }
 
-/// for<'a...'z> X -- all binders are instantiated at once,
-/// and we use deBruijn indices within `self.ty`
+/// for<'a...'z, T...> X -- all binders are instantiated at once,
+/// and we use deBruijn indices within `self.substitution`
 #[derive(Clone, PartialEq, Eq, Hash, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct Fn<I: Interner> {
-    pub num_binders: usize,
+    pub binders: ParameterKinds<I>,
     pub substitution: Substitution<I>,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct Lifetime<I: Interner> {
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "I::serialize_lifetime",
+            deserialize_with = "I::deserialize_lifetime"
+        )
+    )]
     interned: I::InternedLifetime,
 }
 
@@ -671,17 +860,25 @@ impl<I: Interner> Lifetime<I> {
             LifetimeData::BoundVar(_) => true,
             LifetimeData::InferenceVar(_) => false,
             LifetimeData::Placeholder(_) => false,
+            LifetimeData::Static => false,
             LifetimeData::Phantom(..) => unreachable!(),
         }
     }
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum LifetimeData<I: Interner> {
     /// See TyData::Var(_).
     BoundVar(BoundVar),
     InferenceVar(InferenceVar),
     Placeholder(PlaceholderIndex),
+    /// `'static`, which outlives every other lifetime.
+    Static,
     Phantom(Void, PhantomData<I>),
 }
 
@@ -695,6 +892,7 @@ impl<I: Interner> LifetimeData<I> {
 /// Two indexes are required, the one of the universe itself
 /// and the relative index inside the universe.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PlaceholderIndex {
     /// Index *of* the universe.
     pub ui: UniverseIndex,
@@ -715,6 +913,11 @@ impl PlaceholderIndex {
 
 // Fold derive intentionally omitted, folded through Ty
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct ApplicationTy<I: Interner> {
     pub name: TypeName<I>,
     pub substitution: Substitution<I>,
@@ -742,6 +945,7 @@ impl<I: Interner> ApplicationTy<I> {
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ParameterKind<T, L = T> {
     Ty(T),
     Lifetime(L),
@@ -815,7 +1019,19 @@ impl<T, L> ParameterKind<T, L> {
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct Parameter<I: Interner> {
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "I::serialize_parameter",
+            deserialize_with = "I::deserialize_parameter"
+        )
+    )]
     interned: I::InternedParameter,
 }
 
@@ -880,6 +1096,11 @@ impl<I: Interner> ParameterData<I> {
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum AliasTy<I: Interner> {
     Projection(ProjectionTy<I>),
     Opaque(OpaqueTy<I>),
@@ -904,23 +1125,50 @@ impl<I: Interner> AliasTy<I> {
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct ProjectionTy<I: Interner> {
     pub associated_ty_id: AssocTypeId<I>,
     pub substitution: Substitution<I>,
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct OpaqueTy<I: Interner> {
     pub opaque_ty_id: OpaqueTyId<I>,
     pub substitution: Substitution<I>,
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct TraitRef<I: Interner> {
     pub trait_id: TraitId<I>,
     pub substitution: Substitution<I>,
 }
 
+/// A predicate applied to some parameters, e.g. `Reachable(A, B)`.
+#[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
+pub struct PredicateRef<I: Interner> {
+    pub predicate_id: PredicateId<I>,
+    pub substitution: Substitution<I>,
+}
+
 impl<I: Interner> TraitRef<I> {
     pub fn type_parameters<'a>(&'a self, interner: &'a I) -> impl Iterator<Item = Ty<I>> + 'a {
         self.substitution
@@ -944,12 +1192,27 @@ impl<I: Interner> TraitRef<I> {
 
 /// Where clauses that can be written by a Rust programmer.
 #[derive(Clone, PartialEq, Eq, Hash, Fold, SuperVisit, HasInterner, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum WhereClause<I: Interner> {
     Implemented(TraitRef<I>),
     AliasEq(AliasEq<I>),
+
+    /// `T: ~const Trait`: the trait ref holds in const contexts, that
+    /// is, it is implemented by an `impl const` whose own `~const`
+    /// bounds hold in const contexts too.
+    ConstImplemented(TraitRef<I>),
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum WellFormed<I: Interner> {
     /// A predicate which is true is some trait ref is well-formed.
     /// For example, given the following trait definitions:
@@ -980,6 +1243,11 @@ pub enum WellFormed<I: Interner> {
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum FromEnv<I: Interner> {
     /// A predicate which enables deriving everything which should be true if we *know* that
     /// some trait ref is well-formed. For example given the above trait definitions, we can use
@@ -1012,6 +1280,11 @@ pub enum FromEnv<I: Interner> {
 /// logical statement. As much as possible, the Chalk solver should avoid
 /// decomposing this enum, and instead treat its values opaquely.
 #[derive(Clone, PartialEq, Eq, Hash, Fold, SuperVisit, HasInterner, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum DomainGoal<I: Interner> {
     Holds(WhereClause<I>),
 
@@ -1076,6 +1349,10 @@ pub enum DomainGoal<I: Interner> {
     /// Used to activate the "reveal mode", in which opaque (`impl Trait`) types can be equated
     /// to their actual type.
     Reveal(()),
+
+    /// A predicate, which holds if it can be derived from the program clauses
+    /// that mention it or from the environment.
+    Predicate(PredicateRef<I>),
 }
 
 pub type QuantifiedWhereClause<I> = Binders<WhereClause<I>>;
@@ -1120,7 +1397,19 @@ impl<I: Interner> QuantifiedWhereClause<I> {
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct QuantifiedWhereClauses<I: Interner> {
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "I::serialize_quantified_where_clauses",
+            deserialize_with = "I::deserialize_quantified_where_clauses"
+        )
+    )]
     interned: I::InternedQuantifiedWhereClauses,
 }
 
@@ -1197,6 +1486,11 @@ impl<I: Interner> DomainGoal<I> {
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct EqGoal<I: Interner> {
     pub a: Parameter<I>,
     pub b: Parameter<I>,
@@ -1207,6 +1501,11 @@ pub struct EqGoal<I: Interner> {
 /// **match it to an impl** and that impl has a `type Foo = V` where
 /// `U = V`.
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct Normalize<I: Interner> {
     pub alias: AliasTy<I>,
     pub ty: Ty<I>,
@@ -1214,6 +1513,11 @@ pub struct Normalize<I: Interner> {
 
 /// Proves **equality** between an alias and a type.
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct AliasEq<I: Interner> {
     pub alias: AliasTy<I>,
     pub ty: Ty<I>,
@@ -1231,6 +1535,14 @@ impl<I: Interner> HasInterner for AliasEq<I> {
 /// (IOW, we use deBruijn indices, where binders are introduced in reverse order
 /// of `self.binders`.)
 #[derive(Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "T: Serialize, T::Interner: SerdeInterner",
+        deserialize = "T: Deserialize<'de>, T::Interner: SerdeInterner"
+    ))
+)]
 pub struct Binders<T: HasInterner> {
     pub binders: ParameterKinds<T::Interner>,
     value: T,
@@ -1459,6 +1771,11 @@ where
 /// `conditions = cond_1 && cond_2 && ...` is the conjunction of the individual
 /// conditions.
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct ProgramClauseImplication<I: Interner> {
     pub consequence: DomainGoal<I>,
     pub conditions: Goals<I>,
@@ -1466,6 +1783,7 @@ pub struct ProgramClauseImplication<I: Interner> {
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ClausePriority {
     High,
     Low,
@@ -1482,6 +1800,11 @@ impl std::ops::BitAnd for ClausePriority {
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Fold, HasInterner, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum ProgramClauseData<I: Interner> {
     Implies(ProgramClauseImplication<I>),
     ForAll(Binders<ProgramClauseImplication<I>>),
@@ -1521,7 +1844,19 @@ impl<I: Interner> ProgramClauseData<I> {
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct ProgramClause<I: Interner> {
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "I::serialize_program_clause",
+            deserialize_with = "I::deserialize_program_clause"
+        )
+    )]
     interned: I::InternedProgramClause,
 }
 
@@ -1547,7 +1882,19 @@ impl<I: Interner> ProgramClause<I> {
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct ProgramClauses<I: Interner> {
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "I::serialize_program_clauses",
+            deserialize_with = "I::deserialize_program_clauses"
+        )
+    )]
     interned: I::InternedProgramClauses,
 }
 
@@ -1601,7 +1948,19 @@ impl<I: Interner> ProgramClauses<I> {
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct ParameterKinds<I: Interner> {
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "I::serialize_parameter_kinds",
+            deserialize_with = "I::deserialize_parameter_kinds"
+        )
+    )]
     interned: I::InternedParameterKinds,
 }
 
@@ -1654,7 +2013,19 @@ impl<I: Interner> ParameterKinds<I> {
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct CanonicalVarKinds<I: Interner> {
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "I::serialize_canonical_var_kinds",
+            deserialize_with = "I::deserialize_canonical_var_kinds"
+        )
+    )]
     interned: I::InternedCanonicalVarKinds,
 }
 
@@ -1712,6 +2083,14 @@ impl<I: Interner> CanonicalVarKinds<I> {
 /// first appearance; the kind/universe of the variable is recorded in the
 /// `binders` field.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "T: Serialize, T::Interner: SerdeInterner",
+        deserialize = "T: Deserialize<'de>, T::Interner: SerdeInterner"
+    ))
+)]
 pub struct Canonical<T: HasInterner> {
     pub value: T,
     pub binders: CanonicalVarKinds<T::Interner>,
@@ -1728,6 +2107,14 @@ impl<T: HasInterner> HasInterner for Canonical<T> {
 ///
 /// To produce one of these values, use the `u_canonicalize` method.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "T: Serialize, T::Interner: SerdeInterner",
+        deserialize = "T: Deserialize<'de>, T::Interner: SerdeInterner"
+    ))
+)]
 pub struct UCanonical<T: HasInterner> {
     pub canonical: Canonical<T>,
     pub universes: usize,
@@ -1774,7 +2161,19 @@ impl<T: HasInterner> UCanonical<T> {
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, HasInterner)]
 /// A list of goals.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct Goals<I: Interner> {
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "I::serialize_goals",
+            deserialize_with = "I::deserialize_goals"
+        )
+    )]
     interned: I::InternedGoals,
 }
 
@@ -1826,7 +2225,19 @@ impl<I: Interner> Goals<I> {
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, HasInterner)]
 /// A general goal; this is the full range of questions you can pose to Chalk.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct Goal<I: Interner> {
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "I::serialize_goal",
+            deserialize_with = "I::deserialize_goal"
+        )
+    )]
     interned: I::InternedGoal,
 }
 
@@ -1922,17 +2333,30 @@ where
 
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner, Zip)]
 /// A general goal; this is the full range of questions you can pose to Chalk.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum GoalData<I: Interner> {
     /// Introduces a binding at depth 0, shifting other bindings up
     /// (deBruijn index).
     Quantified(QuantifierKind, Binders<Goal<I>>),
     Implies(ProgramClauses<I>, Goal<I>),
     All(Goals<I>),
+
+    /// Holds if any of the goals holds; an empty list never holds
+    Any(Goals<I>),
     Not(Goal<I>),
 
     /// Make two things equal; the rules for doing so are well known to the logic
     EqGoal(EqGoal<I>),
 
+    /// Holds if the constraint holds. Like the constraints produced by
+    /// unification, it is not checked by the solver, but returned with
+    /// the solution.
+    Constraint(Constraint<I>),
+
     /// A "domain goal" indicates some base sort of goal that can be
     /// proven via program clauses
     DomainGoal(DomainGoal<I>),
@@ -1956,6 +2380,7 @@ impl<I: Interner> GoalData<I> {
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum QuantifierKind {
     ForAll,
     Exists,
@@ -1967,17 +2392,35 @@ pub enum QuantifierKind {
 /// lifetime constraints, instead gathering them up to return with our solution
 /// for later checking. This allows for decoupling between type and region
 /// checking in the compiler.
-#[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner)]
+#[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum Constraint<I: Interner> {
     Outlives(Lifetime<I>, Lifetime<I>),
+    TypeOutlives(Ty<I>, Lifetime<I>),
 }
 
 /// A mapping of inference variables to instantiations thereof.
 #[derive(Copy, Clone, PartialEq, Eq, Hash, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct Substitution<I: Interner> {
     /// Map free variable with given index to the value with the same
     /// index. Naturally, the kind of the variable must agree with
     /// the kind of the value.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "I::serialize_substitution",
+            deserialize_with = "I::deserialize_substitution"
+        )
+    )]
     interned: I::InternedSubstitution,
 }
 
@@ -2172,6 +2615,18 @@ impl<'i, I: Interner> Folder<'i, I> for &SubstFolder<'i, I> {
         self
     }
 
+    fn fold_ty(&mut self, ty: &Ty<I>, outer_binder: DebruijnIndex) -> Fallible<Ty<I>> {
+        // Only bound variables are substituted.
+        if ty
+            .flags(self.interner())
+            .intersects(TypeFlags::HAS_BOUND_VARS)
+        {
+            ty.super_fold_with(self.as_dyn(), outer_binder)
+        } else {
+            Ok(ty.clone())
+        }
+    }
+
     fn fold_free_var_ty(
         &mut self,
         bound_var: BoundVar,
@@ -2209,12 +2664,22 @@ impl<'i, I: Interner> Folder<'i, I> for &SubstFolder<'i, I> {
 /// and the constraints represents any region constraints that must
 /// additionally be solved.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct ConstrainedSubst<I: Interner> {
     pub subst: Substitution<I>, /* NB: The `is_trivial` routine relies on the fact that `subst` is folded first. */
     pub constraints: Vec<InEnvironment<Constraint<I>>>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct AnswerSubst<I: Interner> {
     pub subst: Substitution<I>, /* NB: The `is_trivial` routine relies on the fact that `subst` is folded first. */
     pub constraints: Vec<InEnvironment<Constraint<I>>>,
@@ -0,0 +1,138 @@
+//! Bringing goals into a normal form, so that goals that differ only in
+//! how they are nested are the same goal, both to the caches of the
+//! solvers and to tests that compare goals.
+
+use crate::fold::shift::Shift;
+use crate::{
+    Binders, DebruijnIndex, Goal, GoalData, Goals, Interner, Parameter, ParameterKinds,
+    QuantifierKind,
+};
+
+impl<I: Interner> Goal<I> {
+    /// Simplifies this goal without changing its meaning:
+    ///
+    /// - nested `All`s are flattened into one, as are nested `Any`s;
+    /// - trivially true goals are removed from `All`s, and an `All` or
+    ///   `Any` of a single goal is replaced by that goal;
+    /// - quantifiers are hoisted out of `All`s, so that
+    ///   `exists<T> { A }, B` becomes `exists<T> { A, B }`, in the order
+    ///   of the conjuncts;
+    /// - adjacent quantifiers of the same kind are merged, so that
+    ///   `forall<A> { forall<B> { G } }` becomes `forall<A, B> { G }`,
+    ///   and quantifiers that bind nothing are removed;
+    /// - quantifiers and implications whose goal is trivially true,
+    ///   and implications with no clauses, are removed.
+    ///
+    /// Simplifying a goal twice gives the same goal as simplifying it
+    /// once.
+    pub fn simplify(&self, interner: &I) -> Goal<I> {
+        match self.data(interner) {
+            GoalData::Quantified(kind, subgoal) => {
+                let goal = subgoal.skip_binders().simplify(interner);
+                if goal.is_trivially_true(interner) {
+                    return goal;
+                }
+                quantify(interner, *kind, subgoal.binders.clone(), goal)
+            }
+            GoalData::Implies(clauses, goal) => {
+                let goal = goal.simplify(interner);
+                if clauses.is_empty(interner) || goal.is_trivially_true(interner) {
+                    goal
+                } else {
+                    GoalData::Implies(clauses.clone(), goal).intern(interner)
+                }
+            }
+            GoalData::All(goals) => {
+                let mut quantifiers = vec![];
+                let mut conjuncts = vec![];
+                for goal in goals.iter(interner) {
+                    // The conjunct is moved inside the quantifiers hoisted
+                    // out of the conjuncts before it.
+                    let goal = goal
+                        .simplify(interner)
+                        .shifted_in_from(interner, DebruijnIndex::new(quantifiers.len() as u32));
+                    hoist(interner, goal, &mut quantifiers, &mut conjuncts);
+                }
+                quantifiers
+                    .into_iter()
+                    .rev()
+                    .fold(Goal::all(interner, conjuncts), |goal, (kind, binders)| {
+                        quantify(interner, kind, binders, goal)
+                    })
+            }
+            GoalData::Any(goals) => {
+                let mut simplified = vec![];
+                for goal in goals.iter(interner) {
+                    let goal = goal.simplify(interner);
+                    match goal.data(interner) {
+                        GoalData::Any(goals) => simplified.extend(goals.iter(interner).cloned()),
+                        _ => simplified.push(goal),
+                    }
+                }
+                if simplified.len() == 1 {
+                    simplified.pop().unwrap()
+                } else {
+                    GoalData::Any(Goals::from(interner, simplified)).intern(interner)
+                }
+            }
+            GoalData::Not(goal) => GoalData::Not(goal.simplify(interner)).intern(interner),
+            GoalData::EqGoal(_)
+            | GoalData::Constraint(_)
+            | GoalData::DomainGoal(_)
+            | GoalData::CannotProve(()) => self.clone(),
+        }
+    }
+}
+
+/// Quantifies `goal` over `binders`, merging the quantifier with that of
+/// `goal` if it is of the same kind, and leaving it out if it binds
+/// nothing.
+fn quantify<I: Interner>(
+    interner: &I,
+    kind: QuantifierKind,
+    binders: ParameterKinds<I>,
+    goal: Goal<I>,
+) -> Goal<I> {
+    let binders = match goal.data(interner) {
+        GoalData::Quantified(inner_kind, inner) if *inner_kind == kind => {
+            Binders::new(binders, inner.clone()).fuse_binders(interner)
+        }
+        _ => Binders::new(binders, goal),
+    };
+    if binders.len(interner) == 0 {
+        binders.substitute(interner, &[] as &[Parameter<I>])
+    } else {
+        GoalData::Quantified(kind, binders).intern(interner)
+    }
+}
+
+/// Adds the simplified conjunct `goal` to `conjuncts`, flattening it if it
+/// is an `All`, and moving its quantifiers to the end of `quantifiers`;
+/// the conjuncts already there are then shifted in through them.
+fn hoist<I: Interner>(
+    interner: &I,
+    goal: Goal<I>,
+    quantifiers: &mut Vec<(QuantifierKind, ParameterKinds<I>)>,
+    conjuncts: &mut Vec<Goal<I>>,
+) {
+    match goal.data(interner) {
+        GoalData::Quantified(kind, subgoal) => {
+            for conjunct in conjuncts.iter_mut() {
+                *conjunct = conjunct.shifted_in(interner);
+            }
+            quantifiers.push((*kind, subgoal.binders.clone()));
+            hoist(
+                interner,
+                subgoal.skip_binders().clone(),
+                quantifiers,
+                conjuncts,
+            );
+        }
+        GoalData::All(goals) => {
+            for goal in goals.iter(interner) {
+                hoist(interner, goal.clone(), quantifiers, conjuncts);
+            }
+        }
+        _ => conjuncts.push(goal),
+    }
+}
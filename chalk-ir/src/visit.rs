@@ -371,6 +371,7 @@ impl<I: Interner> SuperVisit<I> for Lifetime<I> {
             LifetimeData::Placeholder(universe) => {
                 visitor.visit_free_placeholder_lifetime(*universe, outer_binder)
             }
+            LifetimeData::Static => R::new(),
             LifetimeData::Phantom(..) => unreachable!(),
         }
     }
@@ -6,9 +6,9 @@
 
 use crate::{
     AssocTypeId, ClausePriority, DebruijnIndex, FloatTy, Goals, ImplId, IntTy, Interner,
-    OpaqueTyId, Parameter, ParameterKind, PlaceholderIndex, ProgramClause, ProgramClauseData,
-    ProgramClauses, QuantifiedWhereClauses, QuantifierKind, Scalar, StructId, Substitution,
-    SuperVisit, TraitId, UintTy, UniverseIndex, Visit, VisitResult, Visitor,
+    Mutability, OpaqueTyId, Parameter, ParameterKind, PlaceholderIndex, PredicateId, ProgramClause,
+    ProgramClauseData, ProgramClauses, QuantifiedWhereClauses, QuantifierKind, Scalar, StructId,
+    Substitution, SuperVisit, TraitId, UintTy, UniverseIndex, Visit, VisitResult, Visitor,
 };
 use chalk_engine::{context::Context, ExClause, FlounderedSubgoal, Literal};
 use std::{marker::PhantomData, sync::Arc};
@@ -208,6 +208,7 @@ const_visit!(chalk_engine::TimeStamp);
 const_visit!(ClausePriority);
 const_visit!(());
 const_visit!(Scalar);
+const_visit!(Mutability);
 const_visit!(UintTy);
 const_visit!(IntTy);
 const_visit!(FloatTy);
@@ -234,6 +235,7 @@ id_visit!(ImplId);
 id_visit!(StructId);
 id_visit!(TraitId);
 id_visit!(OpaqueTyId);
+id_visit!(PredicateId);
 id_visit!(AssocTypeId);
 
 impl<I: Interner> SuperVisit<I> for ProgramClause<I> {
@@ -1,4 +1,5 @@
-use crate::{BoundVar, DebruijnIndex, Interner, Visit, VisitResult, Visitor};
+use crate::flags::TypeFlags;
+use crate::{BoundVar, DebruijnIndex, Interner, SuperVisit, Ty, Visit, VisitResult, Visitor};
 
 pub trait VisitExt<I: Interner>: Visit<I> {
     fn has_free_vars(&self, interner: &I) -> bool {
@@ -55,6 +56,17 @@ impl<'i, I: Interner> Visitor<'i, I> for FindFreeVarsVisitor<'i, I> {
         self.interner
     }
 
+    fn visit_ty(&mut self, ty: &Ty<I>, outer_binder: DebruijnIndex) -> Self::Result {
+        if ty
+            .flags(self.interner)
+            .intersects(TypeFlags::HAS_BOUND_VARS)
+        {
+            ty.super_visit_with(self.as_dyn(), outer_binder)
+        } else {
+            FindAny::new()
+        }
+    }
+
     fn visit_free_var_ty(
         &mut self,
         _bound_var: BoundVar,
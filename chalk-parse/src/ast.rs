@@ -1,7 +1,7 @@
 use std::fmt;
 use string_cache::DefaultAtom as Atom;
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct Span {
     pub lo: usize,
     pub hi: usize,
@@ -11,6 +11,17 @@ impl Span {
     pub fn new(lo: usize, hi: usize) -> Self {
         Span { lo, hi }
     }
+
+    /// The line and column of the start of the span in `text`, both
+    /// counted from 1. Columns count characters, not bytes.
+    pub fn line_column(&self, text: &str) -> (usize, usize) {
+        let before = &text[..self.lo];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        (
+            before.matches('\n').count() + 1,
+            before[line_start..].chars().count() + 1,
+        )
+    }
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -23,8 +34,40 @@ pub enum Item {
     StructDefn(StructDefn),
     TraitDefn(TraitDefn),
     OpaqueTyDefn(OpaqueTyDefn),
+    HiddenTyDefn(HiddenTyDefn),
     Impl(Impl),
+    InherentImpl(InherentImpl),
     Clause(Clause),
+    PredicateDefn(PredicateDefn),
+    GoalDefn(GoalDefn),
+    ModDefn(ModDefn),
+}
+
+impl Item {
+    /// Where the item is in the program, from its attributes to its end.
+    pub fn span(&self) -> Span {
+        match self {
+            Item::StructDefn(defn) => defn.span,
+            Item::TraitDefn(defn) => defn.span,
+            Item::OpaqueTyDefn(defn) => defn.span,
+            Item::HiddenTyDefn(defn) => defn.span,
+            Item::Impl(defn) => defn.span,
+            Item::InherentImpl(defn) => defn.span,
+            Item::Clause(clause) => clause.span,
+            Item::PredicateDefn(defn) => defn.span,
+            Item::GoalDefn(defn) => defn.span,
+            Item::ModDefn(defn) => defn.span,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+/// A `mod name { ... }` block; the names of the items it contains are
+/// qualified with the module path, e.g. `name::Foo`.
+pub struct ModDefn {
+    pub name: Identifier,
+    pub items: Vec<Item>,
+    pub span: Span,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -34,12 +77,17 @@ pub struct StructDefn {
     pub where_clauses: Vec<QuantifiedWhereClause>,
     pub fields: Vec<Field>,
     pub flags: StructFlags,
+    pub attributes: Vec<Attribute>,
+    pub span: Span,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct StructFlags {
     pub upstream: bool,
     pub fundamental: bool,
+    pub union: bool,
+    pub manually_drop: bool,
+    pub extern_type: bool,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -48,8 +96,12 @@ pub struct TraitDefn {
     pub parameter_kinds: Vec<ParameterKind>,
     pub where_clauses: Vec<QuantifiedWhereClause>,
     pub assoc_ty_defns: Vec<AssocTyDefn>,
+    pub methods: Vec<MethodDefn>,
     pub flags: TraitFlags,
+    pub safety: Safety,
     pub well_known: Option<WellKnownTrait>,
+    pub attributes: Vec<Attribute>,
+    pub span: Span,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
@@ -58,6 +110,39 @@ pub enum WellKnownTrait {
     CopyTrait,
     CloneTrait,
     DropTrait,
+    DerefTrait,
+}
+
+impl WellKnownTrait {
+    /// Maps the argument of a `#[lang(..)]` attribute to the trait it names.
+    pub fn from_name(name: &str) -> Option<WellKnownTrait> {
+        match name {
+            "sized" => Some(WellKnownTrait::SizedTrait),
+            "copy" => Some(WellKnownTrait::CopyTrait),
+            "clone" => Some(WellKnownTrait::CloneTrait),
+            "drop" => Some(WellKnownTrait::DropTrait),
+            "deref" => Some(WellKnownTrait::DerefTrait),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+/// An attribute on an item, e.g. `#[upstream]`, `#[lang(sized)]` or
+/// `#[priority(1)]`.
+pub struct Attribute {
+    pub name: Identifier,
+    pub args: Vec<Identifier>,
+}
+
+impl Attribute {
+    pub fn is(&self, name: &str) -> bool {
+        &*self.name.str == name
+    }
+}
+
+pub fn has_attribute(attributes: &[Attribute], name: &str) -> bool {
+    attributes.iter().any(|attr| attr.is(name))
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -80,10 +165,22 @@ pub struct AssocTyDefn {
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct OpaqueTyDefn {
-    pub ty: Ty,
+    /// The hidden type, or `None` for `type Foo = impl Bar;`, whose hidden
+    /// type is given by a separate `HiddenTyDefn`.
+    pub ty: Option<Ty>,
     pub parameter_kinds: Vec<ParameterKind>,
     pub identifier: Identifier,
     pub bounds: Vec<QuantifiedInlineBound>,
+    pub span: Span,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+/// `hidden type Foo<T> = Ty;` -- the hidden type of `type Foo<T> = impl Bar;`
+pub struct HiddenTyDefn {
+    pub identifier: Identifier,
+    pub parameter_kinds: Vec<ParameterKind>,
+    pub ty: Ty,
+    pub span: Span,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -109,6 +206,9 @@ pub enum InlineBound {
 pub struct QuantifiedInlineBound {
     pub parameter_kinds: Vec<ParameterKind>,
     pub bound: InlineBound,
+    /// The span of the whole bound, of which `Foo<A = X, B = Y>` has
+    /// one per binding.
+    pub span: Span,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -149,9 +249,14 @@ pub struct Impl {
     pub parameter_kinds: Vec<ParameterKind>,
     pub trait_ref: TraitRef,
     pub polarity: Polarity,
+    pub safety: Safety,
+    pub constness: Constness,
     pub where_clauses: Vec<QuantifiedWhereClause>,
     pub assoc_ty_values: Vec<AssocTyValue>,
+    pub methods: Vec<MethodDefn>,
     pub impl_type: ImplType,
+    pub attributes: Vec<Attribute>,
+    pub span: Span,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -160,6 +265,45 @@ pub enum ImplType {
     External,
 }
 
+#[derive(Clone, PartialEq, Eq, Debug)]
+/// `impl<T> Foo<T> { fn bar(&self); }` -- an impl without a trait.
+pub struct InherentImpl {
+    pub parameter_kinds: Vec<ParameterKind>,
+    pub self_ty: Ty,
+    pub where_clauses: Vec<QuantifiedWhereClause>,
+    pub methods: Vec<MethodDefn>,
+    pub attributes: Vec<Attribute>,
+    pub span: Span,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+/// `fn name<T>(&self, A, B) -> R where T: Bound;` -- a method signature.
+pub struct MethodDefn {
+    pub name: Identifier,
+    pub parameter_kinds: Vec<ParameterKind>,
+    pub receiver: Option<Receiver>,
+    pub inputs: Vec<Ty>,
+    pub output: Option<Ty>,
+    pub where_clauses: Vec<QuantifiedWhereClause>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Receiver {
+    /// `self`
+    Value,
+    /// `&self`
+    Ref,
+    /// `&mut self`
+    RefMut,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+/// `Ty.name()` -- asks which method `name` resolves to on a receiver of type `Ty`.
+pub struct MethodProbe {
+    pub receiver: Ty,
+    pub method: Identifier,
+}
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct AssocTyValue {
     pub name: Identifier,
@@ -172,29 +316,74 @@ pub struct AssocTyValue {
 pub enum Ty {
     Id {
         name: Identifier,
+        span: Span,
     },
     Dyn {
         bounds: Vec<QuantifiedInlineBound>,
+        span: Span,
     },
     Apply {
         name: Identifier,
         args: Vec<Parameter>,
+        span: Span,
     },
     Projection {
         proj: ProjectionTy,
+        span: Span,
     },
     ForAll {
-        lifetime_names: Vec<Identifier>,
+        parameter_kinds: Vec<ParameterKind>,
         ty: Box<Ty>,
+        span: Span,
     },
     Tuple {
         types: Vec<Box<Ty>>,
+        span: Span,
     },
     Scalar {
         ty: ScalarType,
+        span: Span,
+    },
+    Ref {
+        mutability: Mutability,
+        lifetime: Lifetime,
+        ty: Box<Ty>,
+        span: Span,
     },
 }
 
+impl Ty {
+    /// Where the type is in the program.
+    pub fn span(&self) -> Span {
+        match self {
+            Ty::Id { span, .. }
+            | Ty::Dyn { span, .. }
+            | Ty::Apply { span, .. }
+            | Ty::Projection { span, .. }
+            | Ty::ForAll { span, .. }
+            | Ty::Tuple { span, .. }
+            | Ty::Scalar { span, .. }
+            | Ty::Ref { span, .. } => *span,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Mutability {
+    Mut,
+    Not,
+}
+
+impl Mutability {
+    pub fn from_bool(is_mut: bool) -> Mutability {
+        if is_mut {
+            Mutability::Mut
+        } else {
+            Mutability::Not
+        }
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum IntTy {
     Isize,
@@ -232,7 +421,15 @@ pub enum ScalarType {
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum Lifetime {
-    Id { name: Identifier },
+    Id {
+        name: Identifier,
+    },
+    Static,
+    /// `'_`, or the lifetime left out of a reference type like `&T`, in
+    /// which case the span is that of the `&`
+    Anonymous {
+        span: Span,
+    },
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -267,6 +464,44 @@ impl Polarity {
     }
 }
 
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Safety {
+    /// `trait Foo` or `impl Foo for Bar`
+    Safe,
+
+    /// `unsafe trait Foo` or `unsafe impl Foo for Bar`
+    Unsafe,
+}
+
+impl Safety {
+    pub fn from_bool(is_unsafe: bool) -> Safety {
+        if is_unsafe {
+            Safety::Unsafe
+        } else {
+            Safety::Safe
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Constness {
+    /// `impl Foo for Bar`
+    NotConst,
+
+    /// `impl const Foo for Bar`
+    Const,
+}
+
+impl Constness {
+    pub fn from_bool(is_const: bool) -> Constness {
+        if is_const {
+            Constness::Const
+        } else {
+            Constness::NotConst
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Identifier {
     pub str: Atom,
@@ -283,36 +518,87 @@ impl fmt::Display for Identifier {
 pub enum WhereClause {
     Implemented { trait_ref: TraitRef },
     ProjectionEq { projection: ProjectionTy, ty: Ty },
+    ConstImplemented { trait_ref: TraitRef },
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum DomainGoal {
-    Holds { where_clause: WhereClause },
-    Normalize { projection: ProjectionTy, ty: Ty },
-    TraitRefWellFormed { trait_ref: TraitRef },
-    TyWellFormed { ty: Ty },
-    TyFromEnv { ty: Ty },
-    TraitRefFromEnv { trait_ref: TraitRef },
-    IsLocal { ty: Ty },
-    IsUpstream { ty: Ty },
-    IsFullyVisible { ty: Ty },
-    LocalImplAllowed { trait_ref: TraitRef },
+    Holds {
+        where_clause: WhereClause,
+    },
+    Normalize {
+        projection: ProjectionTy,
+        ty: Ty,
+    },
+    TraitRefWellFormed {
+        trait_ref: TraitRef,
+    },
+    TyWellFormed {
+        ty: Ty,
+    },
+    TyFromEnv {
+        ty: Ty,
+    },
+    TraitRefFromEnv {
+        trait_ref: TraitRef,
+    },
+    IsLocal {
+        ty: Ty,
+    },
+    IsUpstream {
+        ty: Ty,
+    },
+    IsFullyVisible {
+        ty: Ty,
+    },
+    LocalImplAllowed {
+        trait_ref: TraitRef,
+    },
     Compatible,
-    DownstreamType { ty: Ty },
+    DownstreamType {
+        ty: Ty,
+    },
     Reveal,
+    Predicate {
+        name: Identifier,
+        args: Vec<Parameter>,
+    },
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum LeafGoal {
-    DomainGoal { goal: DomainGoal },
-    UnifyTys { a: Ty, b: Ty },
-    UnifyLifetimes { a: Lifetime, b: Lifetime },
+    DomainGoal {
+        goal: DomainGoal,
+    },
+    UnifyTys {
+        a: Ty,
+        b: Ty,
+    },
+    UnifyLifetimes {
+        a: Lifetime,
+        b: Lifetime,
+    },
+    TypeOutlives {
+        a: Ty,
+        b: Lifetime,
+    },
+    LifetimeOutlives {
+        a: Lifetime,
+        b: Lifetime,
+    },
+    /// `Ty.name()` -- holds if a method `name` can be called on `Ty`.
+    Method {
+        probe: MethodProbe,
+    },
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct QuantifiedWhereClause {
     pub parameter_kinds: Vec<ParameterKind>,
     pub where_clause: WhereClause,
+    /// The span of the whole where clause, of which `T: Foo<A = X, B = Y>`
+    /// has one per binding.
+    pub span: Span,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -328,6 +614,25 @@ pub struct Clause {
     pub parameter_kinds: Vec<ParameterKind>,
     pub consequence: DomainGoal,
     pub conditions: Vec<Box<Goal>>,
+    pub span: Span,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+/// A `predicate Name(type, lifetime);` declaration. Like custom clauses,
+/// this has no equivalent in Rust: the predicate is defined entirely by
+/// the clauses that mention it.
+pub struct PredicateDefn {
+    pub name: Identifier,
+    pub parameter_kinds: Vec<Kind>,
+    pub span: Span,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+/// A `goal name { G }` definition, which other goals can refer to as `goal name`.
+pub struct GoalDefn {
+    pub name: Identifier,
+    pub goal: Box<Goal>,
+    pub span: Span,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -336,11 +641,17 @@ pub enum Goal {
     Exists(Vec<ParameterKind>, Box<Goal>),
     Implies(Vec<Clause>, Box<Goal>),
     And(Box<Goal>, Vec<Box<Goal>>),
+
+    /// The `or { G1; G2 }` syntax
+    Or(Box<Goal>, Vec<Box<Goal>>),
     Not(Box<Goal>),
 
     /// The `compatible { G }` syntax
     Compatible(Box<Goal>),
 
+    /// The `goal name` syntax, referring to a goal definition
+    Named(Identifier),
+
     // Additional kinds of goals:
     Leaf(LeafGoal),
 }
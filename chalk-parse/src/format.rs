@@ -0,0 +1,966 @@
+//! Formatting of programs: each item is parsed and printed again with
+//! canonical indentation and spacing.
+//!
+//! The order of the items, and of the where clauses, conditions and goals
+//! within them, is kept, since it decides the order in which the solvers
+//! try clauses; within a trait or an impl, associated types are put before
+//! methods.
+//!
+//! Comments between items are kept, as are the comments at the end of the
+//! line that an item ends on, like the `//~ UNIQUE` expectations of batch
+//! files, whose `goal { .. }` blocks are formatted too. Items that have
+//! comments inside them, and templates and their expansions, are kept as
+//! they are written.
+
+use crate::ast::*;
+use crate::template::Origins;
+use crate::{parse_goal, parse_program, parser, strip_comments, Result};
+use std::fmt;
+use std::ops::Range;
+
+const INDENT: &str = "    ";
+
+/// Formats the program `text`, returning the formatted text.
+pub fn format_program(text: &str) -> Result<String> {
+    let stripped = strip_comments(text)?;
+    let items = split_items(text, &stripped)?;
+
+    // The goal blocks of batch files are not items, so they are left out
+    // to check that the rest of the program parses.
+    let mut program_text = text.to_string();
+    for item in &items.items {
+        if is_goal_block(&stripped[item.range.clone()]) {
+            let blank: String = text[item.range.clone()]
+                .chars()
+                .map(|c| if c == '\n' { '\n' } else { ' ' })
+                .collect();
+            program_text.replace_range(item.range.clone(), &blank);
+        }
+    }
+    parse_program(&program_text)?;
+
+    let mut output = String::new();
+    write_items(text, &stripped, &items, 0, &mut output)?;
+    Ok(output)
+}
+
+/// Prints `program`, with the formatting of `format_program`, so that
+/// programs built as ASTs can be given to what takes program text.
+pub fn print_program(program: &Program) -> String {
+    let mut output = String::new();
+    for item in &program.items {
+        write_item(item, 0, &mut output);
+        output.push('\n');
+    }
+    output
+}
+
+/// Prints `goal`, with the formatting of `format_program`.
+pub fn print_goal(goal: &Goal) -> String {
+    format_goal(goal)
+}
+
+/// The items of a program or of a module, in the text they are part of.
+struct Items {
+    items: Vec<ItemText>,
+
+    /// The text after the last item.
+    rest: Range<usize>,
+}
+
+struct ItemText {
+    /// The text between the previous item and this one, which holds the
+    /// comments that precede this item.
+    trivia: Range<usize>,
+
+    range: Range<usize>,
+
+    /// The comment at the end of the line that the item ends on.
+    trailing: Option<Range<usize>>,
+}
+
+/// Splits `text` into items, using `stripped`, the text with its comments
+/// blanked out. Each item ends with the `}` or `;` that closes it.
+fn split_items(text: &str, stripped: &str) -> Result<Items> {
+    let mut items = vec![];
+    let mut pos = 0;
+    loop {
+        let start = match stripped[pos..].find(|c: char| !c.is_whitespace()) {
+            Some(offset) => pos + offset,
+            None => {
+                return Ok(Items {
+                    items,
+                    rest: pos..text.len(),
+                })
+            }
+        };
+
+        let mut depth = 0;
+        let mut end = None;
+        for (offset, c) in stripped[start..].char_indices() {
+            match c {
+                '{' | '(' | '[' => depth += 1,
+                ')' | ']' => depth -= 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        end = Some(start + offset + 1);
+                        break;
+                    }
+                }
+                ';' if depth == 0 => {
+                    end = Some(start + offset + 1);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let end = end.ok_or_else(|| format!("unterminated item at position {}", start))?;
+
+        // A line comment after the item, on the same line, belongs to it.
+        let end_of_line = text[end..].find('\n').map_or(text.len(), |i| end + i);
+        let after = text[end..end_of_line].trim();
+        let (trailing, next) = if after.starts_with("//") {
+            let comment_start = end + text[end..].find("//").unwrap();
+            (Some(comment_start..end_of_line), end_of_line)
+        } else {
+            (None, end)
+        };
+
+        items.push(ItemText {
+            trivia: pos..start,
+            range: start..end,
+            trailing,
+        });
+        pos = next;
+    }
+}
+
+fn first_word(text: &str) -> &str {
+    let end = text
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(text.len());
+    &text[..end]
+}
+
+/// Whether `text` is the `goal { .. }` block of a batch file, which,
+/// unlike a goal definition, has no name.
+fn is_goal_block(text: &str) -> bool {
+    first_word(text) == "goal" && text["goal".len()..].trim_start().starts_with('{')
+}
+
+fn write_items(
+    text: &str,
+    stripped: &str,
+    items: &Items,
+    depth: usize,
+    output: &mut String,
+) -> Result<()> {
+    let indent = INDENT.repeat(depth);
+    let mut at_start = true;
+    for item in &items.items {
+        write_trivia(
+            &text[item.trivia.clone()],
+            &indent,
+            true,
+            &mut at_start,
+            output,
+        );
+        output.push_str(&indent);
+        let line_start = text[..item.range.start].rfind('\n').map_or(0, |i| i + 1);
+        write_item_text(
+            &text[item.range.clone()],
+            &stripped[item.range.clone()],
+            item.range.start - line_start,
+            depth,
+            output,
+        )?;
+        if let Some(trailing) = &item.trailing {
+            output.push(' ');
+            output.push_str(text[trailing.clone()].trim_end());
+        }
+        output.push('\n');
+        at_start = false;
+    }
+    write_trivia(
+        &text[items.rest.clone()],
+        &indent,
+        false,
+        &mut at_start,
+        output,
+    );
+    Ok(())
+}
+
+/// Writes the comments in `trivia`, each on a line of its own, keeping a
+/// blank line wherever `trivia` has blank lines, except at the start of
+/// the program or module. If `before_item`, this includes a blank line
+/// between the last comment and the item that follows `trivia`.
+fn write_trivia(
+    trivia: &str,
+    indent: &str,
+    before_item: bool,
+    at_start: &mut bool,
+    output: &mut String,
+) {
+    let mut newlines = 0;
+    let mut rest = trivia;
+    loop {
+        let trimmed = rest.trim_start();
+        newlines += rest[..rest.len() - trimmed.len()].matches('\n').count();
+        rest = trimmed;
+
+        let comment_len = if rest.starts_with("//") {
+            rest.find('\n').unwrap_or(rest.len())
+        } else if rest.starts_with("/*") {
+            block_comment_len(rest)
+        } else {
+            break;
+        };
+
+        if newlines >= 2 && !*at_start {
+            output.push('\n');
+        }
+        output.push_str(indent);
+        output.push_str(rest[..comment_len].trim_end());
+        output.push('\n');
+        *at_start = false;
+        newlines = 0;
+        rest = &rest[comment_len..];
+    }
+    if before_item && newlines >= 2 && !*at_start {
+        output.push('\n');
+    }
+}
+
+/// The length of the (possibly nested) block comment that `text` starts
+/// with. The comment is known to be terminated, since the program was
+/// stripped of its comments.
+fn block_comment_len(text: &str) -> usize {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < text.len() {
+        if text[i..].starts_with("/*") {
+            depth += 1;
+            i += 2;
+        } else if text[i..].starts_with("*/") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                break;
+            }
+        } else {
+            i += text[i..].chars().next().unwrap().len_utf8();
+        }
+    }
+    i
+}
+
+/// Writes the item `text`, which starts at column `column`, without
+/// indenting its first line.
+fn write_item_text(
+    text: &str,
+    stripped: &str,
+    column: usize,
+    depth: usize,
+    output: &mut String,
+) -> Result<()> {
+    let word = first_word(stripped);
+    if word == "template" || word == "expand" {
+        write_verbatim(text, column, depth, output);
+    } else if word == "mod" {
+        let open = stripped.find('{').unwrap();
+        let name = stripped["mod".len()..open].trim();
+        let body = &text[open + 1..text.len() - 1];
+        let body_stripped = &stripped[open + 1..text.len() - 1];
+        let items = split_items(body, body_stripped)?;
+        let mut body_output = String::new();
+        write_items(body, body_stripped, &items, depth + 1, &mut body_output)?;
+        if body_output.is_empty() {
+            output.push_str(&format!("mod {} {{ }}", name));
+        } else {
+            output.push_str(&format!("mod {} {{\n{}", name, body_output));
+            output.push_str(&INDENT.repeat(depth));
+            output.push('}');
+        }
+    } else if text != stripped {
+        write_verbatim(text, column, depth, output);
+    } else if is_goal_block(stripped) {
+        let open = stripped.find('{').unwrap();
+        let goal = parse_goal(&text[open + 1..text.len() - 1])?;
+        output.push_str(&format!("goal {{ {} }}", format_goal(&goal)));
+    } else {
+        let program = match parser::ProgramParser::new().parse(&Origins::unexpanded(text), text) {
+            Ok(program) => program,
+            Err(e) => Err(format!("parse error: {:?}", e))?,
+        };
+        for item in &program.items {
+            write_item(item, depth, output);
+        }
+    }
+    Ok(())
+}
+
+/// Writes the item `text` as it is, except that its lines are moved from
+/// column `column` to the indentation of `depth`.
+fn write_verbatim(text: &str, column: usize, depth: usize, output: &mut String) {
+    let indent = INDENT.repeat(depth);
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            output.push('\n');
+            let leading = line.len() - line.trim_start().len();
+            let line = &line[leading.min(column)..];
+            if !line.is_empty() {
+                output.push_str(&indent);
+            }
+            output.push_str(line);
+        } else {
+            output.push_str(line);
+        }
+    }
+}
+
+/// Writes `item`, without indenting its first line, and without a newline
+/// at its end.
+fn write_item(item: &Item, depth: usize, output: &mut String) {
+    let indent = INDENT.repeat(depth);
+    let attributes = |attributes: &[Attribute], output: &mut String| {
+        for attribute in attributes {
+            output.push_str(&format_attribute(attribute));
+            output.push('\n');
+            output.push_str(&indent);
+        }
+    };
+    let body = |lines: Vec<String>, output: &mut String| {
+        if lines.is_empty() {
+            output.push_str(" { }");
+        } else {
+            output.push_str(" {\n");
+            for line in lines {
+                output.push_str(&format!("{}{}{}\n", indent, INDENT, line));
+            }
+            output.push_str(&indent);
+            output.push('}');
+        }
+    };
+
+    match item {
+        Item::StructDefn(defn) => {
+            attributes(&defn.attributes, output);
+            if defn.flags.extern_type {
+                output.push_str(&format!("extern type {};", defn.name));
+                return;
+            }
+            let keyword = if defn.flags.union { "union" } else { "struct" };
+            output.push_str(&format!(
+                "{} {}{}{}",
+                keyword,
+                defn.name,
+                format_kinds(&defn.parameter_kinds),
+                format_where_clauses(&defn.where_clauses)
+            ));
+            if defn.fields.is_empty() {
+                output.push_str(" { }");
+            } else {
+                let fields: Vec<_> = defn
+                    .fields
+                    .iter()
+                    .map(|field| format!("{}: {}", field.name, format_ty(&field.ty)))
+                    .collect();
+                output.push_str(&format!(" {{ {} }}", fields.join(", ")));
+            }
+        }
+        Item::TraitDefn(defn) => {
+            attributes(&defn.attributes, output);
+            output.push_str(&format!(
+                "{}trait {}{}{}",
+                format_safety(&defn.safety),
+                defn.name,
+                format_kinds(&defn.parameter_kinds),
+                format_where_clauses(&defn.where_clauses)
+            ));
+            let mut lines: Vec<_> = defn
+                .assoc_ty_defns
+                .iter()
+                .map(|defn| {
+                    let bounds = if defn.bounds.is_empty() {
+                        String::new()
+                    } else {
+                        format!(": {}", format_inline_bounds(&defn.bounds))
+                    };
+                    format!(
+                        "type {}{}{}{};",
+                        defn.name,
+                        format_kinds(&defn.parameter_kinds),
+                        bounds,
+                        format_where_clauses(&defn.where_clauses)
+                    )
+                })
+                .collect();
+            lines.extend(defn.methods.iter().map(format_method));
+            body(lines, output);
+        }
+        Item::OpaqueTyDefn(defn) => match &defn.ty {
+            Some(ty) => output.push_str(&format!(
+                "opaque type {}{}: {} = {};",
+                defn.identifier,
+                format_kinds(&defn.parameter_kinds),
+                format_inline_bounds(&defn.bounds),
+                format_ty(ty)
+            )),
+            None => output.push_str(&format!(
+                "type {}{} = impl {};",
+                defn.identifier,
+                format_kinds(&defn.parameter_kinds),
+                format_inline_bounds(&defn.bounds)
+            )),
+        },
+        Item::HiddenTyDefn(defn) => output.push_str(&format!(
+            "hidden type {}{} = {};",
+            defn.identifier,
+            format_kinds(&defn.parameter_kinds),
+            format_ty(&defn.ty)
+        )),
+        Item::Impl(defn) => {
+            attributes(&defn.attributes, output);
+            let constness = match defn.constness {
+                Constness::Const => "const ",
+                Constness::NotConst => "",
+            };
+            let polarity = match defn.polarity {
+                Polarity::Positive => "",
+                Polarity::Negative => "!",
+            };
+            output.push_str(&format!(
+                "{}impl{} {}{}{}{} for {}{}",
+                format_safety(&defn.safety),
+                format_kinds(&defn.parameter_kinds),
+                constness,
+                polarity,
+                defn.trait_ref.trait_name,
+                format_parameters(&defn.trait_ref.args[1..]),
+                format_parameter(&defn.trait_ref.args[0]),
+                format_where_clauses(&defn.where_clauses)
+            ));
+            let mut lines: Vec<_> = defn
+                .assoc_ty_values
+                .iter()
+                .map(|value| {
+                    format!(
+                        "{}type {}{} = {};",
+                        if value.default { "default " } else { "" },
+                        value.name,
+                        format_kinds(&value.parameter_kinds),
+                        format_ty(&value.value)
+                    )
+                })
+                .collect();
+            lines.extend(defn.methods.iter().map(format_method));
+            body(lines, output);
+        }
+        Item::InherentImpl(defn) => {
+            attributes(&defn.attributes, output);
+            output.push_str(&format!(
+                "impl{} {}{}",
+                format_kinds(&defn.parameter_kinds),
+                format_ty(&defn.self_ty),
+                format_where_clauses(&defn.where_clauses)
+            ));
+            body(defn.methods.iter().map(format_method).collect(), output);
+        }
+        Item::Clause(clause) => {
+            output.push_str(&format!(
+                "forall{} {{ {} }}",
+                format_kinds(&clause.parameter_kinds),
+                format_clause_body(clause)
+            ));
+        }
+        Item::PredicateDefn(defn) => {
+            let kinds: Vec<_> = defn
+                .parameter_kinds
+                .iter()
+                .map(|kind| kind.to_string())
+                .collect();
+            output.push_str(&format!("predicate {}({});", defn.name, kinds.join(", ")));
+        }
+        Item::GoalDefn(defn) => {
+            output.push_str(&format!(
+                "goal {} {{ {} }}",
+                defn.name,
+                format_goal(&defn.goal)
+            ));
+        }
+        Item::ModDefn(defn) => {
+            output.push_str(&format!("mod {}", defn.name));
+            if defn.items.is_empty() {
+                output.push_str(" { }");
+            } else {
+                output.push_str(" {\n");
+                for item in &defn.items {
+                    output.push_str(&format!("{}{}", indent, INDENT));
+                    write_item(item, depth + 1, output);
+                    output.push('\n');
+                }
+                output.push_str(&indent);
+                output.push('}');
+            }
+        }
+    }
+}
+
+fn format_attribute(attribute: &Attribute) -> String {
+    if attribute.args.is_empty() {
+        format!("#[{}]", attribute.name)
+    } else {
+        let args: Vec<_> = attribute.args.iter().map(|arg| arg.to_string()).collect();
+        format!("#[{}({})]", attribute.name, args.join(", "))
+    }
+}
+
+fn format_safety(safety: &Safety) -> &'static str {
+    match safety {
+        Safety::Safe => "",
+        Safety::Unsafe => "unsafe ",
+    }
+}
+
+fn format_method(method: &MethodDefn) -> String {
+    let mut inputs = vec![];
+    match method.receiver {
+        Some(Receiver::Value) => inputs.push("self".to_string()),
+        Some(Receiver::Ref) => inputs.push("&self".to_string()),
+        Some(Receiver::RefMut) => inputs.push("&mut self".to_string()),
+        None => {}
+    }
+    inputs.extend(method.inputs.iter().map(format_ty));
+    let output = match &method.output {
+        Some(ty) => format!(" -> {}", format_ty(ty)),
+        None => String::new(),
+    };
+    format!(
+        "fn {}{}({}){}{};",
+        method.name,
+        format_kinds(&method.parameter_kinds),
+        inputs.join(", "),
+        output,
+        format_where_clauses(&method.where_clauses)
+    )
+}
+
+/// `<T, 'a>`, or nothing if there are no parameters.
+fn format_kinds(kinds: &[ParameterKind]) -> String {
+    if kinds.is_empty() {
+        return String::new();
+    }
+    let kinds: Vec<_> = kinds
+        .iter()
+        .map(|kind| match kind {
+            ParameterKind::Ty(name) | ParameterKind::Lifetime(name) => name.to_string(),
+        })
+        .collect();
+    format!("<{}>", kinds.join(", "))
+}
+
+fn format_forall(kinds: &[ParameterKind]) -> String {
+    if kinds.is_empty() {
+        String::new()
+    } else {
+        format!("forall{} ", format_kinds(kinds))
+    }
+}
+
+/// `<A, B>`, or nothing if there are no parameters.
+fn format_parameters(parameters: &[Parameter]) -> String {
+    if parameters.is_empty() {
+        String::new()
+    } else {
+        format!("<{}>", format_parameter_list(parameters))
+    }
+}
+
+fn format_parameter_list(parameters: &[Parameter]) -> String {
+    let parameters: Vec<_> = parameters.iter().map(format_parameter).collect();
+    parameters.join(", ")
+}
+
+fn format_parameter(parameter: &Parameter) -> String {
+    match parameter {
+        Parameter::Ty(ty) => format_ty(ty),
+        Parameter::Lifetime(lifetime) => format_lifetime(lifetime),
+    }
+}
+
+fn format_lifetime(lifetime: &Lifetime) -> String {
+    match lifetime {
+        Lifetime::Id { name } => name.to_string(),
+        Lifetime::Static => "'static".to_string(),
+        Lifetime::Anonymous { .. } => "'_".to_string(),
+    }
+}
+
+fn format_ty(ty: &Ty) -> String {
+    match ty {
+        Ty::Id { name, .. } => name.to_string(),
+        Ty::Dyn { bounds, .. } => format!("dyn {}", format_inline_bounds(bounds)),
+        Ty::Apply { name, args, .. } => format!("{}<{}>", name, format_parameter_list(args)),
+        Ty::Projection { proj, .. } => format_projection(proj),
+        Ty::ForAll {
+            parameter_kinds,
+            ty,
+            ..
+        } => {
+            if parameter_kinds.is_empty() {
+                format!("fn({})", format_ty(ty))
+            } else {
+                format!("for{} fn({})", format_kinds(parameter_kinds), format_ty(ty))
+            }
+        }
+        Ty::Tuple { types, .. } => {
+            let types: Vec<_> = types.iter().map(|ty| format_ty(ty)).collect();
+            match &types[..] {
+                [ty] => format!("({},)", ty),
+                _ => format!("({})", types.join(", ")),
+            }
+        }
+        Ty::Scalar { ty, .. } => format_scalar(*ty).to_string(),
+        Ty::Ref {
+            mutability,
+            lifetime,
+            ty,
+            ..
+        } => {
+            let lifetime = match lifetime {
+                Lifetime::Anonymous { .. } => String::new(),
+                lifetime => format!("{} ", format_lifetime(lifetime)),
+            };
+            let mutability = match mutability {
+                Mutability::Mut => "mut ",
+                Mutability::Not => "",
+            };
+            // A `for<..>` type can only follow a `&` in parentheses.
+            let referent = match &**ty {
+                Ty::ForAll {
+                    parameter_kinds, ..
+                } if !parameter_kinds.is_empty() => format!("({})", format_ty(ty)),
+                ty => format_ty(ty),
+            };
+            format!("&{}{}{}", lifetime, mutability, referent)
+        }
+    }
+}
+
+fn format_scalar(ty: ScalarType) -> &'static str {
+    match ty {
+        ScalarType::Bool => "bool",
+        ScalarType::Char => "char",
+        ScalarType::Int(IntTy::Isize) => "isize",
+        ScalarType::Int(IntTy::I8) => "i8",
+        ScalarType::Int(IntTy::I16) => "i16",
+        ScalarType::Int(IntTy::I32) => "i32",
+        ScalarType::Int(IntTy::I64) => "i64",
+        ScalarType::Int(IntTy::I128) => "i128",
+        ScalarType::Uint(UintTy::Usize) => "usize",
+        ScalarType::Uint(UintTy::U8) => "u8",
+        ScalarType::Uint(UintTy::U16) => "u16",
+        ScalarType::Uint(UintTy::U32) => "u32",
+        ScalarType::Uint(UintTy::U64) => "u64",
+        ScalarType::Uint(UintTy::U128) => "u128",
+        ScalarType::Float(FloatTy::F32) => "f32",
+        ScalarType::Float(FloatTy::F64) => "f64",
+    }
+}
+
+fn format_projection(projection: &ProjectionTy) -> String {
+    let trait_ref = &projection.trait_ref;
+    format!(
+        "<{} as {}{}>::{}{}",
+        format_parameter(&trait_ref.args[0]),
+        trait_ref.trait_name,
+        format_parameters(&trait_ref.args[1..]),
+        projection.name,
+        format_parameters(&projection.args)
+    )
+}
+
+/// Trait refs are displayed as `Self: Trait<Args>`, whatever their spans,
+/// so two trait refs that display alike are written alike.
+impl fmt::Display for TraitRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", format_trait_ref(self))
+    }
+}
+
+/// `Self: Trait<Args>`.
+fn format_trait_ref(trait_ref: &TraitRef) -> String {
+    format!(
+        "{}: {}{}",
+        format_parameter(&trait_ref.args[0]),
+        trait_ref.trait_name,
+        format_parameters(&trait_ref.args[1..])
+    )
+}
+
+/// `Self: Trait<Args, Bindings>`, where `bindings` are of the form
+/// `Name<Args> = Ty`.
+fn format_bindings(trait_ref: &TraitRef, bindings: Vec<String>) -> String {
+    let mut args: Vec<_> = trait_ref.args[1..].iter().map(format_parameter).collect();
+    args.extend(bindings);
+    format!(
+        "{}: {}<{}>",
+        format_parameter(&trait_ref.args[0]),
+        trait_ref.trait_name,
+        args.join(", ")
+    )
+}
+
+fn format_binding(name: &Identifier, args: &[Parameter], ty: &Ty) -> String {
+    format!("{}{} = {}", name, format_parameters(args), format_ty(ty))
+}
+
+fn format_where_clause(where_clause: &WhereClause) -> String {
+    match where_clause {
+        WhereClause::Implemented { trait_ref } => format_trait_ref(trait_ref),
+        WhereClause::ConstImplemented { trait_ref } => format!(
+            "{}: ~const {}{}",
+            format_parameter(&trait_ref.args[0]),
+            trait_ref.trait_name,
+            format_parameters(&trait_ref.args[1..])
+        ),
+        WhereClause::ProjectionEq { projection, ty } => format_bindings(
+            &projection.trait_ref,
+            vec![format_binding(&projection.name, &projection.args, ty)],
+        ),
+    }
+}
+
+/// ` where A, B`, or nothing if there are no where clauses. Consecutive
+/// bindings of the associated types of the same trait reference are
+/// written together, as in `T: Trait<A = X, B = Y>`.
+fn format_where_clauses(where_clauses: &[QuantifiedWhereClause]) -> String {
+    if where_clauses.is_empty() {
+        return String::new();
+    }
+    let mut clauses: Vec<String> = vec![];
+    let mut i = 0;
+    while i < where_clauses.len() {
+        let clause = &where_clauses[i];
+        let forall = format_forall(&clause.parameter_kinds);
+        i += 1;
+        match &clause.where_clause {
+            WhereClause::ProjectionEq { projection, ty } => {
+                let mut bindings = vec![format_binding(&projection.name, &projection.args, ty)];
+                while let Some(next) = where_clauses.get(i) {
+                    match &next.where_clause {
+                        WhereClause::ProjectionEq {
+                            projection: next_projection,
+                            ty,
+                        } if next.parameter_kinds == clause.parameter_kinds
+                            && next_projection.trait_ref == projection.trait_ref =>
+                        {
+                            bindings.push(format_binding(
+                                &next_projection.name,
+                                &next_projection.args,
+                                ty,
+                            ));
+                            i += 1;
+                        }
+                        _ => break,
+                    }
+                }
+                clauses.push(format!(
+                    "{}{}",
+                    forall,
+                    format_bindings(&projection.trait_ref, bindings)
+                ));
+            }
+            where_clause => {
+                clauses.push(format!("{}{}", forall, format_where_clause(where_clause)))
+            }
+        }
+    }
+    format!(" where {}", clauses.join(", "))
+}
+
+/// `A + B`, writing consecutive bindings of the same trait together, as
+/// in `Trait<A = X, B = Y>`.
+fn format_inline_bounds(bounds: &[QuantifiedInlineBound]) -> String {
+    let mut formatted: Vec<String> = vec![];
+    let mut i = 0;
+    while i < bounds.len() {
+        let bound = &bounds[i];
+        let forall = format_forall(&bound.parameter_kinds);
+        i += 1;
+        match &bound.bound {
+            InlineBound::TraitBound(trait_bound) => formatted.push(format!(
+                "{}{}{}",
+                forall,
+                trait_bound.trait_name,
+                format_parameters(&trait_bound.args_no_self)
+            )),
+            InlineBound::AliasEqBound(alias_eq) => {
+                let mut bindings = vec![format_binding(
+                    &alias_eq.name,
+                    &alias_eq.args,
+                    &alias_eq.value,
+                )];
+                while let Some(next) = bounds.get(i) {
+                    match &next.bound {
+                        InlineBound::AliasEqBound(next_alias_eq)
+                            if next.parameter_kinds == bound.parameter_kinds
+                                && next_alias_eq.trait_bound == alias_eq.trait_bound =>
+                        {
+                            bindings.push(format_binding(
+                                &next_alias_eq.name,
+                                &next_alias_eq.args,
+                                &next_alias_eq.value,
+                            ));
+                            i += 1;
+                        }
+                        _ => break,
+                    }
+                }
+                let mut args: Vec<_> = alias_eq
+                    .trait_bound
+                    .args_no_self
+                    .iter()
+                    .map(format_parameter)
+                    .collect();
+                args.extend(bindings);
+                formatted.push(format!(
+                    "{}{}<{}>",
+                    forall,
+                    alias_eq.trait_bound.trait_name,
+                    args.join(", ")
+                ));
+            }
+        }
+    }
+    formatted.join(" + ")
+}
+
+fn format_domain_goal(goal: &DomainGoal) -> String {
+    match goal {
+        DomainGoal::Holds { where_clause } => format_where_clause(where_clause),
+        DomainGoal::Normalize { projection, ty } => format!(
+            "Normalize({} -> {})",
+            format_projection(projection),
+            format_ty(ty)
+        ),
+        DomainGoal::TraitRefWellFormed { trait_ref } => {
+            format!("WellFormed({})", format_trait_ref(trait_ref))
+        }
+        DomainGoal::TyWellFormed { ty } => format!("WellFormed({})", format_ty(ty)),
+        DomainGoal::TyFromEnv { ty } => format!("FromEnv({})", format_ty(ty)),
+        DomainGoal::TraitRefFromEnv { trait_ref } => {
+            format!("FromEnv({})", format_trait_ref(trait_ref))
+        }
+        DomainGoal::IsLocal { ty } => format!("IsLocal({})", format_ty(ty)),
+        DomainGoal::IsUpstream { ty } => format!("IsUpstream({})", format_ty(ty)),
+        DomainGoal::IsFullyVisible { ty } => format!("IsFullyVisible({})", format_ty(ty)),
+        DomainGoal::LocalImplAllowed { trait_ref } => {
+            format!("LocalImplAllowed({})", format_trait_ref(trait_ref))
+        }
+        DomainGoal::Compatible => "Compatible".to_string(),
+        DomainGoal::DownstreamType { ty } => format!("DownstreamType({})", format_ty(ty)),
+        DomainGoal::Reveal => "Reveal".to_string(),
+        DomainGoal::Predicate { name, args } => {
+            format!("{}({})", name, format_parameter_list(args))
+        }
+    }
+}
+
+fn format_goal(goal: &Goal) -> String {
+    match goal {
+        Goal::ForAll(kinds, goal) => {
+            format!(
+                "forall{} {{ {} }}",
+                format_kinds_or_empty(kinds),
+                format_goal(goal)
+            )
+        }
+        Goal::Exists(kinds, goal) => {
+            format!(
+                "exists{} {{ {} }}",
+                format_kinds_or_empty(kinds),
+                format_goal(goal)
+            )
+        }
+        Goal::Implies(clauses, goal) => {
+            let clauses: Vec<_> = clauses.iter().map(format_inline_clause).collect();
+            format!("if ({}) {{ {} }}", clauses.join("; "), format_goal(goal))
+        }
+        Goal::And(goal, goals) => {
+            let mut formatted = vec![format_goal1(goal)];
+            formatted.extend(goals.iter().map(|goal| format_goal1(goal)));
+            formatted.join(", ")
+        }
+        Goal::Or(goal, goals) => {
+            let mut formatted = vec![format_goal(goal)];
+            formatted.extend(goals.iter().map(|goal| format_goal(goal)));
+            format!("or {{ {} }}", formatted.join("; "))
+        }
+        Goal::Not(goal) => format!("not {{ {} }}", format_goal(goal)),
+        Goal::Compatible(goal) => format!("compatible {{ {} }}", format_goal(goal)),
+        Goal::Named(name) => format!("goal {}", name),
+        Goal::Leaf(LeafGoal::DomainGoal { goal }) => format_domain_goal(goal),
+        Goal::Leaf(LeafGoal::UnifyTys { a, b }) => format!("{} = {}", format_ty(a), format_ty(b)),
+        Goal::Leaf(LeafGoal::UnifyLifetimes { a, b }) => {
+            format!("{} = {}", format_lifetime(a), format_lifetime(b))
+        }
+        Goal::Leaf(LeafGoal::TypeOutlives { a, b }) => {
+            format!("{}: {}", format_ty(a), format_lifetime(b))
+        }
+        Goal::Leaf(LeafGoal::LifetimeOutlives { a, b }) => {
+            format!("{}: {}", format_lifetime(a), format_lifetime(b))
+        }
+        Goal::Leaf(LeafGoal::Method { probe }) => {
+            format!("{}.{}()", format_ty(&probe.receiver), probe.method)
+        }
+    }
+}
+
+/// The parameters of `forall` and `exists` goals, which are written in
+/// angle brackets even when there are none.
+fn format_kinds_or_empty(kinds: &[ParameterKind]) -> String {
+    if kinds.is_empty() {
+        "<>".to_string()
+    } else {
+        format_kinds(kinds)
+    }
+}
+
+/// A goal where a conjunction must be in parentheses.
+fn format_goal1(goal: &Goal) -> String {
+    match goal {
+        Goal::And(..) => format!("({})", format_goal(goal)),
+        _ => format_goal(goal),
+    }
+}
+
+/// `Consequence if A, B`, or `Consequence` if there are no conditions.
+fn format_clause_body(clause: &Clause) -> String {
+    let consequence = format_domain_goal(&clause.consequence);
+    if clause.conditions.is_empty() {
+        consequence
+    } else {
+        let conditions: Vec<_> = clause
+            .conditions
+            .iter()
+            .map(|goal| format_goal1(goal))
+            .collect();
+        format!("{} if {}", consequence, conditions.join(", "))
+    }
+}
+
+/// A clause within the `if (..)` of an implication goal.
+fn format_inline_clause(clause: &Clause) -> String {
+    if clause.parameter_kinds.is_empty() {
+        format_clause_body(clause)
+    } else {
+        format!(
+            "forall{} {{ {} }}",
+            format_kinds(&clause.parameter_kinds),
+            format_clause_body(clause)
+        )
+    }
+}
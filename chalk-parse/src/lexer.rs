@@ -0,0 +1,130 @@
+//! The tokens of a program, with their spans and kinds, for editors and
+//! other tools that highlight `.chalk` sources.
+//!
+//! Tokens are found by the lexer that LALRPOP generates for the parser,
+//! so a program is split into tokens exactly as the parser splits it.
+
+use crate::ast::Span;
+use crate::parser::{self, MatcherBuilder};
+use lalrpop_util::ParseError;
+
+/// What a token is, for the purpose of highlighting it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// A word of the grammar, like `struct`, `forall`, `u32` or
+    /// `WellFormed`.
+    Keyword,
+
+    /// A name, like `Foo`, or a path, like `std::Foo`.
+    Identifier,
+
+    /// A lifetime, like `'a` or `'static`.
+    Lifetime,
+
+    /// A number, like the `3` of `[u8; 3]`.
+    Number,
+
+    /// A symbol, like `{`, `::` or `->`.
+    Punctuation,
+
+    /// A `//` line comment or a `/* */` block comment.
+    Comment,
+
+    /// A character that cannot start a token, like `@`.
+    Unknown,
+}
+
+/// A token of a program.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+
+    /// The text of the token.
+    pub text: &'a str,
+
+    /// The byte offsets of the token in the program.
+    pub span: Span,
+}
+
+/// The tokens of `text`, in order. Unlike parsing, this never fails: a
+/// character that cannot start a token is a token of its own, of kind
+/// `Unknown`, and a block comment that is not terminated runs to the end.
+pub fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let (comments, _) = crate::comments(text);
+    let stripped = crate::blank_comments(text, &comments);
+    let mut tokens: Vec<_> = comments
+        .into_iter()
+        .map(|span| token(text, span.lo, span.hi, TokenKind::Comment))
+        .collect();
+
+    let builder = MatcherBuilder::new();
+    let classifier = Classifier::new(&builder);
+    let mut start = 0;
+    'matcher: loop {
+        for result in builder.matcher(&stripped[start..]) {
+            let (lo, hi, kind) = match result {
+                Ok((lo, token, hi)) => (start + lo, start + hi, classifier.kind(&token)),
+                // The lexer stops at a character that cannot start a token.
+                Err(ParseError::InvalidToken { location }) => {
+                    let lo = start + location;
+                    let len = stripped[lo..].chars().next().map_or(1, char::len_utf8);
+                    start = lo + len;
+                    tokens.push(token(text, lo, lo + len, TokenKind::Unknown));
+                    continue 'matcher;
+                }
+                Err(_) => unreachable!("the lexer only reports invalid tokens"),
+            };
+            tokens.push(token(text, lo, hi, kind));
+        }
+        break;
+    }
+
+    tokens.sort_by_key(|token| token.span.lo);
+    tokens
+}
+
+fn token(text: &str, lo: usize, hi: usize, kind: TokenKind) -> Token<'_> {
+    Token {
+        kind,
+        text: &text[lo..hi],
+        span: Span::new(lo, hi),
+    }
+}
+
+/// Finds the kind of the tokens of the lexer. The lexer numbers its
+/// terminals, but does not say which number stands for what, so the
+/// numbers of the identifier and path terminals are found by lexing an
+/// identifier and a path.
+struct Classifier {
+    identifier: usize,
+    path: usize,
+}
+
+impl Classifier {
+    fn new(builder: &MatcherBuilder) -> Self {
+        let terminal = |text| match builder.matcher(text).next() {
+            Some(Ok((_, parser::Token(terminal, _), _))) => terminal,
+            _ => unreachable!("`{}` is a token", text),
+        };
+        Classifier {
+            identifier: terminal("x"),
+            path: terminal("x::x"),
+        }
+    }
+
+    fn kind(&self, token: &parser::Token<'_>) -> TokenKind {
+        let parser::Token(terminal, text) = *token;
+        let first = text.chars().next().unwrap();
+        if terminal == self.identifier || terminal == self.path {
+            TokenKind::Identifier
+        } else if first == '\'' {
+            TokenKind::Lifetime
+        } else if first.is_ascii_digit() {
+            TokenKind::Number
+        } else if first.is_alphabetic() || first == '_' {
+            TokenKind::Keyword
+        } else {
+            TokenKind::Punctuation
+        }
+    }
+}
@@ -1,69 +1,257 @@
 #![recursion_limit = "1024"]
 #![allow(unused_parens)]
 
-#[macro_use]
-extern crate lalrpop_util;
-
 pub mod ast;
+mod format;
+mod lexer;
+mod template;
+
+pub use crate::format::{format_program, print_goal, print_program};
+pub use crate::lexer::{tokenize, Token, TokenKind};
+
+/// The parser generated by LALRPOP, as `lalrpop_mod!` would declare it,
+/// which also makes its lexer available to `lexer`.
 #[rustfmt::skip]
-lalrpop_mod!(pub parser);
+pub mod parser {
+    include!(concat!(env!("OUT_DIR"), "/parser.rs"));
 
+    pub(crate) use self::__intern_token::__MatcherBuilder as MatcherBuilder;
+}
+
+use crate::template::Origins;
 use lalrpop_util::ParseError;
-use std::fmt::Write;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
+/// Replaces `//` line comments and (possibly nested) `/* */` block
+/// comments with spaces, so that the byte offsets of everything else --
+/// and hence the spans of the parsed AST -- are left unchanged.
+fn strip_comments(text: &str) -> Result<String> {
+    let (comments, unterminated) = comments(text);
+    if let Some(start) = unterminated {
+        Err(format!("unterminated block comment at position {}", start))?;
+    }
+    Ok(blank_comments(text, &comments))
+}
+
+/// Replaces the `comments` of `text` with spaces, except for their line
+/// breaks.
+fn blank_comments(text: &str, comments: &[ast::Span]) -> String {
+    let mut output = text.as_bytes().to_vec();
+    for span in comments {
+        for byte in &mut output[span.lo..span.hi] {
+            if *byte != b'\n' {
+                *byte = b' ';
+            }
+        }
+    }
+    // Only whole comments, which begin and end on ASCII characters, were
+    // overwritten, so the result is still valid UTF-8.
+    String::from_utf8(output).unwrap()
+}
+
+/// The spans of the comments of `text`, and the start of the block
+/// comment that is not terminated, if there is one; its span runs to the
+/// end of `text`. The span of a line comment does not include its `\n`.
+fn comments(text: &str) -> (Vec<ast::Span>, Option<usize>) {
+    let bytes = text.as_bytes();
+    let starts_with = |i: usize, pat: &[u8]| bytes[i..].starts_with(pat);
+    let mut comments = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        if starts_with(i, b"//") {
+            let start = i;
+            while i < bytes.len() && bytes[i] != b'\n' {
+                i += 1;
+            }
+            comments.push(ast::Span::new(start, i));
+        } else if starts_with(i, b"/*") {
+            let start = i;
+            let mut depth = 0;
+            loop {
+                if i >= bytes.len() {
+                    comments.push(ast::Span::new(start, bytes.len()));
+                    return (comments, Some(start));
+                } else if starts_with(i, b"/*") {
+                    depth += 1;
+                } else if starts_with(i, b"*/") {
+                    depth -= 1;
+                } else {
+                    i += 1;
+                    continue;
+                }
+                i += 2;
+                if depth == 0 {
+                    break;
+                }
+            }
+            comments.push(ast::Span::new(start, i));
+        } else {
+            i += 1;
+        }
+    }
+    (comments, None)
+}
+
+/// A syntax error in a program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyntaxError {
+    /// What the parser found, such as "unexpected token `}`".
+    pub message: String,
+
+    /// Where in the program text the error is, if that is known. An
+    /// error in the expansion of a template is in the body of the
+    /// template or in an argument of the `expand`.
+    pub span: Option<ast::Span>,
+
+    /// The tokens that the parser would have accepted instead, by name:
+    /// `` `{` `` for a keyword or punctuation, and words such as
+    /// "identifier" for the others.
+    pub expected: Vec<String>,
+}
+
+impl SyntaxError {
+    /// A short description of the tokens that were expected, such as
+    /// "expected `{` or `where`", if that is known. Only the first few
+    /// of a long list are named.
+    pub fn expected_label(&self) -> Option<String> {
+        const NAMED: usize = 3;
+        let expected = &self.expected;
+        match expected.len() {
+            0 => None,
+            1 => Some(format!("expected {}", expected[0])),
+            n if n <= NAMED => Some(format!(
+                "expected {} or {}",
+                expected[..n - 1].join(", "),
+                expected[n - 1]
+            )),
+            n => Some(format!(
+                "expected one of {} and {} others",
+                expected[..NAMED].join(", "),
+                n - NAMED
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "parse error: {}", self.message)?;
+        if let Some(label) = self.expected_label() {
+            write!(f, "; {}", label)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SyntaxError {}
+
+/// The name of the terminal `terminal` of the grammar, as LALRPOP lists
+/// it among the expected tokens: a quoted keyword or punctuation, or the
+/// regular expression of a class of tokens.
+fn terminal_name(terminal: &str) -> String {
+    if terminal.starts_with("r#") {
+        let name = if terminal.contains("'") {
+            "lifetime"
+        } else if terminal.contains("::") {
+            "path"
+        } else if terminal.contains("[0-9]+") {
+            "number"
+        } else {
+            "identifier"
+        };
+        name.to_string()
+    } else {
+        format!("`{}`", terminal.trim_matches('"'))
+    }
+}
+
+/// The expected tokens `expected` of a parse error, by name, with the
+/// classes of tokens first since they say the most.
+fn expected_names(expected: &[String]) -> Vec<String> {
+    let (mut classes, tokens): (Vec<_>, Vec<_>) = expected
+        .iter()
+        .map(|terminal| terminal_name(terminal))
+        .partition(|name| !name.starts_with('`'));
+    classes.dedup();
+    classes.extend(tokens);
+    classes
+}
+
+/// The syntax error of the parse error `e` in a text whose bytes came
+/// from `origins`.
+fn syntax_error(e: ParseError<usize, parser::Token<'_>, &str>, origins: &Origins) -> SyntaxError {
+    let (message, span, expected) = match e {
+        ParseError::InvalidToken { location } => (
+            "invalid token".to_string(),
+            Some(origins.span(location, location)),
+            vec![],
+        ),
+        ParseError::UnrecognizedEOF { location, expected } => (
+            "unexpected end of input".to_string(),
+            Some(origins.span(location, location)),
+            expected,
+        ),
+        ParseError::UnrecognizedToken {
+            token: (lo, token, hi),
+            expected,
+        } => (
+            format!("unexpected token `{}`", token.1),
+            Some(origins.span(lo, hi)),
+            expected,
+        ),
+        ParseError::ExtraToken {
+            token: (lo, token, hi),
+        } => (
+            format!("unexpected token `{}`", token.1),
+            Some(origins.span(lo, hi)),
+            vec![],
+        ),
+        ParseError::User { error } => (error.to_string(), None, vec![]),
+    };
+    SyntaxError {
+        message,
+        span,
+        expected: expected_names(&expected),
+    }
+}
+
+/// Parses a program. A syntax error is reported as a [`SyntaxError`].
+///
+/// [`SyntaxError`]: struct.SyntaxError.html
 pub fn parse_program(text: &str) -> Result<ast::Program> {
-    match parser::ProgramParser::new().parse(text) {
+    let stripped = strip_comments(text)?;
+    let (text, origins) = template::expand_templates(&stripped)?;
+    match parser::ProgramParser::new().parse(&origins, &text) {
         Ok(v) => Ok(v),
-        Err(e) => Err(format!("parse error: {:?}", e))?,
+        Err(e) => Err(syntax_error(e, &origins))?,
     }
 }
 
 pub fn parse_ty(text: &str) -> Result<ast::Ty> {
-    match parser::TyParser::new().parse(text) {
+    let text = &strip_comments(text)?;
+    match parser::TyParser::new().parse(&Origins::unexpanded(text), text) {
         Ok(v) => Ok(v),
         Err(e) => Err(format!("error parsing `{}`: {:?}", text, e))?,
     }
 }
 
+/// Parses a goal. A syntax error is reported as a [`SyntaxError`].
+///
+/// [`SyntaxError`]: struct.SyntaxError.html
 pub fn parse_goal(text: &str) -> Result<Box<ast::Goal>> {
-    match parser::GoalParser::new().parse(text) {
+    let text = &strip_comments(text)?;
+    let origins = Origins::unexpanded(text);
+    match parser::GoalParser::new().parse(&origins, text) {
         Ok(v) => Ok(v),
-        Err(e) => {
-            let position_string = |start: usize, end: usize| {
-                let mut output = String::new();
-                let text = text.replace("\n", " ").replace("\r", " ");
-                writeln!(output, "position: `{}`", text).expect("str-write cannot fail");
-                output.push_str(&" ".repeat(11 + start));
-                output.push_str(&"^".repeat(end - start));
-                output.push_str("\n");
-                output
-            };
-            match e {
-                ParseError::InvalidToken { location } => Err(format!(
-                    "parse error: {:?}\n{}",
-                    e,
-                    position_string(location, location + 1)
-                ))?,
-                ParseError::UnrecognizedToken {
-                    token: (start, _, end),
-                    ..
-                } => Err(format!(
-                    "parse error: {:?}\n{}",
-                    e,
-                    position_string(start, end)
-                ))?,
-                ParseError::ExtraToken {
-                    token: (start, _, end),
-                    ..
-                } => Err(format!(
-                    "parse error: {:?}\n{}",
-                    e,
-                    position_string(start, end)
-                ))?,
-                _ => Err(format!("parse error: {:?}", e))?,
-            }
-        }
+        Err(e) => Err(syntax_error(e, &origins))?,
+    }
+}
+
+pub fn parse_method_probe(text: &str) -> Result<ast::MethodProbe> {
+    let text = &strip_comments(text)?;
+    match parser::MethodProbeParser::new().parse(&Origins::unexpanded(text), text) {
+        Ok(v) => Ok(v),
+        Err(e) => Err(format!("error parsing `{}`: {:?}", text, e))?,
     }
 }
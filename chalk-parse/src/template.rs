@@ -0,0 +1,361 @@
+//! Templates: families of similar items, generated by substituting
+//! identifiers.
+//!
+//! ```notrust
+//! template Implementable<trait T, type U> {
+//!     impl T for U { }
+//!     goal implementable { U: T }
+//! }
+//!
+//! expand Implementable<Clone, Foo>;
+//! ```
+//!
+//! Expansion happens on the program text, before it is parsed: each
+//! `expand` is replaced by the body of the template, with every
+//! occurrence of a parameter replaced by the corresponding argument.
+//! A template can only be expanded after its definition. Its body may
+//! expand other templates, but not itself.
+//!
+//! The expanded text keeps track of where each of its bytes came from:
+//! the body of the template or an argument of the `expand`. Spans in it
+//! are mapped back to the program text with [`Origins`].
+//!
+//! [`Origins`]: struct.Origins.html
+
+use super::Result;
+use crate::ast::Span;
+use std::collections::BTreeMap;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum ParameterKind {
+    /// `trait T`, whose argument must be a trait name.
+    Trait,
+
+    /// `type T`, whose argument can be any type.
+    Type,
+}
+
+struct Template {
+    parameters: Vec<(ParameterKind, String)>,
+    body: Expansion,
+}
+
+/// Where in the program each byte of a text that was parsed came from,
+/// to map the spans of the parsed AST to spans in the program.
+pub struct Origins {
+    /// The offset in the program of each byte of the text.
+    offsets: Vec<usize>,
+
+    /// The length of the program, where the end of the text is.
+    end: usize,
+}
+
+impl Origins {
+    /// The origins of a text that is parsed as it is written.
+    pub(crate) fn unexpanded(text: &str) -> Self {
+        Origins {
+            offsets: (0..text.len()).collect(),
+            end: text.len(),
+        }
+    }
+
+    fn offset(&self, offset: usize) -> usize {
+        self.offsets.get(offset).cloned().unwrap_or(self.end)
+    }
+
+    /// The span in the program of the bytes `lo..hi` of the text. A span
+    /// whose ends came from different places, such as a path that ends in
+    /// an argument of a template, covers the program text between them.
+    pub fn span(&self, lo: usize, hi: usize) -> Span {
+        let start = self.offset(lo);
+        let end = if hi > lo {
+            self.offset(hi - 1) + 1
+        } else {
+            start
+        };
+        Span::new(start.min(end), start.max(end))
+    }
+}
+
+/// A text being expanded, with the offset in the program of each of
+/// its bytes.
+#[derive(Clone, Default)]
+struct Expansion {
+    text: String,
+    offsets: Vec<usize>,
+}
+
+impl Expansion {
+    /// Appends `part`, a slice of `source`, whose bytes came from
+    /// `offsets`.
+    fn push(&mut self, source: &str, offsets: &[usize], part: &str) {
+        let start = offset_in(source, part);
+        self.text.push_str(part);
+        self.offsets
+            .extend_from_slice(&offsets[start..start + part.len()]);
+    }
+}
+
+/// The offset of `part`, which must be a slice of `text`, in `text`.
+fn offset_in(text: &str, part: &str) -> usize {
+    part.as_ptr() as usize - text.as_ptr() as usize
+}
+
+/// Removes the `template` definitions from `text`, replacing each
+/// `expand` with the expansion of its template.
+pub(crate) fn expand_templates(text: &str) -> Result<(String, Origins)> {
+    let offsets: Vec<_> = (0..text.len()).collect();
+    let expansion = expand(text, &offsets, &mut BTreeMap::new(), &mut vec![])?;
+    let origins = Origins {
+        offsets: expansion.offsets,
+        end: text.len(),
+    };
+    Ok((expansion.text, origins))
+}
+
+/// Expands `text`, whose bytes came from `offsets`.
+fn expand(
+    text: &str,
+    offsets: &[usize],
+    templates: &mut BTreeMap<String, Template>,
+    stack: &mut Vec<String>,
+) -> Result<Expansion> {
+    let mut output = Expansion::default();
+    let mut rest = text;
+    while let Some((start, word)) = next_word(rest) {
+        output.push(text, offsets, &rest[..start]);
+        let after = &rest[start + word.len()..];
+        rest = match word {
+            "template" => define(text, offsets, after, templates)?,
+            "expand" => {
+                let (name, arguments, after) = parse_expansion(after)?;
+                if stack.contains(&name) {
+                    Err(format!("template `{}` expands itself", name))?;
+                }
+                let expansion = instantiate(templates, &name, text, offsets, &arguments)?;
+                stack.push(name);
+                let expansion = expand(&expansion.text, &expansion.offsets, templates, stack)?;
+                output.text.push_str(&expansion.text);
+                output.offsets.extend(expansion.offsets);
+                stack.pop();
+                after
+            }
+            _ => {
+                output.push(text, offsets, word);
+                after
+            }
+        };
+    }
+    output.push(text, offsets, rest);
+    Ok(output)
+}
+
+/// Parses the rest of a `template Name<kind P, ..> { body }`
+/// definition, `text`, a slice of `source`, whose bytes came from
+/// `offsets`, returning the text that follows it.
+fn define<'t>(
+    source: &str,
+    offsets: &[usize],
+    text: &'t str,
+    templates: &mut BTreeMap<String, Template>,
+) -> Result<&'t str> {
+    let (name, rest) =
+        parse_word(text).ok_or_else(|| "expected a name after `template`".to_string())?;
+    let (parameters, rest) = parse_angle(rest)
+        .ok_or_else(|| format!("expected `<` after the name of template `{}`", name))?;
+    let parameters = parameters
+        .iter()
+        .map(|parameter| {
+            let mut words = parameter.split_whitespace();
+            let kind = match words.next() {
+                Some("trait") => ParameterKind::Trait,
+                Some("type") => ParameterKind::Type,
+                _ => Err(format!(
+                    "invalid parameter `{}` of template `{}`; expected `trait P` or `type P`",
+                    parameter, name
+                ))?,
+            };
+            match (words.next(), words.next()) {
+                (Some(parameter), None) if is_identifier(parameter) => {
+                    Ok((kind, parameter.to_string()))
+                }
+                _ => Err(format!(
+                    "invalid parameter `{}` of template `{}`; expected `trait P` or `type P`",
+                    parameter, name
+                ))?,
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let rest = rest.trim_start();
+    if !rest.starts_with('{') {
+        Err(format!(
+            "expected `{{` after the parameters of template `{}`",
+            name
+        ))?;
+    }
+    let close = matching_brace(rest).ok_or_else(|| format!("unterminated template `{}`", name))?;
+    let mut body = Expansion::default();
+    body.push(source, offsets, &rest[1..close]);
+
+    templates.insert(name.to_string(), Template { parameters, body });
+    Ok(&rest[close + 1..])
+}
+
+/// Parses the rest of an `expand Name<A, ..>;`, returning the name,
+/// the arguments and the text that follows it.
+fn parse_expansion(text: &str) -> Result<(String, Vec<&str>, &str)> {
+    let (name, rest) =
+        parse_word(text).ok_or_else(|| "expected a name after `expand`".to_string())?;
+    let (arguments, rest) =
+        parse_angle(rest).ok_or_else(|| format!("expected `<` after `expand {}`", name))?;
+    let rest = rest.trim_start();
+    if !rest.starts_with(';') {
+        Err(format!("expected `;` after `expand {}<..>`", name))?;
+    }
+    Ok((name.to_string(), arguments, &rest[1..]))
+}
+
+/// Substitutes `arguments`, slices of `source`, whose bytes came from
+/// `offsets`, for the parameters in the body of the template `name`.
+fn instantiate(
+    templates: &BTreeMap<String, Template>,
+    name: &str,
+    source: &str,
+    offsets: &[usize],
+    arguments: &[&str],
+) -> Result<Expansion> {
+    let template = templates
+        .get(name)
+        .ok_or_else(|| format!("invalid template name `{}`", name))?;
+    if arguments.len() != template.parameters.len() {
+        Err(format!(
+            "template `{}` expects {} arguments, found {}",
+            name,
+            template.parameters.len(),
+            arguments.len()
+        ))?;
+    }
+    for ((kind, parameter), argument) in template.parameters.iter().zip(arguments) {
+        if argument.is_empty()
+            || (*kind == ParameterKind::Trait && !argument.split("::").all(is_identifier))
+        {
+            Err(format!(
+                "invalid argument `{}` for parameter `{}` of template `{}`",
+                argument, parameter, name
+            ))?;
+        }
+    }
+
+    let body = &template.body;
+    let mut output = Expansion::default();
+    let mut rest = &body.text[..];
+    while let Some((start, word)) = next_word(rest) {
+        output.push(&body.text, &body.offsets, &rest[..start]);
+        match template.parameters.iter().position(|(_, p)| p == word) {
+            Some(index) => output.push(source, offsets, arguments[index]),
+            None => output.push(&body.text, &body.offsets, word),
+        }
+        rest = &rest[start + word.len()..];
+    }
+    output.push(&body.text, &body.offsets, rest);
+    Ok(output)
+}
+
+/// Finds the next identifier in `text`, skipping the names of
+/// lifetimes, returning its offset and the identifier itself.
+fn next_word(text: &str) -> Option<(usize, &str)> {
+    let mut offset = 0;
+    loop {
+        let start = offset + text[offset..].find(is_identifier_start)?;
+        let len = text[start..]
+            .find(|c: char| !is_identifier_continue(c))
+            .unwrap_or(text.len() - start);
+        let lifetime = text[..start].ends_with('\'');
+        let inside_word = text[..start].ends_with(is_identifier_continue);
+        if !lifetime && !inside_word {
+            return Some((start, &text[start..start + len]));
+        }
+        offset = start + len;
+    }
+}
+
+/// Parses an identifier at the start of `text`, after whitespace.
+fn parse_word(text: &str) -> Option<(&str, &str)> {
+    let text = text.trim_start();
+    let len = text
+        .find(|c: char| !is_identifier_continue(c))
+        .unwrap_or(text.len());
+    if is_identifier(&text[..len]) {
+        Some((&text[..len], &text[len..]))
+    } else {
+        None
+    }
+}
+
+/// Parses `<A, B, ..>` at the start of `text`, after whitespace,
+/// splitting it at the commas that are not nested in other brackets.
+fn parse_angle(text: &str) -> Option<(Vec<&str>, &str)> {
+    let text = text.trim_start();
+    if !text.starts_with('<') {
+        return None;
+    }
+    let mut items = vec![];
+    let mut depth = 0;
+    let mut item_start = 1;
+    let mut previous = ' ';
+    for (index, c) in text.char_indices() {
+        match c {
+            '<' | '(' | '[' | '{' => depth += 1,
+            // The `>` of `->` does not close a bracket.
+            '>' if previous == '-' => {}
+            '>' | ')' | ']' | '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    let item = text[item_start..index].trim();
+                    if !item.is_empty() || !items.is_empty() {
+                        items.push(item);
+                    }
+                    return Some((items, &text[index + 1..]));
+                }
+            }
+            ',' if depth == 1 => {
+                items.push(text[item_start..index].trim());
+                item_start = index + 1;
+            }
+            _ => {}
+        }
+        previous = c;
+    }
+    None
+}
+
+/// Finds the `}` closing the `{` that `text` starts with.
+fn matching_brace(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (index, c) in text.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn is_identifier(text: &str) -> bool {
+    text.starts_with(is_identifier_start) && text.chars().all(is_identifier_continue)
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_identifier_continue(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
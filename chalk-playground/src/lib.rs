@@ -0,0 +1,109 @@
+//! An entry point for running chalk in a browser playground: `run` takes
+//! the text of a program and of a goal and returns what came of solving
+//! the goal, which can be handed to JavaScript as JSON.
+//!
+//! Nothing here reads files, spawns threads or reads the clock, so this
+//! builds for `wasm32-unknown-unknown`. As a search cannot be interrupted
+//! from outside without threads, it is limited to a number of steps
+//! instead, so that a goal that takes too long does not hang the page.
+
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::interner::ChalkIr;
+use chalk_integration::query::LoweringDatabase;
+use chalk_integration::report::SolutionReport;
+use chalk_integration::tls;
+use chalk_solve::ext::*;
+use chalk_solve::{Budget, RustIrDatabase, SolverChoice};
+use serde::{Deserialize, Serialize};
+
+/// The number of steps a search may take by default.
+pub const DEFAULT_MAX_STEPS: usize = 100_000;
+
+/// How to solve the goals of the playground.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PlaygroundOptions {
+    pub solver_choice: SolverChoice,
+
+    /// The number of steps a search may take, or `None` for no limit; see
+    /// `Budget::max_steps`.
+    pub max_steps: Option<usize>,
+}
+
+impl Default for PlaygroundOptions {
+    fn default() -> Self {
+        PlaygroundOptions {
+            solver_choice: SolverChoice::default(),
+            max_steps: Some(DEFAULT_MAX_STEPS),
+        }
+    }
+}
+
+/// What came of running a goal against a program.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum PlaygroundResult {
+    /// The goal was solved. `text` is the solution as the REPL prints it.
+    Solved {
+        solution: SolutionReport,
+        text: String,
+    },
+
+    /// The search took more steps than it was allowed.
+    BudgetExhausted,
+
+    /// The program could not be parsed, lowered or checked.
+    ProgramError { message: String },
+
+    /// The goal could not be parsed or lowered.
+    GoalError { message: String },
+}
+
+impl PlaygroundResult {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+/// Parses, lowers and checks `program`, then solves `goal` against it
+/// with the default options.
+pub fn run(program: &str, goal: &str) -> PlaygroundResult {
+    run_with(program, goal, &PlaygroundOptions::default())
+}
+
+/// Like `run`, with the given options.
+pub fn run_with(program: &str, goal: &str, options: &PlaygroundOptions) -> PlaygroundResult {
+    let db = ChalkDatabase::with(program, options.solver_choice);
+    let program = match db.checked_program() {
+        Ok(program) => program,
+        Err(error) => {
+            return PlaygroundResult::ProgramError {
+                message: error.to_string(),
+            }
+        }
+    };
+
+    tls::set_current_program(&program, || {
+        let goal = match db.parse_and_lower_goal(goal) {
+            Ok(goal) => goal.into_peeled_goal(db.interner()),
+            Err(error) => {
+                return PlaygroundResult::GoalError {
+                    message: error.to_string(),
+                }
+            }
+        };
+        let budget = Budget {
+            max_steps: options.max_steps,
+            max_duration: None,
+        };
+        match db.solve_with_budget(&goal, budget) {
+            Ok(solution) => PlaygroundResult::Solved {
+                text: match &solution {
+                    Some(solution) => solution.display(&ChalkIr).to_string(),
+                    None => "No possible solution.".to_string(),
+                },
+                solution: SolutionReport::new(&solution),
+            },
+            Err(_) => PlaygroundResult::BudgetExhausted,
+        }
+    })
+}
@@ -7,26 +7,57 @@
 use chalk_derive::{Fold, HasInterner, Visit};
 use chalk_ir::cast::Cast;
 use chalk_ir::fold::shift::Shift;
+#[cfg(feature = "serde")]
+use chalk_ir::interner::SerdeInterner;
 use chalk_ir::interner::{Interner, TargetInterner};
 use chalk_ir::{
     AliasEq, AliasTy, AssocTypeId, Binders, DebruijnIndex, ImplId, OpaqueTyId, Parameter,
     ParameterKind, ProjectionTy, QuantifiedWhereClause, StructId, Substitution, ToParameter,
     TraitId, TraitRef, Ty, TyData, TypeName, WhereClause,
 };
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::iter;
 
 /// Identifier for an "associated type value" found in some impl.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct AssociatedTyValueId<I: Interner>(pub I::DefId);
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
+pub struct AssociatedTyValueId<I: Interner>(
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "I::serialize_def_id",
+            deserialize_with = "I::deserialize_def_id"
+        )
+    )]
+    pub I::DefId,
+);
 
 chalk_ir::id_fold!(AssociatedTyValueId);
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct ImplDatum<I: Interner> {
     pub polarity: Polarity,
+    pub safety: Safety,
+    pub constness: Constness,
     pub binders: Binders<ImplDatumBound<I>>,
     pub impl_type: ImplType,
     pub associated_ty_value_ids: Vec<AssociatedTyValueId<I>>,
+    pub methods: Vec<MethodDatum<I>>,
+
+    /// The clauses of impls with higher priorities are tried first by the
+    /// solvers (with the default `ClauseOrder`); `0` by default, and set
+    /// with `#[priority(n)]`.
+    pub priority: u32,
 }
 
 impl<I: Interner> ImplDatum<I> {
@@ -34,6 +65,10 @@ impl<I: Interner> ImplDatum<I> {
         self.polarity.is_positive()
     }
 
+    pub fn is_const(&self) -> bool {
+        self.constness == Constness::Const
+    }
+
     pub fn trait_id(&self) -> TraitId<I> {
         self.binders.skip_binders().trait_ref.trait_id
     }
@@ -55,30 +90,143 @@ impl<I: Interner> ImplDatum<I> {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash, HasInterner, Fold)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, HasInterner, Fold, Visit)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct ImplDatumBound<I: Interner> {
     pub trait_ref: TraitRef<I>,
     pub where_clauses: Vec<QuantifiedWhereClause<I>>,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ImplType {
     Local,
     External,
 }
 
+/// An impl that does not implement any trait, e.g.:
+///
+/// ```ignore
+/// impl<T> Foo<T> where T: Clone {
+///     fn bar(&self) -> T;
+/// }
+/// ```
+///
+/// Such impls do not contribute any program clauses; they only supply
+/// the methods considered during method probing.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
+pub struct InherentImplDatum<I: Interner> {
+    pub binders: Binders<InherentImplDatumBound<I>>,
+    pub methods: Vec<MethodDatum<I>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, HasInterner, Fold, Visit)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
+pub struct InherentImplDatumBound<I: Interner> {
+    pub self_ty: Ty<I>,
+    pub where_clauses: Vec<QuantifiedWhereClause<I>>,
+}
+
+/// A method signature, such as `fn bar<U>(&self, U) -> T where U: Clone;`,
+/// declared in a trait, a trait impl or an inherent impl.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
+pub struct MethodDatum<I: Interner> {
+    /// Name of the method.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "I::serialize_identifier",
+            deserialize_with = "I::deserialize_identifier"
+        )
+    )]
+    pub name: I::Identifier,
+
+    /// How the method takes `self`, or `None` for an associated
+    /// function, which cannot be called with method syntax.
+    pub receiver: Option<Receiver>,
+
+    /// The signature. As with associated types, the binders are in
+    /// the order `[method parameters; trait or impl parameters]`.
+    pub binders: Binders<FnSig<I>>,
+}
+
+impl<I: Interner> MethodDatum<I> {
+    /// Number of parameters declared on the method itself, excluding
+    /// those of the enclosing trait or impl.
+    pub fn num_own_parameters(&self, interner: &I, num_outer_parameters: usize) -> usize {
+        self.binders.len(interner) - num_outer_parameters
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, HasInterner, Fold, Visit)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
+pub struct FnSig<I: Interner> {
+    /// Types of the arguments following the receiver.
+    pub inputs: Vec<Ty<I>>,
+    pub output: Ty<I>,
+    pub where_clauses: Vec<QuantifiedWhereClause<I>>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Receiver {
+    /// `self`
+    Value,
+    /// `&self`
+    Ref,
+    /// `&mut self`
+    RefMut,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct DefaultImplDatum<I: Interner> {
     pub binders: Binders<DefaultImplDatumBound<I>>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct DefaultImplDatumBound<I: Interner> {
     pub trait_ref: TraitRef<I>,
     pub accessible_tys: Vec<Ty<I>>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct StructDatum<I: Interner> {
     pub binders: Binders<StructDatumBound<I>>,
     pub id: StructId<I>,
@@ -91,16 +239,36 @@ impl<I: Interner> StructDatum<I> {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, HasInterner)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct StructDatumBound<I: Interner> {
     pub fields: Vec<Ty<I>>,
     pub where_clauses: Vec<QuantifiedWhereClause<I>>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct StructFlags {
     pub upstream: bool,
     pub fundamental: bool,
+
+    /// A `union` rather than a `struct`. All of its fields overlap, so
+    /// none of them is ever dropped, and each must be either `Copy` or
+    /// a `#[manually_drop]` type.
+    pub union: bool,
+
+    /// A type like `ManuallyDrop<T>`, which never drops its contents,
+    /// and so can be the field of a union even if it is not `Copy`.
+    pub manually_drop: bool,
+
+    /// An `extern type`, whose size and layout are unknown: it has no
+    /// fields, is not `Sized`, and only implements the auto traits it
+    /// has explicit impls for.
+    pub extern_type: bool,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -128,6 +296,11 @@ pub struct StructFlags {
 ///
 /// [`ImplDatum`]: struct.ImplDatum.html
 /// [`AssociatedTyDatum`]: struct.AssociatedTyDatum.html
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct TraitDatum<I: Interner> {
     pub id: TraitId<I>,
 
@@ -138,8 +311,15 @@ pub struct TraitDatum<I: Interner> {
     /// chalk we add annotations like `#[auto]`.
     pub flags: TraitFlags,
 
+    /// Whether implementing the trait is unsafe, i.e. `unsafe trait Foo`.
+    /// Impls of the trait must have the same safety.
+    pub safety: Safety,
+
     pub associated_ty_ids: Vec<AssocTypeId<I>>,
 
+    /// The method signatures declared in the trait.
+    pub methods: Vec<MethodDatum<I>>,
+
     /// If this is a well-known trait, which one? If `None`, this is a regular,
     /// user-defined trait.
     pub well_known: Option<WellKnownTrait>,
@@ -148,11 +328,13 @@ pub struct TraitDatum<I: Interner> {
 /// A list of the traits that are "well known" to chalk, which means that
 /// the chalk-solve crate has special, hard-coded impls for them.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum WellKnownTrait {
     SizedTrait,
     CopyTrait,
     CloneTrait,
     DropTrait,
+    DerefTrait,
 }
 
 impl<I: Interner> TraitDatum<I> {
@@ -179,7 +361,12 @@ impl<I: Interner> TraitDatum<I> {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash, HasInterner)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Visit, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct TraitDatumBound<I: Interner> {
     /// Where clauses defined on the trait:
     ///
@@ -191,6 +378,7 @@ pub struct TraitDatumBound<I: Interner> {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TraitFlags {
     /// An "auto trait" is one that is "automatically implemented" for every
     /// struct, so long as no explicit impl is given.
@@ -225,6 +413,11 @@ pub struct TraitFlags {
 
 /// An inline bound, e.g. `: Foo<K>` in `impl<K, T: Foo<K>> SomeType<T>`.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum InlineBound<I: Interner> {
     TraitBound(TraitBound<I>),
     AliasEqBound(AliasEqBound<I>),
@@ -269,6 +462,11 @@ impl<I: Interner> IntoWhereClauses<I> for QuantifiedInlineBound<I> {
 /// Represents a trait bound on e.g. a type or type parameter.
 /// Does not know anything about what it's binding.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct TraitBound<I: Interner> {
     pub trait_id: TraitId<I>,
     pub args_no_self: Vec<Parameter<I>>,
@@ -294,6 +492,11 @@ impl<I: Interner> TraitBound<I> {
 /// Represents an alias equality bound on e.g. a type or type parameter.
 /// Does not know anything about what it's binding.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct AliasEqBound<I: Interner> {
     pub trait_bound: TraitBound<I>,
     pub associated_ty_id: AssocTypeId<I>,
@@ -358,6 +561,11 @@ impl<T> Anonymize for [ParameterKind<T>] {
 /// * The *where clauses* `where_clauses` are things that the impl can *assume* to be true
 ///   (but which projectors must prove).
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct AssociatedTyDatum<I: Interner> {
     /// The trait this associated type is defined in.
     pub trait_id: TraitId<I>,
@@ -366,6 +574,13 @@ pub struct AssociatedTyDatum<I: Interner> {
     pub id: AssocTypeId<I>,
 
     /// Name of this associated type.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "I::serialize_identifier",
+            deserialize_with = "I::deserialize_identifier"
+        )
+    )]
     pub name: I::Identifier,
 
     /// These binders represent the `P0...Pm` variables.  The binders
@@ -379,6 +594,11 @@ pub struct AssociatedTyDatum<I: Interner> {
 /// Encodes the parts of `AssociatedTyDatum` where the parameters
 /// `P0..Pm` are in scope (`bounds` and `where_clauses`).
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct AssociatedTyDatumBound<I: Interner> {
     /// Bounds on the associated type itself.
     ///
@@ -441,6 +661,11 @@ impl<I: Interner> AssociatedTyDatum<I> {
 /// }
 /// ```
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct AssociatedTyValue<I: Interner> {
     /// Impl in which this associated type value is found.  You might
     /// need to look at this to find the generic parameters defined on
@@ -480,6 +705,11 @@ pub struct AssociatedTyValue<I: Interner> {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct AssociatedTyValueBound<I: Interner> {
     /// Type that we normalize to. The X in `type Foo<'a> = X`.
     pub ty: Ty<I>,
@@ -491,6 +721,11 @@ pub struct AssociatedTyValueBound<I: Interner> {
 /// opaque type T: A + B = HiddenTy;
 /// ```
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct OpaqueTyDatum<I: Interner> {
     /// The placeholder `!T` that corresponds to the opaque type `T`.
     pub opaque_ty_id: OpaqueTyId<I>,
@@ -499,7 +734,12 @@ pub struct OpaqueTyDatum<I: Interner> {
     pub bound: Binders<OpaqueTyDatumBound<I>>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, HasInterner)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct OpaqueTyDatumBound<I: Interner> {
     /// The value for the "hidden type" for `opaque type Foo = ...`
     pub hidden_ty: Ty<I>,
@@ -509,6 +749,7 @@ pub struct OpaqueTyDatumBound<I: Interner> {
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Polarity {
     Positive,
     Negative,
@@ -522,3 +763,19 @@ impl Polarity {
         }
     }
 }
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Safety {
+    Safe,
+    Unsafe,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Constness {
+    /// `impl Foo for Bar`, which only applies at runtime.
+    NotConst,
+    /// `impl const Foo for Bar`, which also applies in const contexts.
+    Const,
+}
@@ -1,6 +1,8 @@
 use self::builder::ClauseBuilder;
 use self::env_elaborator::elaborate_env_clauses;
+use self::order::{ClauseOrder, PrioritizedClause};
 use self::program_clauses::ToProgramClauses;
+use self::provider::ProgramClauseProvider;
 use crate::split::Split;
 use crate::RustIrDatabase;
 use chalk_engine::context::Floundered;
@@ -9,13 +11,16 @@ use chalk_ir::could_match::CouldMatch;
 use chalk_ir::interner::Interner;
 use chalk_ir::*;
 use rustc_hash::FxHashSet;
+use std::ops::Range;
 
 pub mod builder;
 mod builtin_traits;
-mod dyn_ty;
+pub(crate) mod dyn_ty;
 mod env_elaborator;
 mod generalize;
+pub mod order;
 pub mod program_clauses;
+pub mod provider;
 
 /// For auto-traits, we generate a default rule for every struct,
 /// unless there is a manual impl for that struct given explicitly.
@@ -78,6 +83,12 @@ pub fn push_auto_trait_impls<I: Interner>(
         return;
     }
 
+    // Extern types only implement the auto traits they have impls for.
+    if struct_datum.flags.extern_type {
+        debug!("extern type");
+        return;
+    }
+
     let binders = struct_datum.binders.map_ref(|b| &b.fields);
     builder.push_binders(&binders, |builder, fields| {
         let self_ty: Ty<_> = ApplicationTy {
@@ -108,15 +119,24 @@ pub fn push_auto_trait_impls<I: Interner>(
     });
 }
 
+/// The clauses pushed for each impl, as ranges of indices into the
+/// list of clauses, along with the priority of the impl.
+type ImplPriorities = Vec<(Range<usize>, u32)>;
+
 /// Given some goal `goal` that must be proven, along with
 /// its `environment`, figures out the program clauses that apply
-/// to this goal from the Rust program. So for example if the goal
+/// to this goal from the Rust program, as generated by
+/// `clause_provider`. So for example if the goal
 /// is `Implemented(T: Clone)`, then this function might return clauses
-/// derived from the trait `Clone` and its impls.
+/// derived from the trait `Clone` and its impls. The clauses are
+/// returned in the order in which to try them, as decided by
+/// `clause_order`.
 pub(crate) fn program_clauses_for_goal<'db, I: Interner>(
     db: &'db dyn RustIrDatabase<I>,
     environment: &Environment<I>,
     goal: &DomainGoal<I>,
+    clause_provider: &dyn ProgramClauseProvider<I>,
+    clause_order: &dyn ClauseOrder<I>,
 ) -> Result<Vec<ProgramClause<I>>, Floundered> {
     debug_heading!(
         "program_clauses_for_goal(goal={:?}, environment={:?})",
@@ -125,21 +145,38 @@ pub(crate) fn program_clauses_for_goal<'db, I: Interner>(
     );
     let interner = db.interner();
 
-    // FIXME: change this to use `.chain().filter()`
-    let mut vec = vec![];
-    vec.extend(db.custom_clauses());
-    program_clauses_that_could_match(db, environment, goal, &mut vec)?;
-    vec.retain(|c| c.could_match(interner, goal));
-    vec.extend(
-        db.program_clauses_for_env(environment)
-            .iter(interner)
-            .filter(|c| (*c).could_match(interner, goal))
-            .cloned(),
-    );
-
-    debug!("vec = {:#?}", vec);
+    let mut clauses = vec![];
+    clause_provider.program_clauses(db, environment, goal, &mut clauses)?;
+    let mut clauses: Vec<_> = clauses
+        .into_iter()
+        .chain(
+            db.program_clauses_for_env(environment)
+                .iter(interner)
+                .map(|clause| PrioritizedClause {
+                    clause: clause.clone(),
+                    priority: 0,
+                }),
+        )
+        .filter(|c| c.clause.could_match(interner, goal))
+        .collect();
+    clause_order.order_clauses(interner, goal, &mut clauses);
+
+    debug!("clauses = {:#?}", clauses);
+
+    Ok(clauses.into_iter().map(|c| c.clause).collect())
+}
 
-    Ok(vec)
+/// Pushes the program clauses of the impl `impl_id`, recording its
+/// priority for them.
+fn push_impl_clauses<I: Interner>(
+    builder: &mut ClauseBuilder<'_, I>,
+    impl_id: ImplId<I>,
+    impl_priorities: &mut ImplPriorities,
+) {
+    let impl_datum = builder.db.impl_datum(impl_id);
+    let start = builder.clause_count();
+    impl_datum.to_program_clauses(builder);
+    impl_priorities.push((start..builder.clause_count(), impl_datum.priority));
 }
 
 /// Returns a set of program clauses that could possibly match
@@ -151,6 +188,7 @@ fn program_clauses_that_could_match<I: Interner>(
     environment: &Environment<I>,
     goal: &DomainGoal<I>,
     clauses: &mut Vec<ProgramClause<I>>,
+    impl_priorities: &mut ImplPriorities,
 ) -> Result<(), Floundered> {
     let interner = db.interner();
     let builder = &mut ClauseBuilder::new(db, clauses);
@@ -178,7 +216,7 @@ fn program_clauses_that_could_match<I: Interner>(
                 trait_ref.trait_id,
                 trait_ref.substitution.parameters(interner),
             ) {
-                db.impl_datum(impl_id).to_program_clauses(builder);
+                push_impl_clauses(builder, impl_id, impl_priorities);
             }
 
             // If this is a `Foo: Send` (or any auto-trait), then add
@@ -236,6 +274,14 @@ fn program_clauses_that_could_match<I: Interner>(
                 );
             }
         }
+        DomainGoal::Holds(WhereClause::ConstImplemented(trait_ref)) => {
+            for impl_id in db.impls_for_trait(
+                trait_ref.trait_id,
+                trait_ref.substitution.parameters(interner),
+            ) {
+                push_impl_clauses(builder, impl_id, impl_priorities);
+            }
+        }
         DomainGoal::Holds(WhereClause::AliasEq(alias_eq)) => match &alias_eq.alias {
             AliasTy::Projection(proj) => db
                 .associated_ty_data(proj.associated_ty_id)
@@ -287,6 +333,7 @@ fn program_clauses_that_could_match<I: Interner>(
                     builder,
                     trait_id,
                     trait_parameters,
+                    impl_priorities,
                 );
             }
             AliasTy::Opaque(_) => (),
@@ -296,6 +343,7 @@ fn program_clauses_that_could_match<I: Interner>(
             .to_program_clauses(builder),
         DomainGoal::Compatible(()) => (),
         DomainGoal::Reveal(()) => (),
+        DomainGoal::Predicate(_) => (), // Defined by the custom clauses only
     };
 
     Ok(())
@@ -319,6 +367,7 @@ fn push_program_clauses_for_associated_type_values_in_impls_of<I: Interner>(
     builder: &mut ClauseBuilder<'_, I>,
     trait_id: TraitId<I>,
     trait_parameters: &[Parameter<I>],
+    impl_priorities: &mut ImplPriorities,
 ) {
     debug_heading!(
         "push_program_clauses_for_associated_type_values_in_impls_of(\
@@ -336,11 +385,13 @@ fn push_program_clauses_for_associated_type_values_in_impls_of<I: Interner>(
 
         debug!("impl_id = {:?}", impl_id);
 
+        let start = builder.clause_count();
         for &atv_id in &impl_datum.associated_ty_value_ids {
             let atv = builder.db.associated_ty_value(atv_id);
             debug!("atv_id = {:?} atv = {:#?}", atv_id, atv);
             atv.to_program_clauses(builder);
         }
+        impl_priorities.push((start..builder.clause_count(), impl_datum.priority));
     }
 }
 
@@ -380,6 +431,8 @@ fn match_ty<I: Interner>(
                 .substitution
                 .iter(interner)
                 .map(|p| p.assert_ty_ref(interner))
+                // The type parameters bound by `for<T>` carry no clauses.
+                .filter(|ty| ty.bound(interner).is_none())
                 .map(|ty| match_ty(builder, environment, &ty))
                 .collect::<Result<_, Floundered>>()?;
         }
@@ -410,6 +463,7 @@ fn match_type_name<I: Interner>(
         TypeName::Tuple(_) => {
             builder.push_fact(WellFormed::Ty(application.clone().intern(interner)))
         }
+        TypeName::Ref(_) => builder.push_fact(WellFormed::Ty(application.clone().intern(interner))),
     }
 }
 
@@ -434,22 +488,68 @@ pub fn program_clauses_for_env<'db, I: Interner>(
     db: &'db dyn RustIrDatabase<I>,
     environment: &Environment<I>,
 ) -> ProgramClauses<I> {
-    let mut last_round = environment
+    // The clauses are kept in the order in which they are found, rather
+    // than in the order of a hash set, so that they are tried in the same
+    // order on every run and platform.
+    let mut seen = FxHashSet::default();
+    let mut last_round: Vec<_> = environment
         .clauses
         .as_slice(db.interner())
         .iter()
+        .filter(|clause| seen.insert((*clause).clone()))
         .cloned()
-        .collect::<FxHashSet<_>>();
+        .collect();
     let mut closure = last_round.clone();
-    let mut next_round = FxHashSet::default();
     while !last_round.is_empty() {
-        elaborate_env_clauses(db, &last_round.drain().collect::<Vec<_>>(), &mut next_round);
-        last_round.extend(
-            next_round
-                .drain()
-                .filter(|clause| closure.insert(clause.clone())),
-        );
+        let mut next_round = vec![];
+        elaborate_env_clauses(db, &last_round, &mut next_round);
+        last_round = next_round
+            .into_iter()
+            .filter(|clause| seen.insert(clause.clone()))
+            .collect();
+        closure.extend(last_round.iter().cloned());
     }
 
     ProgramClauses::from(db.interner(), closure)
 }
+
+/// An item of the program, whose program clauses can be inspected with
+/// `program_clauses_for_item`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ItemId<I: Interner> {
+    Struct(StructId<I>),
+    Trait(TraitId<I>),
+    Impl(ImplId<I>),
+    AssociatedTy(AssocTypeId<I>),
+    OpaqueTy(OpaqueTyId<I>),
+}
+
+/// The program clauses that `item` contributes to the program, that is,
+/// the rules that the solver uses when it proves goals that mention the
+/// item. The clauses of an impl include those of the values it gives
+/// its associated types; a negative impl contributes none. The clauses
+/// for auto traits, which a struct gets unless it has an impl of the
+/// trait, are not included.
+pub fn program_clauses_for_item<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    item: ItemId<I>,
+) -> Vec<ProgramClause<I>> {
+    let mut clauses = vec![];
+    let builder = &mut ClauseBuilder::new(db, &mut clauses);
+    match item {
+        ItemId::Struct(id) => db.struct_datum(id).to_program_clauses(builder),
+        ItemId::Trait(id) => db.trait_datum(id).to_program_clauses(builder),
+        ItemId::Impl(id) => {
+            let datum = db.impl_datum(id);
+            if datum.is_positive() {
+                datum.to_program_clauses(builder);
+                for &value_id in &datum.associated_ty_value_ids {
+                    db.associated_ty_value(value_id).to_program_clauses(builder);
+                }
+            }
+        }
+        ItemId::AssociatedTy(id) => db.associated_ty_data(id).to_program_clauses(builder),
+        ItemId::OpaqueTy(id) => db.opaque_ty_data(id).to_program_clauses(builder),
+    }
+    clauses
+}
@@ -26,6 +26,11 @@ impl<'me, I: Interner> ClauseBuilder<'me, I> {
         }
     }
 
+    /// The number of clauses in the output vector so far.
+    pub(crate) fn clause_count(&self) -> usize {
+        self.clauses.len()
+    }
+
     /// Pushes a "fact" `forall<..> { consequence }` into the set of
     /// program clauses, meaning something that we can assume to be
     /// true unconditionally. The `forall<..>` binders will be
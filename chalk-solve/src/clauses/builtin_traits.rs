@@ -26,8 +26,8 @@ pub fn add_builtin_program_clauses<I: Interner>(
         WellKnownTrait::SizedTrait => sized::add_sized_program_clauses(db, builder, trait_ref, ty),
         WellKnownTrait::CopyTrait => copy::add_copy_program_clauses(db, builder, trait_ref, ty),
         WellKnownTrait::CloneTrait => clone::add_clone_program_clauses(db, builder, trait_ref, ty),
-        // Drop impls are provided explicitly
-        WellKnownTrait::DropTrait => (),
+        // Drop and Deref impls are provided explicitly
+        WellKnownTrait::DropTrait | WellKnownTrait::DerefTrait => (),
     }
 }
 
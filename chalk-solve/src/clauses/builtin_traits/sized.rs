@@ -14,6 +14,11 @@ fn push_struct_sized_conditions<I: Interner>(
 ) {
     let struct_datum = db.struct_datum(struct_id);
 
+    // Extern types have an unknown size
+    if struct_datum.flags.extern_type {
+        return;
+    }
+
     // Structs with no fields are always Sized
     if struct_datum.binders.skip_binders().fields.is_empty() {
         builder.push_fact(trait_ref.clone());
@@ -71,7 +76,7 @@ pub fn add_sized_program_clauses<I: Interner>(
             TypeName::Struct(struct_id) => {
                 push_struct_sized_conditions(db, builder, trait_ref, *struct_id, substitution)
             }
-            TypeName::Scalar(_) => builder.push_fact(trait_ref.clone()),
+            TypeName::Scalar(_) | TypeName::Ref(_) => builder.push_fact(trait_ref.clone()),
             TypeName::Tuple(arity) => {
                 push_tuple_sized_conditions(db, builder, trait_ref, *arity, substitution)
             }
@@ -1,6 +1,7 @@
 use rustc_hash::FxHashSet;
 
 use super::{builder::ClauseBuilder, generalize};
+use crate::object_safety::is_object_safe;
 use crate::RustIrDatabase;
 use chalk_ir::{
     cast::Cast, fold::shift::Shift, interner::Interner, Binders, BoundVar, DebruijnIndex, TraitId,
@@ -71,7 +72,7 @@ pub(super) fn build_dyn_self_ty_clauses<I: Interner>(
                     push_dyn_ty_impl_clauses(db, builder, trait_ref.clone())
                 }
                 // Associated item bindings are just taken as facts (?)
-                WhereClause::AliasEq(_) => builder.push_fact(wc),
+                WhereClause::AliasEq(_) | WhereClause::ConstImplemented(_) => builder.push_fact(wc),
             });
         }
     });
@@ -86,6 +87,12 @@ fn push_dyn_ty_impl_clauses<I: Interner>(
     trait_ref: TraitRef<I>,
 ) {
     let interner = db.interner();
+
+    // A trait that is not object safe cannot be implemented by `dyn Trait`.
+    if !is_object_safe(db, trait_ref.trait_id) {
+        return;
+    }
+
     // We have some `dyn Trait`, and some `trait SuperTrait: WC`
     // which is a super trait of `Trait` (including actually
     // just being the same trait); then we want to push
@@ -159,7 +166,7 @@ pub fn super_traits<I: Interner>(
                                 }
                                 Some(tr.clone())
                             }
-                            WhereClause::AliasEq(_) => None,
+                            WhereClause::AliasEq(_) | WhereClause::ConstImplemented(_) => None,
                         })
                     })
                     .collect::<Vec<_>>()
@@ -10,7 +10,6 @@ use crate::TyData;
 use chalk_ir::interner::Interner;
 use chalk_ir::visit::{Visit, Visitor};
 use chalk_ir::DebruijnIndex;
-use rustc_hash::FxHashSet;
 
 /// When proving a `FromEnv` goal, we elaborate all `FromEnv` goals
 /// found in the environment.
@@ -21,14 +20,9 @@ use rustc_hash::FxHashSet;
 pub(super) fn elaborate_env_clauses<I: Interner>(
     db: &dyn RustIrDatabase<I>,
     in_clauses: &[ProgramClause<I>],
-    out: &mut FxHashSet<ProgramClause<I>>,
+    out: &mut Vec<ProgramClause<I>>,
 ) {
-    let mut this_round = vec![];
-    in_clauses.visit_with(
-        &mut EnvElaborator::new(db, &mut this_round),
-        DebruijnIndex::INNERMOST,
-    );
-    out.extend(this_round);
+    in_clauses.visit_with(&mut EnvElaborator::new(db, out), DebruijnIndex::INNERMOST);
 }
 
 struct EnvElaborator<'me, I: Interner> {
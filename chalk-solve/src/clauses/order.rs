@@ -0,0 +1,79 @@
+//! The order in which the solvers try the program clauses that could
+//! prove a goal.
+//!
+//! Both solvers end up with the same solutions whatever the order, but
+//! trying the most promising clauses first lets the recursive solver
+//! stop as soon as a goal is found to be ambiguous, and lets the SLG
+//! solver find the answers of a goal sooner. The order is chosen by a
+//! `ClauseOrder`, set with `Solver::set_clause_order`.
+
+use chalk_ir::interner::Interner;
+use chalk_ir::*;
+use std::fmt::Debug;
+
+/// A program clause that could prove a goal, along with its priority.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PrioritizedClause<I: Interner> {
+    pub clause: ProgramClause<I>,
+
+    /// The priority of the impl the clause comes from, as given by
+    /// `#[priority(n)]`; `0` for clauses that do not come from an
+    /// impl.
+    pub priority: u32,
+}
+
+/// Decides the order in which the solvers try the program clauses that
+/// could prove a goal.
+pub trait ClauseOrder<I: Interner>: Debug + Send + Sync {
+    /// Sorts `clauses`, the clauses that could prove `goal`, in the
+    /// order in which to try them. They come in the order in which they
+    /// were found: the clauses of the program, the impls being in the
+    /// order they are declared in, followed by those of the
+    /// environment.
+    fn order_clauses(
+        &self,
+        interner: &I,
+        goal: &DomainGoal<I>,
+        clauses: &mut [PrioritizedClause<I>],
+    );
+}
+
+/// Tries the clauses of impls with higher priorities first, keeping the
+/// order in which the clauses were found otherwise. This is the default.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ByPriority;
+
+impl<I: Interner> ClauseOrder<I> for ByPriority {
+    fn order_clauses(
+        &self,
+        _interner: &I,
+        _goal: &DomainGoal<I>,
+        clauses: &mut [PrioritizedClause<I>],
+    ) {
+        clauses.sort_by_key(|clause| std::cmp::Reverse(clause.priority));
+    }
+}
+
+/// Like `ByPriority`, but among the clauses of equal priority, tries
+/// the ones that introduce fewer variables first. Those are the most
+/// specific clauses, like `impl Clone for Vec<u32>` compared to
+/// `impl<T> Clone for Vec<T>`, and leave fewer variables unresolved.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FewestVariablesFirst;
+
+impl<I: Interner> ClauseOrder<I> for FewestVariablesFirst {
+    fn order_clauses(
+        &self,
+        interner: &I,
+        _goal: &DomainGoal<I>,
+        clauses: &mut [PrioritizedClause<I>],
+    ) {
+        clauses.sort_by_key(|clause| {
+            let variables = match clause.clause.data(interner) {
+                ProgramClauseData::Implies(_) => 0,
+                ProgramClauseData::ForAll(binders) => binders.len(interner),
+            };
+            (std::cmp::Reverse(clause.priority), variables)
+        });
+    }
+}
@@ -23,15 +23,42 @@ impl<I: Interner> ToProgramClauses<I> for ImplDatum<I> {
     /// }
     /// ```
     ///
+    /// A `~const` bound on the impl only needs to hold at runtime for
+    /// the impl to apply at runtime. If the impl is an `impl const`, we
+    /// also generate a rule that applies in const contexts, requiring
+    /// the `~const` bounds to hold in const contexts as well:
+    ///
+    /// ```notrust
+    /// -- Rule ConstImplemented-From-Impl
+    /// forall<T> {
+    ///     ConstImplemented(Vec<T>: Clone) :-
+    ///         ConstImplemented(T: Clone),
+    ///         Implemented(T: Clone).
+    /// }
+    /// ```
+    ///
     /// For a negative impl like `impl... !Clone for ...`, however, we
     /// generate nothing -- this is just a way to *opt out* from the
     /// default auto trait impls, it doesn't have any positive effect
     /// on its own.
     fn to_program_clauses(&self, builder: &mut ClauseBuilder<'_, I>) {
         if self.is_positive() {
+            let is_const = self.is_const();
             let binders = self.binders.map_ref(|b| (&b.trait_ref, &b.where_clauses));
             builder.push_binders(&binders, |builder, (trait_ref, where_clauses)| {
-                builder.push_clause(trait_ref, where_clauses);
+                builder.push_clause(
+                    trait_ref.clone(),
+                    where_clauses
+                        .iter()
+                        .filter(|qwc| match qwc.skip_binders() {
+                            WhereClause::ConstImplemented(_) => false,
+                            _ => true,
+                        })
+                        .cloned(),
+                );
+                if is_const {
+                    builder.push_clause(WhereClause::ConstImplemented(trait_ref), where_clauses);
+                }
             });
         }
     }
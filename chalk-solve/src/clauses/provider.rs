@@ -0,0 +1,68 @@
+//! Where the solvers get the program clauses that could prove a goal.
+//!
+//! The clauses are never generated for the whole program up front:
+//! each time a goal is solved, only the clauses of its domain are
+//! generated, that is those of the trait, associated type or type the
+//! goal is about, and of the impls of the trait whose types could match
+//! those of the goal. The clauses are generated by a
+//! `ProgramClauseProvider`, set with `Solver::set_clause_provider`.
+
+use super::order::PrioritizedClause;
+use super::{program_clauses_that_could_match, ImplPriorities};
+use crate::RustIrDatabase;
+use chalk_engine::context::Floundered;
+use chalk_ir::interner::Interner;
+use chalk_ir::*;
+use std::fmt::Debug;
+
+/// Generates the program clauses that could prove a goal.
+pub trait ProgramClauseProvider<I: Interner>: Debug + Send + Sync {
+    /// Pushes the program clauses that could prove `goal` onto
+    /// `clauses`, along with the priorities of the impls they come from.
+    /// This can be any superset of the clauses that prove it, but the
+    /// fewer the clauses, the faster the solving. The clauses of
+    /// `environment` are added by the solver, and must not be pushed.
+    ///
+    /// Returns `Err(Floundered)` if there are too many clauses to
+    /// enumerate, as for the impls of an auto trait for an unknown type.
+    fn program_clauses(
+        &self,
+        db: &dyn RustIrDatabase<I>,
+        environment: &Environment<I>,
+        goal: &DomainGoal<I>,
+        clauses: &mut Vec<PrioritizedClause<I>>,
+    ) -> Result<(), Floundered>;
+}
+
+/// Generates the program clauses of the goal's domain from the
+/// declarations of `RustIrDatabase`. This is the default.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FromProgram;
+
+impl<I: Interner> ProgramClauseProvider<I> for FromProgram {
+    fn program_clauses(
+        &self,
+        db: &dyn RustIrDatabase<I>,
+        environment: &Environment<I>,
+        goal: &DomainGoal<I>,
+        clauses: &mut Vec<PrioritizedClause<I>>,
+    ) -> Result<(), Floundered> {
+        let mut vec = vec![];
+        vec.extend(db.custom_clauses());
+        let mut impl_priorities: ImplPriorities = vec![];
+        program_clauses_that_could_match(db, environment, goal, &mut vec, &mut impl_priorities)?;
+
+        let mut priorities = vec![0; vec.len()];
+        for (range, priority) in impl_priorities {
+            for clause_priority in &mut priorities[range] {
+                *clause_priority = priority;
+            }
+        }
+        clauses.extend(
+            vec.into_iter()
+                .zip(priorities)
+                .map(|(clause, priority)| PrioritizedClause { clause, priority }),
+        );
+        Ok(())
+    }
+}
@@ -10,32 +10,36 @@ pub trait IsCoinductive<I: Interner> {
     /// is any trait. The latter is needed for dealing with WF
     /// requirements and cyclic traits, which generates cycles in the
     /// proof tree which must not be rejected but instead must be
-    /// treated as a success.
-    fn is_coinductive(&self, db: &dyn RustIrDatabase<I>) -> bool;
+    /// treated as a success. Goals about auto traits are only
+    /// coinductive if `coinductive_auto_traits` is set.
+    fn is_coinductive(&self, db: &dyn RustIrDatabase<I>, coinductive_auto_traits: bool) -> bool;
 }
 
 impl<I: Interner> IsCoinductive<I> for Goal<I> {
-    fn is_coinductive(&self, db: &dyn RustIrDatabase<I>) -> bool {
+    fn is_coinductive(&self, db: &dyn RustIrDatabase<I>, coinductive_auto_traits: bool) -> bool {
         let interner = db.interner();
         match self.data(interner) {
             GoalData::DomainGoal(DomainGoal::Holds(wca)) => match wca {
-                WhereClause::Implemented(tr) => {
-                    db.trait_datum(tr.trait_id).is_auto_trait()
+                WhereClause::Implemented(tr) | WhereClause::ConstImplemented(tr) => {
+                    (coinductive_auto_traits && db.trait_datum(tr.trait_id).is_auto_trait())
                         || db.trait_datum(tr.trait_id).is_coinductive_trait()
                 }
                 WhereClause::AliasEq(..) => false,
             },
             GoalData::DomainGoal(DomainGoal::WellFormed(WellFormed::Trait(..))) => true,
-            GoalData::Quantified(QuantifierKind::ForAll, goal) => {
-                goal.skip_binders().is_coinductive(db)
-            }
+            GoalData::Quantified(QuantifierKind::ForAll, goal) => goal
+                .skip_binders()
+                .is_coinductive(db, coinductive_auto_traits),
             _ => false,
         }
     }
 }
 
 impl<I: Interner> IsCoinductive<I> for UCanonical<InEnvironment<Goal<I>>> {
-    fn is_coinductive(&self, db: &dyn RustIrDatabase<I>) -> bool {
-        self.canonical.value.goal.is_coinductive(db)
+    fn is_coinductive(&self, db: &dyn RustIrDatabase<I>, coinductive_auto_traits: bool) -> bool {
+        self.canonical
+            .value
+            .goal
+            .is_coinductive(db, coinductive_auto_traits)
     }
 }
@@ -0,0 +1,263 @@
+//! Graphviz DOT graphs of the search for a solution, built from a
+//! [`ProofTree`] or from the [`TraceEvent`]s of a search, so that the
+//! search can be rendered with `dot -Tsvg` and inspected.
+//!
+//! Goals are ellipses and the clauses tried for them are boxes, with the
+//! subgoals of a clause below it. Nodes are colored by outcome: green for
+//! a unique solution, orange for an ambiguous one and red for none.
+//!
+//! [`ProofTree`]: ../proof_tree/struct.ProofTree.html
+//! [`TraceEvent`]: ../trace/enum.TraceEvent.html
+
+use crate::proof_tree::ProofTree;
+use crate::trace::TraceEvent;
+use crate::Solution;
+use chalk_engine::fallible::Fallible;
+use chalk_ir::interner::Interner;
+use std::fmt;
+
+impl<I: Interner> ProofTree<I> {
+    /// Renders this proof tree as a DOT graph.
+    pub fn dot<'a>(&'a self, interner: &'a I) -> ProofTreeDot<'a, I> {
+        ProofTreeDot {
+            tree: self,
+            interner,
+        }
+    }
+}
+
+/// A proof tree as a DOT graph; see [`ProofTree::dot`].
+///
+/// [`ProofTree::dot`]: ../proof_tree/struct.ProofTree.html#method.dot
+pub struct ProofTreeDot<'a, I: Interner> {
+    tree: &'a ProofTree<I>,
+    interner: &'a I,
+}
+
+impl<'a, I: Interner> ProofTreeDot<'a, I> {
+    fn add_tree(&self, graph: &mut Graph, tree: &ProofTree<I>, parent: Option<usize>) {
+        let goal = graph.node(
+            &format!("{:?}", tree.goal.canonical.value.goal),
+            Some(solution_label(&tree.solution, self.interner)),
+            "ellipse",
+            solution_color(&tree.solution),
+        );
+        if let Some(parent) = parent {
+            graph.edge(parent, goal, None);
+        }
+
+        let mut parent = goal;
+        if let Some(clause) = &tree.clause {
+            let clause = graph.node(&format!("{:?}", clause), None, "box", "white");
+            graph.edge(goal, clause, None);
+            parent = clause;
+        }
+        for subgoal in &tree.subgoals {
+            self.add_tree(graph, subgoal, Some(parent));
+        }
+    }
+}
+
+impl<'a, I: Interner> fmt::Display for ProofTreeDot<'a, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut graph = Graph::default();
+        self.add_tree(&mut graph, self.tree, None);
+        write!(f, "{}", graph)
+    }
+}
+
+/// The steps of a search, as reported to a [`TraceSink`], as a DOT
+/// graph. Unlike a proof tree, this includes every clause that was
+/// tried, including those that did not unify with their goal (which are
+/// grey), and the goals whose solutions were found in the cache, or
+/// whose SLG tables were reused (whose edges are dotted), which makes it
+/// the graph to look at when a search takes too long.
+///
+/// [`TraceSink`]: ../trace/trait.TraceSink.html
+pub struct TraceDot<'a, I: Interner> {
+    events: &'a [TraceEvent<I>],
+    interner: &'a I,
+}
+
+impl<'a, I: Interner> TraceDot<'a, I> {
+    /// Renders `events`, the events of a single search in the order that
+    /// they were reported, as a DOT graph.
+    pub fn new(events: &'a [TraceEvent<I>], interner: &'a I) -> Self {
+        TraceDot { events, interner }
+    }
+}
+
+impl<'a, I: Interner> fmt::Display for TraceDot<'a, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut graph = Graph::default();
+
+        // The goals being solved, innermost last, each with the clause
+        // last tried for it, which is the parent of the goals entered
+        // next.
+        let mut stack: Vec<(usize, Option<usize>)> = vec![];
+        let parent = |stack: &[(usize, Option<usize>)]| {
+            stack.last().map(|&(goal, clause)| clause.unwrap_or(goal))
+        };
+
+        for event in self.events {
+            match event {
+                TraceEvent::GoalEntered { goal, depth } => {
+                    // The SLG solver does not report when goals are
+                    // solved, so the goals left on the stack are popped
+                    // here.
+                    stack.truncate(*depth);
+                    let node = graph.node(
+                        &format!("{:?}", goal.canonical.value.goal),
+                        None,
+                        "ellipse",
+                        "white",
+                    );
+                    if let Some(parent) = parent(&stack) {
+                        graph.edge(parent, node, None);
+                    }
+                    stack.push((node, None));
+                }
+                TraceEvent::GoalSolved { goal, solution } => {
+                    if let Some((node, _)) = stack.pop() {
+                        graph.set_outcome(
+                            node,
+                            &format!("{:?}", goal.canonical.value.goal),
+                            &solution_label(solution, self.interner),
+                            solution_color(solution),
+                        );
+                    }
+                }
+                TraceEvent::CacheHit { goal, solution } => {
+                    let node = graph.node(
+                        &format!("{:?}", goal.canonical.value.goal),
+                        Some(solution_label(solution, self.interner)),
+                        "ellipse",
+                        solution_color(solution),
+                    );
+                    if let Some(parent) = parent(&stack) {
+                        graph.edge(parent, node, Some("dotted"));
+                    }
+                }
+                TraceEvent::TableReused { goal } => {
+                    let node = graph.node(
+                        &format!("{:?}", goal.canonical.value.goal),
+                        None,
+                        "ellipse",
+                        "white",
+                    );
+                    if let Some(parent) = parent(&stack) {
+                        graph.edge(parent, node, Some("dotted"));
+                    }
+                }
+                TraceEvent::ClauseTried { clause, .. } => {
+                    if let Some((goal, _)) = stack.pop() {
+                        let node = graph.node(&format!("{:?}", clause), None, "box", "white");
+                        graph.edge(goal, node, None);
+                        stack.push((goal, Some(node)));
+                    }
+                }
+                TraceEvent::UnificationFailed { .. } => {
+                    if let Some(&(_, Some(clause))) = stack.last() {
+                        graph.set_color(clause, "lightgrey");
+                    }
+                }
+            }
+        }
+
+        write!(f, "{}", graph)
+    }
+}
+
+fn solution_label<I: Interner>(solution: &Fallible<Solution<I>>, interner: &I) -> String {
+    match solution {
+        Ok(solution) => solution.display(interner).to_string(),
+        Err(_) => "No possible solution".to_string(),
+    }
+}
+
+fn solution_color<I: Interner>(solution: &Fallible<Solution<I>>) -> &'static str {
+    match solution {
+        Ok(Solution::Unique(_)) => "palegreen",
+        Ok(Solution::Ambig(_)) => "orange",
+        Err(_) => "salmon",
+    }
+}
+
+/// A graph being built. Nodes are only written out at the end, so that
+/// the outcome of a goal can be filled in once it is known.
+#[derive(Default)]
+struct Graph {
+    nodes: Vec<Node>,
+    edges: Vec<(usize, usize, Option<&'static str>)>,
+}
+
+struct Node {
+    label: String,
+    shape: &'static str,
+    color: &'static str,
+}
+
+impl Graph {
+    fn node(
+        &mut self,
+        text: &str,
+        outcome: Option<String>,
+        shape: &'static str,
+        color: &'static str,
+    ) -> usize {
+        let label = match outcome {
+            Some(outcome) => format!("{}\n{}", text, outcome),
+            None => text.to_string(),
+        };
+        self.nodes.push(Node {
+            label,
+            shape,
+            color,
+        });
+        self.nodes.len() - 1
+    }
+
+    fn set_outcome(&mut self, node: usize, text: &str, outcome: &str, color: &'static str) {
+        self.nodes[node].label = format!("{}\n{}", text, outcome);
+        self.nodes[node].color = color;
+    }
+
+    fn set_color(&mut self, node: usize, color: &'static str) {
+        self.nodes[node].color = color;
+    }
+
+    fn edge(&mut self, from: usize, to: usize, style: Option<&'static str>) {
+        self.edges.push((from, to, style));
+    }
+}
+
+impl fmt::Display for Graph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "digraph search {{")?;
+        writeln!(f, "    node [style=filled];")?;
+        for (index, node) in self.nodes.iter().enumerate() {
+            writeln!(
+                f,
+                "    n{} [label=\"{}\", shape={}, fillcolor={}];",
+                index,
+                escape(&node.label),
+                node.shape,
+                node.color
+            )?;
+        }
+        for (from, to, style) in &self.edges {
+            match style {
+                Some(style) => writeln!(f, "    n{} -> n{} [style={}];", from, to, style)?,
+                None => writeln!(f, "    n{} -> n{};", from, to)?,
+            }
+        }
+        writeln!(f, "}}")
+    }
+}
+
+/// Escapes `text` for a double-quoted DOT string.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
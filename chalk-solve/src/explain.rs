@@ -0,0 +1,520 @@
+//! Explanations of why a goal has no solution: the program clauses that
+//! could have proven it, and why each of them was rejected, down to the
+//! goals they required that have no solution in turn. See
+//! [`Solver::explain_failure`].
+//!
+//! [`Solver::explain_failure`]: ../struct.Solver.html#method.explain_failure
+
+use crate::clauses::order::ByPriority;
+use crate::clauses::program_clauses_for_goal;
+use crate::infer::InferenceTable;
+use crate::recursive::RecursiveContext;
+use crate::RustIrDatabase;
+use chalk_ir::cast::Cast;
+use chalk_ir::interner::Interner;
+use chalk_ir::*;
+use std::fmt;
+
+/// How many goals deep an explanation goes.
+const MAX_DEPTH: usize = 8;
+
+/// Why a goal has no solution.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Explanation<I: Interner> {
+    /// The goal, in canonical form.
+    pub goal: UCanonical<InEnvironment<Goal<I>>>,
+
+    pub reason: Reason<I>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Reason<I: Interner> {
+    /// The goal is a domain goal, and each of the program clauses that
+    /// could prove it was rejected. There may be no such clauses.
+    Clauses(Vec<RejectedClause<I>>),
+
+    /// The goal requires a goal that has no solution, such as one of
+    /// the goals of a conjunction, or the goal of a `forall`.
+    Subgoal(Box<Explanation<I>>),
+
+    /// The goal is an `or`, none of whose alternatives has a solution.
+    Alternatives(Vec<Explanation<I>>),
+
+    /// The goal is a `not { .. }`, whose goal has a solution.
+    Negation,
+
+    /// The goal is an equality whose sides do not unify.
+    Inequality,
+
+    /// The goals that the goal requires each have a solution, but not
+    /// a common one, or not one that the goal accepts (as when it would
+    /// constrain the placeholders of a `forall`).
+    Unexplained,
+
+    /// The goal is already being explained, by one of the goals that
+    /// require it.
+    Cycle,
+
+    /// The goal is too deep to be explained further.
+    TooDeep,
+}
+
+/// A program clause that could have proven a goal, and why it did not.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RejectedClause<I: Interner> {
+    pub clause: ProgramClause<I>,
+    pub rejection: Rejection<I>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Rejection<I: Interner> {
+    /// The parameter `index` of the trait reference of the goal, `goal`,
+    /// does not unify with `clause`, the same parameter in the
+    /// consequence of the clause.
+    Mismatch {
+        index: usize,
+        goal: Parameter<I>,
+        clause: Parameter<I>,
+    },
+
+    /// The consequence of the clause does not unify with the goal.
+    Unification,
+
+    /// A condition of the clause has no solution.
+    Condition(Box<Explanation<I>>),
+
+    /// Each condition of the clause has a solution, but not a common
+    /// one, or not one that the goal accepts.
+    Unexplained,
+}
+
+impl<I: Interner> Explanation<I> {
+    pub fn display(&self) -> ExplanationDisplay<'_, I> {
+        ExplanationDisplay { explanation: self }
+    }
+}
+
+/// Explains why goals have no solution, checking which of the goals
+/// they require have solutions with a recursive solver.
+pub(crate) struct Explainer<'me, I: Interner> {
+    program: &'me dyn RustIrDatabase<I>,
+    context: &'me mut RecursiveContext<I>,
+
+    /// The goals being explained, outermost first.
+    stack: Vec<UCanonical<InEnvironment<Goal<I>>>>,
+}
+
+impl<'me, I: Interner> Explainer<'me, I> {
+    pub(crate) fn new(
+        program: &'me dyn RustIrDatabase<I>,
+        context: &'me mut RecursiveContext<I>,
+    ) -> Self {
+        Explainer {
+            program,
+            context,
+            stack: vec![],
+        }
+    }
+
+    /// Explains why `goal` has no solution, or returns `None` if it has
+    /// one.
+    pub(crate) fn explain_failure(
+        &mut self,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+    ) -> Option<Explanation<I>> {
+        if self.has_solution(goal) {
+            None
+        } else {
+            Some(self.explain(goal, 0))
+        }
+    }
+
+    fn has_solution(&mut self, goal: &UCanonical<InEnvironment<Goal<I>>>) -> bool {
+        self.context
+            .solver(self.program)
+            .solve_root_goal(goal)
+            .is_ok()
+    }
+
+    /// Explains why `goal`, which has no solution, has none.
+    fn explain(
+        &mut self,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+        depth: usize,
+    ) -> Explanation<I> {
+        let reason = if self.stack.contains(goal) {
+            Reason::Cycle
+        } else if depth >= MAX_DEPTH {
+            Reason::TooDeep
+        } else {
+            let interner = self.program.interner();
+            self.stack.push(goal.clone());
+            let (mut infer, _, InEnvironment { environment, goal }) =
+                InferenceTable::from_canonical(interner, goal.universes, &goal.canonical);
+            let reason = self.reason(&mut infer, &environment, &goal, depth);
+            self.stack.pop();
+            reason
+        };
+        Explanation {
+            goal: goal.clone(),
+            reason,
+        }
+    }
+
+    fn reason(
+        &mut self,
+        infer: &mut InferenceTable<I>,
+        environment: &Environment<I>,
+        goal: &Goal<I>,
+        depth: usize,
+    ) -> Reason<I> {
+        let interner = self.program.interner();
+        match goal.data(interner) {
+            GoalData::Quantified(QuantifierKind::ForAll, subgoal) => {
+                let subgoal = infer.instantiate_binders_universally(interner, subgoal);
+                self.subgoal_reason(infer, environment, Some(subgoal), depth)
+            }
+            GoalData::Quantified(QuantifierKind::Exists, subgoal) => {
+                let subgoal = infer.instantiate_binders_existentially(interner, subgoal);
+                self.subgoal_reason(infer, environment, Some(subgoal), depth)
+            }
+            GoalData::Implies(clauses, subgoal) => {
+                let environment =
+                    environment.add_clauses(interner, clauses.iter(interner).cloned());
+                self.subgoal_reason(infer, &environment, Some(subgoal.clone()), depth)
+            }
+            GoalData::All(goals) => {
+                self.subgoal_reason(infer, environment, goals.iter(interner).cloned(), depth)
+            }
+            GoalData::Any(goals) => Reason::Alternatives(
+                goals
+                    .iter(interner)
+                    .map(|goal| {
+                        let goal = InEnvironment::new(environment, goal.clone());
+                        let (goal, _) = infer.canonicalize_query(interner, &goal);
+                        self.explain(&goal, depth + 1)
+                    })
+                    .collect(),
+            ),
+            GoalData::Not(_) => Reason::Negation,
+            GoalData::EqGoal(_) => Reason::Inequality,
+            GoalData::DomainGoal(domain_goal) => {
+                Reason::Clauses(self.rejected_clauses(infer, environment, domain_goal, depth))
+            }
+            GoalData::Constraint(_) | GoalData::CannotProve(()) => Reason::Unexplained,
+        }
+    }
+
+    /// Explains the first of `goals` that has no solution on its own.
+    fn subgoal_reason(
+        &mut self,
+        infer: &mut InferenceTable<I>,
+        environment: &Environment<I>,
+        goals: impl IntoIterator<Item = Goal<I>>,
+        depth: usize,
+    ) -> Reason<I> {
+        let goals = goals
+            .into_iter()
+            .map(|goal| InEnvironment::new(environment, goal));
+        match self.first_failure(infer, goals, depth) {
+            Some(explanation) => Reason::Subgoal(Box::new(explanation)),
+            None => Reason::Unexplained,
+        }
+    }
+
+    /// Explains the first of `goals` that has no solution on its own, if
+    /// any.
+    fn first_failure(
+        &mut self,
+        infer: &mut InferenceTable<I>,
+        goals: impl IntoIterator<Item = InEnvironment<Goal<I>>>,
+        depth: usize,
+    ) -> Option<Explanation<I>> {
+        let interner = self.program.interner();
+        for InEnvironment {
+            environment,
+            mut goal,
+        } in goals
+        {
+            // Where clauses are lowered to goals within empty binders,
+            // which are not worth explaining on their own.
+            while let GoalData::Quantified(_, subgoal) = goal.data(interner) {
+                if !subgoal.binders.is_empty(interner) {
+                    break;
+                }
+                goal = infer.instantiate_binders_existentially(interner, subgoal);
+            }
+            let goal = InEnvironment::new(&environment, goal);
+            let (goal, _) = infer.canonicalize_query(interner, &goal);
+            if !self.has_solution(&goal) {
+                return Some(self.explain(&goal, depth + 1));
+            }
+        }
+        None
+    }
+
+    fn rejected_clauses(
+        &mut self,
+        infer: &mut InferenceTable<I>,
+        environment: &Environment<I>,
+        goal: &DomainGoal<I>,
+        depth: usize,
+    ) -> Vec<RejectedClause<I>> {
+        // The clauses for the goal are only those that could match it.
+        // Those for the goal with fresh variables as the parameters of its
+        // trait reference also include the others, so that the parameter
+        // that they do not match can be pointed out. (A goal that
+        // flounders is ambiguous, so it is never explained.)
+        let interner = self.program.interner();
+        let clause_provider = self.context.clause_provider();
+        let program_clauses = |goal: &DomainGoal<I>| {
+            program_clauses_for_goal(
+                self.program,
+                environment,
+                goal,
+                &*clause_provider,
+                &ByPriority,
+            )
+            .unwrap_or_default()
+        };
+        let mut clauses = vec![];
+        if let Some(generalized) = generalize(infer, interner, goal) {
+            clauses.extend(program_clauses(&generalized));
+        }
+        for clause in program_clauses(goal) {
+            if !clauses.contains(&clause) {
+                clauses.push(clause);
+            }
+        }
+
+        clauses
+            .into_iter()
+            .map(|clause| {
+                let snapshot = infer.snapshot();
+                let rejection = self.rejection(infer, environment, goal, &clause, depth);
+                infer.rollback_to(snapshot);
+                RejectedClause { clause, rejection }
+            })
+            .filter(|rejected| !rejected.only_compatible(interner))
+            .collect()
+    }
+
+    /// Why `clause` does not prove `goal`.
+    fn rejection(
+        &mut self,
+        infer: &mut InferenceTable<I>,
+        environment: &Environment<I>,
+        goal: &DomainGoal<I>,
+        clause: &ProgramClause<I>,
+        depth: usize,
+    ) -> Rejection<I> {
+        let interner = self.program.interner();
+        let ProgramClauseImplication {
+            consequence,
+            conditions,
+            ..
+        } = match clause.data(interner) {
+            ProgramClauseData::Implies(implication) => implication.clone(),
+            ProgramClauseData::ForAll(implication) => {
+                infer.instantiate_binders_existentially(interner, implication)
+            }
+        };
+        let unified = match infer.unify(interner, environment, goal, &consequence) {
+            Ok(unified) => unified,
+            Err(_) => return self.mismatch(infer, environment, goal, &consequence),
+        };
+
+        let goals = conditions
+            .iter(interner)
+            .map(|condition| InEnvironment::new(environment, condition.clone()))
+            .chain(unified.goals.into_iter().map(|goal| goal.cast(interner)))
+            .collect::<Vec<_>>();
+        match self.first_failure(infer, goals, depth) {
+            Some(explanation) => Rejection::Condition(Box::new(explanation)),
+            None => Rejection::Unexplained,
+        }
+    }
+
+    /// Finds the parameter of the trait reference of `goal` that does
+    /// not unify with the same parameter of `consequence`.
+    fn mismatch(
+        &mut self,
+        infer: &mut InferenceTable<I>,
+        environment: &Environment<I>,
+        goal: &DomainGoal<I>,
+        consequence: &DomainGoal<I>,
+    ) -> Rejection<I> {
+        let interner = self.program.interner();
+        let (goal_ref, clause_ref) = match (trait_ref(goal), trait_ref(consequence)) {
+            (Some(goal_ref), Some(clause_ref)) if goal_ref.trait_id == clause_ref.trait_id => {
+                (goal_ref, clause_ref)
+            }
+            _ => return Rejection::Unification,
+        };
+        let parameters = goal_ref
+            .substitution
+            .parameters(interner)
+            .iter()
+            .zip(clause_ref.substitution.parameters(interner));
+        for (index, (goal, clause)) in parameters.enumerate() {
+            if infer.unify(interner, environment, goal, clause).is_err() {
+                return Rejection::Mismatch {
+                    index,
+                    goal: infer.normalize_deep(interner, goal),
+                    clause: infer.normalize_deep(interner, clause),
+                };
+            }
+        }
+        Rejection::Unification
+    }
+}
+
+impl<I: Interner> RejectedClause<I> {
+    /// Whether the clause was rejected because it only applies within
+    /// `compatible { .. }` goals, which is not worth pointing out.
+    fn only_compatible(&self, interner: &I) -> bool {
+        match &self.rejection {
+            Rejection::Condition(explanation) => matches!(
+                explanation.goal.canonical.value.goal.data(interner),
+                GoalData::DomainGoal(DomainGoal::Compatible(()))
+            ),
+            _ => false,
+        }
+    }
+}
+
+/// `goal` with fresh variables as the parameters of its trait reference,
+/// if it is an `Implemented` goal.
+fn generalize<I: Interner>(
+    infer: &mut InferenceTable<I>,
+    interner: &I,
+    goal: &DomainGoal<I>,
+) -> Option<DomainGoal<I>> {
+    match goal {
+        DomainGoal::Holds(WhereClause::Implemented(trait_ref)) => {
+            let parameters: Vec<_> = trait_ref
+                .substitution
+                .iter(interner)
+                .map(|parameter| {
+                    let var = infer.new_variable(UniverseIndex::root());
+                    match parameter.data(interner) {
+                        ParameterKind::Ty(_) => var.to_ty(interner).cast(interner),
+                        ParameterKind::Lifetime(_) => var.to_lifetime(interner).cast(interner),
+                    }
+                })
+                .collect();
+            let trait_ref = TraitRef {
+                trait_id: trait_ref.trait_id,
+                substitution: Substitution::from(interner, parameters),
+            };
+            Some(trait_ref.cast(interner))
+        }
+        _ => None,
+    }
+}
+
+/// The trait reference of `goal`, if it is about one.
+fn trait_ref<I: Interner>(goal: &DomainGoal<I>) -> Option<&TraitRef<I>> {
+    match goal {
+        DomainGoal::Holds(WhereClause::Implemented(trait_ref))
+        | DomainGoal::Holds(WhereClause::ConstImplemented(trait_ref))
+        | DomainGoal::WellFormed(WellFormed::Trait(trait_ref))
+        | DomainGoal::FromEnv(FromEnv::Trait(trait_ref))
+        | DomainGoal::LocalImplAllowed(trait_ref) => Some(trait_ref),
+        _ => None,
+    }
+}
+
+/// Prints an explanation with the goal that has no solution, followed by
+/// the reason, indented below it:
+///
+/// ```notrust
+/// Implemented(Vec<Bar>: Clone) has no solution
+///     rejected for<type> Implemented(Vec<^0.0>: Clone) :- Implemented(^0.0: Clone)
+///         Implemented(Bar: Clone) has no solution
+///             rejected for<> Implemented(Foo: Clone)
+///                 parameter 0 is `Bar`, which does not unify with `Foo`
+/// ```
+pub struct ExplanationDisplay<'a, I: Interner> {
+    explanation: &'a Explanation<I>,
+}
+
+impl<'a, I: Interner> ExplanationDisplay<'a, I> {
+    fn fmt_at(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        explanation: &Explanation<I>,
+        depth: usize,
+    ) -> fmt::Result {
+        writeln!(
+            f,
+            "{}{:?} has no solution",
+            "    ".repeat(depth),
+            explanation.goal.canonical.value.goal
+        )?;
+        let indent = "    ".repeat(depth + 1);
+        match &explanation.reason {
+            Reason::Clauses(clauses) if clauses.is_empty() => {
+                writeln!(f, "{}no program clause could prove it", indent)
+            }
+            Reason::Clauses(clauses) => {
+                for RejectedClause { clause, rejection } in clauses {
+                    writeln!(f, "{}rejected {:?}", indent, clause)?;
+                    self.fmt_rejection(f, rejection, depth + 2)?;
+                }
+                Ok(())
+            }
+            Reason::Subgoal(explanation) => self.fmt_at(f, explanation, depth + 1),
+            Reason::Alternatives(explanations) => {
+                for explanation in explanations {
+                    self.fmt_at(f, explanation, depth + 1)?;
+                }
+                Ok(())
+            }
+            Reason::Negation => writeln!(f, "{}the goal it negates has a solution", indent),
+            Reason::Inequality => writeln!(f, "{}its sides do not unify", indent),
+            Reason::Unexplained => writeln!(
+                f,
+                "{}the goals it requires have solutions, but not together",
+                indent
+            ),
+            Reason::Cycle => writeln!(f, "{}(already being explained above)", indent),
+            Reason::TooDeep => writeln!(f, "{}(not explained further)", indent),
+        }
+    }
+
+    fn fmt_rejection(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        rejection: &Rejection<I>,
+        depth: usize,
+    ) -> fmt::Result {
+        let indent = "    ".repeat(depth);
+        match rejection {
+            Rejection::Mismatch {
+                index,
+                goal,
+                clause,
+            } => writeln!(
+                f,
+                "{}parameter {} is `{:?}`, which does not unify with `{:?}`",
+                indent, index, goal, clause
+            ),
+            Rejection::Unification => {
+                writeln!(f, "{}its consequence does not unify with the goal", indent)
+            }
+            Rejection::Condition(explanation) => self.fmt_at(f, explanation, depth),
+            Rejection::Unexplained => writeln!(
+                f,
+                "{}its conditions have solutions, but not together",
+                indent
+            ),
+        }
+    }
+}
+
+impl<'a, I: Interner> fmt::Display for ExplanationDisplay<'a, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_at(f, self.explanation, 0)
+    }
+}
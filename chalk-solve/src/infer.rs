@@ -1,11 +1,72 @@
+//! The inference table, which tracks the values of inference variables,
+//! and the unifier that relates terms containing them.
+//!
+//! Goals that mention inference variables are solved as canonical
+//! queries, which do not depend on the table they came from:
+//! `InferenceTable::canonicalize_query` turns a goal into one, and
+//! `InferenceTable::apply_answer` applies an answer to it back to the
+//! table. Embedders caching answers by query can use the same functions
+//! as the solvers do.
+//!
+//! `InferenceTable::unify` either makes its two terms equal or leaves
+//! the table as it was. A sequence of unifications can be undone
+//! together by taking a `snapshot` first, and going back to it with
+//! `rollback_to`. Snapshots nest, and must be rolled back or committed
+//! in the reverse of the order they were taken in.
+//!
+//! Unification never builds infinite terms: binding `?T` to a term that
+//! mentions `?T` itself, like `Vec<?T>` or `fn(?T)`, directly or through
+//! other variables, fails the "occurs check" and is rejected with
+//! `NoSolution`. The same check rejects terms that name placeholders the
+//! variable cannot see.
+//!
+//! ```
+//! use chalk_engine::fallible::NoSolution;
+//! use chalk_integration::interner::ChalkIr;
+//! use chalk_ir::*;
+//! use chalk_solve::infer::InferenceTable;
+//!
+//! let interner = &ChalkIr;
+//! let environment = Environment::new(interner);
+//! let mut table = InferenceTable::new();
+//! let t = table.new_variable(UniverseIndex::ROOT).to_ty(interner);
+//! let tuple = |elements: &[Ty<ChalkIr>]| {
+//!     TyData::Apply(ApplicationTy {
+//!         name: TypeName::Tuple(elements.len()),
+//!         substitution: Substitution::from(interner, elements.iter().cloned()),
+//!     })
+//!     .intern(interner)
+//! };
+//!
+//! // `?T = (?T,)` would make `?T` an infinite type.
+//! let result = table.unify(interner, &environment, &t, &tuple(&[t.clone()]));
+//! assert_eq!(result.unwrap_err(), NoSolution);
+//!
+//! // Unifications can be undone by rolling back to a snapshot.
+//! let snapshot = table.snapshot();
+//! table.unify(interner, &environment, &t, &tuple(&[])).unwrap();
+//! assert_eq!(table.normalize_deep(interner, &t), tuple(&[]));
+//! table.rollback_to(snapshot);
+//! assert_eq!(table.normalize_deep(interner, &t), t);
+//!
+//! // `?T = ((),)` is fine, and kept by committing.
+//! let snapshot = table.snapshot();
+//! let unit = tuple(&[]);
+//! table.unify(interner, &environment, &t, &tuple(&[unit])).unwrap();
+//! table.commit(snapshot);
+//! assert_eq!(table.normalize_deep(interner, &t), tuple(&[tuple(&[])]));
+//! ```
+
 use chalk_ir::interner::{HasInterner, Interner};
 use chalk_ir::*;
 use chalk_ir::{cast::Cast, fold::Fold};
 
 pub(crate) mod canonicalize;
+mod escaping;
 pub(crate) mod instantiate;
 mod invert;
 mod normalize_deep;
+mod query;
 mod test;
 pub(crate) mod ucanonicalize;
 pub(crate) mod unify;
@@ -13,14 +74,23 @@ pub(crate) mod var;
 
 use self::var::*;
 
+pub use self::query::AnswerMapping;
+pub use self::unify::UnificationResult;
+pub use self::var::EnaVariable;
+
+/// A table of inference variables, each created in some universe, and
+/// the values they have been unified with so far.
 #[derive(Clone)]
-pub(crate) struct InferenceTable<I: Interner> {
+pub struct InferenceTable<I: Interner> {
     unify: ena::unify::InPlaceUnificationTable<EnaVariable<I>>,
     vars: Vec<EnaVariable<I>>,
     max_universe: UniverseIndex,
 }
 
-pub(crate) struct InferenceSnapshot<I: Interner> {
+/// The state of an `InferenceTable` at some point, to go back to with
+/// `InferenceTable::rollback_to`, or to keep the changes since with
+/// `InferenceTable::commit`.
+pub struct InferenceSnapshot<I: Interner> {
     unify_snapshot: ena::unify::Snapshot<ena::unify::InPlace<EnaVariable<I>>>,
     max_universe: UniverseIndex,
     vars: Vec<EnaVariable<I>>,
@@ -29,9 +99,15 @@ pub(crate) struct InferenceSnapshot<I: Interner> {
 #[allow(type_alias_bounds)]
 pub(crate) type ParameterEnaVariable<I: Interner> = ParameterKind<EnaVariable<I>>;
 
+impl<I: Interner> Default for InferenceTable<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<I: Interner> InferenceTable<I> {
     /// Create an empty inference table with no variables.
-    pub(crate) fn new() -> Self {
+    pub fn new() -> Self {
         InferenceTable {
             unify: ena::unify::UnificationTable::new(),
             vars: vec![],
@@ -72,7 +148,7 @@ impl<I: Interner> InferenceTable<I> {
     /// others created within this inference table. This universe is
     /// able to see all previously created universes (though hopefully
     /// it is only brought into contact with its logical *parents*).
-    pub(crate) fn new_universe(&mut self) -> UniverseIndex {
+    pub fn new_universe(&mut self) -> UniverseIndex {
         let u = self.max_universe.next();
         self.max_universe = u;
         debug!("new_universe: {:?}", u);
@@ -82,7 +158,7 @@ impl<I: Interner> InferenceTable<I> {
     /// Creates a new inference variable and returns its index. The
     /// kind of the variable should be known by the caller, but is not
     /// tracked directly by the inference table.
-    pub(crate) fn new_variable(&mut self, ui: UniverseIndex) -> EnaVariable<I> {
+    pub fn new_variable(&mut self, ui: UniverseIndex) -> EnaVariable<I> {
         let var = self.unify.new_key(InferenceValue::Unbound(ui));
         self.vars.push(var);
         debug!("new_variable: var={:?} ui={:?}", var, ui);
@@ -95,7 +171,7 @@ impl<I: Interner> InferenceTable<I> {
     /// must respect a stack discipline (i.e., rollback or commit
     /// snapshots in reverse order of that with which they were
     /// created).
-    pub(crate) fn snapshot(&mut self) -> InferenceSnapshot<I> {
+    pub fn snapshot(&mut self) -> InferenceSnapshot<I> {
         let unify_snapshot = self.unify.snapshot();
         let vars = self.vars.clone();
         let max_universe = self.max_universe;
@@ -107,14 +183,14 @@ impl<I: Interner> InferenceTable<I> {
     }
 
     /// Restore the table to the state it had when the snapshot was taken.
-    pub(crate) fn rollback_to(&mut self, snapshot: InferenceSnapshot<I>) {
+    pub fn rollback_to(&mut self, snapshot: InferenceSnapshot<I>) {
         self.unify.rollback_to(snapshot.unify_snapshot);
         self.vars = snapshot.vars;
         self.max_universe = snapshot.max_universe;
     }
 
     /// Make permanent the changes made since the snapshot was taken.
-    pub(crate) fn commit(&mut self, snapshot: InferenceSnapshot<I>) {
+    pub fn commit(&mut self, snapshot: InferenceSnapshot<I>) {
         self.unify.commit(snapshot.unify_snapshot);
     }
 
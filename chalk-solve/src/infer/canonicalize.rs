@@ -1,6 +1,7 @@
 use chalk_engine::fallible::*;
+use chalk_ir::flags::TypeFlags;
 use chalk_ir::fold::shift::Shift;
-use chalk_ir::fold::{Fold, Folder};
+use chalk_ir::fold::{Fold, Folder, SuperFold};
 use chalk_ir::interner::{HasInterner, Interner};
 use chalk_ir::*;
 use std::cmp::max;
@@ -109,6 +110,16 @@ where
         self
     }
 
+    fn fold_ty(&mut self, ty: &Ty<I>, outer_binder: DebruijnIndex) -> Fallible<Ty<I>> {
+        // Only inference variables and placeholders are canonicalized.
+        let flags = TypeFlags::HAS_INFERENCE_VARS | TypeFlags::HAS_PLACEHOLDERS;
+        if ty.flags(self.interner()).intersects(flags) {
+            ty.super_fold_with(self.as_dyn(), outer_binder)
+        } else {
+            Ok(ty.clone())
+        }
+    }
+
     fn fold_free_placeholder_ty(
         &mut self,
         universe: PlaceholderIndex,
@@ -0,0 +1,275 @@
+use chalk_ir::interner::Interner;
+use chalk_ir::visit::visitors::FindAny;
+use chalk_ir::visit::{Visit, VisitResult, Visitor};
+use chalk_ir::*;
+use rustc_hash::FxHashSet;
+
+use super::var::EnaVariable;
+use super::InferenceTable;
+
+impl<I: Interner> InferenceTable<I> {
+    /// Removes the placeholders that escape from the region constraints
+    /// `constraints` of a goal with `num_universes` universes, returning
+    /// `None` if that is not possible.
+    ///
+    /// A placeholder escapes if it is of universe `num_universes` or up,
+    /// and so was introduced by a `forall` goal while solving the goal,
+    /// or if it is related to an inference variable of a lower universe,
+    /// which cannot name it: peeling a goal lifts its `forall`s into the
+    /// universes of the goal, so in `exists<'x> { forall<'a> { 'x: 'a } }`
+    /// the constraint `'?0: '!1` relates the existential lifetime `'?0`
+    /// of universe 0 to the placeholder `'!1`, which would otherwise
+    /// escape into its answer. As a placeholder stands for *any* lifetime:
+    ///
+    /// - `'a: '!1` holds for all `'!1` only if `'a: 'static` does;
+    /// - `'!1: 'a` cannot hold for all `'!1`, unless `'a` is `'!1`;
+    /// - `T: '!1` holds for all `'!1` only if `T: 'static` does.
+    ///
+    /// A type outlives constraint whose type mentions an escaping
+    /// placeholder is also rejected, which is conservative. Clauses of
+    /// the environment that mention placeholders of universe
+    /// `num_universes` or up are dropped, which only makes the remaining
+    /// constraints harder to satisfy.
+    pub(crate) fn resolve_escaping_placeholders(
+        &mut self,
+        interner: &I,
+        num_universes: usize,
+        constraints: Vec<InEnvironment<Constraint<I>>>,
+    ) -> Option<Vec<InEnvironment<Constraint<I>>>> {
+        let constraints = self.normalize_deep(interner, &constraints);
+        let is_static =
+            |lifetime: &Lifetime<I>| matches!(lifetime.data(interner), LifetimeData::Static);
+        let static_lifetime = LifetimeData::Static.intern(interner);
+
+        let mut resolved = vec![];
+        let mut seen = FxHashSet::default();
+        for InEnvironment {
+            environment,
+            goal: constraint,
+        } in constraints
+        {
+            let constraint = match constraint {
+                Constraint::Outlives(a, b) => {
+                    if a == b || is_static(&a) {
+                        continue;
+                    }
+                    let a_universes = self.universes_related_to(interner, num_universes, &a);
+                    let b_universes = self.universes_related_to(interner, num_universes, &b);
+                    if escapes(interner, b_universes, &a) {
+                        return None;
+                    }
+                    if escapes(interner, a_universes, &b) {
+                        Constraint::Outlives(a, static_lifetime.clone())
+                    } else {
+                        Constraint::Outlives(a, b)
+                    }
+                }
+                Constraint::TypeOutlives(ty, lifetime) => {
+                    let ty_universes = self.universes_related_to(interner, num_universes, &ty);
+                    let lifetime_universes =
+                        self.universes_related_to(interner, num_universes, &lifetime);
+                    if escapes(interner, lifetime_universes, &ty) {
+                        return None;
+                    }
+                    if escapes(interner, ty_universes, &lifetime) {
+                        Constraint::TypeOutlives(ty, static_lifetime.clone())
+                    } else {
+                        Constraint::TypeOutlives(ty, lifetime)
+                    }
+                }
+            };
+            let environment = if escapes(interner, num_universes, &environment) {
+                let clauses = environment
+                    .clauses
+                    .iter(interner)
+                    .filter(|clause| !escapes(interner, num_universes, *clause))
+                    .cloned();
+                Environment::new(interner).add_clauses(interner, clauses)
+            } else {
+                environment
+            };
+            let constraint = InEnvironment::new(&environment, constraint);
+            if seen.insert(constraint.clone()) {
+                resolved.push(constraint);
+            }
+        }
+        Some(resolved)
+    }
+
+    /// The number of universes whose placeholders may be related to
+    /// `value` in a constraint of a goal with `num_universes` universes:
+    /// if `value` mentions inference variables, only the universes up to
+    /// the highest one of them and of the placeholders of `value` can be
+    /// named by it.
+    fn universes_related_to<T: Visit<I>>(
+        &mut self,
+        interner: &I,
+        num_universes: usize,
+        value: &T,
+    ) -> usize {
+        let universe = value.visit_with(
+            &mut VariableUniverseVisitor {
+                interner,
+                table: self,
+            },
+            DebruijnIndex::INNERMOST,
+        );
+        match universe {
+            VariableUniverse {
+                has_variables: true,
+                max,
+            } => num_universes.min(max.counter + 1),
+            _ => num_universes,
+        }
+    }
+}
+
+/// Whether `value` mentions placeholders of universes `num_universes`
+/// and up.
+fn escapes<I: Interner, T: Visit<I>>(interner: &I, num_universes: usize, value: &T) -> bool {
+    value
+        .visit_with(
+            &mut EscapingPlaceholderVisitor {
+                interner,
+                num_universes,
+            },
+            DebruijnIndex::INNERMOST,
+        )
+        .to_bool()
+}
+
+/// Finds the placeholders of universes `num_universes` and up.
+struct EscapingPlaceholderVisitor<'i, I: Interner> {
+    interner: &'i I,
+    num_universes: usize,
+}
+
+impl<'i, I: Interner> EscapingPlaceholderVisitor<'i, I> {
+    fn escapes(&self, placeholder: PlaceholderIndex) -> FindAny {
+        FindAny {
+            found: placeholder.ui.counter >= self.num_universes,
+        }
+    }
+}
+
+impl<'i, I: Interner> Visitor<'i, I> for EscapingPlaceholderVisitor<'i, I> {
+    type Result = FindAny;
+
+    fn as_dyn(&mut self) -> &mut dyn Visitor<'i, I, Result = Self::Result> {
+        self
+    }
+
+    fn interner(&self) -> &'i I {
+        self.interner
+    }
+
+    fn visit_free_placeholder_ty(
+        &mut self,
+        placeholder: PlaceholderIndex,
+        _outer_binder: DebruijnIndex,
+    ) -> Self::Result {
+        self.escapes(placeholder)
+    }
+
+    fn visit_free_placeholder_lifetime(
+        &mut self,
+        placeholder: PlaceholderIndex,
+        _outer_binder: DebruijnIndex,
+    ) -> Self::Result {
+        self.escapes(placeholder)
+    }
+}
+
+/// The highest universe of the inference variables and placeholders of
+/// a value, and whether it has inference variables at all.
+struct VariableUniverse {
+    has_variables: bool,
+    max: UniverseIndex,
+}
+
+impl VisitResult for VariableUniverse {
+    fn new() -> Self {
+        VariableUniverse {
+            has_variables: false,
+            max: UniverseIndex::ROOT,
+        }
+    }
+
+    fn return_early(&self) -> bool {
+        false
+    }
+
+    fn combine(self, other: Self) -> Self {
+        VariableUniverse {
+            has_variables: self.has_variables || other.has_variables,
+            max: self.max.max(other.max),
+        }
+    }
+}
+
+/// Finds the highest universe of the inference variables, which must
+/// be unbound, and placeholders of a value.
+struct VariableUniverseVisitor<'i, 't, I: Interner> {
+    interner: &'i I,
+    table: &'t mut InferenceTable<I>,
+}
+
+impl<'i, 't, I: Interner> VariableUniverseVisitor<'i, 't, I> {
+    fn variable(&mut self, var: InferenceVar) -> VariableUniverse {
+        VariableUniverse {
+            has_variables: true,
+            max: self.table.universe_of_unbound_var(EnaVariable::from(var)),
+        }
+    }
+
+    fn placeholder(&self, placeholder: PlaceholderIndex) -> VariableUniverse {
+        VariableUniverse {
+            has_variables: false,
+            max: placeholder.ui,
+        }
+    }
+}
+
+impl<'i, 't, I: Interner> Visitor<'i, I> for VariableUniverseVisitor<'i, 't, I> {
+    type Result = VariableUniverse;
+
+    fn as_dyn(&mut self) -> &mut dyn Visitor<'i, I, Result = Self::Result> {
+        self
+    }
+
+    fn interner(&self) -> &'i I {
+        self.interner
+    }
+
+    fn visit_free_placeholder_ty(
+        &mut self,
+        placeholder: PlaceholderIndex,
+        _outer_binder: DebruijnIndex,
+    ) -> Self::Result {
+        self.placeholder(placeholder)
+    }
+
+    fn visit_free_placeholder_lifetime(
+        &mut self,
+        placeholder: PlaceholderIndex,
+        _outer_binder: DebruijnIndex,
+    ) -> Self::Result {
+        self.placeholder(placeholder)
+    }
+
+    fn visit_inference_ty(
+        &mut self,
+        var: InferenceVar,
+        _outer_binder: DebruijnIndex,
+    ) -> Self::Result {
+        self.variable(var)
+    }
+
+    fn visit_inference_lifetime(
+        &mut self,
+        var: InferenceVar,
+        _outer_binder: DebruijnIndex,
+    ) -> Self::Result {
+        self.variable(var)
+    }
+}
@@ -127,16 +127,11 @@ impl<'a, I> IntoBindersAndValue<'a, I> for &'a Fn<I>
 where
     I: Interner,
 {
-    type Binders = std::iter::Map<std::ops::Range<usize>, fn(usize) -> chalk_ir::ParameterKind<()>>;
+    type Binders = std::iter::Cloned<std::slice::Iter<'a, ParameterKind<()>>>;
     type Value = &'a Substitution<I>;
 
-    fn into_binders_and_value(self, _interner: &'a I) -> (Self::Binders, Self::Value) {
-        fn make_lifetime(_: usize) -> ParameterKind<()> {
-            ParameterKind::Lifetime(())
-        }
-
-        let p: fn(usize) -> ParameterKind<()> = make_lifetime;
-        ((0..self.num_binders).map(p), &self.substitution)
+    fn into_binders_and_value(self, interner: &'a I) -> (Self::Binders, Self::Value) {
+        (self.binders.iter(interner).cloned(), &self.substitution)
     }
 }
 
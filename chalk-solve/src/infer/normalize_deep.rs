@@ -1,6 +1,7 @@
 use chalk_engine::fallible::*;
+use chalk_ir::flags::TypeFlags;
 use chalk_ir::fold::shift::Shift;
-use chalk_ir::fold::{Fold, Folder};
+use chalk_ir::fold::{Fold, Folder, SuperFold};
 use chalk_ir::interner::Interner;
 use chalk_ir::*;
 
@@ -18,7 +19,7 @@ impl<I: Interner> InferenceTable<I> {
     /// See also `InferenceTable::canonicalize`, which -- during real
     /// processing -- is often used to capture the "current state" of
     /// variables.
-    pub(crate) fn normalize_deep<T: Fold<I>>(&mut self, interner: &I, value: &T) -> T::Result {
+    pub fn normalize_deep<T: Fold<I>>(&mut self, interner: &I, value: &T) -> T::Result {
         value
             .fold_with(
                 &mut DeepNormalizer {
@@ -44,6 +45,18 @@ where
         self
     }
 
+    fn fold_ty(&mut self, ty: &Ty<I>, outer_binder: DebruijnIndex) -> Fallible<Ty<I>> {
+        // Only inference variables are normalized.
+        if ty
+            .flags(self.interner)
+            .intersects(TypeFlags::HAS_INFERENCE_VARS)
+        {
+            ty.super_fold_with(self.as_dyn(), outer_binder)
+        } else {
+            Ok(ty.clone())
+        }
+    }
+
     fn fold_inference_ty(
         &mut self,
         var: InferenceVar,
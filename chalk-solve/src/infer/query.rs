@@ -0,0 +1,109 @@
+use chalk_ir::fold::Fold;
+use chalk_ir::interner::{HasInterner, Interner};
+use chalk_ir::visit::Visit;
+use chalk_ir::*;
+
+use super::canonicalize::Canonicalized;
+use super::ucanonicalize::{UCanonicalized, UniverseMap};
+use super::unify::UnificationResult;
+use super::{InferenceTable, ParameterEnaVariable, ParameterEnaVariableExt};
+
+impl<I: Interner> InferenceTable<I> {
+    /// Canonicalizes `value` into a query that does not depend on this
+    /// table, so that it can be solved, or its answer looked up in a cache,
+    /// on its own. This is what the solvers do with each subgoal.
+    ///
+    /// The inference variables of `value` are resolved to their values so
+    /// far; those that remain are renumbered in order of appearance, and
+    /// the universes that appear in it are renumbered from the root. Two
+    /// values that differ only in the numbering of their variables and
+    /// universes thus give the same query.
+    ///
+    /// Also returns the mapping with which `apply_answer` applies the
+    /// answers to the query to this table.
+    pub fn canonicalize_query<T>(
+        &mut self,
+        interner: &I,
+        value: &T,
+    ) -> (UCanonical<T::Result>, AnswerMapping<I>)
+    where
+        T: Fold<I>,
+        T::Result: HasInterner<Interner = I> + Fold<I, Result = T::Result> + Visit<I>,
+    {
+        let Canonicalized {
+            quantified,
+            free_vars,
+            ..
+        } = self.canonicalize(interner, value);
+        let UCanonicalized {
+            quantified,
+            universes,
+        } = self.u_canonicalize(interner, &quantified);
+        (
+            quantified,
+            AnswerMapping {
+                free_vars,
+                universes,
+            },
+        )
+    }
+
+    /// Applies `answer`, an answer to a query canonicalized with
+    /// `canonicalize_query`, to the inference variables of the value the
+    /// query was canonicalized from. Returns the region constraints of the
+    /// answer, and the goals and constraints of binding the variables.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the variables cannot be bound to the values of `answer`,
+    /// which is only possible if it is not an answer to the query, or if
+    /// the table changed since the query was canonicalized.
+    pub fn apply_answer(
+        &mut self,
+        interner: &I,
+        mapping: AnswerMapping<I>,
+        answer: &Canonical<ConstrainedSubst<I>>,
+    ) -> UnificationResult<I> {
+        let answer = mapping.universes.map_from_canonical(interner, answer);
+        let ConstrainedSubst {
+            subst,
+            mut constraints,
+        } = self.instantiate_canonical(interner, &answer);
+        debug!("apply_answer: adding constraints {:?}", constraints);
+
+        // We use the empty environment for unification here because we're
+        // really just doing a substitution on unconstrained variables, which is
+        // guaranteed to succeed without generating any new constraints.
+        let empty_env = &Environment::new(interner);
+
+        let mut goals = vec![];
+        for (i, free_var) in mapping.free_vars.into_iter().enumerate() {
+            let subst_value = subst.at(interner, i);
+            let free_value = free_var.to_parameter(interner);
+            let result = self
+                .unify(interner, empty_env, &free_value, subst_value)
+                .unwrap_or_else(|err| {
+                    panic!(
+                        "apply_answer failed with free_var={:?}, subst_value={:?}: {:?}",
+                        free_var, subst_value, err
+                    );
+                });
+            goals.extend(result.goals);
+            constraints.extend(result.constraints);
+        }
+        UnificationResult { goals, constraints }
+    }
+}
+
+/// How the inference variables and universes of a value were renumbered
+/// by `InferenceTable::canonicalize_query`, which `apply_answer` needs to
+/// map an answer to the query back.
+#[derive(Clone, Debug)]
+pub struct AnswerMapping<I: Interner> {
+    /// The inference variables of the value, in the order of the
+    /// variables of the query.
+    pub(crate) free_vars: Vec<ParameterEnaVariable<I>>,
+
+    /// The universes of the value, by universe of the query.
+    pub(crate) universes: UniverseMap,
+}
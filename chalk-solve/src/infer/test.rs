@@ -3,6 +3,7 @@
 use super::unify::UnificationResult;
 use super::*;
 use chalk_integration::interner::ChalkIr;
+use chalk_ir::cast::Cast;
 
 #[test]
 fn infer() {
@@ -70,6 +71,31 @@ fn cycle_indirect() {
     table.unify(interner, &environment0, &a, &b).unwrap_err();
 }
 
+#[test]
+fn cycle_through_bound_variable() {
+    // exists(A, B -> A = foo B, B = foo A) ---> error
+    let interner = &ChalkIr;
+    let mut table: InferenceTable<ChalkIr> = InferenceTable::new();
+    let environment0 = Environment::new(interner);
+    let a = table.new_variable(U0).to_ty(interner);
+    let b = table.new_variable(U0).to_ty(interner);
+    table
+        .unify(interner, &environment0, &a, &ty!(apply (item 0) (expr b)))
+        .unwrap();
+    table
+        .unify(interner, &environment0, &b, &ty!(apply (item 0) (expr a)))
+        .unwrap_err();
+
+    // The failed unification left `B` unbound.
+    table
+        .unify(interner, &environment0, &b, &ty!(apply (item 1)))
+        .unwrap();
+    assert_eq!(
+        table.normalize_deep(interner, &a),
+        ty!(apply (item 0) (apply (item 1)))
+    );
+}
+
 #[test]
 fn universe_error_indirect_1() {
     // exists(A -> forall(X -> exists(B -> B = X, A = B))) ---> error
@@ -313,3 +339,50 @@ fn lifetime_constraint_indirect() {
         "InEnvironment { environment: Env([]), goal: \'!1_0: \'?2 }",
     );
 }
+
+#[test]
+fn canonicalize_query_and_apply_answer() {
+    let interner = &ChalkIr;
+
+    // `?1: Foo<?0>` in a table with an unrelated variable and an
+    // unused universe gives the same query as `?0: Foo<?1>`.
+    let mut table1 = make_table();
+    let _ = table1.new_variable(U0);
+    let a1 = table1.new_variable(U2).to_ty(interner);
+    let b1 = table1.new_variable(U2).to_ty(interner);
+    let (query1, mapping1) =
+        table1.canonicalize_query(interner, &ty!(apply (item 0) (expr b1) (expr a1)));
+
+    let mut table2: InferenceTable<ChalkIr> = InferenceTable::new();
+    let _ = table2.new_universe(); // U1
+    let a2 = table2.new_variable(U1).to_ty(interner);
+    let b2 = table2.new_variable(U1).to_ty(interner);
+    let (query2, _) = table2.canonicalize_query(interner, &ty!(apply (item 0) (expr a2) (expr b2)));
+    assert_eq!(query1, query2);
+    assert_eq!(query1.universes, 1);
+
+    // The answer `[?0 := Foo, ?1 := ?0]` binds `?1` (the first variable
+    // of the query) to `Foo` in the first table, and leaves `?0` a
+    // variable.
+    let answer = Canonical {
+        value: ConstrainedSubst {
+            subst: Substitution::from(
+                interner,
+                vec![
+                    ty!(apply (item 1)).cast(interner),
+                    ty!(bound 0).cast(interner),
+                ],
+            ),
+            constraints: vec![],
+        },
+        binders: CanonicalVarKinds::from(interner, vec![ParameterKind::Ty(U0)]),
+    };
+    let UnificationResult { goals, constraints } = table1.apply_answer(interner, mapping1, &answer);
+    assert!(goals.is_empty());
+    assert!(constraints.is_empty());
+    assert_eq!(table1.normalize_deep(interner, &b1), ty!(apply (item 1)));
+    assert!(table1
+        .normalize_deep(interner, &a1)
+        .inference_var(interner)
+        .is_some());
+}
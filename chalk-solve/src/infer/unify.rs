@@ -3,13 +3,24 @@ use super::*;
 use crate::infer::instantiate::IntoBindersAndValue;
 use chalk_engine::fallible::*;
 use chalk_ir::cast::Cast;
-use chalk_ir::fold::{Fold, Folder};
+use chalk_ir::flags::TypeFlags;
+use chalk_ir::fold::{Fold, Folder, SuperFold};
 use chalk_ir::interner::{HasInterner, Interner};
 use chalk_ir::zip::{Zip, Zipper};
 use std::fmt::Debug;
 
 impl<I: Interner> InferenceTable<I> {
-    pub(crate) fn unify<T>(
+    /// Unifies `a` and `b`, binding the inference variables of either to
+    /// make them equal. Returns the goals and region constraints under
+    /// which they are equal: equating an alias like `<?T as Iterator>::Item`
+    /// with a type produces an `AliasEq` goal, and equating lifetimes
+    /// that cannot be unified (because of their universes) produces
+    /// constraints that they outlive each other.
+    ///
+    /// Returns `NoSolution`, leaving the table as it was, if `a` and `b`
+    /// cannot be equal, including when that would bind a variable to a
+    /// term containing itself.
+    pub fn unify<T>(
         &mut self,
         interner: &I,
         environment: &Environment<I>,
@@ -47,10 +58,14 @@ struct Unifier<'t, I: Interner> {
     interner: &'t I,
 }
 
+/// The outcome of a successful unification.
 #[derive(Debug)]
-pub(crate) struct UnificationResult<I: Interner> {
-    pub(crate) goals: Vec<InEnvironment<DomainGoal<I>>>,
-    pub(crate) constraints: Vec<InEnvironment<Constraint<I>>>,
+pub struct UnificationResult<I: Interner> {
+    /// Goals that must hold for the unified terms to be equal.
+    pub goals: Vec<InEnvironment<DomainGoal<I>>>,
+    /// Region constraints that must hold for the unified terms to be
+    /// equal.
+    pub constraints: Vec<InEnvironment<Constraint<I>>>,
 }
 
 impl<'t, I: Interner> Unifier<'t, I> {
@@ -317,6 +332,19 @@ impl<'t, I: Interner> Unifier<'t, I> {
                 }
             }
 
+            (&LifetimeData::InferenceVar(var), &LifetimeData::Static)
+            | (&LifetimeData::Static, &LifetimeData::InferenceVar(var)) => {
+                // `'static` is nameable from every universe.
+                let var = EnaVariable::from(var);
+                debug!("unify_lifetime_lifetime: {:?} to 'static", var);
+                let v = LifetimeData::Static.intern(interner);
+                self.table
+                    .unify
+                    .unify_var_value(var, InferenceValue::from_lifetime(interner, v))
+                    .unwrap();
+                Ok(())
+            }
+
             (&LifetimeData::Placeholder(_), &LifetimeData::Placeholder(_)) => {
                 if a != b {
                     Ok(self.push_lifetime_eq_constraint(a.clone(), b.clone()))
@@ -325,6 +353,17 @@ impl<'t, I: Interner> Unifier<'t, I> {
                 }
             }
 
+            // `'static` outlives everything, so equating it with a
+            // placeholder only requires that the placeholder outlives it.
+            (&LifetimeData::Placeholder(_), &LifetimeData::Static) => {
+                Ok(self.push_lifetime_outlives_constraint(a.clone(), b.clone()))
+            }
+            (&LifetimeData::Static, &LifetimeData::Placeholder(_)) => {
+                Ok(self.push_lifetime_outlives_constraint(b.clone(), a.clone()))
+            }
+
+            (&LifetimeData::Static, &LifetimeData::Static) => Ok(()),
+
             (LifetimeData::BoundVar(_), _) | (_, LifetimeData::BoundVar(_)) => panic!(
                 "unification encountered bound variable: a={:?} b={:?}",
                 a, b
@@ -335,13 +374,14 @@ impl<'t, I: Interner> Unifier<'t, I> {
     }
 
     fn push_lifetime_eq_constraint(&mut self, a: Lifetime<I>, b: Lifetime<I>) {
+        self.push_lifetime_outlives_constraint(a.clone(), b.clone());
+        self.push_lifetime_outlives_constraint(b, a);
+    }
+
+    fn push_lifetime_outlives_constraint(&mut self, a: Lifetime<I>, b: Lifetime<I>) {
         self.constraints.push(InEnvironment::new(
             self.environment,
-            Constraint::Outlives(a.clone(), b.clone()),
-        ));
-        self.constraints.push(InEnvironment::new(
-            self.environment,
-            Constraint::Outlives(b, a),
+            Constraint::Outlives(a, b),
         ));
     }
 }
@@ -406,6 +446,16 @@ where
         self
     }
 
+    fn fold_ty(&mut self, ty: &Ty<I>, outer_binder: DebruijnIndex) -> Fallible<Ty<I>> {
+        // Only inference variables and placeholders are checked.
+        let flags = TypeFlags::HAS_INFERENCE_VARS | TypeFlags::HAS_PLACEHOLDERS;
+        if ty.flags(self.interner()).intersects(flags) {
+            ty.super_fold_with(self.as_dyn(), outer_binder)
+        } else {
+            Ok(ty.clone())
+        }
+    }
+
     fn fold_free_placeholder_ty(
         &mut self,
         universe: PlaceholderIndex,
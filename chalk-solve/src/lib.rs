@@ -16,12 +16,18 @@ mod test_macros;
 pub mod clauses;
 pub mod coherence;
 mod coinductive_goal;
+pub mod dot;
+pub mod explain;
 pub mod ext;
 pub mod goal_builder;
-mod infer;
+pub mod infer;
+pub mod object_safety;
+pub mod proof_tree;
 pub mod recursive;
 mod solve;
 pub mod split;
+pub mod stratification;
+pub mod trace;
 pub mod wf;
 
 pub trait RustIrDatabase<I: Interner>: Debug {
@@ -97,7 +103,12 @@ pub trait RustIrDatabase<I: Interner>: Debug {
 
 pub use clauses::program_clauses_for_env;
 
+pub use solve::AnswerCacheStats;
+pub use solve::Budget;
+pub use solve::BudgetExhausted;
+pub use solve::CancellationToken;
 pub use solve::Guidance;
 pub use solve::Solution;
 pub use solve::Solver;
 pub use solve::SolverChoice;
+pub use solve::SolverStats;
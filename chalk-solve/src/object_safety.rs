@@ -0,0 +1,155 @@
+//! Object safety: whether a trait can be used as the `Trait` in a
+//! `dyn Trait` type.
+//!
+//! A trait is object safe when it and all of its super traits are.
+//! A single trait is object safe if it does not require `Self: Sized`
+//! and each of its methods either requires `Self: Sized` (and so is
+//! not callable on a trait object) or can be dispatched through the
+//! object: it has a receiver, no type parameters of its own, and does
+//! not mention `Self` in its arguments or return type, except through
+//! projections such as `Self::Item`.
+
+use crate::clauses::dyn_ty::super_traits;
+use crate::RustIrDatabase;
+use chalk_ir::interner::Interner;
+use chalk_ir::visit::visitors::FindAny;
+use chalk_ir::visit::{SuperVisit, Visit, VisitResult, Visitor};
+use chalk_ir::*;
+use chalk_rust_ir::{MethodDatum, WellKnownTrait};
+
+pub fn is_object_safe<I: Interner>(db: &dyn RustIrDatabase<I>, trait_id: TraitId<I>) -> bool {
+    super_traits(db, trait_id)
+        .skip_binders()
+        .iter()
+        .all(|trait_ref| {
+            is_object_safe_ignoring_super_traits(db, trait_ref.skip_binders().trait_id)
+        })
+}
+
+fn is_object_safe_ignoring_super_traits<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    trait_id: TraitId<I>,
+) -> bool {
+    let interner = db.interner();
+    let trait_datum = db.trait_datum(trait_id);
+    let num_trait_parameters = trait_datum.binders.len(interner);
+
+    // `Self` is the first parameter of the trait.
+    if requires_self_sized(db, &trait_datum.binders.skip_binders().where_clauses, 0) {
+        return false;
+    }
+
+    trait_datum
+        .methods
+        .iter()
+        .all(|method| is_dispatchable(db, method, num_trait_parameters))
+}
+
+fn is_dispatchable<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    method: &MethodDatum<I>,
+    num_trait_parameters: usize,
+) -> bool {
+    let interner = db.interner();
+    let num_own_parameters = method.num_own_parameters(interner, num_trait_parameters);
+    let sig = method.binders.skip_binders();
+
+    // The trait's parameters follow the method's own, and `Self` is
+    // the first of those.
+    let self_index = num_own_parameters;
+
+    // Methods that require `Self: Sized` cannot be called on a trait
+    // object, so they do not affect object safety.
+    if requires_self_sized(db, &sig.where_clauses, self_index) {
+        return true;
+    }
+
+    let has_type_parameters = method.binders.binders.as_slice(interner)[..num_own_parameters]
+        .iter()
+        .any(|kind| kind.is_ty());
+
+    method.receiver.is_some()
+        && !has_type_parameters
+        && !mentions_self(interner, &sig.inputs, self_index)
+        && !mentions_self(interner, &sig.output, self_index)
+}
+
+/// Checks whether `where_clauses` contain `Self: Sized`, where `Self`
+/// is the bound variable with the given index.
+fn requires_self_sized<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    where_clauses: &[QuantifiedWhereClause<I>],
+    self_index: usize,
+) -> bool {
+    let interner = db.interner();
+    let sized_trait = match db.well_known_trait_id(WellKnownTrait::SizedTrait) {
+        Some(id) => id,
+        None => return false,
+    };
+
+    // Each where clause introduces its own binder, hence `ONE`.
+    let self_var = BoundVar::new(DebruijnIndex::ONE, self_index);
+    where_clauses.iter().any(|qwc| match qwc.skip_binders() {
+        WhereClause::Implemented(trait_ref) => {
+            trait_ref.trait_id == sized_trait
+                && trait_ref.self_type_parameter(interner).bound(interner) == Some(self_var)
+        }
+        WhereClause::AliasEq(_) | WhereClause::ConstImplemented(_) => false,
+    })
+}
+
+fn mentions_self<I: Interner>(interner: &I, value: &impl Visit<I>, self_index: usize) -> bool {
+    let mut finder = SelfFinder {
+        interner,
+        self_var: BoundVar::new(DebruijnIndex::INNERMOST, self_index),
+    };
+    value
+        .visit_with(&mut finder, DebruijnIndex::INNERMOST)
+        .to_bool()
+}
+
+/// Looks for occurrences of `Self` outside of projections on `Self`.
+struct SelfFinder<'i, I: Interner> {
+    interner: &'i I,
+    self_var: BoundVar,
+}
+
+impl<'i, I: Interner> SelfFinder<'i, I> {
+    fn is_self(&self, ty: &Ty<I>, outer_binder: DebruijnIndex) -> bool {
+        ty.bound(self.interner)
+            .and_then(|bound_var| bound_var.shifted_out_to(outer_binder))
+            == Some(self.self_var)
+    }
+}
+
+impl<'i, I: Interner> Visitor<'i, I> for SelfFinder<'i, I> {
+    type Result = FindAny;
+
+    fn as_dyn(&mut self) -> &mut dyn Visitor<'i, I, Result = Self::Result> {
+        self
+    }
+
+    fn interner(&self) -> &'i I {
+        self.interner
+    }
+
+    fn visit_ty(&mut self, ty: &Ty<I>, outer_binder: DebruijnIndex) -> FindAny {
+        let interner = self.interner;
+        match ty.data(interner) {
+            // `Self::Item` is fine, as the object type fixes its value.
+            TyData::Alias(AliasTy::Projection(projection))
+                if self.is_self(
+                    projection
+                        .substitution
+                        .at(interner, 0)
+                        .assert_ty_ref(interner),
+                    outer_binder,
+                ) =>
+            {
+                FindAny::new()
+            }
+            _ if self.is_self(ty, outer_binder) => FindAny::FOUND,
+            _ => ty.super_visit_with(self, outer_binder),
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! Proof trees: the derivation of a solution, recording which program
+//! clause was used to solve each goal and how its subgoals were solved
+//! in turn. See [`Solver::solve_with_proof_tree`].
+//!
+//! [`Solver::solve_with_proof_tree`]: ../struct.Solver.html#method.solve_with_proof_tree
+
+use crate::Solution;
+use chalk_engine::fallible::Fallible;
+use chalk_ir::interner::Interner;
+use chalk_ir::*;
+use std::fmt;
+
+/// How a single goal was solved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofTree<I: Interner> {
+    /// The goal, in canonical form. Its canonical variables are the
+    /// ones that the substitution of `solution` refers to.
+    pub goal: UCanonical<InEnvironment<Goal<I>>>,
+
+    /// The solution that was found for `goal`, or an error if it has
+    /// none (as for the goal of a `not { .. }` that holds).
+    pub solution: Fallible<Solution<I>>,
+
+    /// The program clause that `goal` was solved with. This is `None`
+    /// for goals that are not domain goals, which are solved by
+    /// solving their parts, and for goals that were not solved again
+    /// because they were being solved already (in a cycle) or were too
+    /// deep.
+    pub clause: Option<ProgramClause<I>>,
+
+    /// The proof trees of the goals that solving `goal` required, in
+    /// the order that they were solved.
+    pub subgoals: Vec<ProofTree<I>>,
+}
+
+impl<I: Interner> ProofTree<I> {
+    pub fn display<'a>(&'a self, interner: &'a I) -> ProofTreeDisplay<'a, I> {
+        ProofTreeDisplay {
+            tree: self,
+            interner,
+        }
+    }
+}
+
+/// Prints a proof tree with one goal per line, followed by the clause it
+/// was solved with, indenting the subgoals below their goal:
+///
+/// ```notrust
+/// Implemented(Vec<Foo>: Clone) => Unique; substitution [], lifetime constraints []
+///     by forall<T> { Implemented(Vec<T>: Clone) :- Implemented(T: Clone) }
+///     Implemented(Foo: Clone) => Unique; substitution [], lifetime constraints []
+///         by Implemented(Foo: Clone)
+/// ```
+pub struct ProofTreeDisplay<'a, I: Interner> {
+    tree: &'a ProofTree<I>,
+    interner: &'a I,
+}
+
+impl<'a, I: Interner> ProofTreeDisplay<'a, I> {
+    fn fmt_at(&self, f: &mut fmt::Formatter<'_>, tree: &ProofTree<I>, depth: usize) -> fmt::Result {
+        let indent = "    ".repeat(depth);
+        write!(f, "{}{:?} => ", indent, tree.goal.canonical.value.goal)?;
+        match &tree.solution {
+            Ok(solution) => writeln!(f, "{}", solution.display(self.interner))?,
+            Err(_) => writeln!(f, "No possible solution")?,
+        }
+        if let Some(clause) = &tree.clause {
+            writeln!(f, "{}    by {:?}", indent, clause)?;
+        }
+        for subgoal in &tree.subgoals {
+            self.fmt_at(f, subgoal, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, I: Interner> fmt::Display for ProofTreeDisplay<'a, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_at(f, self.tree, 0)
+    }
+}
@@ -1,27 +1,84 @@
 use super::*;
 
 mod fulfill;
+mod proof;
 mod search_graph;
 mod stack;
 
 use self::fulfill::Fulfill;
+use self::proof::{ProofFrame, ProofRecorder};
 use self::search_graph::{DepthFirstNumber, SearchGraph};
 use self::stack::{Stack, StackDepth};
+use crate::proof_tree::ProofTree;
+use crate::trace::{TraceEvent, TraceSink};
 use chalk_engine::{
     context::Floundered,
     fallible::{Fallible, NoSolution},
 };
+use chalk_ir::fold::shift::Shift;
+use clauses::order::{ByPriority, ClauseOrder};
 use clauses::program_clauses_for_goal;
-use rustc_hash::FxHashMap;
+use clauses::provider::{FromProgram, ProgramClauseProvider};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::mem;
+use std::sync::Arc;
 
 type UCanonicalGoal<I> = UCanonical<InEnvironment<Goal<I>>>;
 
 pub(crate) struct RecursiveContext<I: Interner> {
     stack: Stack,
     search_graph: SearchGraph<I>,
-    cache: FxHashMap<UCanonicalGoal<I>, Fallible<Solution<I>>>,
+    cache: FxHashMap<UCanonicalGoal<I>, CacheEntry<I>>,
 
     caching_enabled: bool,
+
+    /// The overflow depth up to which root goals whose subgoals overflow
+    /// are solved again with deeper overflow depths, if they are; see
+    /// `solve_root_goal`.
+    max_deepening_depth: Option<usize>,
+
+    /// Set when a goal is given up on because of the overflow depth, or
+    /// because solving was stopped. The solutions that depend on such a
+    /// goal are never cached, as they might be different when the goal
+    /// is reached less deeply or solved to the end.
+    overflowed: bool,
+
+    /// The goals on the stack when a goal was first given up on because
+    /// of the overflow depth, while solving the last root goal: the root
+    /// goal first, each goal followed by the subgoal it was solving, and
+    /// the goal given up on last.
+    overflow_stack: Option<Vec<UCanonicalGoal<I>>>,
+
+    /// Records the proof trees of the goals, when requested.
+    proof: Option<ProofRecorder<I>>,
+
+    /// Receives the steps taken by the solver, if set.
+    trace: Option<Box<dyn TraceSink<I>>>,
+
+    /// The traits whose impls the goal being solved depends on so far.
+    traits: FxHashSet<TraitId<I>>,
+
+    /// Generates the program clauses for a goal.
+    clause_provider: Arc<dyn ProgramClauseProvider<I>>,
+
+    /// The order in which the program clauses for a goal are tried.
+    clause_order: Arc<dyn ClauseOrder<I>>,
+
+    /// Whether goals about auto traits are coinductive.
+    coinductive_auto_traits: bool,
+
+    /// The work done to solve the last root goal.
+    stats: SolverStats,
+}
+
+/// A solution in the cache of the recursive solver.
+pub(super) struct CacheEntry<I: Interner> {
+    solution: Fallible<Solution<I>>,
+
+    /// The traits whose impls the solution depends on, directly or
+    /// through the solutions of its subgoals. The solution must be
+    /// discarded when one of their impls is added or removed.
+    traits: FxHashSet<TraitId<I>>,
 }
 
 /// A Solver is the basic context in which you can propose goals for a given
@@ -32,6 +89,44 @@ pub(crate) struct RecursiveContext<I: Interner> {
 pub(crate) struct Solver<'me, I: Interner> {
     program: &'me dyn RustIrDatabase<I>,
     context: &'me mut RecursiveContext<I>,
+
+    /// Checked before solving each new goal; once it returns `false`,
+    /// goals are given up on as ambiguous.
+    should_continue: &'me dyn std::ops::Fn() -> bool,
+}
+
+/// Passes the distinct answers of a root goal on to a callback as they
+/// are found, each once the next one is, so that the callback can be told
+/// whether another answer follows.
+struct AnswerStream<'f, I: Interner> {
+    seen: Vec<Solution<I>>,
+    pending: Option<Solution<I>>,
+    f: &'f mut dyn FnMut(Solution<I>, bool) -> bool,
+}
+
+impl<I: Interner> AnswerStream<'_, I> {
+    /// Records `answer`, unless it was found before, passing on the
+    /// answer before it. Returns `false` if the callback wants no more
+    /// answers.
+    fn push(&mut self, answer: Solution<I>) -> bool {
+        if self.seen.contains(&answer) {
+            return true;
+        }
+        self.seen.push(answer.clone());
+        match self.pending.replace(answer) {
+            Some(previous) => (self.f)(previous, true),
+            None => true,
+        }
+    }
+
+    /// Passes on the last answer, if there is one, telling the callback
+    /// whether something follows it. Returns what the callback returns.
+    fn finish(self, has_next: bool) -> bool {
+        match self.pending {
+            Some(last) => (self.f)(last, has_next),
+            None => true,
+        }
+    }
 }
 
 /// The `minimums` struct is used while solving to track whether we encountered
@@ -62,22 +157,100 @@ impl<T> MergeWith<T> for Fallible<T> {
 }
 
 impl<I: Interner> RecursiveContext<I> {
-    pub(crate) fn new(overflow_depth: usize, caching_enabled: bool) -> Self {
+    pub(crate) fn new(
+        overflow_depth: usize,
+        caching_enabled: bool,
+        max_deepening_depth: Option<usize>,
+    ) -> Self {
         RecursiveContext {
             stack: Stack::new(overflow_depth),
             search_graph: SearchGraph::new(),
             cache: FxHashMap::default(),
             caching_enabled,
+            max_deepening_depth,
+            overflowed: false,
+            overflow_stack: None,
+            proof: None,
+            trace: None,
+            traits: FxHashSet::default(),
+            clause_provider: Arc::new(FromProgram),
+            clause_order: Arc::new(ByPriority),
+            coinductive_auto_traits: true,
+            stats: SolverStats::default(),
         }
     }
 
+    pub(crate) fn set_trace_sink(&mut self, sink: Option<Box<dyn TraceSink<I>>>) {
+        self.trace = sink;
+    }
+
+    /// Sets how the program clauses for a goal are generated, discarding
+    /// the cached solutions.
+    pub(crate) fn set_clause_provider(
+        &mut self,
+        clause_provider: Arc<dyn ProgramClauseProvider<I>>,
+    ) {
+        self.clause_provider = clause_provider;
+        self.cache.clear();
+    }
+
+    pub(crate) fn clause_provider(&self) -> Arc<dyn ProgramClauseProvider<I>> {
+        self.clause_provider.clone()
+    }
+
+    pub(crate) fn set_clause_order(&mut self, clause_order: Arc<dyn ClauseOrder<I>>) {
+        self.clause_order = clause_order;
+    }
+
+    pub(crate) fn set_coinductive_auto_traits(&mut self, coinductive_auto_traits: bool) {
+        self.coinductive_auto_traits = coinductive_auto_traits;
+    }
+
+    pub(crate) fn stats(&self) -> SolverStats {
+        self.stats
+    }
+
+    pub(crate) fn overflow_stack(&self) -> Option<&[UCanonicalGoal<I>]> {
+        self.overflow_stack.as_deref()
+    }
+
+    /// Records that the last root goal was found in the answer cache of
+    /// the `Solver`, without solving it.
+    pub(crate) fn record_answer_cache_hit(&mut self) {
+        self.stats = SolverStats {
+            cache_hits: 1,
+            ..SolverStats::default()
+        };
+        self.overflow_stack = None;
+    }
+
+    /// Discards the cached solutions that depend on the impls of any of
+    /// `trait_ids`, keeping the others.
+    pub(crate) fn invalidate_traits(&mut self, trait_ids: &[TraitId<I>]) {
+        assert!(self.stack.is_empty());
+        self.cache
+            .retain(|_, entry| !trait_ids.iter().any(|id| entry.traits.contains(id)));
+    }
+
     pub(crate) fn solver<'me>(
         &'me mut self,
         program: &'me dyn RustIrDatabase<I>,
+    ) -> Solver<'me, I> {
+        self.limited_solver(program, &|| true)
+    }
+
+    /// Like `solver`, but the solver stops when `should_continue`
+    /// returns `false`, treating the goals it has not solved yet as
+    /// ambiguous.
+    pub(crate) fn limited_solver<'me>(
+        &'me mut self,
+        program: &'me dyn RustIrDatabase<I>,
+        should_continue: &'me dyn std::ops::Fn() -> bool,
     ) -> Solver<'me, I> {
         Solver {
             program,
             context: self,
+            should_continue,
         }
     }
 }
@@ -98,30 +271,264 @@ impl<'me, I: Interner> Solver<'me, I> {
     /// }`, `into_peeled_goal` can be used to create a canonical goal
     /// `SomeType<!1>: Foo<?0>`. This function will then return a
     /// solution with the substitution `?0 := u8`.
+    ///
+    /// With iterative deepening, the goal is first solved with the
+    /// overflow depth of the solver, then solved again with twice the
+    /// depth for as long as some goal overflows, up to the maximum
+    /// deepening depth, or until solving is stopped. The goals solved
+    /// without overflowing are cached in between, so only the goals that
+    /// overflowed are solved again.
     pub(crate) fn solve_root_goal(
         &mut self,
         canonical_goal: &UCanonicalGoal<I>,
     ) -> Fallible<Solution<I>> {
-        debug!("solve_root_goal(canonical_goal={:?})", canonical_goal);
-        assert!(self.context.stack.is_empty());
+        self.context.stats = SolverStats::default();
+        self.deepening(|solver| solver.solve_root_goal_once(canonical_goal))
+    }
+
+    /// Solves a canonical goal like `solve_root_goal`, but passes the
+    /// distinct answers of the goal to `f` as they are found, rather than
+    /// their combined solution. The solutions of a domain goal from each
+    /// of its program clauses are kept apart, and those of the
+    /// alternatives of a disjunction, recursively. Answers found more
+    /// than once are passed once. An answer from a low-priority clause is
+    /// only passed once all the clauses of its goal have been tried, and
+    /// is dropped when a high-priority clause has an answer for the same
+    /// inputs, as when combining them. Other goals have at most one
+    /// answer, their solution.
+    ///
+    /// Each answer is a unique solution, with its lifetime constraints,
+    /// unless the clause it comes from leaves it ambiguous. `f` is also
+    /// told whether another answer follows, so each answer is passed once
+    /// the next one is found; no more answers are looked for once `f`
+    /// returns `false`. Returns whether every answer was passed, or
+    /// `Err(Floundered)` once the program clauses of a domain goal cannot
+    /// be enumerated.
+    pub(crate) fn solve_root_goal_answers(
+        &mut self,
+        canonical_goal: &UCanonicalGoal<I>,
+        f: &mut dyn FnMut(Solution<I>, bool) -> bool,
+    ) -> Result<bool, Floundered> {
+        self.context.stats = SolverStats::default();
+        self.start_root_goal(canonical_goal);
+        let mut stream = AnswerStream {
+            seen: vec![],
+            pending: None,
+            f,
+        };
+        match self.push_answers(canonical_goal, &mut stream) {
+            Ok(true) => Ok(stream.finish(false)),
+            Ok(false) => Ok(false),
+            Err(Floundered) => match stream.finish(true) {
+                true => Err(Floundered),
+                false => Ok(false),
+            },
+        }
+    }
+
+    /// Runs `solve`, which solves a root goal with the current overflow
+    /// depth, again with deeper overflow depths as `solve_root_goal`
+    /// describes.
+    fn deepening<R>(&mut self, mut solve: impl FnMut(&mut Self) -> R) -> R {
+        let overflow_depth = self.context.stack.overflow_depth();
+        let max_depth = match self.context.max_deepening_depth {
+            Some(max_depth) if max_depth > overflow_depth => max_depth,
+            _ => return solve(self),
+        };
+
+        let mut depth = overflow_depth;
+        loop {
+            self.context.overflowed = false;
+            let result = solve(self);
+            if !self.context.overflowed || depth >= max_depth || !(self.should_continue)() {
+                self.context.stack.set_overflow_depth(overflow_depth);
+                return result;
+            }
+            depth = usize::min(depth.saturating_mul(2), max_depth);
+            debug!("solve_root_goal: overflowed, deepening to {}", depth);
+            self.context.stack.set_overflow_depth(depth);
+        }
+    }
+
+    /// Solves a canonical goal like `solve_root_goal`, with the current
+    /// overflow depth.
+    fn solve_root_goal_once(
+        &mut self,
+        canonical_goal: &UCanonicalGoal<I>,
+    ) -> Fallible<Solution<I>> {
+        self.start_root_goal(canonical_goal);
         let minimums = &mut Minimums::new();
         self.solve_goal(canonical_goal.clone(), minimums)
     }
 
+    /// Resets what the solver records about the root goal it solves.
+    fn start_root_goal(&mut self, canonical_goal: &UCanonicalGoal<I>) {
+        debug!("solve_root_goal(canonical_goal={:?})", canonical_goal);
+        assert!(self.context.stack.is_empty());
+        self.context.traits.clear();
+        self.context.overflowed = false;
+        self.context.overflow_stack = None;
+    }
+
+    /// Pushes the answers of `canonical_goal` to `stream`, each solved
+    /// with iterative deepening on its own; see `solve_root_goal_answers`.
+    /// Returns `Ok(false)` once the stream wants no more answers.
+    fn push_answers(
+        &mut self,
+        canonical_goal: &UCanonicalGoal<I>,
+        stream: &mut AnswerStream<'_, I>,
+    ) -> Result<bool, Floundered> {
+        let interner = self.program.interner();
+        let UCanonical {
+            universes,
+            canonical:
+                Canonical {
+                    binders,
+                    value: InEnvironment { environment, goal },
+                },
+        } = canonical_goal.clone();
+        match goal.data(interner) {
+            GoalData::DomainGoal(domain_goal) => {
+                let clauses = self.program_clauses_for_goal(&environment, domain_goal)?;
+                let canonical_goal = UCanonical {
+                    universes,
+                    canonical: Canonical {
+                        binders,
+                        value: InEnvironment {
+                            environment,
+                            goal: domain_goal.clone(),
+                        },
+                    },
+                };
+                let mut high_priority_inputs = vec![];
+                let mut low_priority_answers = vec![];
+                for clause in clauses {
+                    let (result, priority) = self.deepening(|solver| {
+                        let minimums = &mut Minimums::new();
+                        solver.solve_from_clauses(&canonical_goal, Some(clause.clone()), minimums)
+                    });
+                    let solution = match result {
+                        Ok(solution) => solution,
+                        Err(NoSolution) => continue,
+                    };
+                    match priority {
+                        ClausePriority::High => {
+                            high_priority_inputs.push(calculate_inputs(
+                                interner,
+                                domain_goal,
+                                &solution,
+                            ));
+                            if !stream.push(solution) {
+                                return Ok(false);
+                            }
+                        }
+                        ClausePriority::Low => low_priority_answers.push(solution),
+                    }
+                }
+
+                for solution in low_priority_answers {
+                    if high_priority_inputs.contains(&calculate_inputs(
+                        interner,
+                        domain_goal,
+                        &solution,
+                    )) {
+                        debug!("dropping answer {:?} because of lower prio", solution);
+                        continue;
+                    }
+                    if !stream.push(solution) {
+                        return Ok(false);
+                    }
+                }
+            }
+
+            GoalData::Any(goals) => {
+                for subgoal in goals.iter(interner) {
+                    let canonical_goal = UCanonical {
+                        universes,
+                        canonical: Canonical {
+                            binders: binders.clone(),
+                            value: InEnvironment {
+                                environment: environment.clone(),
+                                goal: subgoal.clone(),
+                            },
+                        },
+                    };
+                    if !self.push_answers(&canonical_goal, stream)? {
+                        return Ok(false);
+                    }
+                }
+            }
+
+            _ => {
+                let result = self.deepening(|solver| {
+                    solver.solve_goal(canonical_goal.clone(), &mut Minimums::new())
+                });
+                if let Ok(solution) = result {
+                    return Ok(stream.push(solution));
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Solves a canonical goal like `solve_root_goal`, also recording
+    /// how it was solved. Cached solutions are not used, as they do not
+    /// record how they were found.
+    pub(crate) fn solve_root_goal_with_proof_tree(
+        &mut self,
+        canonical_goal: &UCanonicalGoal<I>,
+    ) -> Option<ProofTree<I>> {
+        let caching_enabled = mem::replace(&mut self.context.caching_enabled, false);
+        let cache = mem::take(&mut self.context.cache);
+        self.context.proof = Some(ProofRecorder::new());
+        self.context.stats = SolverStats::default();
+        let _ = self.solve_root_goal_once(canonical_goal);
+        self.context.caching_enabled = caching_enabled;
+        self.context.cache = cache;
+        self.context.proof.take().unwrap().into_root()
+    }
+
     /// Attempt to solve a goal that has been fully broken down into leaf form
-    /// and canonicalized. This is where the action really happens, and is the
-    /// place where we would perform caching in rustc (and may eventually do in Chalk).
+    /// and canonicalized, recording its proof tree if requested.
     fn solve_goal(
         &mut self,
         goal: UCanonicalGoal<I>,
         minimums: &mut Minimums,
+    ) -> Fallible<Solution<I>> {
+        let proof_goal = match &mut self.context.proof {
+            Some(proof) => {
+                proof.push_frame();
+                Some(goal.clone())
+            }
+            None => None,
+        };
+        let result = self.solve_goal_unrecorded(goal, minimums);
+        if let (Some(proof), Some(goal)) = (&mut self.context.proof, proof_goal) {
+            proof.finish_goal(goal, &result);
+        }
+        result
+    }
+
+    /// This is where the action really happens, and is the place where we
+    /// would perform caching in rustc (and may eventually do in Chalk).
+    fn solve_goal_unrecorded(
+        &mut self,
+        goal: UCanonicalGoal<I>,
+        minimums: &mut Minimums,
     ) -> Fallible<Solution<I>> {
         info_heading!("solve_goal({:?})", goal);
 
         // First check the cache.
-        if let Some(value) = self.context.cache.get(&goal) {
-            debug!("solve_reduced_goal: cache hit, value={:?}", value);
-            return value.clone();
+        if let Some(entry) = self.context.cache.get(&goal) {
+            debug!("solve_reduced_goal: cache hit, value={:?}", entry.solution);
+            let value = entry.solution.clone();
+            self.context.traits.extend(entry.traits.iter().cloned());
+            self.context.stats.cache_hits += 1;
+            self.trace(|| TraceEvent::CacheHit {
+                goal: goal.clone(),
+                solution: value.clone(),
+            });
+            return value;
         }
 
         // Next, check if the goal is in the search tree already.
@@ -134,6 +541,16 @@ impl<'me, I: Interner> Solver<'me, I> {
                 //
                 // XXX how does caching with coinduction work?
                 if self.context.stack.coinductive_cycle_from(depth) {
+                    // The cycle does not affect the caching of this
+                    // goal, so at least record the impls it uses.
+                    let interner = self.program.interner();
+                    if let GoalData::DomainGoal(domain_goal) =
+                        goal.canonical.value.goal.data(interner)
+                    {
+                        self.context
+                            .traits
+                            .extend(impls_trait_id(self.program, domain_goal));
+                    }
                     let value = ConstrainedSubst {
                         subst: goal.trivial_substitution(self.program.interner()),
                         constraints: vec![],
@@ -149,6 +566,8 @@ impl<'me, I: Interner> Solver<'me, I> {
             }
 
             minimums.update_from(self.context.search_graph[dfn].links);
+            let traits = &self.context.search_graph[dfn].traits;
+            self.context.traits.extend(traits.iter().cloned());
 
             // Return the solution from the table.
             let previous_solution = self.context.search_graph[dfn].solution.clone();
@@ -158,12 +577,56 @@ impl<'me, I: Interner> Solver<'me, I> {
                 previous_solution, previous_solution_priority
             );
             previous_solution
+        } else if self.context.stack.is_full() {
+            // We are too deep to keep going, so rather than fail, we
+            // give up on the goal and treat it as ambiguous, keeping the
+            // stack of goals that led to it for the caller.
+            let mut stack: Vec<_> = self
+                .context
+                .search_graph
+                .stack_goals()
+                .into_iter()
+                .cloned()
+                .collect();
+            stack.push(goal);
+            info!(
+                "solve_goal: overflow depth reached, goal stack = {:?}",
+                stack
+            );
+            self.context.overflowed = true;
+            if self.context.overflow_stack.is_none() {
+                self.context.overflow_stack = Some(stack);
+            }
+            Ok(Solution::Ambig(Guidance::Unknown))
+        } else if !(self.should_continue)() {
+            info!("solve_goal: stopped before solving {:?}", goal);
+            self.context.overflowed = true;
+            Ok(Solution::Ambig(Guidance::Unknown))
         } else {
             // Otherwise, push the goal onto the stack and create a table.
             // The initial result for this table is error.
-            let depth = self.context.stack.push(self.program, &goal);
+            let stack_len = self.context.stack.len();
+            self.context.stats.subgoals += 1;
+            self.context.stats.max_depth = usize::max(self.context.stats.max_depth, stack_len + 1);
+            self.trace(|| TraceEvent::GoalEntered {
+                goal: goal.clone(),
+                depth: stack_len,
+            });
+            let traced_goal = self.context.trace.as_ref().map(|_| goal.clone());
+
+            let depth =
+                self.context
+                    .stack
+                    .push(self.program, &goal, self.context.coinductive_auto_traits);
             let dfn = self.context.search_graph.insert(&goal, depth);
+            let outer_overflowed = mem::replace(&mut self.context.overflowed, false);
+            let outer_traits = mem::take(&mut self.context.traits);
             let subgoal_minimums = self.solve_new_subgoal(goal, depth, dfn);
+            let overflowed = self.context.overflowed;
+            self.context.overflowed |= outer_overflowed;
+            let traits = mem::replace(&mut self.context.traits, outer_traits);
+            self.context.traits.extend(traits.iter().cloned());
+            self.context.search_graph[dfn].traits = traits;
             self.context.search_graph[dfn].links = subgoal_minimums;
             self.context.search_graph[dfn].stack_depth = None;
             self.context.stack.pop(depth);
@@ -178,24 +641,38 @@ impl<'me, I: Interner> Solver<'me, I> {
             // cache now. This is a sort of hack to alleviate the
             // worst of the repeated work that we do during tabling.
             if subgoal_minimums.positive >= dfn {
-                if self.context.caching_enabled {
+                if self.context.caching_enabled && !overflowed {
                     self.context
                         .search_graph
                         .move_to_cache(dfn, &mut self.context.cache);
                     debug!("solve_reduced_goal: SCC head encountered, moving to cache");
                 } else {
                     debug!(
-                        "solve_reduced_goal: SCC head encountered, rolling back as caching disabled"
+                        "solve_reduced_goal: SCC head encountered, rolling back as caching disabled or overflow"
                     );
                     self.context.search_graph.rollback_to(dfn);
                 }
             }
 
             info!("solve_goal: solution = {:?} prio {:?}", result, priority);
+            if let Some(goal) = traced_goal {
+                self.trace(|| TraceEvent::GoalSolved {
+                    goal,
+                    solution: result.clone(),
+                });
+            }
             result
         }
     }
 
+    /// Reports the event built by `event` to the trace sink, if there
+    /// is one.
+    fn trace(&mut self, event: impl FnOnce() -> TraceEvent<I>) {
+        if let Some(sink) = &mut self.context.trace {
+            sink.event(event());
+        }
+    }
+
     fn solve_new_subgoal(
         &mut self,
         canonical_goal: UCanonicalGoal<I>,
@@ -220,6 +697,11 @@ impl<'me, I: Interner> Solver<'me, I> {
         // so this function will eventually be constant and the loop terminates.
         let minimums = &mut Minimums::new();
         loop {
+            // Only the last iteration's way of solving the goal is kept.
+            if let Some(proof) = &mut self.context.proof {
+                proof.replace_frame(ProofFrame::new());
+            }
+
             let UCanonical {
                 universes,
                 canonical:
@@ -249,6 +731,9 @@ impl<'me, I: Interner> Solver<'me, I> {
                     // clauses. We try each approach in turn:
 
                     let InEnvironment { environment, goal } = &canonical_goal.canonical.value;
+                    self.context
+                        .traits
+                        .extend(impls_trait_id(self.program, goal));
 
                     let (prog_solution, prog_prio) = {
                         debug_heading!("prog_clauses");
@@ -268,6 +753,39 @@ impl<'me, I: Interner> Solver<'me, I> {
                     (prog_solution, prog_prio)
                 }
 
+                GoalData::Any(goals) => {
+                    // Try each alternative on its own, combining the
+                    // solutions as we do for multiple program clauses.
+                    let interner = self.program.interner();
+                    let mut cur_solution: Option<Solution<I>> = None;
+                    let mut frames = vec![];
+                    for subgoal in goals.iter(interner) {
+                        let canonical_goal = UCanonical {
+                            universes,
+                            canonical: Canonical {
+                                binders: binders.clone(),
+                                value: InEnvironment {
+                                    environment: environment.clone(),
+                                    goal: subgoal.clone(),
+                                },
+                            },
+                        };
+                        let ((result, _), frame) = self.with_proof_frame(|solver| {
+                            solver.solve_via_simplification(&canonical_goal, minimums)
+                        });
+                        if let Ok(solution) = result {
+                            frames.extend(frame.map(|frame| (solution.clone(), frame)));
+                            cur_solution = Some(match cur_solution {
+                                None => solution,
+                                Some(cur) => cur.combine(solution, interner, &binders),
+                            });
+                        }
+                    }
+                    let result = cur_solution.ok_or(NoSolution);
+                    self.keep_proof_frame(&result, frames);
+                    (result, ClausePriority::High)
+                }
+
                 _ => {
                     let canonical_goal = UCanonical {
                         universes,
@@ -299,6 +817,7 @@ impl<'me, I: Interner> Solver<'me, I> {
 
             let (current_answer, current_prio) = combine_with_priorities_for_goal(
                 self.program.interner(),
+                &canonical_goal.canonical.binders,
                 &canonical_goal.canonical.value.goal,
                 old_answer.clone(),
                 old_prio,
@@ -359,6 +878,7 @@ impl<'me, I: Interner> Solver<'me, I> {
         C: IntoIterator<Item = ProgramClause<I>>,
     {
         let mut cur_solution = None;
+        let mut frames = vec![];
         for program_clause in clauses {
             debug_heading!("clause={:?}", program_clause);
 
@@ -369,20 +889,30 @@ impl<'me, I: Interner> Solver<'me, I> {
 
             match program_clause.data(self.program.interner()) {
                 ProgramClauseData::Implies(implication) => {
-                    let res = self.solve_via_implication(
-                        canonical_goal,
-                        &Binders::new(
-                            ParameterKinds::from(self.program.interner(), vec![]),
-                            implication.clone(),
-                        ),
-                        minimums,
-                    );
+                    // Clauses from the environment may refer to the
+                    // canonical variables of the goal, so shift them in
+                    // past the (empty) binder we add.
+                    let (res, frame) = self.with_proof_frame(|solver| {
+                        solver.solve_via_implication(
+                            canonical_goal,
+                            &Binders::new(
+                                ParameterKinds::from(solver.program.interner(), vec![]),
+                                implication.shifted_in(solver.program.interner()),
+                            ),
+                            minimums,
+                        )
+                    });
                     if let (Ok(solution), priority) = res {
                         debug!("ok: solution={:?} prio={:?}", solution, priority);
+                        frames.extend(frame.map(|mut frame| {
+                            frame.set_clause(program_clause.clone());
+                            (solution.clone(), frame)
+                        }));
                         cur_solution = Some(match cur_solution {
                             None => (solution, priority),
                             Some((cur, cur_priority)) => combine_with_priorities(
                                 self.program.interner(),
+                                &canonical_goal.canonical.binders,
                                 &canonical_goal.canonical.value.goal,
                                 cur,
                                 cur_priority,
@@ -395,13 +925,20 @@ impl<'me, I: Interner> Solver<'me, I> {
                     }
                 }
                 ProgramClauseData::ForAll(implication) => {
-                    let res = self.solve_via_implication(canonical_goal, implication, minimums);
+                    let (res, frame) = self.with_proof_frame(|solver| {
+                        solver.solve_via_implication(canonical_goal, implication, minimums)
+                    });
                     if let (Ok(solution), priority) = res {
                         debug!("ok: solution={:?} prio={:?}", solution, priority);
+                        frames.extend(frame.map(|mut frame| {
+                            frame.set_clause(program_clause.clone());
+                            (solution.clone(), frame)
+                        }));
                         cur_solution = Some(match cur_solution {
                             None => (solution, priority),
                             Some((cur, cur_priority)) => combine_with_priorities(
                                 self.program.interner(),
+                                &canonical_goal.canonical.binders,
                                 &canonical_goal.canonical.value.goal,
                                 cur,
                                 cur_priority,
@@ -415,7 +952,45 @@ impl<'me, I: Interner> Solver<'me, I> {
                 }
             }
         }
-        cur_solution.map_or((Err(NoSolution), ClausePriority::High), |(s, p)| (Ok(s), p))
+        let (result, priority) =
+            cur_solution.map_or((Err(NoSolution), ClausePriority::High), |(s, p)| (Ok(s), p));
+        self.keep_proof_frame(&result, frames);
+        (result, priority)
+    }
+
+    /// Runs `op`, which tries one way of solving the current goal,
+    /// returning the subgoals that it solved if proof trees are being
+    /// recorded.
+    fn with_proof_frame<R>(
+        &mut self,
+        op: impl FnOnce(&mut Self) -> R,
+    ) -> (R, Option<ProofFrame<I>>) {
+        if let Some(proof) = &mut self.context.proof {
+            proof.push_frame();
+        }
+        let result = op(self);
+        let frame = self.context.proof.as_mut().map(|proof| proof.pop_frame());
+        (result, frame)
+    }
+
+    /// Keeps the frame of the way of solving the current goal that the
+    /// combined `result` came from, if any: the first one whose solution
+    /// is the same. A result combined from several ways of solving the
+    /// goal is recorded without a clause.
+    fn keep_proof_frame(
+        &mut self,
+        result: &Fallible<Solution<I>>,
+        mut frames: Vec<(Solution<I>, ProofFrame<I>)>,
+    ) {
+        let proof = match &mut self.context.proof {
+            Some(proof) => proof,
+            None => return,
+        };
+        if let Ok(result) = result {
+            if let Some(index) = frames.iter().position(|(solution, _)| solution == result) {
+                proof.replace_frame(frames.swap_remove(index).1);
+            }
+        }
     }
 
     /// Modus ponens! That is: try to apply an implication by proving its premises.
@@ -432,17 +1007,28 @@ impl<'me, I: Interner> Solver<'me, I> {
             canonical_goal,
             clause
         );
+        self.trace(|| TraceEvent::ClauseTried {
+            goal: canonical_goal.clone(),
+            clause: clause.clone(),
+        });
+        self.context.stats.unifications += 1;
         let interner = self.program.interner();
         let (mut fulfill, subst, goal) = Fulfill::new(self, canonical_goal);
+        let instantiated_clause = subst.apply(clause, interner);
         let ProgramClauseImplication {
             consequence,
             conditions,
-            priority: _,
-        } = fulfill.instantiate_binders_existentially(clause);
+            priority,
+        } = fulfill.instantiate_binders_existentially(&instantiated_clause);
 
         debug!("the subst is {:?}", subst);
 
         if let Err(e) = fulfill.unify(&goal.environment, &goal.goal, &consequence) {
+            drop(fulfill);
+            self.trace(|| TraceEvent::UnificationFailed {
+                goal: canonical_goal.clone(),
+                clause: clause.clone(),
+            });
             return (Err(e), ClausePriority::High);
         }
 
@@ -454,10 +1040,7 @@ impl<'me, I: Interner> Solver<'me, I> {
         }
 
         // and then try to solve
-        (
-            fulfill.solve(subst, minimums),
-            clause.skip_binders().priority,
-        )
+        (fulfill.solve(subst, minimums), priority)
     }
 
     fn program_clauses_for_goal(
@@ -465,7 +1048,35 @@ impl<'me, I: Interner> Solver<'me, I> {
         environment: &Environment<I>,
         goal: &DomainGoal<I>,
     ) -> Result<Vec<ProgramClause<I>>, Floundered> {
-        program_clauses_for_goal(self.program, environment, goal)
+        program_clauses_for_goal(
+            self.program,
+            environment,
+            goal,
+            &*self.context.clause_provider,
+            &*self.context.clause_order,
+        )
+    }
+}
+
+/// The trait whose impls give the program clauses for `goal`, if any.
+/// Other program clauses do not depend on impls, except through the
+/// subgoals they lead to.
+pub(crate) fn impls_trait_id<I: Interner>(
+    program: &dyn RustIrDatabase<I>,
+    goal: &DomainGoal<I>,
+) -> Option<TraitId<I>> {
+    match goal {
+        DomainGoal::Holds(WhereClause::Implemented(trait_ref))
+        | DomainGoal::Holds(WhereClause::ConstImplemented(trait_ref)) => Some(trait_ref.trait_id),
+        DomainGoal::Normalize(Normalize {
+            alias: AliasTy::Projection(projection),
+            ..
+        }) => Some(
+            program
+                .associated_ty_data(projection.associated_ty_id)
+                .trait_id,
+        ),
+        _ => None,
     }
 }
 
@@ -484,6 +1095,7 @@ fn calculate_inputs<I: Interner>(
 
 fn combine_with_priorities_for_goal<I: Interner>(
     interner: &I,
+    binders: &CanonicalVarKinds<I>,
     goal: &Goal<I>,
     a: Fallible<Solution<I>>,
     prio_a: ClausePriority,
@@ -500,7 +1112,7 @@ fn combine_with_priorities_for_goal<I: Interner>(
     match (a, b) {
         (Ok(a), Ok(b)) => {
             let (solution, prio) =
-                combine_with_priorities(interner, domain_goal, a, prio_a, b, prio_b);
+                combine_with_priorities(interner, binders, domain_goal, a, prio_a, b, prio_b);
             (Ok(solution), prio)
         }
         (Ok(solution), Err(_)) => (Ok(solution), prio_a),
@@ -511,6 +1123,7 @@ fn combine_with_priorities_for_goal<I: Interner>(
 
 fn combine_with_priorities<I: Interner>(
     interner: &I,
+    binders: &CanonicalVarKinds<I>,
     domain_goal: &DomainGoal<I>,
     a: Solution<I>,
     prio_a: ClausePriority,
@@ -535,10 +1148,13 @@ fn combine_with_priorities<I: Interner>(
                 );
                 (higher, ClausePriority::High)
             } else {
-                (higher.combine(lower, interner), ClausePriority::High)
+                (
+                    higher.combine(lower, interner, binders),
+                    ClausePriority::High,
+                )
             }
         }
-        (_, _, a, b) => (a.combine(b, interner), prio_a),
+        (_, _, a, b) => (a.combine(b, interner, binders), prio_a),
     }
 }
 
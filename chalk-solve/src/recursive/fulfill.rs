@@ -3,13 +3,7 @@ use crate::solve::truncate;
 use cast::Cast;
 use chalk_engine::fallible::NoSolution;
 use fold::Fold;
-use infer::{
-    canonicalize::Canonicalized,
-    instantiate::IntoBindersAndValue,
-    ucanonicalize::{UCanonicalized, UniverseMap},
-    unify::UnificationResult,
-    InferenceTable, ParameterEnaVariable, ParameterEnaVariableExt,
-};
+use infer::{instantiate::IntoBindersAndValue, AnswerMapping, InferenceTable, UnificationResult};
 use interner::HasInterner;
 use rustc_hash::FxHashSet;
 use std::fmt::Debug;
@@ -46,8 +40,7 @@ enum Obligation<I: Interner> {
 /// so that we can update inference state accordingly.
 #[derive(Clone, Debug)]
 struct PositiveSolution<I: Interner> {
-    free_vars: Vec<ParameterEnaVariable<I>>,
-    universes: UniverseMap,
+    mapping: AnswerMapping<I>,
     solution: Solution<I>,
 }
 
@@ -72,12 +65,20 @@ pub(crate) struct Fulfill<'s, 'db, I: Interner> {
     solver: &'s mut Solver<'db, I>,
     infer: InferenceTable<I>,
 
+    /// The number of universes of the goal being solved. Placeholders
+    /// of later universes were introduced while solving it, and must
+    /// not appear in its solution.
+    universes: usize,
+
     /// The remaining goals to prove or refute
     obligations: Vec<Obligation<I>>,
 
     /// Lifetime constraints that must be fulfilled for a solution to be fully
-    /// validated.
-    constraints: FxHashSet<InEnvironment<Constraint<I>>>,
+    /// validated, without duplicates, in the order in which they were found.
+    constraints: Vec<InEnvironment<Constraint<I>>>,
+
+    /// The constraints in `constraints`, to tell new ones apart quickly.
+    seen_constraints: FxHashSet<InEnvironment<Constraint<I>>>,
 
     /// Record that a goal has been processed that can neither be proved nor
     /// refuted. In such a case the solution will be either `CannotProve`, or `Err`
@@ -98,8 +99,10 @@ impl<'s, 'db, I: Interner> Fulfill<'s, 'db, I> {
         let fulfill = Fulfill {
             solver,
             infer,
+            universes: ucanonical_goal.universes,
             obligations: vec![],
-            constraints: FxHashSet::default(),
+            constraints: vec![],
+            seen_constraints: FxHashSet::default(),
             cannot_prove: false,
         };
         (fulfill, subst, canonical_goal)
@@ -162,7 +165,7 @@ impl<'s, 'db, I: Interner> Fulfill<'s, 'db, I> {
         debug!("unify({:?}, {:?}) succeeded", a, b);
         debug!("unify: goals={:?}", goals);
         debug!("unify: constraints={:?}", constraints);
-        self.constraints.extend(constraints);
+        self.add_constraints(constraints);
         let interner = self.solver.program.interner();
         for goal in goals {
             self.push_obligation(Obligation::Prove(goal.cast(interner)));
@@ -206,13 +209,16 @@ impl<'s, 'db, I: Interner> Fulfill<'s, 'db, I> {
                 let in_env = InEnvironment::new(environment, subgoal.clone());
                 self.push_obligation(Obligation::Refute(in_env));
             }
-            GoalData::DomainGoal(_) => {
+            GoalData::DomainGoal(_) | GoalData::Any(_) => {
                 let in_env = InEnvironment::new(environment, goal);
                 self.push_obligation(Obligation::Prove(in_env));
             }
             GoalData::EqGoal(EqGoal { a, b }) => {
                 self.unify(&environment, &a, &b)?;
             }
+            GoalData::Constraint(constraint) => {
+                self.add_constraints(Some(InEnvironment::new(environment, constraint.clone())));
+            }
             GoalData::CannotProve(()) => {
                 self.cannot_prove = true;
             }
@@ -226,19 +232,10 @@ impl<'s, 'db, I: Interner> Fulfill<'s, 'db, I> {
         minimums: &mut Minimums,
     ) -> Fallible<PositiveSolution<I>> {
         let interner = self.solver.program.interner();
-        let Canonicalized {
-            quantified,
-            free_vars,
-            ..
-        } = self.infer.canonicalize(interner, &wc);
-        let UCanonicalized {
-            quantified,
-            universes,
-        } = self.infer.u_canonicalize(interner, &quantified);
+        let (quantified, mapping) = self.infer.canonicalize_query(interner, wc);
         let result = self.solver.solve_goal(quantified, minimums);
         Ok(PositiveSolution {
-            free_vars,
-            universes,
+            mapping,
             solution: result?,
         })
     }
@@ -257,12 +254,10 @@ impl<'s, 'db, I: Interner> Fulfill<'s, 'db, I> {
         };
 
         // Negate the result
-        let UCanonicalized {
-            quantified,
-            universes: _,
-        } = self
+        let quantified = self
             .infer
-            .u_canonicalize(self.solver.program.interner(), &canonicalized);
+            .u_canonicalize(self.solver.program.interner(), &canonicalized)
+            .quantified;
         let mut minimums = Minimums::new(); // FIXME -- minimums here seems wrong
         if let Ok(solution) = self.solver.solve_goal(quantified, &mut minimums) {
             if solution.is_unique() {
@@ -275,46 +270,30 @@ impl<'s, 'db, I: Interner> Fulfill<'s, 'db, I> {
         }
     }
 
-    /// Trying to prove some goal led to a the substitution `subst`; we
-    /// wish to apply that substitution to our own inference variables
-    /// (and incorporate any region constraints). This substitution
-    /// requires some mapping to get it into our namespace -- first,
-    /// the universes it refers to have been canonicalized, and
-    /// `universes` stores the mapping back into our
-    /// universes. Second, the free variables that appear within can
-    /// be mapped into our variables with `free_vars`.
-    fn apply_solution(
+    fn add_constraints(
         &mut self,
-        free_vars: Vec<ParameterEnaVariable<I>>,
-        universes: UniverseMap,
-        subst: Canonical<ConstrainedSubst<I>>,
+        constraints: impl IntoIterator<Item = InEnvironment<Constraint<I>>>,
     ) {
-        let subst = universes.map_from_canonical(self.interner(), &subst);
-        let ConstrainedSubst { subst, constraints } = self
-            .infer
-            .instantiate_canonical(self.solver.program.interner(), &subst);
+        for constraint in constraints {
+            if self.seen_constraints.insert(constraint.clone()) {
+                self.constraints.push(constraint);
+            }
+        }
+    }
 
-        debug!(
-            "fulfill::apply_solution: adding constraints {:?}",
-            constraints
-        );
-        self.constraints.extend(constraints);
-
-        // We use the empty environment for unification here because we're
-        // really just doing a substitution on unconstrained variables, which is
-        // guaranteed to succeed without generating any new constraints.
-        let empty_env = &Environment::new(self.solver.program.interner());
-
-        for (i, free_var) in free_vars.into_iter().enumerate() {
-            let subst_value = subst.at(self.interner(), i);
-            let free_value = free_var.to_parameter(self.interner());
-            self.unify(empty_env, &free_value, subst_value)
-                .unwrap_or_else(|err| {
-                    panic!(
-                        "apply_solution failed with free_var={:?}, subst_value={:?}: {:?}",
-                        free_var, subst_value, err
-                    );
-                });
+    /// Trying to prove some goal led to a the substitution `subst`; we
+    /// wish to apply that substitution to our own inference variables
+    /// (and incorporate any region constraints). The goal was
+    /// canonicalized with `mapping`, which maps the substitution back
+    /// into our namespace.
+    fn apply_solution(&mut self, mapping: AnswerMapping<I>, subst: Canonical<ConstrainedSubst<I>>) {
+        let UnificationResult { goals, constraints } =
+            self.infer
+                .apply_answer(self.solver.program.interner(), mapping, &subst);
+        self.add_constraints(constraints);
+        let interner = self.solver.program.interner();
+        for goal in goals {
+            self.push_obligation(Obligation::Prove(goal.cast(interner)));
         }
     }
 
@@ -343,15 +322,11 @@ impl<'s, 'db, I: Interner> Fulfill<'s, 'db, I> {
             while let Some(obligation) = self.obligations.pop() {
                 let ambiguous = match obligation {
                     Obligation::Prove(ref wc) => {
-                        let PositiveSolution {
-                            free_vars,
-                            universes,
-                            solution,
-                        } = self.prove(wc, minimums)?;
+                        let PositiveSolution { mapping, solution } = self.prove(wc, minimums)?;
 
                         if solution.has_definite() {
                             if let Some(constrained_subst) = solution.constrained_subst() {
-                                self.apply_solution(free_vars, universes, constrained_subst);
+                                self.apply_solution(mapping, constrained_subst);
                                 progress = true;
                             }
                         }
@@ -407,7 +382,14 @@ impl<'s, 'db, I: Interner> Fulfill<'s, 'db, I> {
             // No obligations remain, so we have definitively solved our goals,
             // and the current inference state is the unique way to solve them.
 
-            let constraints = self.constraints.into_iter().collect();
+            let constraints = self
+                .infer
+                .resolve_escaping_placeholders(
+                    self.solver.program.interner(),
+                    self.universes,
+                    self.constraints,
+                )
+                .ok_or(NoSolution)?;
             let constrained = self.infer.canonicalize(
                 self.solver.program.interner(),
                 &ConstrainedSubst { subst, constraints },
@@ -432,13 +414,10 @@ impl<'s, 'db, I: Interner> Fulfill<'s, 'db, I> {
 
             while let Some(obligation) = self.obligations.pop() {
                 if let Obligation::Prove(goal) = obligation {
-                    let PositiveSolution {
-                        free_vars,
-                        universes,
-                        solution,
-                    } = self.prove(&goal, minimums).unwrap();
+                    let PositiveSolution { mapping, solution } =
+                        self.prove(&goal, minimums).unwrap();
                     if let Some(constrained_subst) = solution.constrained_subst() {
-                        self.apply_solution(free_vars, universes, constrained_subst);
+                        self.apply_solution(mapping, constrained_subst);
                         let subst = self
                             .infer
                             .canonicalize(self.solver.program.interner(), &subst);
@@ -0,0 +1,114 @@
+use super::UCanonicalGoal;
+use crate::proof_tree::ProofTree;
+use crate::Solution;
+use chalk_engine::fallible::Fallible;
+use chalk_ir::interner::Interner;
+use chalk_ir::ProgramClause;
+use rustc_hash::FxHashMap;
+
+/// Records the proof trees of the goals being solved. There is a frame
+/// for each goal on the stack, collecting the proof trees of its
+/// subgoals; the bottom frame collects the tree of the root goal.
+pub(super) struct ProofRecorder<I: Interner> {
+    frames: Vec<ProofFrame<I>>,
+
+    /// The trees of the goals that have been solved, so that they can
+    /// be reused when the solution of such a goal is taken from the
+    /// search graph rather than solved again.
+    solved: FxHashMap<UCanonicalGoal<I>, ProofTree<I>>,
+}
+
+/// What is known about how a goal was solved, so far.
+pub(super) struct ProofFrame<I: Interner> {
+    clause: Option<ProgramClause<I>>,
+    subgoals: Vec<ProofTree<I>>,
+}
+
+impl<I: Interner> ProofFrame<I> {
+    pub(super) fn new() -> Self {
+        ProofFrame {
+            clause: None,
+            subgoals: vec![],
+        }
+    }
+
+    pub(super) fn set_clause(&mut self, clause: ProgramClause<I>) {
+        self.clause = Some(clause);
+    }
+}
+
+impl<I: Interner> ProofRecorder<I> {
+    pub(super) fn new() -> Self {
+        ProofRecorder {
+            frames: vec![ProofFrame::new()],
+            solved: FxHashMap::default(),
+        }
+    }
+
+    /// Starts collecting the subgoals of a new goal, or of a new way of
+    /// solving the current goal.
+    pub(super) fn push_frame(&mut self) {
+        self.frames.push(ProofFrame::new());
+    }
+
+    pub(super) fn pop_frame(&mut self) -> ProofFrame<I> {
+        self.frames.pop().unwrap()
+    }
+
+    /// Replaces what is known about the current goal, as when one of
+    /// the ways of solving it is chosen.
+    pub(super) fn replace_frame(&mut self, frame: ProofFrame<I>) {
+        *self.frames.last_mut().unwrap() = frame;
+    }
+
+    /// Completes the tree of `goal`, whose frame is the current one, and
+    /// adds it to the subgoals of the enclosing goal.
+    pub(super) fn finish_goal(
+        &mut self,
+        goal: UCanonicalGoal<I>,
+        solution: &Fallible<Solution<I>>,
+    ) {
+        let ProofFrame {
+            clause,
+            mut subgoals,
+        } = self.pop_frame();
+
+        // The ambiguous solutions of the subgoals of a unique solution
+        // come from attempts that were repeated once more was known.
+        if let Ok(Solution::Unique(_)) = solution {
+            subgoals.retain(|subgoal| match &subgoal.solution {
+                Ok(solution) => !solution.is_ambig(),
+                Err(_) => true,
+            });
+        }
+
+        let earlier = self
+            .solved
+            .get(&goal)
+            .filter(|tree| tree.solution == *solution);
+        let tree = match earlier {
+            Some(tree) if clause.is_none() && subgoals.is_empty() => tree.clone(),
+            _ => {
+                let tree = ProofTree {
+                    goal: goal.clone(),
+                    solution: solution.clone(),
+                    clause,
+                    subgoals,
+                };
+                self.solved.insert(goal, tree.clone());
+                tree
+            }
+        };
+
+        // A goal that is solved again replaces the earlier attempt.
+        let subgoals = &mut self.frames.last_mut().unwrap().subgoals;
+        subgoals.retain(|subgoal| subgoal.goal != tree.goal);
+        subgoals.push(tree);
+    }
+
+    /// The tree of the root goal, once it is solved.
+    pub(super) fn into_root(mut self) -> Option<ProofTree<I>> {
+        assert_eq!(self.frames.len(), 1);
+        self.frames.pop().unwrap().subgoals.pop()
+    }
+}
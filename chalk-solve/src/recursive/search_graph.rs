@@ -4,11 +4,11 @@ use std::ops::IndexMut;
 use std::usize;
 
 use super::stack::StackDepth;
-use super::{Minimums, UCanonicalGoal};
+use super::{CacheEntry, Minimums, UCanonicalGoal};
 use crate::Solution;
 use chalk_engine::fallible::{Fallible, NoSolution};
-use chalk_ir::{interner::Interner, ClausePriority};
-use rustc_hash::FxHashMap;
+use chalk_ir::{interner::Interner, ClausePriority, TraitId};
+use rustc_hash::{FxHashMap, FxHashSet};
 
 pub(super) struct SearchGraph<I: Interner> {
     indices: FxHashMap<UCanonicalGoal<I>, DepthFirstNumber>,
@@ -35,6 +35,10 @@ pub(super) struct Node<I: Interner> {
     /// from the stack, it contains the DFN of the minimal ancestor
     /// that the table reached (or MAX if no cycle was encountered).
     pub(crate) links: Minimums,
+
+    /// The traits whose impls the solution depends on, directly or
+    /// through the solutions of its subgoals.
+    pub(crate) traits: FxHashSet<TraitId<I>>,
 }
 
 impl<I: Interner> SearchGraph<I> {
@@ -69,6 +73,7 @@ impl<I: Interner> SearchGraph<I> {
             solution_priority: ClausePriority::High,
             stack_depth: Some(stack_depth),
             links: Minimums { positive: dfn },
+            traits: FxHashSet::default(),
         };
         self.nodes.push(node);
         let previous_index = self.indices.insert(goal.clone(), dfn);
@@ -76,6 +81,15 @@ impl<I: Interner> SearchGraph<I> {
         dfn
     }
 
+    /// The goals that are actively being explored, outermost first.
+    pub(crate) fn stack_goals(&self) -> Vec<&UCanonicalGoal<I>> {
+        self.nodes
+            .iter()
+            .filter(|node| node.stack_depth.is_some())
+            .map(|node| &node.goal)
+            .collect()
+    }
+
     /// Clears all nodes with a depth-first number greater than or equal `dfn`.
     pub(crate) fn rollback_to(&mut self, dfn: DepthFirstNumber) {
         debug!("rollback_to(dfn={:?})", dfn);
@@ -85,18 +99,35 @@ impl<I: Interner> SearchGraph<I> {
 
     /// Removes all nodes with a depth-first-number greater than or
     /// equal to `dfn`, adding their final solutions into the cache.
+    ///
+    /// A node that reached one of its ancestors depends on the solution
+    /// of that ancestor as it was while being solved, so it is cached
+    /// with the traits of the node at `dfn`, which include those of all
+    /// the nodes solved after it.
     pub(crate) fn move_to_cache(
         &mut self,
         dfn: DepthFirstNumber,
-        cache: &mut FxHashMap<UCanonicalGoal<I>, Fallible<Solution<I>>>,
+        cache: &mut FxHashMap<UCanonicalGoal<I>, CacheEntry<I>>,
     ) {
         debug!("move_to_cache(dfn={:?})", dfn);
         self.indices.retain(|_key, value| *value < dfn);
-        for node in self.nodes.drain(dfn.index..) {
+        let head_traits = self.nodes[dfn.index].traits.clone();
+        for (index, node) in self.nodes.drain(dfn.index..).enumerate() {
             assert!(node.stack_depth.is_none());
             assert!(node.links.positive >= dfn);
             debug!("caching solution {:?} for {:?}", node.solution, node.goal);
-            cache.insert(node.goal, node.solution);
+            let traits = if node.links.positive < dfn + index {
+                head_traits.clone()
+            } else {
+                node.traits
+            };
+            cache.insert(
+                node.goal,
+                CacheEntry {
+                    solution: node.solution,
+                    traits,
+                },
+            );
         }
     }
 }
@@ -42,23 +42,38 @@ impl Stack {
         self.entries.is_empty()
     }
 
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub(crate) fn overflow_depth(&self) -> usize {
+        self.overflow_depth
+    }
+
+    /// Changes the overflow depth, which can only be done while the
+    /// stack is empty.
+    pub(crate) fn set_overflow_depth(&mut self, overflow_depth: usize) {
+        assert!(self.is_empty());
+        self.overflow_depth = overflow_depth;
+    }
+
+    /// True if pushing another goal would exceed the overflow depth.
+    pub(crate) fn is_full(&self) -> bool {
+        self.entries.len() >= self.overflow_depth
+    }
+
     pub(crate) fn push<I: Interner>(
         &mut self,
         program: &dyn RustIrDatabase<I>,
         goal: &UCanonicalGoal<I>,
+        coinductive_auto_traits: bool,
     ) -> StackDepth {
+        assert!(!self.is_full(), "overflow depth reached");
         let depth = StackDepth {
             depth: self.entries.len(),
         };
 
-        if depth.depth >= self.overflow_depth {
-            // This shoudl perhaps be a result or something, though
-            // really I'd prefer to move to subgoal abstraction for
-            // guaranteeing termination. -nmatsakis
-            panic!("overflow depth reached")
-        }
-
-        let coinductive_goal = goal.is_coinductive(program);
+        let coinductive_goal = goal.is_coinductive(program, coinductive_auto_traits);
         self.entries.push(StackEntry {
             coinductive_goal,
             cycle: false,
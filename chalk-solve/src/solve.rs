@@ -1,10 +1,24 @@
+use crate::clauses::order::ClauseOrder;
+use crate::clauses::provider::ProgramClauseProvider;
+use crate::explain::{Explainer, Explanation};
+use crate::proof_tree::ProofTree;
+use crate::recursive::{impls_trait_id, RecursiveContext};
 use crate::solve::slg::SlgContext;
-use crate::{recursive::RecursiveContext, RustIrDatabase};
+use crate::trace::TraceSink;
+use crate::RustIrDatabase;
+use chalk_engine::context::Floundered;
 use chalk_engine::forest::{Forest, SubstitutionResult};
 use chalk_ir::interner::Interner;
 use chalk_ir::*;
+use rustc_hash::FxHashMap;
+use std::cell::Cell;
 use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+#[cfg(feature = "parallel")]
+mod parallel;
 mod slg;
 pub(crate) mod truncate;
 
@@ -13,7 +27,12 @@ pub(crate) mod truncate;
 pub enum Solution<I: Interner> {
     /// The goal indeed holds, and there is a unique value for all existential
     /// variables. In this case, we also record a set of lifetime constraints
-    /// which must also hold for the goal to be valid.
+    /// which must also hold for the goal to be valid. They are left for the
+    /// caller to check, for example with a region checker.
+    ///
+    /// Several ways of proving the goal with the same substitution, whose
+    /// constraints all include those of one of them, are a unique solution
+    /// with the constraints of that one.
     Unique(Canonical<ConstrainedSubst<I>>),
 
     /// The goal may be provable in multiple ways, but regardless we may have some guidance
@@ -53,29 +72,37 @@ impl<I: Interner> Solution<I> {
     /// the values for existential variables; attempt to combine them. This
     /// operation does not depend on the order of its arguments.
     //
-    // This actually isn't as precise as it could be, in two ways:
-    //
-    // a. It might be that while there are multiple distinct candidates, they
-    //    all agree about *some things*. To be maximally precise, we would
-    //    compute the intersection of what they agree on. It's not clear though
-    //    that this is actually what we want Rust's inference to do, and it's
-    //    certainly not what it does today.
-    //
-    // b. There might also be an ambiguous candidate and a successful candidate,
-    //    both with the same refined-goal. In that case, we could probably claim
-    //    success, since if the conditions of the ambiguous candidate were met,
-    //    we know the success would apply.  Example: `?0: Clone` yields ambiguous
-    //    candidate `Option<?0>: Clone` and successful candidate `Option<?0>:
-    //    Clone`.
+    // When both candidates give definite guidance, the combined guidance is
+    // what they agree on, as computed by anti-unification: for example,
+    // `?0 := Vec<u32>` and `?0 := Vec<i32>` combine to `?0 := Vec<?X>`.
+    // `root_binders` are the binders of the canonical goal that both
+    // candidates are solutions for.
     //
-    // But you get the idea.
-    pub(crate) fn combine(self, other: Solution<I>, interner: &I) -> Solution<I> {
+    // This actually isn't as precise as it could be, though: there might
+    // be an ambiguous candidate and a successful candidate,
+    // both with the same refined-goal. In that case, we could probably claim
+    // success, since if the conditions of the ambiguous candidate were met,
+    // we know the success would apply.  Example: `?0: Clone` yields ambiguous
+    // candidate `Option<?0>: Clone` and successful candidate `Option<?0>:
+    // Clone`.
+    pub(crate) fn combine(
+        self,
+        other: Solution<I>,
+        interner: &I,
+        root_binders: &CanonicalVarKinds<I>,
+    ) -> Solution<I> {
         use self::Guidance::*;
 
         if self == other {
             return self;
         }
 
+        if let (Solution::Unique(constrained1), Solution::Unique(constrained2)) = (&self, &other) {
+            if let Some(constrained) = weaker_constrained_subst(constrained1, constrained2) {
+                return Solution::Unique(constrained);
+            }
+        }
+
         debug!(
             "combine {} with {}",
             self.display(interner),
@@ -85,8 +112,18 @@ impl<I: Interner> Solution<I> {
         // Otherwise, always downgrade to Ambig:
 
         let guidance = match (self.into_guidance(), other.into_guidance()) {
-            (Definite(ref subst1), Definite(ref subst2)) if subst1 == subst2 => {
-                Definite(subst1.clone())
+            (Definite(subst1), Definite(subst2)) => {
+                let subst = slg::aggregate::merge_into_guidance(
+                    interner,
+                    root_binders,
+                    subst1,
+                    &subst2.value,
+                );
+                if subst.value.is_empty(interner) || slg::aggregate::is_trivial(interner, &subst) {
+                    Unknown
+                } else {
+                    Definite(subst)
+                }
             }
             (Suggested(ref subst1), Suggested(ref subst2)) if subst1 == subst2 => {
                 Suggested(subst1.clone())
@@ -136,7 +173,7 @@ impl<I: Interner> Solution<I> {
         }
     }
 
-    pub(crate) fn is_ambig(&self) -> bool {
+    pub fn is_ambig(&self) -> bool {
         match *self {
             Solution::Ambig(_) => true,
             _ => false,
@@ -151,6 +188,32 @@ impl<I: Interner> Solution<I> {
     }
 }
 
+/// If `a` and `b` have the same substitution, and the region constraints of
+/// one of them include all of those of the other, returns the one with fewer
+/// constraints. Either of them is enough for the goal to hold, and the
+/// constraints of the other imply its own, so the goal holds exactly when
+/// those fewer constraints do.
+pub(crate) fn weaker_constrained_subst<I: Interner>(
+    a: &Canonical<ConstrainedSubst<I>>,
+    b: &Canonical<ConstrainedSubst<I>>,
+) -> Option<Canonical<ConstrainedSubst<I>>> {
+    if a.binders != b.binders || a.value.subst != b.value.subst {
+        return None;
+    }
+    let includes = |c1: &ConstrainedSubst<I>, c2: &ConstrainedSubst<I>| {
+        c2.constraints
+            .iter()
+            .all(|constraint| c1.constraints.contains(constraint))
+    };
+    if includes(&b.value, &a.value) {
+        Some(a.clone())
+    } else if includes(&a.value, &b.value) {
+        Some(b.clone())
+    } else {
+        None
+    }
+}
+
 pub struct SolutionDisplay<'a, I: Interner> {
     solution: &'a Solution<I>,
     interner: &'a I,
@@ -176,17 +239,39 @@ impl<'a, I: Interner> fmt::Display for SolutionDisplay<'a, I> {
     }
 }
 
+/// The overflow depth of the default recursive solver.
+const DEFAULT_OVERFLOW_DEPTH: usize = 100;
+
+/// Which solver to use, and how it behaves. Start from `slg_default` or
+/// `recursive`, and adjust the behaviors with the builder methods:
+///
+/// ```
+/// # use chalk_solve::SolverChoice;
+/// let solver_choice = SolverChoice::recursive()
+///     .max_depth(20)
+///     .coinductive_auto_traits(true);
+/// ```
 #[derive(Copy, Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Hash)]
 pub enum SolverChoice {
     /// Run the SLG solver, producing a Solution.
     SLG {
         max_size: usize,
         expected_answers: Option<usize>,
+        /// Whether goals about auto traits are coinductive; see
+        /// `SolverChoice::coinductive_auto_traits`.
+        coinductive_auto_traits: bool,
     },
     /// Run the recursive solver.
     Recursive {
         overflow_depth: usize,
         caching_enabled: bool,
+        /// The overflow depth up to which goals are solved again, with
+        /// twice the overflow depth each time, while some goal overflows,
+        /// if they are; see `SolverChoice::iterative_deepening`.
+        max_deepening_depth: Option<usize>,
+        /// Whether goals about auto traits are coinductive; see
+        /// `SolverChoice::coinductive_auto_traits`.
+        coinductive_auto_traits: bool,
     },
 }
 
@@ -196,6 +281,7 @@ impl SolverChoice {
         SolverChoice::SLG {
             max_size,
             expected_answers,
+            coinductive_auto_traits: true,
         }
     }
 
@@ -206,10 +292,133 @@ impl SolverChoice {
 
     /// Returns the default recursive solver setup.
     pub fn recursive() -> Self {
+        SolverChoice::recursive_with_overflow_depth(DEFAULT_OVERFLOW_DEPTH)
+    }
+
+    /// Returns the recursive solver setup, giving up on goals (treating
+    /// them as ambiguous) that are nested more than `overflow_depth` deep.
+    pub fn recursive_with_overflow_depth(overflow_depth: usize) -> Self {
         SolverChoice::Recursive {
-            overflow_depth: 100,
+            overflow_depth,
             caching_enabled: true,
+            max_deepening_depth: None,
+            coinductive_auto_traits: true,
+        }
+    }
+
+    /// Returns the default recursive solver setup with iterative
+    /// deepening up to `max_overflow_depth`; see `iterative_deepening`.
+    pub fn recursive_iterative_deepening(max_overflow_depth: usize) -> Self {
+        SolverChoice::recursive().iterative_deepening(Some(max_overflow_depth))
+    }
+
+    /// Sets the overflow depth of the recursive solver: goals nested more
+    /// than `max_depth` deep are given up on, and treated as ambiguous.
+    /// Has no effect on the SLG solver, which bounds the size of goals
+    /// instead; see `max_size`.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        if let SolverChoice::Recursive { overflow_depth, .. } = &mut self {
+            *overflow_depth = max_depth;
+        }
+        self
+    }
+
+    /// Sets the size past which the SLG solver truncates goals and
+    /// answers. Has no effect on the recursive solver, which bounds the
+    /// depth of goals instead; see `max_depth`.
+    pub fn max_size(mut self, size: usize) -> Self {
+        if let SolverChoice::SLG { max_size, .. } = &mut self {
+            *max_size = size;
+        }
+        self
+    }
+
+    /// Sets how many answers the SLG solver must find for each goal;
+    /// making a solution panics otherwise. Only really useful for tests.
+    /// Has no effect on the recursive solver.
+    pub fn expected_answers(mut self, answers: Option<usize>) -> Self {
+        if let SolverChoice::SLG {
+            expected_answers, ..
+        } = &mut self
+        {
+            *expected_answers = answers;
+        }
+        self
+    }
+
+    /// Sets whether the recursive solver caches the solutions of goals
+    /// that do not depend on goals still being solved. Has no effect on
+    /// the SLG solver, whose tables always do.
+    pub fn caching(mut self, enabled: bool) -> Self {
+        if let SolverChoice::Recursive {
+            caching_enabled, ..
+        } = &mut self
+        {
+            *caching_enabled = enabled;
+        }
+        self
+    }
+
+    /// Sets whether the recursive solver uses iterative deepening, and up
+    /// to which overflow depth. With iterative deepening, a goal is first
+    /// solved with the overflow depth of the solver. If some goal
+    /// overflows, the goal is solved again with twice the overflow depth,
+    /// and so on, until no goal overflows, the depth reaches
+    /// `max_overflow_depth`, or solving is stopped by cancellation or a
+    /// budget. This finds the solutions that are deeper than the overflow
+    /// depth, at the cost of solving the goals that overflow again. The
+    /// solutions of the goals that did not overflow are cached, unless
+    /// caching is disabled, so they are not solved again.
+    ///
+    /// As the recursive solver recurses on the goals it solves, the
+    /// maximum depth also keeps infinitely recursive goals from
+    /// exhausting the stack of the thread. Has no effect on the SLG
+    /// solver.
+    pub fn iterative_deepening(mut self, max_overflow_depth: Option<usize>) -> Self {
+        if let SolverChoice::Recursive {
+            max_deepening_depth,
+            ..
+        } = &mut self
+        {
+            *max_deepening_depth = max_overflow_depth;
+        }
+        self
+    }
+
+    /// Sets whether goals about auto traits are coinductive, that is,
+    /// whether proving `T: Send` may assume `T: Send` when it comes up
+    /// again, as it does for recursive types. This is the case by
+    /// default, as in Rust; without it, such cycles have no solution.
+    pub fn coinductive_auto_traits(mut self, enabled: bool) -> Self {
+        match &mut self {
+            SolverChoice::SLG {
+                coinductive_auto_traits,
+                ..
+            }
+            | SolverChoice::Recursive {
+                coinductive_auto_traits,
+                ..
+            } => *coinductive_auto_traits = enabled,
         }
+        self
+    }
+
+    /// Solves the given goal, which must be in canonical form, with a
+    /// fresh solver. If the goal is a conjunction of goals that share no
+    /// variables, each group of goals that do is solved on its own
+    /// thread, and their solutions are combined.
+    #[cfg(feature = "parallel")]
+    pub fn solve_parallel<I>(
+        self,
+        program: &(dyn RustIrDatabase<I> + Sync),
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+    ) -> Option<Solution<I>>
+    where
+        I: Interner + Send + Sync,
+        UCanonical<InEnvironment<Goal<I>>>: Send + Sync,
+        Solution<I>: Send,
+    {
+        parallel::solve_parallel(self, program, goal)
     }
 
     /// Creates a solver state.
@@ -218,16 +427,25 @@ impl SolverChoice {
             SolverChoice::SLG {
                 max_size,
                 expected_answers,
-            } => Solver(SolverImpl::Slg {
-                forest: Box::new(Forest::new(SlgContext::new(max_size, expected_answers))),
-            }),
+                coinductive_auto_traits,
+            } => {
+                let mut context = SlgContext::new(max_size, expected_answers);
+                context.set_coinductive_auto_traits(coinductive_auto_traits);
+                Solver::new(SolverImpl::Slg {
+                    forest: Box::new(Forest::new(context)),
+                })
+            }
             SolverChoice::Recursive {
                 overflow_depth,
                 caching_enabled,
-            } => Solver(SolverImpl::Recursive(Box::new(RecursiveContext::new(
-                overflow_depth,
-                caching_enabled,
-            )))),
+                max_deepening_depth,
+                coinductive_auto_traits,
+            } => {
+                let mut context =
+                    RecursiveContext::new(overflow_depth, caching_enabled, max_deepening_depth);
+                context.set_coinductive_auto_traits(coinductive_auto_traits);
+                Solver::new(SolverImpl::Recursive(Box::new(context)))
+            }
         }
     }
 }
@@ -239,11 +457,130 @@ impl Default for SolverChoice {
     }
 }
 
+/// A flag that can be set, from any thread, to stop the solving of a
+/// goal; see [`Solver::solve_cancellable`]. Clones of a token share the
+/// same flag.
+///
+/// [`Solver::solve_cancellable`]: struct.Solver.html#method.solve_cancellable
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    /// Stops the solving of the goals that this token was passed to.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Limits on the work done to solve a goal; see
+/// [`Solver::solve_with_budget`]. The default budget is unlimited.
+///
+/// [`Solver::solve_with_budget`]: struct.Solver.html#method.solve_with_budget
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Budget {
+    /// The number of steps that may be taken. A step is the solving of
+    /// a new goal with the recursive solver, or a quantum of work with
+    /// the SLG solver.
+    pub max_steps: Option<usize>,
+
+    /// The time that may be spent.
+    pub max_duration: Option<Duration>,
+}
+
+/// The budget for solving a goal ran out before it was solved.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BudgetExhausted;
+
+impl fmt::Display for BudgetExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "budget exhausted")
+    }
+}
+
+/// How often the answer cache of a solver had the solution of a goal;
+/// see [`Solver::enable_answer_cache`].
+///
+/// [`Solver::enable_answer_cache`]: struct.Solver.html#method.enable_answer_cache
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AnswerCacheStats {
+    /// The number of goals whose solution was in the cache.
+    pub hits: usize,
+
+    /// The number of goals that had to be solved.
+    pub misses: usize,
+}
+
+/// How much work the solver did to solve the last goal posed to it; see
+/// [`Solver::last_stats`]. Comparing them across versions of the solver
+/// shows whether it does more or less work for a goal.
+///
+/// The SLG solver counts the goals it creates a table for as solved, the
+/// clauses it resolves against them as unified, and the goals whose
+/// table it reuses as found in a cache.
+///
+/// [`Solver::last_stats`]: struct.Solver.html#method.last_stats
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SolverStats {
+    /// The number of goals that were solved, the goal itself included,
+    /// rather than found in a cache or on the stack of goals.
+    pub subgoals: usize,
+
+    /// The number of program clauses whose consequence was unified with
+    /// a goal.
+    pub unifications: usize,
+
+    /// The number of goals whose solution was found in a cache.
+    pub cache_hits: usize,
+
+    /// The largest number of goals that were on the stack at once.
+    pub max_depth: usize,
+}
+
+/// The solutions of the goals posed to a solver so far.
+struct AnswerCache<I: Interner> {
+    solutions: FxHashMap<UCanonical<InEnvironment<Goal<I>>>, Option<Solution<I>>>,
+    stats: AnswerCacheStats,
+}
+
+impl<I: Interner> AnswerCache<I> {
+    fn new() -> Self {
+        AnswerCache {
+            solutions: FxHashMap::default(),
+            stats: AnswerCacheStats::default(),
+        }
+    }
+
+    /// Looks up the solution of `goal`, counting a hit or a miss.
+    fn get(&mut self, goal: &UCanonical<InEnvironment<Goal<I>>>) -> Option<Option<Solution<I>>> {
+        let solution = self.solutions.get(goal).cloned();
+        if solution.is_some() {
+            self.stats.hits += 1;
+        } else {
+            self.stats.misses += 1;
+        }
+        solution
+    }
+}
+
 /// Finds the solution to "goals", or trait queries -- i.e., figures
 /// out what sets of types implement which traits. Also, between
 /// queries, this struct stores the cached state from previous solver
 /// attempts, which can then be re-used later.
-pub struct Solver<I: Interner>(SolverImpl<I>);
+pub struct Solver<I: Interner> {
+    solver: SolverImpl<I>,
+
+    /// The solutions of root goals, once enabled with
+    /// `enable_answer_cache`.
+    answer_cache: Option<AnswerCache<I>>,
+}
 
 enum SolverImpl<I: Interner> {
     Slg { forest: Box<Forest<SlgContext<I>>> },
@@ -251,6 +588,13 @@ enum SolverImpl<I: Interner> {
 }
 
 impl<I: Interner> Solver<I> {
+    fn new(solver: SolverImpl<I>) -> Self {
+        Solver {
+            solver,
+            answer_cache: None,
+        }
+    }
+
     /// Attempts to solve the given goal, which must be in canonical
     /// form. Returns a unique solution (if one exists).  This will do
     /// only as much work towards `goal` as it has to (and that work
@@ -274,13 +618,7 @@ impl<I: Interner> Solver<I> {
         program: &dyn RustIrDatabase<I>,
         goal: &UCanonical<InEnvironment<Goal<I>>>,
     ) -> Option<Solution<I>> {
-        match &mut self.0 {
-            SolverImpl::Slg { forest } => {
-                let ops = forest.context().ops(program);
-                forest.solve(&ops, goal, || true)
-            }
-            SolverImpl::Recursive(ctx) => ctx.solver(program).solve_root_goal(goal).ok(),
-        }
+        self.solve_limited(program, goal, || true)
     }
 
     /// Attempts to solve the given goal, which must be in canonical
@@ -311,15 +649,91 @@ impl<I: Interner> Solver<I> {
         goal: &UCanonical<InEnvironment<Goal<I>>>,
         should_continue: impl std::ops::Fn() -> bool,
     ) -> Option<Solution<I>> {
-        match &mut self.0 {
+        if let Some(solution) = self.answer_cache.as_mut().and_then(|cache| cache.get(goal)) {
+            match &mut self.solver {
+                SolverImpl::Slg { forest } => forest.context().set_stats(SolverStats {
+                    cache_hits: 1,
+                    ..SolverStats::default()
+                }),
+                SolverImpl::Recursive(ctx) => ctx.record_answer_cache_hit(),
+            }
+            return solution;
+        }
+
+        // Solutions of goals whose solving was stopped are not cached.
+        let stopped = Cell::new(false);
+        let should_continue = || {
+            let go_on = should_continue();
+            if !go_on {
+                stopped.set(true);
+            }
+            go_on
+        };
+        let solution = match &mut self.solver {
             SolverImpl::Slg { forest } => {
+                forest.context().set_stats(SolverStats::default());
                 let ops = forest.context().ops(program);
                 forest.solve(&ops, goal, should_continue)
             }
-            SolverImpl::Recursive(ctx) => {
-                // TODO support should_continue in recursive solver
-                ctx.solver(program).solve_root_goal(goal).ok()
+            SolverImpl::Recursive(ctx) => ctx
+                .limited_solver(program, &should_continue)
+                .solve_root_goal(goal)
+                .ok(),
+        };
+        if let Some(cache) = &mut self.answer_cache {
+            if !stopped.get() {
+                cache.solutions.insert(goal.clone(), solution.clone());
+            }
+        }
+        solution
+    }
+
+    /// Attempts to solve the given goal, which must be in canonical
+    /// form, like `solve_limited`, stopping once `token` is cancelled.
+    /// The solution of a goal whose solving was stopped is ambiguous,
+    /// and is not cached, so the goal can be solved again later.
+    pub fn solve_cancellable(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+        token: &CancellationToken,
+    ) -> Option<Solution<I>> {
+        self.solve_limited(program, goal, || !token.is_cancelled())
+    }
+
+    /// Attempts to solve the given goal, which must be in canonical
+    /// form, like `solve_limited`, stopping once `budget` is used up.
+    /// As with cancellation, the goals whose solving was stopped are not
+    /// cached.
+    ///
+    /// # Returns
+    ///
+    /// - `Err(BudgetExhausted)` if the budget ran out.
+    /// - `Ok(solution)` otherwise, where `solution` is as for `solve`.
+    pub fn solve_with_budget(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+        budget: Budget,
+    ) -> Result<Option<Solution<I>>, BudgetExhausted> {
+        // The clock is only read if there is a time limit, as there is no
+        // clock to read on some targets, like wasm32-unknown-unknown.
+        let deadline = budget.max_duration.map(|max| (Instant::now(), max));
+        let steps = Cell::new(0);
+        let exhausted = Cell::new(false);
+        let solution = self.solve_limited(program, goal, || {
+            steps.set(steps.get() + 1);
+            let out_of_steps = budget.max_steps.map_or(false, |max| steps.get() > max);
+            let out_of_time = deadline.map_or(false, |(start, max)| start.elapsed() >= max);
+            if out_of_steps || out_of_time {
+                exhausted.set(true);
             }
+            !exhausted.get()
+        });
+        if exhausted.get() {
+            Err(BudgetExhausted)
+        } else {
+            Ok(solution)
         }
     }
 
@@ -341,6 +755,17 @@ impl<I: Interner> Solver<I> {
     ///   - second argument is ther next solution present
     ///   - returns true if next solution should be handled
     ///
+    /// Both solvers enumerate the answers of a goal one by one, each
+    /// passed once the next one is found, and stop producing them once
+    /// `f` returns `false`. The answers of the recursive solver are the
+    /// distinct solutions that the program clauses of a goal, or the
+    /// alternatives of a disjunction, give on their own, rather than the
+    /// solution that combines them; those of low-priority clauses come
+    /// after the others. Each is `Definite` if it is unique, with its
+    /// lifetime constraints, and `Ambiguous` otherwise, with its guidance
+    /// as its substitution, or the identity substitution if there is no
+    /// guidance. Other goals have at most one answer, their solution.
+    ///
     /// # Returns
     ///
     /// - `true` all solutions were processed with the function.
@@ -349,14 +774,249 @@ impl<I: Interner> Solver<I> {
         &mut self,
         program: &dyn RustIrDatabase<I>,
         goal: &UCanonical<InEnvironment<Goal<I>>>,
-        f: impl FnMut(SubstitutionResult<Canonical<ConstrainedSubst<I>>>, bool) -> bool,
+        mut f: impl FnMut(SubstitutionResult<Canonical<ConstrainedSubst<I>>>, bool) -> bool,
     ) -> bool {
-        match &mut self.0 {
+        match &mut self.solver {
             SolverImpl::Slg { forest } => {
+                forest.context().set_stats(SolverStats::default());
                 let ops = forest.context().ops(program);
                 forest.solve_multiple(&ops, goal, f)
             }
-            SolverImpl::Recursive(_ctx) => unimplemented!(),
+            SolverImpl::Recursive(ctx) => {
+                let interner = program.interner();
+                let result =
+                    ctx.solver(program)
+                        .solve_root_goal_answers(goal, &mut |answer, has_next| {
+                            let result = match answer {
+                                Solution::Unique(subst) => SubstitutionResult::Definite(subst),
+                                Solution::Ambig(guidance) => {
+                                    let subst = match guidance {
+                                        Guidance::Definite(subst) | Guidance::Suggested(subst) => {
+                                            subst
+                                        }
+                                        Guidance::Unknown => Canonical {
+                                            value: goal.trivial_substitution(interner),
+                                            binders: goal.canonical.binders.clone(),
+                                        },
+                                    };
+                                    SubstitutionResult::Ambiguous(Canonical {
+                                        value: ConstrainedSubst {
+                                            subst: subst.value,
+                                            constraints: vec![],
+                                        },
+                                        binders: subst.binders,
+                                    })
+                                }
+                            };
+                            f(result, has_next)
+                        });
+                match result {
+                    Ok(all) => all,
+                    Err(Floundered) => f(SubstitutionResult::Floundered, false),
+                }
+            }
+        }
+    }
+
+    /// Discards the cached solutions that could be affected by adding
+    /// or removing impls of the traits `trait_ids`, so that the solver
+    /// can keep being used after doing so. The solutions that do not
+    /// depend on those impls are kept: the recursive solver keeps the
+    /// cached solutions of the other goals, and the SLG solver keeps the
+    /// tables of the goals that are not about those traits and that did
+    /// not select such a goal as a subgoal, directly or not.
+    ///
+    /// `program` is only used to find the traits of associated types.
+    pub fn invalidate_traits(&mut self, program: &dyn RustIrDatabase<I>, trait_ids: &[TraitId<I>]) {
+        if let Some(cache) = &mut self.answer_cache {
+            cache.solutions.clear();
+        }
+        match &mut self.solver {
+            SolverImpl::Slg { forest } => {
+                let interner = program.interner();
+                forest.invalidate(|goal| match goal.canonical.value.goal.data(interner) {
+                    GoalData::DomainGoal(domain_goal) => match impls_trait_id(program, domain_goal)
+                    {
+                        Some(trait_id) => trait_ids.contains(&trait_id),
+                        None => false,
+                    },
+                    _ => false,
+                });
+            }
+            SolverImpl::Recursive(ctx) => ctx.invalidate_traits(trait_ids),
+        }
+    }
+
+    /// Installs `sink` to receive the steps taken while solving goals,
+    /// replacing any earlier sink, or removes the sink if `sink` is
+    /// `None`. See the `trace` module for the steps each solver reports.
+    pub fn set_trace_sink(&mut self, sink: Option<Box<dyn TraceSink<I>>>) {
+        match &mut self.solver {
+            SolverImpl::Slg { forest } => forest.context().set_trace_sink(sink),
+            SolverImpl::Recursive(ctx) => ctx.set_trace_sink(sink),
+        }
+    }
+
+    /// Keeps the solution of each goal solved from now on with `solve`
+    /// (or `solve_limited` and the like, unless solving was stopped), and
+    /// returns it whenever the same goal is posed again, without solving
+    /// it. The goals are compared in canonical form, so goals that differ
+    /// only in the names of their variables share their solution.
+    ///
+    /// This is worth it when many goals are posed more than once, as is
+    /// common in test suites. The recursive solver already caches the
+    /// solutions of the goals it completes, unless caching is disabled,
+    /// but the SLG solver aggregates the answers of a goal again each
+    /// time it is posed. The cache is cleared by `invalidate_traits` and
+    /// `set_clause_order`.
+    pub fn enable_answer_cache(&mut self) {
+        if self.answer_cache.is_none() {
+            self.answer_cache = Some(AnswerCache::new());
+        }
+    }
+
+    /// How often the answer cache had the solution of a goal, if it was
+    /// enabled.
+    pub fn answer_cache_stats(&self) -> Option<AnswerCacheStats> {
+        self.answer_cache.as_ref().map(|cache| cache.stats)
+    }
+
+    /// How much work the solver did to solve the last goal posed to it,
+    /// whichever method it was posed with. The SLG solver reports the
+    /// work of the recursive solver that it uses for proof trees and
+    /// explanations.
+    ///
+    /// With iterative deepening, the work done at all the overflow depths
+    /// is counted. A solution found in the answer cache counts as a
+    /// cache hit only.
+    pub fn last_stats(&self) -> SolverStats {
+        match &self.solver {
+            SolverImpl::Slg { forest } => forest.context().stats(),
+            SolverImpl::Recursive(ctx) => ctx.stats(),
+        }
+    }
+
+    /// The goals that the recursive solver was solving when it gave up on
+    /// one because of its overflow depth, while solving the last goal
+    /// posed to it: that goal first, each goal followed by the subgoal
+    /// it was solving, and the goal given up on last. The solution of
+    /// such a goal is ambiguous, which this tells apart from ambiguity
+    /// in the program.
+    ///
+    /// This is `None` if no goal overflowed, if the solution was found
+    /// in the answer cache, or for the SLG solver, which bounds the size
+    /// of goals rather than their depth.
+    pub fn last_overflow(&self) -> Option<&[UCanonical<InEnvironment<Goal<I>>>]> {
+        match &self.solver {
+            SolverImpl::Slg { .. } => None,
+            SolverImpl::Recursive(ctx) => ctx.overflow_stack(),
+        }
+    }
+
+    /// Sets the order in which the program clauses that could prove a
+    /// goal are tried, which is `ByPriority` by default. The SLG solver
+    /// discards its tables, which were built trying clauses in the
+    /// previous order.
+    pub fn set_clause_order(&mut self, clause_order: Arc<dyn ClauseOrder<I>>) {
+        if let Some(cache) = &mut self.answer_cache {
+            cache.solutions.clear();
+        }
+        match &mut self.solver {
+            SolverImpl::Slg { forest } => {
+                let mut context = forest.context().clone();
+                context.set_clause_order(clause_order);
+                **forest = Forest::new(context);
+            }
+            SolverImpl::Recursive(ctx) => ctx.set_clause_order(clause_order),
+        }
+    }
+
+    /// Sets how the program clauses for a goal are generated; see
+    /// `ProgramClauseProvider`. By default, they are generated from the
+    /// declarations of the program. The solvers discard the solutions
+    /// they cached, which were found with the previous clauses.
+    pub fn set_clause_provider(&mut self, clause_provider: Arc<dyn ProgramClauseProvider<I>>) {
+        if let Some(cache) = &mut self.answer_cache {
+            cache.solutions.clear();
+        }
+        match &mut self.solver {
+            SolverImpl::Slg { forest } => {
+                let mut context = forest.context().clone();
+                context.set_clause_provider(clause_provider);
+                **forest = Forest::new(context);
+            }
+            SolverImpl::Recursive(ctx) => ctx.set_clause_provider(clause_provider),
+        }
+    }
+
+    /// Attempts to solve the given goal, which must be in canonical
+    /// form, recording how it was solved: the program clause used for
+    /// each goal, and how the goals it required were solved in turn.
+    ///
+    /// The proof tree is recorded by the recursive solver, without using
+    /// the solutions cached by earlier attempts. With the SLG solver, the
+    /// goal is solved again by a recursive solver with the default
+    /// overflow depth, so the solution in the tree might be less precise
+    /// than the one found by `solve`.
+    ///
+    /// # Returns
+    ///
+    /// - `None` if the goal cannot be proven.
+    /// - `Some(tree)` otherwise, where the solution of the root of `tree`
+    ///   is the solution of the goal.
+    pub fn solve_with_proof_tree(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+    ) -> Option<ProofTree<I>> {
+        let tree = match &mut self.solver {
+            SolverImpl::Slg { forest } => {
+                let mut context = forest
+                    .context()
+                    .recursive_context(DEFAULT_OVERFLOW_DEPTH, false);
+                let tree = context
+                    .solver(program)
+                    .solve_root_goal_with_proof_tree(goal);
+                forest.context().set_stats(context.stats());
+                tree
+            }
+            SolverImpl::Recursive(ctx) => ctx.solver(program).solve_root_goal_with_proof_tree(goal),
+        }?;
+        if tree.solution.is_ok() {
+            Some(tree)
+        } else {
+            None
+        }
+    }
+
+    /// Explains why the given goal, which must be in canonical form, has
+    /// no solution: which program clauses could have proven it, and why
+    /// each of them was rejected, down to the goals they required that
+    /// have no solution in turn.
+    ///
+    /// The explanation is found with the recursive solver, reusing its
+    /// cached solutions. With the SLG solver, a recursive solver with the
+    /// default overflow depth is used instead.
+    ///
+    /// # Returns
+    ///
+    /// - `None` if the goal has a solution.
+    /// - `Some(explanation)` otherwise.
+    pub fn explain_failure(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+    ) -> Option<Explanation<I>> {
+        match &mut self.solver {
+            SolverImpl::Slg { forest } => {
+                let mut context = forest
+                    .context()
+                    .recursive_context(DEFAULT_OVERFLOW_DEPTH, true);
+                let explanation = Explainer::new(program, &mut context).explain_failure(goal);
+                forest.context().set_stats(context.stats());
+                explanation
+            }
+            SolverImpl::Recursive(ctx) => Explainer::new(program, ctx).explain_failure(goal),
         }
     }
 }
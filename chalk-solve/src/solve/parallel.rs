@@ -0,0 +1,288 @@
+//! Solving the independent conjuncts of a goal in parallel.
+//!
+//! A goal like `exists<T, U> { T: Foo, U: Bar }` is the conjunction of
+//! `T: Foo` and `U: Bar`, which share no variables. Such conjuncts
+//! cannot affect each other, so they can be solved separately, each on
+//! its own thread with its own solver, and their solutions combined.
+
+use super::{Guidance, Solution, SolverChoice};
+use crate::infer::InferenceTable;
+use crate::RustIrDatabase;
+use chalk_ir::interner::Interner;
+use chalk_ir::visit::{Visit, Visitor};
+use chalk_ir::*;
+use rayon::prelude::*;
+
+/// Solves `goal` like `SolverChoice::solve_parallel`.
+pub(super) fn solve_parallel<I>(
+    solver_choice: SolverChoice,
+    program: &(dyn RustIrDatabase<I> + Sync),
+    goal: &UCanonical<InEnvironment<Goal<I>>>,
+) -> Option<Solution<I>>
+where
+    I: Interner + Send + Sync,
+    UCanonical<InEnvironment<Goal<I>>>: Send + Sync,
+    Solution<I>: Send,
+{
+    let components = match independent_components(program.interner(), goal) {
+        Some(components) => components,
+        None => return solver_choice.into_solver().solve(program, goal),
+    };
+    debug!(
+        "solve_parallel: solving {} independent components",
+        components.len()
+    );
+    let solutions: Vec<Option<Solution<I>>> = components
+        .par_iter()
+        .map(|component| solver_choice.into_solver().solve(program, &component.goal))
+        .collect();
+    let solutions: Option<Vec<Solution<I>>> = solutions.into_iter().collect();
+    Some(combine(program.interner(), goal, &components, solutions?))
+}
+
+/// A group of conjuncts of a goal that share no variables with the
+/// rest of the goal.
+struct Component<I: Interner> {
+    /// The conjuncts of the group, in their original order, as a goal
+    /// that only binds the variables they mention.
+    goal: UCanonical<InEnvironment<Goal<I>>>,
+
+    /// For each variable bound by `goal`, the index of the variable of
+    /// the original goal that it stands for.
+    variables: Vec<usize>,
+}
+
+/// Splits the conjunction `goal` into groups of conjuncts that share no
+/// variables, returning a component for each group. Returns `None` if
+/// the goal cannot be split.
+fn independent_components<I: Interner>(
+    interner: &I,
+    goal: &UCanonical<InEnvironment<Goal<I>>>,
+) -> Option<Vec<Component<I>>> {
+    let InEnvironment {
+        environment,
+        goal: root,
+    } = &goal.canonical.value;
+    let conjuncts = match root.data(interner) {
+        GoalData::All(goals) => goals.as_slice(interner),
+        _ => return None,
+    };
+
+    // Clauses of the environment that mention variables could
+    // constrain them in any conjunct.
+    if !variables(interner, environment).is_empty() {
+        return None;
+    }
+
+    // Group the indices of the conjuncts, merging the groups of
+    // conjuncts that share a variable.
+    let mut groups: Vec<(Vec<usize>, Vec<usize>)> = vec![];
+    for (position, conjunct) in conjuncts.iter().enumerate() {
+        let mut vars = variables(interner, conjunct);
+        let mut members = vec![position];
+        let mut index = 0;
+        while index < groups.len() {
+            if groups[index].0.iter().any(|var| vars.contains(var)) {
+                let (group_vars, group_members) = groups.remove(index);
+                vars.extend(group_vars);
+                members.extend(group_members);
+            } else {
+                index += 1;
+            }
+        }
+        groups.push((vars, members));
+    }
+    if groups.len() < 2 {
+        return None;
+    }
+
+    // Keep the components, and the conjuncts within them, in the order
+    // of the original goal. Merged groups share variables, which must
+    // only be bound once.
+    for (vars, members) in &mut groups {
+        vars.sort_unstable();
+        vars.dedup();
+        members.sort_unstable();
+    }
+    groups.sort_by_key(|(_, members)| members[0]);
+
+    let binders = goal.canonical.binders.as_slice(interner);
+    Some(
+        groups
+            .into_iter()
+            .map(|(vars, members)| {
+                // Renumber the variables of the group to their position
+                // in `vars`.
+                let renumbering = Substitution::from(
+                    interner,
+                    binders.iter().enumerate().map(|(index, kind)| {
+                        let index = vars.binary_search(&index).unwrap_or(index);
+                        (&kind.map_ref(|_| ()), index).to_parameter(interner)
+                    }),
+                );
+                let members = members
+                    .into_iter()
+                    .map(|member| renumbering.apply(&conjuncts[member], interner));
+                Component {
+                    goal: UCanonical {
+                        universes: goal.universes,
+                        canonical: Canonical {
+                            binders: CanonicalVarKinds::from(
+                                interner,
+                                vars.iter().map(|&var| binders[var]),
+                            ),
+                            value: InEnvironment {
+                                environment: environment.clone(),
+                                goal: GoalData::All(Goals::from(interner, members))
+                                    .intern(interner),
+                            },
+                        },
+                    },
+                    variables: vars,
+                }
+            })
+            .collect(),
+    )
+}
+
+/// Combines the solutions of the `components` of `goal`. Each of them
+/// only constrains the variables of its component, so they can be
+/// merged by unifying them with the variables of `goal` they stand for.
+fn combine<I: Interner>(
+    interner: &I,
+    goal: &UCanonical<InEnvironment<Goal<I>>>,
+    components: &[Component<I>],
+    solutions: Vec<Solution<I>>,
+) -> Solution<I> {
+    let (mut infer, subst, InEnvironment { environment, .. }) =
+        InferenceTable::from_canonical(interner, goal.universes, &goal.canonical);
+    let mut constraints = vec![];
+    let mut all_unique = true;
+    let mut suggested = false;
+    for (component, solution) in components.iter().zip(solutions) {
+        let solution_subst = match solution {
+            Solution::Unique(constrained) => {
+                let constrained = infer.instantiate_canonical(interner, &constrained);
+                constraints.extend(constrained.constraints);
+                constrained.subst
+            }
+            Solution::Ambig(guidance) => {
+                all_unique = false;
+                match guidance {
+                    Guidance::Definite(subst) => infer.instantiate_canonical(interner, &subst),
+                    Guidance::Suggested(subst) => {
+                        suggested = true;
+                        infer.instantiate_canonical(interner, &subst)
+                    }
+                    Guidance::Unknown => continue,
+                }
+            }
+        };
+        for (index, &var) in component.variables.iter().enumerate() {
+            infer
+                .unify(
+                    interner,
+                    &environment,
+                    subst.at(interner, var),
+                    solution_subst.at(interner, index),
+                )
+                .expect("solutions of independent goals must unify");
+        }
+    }
+
+    if all_unique {
+        let constrained = ConstrainedSubst { subst, constraints };
+        return Solution::Unique(infer.canonicalize(interner, &constrained).quantified);
+    }
+    if infer.is_trivial_substitution(interner, &subst) {
+        return Solution::Ambig(Guidance::Unknown);
+    }
+    let subst = infer.canonicalize(interner, &subst).quantified;
+    if suggested {
+        Solution::Ambig(Guidance::Suggested(subst))
+    } else {
+        Solution::Ambig(Guidance::Definite(subst))
+    }
+}
+
+/// The indices of the canonical variables that `value` mentions.
+fn variables<I: Interner, T: Visit<I>>(interner: &I, value: &T) -> Vec<usize> {
+    let mut collector = VariableCollector {
+        interner,
+        variables: vec![],
+    };
+    value.visit_with(&mut collector, DebruijnIndex::INNERMOST);
+    collector.variables
+}
+
+struct VariableCollector<'i, I: Interner> {
+    interner: &'i I,
+    variables: Vec<usize>,
+}
+
+impl<'i, I: Interner> VariableCollector<'i, I> {
+    fn add(&mut self, bound_var: BoundVar, outer_binder: DebruijnIndex) {
+        if let Some(var) = bound_var.shifted_out_to(outer_binder) {
+            if !self.variables.contains(&var.index) {
+                self.variables.push(var.index);
+            }
+        }
+    }
+}
+
+impl<'i, I: Interner> Visitor<'i, I> for VariableCollector<'i, I> {
+    type Result = ();
+
+    fn as_dyn(&mut self) -> &mut dyn Visitor<'i, I, Result = Self::Result> {
+        self
+    }
+
+    fn interner(&self) -> &'i I {
+        self.interner
+    }
+
+    fn visit_free_var_ty(&mut self, bound_var: BoundVar, outer_binder: DebruijnIndex) {
+        self.add(bound_var, outer_binder)
+    }
+
+    fn visit_free_var_lifetime(&mut self, bound_var: BoundVar, outer_binder: DebruijnIndex) {
+        self.add(bound_var, outer_binder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ext::GoalExt;
+    use chalk_integration::db::ChalkDatabase;
+    use chalk_integration::interner::ChalkIr;
+
+    #[test]
+    fn components_bind_each_variable_once() {
+        let db = ChalkDatabase::with(
+            "struct Vec<T> { } struct Foo { } trait Clone { } trait Copy { }",
+            // The solver choice of the `chalk_solve` that
+            // `chalk_integration` depends on, not of this one.
+            Default::default(),
+        );
+        db.with_program(|_| {
+            let interner = &ChalkIr;
+            let goal = db
+                .parse_and_lower_goal("exists<T, U, V> { V: Copy, T = Foo, U = Vec<T>, U: Clone }")
+                .unwrap()
+                .into_peeled_goal(interner);
+            let components = independent_components(interner, &goal).unwrap();
+            let variables: Vec<_> = components
+                .iter()
+                .map(|component| component.variables.clone())
+                .collect();
+            assert_eq!(variables, vec![vec![0], vec![1, 2]]);
+            for component in &components {
+                assert_eq!(
+                    component.goal.canonical.binders.len(interner),
+                    component.variables.len()
+                );
+            }
+        });
+    }
+}
@@ -1,26 +1,32 @@
+use crate::clauses::order::{ByPriority, ClauseOrder};
 use crate::clauses::program_clauses_for_goal;
+use crate::clauses::provider::{FromProgram, ProgramClauseProvider};
 use crate::coinductive_goal::IsCoinductive;
-use crate::infer::ucanonicalize::{UCanonicalized, UniverseMap};
+use crate::infer::ucanonicalize::UniverseMap;
 use crate::infer::unify::UnificationResult;
 use crate::infer::InferenceTable;
+use crate::recursive::RecursiveContext;
 use crate::solve::truncate;
-use crate::solve::Solution;
+use crate::solve::{Solution, SolverStats};
+use crate::trace::{TraceEvent, TraceSink};
 use crate::RustIrDatabase;
 use chalk_derive::HasInterner;
 use chalk_engine::context;
-use chalk_engine::context::Floundered;
+use chalk_engine::context::{Floundered, SolverEvent};
 use chalk_engine::fallible::Fallible;
 use chalk_engine::hh::HhGoal;
 use chalk_engine::{CompleteAnswer, ExClause, Literal};
 use chalk_ir::cast::Cast;
 use chalk_ir::cast::Caster;
+use chalk_ir::fold::shift::Shift;
 use chalk_ir::interner::Interner;
 use chalk_ir::*;
 
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
 use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
 
-mod aggregate;
+pub(super) mod aggregate;
 mod resolvent;
 
 #[derive(Clone, Debug, HasInterner)]
@@ -31,23 +37,100 @@ pub(crate) struct SlgContext<I: Interner> {
     /// will panic if the number of cached answers does not
     /// equal this when a solution is made.
     expected_answers: Option<usize>,
+    /// Generates the program clauses for a goal.
+    clause_provider: Arc<dyn ProgramClauseProvider<I>>,
+    /// The order in which the program clauses for a goal are tried.
+    clause_order: Arc<dyn ClauseOrder<I>>,
+    /// Whether goals about auto traits are coinductive.
+    coinductive_auto_traits: bool,
+    /// Receives the steps taken by the solver, and counts them. It is
+    /// shared with the `SlgContextOps`, which report the steps.
+    monitor: Arc<Mutex<SlgMonitor<I>>>,
     phantom: PhantomData<I>,
 }
 
+/// The trace sink of an SLG solver, and the statistics of the last goal
+/// it solved.
+struct SlgMonitor<I: Interner> {
+    trace: Option<Box<dyn TraceSink<I>>>,
+    stats: SolverStats,
+}
+
+impl<I: Interner> Debug for SlgMonitor<I> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "SlgMonitor {{ .. }}")
+    }
+}
+
 impl<I: Interner> SlgContext<I> {
     pub(crate) fn new(max_size: usize, expected_answers: Option<usize>) -> SlgContext<I> {
         SlgContext {
             max_size,
             expected_answers,
+            clause_provider: Arc::new(FromProgram),
+            clause_order: Arc::new(ByPriority),
+            coinductive_auto_traits: true,
+            monitor: Arc::new(Mutex::new(SlgMonitor {
+                trace: None,
+                stats: SolverStats::default(),
+            })),
             phantom: PhantomData,
         }
     }
 
+    pub(crate) fn set_trace_sink(&self, sink: Option<Box<dyn TraceSink<I>>>) {
+        self.monitor.lock().unwrap().trace = sink;
+    }
+
+    pub(crate) fn stats(&self) -> SolverStats {
+        self.monitor.lock().unwrap().stats
+    }
+
+    /// Sets the statistics reported for the last goal, which are
+    /// counted from there on.
+    pub(crate) fn set_stats(&self, stats: SolverStats) {
+        self.monitor.lock().unwrap().stats = stats;
+    }
+
+    pub(crate) fn set_clause_provider(
+        &mut self,
+        clause_provider: Arc<dyn ProgramClauseProvider<I>>,
+    ) {
+        self.clause_provider = clause_provider;
+    }
+
+    pub(crate) fn set_clause_order(&mut self, clause_order: Arc<dyn ClauseOrder<I>>) {
+        self.clause_order = clause_order;
+    }
+
+    /// A recursive solver context that gets and orders the program
+    /// clauses of goals like this one, for the tasks that only the
+    /// recursive solver does.
+    pub(crate) fn recursive_context(
+        &self,
+        overflow_depth: usize,
+        caching_enabled: bool,
+    ) -> RecursiveContext<I> {
+        let mut context = RecursiveContext::new(overflow_depth, caching_enabled, None);
+        context.set_clause_provider(self.clause_provider.clone());
+        context.set_clause_order(self.clause_order.clone());
+        context.set_coinductive_auto_traits(self.coinductive_auto_traits);
+        context
+    }
+
+    pub(crate) fn set_coinductive_auto_traits(&mut self, coinductive_auto_traits: bool) {
+        self.coinductive_auto_traits = coinductive_auto_traits;
+    }
+
     pub(crate) fn ops<'p>(&self, program: &'p dyn RustIrDatabase<I>) -> SlgContextOps<'p, I> {
         SlgContextOps {
             program,
             max_size: self.max_size,
             expected_answers: self.expected_answers,
+            clause_provider: self.clause_provider.clone(),
+            clause_order: self.clause_order.clone(),
+            coinductive_auto_traits: self.coinductive_auto_traits,
+            monitor: self.monitor.clone(),
         }
     }
 }
@@ -57,6 +140,24 @@ pub(crate) struct SlgContextOps<'me, I: Interner> {
     program: &'me dyn RustIrDatabase<I>,
     max_size: usize,
     expected_answers: Option<usize>,
+    clause_provider: Arc<dyn ProgramClauseProvider<I>>,
+    clause_order: Arc<dyn ClauseOrder<I>>,
+    coinductive_auto_traits: bool,
+    monitor: Arc<Mutex<SlgMonitor<I>>>,
+}
+
+impl<I: Interner> SlgContextOps<'_, I> {
+    /// Reports the event built by `event` to the trace sink, if there
+    /// is one.
+    fn trace(&self, event: impl FnOnce() -> TraceEvent<I>) {
+        if let Some(sink) = &mut self.monitor.lock().unwrap().trace {
+            sink.event(event());
+        }
+    }
+
+    fn count(&self, count: impl FnOnce(&mut SolverStats)) {
+        count(&mut self.monitor.lock().unwrap().stats);
+    }
 }
 
 #[derive(Clone)]
@@ -85,6 +186,7 @@ impl<I: Interner> context::Context for SlgContext<I> {
     type GoalInEnvironment = InEnvironment<Goal<I>>;
     type Substitution = Substitution<I>;
     type RegionConstraint = InEnvironment<Constraint<I>>;
+    type Constraint = Constraint<I>;
     type Variance = ();
     type Interner = I;
 
@@ -92,6 +194,13 @@ impl<I: Interner> context::Context for SlgContext<I> {
         InEnvironment::new(environment, goal)
     }
 
+    fn constraint_in_environment(
+        environment: &Environment<I>,
+        constraint: Constraint<I>,
+    ) -> InEnvironment<Constraint<I>> {
+        InEnvironment::new(environment, constraint)
+    }
+
     fn inference_normalized_subst_from_ex_clause(
         canon_ex_clause: &Canonical<ExClause<SlgContext<I>>>,
     ) -> &Substitution<I> {
@@ -151,7 +260,7 @@ impl<I: Interner> context::Context for SlgContext<I> {
 
 impl<'me, I: Interner> context::ContextOps<SlgContext<I>> for SlgContextOps<'me, I> {
     fn is_coinductive(&self, goal: &UCanonical<InEnvironment<Goal<I>>>) -> bool {
-        goal.is_coinductive(self.program)
+        goal.is_coinductive(self.program, self.coinductive_auto_traits)
     }
 
     fn map_goal_from_canonical(
@@ -176,7 +285,13 @@ impl<'me, I: Interner> context::ContextOps<SlgContext<I>> for SlgContextOps<'me,
         goal: &DomainGoal<I>,
         _infer: &mut TruncatingInferenceTable<I>,
     ) -> Result<Vec<ProgramClause<I>>, Floundered> {
-        let clauses: Vec<_> = program_clauses_for_goal(self.program, environment, goal)?;
+        let clauses: Vec<_> = program_clauses_for_goal(
+            self.program,
+            environment,
+            goal,
+            &*self.clause_provider,
+            &*self.clause_order,
+        )?;
 
         Ok(clauses)
     }
@@ -275,6 +390,11 @@ impl<'me, I: Interner> context::ContextOps<SlgContext<I>> for SlgContextOps<'me,
         domain_goal.cast(self.program.interner())
     }
 
+    fn into_any_goal(&self, goals: Vec<Goal<I>>) -> Goal<I> {
+        let interner = self.program.interner();
+        GoalData::Any(Goals::from(interner, goals)).intern(interner)
+    }
+
     fn is_trivial_substitution(
         &self,
         u_canon: &UCanonical<InEnvironment<Goal<I>>>,
@@ -295,12 +415,74 @@ impl<'me, I: Interner> context::ContextOps<SlgContext<I>> for SlgContextOps<'me,
             }
             GoalData::Implies(dg, subgoal) => HhGoal::Implies(dg, subgoal),
             GoalData::All(goals) => HhGoal::All(goals.iter(interner).cloned().collect()),
+            GoalData::Any(goals) => HhGoal::Any(goals.iter(interner).cloned().collect()),
             GoalData::Not(g1) => HhGoal::Not(g1),
             GoalData::EqGoal(EqGoal { a, b }) => HhGoal::Unify((), a, b),
+            GoalData::Constraint(constraint) => HhGoal::Constraint(constraint),
             GoalData::DomainGoal(domain_goal) => HhGoal::DomainGoal(domain_goal),
             GoalData::CannotProve(()) => HhGoal::CannotProve,
         }
     }
+
+    fn on_event(&self, event: SolverEvent<'_, SlgContext<I>>) {
+        let interner = self.interner();
+        match event {
+            SolverEvent::TableCreated { goal, depth } => {
+                self.count(|stats| {
+                    stats.subgoals += 1;
+                    stats.max_depth = usize::max(stats.max_depth, depth + 1);
+                });
+                self.trace(|| TraceEvent::GoalEntered {
+                    goal: goal.clone(),
+                    depth,
+                })
+            }
+            SolverEvent::TableReused { goal } => {
+                self.count(|stats| stats.cache_hits += 1);
+                self.trace(|| TraceEvent::TableReused { goal: goal.clone() })
+            }
+            SolverEvent::ClauseTried {
+                goal,
+                clause,
+                unified,
+            } => {
+                self.count(|stats| stats.unifications += 1);
+                // Clauses are only tried against the goals of domain goal
+                // tables.
+                let domain_goal = match goal.canonical.value.goal.data(interner) {
+                    GoalData::DomainGoal(domain_goal) => domain_goal,
+                    _ => return,
+                };
+                let goal = || UCanonical {
+                    canonical: Canonical {
+                        value: InEnvironment::new(
+                            &goal.canonical.value.environment,
+                            domain_goal.clone(),
+                        ),
+                        binders: goal.canonical.binders.clone(),
+                    },
+                    universes: goal.universes,
+                };
+                let clause = || match clause.data(interner) {
+                    ProgramClauseData::Implies(implication) => Binders::new(
+                        ParameterKinds::from(interner, vec![]),
+                        implication.shifted_in(interner),
+                    ),
+                    ProgramClauseData::ForAll(implication) => implication.clone(),
+                };
+                self.trace(|| TraceEvent::ClauseTried {
+                    goal: goal(),
+                    clause: clause(),
+                });
+                if !unified {
+                    self.trace(|| TraceEvent::UnificationFailed {
+                        goal: goal(),
+                        clause: clause(),
+                    });
+                }
+            }
+        }
+    }
 }
 
 impl<I: Interner> TruncatingInferenceTable<I> {
@@ -347,12 +529,8 @@ impl<I: Interner> context::UnificationOps<SlgContext<I>> for TruncatingInference
         interner: &I,
         value: &InEnvironment<Goal<I>>,
     ) -> (UCanonical<InEnvironment<Goal<I>>>, UniverseMap) {
-        let canonicalized_goal = self.infer.canonicalize(interner, value).quantified;
-        let UCanonicalized {
-            quantified,
-            universes,
-        } = self.infer.u_canonicalize(interner, &canonicalized_goal);
-        (quantified, universes)
+        let (quantified, mapping) = self.infer.canonicalize_query(interner, value);
+        (quantified, mapping.universes)
     }
 
     fn canonicalize_ex_clause(
@@ -374,6 +552,16 @@ impl<I: Interner> context::UnificationOps<SlgContext<I>> for TruncatingInference
             .quantified
     }
 
+    fn resolve_escaping_placeholders(
+        &mut self,
+        interner: &I,
+        num_universes: usize,
+        constraints: Vec<InEnvironment<Constraint<I>>>,
+    ) -> Option<Vec<InEnvironment<Constraint<I>>>> {
+        self.infer
+            .resolve_escaping_placeholders(interner, num_universes, constraints)
+    }
+
     fn canonicalize_answer_subst(
         &mut self,
         interner: &I,
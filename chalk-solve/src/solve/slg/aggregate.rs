@@ -3,7 +3,7 @@ use crate::infer::InferenceTable;
 use crate::solve::slg::SlgContext;
 use crate::solve::slg::SlgContextOps;
 use crate::solve::slg::SubstitutionExt;
-use crate::solve::{Guidance, Solution};
+use crate::solve::{weaker_constrained_subst, Guidance, Solution};
 use chalk_ir::cast::Cast;
 use chalk_ir::interner::Interner;
 use chalk_ir::*;
@@ -22,7 +22,10 @@ impl<I: Interner> context::AggregateOps<SlgContext<I>> for SlgContextOps<'_, I>
         should_continue: impl std::ops::Fn() -> bool,
     ) -> Option<Solution<I>> {
         let interner = self.program.interner();
-        let CompleteAnswer { subst, ambiguous } = match answers.next_answer(|| should_continue()) {
+        let CompleteAnswer {
+            mut subst,
+            ambiguous,
+        } = match answers.next_answer(|| should_continue()) {
             AnswerResult::NoMoreSolutions => {
                 // No answers at all
                 return None;
@@ -37,8 +40,26 @@ impl<I: Interner> context::AggregateOps<SlgContext<I>> for SlgContextOps<'_, I>
             }
         };
 
+        // Skip the unconditional answers that only add region
+        // constraints to this one, and replace this one with those that
+        // only remove some: see `weaker_constrained_subst`.
+        let mut next_answer = answers.peek_answer(&should_continue);
+        if !ambiguous {
+            while let AnswerResult::Answer(CompleteAnswer {
+                subst: next_subst,
+                ambiguous: false,
+            }) = &next_answer
+            {
+                match weaker_constrained_subst(&subst, next_subst) {
+                    Some(weaker) => subst = weaker,
+                    None => break,
+                }
+                answers.next_answer(&should_continue);
+                next_answer = answers.peek_answer(&should_continue);
+            }
+        }
+
         // Exactly 1 unconditional answer?
-        let next_answer = answers.peek_answer(|| should_continue());
         if next_answer.is_quantum_exceeded() {
             return Some(Solution::Ambig(Guidance::Suggested(
                 subst.map(interner, |cs| cs.subst),
@@ -56,10 +77,10 @@ impl<I: Interner> context::AggregateOps<SlgContext<I>> for SlgContextOps<'_, I>
         //
         // FIXME-- there is actually a 3rd possibility. We could have
         // >1 answer where all the answers have the same substitution,
-        // but different region constraints. We should collapse those
-        // cases into an `OR` region constraint at some point, but I
-        // leave that for future work. This is basically
-        // rust-lang/rust#21974.
+        // but different region constraints, none of which include the
+        // others. We should collapse those cases into an `OR` region
+        // constraint at some point, but I leave that for future work.
+        // This is basically rust-lang/rust#21974.
         let mut subst = subst.map(interner, |cs| cs.subst);
 
         // Extract answers and merge them into `subst`. Stop once we have
@@ -97,9 +118,9 @@ impl<I: Interner> context::AggregateOps<SlgContext<I>> for SlgContextOps<'_, I>
             };
             subst = merge_into_guidance(
                 interner,
-                SlgContext::canonical(root_goal),
+                &SlgContext::canonical(root_goal).binders,
                 subst,
-                &new_subst,
+                &new_subst.value.subst,
             );
             num_answers += 1;
         };
@@ -120,20 +141,15 @@ impl<I: Interner> context::AggregateOps<SlgContext<I>> for SlgContextOps<'_, I>
 /// than the old guidance. For example, if we had a guidance of `?0 =
 /// u32` and the new answer is `?0 = i32`, then the guidance would
 /// become `?0 = ?X` (where `?X` is some fresh variable).
-fn merge_into_guidance<I: Interner>(
+///
+/// `root_binders` are the binders of the canonical root goal.
+pub(crate) fn merge_into_guidance<I: Interner>(
     interner: &I,
-    root_goal: &Canonical<InEnvironment<Goal<I>>>,
+    root_binders: &CanonicalVarKinds<I>,
     guidance: Canonical<Substitution<I>>,
-    answer: &Canonical<ConstrainedSubst<I>>,
+    subst1: &Substitution<I>,
 ) -> Canonical<Substitution<I>> {
     let mut infer = InferenceTable::new();
-    let Canonical {
-        value: ConstrainedSubst {
-            subst: subst1,
-            constraints: _,
-        },
-        binders: _,
-    } = answer;
 
     // Collect the types that the two substitutions have in
     // common.
@@ -146,7 +162,7 @@ fn merge_into_guidance<I: Interner>(
             // We have two values for some variable X that
             // appears in the root goal. Find out the universe
             // of X.
-            let universe = root_goal.binders.as_slice(interner)[index].into_inner();
+            let universe = root_binders.as_slice(interner)[index].into_inner();
 
             let ty = match value.data(interner) {
                 ParameterKind::Ty(ty) => ty,
@@ -177,7 +193,7 @@ fn merge_into_guidance<I: Interner>(
     infer.canonicalize(interner, &aggr_subst).quantified
 }
 
-fn is_trivial<I: Interner>(interner: &I, subst: &Canonical<Substitution<I>>) -> bool {
+pub(crate) fn is_trivial<I: Interner>(interner: &I, subst: &Canonical<Substitution<I>>) -> bool {
     // A subst is trivial if..
     subst
         .value
@@ -413,7 +429,10 @@ impl<I: Interner> AntiUnifier<'_, '_, I> {
                 self.new_lifetime_variable()
             }
 
-            (LifetimeData::Placeholder(_), LifetimeData::Placeholder(_)) => {
+            (LifetimeData::Placeholder(_), LifetimeData::Placeholder(_))
+            | (LifetimeData::Placeholder(_), LifetimeData::Static)
+            | (LifetimeData::Static, LifetimeData::Placeholder(_))
+            | (LifetimeData::Static, LifetimeData::Static) => {
                 if l1 == l2 {
                     l1.clone()
                 } else {
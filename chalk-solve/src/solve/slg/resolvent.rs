@@ -462,7 +462,8 @@ impl<'i, I: Interner> Zipper<'i, I> for AnswerSubstitutor<'i, I> {
                 self.assert_matching_vars(*answer_depth, *pending_depth)
             }
 
-            (LifetimeData::Placeholder(_), LifetimeData::Placeholder(_)) => {
+            (LifetimeData::Placeholder(_), LifetimeData::Placeholder(_))
+            | (LifetimeData::Static, LifetimeData::Static) => {
                 assert_eq!(answer, pending);
                 Ok(())
             }
@@ -472,7 +473,9 @@ impl<'i, I: Interner> Zipper<'i, I> for AnswerSubstitutor<'i, I> {
                 answer, pending,
             ),
 
-            (LifetimeData::BoundVar(_), _) | (LifetimeData::Placeholder(_), _) => panic!(
+            (LifetimeData::BoundVar(_), _)
+            | (LifetimeData::Placeholder(_), _)
+            | (LifetimeData::Static, _) => panic!(
                 "structural mismatch between answer `{:?}` and pending goal `{:?}`",
                 answer, pending,
             ),
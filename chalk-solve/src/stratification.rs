@@ -0,0 +1,194 @@
+//! Checking that a program is stratified: that no predicate depends on
+//! itself through negation.
+//!
+//! In a program like
+//!
+//! ```notrust
+//! forall<> { Alice: P if not { Alice: Q } }
+//! forall<> { Alice: Q if not { Alice: P } }
+//! ```
+//!
+//! whether `Alice: P` holds depends on whether it does not hold, so
+//! the program has no meaning. The check is done over the predicates
+//! of the program clauses, without their parameters, so it also rejects
+//! some programs whose negated goals would never actually lead back to
+//! themselves.
+
+use chalk_ir::interner::Interner;
+use chalk_ir::*;
+use petgraph::algo;
+use petgraph::prelude::*;
+use std::fmt;
+
+/// The predicate of a domain goal, without its parameters: the
+/// `Implemented(Foo)` of `Implemented(T: Foo)`, for example.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Predicate<I: Interner> {
+    Implemented(TraitId<I>),
+    ConstImplemented(TraitId<I>),
+    AliasEq(AssocTypeId<I>),
+    OpaqueAliasEq(OpaqueTyId<I>),
+    Normalize(AssocTypeId<I>),
+    OpaqueNormalize(OpaqueTyId<I>),
+    WellFormedTrait(TraitId<I>),
+    WellFormedTy,
+    FromEnvTrait(TraitId<I>),
+    FromEnvTy,
+    IsLocal,
+    IsUpstream,
+    IsFullyVisible,
+    LocalImplAllowed(TraitId<I>),
+    Compatible,
+    DownstreamType,
+    Reveal,
+    Predicate(PredicateId<I>),
+}
+
+impl<I: Interner> Predicate<I> {
+    /// The predicate of `goal`.
+    pub fn of(goal: &DomainGoal<I>) -> Self {
+        match goal {
+            DomainGoal::Holds(WhereClause::Implemented(trait_ref)) => {
+                Predicate::Implemented(trait_ref.trait_id)
+            }
+            DomainGoal::Holds(WhereClause::ConstImplemented(trait_ref)) => {
+                Predicate::ConstImplemented(trait_ref.trait_id)
+            }
+            DomainGoal::Holds(WhereClause::AliasEq(AliasEq { alias, .. })) => match alias {
+                AliasTy::Projection(projection) => Predicate::AliasEq(projection.associated_ty_id),
+                AliasTy::Opaque(opaque_ty) => Predicate::OpaqueAliasEq(opaque_ty.opaque_ty_id),
+            },
+            DomainGoal::Normalize(Normalize { alias, .. }) => match alias {
+                AliasTy::Projection(projection) => {
+                    Predicate::Normalize(projection.associated_ty_id)
+                }
+                AliasTy::Opaque(opaque_ty) => Predicate::OpaqueNormalize(opaque_ty.opaque_ty_id),
+            },
+            DomainGoal::WellFormed(WellFormed::Trait(trait_ref)) => {
+                Predicate::WellFormedTrait(trait_ref.trait_id)
+            }
+            DomainGoal::WellFormed(WellFormed::Ty(_)) => Predicate::WellFormedTy,
+            DomainGoal::FromEnv(FromEnv::Trait(trait_ref)) => {
+                Predicate::FromEnvTrait(trait_ref.trait_id)
+            }
+            DomainGoal::FromEnv(FromEnv::Ty(_)) => Predicate::FromEnvTy,
+            DomainGoal::IsLocal(_) => Predicate::IsLocal,
+            DomainGoal::IsUpstream(_) => Predicate::IsUpstream,
+            DomainGoal::IsFullyVisible(_) => Predicate::IsFullyVisible,
+            DomainGoal::LocalImplAllowed(trait_ref) => {
+                Predicate::LocalImplAllowed(trait_ref.trait_id)
+            }
+            DomainGoal::Compatible(()) => Predicate::Compatible,
+            DomainGoal::DownstreamType(_) => Predicate::DownstreamType,
+            DomainGoal::Reveal(()) => Predicate::Reveal,
+            DomainGoal::Predicate(predicate_ref) => {
+                Predicate::Predicate(predicate_ref.predicate_id)
+            }
+        }
+    }
+}
+
+/// A cycle of predicates that goes through negation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StratificationError<I: Interner> {
+    /// The predicates of the cycle, each with whether it depends on the
+    /// next one through negation. The last one depends on the first.
+    pub cycle: Vec<(Predicate<I>, bool)>,
+}
+
+impl<I: Interner> fmt::Display for StratificationError<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "negative cycle between predicates: ")?;
+        for (predicate, negated) in &self.cycle {
+            write!(f, "`{:?}` -> ", predicate)?;
+            if *negated {
+                write!(f, "not ")?;
+            }
+        }
+        write!(f, "`{:?}`", self.cycle[0].0)
+    }
+}
+
+impl<I: Interner> std::error::Error for StratificationError<I> {}
+
+/// Checks that no predicate of `clauses` depends on itself through
+/// negation, returning one of the cycles otherwise.
+pub fn check_stratification<I: Interner>(
+    interner: &I,
+    clauses: &[ProgramClause<I>],
+) -> Result<(), StratificationError<I>> {
+    // An edge from the predicate of each clause to the predicates of its
+    // conditions, with whether one of them is negated.
+    let mut graph: DiGraphMap<Predicate<I>, bool> = DiGraphMap::new();
+    for clause in clauses {
+        let implication = match clause.data(interner) {
+            ProgramClauseData::Implies(implication) => implication,
+            ProgramClauseData::ForAll(binders) => binders.skip_binders(),
+        };
+        let head = Predicate::of(&implication.consequence);
+        graph.add_node(head);
+        for condition in implication.conditions.iter(interner) {
+            add_dependencies(interner, &mut graph, head, condition, false);
+        }
+    }
+
+    let negated_edges: Vec<_> = graph
+        .all_edges()
+        .filter(|&(_, _, &negated)| negated)
+        .map(|(head, predicate, _)| (head, predicate))
+        .collect();
+    for (head, predicate) in negated_edges {
+        let path = algo::astar(&graph, predicate, |node| node == head, |_| 0, |_| 0);
+        if let Some((_, path)) = path {
+            // `path` leads from `predicate` back to `head`.
+            let nodes: Vec<_> = Some(head)
+                .into_iter()
+                .chain(path.into_iter().take_while(|&node| node != head))
+                .collect();
+            let cycle = nodes
+                .iter()
+                .enumerate()
+                .map(|(index, &node)| {
+                    let next = nodes.get(index + 1).cloned().unwrap_or(head);
+                    (node, graph[(node, next)])
+                })
+                .collect();
+            return Err(StratificationError { cycle });
+        }
+    }
+    Ok(())
+}
+
+/// Adds edges from `head` to the predicates that `goal` depends on,
+/// which are negated if `negated` is, or if they are within a `not` in
+/// `goal`.
+fn add_dependencies<I: Interner>(
+    interner: &I,
+    graph: &mut DiGraphMap<Predicate<I>, bool>,
+    head: Predicate<I>,
+    goal: &Goal<I>,
+    negated: bool,
+) {
+    match goal.data(interner) {
+        GoalData::Quantified(_, subgoal) => {
+            add_dependencies(interner, graph, head, subgoal.skip_binders(), negated)
+        }
+        GoalData::Implies(_, subgoal) => add_dependencies(interner, graph, head, subgoal, negated),
+        GoalData::All(subgoals) | GoalData::Any(subgoals) => {
+            for subgoal in subgoals.iter(interner) {
+                add_dependencies(interner, graph, head, subgoal, negated);
+            }
+        }
+        GoalData::Not(subgoal) => add_dependencies(interner, graph, head, subgoal, true),
+        GoalData::DomainGoal(domain_goal) => {
+            let predicate = Predicate::of(domain_goal);
+            match graph.edge_weight_mut(head, predicate) {
+                Some(edge_negated) => *edge_negated |= negated,
+                None => {
+                    graph.add_edge(head, predicate, negated);
+                }
+            }
+        }
+        GoalData::EqGoal(_) | GoalData::Constraint(_) | GoalData::CannotProve(()) => {}
+    }
+}
@@ -13,7 +13,10 @@ macro_rules! ty {
 
     (function $n:tt $($arg:tt)*) => {
         chalk_ir::TyData::Function(Fn {
-            num_binders: $n,
+            binders: chalk_ir::ParameterKinds::from(
+                &chalk_integration::interner::ChalkIr,
+                (0..$n).map(|_| chalk_ir::ParameterKind::Lifetime(())),
+            ),
             substitution: chalk_ir::Substitution::from(
                 &chalk_integration::interner::ChalkIr,
                 vec![$(arg!($arg)),*] as Vec<chalk_ir::Parameter<_>>
@@ -0,0 +1,138 @@
+//! Structured tracing of the steps taken by the solver. Each step is
+//! reported as a [`TraceEvent`] to the [`TraceSink`] installed with
+//! [`Solver::set_trace_sink`], which may log, filter or collect them.
+//!
+//! The recursive solver reports the goals it solves one by one. The SLG
+//! solver reports the goals it creates a table for, and the clauses it
+//! tries when doing so, but not when the goals are solved, as it may
+//! come back to a goal later to find more answers. A goal that already
+//! has a table is reported with `TableReused` instead.
+//!
+//! [`TraceEvent`]: enum.TraceEvent.html
+//! [`TraceSink`]: trait.TraceSink.html
+//! [`Solver::set_trace_sink`]: ../struct.Solver.html#method.set_trace_sink
+
+use crate::Solution;
+use chalk_engine::fallible::Fallible;
+use chalk_ir::interner::Interner;
+use chalk_ir::*;
+use std::fmt;
+
+/// A step taken by the solver.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TraceEvent<I: Interner> {
+    /// The solver started solving `goal`, with `depth` goals already
+    /// on its stack.
+    GoalEntered {
+        goal: UCanonical<InEnvironment<Goal<I>>>,
+        depth: usize,
+    },
+
+    /// The solver finished solving `goal`.
+    GoalSolved {
+        goal: UCanonical<InEnvironment<Goal<I>>>,
+        solution: Fallible<Solution<I>>,
+    },
+
+    /// The solution of `goal` was found in the cache of earlier
+    /// solutions.
+    CacheHit {
+        goal: UCanonical<InEnvironment<Goal<I>>>,
+        solution: Fallible<Solution<I>>,
+    },
+
+    /// The SLG solver found the table of `goal`, which has the answers
+    /// found for it so far, instead of creating one.
+    TableReused {
+        goal: UCanonical<InEnvironment<Goal<I>>>,
+    },
+
+    /// The solver is trying to solve `goal` with `clause`.
+    ClauseTried {
+        goal: UCanonical<InEnvironment<DomainGoal<I>>>,
+        clause: Binders<ProgramClauseImplication<I>>,
+    },
+
+    /// The consequence of `clause` does not unify with `goal`.
+    UnificationFailed {
+        goal: UCanonical<InEnvironment<DomainGoal<I>>>,
+        clause: Binders<ProgramClauseImplication<I>>,
+    },
+}
+
+impl<I: Interner> TraceEvent<I> {
+    pub fn display<'a>(&'a self, interner: &'a I) -> TraceEventDisplay<'a, I> {
+        TraceEventDisplay {
+            event: self,
+            interner,
+        }
+    }
+}
+
+pub struct TraceEventDisplay<'a, I: Interner> {
+    event: &'a TraceEvent<I>,
+    interner: &'a I,
+}
+
+impl<'a, I: Interner> fmt::Display for TraceEventDisplay<'a, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let solution = |solution: &Fallible<Solution<I>>| match solution {
+            Ok(solution) => solution.display(self.interner).to_string(),
+            Err(_) => "No possible solution".to_string(),
+        };
+        match self.event {
+            TraceEvent::GoalEntered { goal, depth } => {
+                write!(
+                    f,
+                    "goal entered at depth {}: {:?}",
+                    depth, goal.canonical.value.goal
+                )
+            }
+            TraceEvent::GoalSolved { goal, solution: s } => {
+                write!(
+                    f,
+                    "goal solved: {:?} => {}",
+                    goal.canonical.value.goal,
+                    solution(s)
+                )
+            }
+            TraceEvent::CacheHit { goal, solution: s } => {
+                write!(
+                    f,
+                    "cache hit: {:?} => {}",
+                    goal.canonical.value.goal,
+                    solution(s)
+                )
+            }
+            TraceEvent::TableReused { goal } => {
+                write!(f, "table reused: {:?}", goal.canonical.value.goal)
+            }
+            TraceEvent::ClauseTried { goal, clause } => {
+                write!(
+                    f,
+                    "clause tried: {:?} with {:?}",
+                    goal.canonical.value.goal, clause
+                )
+            }
+            TraceEvent::UnificationFailed { goal, clause } => {
+                write!(
+                    f,
+                    "unification failed: {:?} with {:?}",
+                    goal.canonical.value.goal, clause
+                )
+            }
+        }
+    }
+}
+
+/// Receives the steps taken by the solver. Any `FnMut(TraceEvent<I>)`
+/// closure is a sink.
+pub trait TraceSink<I: Interner>: Send {
+    fn event(&mut self, event: TraceEvent<I>);
+}
+
+impl<I: Interner, F: FnMut(TraceEvent<I>) + Send> TraceSink<I> for F {
+    fn event(&mut self, event: TraceEvent<I>) {
+        self(event)
+    }
+}
@@ -16,6 +16,7 @@ use chalk_rust_ir::*;
 pub enum WfError<I: Interner> {
     IllFormedTypeDecl(chalk_ir::StructId<I>),
     IllFormedTraitImpl(chalk_ir::TraitId<I>),
+    IllFormedImplMethod(chalk_ir::TraitId<I>, I::Identifier),
 }
 
 impl<I: Interner> fmt::Display for WfError<I> {
@@ -31,6 +32,11 @@ impl<I: Interner> fmt::Display for WfError<I> {
                 "trait impl for `{:?}` does not meet well-formedness requirements",
                 id
             ),
+            WfError::IllFormedImplMethod(id, name) => write!(
+                f,
+                "method `{}` in trait impl for `{:?}` does not match the trait's signature",
+                name, id
+            ),
         }
     }
 }
@@ -80,7 +86,7 @@ impl<'i, I: Interner> Visitor<'i, I> for InputTypeCollector<'i, I> {
                 .clone()
                 .intern(self.interner)
                 .visit_with(self, outer_binder),
-            WhereClause::Implemented(trait_ref) => {
+            WhereClause::Implemented(trait_ref) | WhereClause::ConstImplemented(trait_ref) => {
                 trait_ref.visit_with(self, outer_binder);
             }
         }
@@ -89,9 +95,14 @@ impl<'i, I: Interner> Visitor<'i, I> for InputTypeCollector<'i, I> {
     fn visit_ty(&mut self, ty: &Ty<I>, outer_binder: DebruijnIndex) {
         let interner = self.interner();
 
+        // Types that mention the variables bound by a quantified where
+        // clause, like `&'a T` in `forall<'a> &'a T: Foo`, cannot be named
+        // outside of it, so they are left out; the types within them that
+        // do not mention those variables are still collected.
         let mut push_ty = || {
-            self.types
-                .push(ty.shifted_out_to(interner, outer_binder).unwrap())
+            if let Ok(ty) = ty.shifted_out_to(interner, outer_binder) {
+                self.types.push(ty);
+            }
         };
         match ty.data(interner) {
             TyData::Apply(apply) => {
@@ -156,6 +167,24 @@ where
         // ```
         let struct_datum = self.db.struct_datum(struct_id);
 
+        // The fields of a union are never dropped, so each one must be
+        // `Copy` or `ManuallyDrop`-like; without a `Copy` trait, only the
+        // latter are allowed.
+        if struct_datum.flags.union
+            && self
+                .db
+                .well_known_trait_id(WellKnownTrait::CopyTrait)
+                .is_none()
+            && !struct_datum
+                .binders
+                .skip_binders()
+                .fields
+                .iter()
+                .all(|ty| WfWellKnownGoals::is_manually_drop(self.db, ty))
+        {
+            return Err(WfError::IllFormedTypeDecl(struct_id));
+        }
+
         let mut gb = GoalBuilder::new(self.db);
         let struct_data = struct_datum
             .binders
@@ -164,32 +193,42 @@ where
         // We make a goal like...
         //
         // forall<T> { ... }
-        let wg_goal = gb.forall(&struct_data, (), |gb, _, (fields, where_clauses), ()| {
-            let interner = gb.interner();
+        let wg_goal = gb.forall(
+            &struct_data,
+            struct_id,
+            |gb, _, (fields, where_clauses), struct_id| {
+                let interner = gb.interner();
 
-            // struct is well-formed in terms of Sized
-            let sized_constraint_goal = WfWellKnownGoals::struct_sized_constraint(gb.db(), fields);
+                // struct is well-formed in terms of Sized
+                let sized_constraint_goal =
+                    WfWellKnownGoals::struct_sized_constraint(gb.db(), fields);
 
-            // (FromEnv(T: Eq) => ...)
-            gb.implies(
-                where_clauses
-                    .iter()
-                    .cloned()
-                    .map(|wc| wc.into_from_env_goal(interner)),
-                |gb| {
-                    // WellFormed(Vec<T>), for each field type `Vec<T>` or type that appears in the where clauses
-                    let types =
-                        InputTypeCollector::types_in(gb.interner(), (&fields, &where_clauses));
-
-                    gb.all(
-                        types
-                            .into_iter()
-                            .map(|ty| ty.well_formed().cast(interner))
-                            .chain(sized_constraint_goal.into_iter()),
-                    )
-                },
-            )
-        });
+                // union fields do not need dropping
+                let union_constraint_goal =
+                    WfWellKnownGoals::union_fields_constraint(gb.db(), struct_id, fields);
+
+                // (FromEnv(T: Eq) => ...)
+                gb.implies(
+                    where_clauses
+                        .iter()
+                        .cloned()
+                        .map(|wc| wc.into_from_env_goal(interner)),
+                    |gb| {
+                        // WellFormed(Vec<T>), for each field type `Vec<T>` or type that appears in the where clauses
+                        let types =
+                            InputTypeCollector::types_in(gb.interner(), (&fields, &where_clauses));
+
+                        gb.all(
+                            types
+                                .into_iter()
+                                .map(|ty| ty.well_formed().cast(interner))
+                                .chain(sized_constraint_goal.into_iter())
+                                .chain(union_constraint_goal.into_iter()),
+                        )
+                    },
+                )
+            },
+        );
 
         let wg_goal = wg_goal.into_closed_goal(interner);
 
@@ -232,11 +271,32 @@ where
             None => false,
         };
 
-        if is_legal {
-            Ok(())
-        } else {
-            Err(WfError::IllFormedTraitImpl(trait_id))
+        if !is_legal {
+            return Err(WfError::IllFormedTraitImpl(trait_id));
+        }
+
+        for method in &impl_datum.methods {
+            let is_legal = match compute_method_goal(self.db, &impl_datum, method) {
+                Some(goal) => {
+                    debug!("WF impl method goal: {:?}", goal);
+                    match self
+                        .solver_choice
+                        .into_solver()
+                        .solve(self.db, &goal.into_closed_goal(interner))
+                    {
+                        Some(sol) => sol.is_unique(),
+                        None => false,
+                    }
+                }
+                None => false,
+            };
+
+            if !is_legal {
+                return Err(WfError::IllFormedImplMethod(trait_id, method.name.clone()));
+            }
         }
+
+        Ok(())
     }
 }
 
@@ -456,6 +516,125 @@ fn compute_assoc_ty_goal<I: Interner>(
     ))
 }
 
+/// Checks an impl method against the method of the same name in the
+/// trait, for example in:
+///
+/// ```ignore
+/// trait Foo {
+///     fn foo<U>(&self, u: U) where U: Clone;
+/// }
+///
+/// impl<T> Foo for Box<T> {
+///     fn foo<U>(&self, u: U) where U: Copy;
+/// }
+/// ```
+///
+/// we would issue the following goal, which fails, as `U: Clone`
+/// does not imply `U: Copy`:
+///
+/// ```ignore
+/// forall<U, T> {
+///     if (FromEnv(U: Clone), /* impl WF environment */) {
+///         U = U, () = (), U: Copy
+///     }
+/// }
+/// ```
+///
+/// Returns `None` if the signatures do not even have the same shape:
+/// the trait has no such method, or it differs in its receiver, its
+/// number of arguments, or its own parameters.
+fn compute_method_goal<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    impl_datum: &ImplDatum<I>,
+    impl_method: &MethodDatum<I>,
+) -> Option<Goal<I>> {
+    let interner = db.interner();
+    let trait_datum = db.trait_datum(impl_datum.trait_id());
+    let trait_method = trait_datum
+        .methods
+        .iter()
+        .find(|method| method.name == impl_method.name)?;
+
+    let impl_own_kinds = &impl_method.binders.binders.as_slice(interner)
+        [..impl_method.num_own_parameters(interner, impl_datum.binders.len(interner))];
+    let trait_own_kinds = &trait_method.binders.binders.as_slice(interner)
+        [..trait_method.num_own_parameters(interner, trait_datum.binders.len(interner))];
+    if impl_method.receiver != trait_method.receiver
+        || impl_own_kinds != trait_own_kinds
+        || impl_method.binders.skip_binders().inputs.len()
+            != trait_method.binders.skip_binders().inputs.len()
+    {
+        return None;
+    }
+
+    let mut gb = GoalBuilder::new(db);
+    // forall<M0..Mm, P0..Pn> { .. }
+    Some(gb.forall(
+        &impl_method.binders,
+        (impl_datum.binders.clone(), trait_method.binders.clone()),
+        |gb, substitution, impl_sig, (impl_binders, trait_method_binders)| {
+            let interner = gb.interner();
+            let parameters = substitution.parameters(interner);
+            let (method_parameters, impl_parameters) =
+                parameters.split_at(parameters.len() - impl_binders.len(interner));
+
+            let ImplDatumBound {
+                trait_ref,
+                where_clauses,
+            } = impl_binders.substitute(interner, impl_parameters);
+
+            // The trait method is in terms of the method's own parameters
+            // followed by the trait's, which are given by the impl's trait ref.
+            let trait_sig = trait_method_binders.substitute(
+                interner,
+                &method_parameters
+                    .iter()
+                    .chain(trait_ref.substitution.iter(interner))
+                    .cloned()
+                    .collect::<Vec<_>>(),
+            );
+
+            // if (/* impl WF environment */, /* trait method where clauses */) { .. }
+            let environment: Vec<ProgramClause<I>> =
+                impl_wf_environment(interner, &where_clauses, &trait_ref)
+                    .chain(
+                        trait_sig
+                            .where_clauses
+                            .iter()
+                            .cloned()
+                            .map(|qwc| qwc.into_from_env_goal(interner).cast(interner)),
+                    )
+                    .collect();
+
+            gb.implies(environment, |gb| {
+                let interner = gb.interner();
+
+                // The argument and return types must be the same, and the impl
+                // method may only require what the trait method does.
+                let eq_goals = impl_sig
+                    .inputs
+                    .iter()
+                    .zip(&trait_sig.inputs)
+                    .chain(Some((&impl_sig.output, &trait_sig.output)))
+                    .map(|(a, b)| {
+                        EqGoal {
+                            a: a.clone().cast(interner),
+                            b: b.clone().cast(interner),
+                        }
+                        .cast(interner)
+                    });
+                let where_clause_goals = impl_sig
+                    .where_clauses
+                    .iter()
+                    .cloned()
+                    .map(|qwc| qwc.cast(interner));
+
+                gb.all::<_, Goal<I>>(eq_goals.chain(where_clause_goals))
+            })
+        },
+    ))
+}
+
 /// Defines methods to compute well-formedness goals for well-known
 /// traits (e.g. a goal for all fields of struct in a Copy impl to be Copy)
 struct WfWellKnownGoals {}
@@ -469,9 +648,10 @@ impl WfWellKnownGoals {
     ) -> Option<Goal<I>> {
         match db.trait_datum(trait_ref.trait_id).well_known? {
             WellKnownTrait::CopyTrait => Self::copy_impl_constraint(db, trait_ref),
-            WellKnownTrait::DropTrait | WellKnownTrait::CloneTrait | WellKnownTrait::SizedTrait => {
-                None
-            }
+            WellKnownTrait::DropTrait
+            | WellKnownTrait::CloneTrait
+            | WellKnownTrait::SizedTrait
+            | WellKnownTrait::DerefTrait => None,
         }
     }
 
@@ -488,7 +668,9 @@ impl WfWellKnownGoals {
             // You can't add a manual implementation of Sized
             WellKnownTrait::SizedTrait => Some(GoalData::CannotProve(()).intern(interner)),
             WellKnownTrait::DropTrait => Self::drop_impl_constraint(db, impl_datum),
-            WellKnownTrait::CopyTrait | WellKnownTrait::CloneTrait => None,
+            WellKnownTrait::CopyTrait | WellKnownTrait::CloneTrait | WellKnownTrait::DerefTrait => {
+                None
+            }
         }
     }
 
@@ -519,6 +701,47 @@ impl WfWellKnownGoals {
         ))
     }
 
+    /// Computes a goal to prove that the fields of a union never need
+    /// dropping: each field must be `Copy`, unless it is `ManuallyDrop`-like.
+    pub fn union_fields_constraint<I: Interner>(
+        db: &dyn RustIrDatabase<I>,
+        struct_id: StructId<I>,
+        fields: &[Ty<I>],
+    ) -> Option<Goal<I>> {
+        if !db.struct_datum(struct_id).flags.union {
+            return None;
+        }
+
+        let interner = db.interner();
+
+        let copy_trait = db.well_known_trait_id(WellKnownTrait::CopyTrait)?;
+
+        Some(Goal::all(
+            interner,
+            fields
+                .iter()
+                .filter(|ty| !Self::is_manually_drop(db, ty))
+                .map(|ty| {
+                    TraitRef {
+                        trait_id: copy_trait,
+                        substitution: Substitution::from1(interner, ty.clone()),
+                    }
+                    .cast(interner)
+                }),
+        ))
+    }
+
+    /// Whether `ty` is a `#[manually_drop]` struct like `ManuallyDrop<T>`.
+    fn is_manually_drop<I: Interner>(db: &dyn RustIrDatabase<I>, ty: &Ty<I>) -> bool {
+        match ty.data(db.interner()) {
+            TyData::Apply(ApplicationTy {
+                name: TypeName::Struct(struct_id),
+                ..
+            }) => db.struct_datum(*struct_id).flags.manually_drop,
+            _ => false,
+        }
+    }
+
     /// Computes a goal to prove constraints on a Copy implementation.
     /// Copy impl is considered well-formed for
     ///    a) certain builtin types (scalar values, shared ref, etc..)
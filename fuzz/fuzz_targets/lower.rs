@@ -0,0 +1,10 @@
+//! Lowers generated programs; see `FuzzProgram::check_lowering`.
+
+#![no_main]
+
+use chalk_integration::fuzz::FuzzProgram;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|program: FuzzProgram| {
+    program.check_lowering();
+});
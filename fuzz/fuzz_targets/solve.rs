@@ -0,0 +1,11 @@
+//! Solves generated goals with each solver, checking that the solvers
+//! agree; see `FuzzProgram::check_solvers`.
+
+#![no_main]
+
+use chalk_integration::fuzz::FuzzProgram;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|program: FuzzProgram| {
+    program.check_solvers();
+});
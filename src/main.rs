@@ -3,13 +3,21 @@ extern crate serde_derive;
 
 use std::fs::File;
 use std::io::Read;
+use std::path::PathBuf;
 use std::process::exit;
+use std::sync::{Arc, Mutex};
 
 use chalk_integration::db::ChalkDatabase;
+use chalk_integration::diagnostic::Diagnostic;
 use chalk_integration::interner::ChalkIr;
-use chalk_integration::lowering::*;
+use chalk_integration::program::Program;
 use chalk_integration::query::LoweringDatabase;
+use chalk_integration::reduce::{reduce_failure, Failure};
+use chalk_integration::report::{CheckReport, GoalReport, Outcome};
+use chalk_solve::clauses::{program_clauses_for_item, ItemId};
+use chalk_solve::dot::TraceDot;
 use chalk_solve::ext::*;
+use chalk_solve::trace::TraceEvent;
 use chalk_solve::{RustIrDatabase, SolverChoice};
 use docopt::Docopt;
 use rustyline::error::ReadlineError;
@@ -20,40 +28,139 @@ const USAGE: &'static str = "
 chalk repl
 
 Usage:
-  chalk [options]
+  chalk [options] [--program=PATH...] [--goal=GOAL...]
+  chalk [options] check <file>...
+  chalk fmt [--check] <file>...
+  chalk lsp
+  chalk [options] reduce <file> --goal=GOAL [--expect=SOLUTION]
+  chalk [options] --batch=PATH
   chalk (-h | --help)
 
+`chalk check` solves the annotated goals of each batch file and prints a
+summary of each file. It exits with status 0 if every goal met its
+expectation, 1 if some did not, and 2 if a file could not be read, parsed
+or lowered. `--batch=PATH` is the same as `check PATH`.
+
+`chalk fmt` formats each file in place. With `--check`, it instead lists
+the files that are not formatted, and exits with status 1 if there are
+any. It exits with status 2 if a file could not be read or parsed.
+
+`chalk lsp` runs a language server for `.chalk` files, which speaks the
+Language Server Protocol over stdin and stdout.
+
+`chalk reduce` deletes items, where clauses and parameters from the
+program in the file for as long as solving the goal against it panics or,
+with `--expect`, has a solution that does not start with SOLUTION, and
+prints what is left. It exits with status 1 if the goal does not fail
+that way to begin with, and 2 if the file could not be read or parsed.
+
 Options:
   --help              Show this screen.
-  --program=PATH      Specifies the path to the `.chalk` file containing traits/impls.
+  --program=PATH      Specifies the path to a `.chalk` file containing traits/impls
+                      (may be given more than once; the files are concatenated).
   --goal=GOAL         Specifies a goal to evaluate (may be given more than once).
-  --overflow-depth=N  Specifies the overflow depth [default: 10].
+  --expect=SOLUTION   Specifies the start of the expected solution of the goal
+                      of `chalk reduce`.
+  --batch=PATH        Checks the annotated goals of a batch file, reporting
+                      those whose solution does not match.
+  --solver=S          Specifies the solver, `slg` or `recursive` [default: slg].
+  --overflow-depth=N  Specifies the overflow depth [default: 10]. Goals that
+                      overflow are reported as ambiguous.
+  --iterative-deepening=N
+                      Solve goals with the recursive solver again while
+                      some goal overflows, doubling the overflow depth each
+                      time, up to N.
+  --inductive-auto-traits
+                      Treat goals about auto traits inductively, rather than
+                      coinductively.
   --multiple          Output multiple answers instead of ambiguous solution.
+  --proof-tree        Output how each goal was solved, as a proof tree.
+  --explain           Output why each goal that has no solution has none.
+  --dot               Output the proof trees and traces as Graphviz DOT graphs,
+                      each after its goal is solved.
+  --format=F          Specifies the output format, `text` or `json` [default: text].
+                      In the `json` format, each goal and each checked file is
+                      reported as a JSON object on a line of its own, and only
+                      the solutions of goals are output.
+  --stats             Output how much work the solver did for each goal.
+  --trace             Output each step taken by the solver.
 ";
 
 /// This struct represents the various command line options available.
 #[derive(Debug, Deserialize)]
 struct Args {
-    flag_program: Option<String>,
+    flag_program: Vec<String>,
     flag_goal: Vec<String>,
+    flag_batch: Option<String>,
+    cmd_check: bool,
+    cmd_fmt: bool,
+    cmd_lsp: bool,
+    cmd_reduce: bool,
+    flag_check: bool,
+    flag_expect: Option<String>,
+    arg_file: Vec<String>,
+    flag_solver: String,
     flag_overflow_depth: usize,
+    flag_iterative_deepening: Option<usize>,
+    flag_inductive_auto_traits: bool,
     flag_multiple: bool,
+    flag_proof_tree: bool,
+    flag_explain: bool,
+    flag_dot: bool,
+    flag_stats: bool,
+    flag_trace: bool,
+    flag_format: String,
 }
 
 /// A loaded and parsed program.
 struct LoadedProgram {
     text: String,
     db: ChalkDatabase,
+
+    /// The files the program was read from, if any, with the offset in
+    /// the program that each starts at.
+    files: Vec<(String, usize)>,
 }
 
 impl LoadedProgram {
-    /// Creates a new Program struct, given a `.chalk` file as a String and
+    /// Creates a new Program struct, given `.chalk` source as a String and
     /// a [`SolverChoice`].
     ///
     /// [`SolverChoice`]: struct.solve.SolverChoice.html
     fn new(text: String, solver_choice: SolverChoice) -> Result<LoadedProgram> {
         let db = ChalkDatabase::with(&text, solver_choice);
-        Ok(LoadedProgram { text, db })
+        Ok(LoadedProgram {
+            text,
+            db,
+            files: vec![],
+        })
+    }
+
+    /// The checked program or, if it has errors, the errors, each rendered
+    /// against the file it is in if it is about a particular place in it.
+    fn checked_program(&self) -> Result<Arc<Program>> {
+        self.db.checked_program().map_err(|e| {
+            if e.diagnostics().iter().all(|d| d.span.is_none()) {
+                return e.into();
+            }
+            let mut rendered = vec![];
+            for diagnostic in e.diagnostics() {
+                let lo = diagnostic.span.map_or(0, |span| span.lo);
+                let (file, start) = self
+                    .files
+                    .iter()
+                    .rev()
+                    .find(|(_, start)| *start <= lo)
+                    .map_or(("<program>", 0), |(file, start)| (file.as_str(), *start));
+                let mut diagnostic = diagnostic.clone();
+                if let Some(span) = &mut diagnostic.span {
+                    span.lo -= start;
+                    span.hi -= start;
+                }
+                rendered.push(diagnostic.render(file, &self.text[start..]));
+            }
+            Rendered(rendered.join("\n")).into()
+        })
     }
 
     /// Parse a goal and attempt to solve it, using the specified solver.
@@ -61,12 +168,35 @@ impl LoadedProgram {
         &self,
         mut rl: Option<&mut rustyline::Editor<()>>,
         text: &str,
-        multiple_answers: bool,
+        args: &Args,
     ) -> Result<()> {
-        let program = self.db.checked_program()?;
-        let goal = chalk_parse::parse_goal(text)?.lower(&*program)?;
+        if args.flag_format == "json" {
+            println!("{}", GoalReport::solve(&self.db, text).to_json());
+            return Ok(());
+        }
+        self.checked_program()?;
+        let goal = self
+            .db
+            .parse_and_lower_goal(text)
+            .map_err(|e| Rendered(e.render("<goal>", text)))?;
         let peeled_goal = goal.into_peeled_goal(self.db.interner());
-        if multiple_answers {
+        let events = Arc::new(Mutex::new(vec![]));
+        if args.flag_trace && args.flag_dot {
+            let events = events.clone();
+            self.db
+                .set_trace_sink(Some(Box::new(move |event: TraceEvent<ChalkIr>| {
+                    events.lock().unwrap().push(event)
+                })));
+        } else if args.flag_trace {
+            self.db
+                .set_trace_sink(Some(Box::new(|event: TraceEvent<ChalkIr>| {
+                    println!("{}", event.display(&ChalkIr))
+                })));
+        } else {
+            // Tracing may have been turned off with `:set`.
+            self.db.set_trace_sink(None);
+        }
+        if args.flag_multiple {
             if self.db.solve_multiple(&peeled_goal, |v, has_next| {
                 println!("{}\n", v.as_ref().map(|v| v.display(&ChalkIr)));
                 if has_next {
@@ -93,11 +223,39 @@ impl LoadedProgram {
             }) {
                 println!("No more solutions");
             }
+        } else if args.flag_proof_tree {
+            match self.db.solve_with_proof_tree(&peeled_goal) {
+                Some(tree) if args.flag_dot => println!("{}", tree.dot(&ChalkIr)),
+                Some(tree) => println!("{}", tree.display(&ChalkIr)),
+                None => println!("No possible solution.\n"),
+            }
         } else {
             match self.db.solve(&peeled_goal) {
                 Some(v) => println!("{}\n", v.display(&ChalkIr)),
                 None => println!("No possible solution.\n"),
             }
+            if let Some(stack) = self.db.last_overflow() {
+                println!("overflow depth reached, goal stack:");
+                for goal in stack {
+                    println!("    {:?}", goal.canonical.value.goal);
+                }
+                println!();
+            }
+            if args.flag_stats {
+                let stats = self.db.last_stats();
+                println!(
+                    "subgoals: {}, unifications: {}, cache hits: {}, max depth: {}\n",
+                    stats.subgoals, stats.unifications, stats.cache_hits, stats.max_depth
+                );
+            }
+            if args.flag_explain {
+                if let Some(explanation) = self.db.explain_failure(&peeled_goal) {
+                    println!("{}", explanation.display());
+                }
+            }
+        }
+        if args.flag_trace && args.flag_dot {
+            println!("{}", TraceDot::new(&events.lock().unwrap(), &ChalkIr));
         }
         Ok(())
     }
@@ -105,7 +263,7 @@ impl LoadedProgram {
 
 fn run() -> Result<()> {
     // Parse the command line arguments.
-    let args: &Args = &Docopt::new(USAGE)
+    let args: &mut Args = &mut Docopt::new(USAGE)
         .and_then(|d| d.deserialize())
         .unwrap_or_else(|e| e.exit());
 
@@ -114,11 +272,38 @@ fn run() -> Result<()> {
         eprintln!("error: overflow depth must be at least 1");
         exit(1);
     }
+    if args.flag_solver != "slg" && args.flag_solver != "recursive" {
+        eprintln!("error: unknown solver `{}`", args.flag_solver);
+        exit(1);
+    }
+    if args.flag_format != "text" && args.flag_format != "json" {
+        eprintln!("error: unknown format `{}`", args.flag_format);
+        exit(1);
+    }
 
-    // Load the .chalk file, if given.
+    if args.cmd_check {
+        exit(check(args, &args.arg_file));
+    }
+    if args.cmd_fmt {
+        exit(fmt(&args.arg_file, args.flag_check));
+    }
+    if args.cmd_lsp {
+        let stdin = std::io::stdin();
+        let stdout = std::io::stdout();
+        chalk_integration::lsp::run(stdin.lock(), stdout.lock())?;
+        return Ok(());
+    }
+    if args.cmd_reduce {
+        exit(reduce(args, &args.arg_file[0], &args.flag_goal[0]));
+    }
+    if let Some(path) = &args.flag_batch {
+        exit(check(args, &[path]));
+    }
+
+    // Load the .chalk files, if given.
     let mut prog = None;
-    if let Some(program) = &args.flag_program {
-        match load_program(args, program) {
+    if !args.flag_program.is_empty() {
+        match load_program(args, &args.flag_program) {
             Ok(p) => prog = Some(p),
             Err(err) => {
                 eprintln!("error loading program: {}", err);
@@ -128,12 +313,25 @@ fn run() -> Result<()> {
     }
 
     if args.flag_goal.is_empty() {
-        // The user specified no goal. Enter interactive mode.
-        readline_loop(&mut rustyline::Editor::new(), "?- ", |rl, line| {
-            if let Err(e) = process(args, line, rl, &mut prog) {
-                eprintln!("error: {}", e);
+        // The user specified no goal. Enter interactive mode, with the
+        // commands of earlier sessions in the history.
+        let rl = &mut rustyline::Editor::new();
+        let history = history_path();
+        if let Some(history) = &history {
+            // There is no history the first time the REPL is run.
+            let _ = rl.load_history(history);
+        }
+        let result = command_loop(rl, |rl, command| {
+            if let Err(e) = process(args, command, rl, &mut prog) {
+                print_error(&*e);
             }
-        })
+        });
+        if let Some(history) = &history {
+            if let Err(e) = rl.save_history(history) {
+                eprintln!("error saving history: {}", e);
+            }
+        }
+        result
     } else {
         // Check that a program was provided.
         // TODO: It's customary to print Usage info when an error like this
@@ -142,12 +340,30 @@ fn run() -> Result<()> {
             "error: cannot eval without a program; use `--program` to specify one."
         ))?;
 
+        // In the json format, report every goal, including those that
+        // cannot be solved, and exit with an error if there were any.
+        if args.flag_format == "json" {
+            let mut status = 0;
+            for g in &args.flag_goal {
+                let report = GoalReport::solve(&prog.db, g);
+                println!("{}", report.to_json());
+                if let Outcome::Error(_) = report.outcome {
+                    status = 1;
+                }
+            }
+            exit(status);
+        }
+
         // Evaluate the goal(s). If any goal returns an error, print the error
         // and exit.
+        if let Err(e) = prog.checked_program() {
+            print_error(&*e);
+            exit(1);
+        }
         prog.db.with_program(|_| -> Result<()> {
             for g in &args.flag_goal {
-                if let Err(e) = prog.goal(None, g, args.flag_multiple) {
-                    eprintln!("error: {}", e);
+                if let Err(e) = prog.goal(None, g, args) {
+                    print_error(&*e);
                     exit(1);
                 }
             }
@@ -158,6 +374,55 @@ fn run() -> Result<()> {
     }
 }
 
+/// Checks the batch files at `paths`, printing the goals that did not meet
+/// their expectations and a summary of each file, and returns the status
+/// to exit with: 0 if every goal met its expectation, 1 if some did not,
+/// and 2 if some file could not be checked at all.
+fn check<S: AsRef<str>>(args: &Args, paths: &[S]) -> i32 {
+    let mut status = 0;
+    for path in paths {
+        let path = path.as_ref();
+        let mut text = String::new();
+        let read = File::open(path).and_then(|mut file| file.read_to_string(&mut text));
+
+        if args.flag_format == "json" {
+            let report = match read {
+                Ok(_) => CheckReport::check(path, &text, args.solver_choice()),
+                Err(e) => CheckReport::error(path, &e),
+            };
+            println!("{}", report.to_json());
+            match report {
+                CheckReport::Checked { failed, .. } if failed > 0 => status = status.max(1),
+                CheckReport::Checked { .. } => {}
+                CheckReport::Error { .. } => status = 2,
+            }
+            continue;
+        }
+
+        if let Err(e) = read {
+            eprintln!("{}: error: {}", path, e);
+            status = 2;
+            continue;
+        }
+        match chalk_integration::batch::run_batch(&text, args.solver_choice()) {
+            Ok(report) => {
+                for mismatch in &report.mismatches {
+                    eprintln!("{}: {}", path, mismatch);
+                }
+                println!("{}: {}", path, report);
+                if !report.passed() {
+                    status = status.max(1);
+                }
+            }
+            Err(e) => {
+                eprint!("{}", e.render(path, &text));
+                status = 2;
+            }
+        }
+    }
+    status
+}
+
 /// Reads input lines from the user. Lines start with the string given by `prompt`.
 /// Each line the user enters is passed to the function `f` for processing.
 ///
@@ -188,12 +453,70 @@ where
     Ok(())
 }
 
+/// Reads commands from the user, at the prompt `?- `, and passes each one
+/// to `f`. A command whose braces are not all closed continues on the
+/// next line, at the prompt `... `, so that a goal like `forall<T> {` can
+/// be finished on the lines that follow; the lines of such a command are
+/// joined into one line of the history. Interrupting a command that has
+/// been continued abandons it.
+///
+/// The loop terminates when EOF is reached or if an error occurs while
+/// reading the next line.
+fn command_loop<F>(rl: &mut rustyline::Editor<()>, mut f: F) -> Result<()>
+where
+    F: FnMut(&mut rustyline::Editor<()>, &str),
+{
+    let mut command = String::new();
+    loop {
+        let prompt = if command.is_empty() { "?- " } else { "... " };
+        match rl.readline(prompt) {
+            Ok(line) => {
+                if !command.is_empty() {
+                    command.push(' ');
+                }
+                command.push_str(line.trim());
+                if has_unclosed_braces(&command) {
+                    continue;
+                }
+
+                rl.add_history_entry(&command);
+                f(rl, &command);
+                command.clear();
+            }
+
+            Err(ReadlineError::Interrupted) if !command.is_empty() => command.clear(),
+
+            // EOF: We're done.
+            Err(ReadlineError::Eof) => break,
+
+            // Some other error occurred.
+            Err(e) => Err(e)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// True if `command` opens more braces than it closes.
+fn has_unclosed_braces(command: &str) -> bool {
+    let opened = command.matches('{').count();
+    let closed = command.matches('}').count();
+    opened > closed
+}
+
+/// The file the history of the REPL is kept in, from one session to the
+/// next: `.chalk_history` in the home directory, if there is one.
+fn history_path() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME")?;
+    Some(PathBuf::from(home).join(".chalk_history"))
+}
+
 /// Process a single command. `args` is a struct containing the command-line
 /// arguments, and `prog` is a parsed `.chalk` file.
 // TODO: Could we pass in an Options struct or something? The Args struct
 // still has Strings where it should have Enums... (e.g. solver_choice)
 fn process(
-    args: &Args,
+    args: &mut Args,
     command: &str,
     rl: &mut rustyline::Editor<()>,
     prog: &mut Option<LoadedProgram>,
@@ -208,22 +531,26 @@ fn process(
         // Load a .chalk file via stdin, until EOF is found.
         let chalk_prog = LoadedProgram::new(read_program(rl)?, args.solver_choice())?;
         // Let's do a sanity check before going forward.
-        let _ = chalk_prog.db.checked_program()?;
+        let _ = chalk_prog.checked_program()?;
         *prog = Some(chalk_prog);
-    } else if command.starts_with("load ") {
-        // Load a .chalk file.
-        let filename = &command["load ".len()..];
-        let chalk_prog = load_program(args, filename)?;
+    } else if let Some(filenames) = command.strip_prefix("load ") {
+        // Load one or more .chalk files.
+        let filenames: Vec<_> = filenames.split_whitespace().collect();
+        let chalk_prog = load_program(args, &filenames)?;
         // Let's do a sanity check before going forward.
-        let _ = chalk_prog.db.checked_program()?;
+        let _ = chalk_prog.checked_program()?;
         *prog = Some(chalk_prog);
+    } else if command == ":set" || command.starts_with(":set ") {
+        // Change a setting, or print them all.
+        set(args, &command[":set".len()..], prog)?
     } else if command.starts_with("debug ") {
         match command.split_whitespace().nth(1) {
             Some(level) => std::env::set_var("CHALK_DEBUG", level),
             None => println!("debug <level> set debug level to <level>"),
         }
     } else {
-        // The command is either "print", "lowered", or a goal.
+        // The command is either "print", "lowered", ":program", ":print",
+        // ":clauses", "probe", or a goal.
 
         // Check that a program has been loaded.
         let prog = prog.as_ref().ok_or(format!(
@@ -231,7 +558,7 @@ fn process(
         ))?;
 
         // Attempt to parse the program.
-        prog.db.with_program(|_| -> Result<()> {
+        prog.db.with_program(|program| -> Result<()> {
             match command {
                 // Print out the loaded program.
                 "print" => println!("{}", prog.text),
@@ -239,10 +566,46 @@ fn process(
                 // TODO: Write a line of documentation here.
                 "lowered" => println!("{:#?}", prog.db.environment()),
 
+                // Print out the lowered IR of the program, or of an item.
+                ":program" => println!("{:#?}", program),
+                // Print out the program clauses of an item, and for a
+                // trait, of its associated types and impls.
+                _ if command.starts_with(":clauses ") => {
+                    let name = command[":clauses ".len()..].trim();
+                    let items = program.items_named(name);
+                    if items.is_empty() {
+                        Err(format!("no struct, trait or opaque type named `{}`", name))?;
+                    }
+                    for item in items {
+                        match item {
+                            ItemId::Struct(id) => println!("struct {:?}:", id),
+                            ItemId::Trait(id) => println!("trait {:?}:", id),
+                            ItemId::Impl(id) => println!("{:?}:", id),
+                            ItemId::AssociatedTy(id) => println!("type {:?}:", id),
+                            ItemId::OpaqueTy(id) => println!("opaque type {:?}:", id),
+                        }
+                        for clause in program_clauses_for_item(&prog.db, item) {
+                            println!("    {}", program.display(&clause));
+                        }
+                    }
+                }
+                _ if command.starts_with(":print ") => {
+                    let name = command[":print ".len()..].trim();
+                    match program.lowered_item(name) {
+                        Some(lowered) => print!("{}", lowered),
+                        None => Err(format!("no struct, trait or opaque type named `{}`", name))?,
+                    }
+                }
+
+                // Resolve a method call like `Foo.bar()`.
+                _ if command.starts_with("probe ") => {
+                    println!("{}", prog.db.probe_method(&command["probe ".len()..])?)
+                }
+
                 // Assume this is a goal.
                 // TODO: Print out "type 'help' to see available commands" if it
                 // fails to parse?
-                _ => prog.goal(Some(rl), command, args.flag_multiple)?,
+                _ => prog.goal(Some(rl), command, args)?,
             }
             Ok(())
         })?
@@ -251,13 +614,203 @@ fn process(
     Ok(())
 }
 
-/// Load the file into a string, and parse it.
+/// Handles `:set <setting> <value>`, given the words after `:set`: changes
+/// the setting, and reconfigures the solver of the loaded program to match,
+/// without lowering the program again. With no setting, prints them all.
+fn set(args: &mut Args, words: &str, prog: &mut Option<LoadedProgram>) -> Result<()> {
+    let words: Vec<_> = words.split_whitespace().collect();
+    let (setting, value) = match words[..] {
+        [] => {
+            print_settings(args);
+            return Ok(());
+        }
+        [setting, value] => (setting, value),
+        _ => Err(":set takes a setting and a value; type `:set` to see the settings")?,
+    };
+
+    match setting {
+        "solver" => {
+            if value != "slg" && value != "recursive" {
+                Err(format!("unknown solver `{}`", value))?;
+            }
+            args.flag_solver = value.to_string();
+        }
+        "overflow-depth" => {
+            let depth = value.parse()?;
+            if depth == 0 {
+                Err("overflow depth must be at least 1")?;
+            }
+            args.flag_overflow_depth = depth;
+        }
+        "iterative-deepening" => {
+            args.flag_iterative_deepening = match value {
+                "off" => None,
+                _ => Some(value.parse()?),
+            };
+        }
+        "coinductive-auto-traits" => args.flag_inductive_auto_traits = !parse_on_off(value)?,
+        _ => {
+            let flag = match setting {
+                "multiple" => &mut args.flag_multiple,
+                "proof-tree" => &mut args.flag_proof_tree,
+                "explain" => &mut args.flag_explain,
+                "dot" => &mut args.flag_dot,
+                "stats" => &mut args.flag_stats,
+                "trace" => &mut args.flag_trace,
+                _ => Err(format!("unknown setting `{}`", setting))?,
+            };
+            *flag = parse_on_off(value)?;
+        }
+    }
+
+    if let Some(prog) = prog {
+        prog.db.set_solver_choice(args.solver_choice());
+    }
+    Ok(())
+}
+
+fn parse_on_off(value: &str) -> Result<bool> {
+    match value {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err(format!("expected `on` or `off`, found `{}`", value))?,
+    }
+}
+
+/// Prints the settings that can be changed with `:set`.
+fn print_settings(args: &Args) {
+    let on_off = |flag: bool| if flag { "on" } else { "off" };
+    println!("solver                  {}", args.flag_solver);
+    println!("overflow-depth          {}", args.flag_overflow_depth);
+    match args.flag_iterative_deepening {
+        Some(depth) => println!("iterative-deepening     {}", depth),
+        None => println!("iterative-deepening     off"),
+    }
+    println!(
+        "coinductive-auto-traits {}",
+        on_off(!args.flag_inductive_auto_traits)
+    );
+    println!("multiple                {}", on_off(args.flag_multiple));
+    println!("proof-tree              {}", on_off(args.flag_proof_tree));
+    println!("explain                 {}", on_off(args.flag_explain));
+    println!("dot                     {}", on_off(args.flag_dot));
+    println!("stats                   {}", on_off(args.flag_stats));
+    println!("trace                   {}", on_off(args.flag_trace));
+}
+
+/// Formats the files at `paths` in place or, if `check`, lists those that
+/// are not formatted, returning the exit status.
+fn fmt<S: AsRef<str>>(paths: &[S], check: bool) -> i32 {
+    let mut status = 0;
+    for path in paths {
+        let path = path.as_ref();
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("{}: error: {}", path, e);
+                status = 2;
+                continue;
+            }
+        };
+        let formatted = match chalk_parse::format_program(&text) {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                eprint_parse_error(path, &text, &*e);
+                status = 2;
+                continue;
+            }
+        };
+        if formatted == text {
+            continue;
+        }
+        if check {
+            println!("{}", path);
+            status = status.max(1);
+        } else if let Err(e) = std::fs::write(path, formatted) {
+            eprintln!("{}: error: {}", path, e);
+            status = 2;
+        }
+    }
+    status
+}
+
+/// Reduces the program in the file at `path`, against which `goal` fails,
+/// and prints what is left of it, returning the exit status.
+fn reduce(args: &Args, path: &str, goal: &str) -> i32 {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("{}: error: {}", path, e);
+            return 2;
+        }
+    };
+    let program = match chalk_parse::parse_program(&text) {
+        Ok(program) => program,
+        Err(e) => {
+            eprint_parse_error(path, &text, &*e);
+            return 2;
+        }
+    };
+    let failure = match &args.flag_expect {
+        Some(expected) => Failure::WrongSolution(expected.clone()),
+        None => Failure::Panic,
+    };
+
+    // Most of the programs tried along the way panic, if the original
+    // does; the messages would only bury the result.
+    std::panic::set_hook(Box::new(|_| {}));
+    if !failure.occurs(&text, goal, args.solver_choice()) {
+        eprintln!("{}: error: `{}` does not fail", path, goal);
+        return 1;
+    }
+    let reduced = reduce_failure(&program, goal, &failure, args.solver_choice());
+    print!("{}", chalk_parse::print_program(&reduced));
+    0
+}
+
+/// Prints the error `e` in parsing `text`, the text of the file at `path`:
+/// a syntax error is rendered as a diagnostic.
+fn eprint_parse_error(path: &str, text: &str, e: &(dyn std::error::Error + 'static)) {
+    match e.downcast_ref::<chalk_parse::SyntaxError>() {
+        Some(e) => eprint!("{}", Diagnostic::syntax(e).render(path, text)),
+        None => eprintln!("{}: error: {}", path, e),
+    }
+}
+
+/// Load the files into a single string, and parse it.
 // TODO: Could we pass in an Options struct or something? The Args struct
 // still has Strings where it should have Enums... (e.g. solver_choice)
-fn load_program(args: &Args, filename: &str) -> Result<LoadedProgram> {
+fn load_program<S: AsRef<str>>(args: &Args, filenames: &[S]) -> Result<LoadedProgram> {
     let mut text = String::new();
-    File::open(filename)?.read_to_string(&mut text)?;
-    Ok(LoadedProgram::new(text, args.solver_choice())?)
+    let mut files = vec![];
+    for filename in filenames {
+        files.push((filename.as_ref().to_string(), text.len()));
+        File::open(filename.as_ref())?.read_to_string(&mut text)?;
+        text.push('\n');
+    }
+    Ok(LoadedProgram {
+        files,
+        ..LoadedProgram::new(text, args.solver_choice())?
+    })
+}
+
+/// An error rendered as a diagnostic, which is printed as it is.
+#[derive(Debug)]
+struct Rendered(String);
+
+impl std::fmt::Display for Rendered {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for Rendered {}
+
+fn print_error(e: &(dyn std::error::Error + 'static)) {
+    match e.downcast_ref::<Rendered>() {
+        Some(rendered) => eprint!("{}", rendered),
+        None => eprintln!("error: {}", e),
+    }
 }
 
 /// Print out help for commands in interpreter mode.
@@ -266,11 +819,19 @@ fn help() {
     println!("Commands:");
     println!("  help          print this output");
     println!("  program       provide a program via stdin");
-    println!("  load <file>   load program from <file>");
+    println!("  load <file>.. load program from one or more <file>s");
     println!("  print         print the current program");
     println!("  lowered       print the lowered program");
+    println!("  :program      print the lowered IR of the program");
+    println!("  :print <item> print the lowered IR of the struct, trait or opaque type <item>");
+    println!("  :clauses <item> print the program clauses of <item>");
     println!("  <goal>        attempt to solve <goal>");
+    println!("  probe <call>  resolve a method <call> like `Foo.bar()`");
+    println!("  :set <setting> <value> change a setting, like `:set solver recursive`");
+    println!("  :set          print the settings");
     println!("  debug <level> set debug level to <level>");
+    println!();
+    println!("A command with unclosed braces continues on the next line.");
 }
 
 /// Read a program from the command-line. Stop reading when EOF is read. If
@@ -287,10 +848,13 @@ fn read_program(rl: &mut rustyline::Editor<()>) -> Result<String> {
 
 impl Args {
     fn solver_choice(&self) -> SolverChoice {
-        SolverChoice::SLG {
-            max_size: self.flag_overflow_depth,
-            expected_answers: None,
+        match &self.flag_solver[..] {
+            "recursive" => SolverChoice::recursive()
+                .max_depth(self.flag_overflow_depth)
+                .iterative_deepening(self.flag_iterative_deepening),
+            _ => SolverChoice::slg_default().max_size(self.flag_overflow_depth),
         }
+        .coinductive_auto_traits(!self.flag_inductive_auto_traits)
     }
 }
 
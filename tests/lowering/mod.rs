@@ -1,12 +1,16 @@
 use chalk_integration::db::ChalkDatabase;
 use chalk_integration::query::LoweringDatabase;
+use chalk_solve::ext::*;
+use chalk_solve::RustIrDatabase;
 use chalk_solve::SolverChoice;
 
+mod snapshot;
+
 #[test]
 fn lower_success() {
     lowering_success! {
         program {
-            struct Foo { field: Foo }
+            struct Foo { field: u32 }
             trait Bar { }
             impl Bar for Foo { }
         }
@@ -163,6 +167,36 @@ fn goal_quantifiers() {
     });
 }
 
+#[test]
+fn comments() {
+    let db = ChalkDatabase::with(
+        "
+            // A line comment.
+            trait Foo<A, B> { } // a trailing comment
+
+            /* A block comment, /* which nests, */
+               and spans lines. */
+            struct Bar</* T */ U> { field: U }
+            ",
+        SolverChoice::default(),
+    );
+    let goal = db
+        .parse_and_lower_goal("forall<X> { /* comment */ exists<Y> { Bar<X>: Foo<Y, X> } } // done")
+        .unwrap();
+    db.with_program(|_| {
+        assert_eq!(
+            format!("{:?}", goal),
+            "ForAll<type> { Exists<type> { Implemented(Bar<^1.0>: Foo<^0.0, ^1.0>) } }"
+        );
+    });
+
+    let db = ChalkDatabase::with("trait Foo { } /* unterminated", SolverChoice::default());
+    assert_eq!(
+        db.checked_program().unwrap_err().to_string(),
+        "unterminated block comment at position 14"
+    );
+}
+
 #[test]
 fn atc_accounting() {
     let db = ChalkDatabase::with(
@@ -190,7 +224,7 @@ fn atc_accounting() {
         assert_eq!(
             &atv_text[..].replace(",\n", "\n"),
             &r#"AssociatedTyValue {
-    impl_id: ImplId(#2),
+    impl_id: impl(Vec<^0.0>: Iterable),
     associated_ty_id: (Iterable::Iter),
     value: for<lifetime, type> AssociatedTyValueBound {
         ty: Iter<'^0.0, ^0.1>
@@ -349,6 +383,40 @@ fn gat_higher_ranked_bound() {
     }
 }
 
+#[test]
+fn multiple_assoc_ty_bindings() {
+    lowering_success! {
+        program {
+            trait Iterator {
+                type Item;
+                type Len;
+            }
+            struct Foo { }
+
+            trait Bar
+            where
+                Self: Iterator<Item = Foo, Len = Foo>
+            {
+                type Assoc: Iterator<Item = Foo, Len = Foo>;
+            }
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Iterator {
+                type Item;
+            }
+            struct Foo { }
+            trait Bar where Self: Iterator<Item = Foo, Len = Foo> { }
+        }
+
+        error_msg {
+            "no associated type `Len` defined in trait"
+        }
+    }
+}
+
 #[test]
 fn duplicate_parameters() {
     lowering_error! {
@@ -357,7 +425,28 @@ fn duplicate_parameters() {
         }
 
         error_msg {
-            "duplicate or shadowed parameters"
+            "duplicate parameter name `T`"
+        }
+    }
+
+    lowering_error! {
+        program {
+            struct Foo<'a, T, 'a> { }
+        }
+
+        error_msg {
+            "duplicate parameter name `'a`"
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Bar { }
+            impl<T, U, T> Bar for U { }
+        }
+
+        error_msg {
+            "duplicate parameter name `T`"
         }
     }
 
@@ -398,6 +487,49 @@ fn duplicate_parameters() {
     }
 }
 
+#[test]
+fn modules() {
+    lowering_success! {
+        program {
+            mod a {
+                struct Foo { }
+                trait Bar { }
+            }
+            mod b {
+                struct Foo { }
+                impl a::Bar for Foo { }
+            }
+            impl a::Bar for a::Foo { }
+        }
+    }
+
+    lowering_error! {
+        program {
+            mod a {
+                struct Foo { }
+            }
+            trait Bar { }
+            impl Bar for Foo { }
+        }
+        error_msg {
+            "invalid type name `Foo`"
+        }
+    }
+
+    lowering_error! {
+        program {
+            struct Foo { }
+            mod a {
+                trait Bar { }
+            }
+            impl b::Bar for Foo { }
+        }
+        error_msg {
+            "invalid type name `b::Bar`"
+        }
+    }
+}
+
 #[test]
 fn upstream_items() {
     lowering_success! {
@@ -409,69 +541,911 @@ fn upstream_items() {
 }
 
 #[test]
-fn fundamental_multiple_type_parameters() {
+fn attributes() {
+    lowering_success! {
+        program {
+            #[upstream] #[fundamental] struct Box<T> { }
+            #[coinductive] #[upstream] #[marker] trait Foo { }
+            #[lang(sized)] trait Sized { }
+            #[upstream] impl<T> Foo for Box<T> { }
+        }
+    }
+
     lowering_error! {
         program {
-            #[fundamental]
-            struct Boxes<T, U> { }
+            #[foo] struct Foo { }
+        }
+        error_msg {
+            "unknown attribute `foo`"
+        }
+    }
+
+    lowering_error! {
+        program {
+            #[auto] struct Foo { }
+        }
+        error_msg {
+            "unknown attribute `auto`"
         }
+    }
 
+    lowering_error! {
+        program {
+            #[lang(foo)] trait Foo { }
+        }
         error_msg {
-            "only a single parameter supported for fundamental type `Boxes`"
+            "unknown lang item `foo`"
+        }
+    }
+
+    lowering_error! {
+        program {
+            #[marker(sized)] trait Foo { }
+        }
+        error_msg {
+            "invalid arguments for attribute `marker`"
+        }
+    }
+
+    lowering_success! {
+        program {
+            trait Foo { }
+            struct Bar { }
+            #[priority(2)] impl Foo for Bar { }
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Foo { }
+            struct Bar { }
+            #[priority(high)] impl Foo for Bar { }
+        }
+        error_msg {
+            "invalid arguments for attribute `priority`"
         }
     }
 }
 
 #[test]
-fn tuples() {
+fn opaque_type_alias() {
     lowering_success! {
         program {
-            trait Foo { }
+            struct Foo<T> { }
+            trait Bar { }
+            type Baz<T> = impl Bar;
+            hidden type Baz<T> = Foo<T>;
+        }
+    }
 
-            // `()` is an empty tuple
-            impl Foo for () { }
-            // `(i32,)` is a tuple
-            impl Foo for (i32,) { }
-            // `(i32)` is `i32` is a scalar
-            impl Foo for (i32) { }
-            impl Foo for (i32, u32) { }
-            impl Foo for (i32, u32, f32) { }
+    lowering_error! {
+        program {
+            trait Bar { }
+            type Baz = impl Bar;
+        }
+        error_msg {
+            "no hidden type defined for opaque type `Baz`"
+        }
+    }
+
+    lowering_error! {
+        program {
+            struct Foo { }
+            trait Bar { }
+            opaque type Baz: Bar = Foo;
+            hidden type Baz = Foo;
+        }
+        error_msg {
+            "hidden type for opaque type `Baz` defined more than once"
+        }
+    }
+
+    lowering_error! {
+        program {
+            struct Foo<T> { }
+            trait Bar { }
+            type Baz<T> = impl Bar;
+            hidden type Baz<U> = Foo<U>;
+        }
+        error_msg {
+            "hidden type for opaque type `Baz` must declare the same parameters as the opaque type"
+        }
+    }
+
+    lowering_error! {
+        program {
+            struct Foo { }
+            hidden type Foo = Foo;
+        }
+        error_msg {
+            "expected an opaque type, found `Foo`, which is not an opaque type"
         }
     }
 }
 
 #[test]
-fn scalars() {
+fn recursive_structs() {
     lowering_success! {
         program {
-            trait Foo { }
+            struct Box<T> { }
+            struct List<T> { value: T, next: Box<List<T>> }
+            struct Pair<T> { a: T, b: T }
+            struct Nested { pair: Pair<Box<Nested>> }
+        }
+    }
 
-            impl Foo for i8 { }
-            impl Foo for i16 { }
-            impl Foo for i32 { }
-            impl Foo for i64 { }
-            impl Foo for i128 { }
-            impl Foo for isize { }
-            impl Foo for u8 { }
-            impl Foo for u16 { }
-            impl Foo for u32 { }
-            impl Foo for u64 { }
-            impl Foo for u128 { }
-            impl Foo for usize { }
-            impl Foo for f32 { }
-            impl Foo for f64 { }
-            impl Foo for bool { }
-            impl Foo for char { }
+    lowering_error! {
+        program {
+            struct List { next: List }
+        }
+        error_msg {
+            "recursive struct `List` has infinite size; insert some indirection to make it representable"
         }
     }
 
     lowering_error! {
         program {
-            struct i32 { }
+            struct Foo { bar: (u32, Bar) }
+            struct Bar { foo: Foo }
+        }
+        error_msg {
+            "recursive struct `Foo` has infinite size; insert some indirection to make it representable"
+        }
+    }
+
+    lowering_error! {
+        program {
+            struct Wrapper<T> { value: T }
+            struct Node { child: Wrapper<Node> }
+        }
+        error_msg {
+            "recursive struct `Node` has infinite size; insert some indirection to make it representable"
+        }
+    }
+
+    // A struct holding a recursive struct is not recursive itself, but a
+    // cycle of its own is still reported.
+    lowering_error! {
+        program {
+            struct R { r: R }
+            struct S { s: S, r: R }
+            struct T { r: R }
+        }
+        error_msg {
+            "recursive struct `R` has infinite size; insert some indirection to make it representable\n\
+             recursive struct `S` has infinite size; insert some indirection to make it representable"
+        }
+    }
+}
+
+#[test]
+fn recursive_structs_diamond() {
+    // Each struct is reachable through many paths, which must not all be
+    // walked.
+    let levels = 40;
+    let mut program = String::new();
+    for i in 0..levels {
+        program += &format!("struct S{0} {{ a: A{0}, b: B{0} }}\n", i);
+        program += &format!("struct A{} {{ s: S{} }}\n", i, i + 1);
+        program += &format!("struct B{} {{ s: S{} }}\n", i, i + 1);
+    }
+    program += &format!("struct S{} {{ }}\n", levels);
+    ChalkDatabase::with(&program, SolverChoice::default())
+        .checked_program()
+        .unwrap();
+}
+
+#[test]
+fn inherent_impls() {
+    lowering_success! {
+        program {
+            struct Foo<T> { }
+            trait Clone { }
+            impl<T> Foo<T> where T: Clone {
+                fn new(T) -> Foo<T>;
+                fn get(&self) -> T;
+                fn set(&mut self, T);
+                fn into_inner(self) -> T;
+            }
+            impl u32 { }
+        }
+    }
+
+    lowering_error! {
+        program {
+            struct Foo { }
+            impl Foo {
+                fn bar(&self);
+                fn bar(self);
+            }
         }
+        error_msg {
+            "duplicate definitions with name `bar`"
+        }
+    }
+}
+
+#[test]
+fn trait_methods() {
+    lowering_success! {
+        program {
+            trait Clone { }
+            trait Iterator {
+                type Item;
+                fn next(&mut self) -> <Self as Iterator>::Item;
+                fn map<F>(self, F) -> Self where F: Clone;
+            }
+            struct Foo { }
+            impl Iterator for Foo {
+                type Item = u32;
+                fn next(&mut self) -> u32;
+                fn map<F>(self, F) -> Foo where F: Clone;
+            }
+        }
+    }
 
+    lowering_error! {
+        program {
+            #[auto] trait Send {
+                fn send(&self);
+            }
+        }
+        error_msg {
+            "auto trait `Send` cannot define methods"
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Foo {
+                fn foo(&self);
+            }
+            struct Bar { }
+            impl !Foo for Bar {
+                fn foo(&self);
+            }
+        }
+        error_msg {
+            "negative impl for trait `Foo` cannot define methods"
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Foo {
+                fn foo(&self);
+                fn foo(self);
+            }
+        }
+        error_msg {
+            "duplicate definitions with name `foo`"
+        }
+    }
+}
+
+#[test]
+fn unsafe_traits() {
+    lowering_success! {
+        program {
+            unsafe trait Send { }
+            struct Foo { }
+            struct Bar { }
+            unsafe impl Send for Foo { }
+            impl !Send for Bar { }
+        }
+    }
+
+    lowering_error! {
+        program {
+            unsafe trait Send { }
+            struct Foo { }
+            impl Send for Foo { }
+        }
+        error_msg {
+            "the trait `Send` requires an `unsafe impl` declaration"
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Clone { }
+            struct Foo { }
+            unsafe impl Clone for Foo { }
+        }
+        error_msg {
+            "implementing the trait `Clone` is not unsafe"
+        }
+    }
+
+    lowering_error! {
+        program {
+            unsafe trait Send { }
+            struct Foo { }
+            unsafe impl !Send for Foo { }
+        }
+        error_msg {
+            "negative impl for trait `Send` cannot be unsafe"
+        }
+    }
+}
+
+#[test]
+fn const_impls() {
+    lowering_success! {
+        program {
+            trait Clone { }
+            struct Vec<T> { }
+            impl<T> const Clone for Vec<T> where T: ~const Clone { }
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Clone { }
+            struct Foo { }
+            impl const !Clone for Foo { }
+        }
+        error_msg {
+            "negative impl for trait `Clone` cannot be const"
+        }
+    }
+}
+
+#[test]
+fn extern_types() {
+    lowering_success! {
+        program {
+            extern type Foo;
+            #[upstream] extern type Bar;
+        }
+    }
+}
+
+#[test]
+fn static_lifetime() {
+    lowering_success! {
+        program {
+            struct Ref<'a, T> { }
+            struct Foo { field: Ref<'static, u32> }
+            trait Bar { }
+            impl Bar for Ref<'static, u32> { }
+        }
+    }
+}
+
+#[test]
+fn anonymous_lifetimes() {
+    lowering_success! {
+        program {
+            trait Foo<T> { fn get(&self, &T) -> &'_ u32; }
+            trait Bar { }
+            impl<T> Foo<T> for &T where &'_ T: Bar { fn get(&self, &T) -> &'_ u32; }
+            struct Baz { }
+            impl Baz { fn get(&self, &mut Baz) -> &Baz; }
+        }
+    }
+
+    lowering_success! {
+        program {
+            trait Foo where &'_ Self: Foo { }
+            struct Bar<T> where &T: Foo { }
+            forall<T> { &T: Foo }
+            forall<T> { &'_ T: Foo if &T: Foo }
+        }
+    }
+
+    lowering_error! {
+        program {
+            struct Foo<T> { field: &T }
+        }
+
+        error_msg {
+            "anonymous lifetimes are not allowed here"
+        }
+    }
+}
+
+#[test]
+fn self_in_impls() {
+    lowering_success! {
+        program {
+            trait Clone { }
+            trait Foo { type Assoc; fn get(&self) -> Self; }
+            struct Bar<T> { }
+            impl<T> Foo for Bar<T> where Self: Clone {
+                type Assoc = Self;
+                fn get(&self) -> Self;
+            }
+            impl<T> Bar<T> where Self: Clone { fn new() -> Self; }
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Foo<T> { }
+            struct Bar { }
+            impl Foo<Self> for Bar { }
+        }
+
+        error_msg {
+            "invalid type name `Self`"
+        }
+    }
+}
+
+#[test]
+fn shorthand_projections() {
+    lowering_success! {
+        program {
+            trait Iterator { type Item; fn next(&mut self) -> Self::Item; }
+            trait Clone { }
+            struct Cloned<I> where I: Iterator, I::Item: Clone { item: I::Item }
+            trait Sum where Self: Iterator { type Total; fn sum(self) -> Self::Total where Self::Item: Clone; }
+            struct Counter { }
+            impl Iterator for Counter { type Item = u32; fn next(&mut self) -> Self::Item; }
+        }
+    }
+
+    // The trait of an associated type binding is a bound too.
+    lowering_success! {
+        program {
+            trait Iterator { type Item; }
+            struct Foo { }
+            struct Wrap<T> where T: Iterator<Item = Foo> { x: T::Item }
+            struct Both<T> where T: Iterator, T: Iterator<Item = Foo> { x: T::Item }
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Iterator { type Item; }
+            struct Foo<T> { item: T::Item }
+        }
+
+        error_msg {
+            "no bound in scope declares associated type `T::Item`"
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Iterator { type Item; }
+            trait Stream { type Item; }
+            struct Foo<T> where T: Iterator, T: Stream { item: T::Item }
+        }
+
+        error_msg {
+            "ambiguous associated type `T::Item`, which could come from `Iterator`, `Stream`"
+        }
+    }
+}
+
+#[test]
+fn quantified_types_over_types() {
+    lowering_success! {
+        program {
+            trait Foo { }
+            struct Vec<T> { }
+            impl<'a> Foo for for<T, 'b> fn(Vec<T>) { }
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Foo { }
+            struct Vec<T> { }
+            impl<T> Foo for for<T> fn(Vec<T>) { }
+        }
+
+        error_msg {
+            "duplicate or shadowed parameters"
+        }
+    }
+}
+
+#[test]
+fn reserved_names() {
+    lowering_error! {
+        program {
+            struct Self { }
+        }
+
+        error_msg {
+            "`Self` is a reserved name and cannot be defined"
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Foo<Self> { }
+        }
+
+        error_msg {
+            "`Self` is a reserved name and cannot be defined"
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Foo { type Self; }
+        }
+
+        error_msg {
+            "`Self` is a reserved name and cannot be defined"
+        }
+    }
+
+    lowering_error! {
+        program {
+            struct Foo { }
+            impl Foo { fn get<Self>(self); }
+        }
+
+        error_msg {
+            "`Self` is a reserved name and cannot be defined"
+        }
+    }
+}
+
+#[test]
+fn fundamental_multiple_type_parameters() {
+    lowering_error! {
+        program {
+            #[fundamental]
+            struct Boxes<T, U> { }
+        }
+
+        error_msg {
+            "only a single parameter supported for fundamental type `Boxes`"
+        }
+    }
+}
+
+#[test]
+fn tuples() {
+    lowering_success! {
+        program {
+            trait Foo { }
+
+            // `()` is an empty tuple
+            impl Foo for () { }
+            // `(i32,)` is a tuple
+            impl Foo for (i32,) { }
+            // `(i32)` is `i32` is a scalar
+            impl Foo for (i32) { }
+            impl Foo for (i32, u32) { }
+            impl Foo for (i32, u32, f32) { }
+        }
+    }
+}
+
+#[test]
+fn scalars() {
+    lowering_success! {
+        program {
+            trait Foo { }
+
+            impl Foo for i8 { }
+            impl Foo for i16 { }
+            impl Foo for i32 { }
+            impl Foo for i64 { }
+            impl Foo for i128 { }
+            impl Foo for isize { }
+            impl Foo for u8 { }
+            impl Foo for u16 { }
+            impl Foo for u32 { }
+            impl Foo for u64 { }
+            impl Foo for u128 { }
+            impl Foo for usize { }
+            impl Foo for f32 { }
+            impl Foo for f64 { }
+            impl Foo for bool { }
+            impl Foo for char { }
+        }
+    }
+
+    lowering_error! {
+        program {
+            struct i32 { }
+        }
+
+        error_msg {
+            "parse error: unexpected token `i32`; expected identifier"
+        }
+    }
+}
+
+#[test]
+fn program_from_multiple_files() {
+    let prelude = concat!(env!("CARGO_MANIFEST_DIR"), "/libstd.chalk");
+    // A directory per test process, so that concurrent runs of the test
+    // suite don't clobber each other's files.
+    let dir = std::env::temp_dir().join(format!(
+        "chalk-program_from_multiple_files-{}",
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    let program = dir.join("program.chalk");
+    std::fs::write(&program, "struct Foo { } impl Clone for Foo { }").unwrap();
+
+    let db = ChalkDatabase::with_files(
+        &[prelude.as_ref(), program.as_path()],
+        SolverChoice::default(),
+    )
+    .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    db.with_program(|_| {
+        let goal = db.parse_and_lower_goal("Box<Foo>: Clone").unwrap();
+        let peeled_goal = goal.into_peeled_goal(db.interner());
+        assert!(db.solve(&peeled_goal).is_some());
+    });
+}
+
+#[test]
+fn predicates() {
+    lowering_success! {
+        program {
+            struct Foo { }
+            predicate Edge(type, type);
+            predicate Outlives(lifetime, type);
+
+            forall<> { Edge(Foo, Foo) }
+            forall<'a, T> { Outlives('a, T) if Edge(T, Foo) }
+        }
+    }
+
+    lowering_error! {
+        program {
+            struct Foo { }
+            forall<> { Edge(Foo, Foo) }
+        }
+
+        error_msg {
+            "invalid predicate name `Edge`"
+        }
+    }
+
+    lowering_error! {
+        program {
+            struct Foo { }
+            predicate Edge(type, type);
+            forall<> { Edge(Foo) }
+        }
+
+        error_msg {
+            "`Edge` takes 2 type parameters, not 1"
+        }
+    }
+
+    lowering_error! {
+        program {
+            struct Foo { }
+            predicate Outlives(lifetime, type);
+            forall<> { Outlives(Foo, Foo) }
+        }
+
+        error_msg {
+            "incorrect parameter kind for `Outlives`: expected lifetime, found type"
+        }
+    }
+}
+
+#[test]
+fn goal_definitions() {
+    lowering_success! {
+        program {
+            trait Foo { }
+            goal trivial { exists<T> { T = T } }
+            goal uses_trivial { goal trivial, forall<T> { if (T: Foo) { T: Foo } } }
+        }
+    }
+
+    lowering_error! {
+        program {
+            goal uses_later { goal later }
+            goal later { exists<T> { T = T } }
+        }
+
+        error_msg {
+            "invalid goal name `later`"
+        }
+    }
+
+    lowering_error! {
+        program {
+            goal recursive { goal recursive }
+        }
+
+        error_msg {
+            "invalid goal name `recursive`"
+        }
+    }
+}
+
+#[test]
+fn templates() {
+    lowering_success! {
+        program {
+            struct Foo<'a> { }
+            struct Bar { }
+            trait Clone { }
+            trait Copy where Self: Clone { }
+
+            template Implementable<trait T, type U> {
+                impl<'a> T for U { }
+            }
+
+            template Copyable<type U> {
+                expand Implementable<Clone, U>;
+                expand Implementable<Copy, U>;
+            }
+
+            expand Copyable<Foo<'a> >;
+            expand Copyable<Bar>;
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Clone { }
+            expand Implementable<Clone>;
+            template Implementable<trait T> {
+                impl T for Foo { }
+            }
+        }
+
+        error_msg {
+            "invalid template name `Implementable`"
+        }
+    }
+
+    lowering_error! {
+        program {
+            struct Foo { }
+            template Implementable<trait T> {
+                impl T for Foo { }
+            }
+            expand Implementable<Foo, Foo>;
+        }
+
+        error_msg {
+            "template `Implementable` expects 1 arguments, found 2"
+        }
+    }
+
+    lowering_error! {
+        program {
+            struct Foo { }
+            template Implementable<trait T> {
+                impl T for Foo { }
+            }
+            expand Implementable<Vec<Foo> >;
+        }
+
+        error_msg {
+            "invalid argument `Vec<Foo>` for parameter `T` of template `Implementable`"
+        }
+    }
+
+    lowering_error! {
+        program {
+            template Forever<type T> {
+                expand Forever<T>;
+            }
+            expand Forever<u32>;
+        }
+
+        error_msg {
+            "template `Forever` expands itself"
+        }
+    }
+
+    lowering_error! {
+        program {
+            template Implementable<struct T> { }
+        }
+
+        error_msg {
+            "invalid parameter `struct T` of template `Implementable`; expected `trait P` or `type P`"
+        }
+    }
+}
+
+#[test]
+fn multiple_errors() {
+    // The errors of different items are all reported, in order.
+    lowering_error! {
+        program {
+            struct Foo { x: Qux }
+            trait Bar { }
+            impl Bar for Baz { }
+            impl Bar for Foo where Foo: Quux { }
+        }
+        error_msg {
+            "invalid type name `Qux`\n\
+             invalid type name `Baz`\n\
+             invalid type name `Quux`"
+        }
+    }
+
+    lowering_error! {
+        program {
+            #[marker] struct Foo { }
+            struct Self { }
+        }
+        error_msg {
+            "unknown attribute `marker`\n\
+             `Self` is a reserved name and cannot be defined"
+        }
+    }
+
+    // An item only has its first error reported.
+    lowering_error! {
+        program {
+            struct Foo<T, T> { x: Qux }
+        }
+        error_msg {
+            "duplicate parameter name `T`"
+        }
+    }
+
+    // The names of the items are only resolved when all of them are
+    // well-formed, so the errors in resolving them are not reported
+    // until then.
+    lowering_error! {
+        program {
+            struct Foo<T, T> { }
+            struct Bar { x: Qux }
+        }
+        error_msg {
+            "duplicate parameter name `T`"
+        }
+    }
+
+    // The structs of a cycle are reported once.
+    lowering_error! {
+        program {
+            struct Foo { bar: Bar }
+            struct Bar { foo: Foo }
+            struct Baz { baz: Baz }
+        }
+        error_msg {
+            "recursive struct `Foo` has infinite size; insert some indirection to make it representable\n\
+             recursive struct `Baz` has infinite size; insert some indirection to make it representable"
+        }
+    }
+}
+
+#[test]
+fn invalid_lifetime_names() {
+    lowering_error! {
+        program {
+            struct Foo { x: &'a u32 }
+        }
+        error_msg {
+            "invalid lifetime name `'a`; no lifetimes are in scope"
+        }
+    }
+
+    lowering_error! {
+        program {
+            struct Foo<'a> { x: &'b u32 }
+        }
+        error_msg {
+            "invalid lifetime name `'b`; the lifetime in scope is `'a`"
+        }
+    }
+
+    // The lifetimes are listed in the order they are declared in, and
+    // those named for anonymous lifetimes are left out.
+    lowering_error! {
+        program {
+            trait Bar<'x> { }
+            impl<'b, 'a> Bar<'a> for &u32 where forall<'c> u32: Bar<'d> { }
+        }
         error_msg {
-            "parse error: UnrecognizedToken { token: (8, Token(49, \"i32\"), 11), expected: [\"r#\\\"([A-Za-z]|_)([A-Za-z0-9]|_)*\\\"#\"] }"
+            "invalid lifetime name `'d`; the lifetimes in scope are `'b`, `'a`, `'c`"
         }
     }
 }
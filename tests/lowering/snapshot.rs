@@ -0,0 +1,143 @@
+//! Snapshot tests of lowering: each `.chalk` file in `snapshots` is
+//! lowered, and the lowered IR of its items and its program clauses are
+//! compared against the `.lowered` file next to it. The IR is rendered
+//! with `Debug`, which shows the de Bruijn indices of bound variables, so
+//! that a change in the indices or in the order of the clauses shows up
+//! as a change in the snapshot.
+//!
+//! To accept the current output after an intended change, or to create
+//! the snapshot of a new file, run the tests with `CHALK_BLESS=1`:
+//!
+//! ```notrust
+//! $ CHALK_BLESS=1 cargo test --test lib lowering_snapshots
+//! ```
+
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::query::LoweringDatabase;
+use chalk_integration::tls;
+use chalk_solve::SolverChoice;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+const SNAPSHOTS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/lowering/snapshots");
+
+/// Renders the lowered IR of `text`: the items in the order they were
+/// declared in, as `:print` in the REPL shows them, followed by all the
+/// program clauses in the order the solvers see them. A program that
+/// does not lower is rendered as its error.
+fn render(text: &str) -> String {
+    let db = ChalkDatabase::with(text, SolverChoice::default());
+    let (program, environment) = match db.checked_program().and_then(|program| {
+        let environment = db.environment()?;
+        Ok((program, environment))
+    }) {
+        Ok(lowered) => lowered,
+        Err(error) => return format!("error: {}\n", error),
+    };
+
+    tls::set_current_program(&program, || {
+        let mut names: Vec<_> = program
+            .struct_kinds
+            .iter()
+            .map(|(id, kind)| (id.0, &kind.name))
+            .chain(
+                program
+                    .trait_kinds
+                    .iter()
+                    .map(|(id, kind)| (id.0, &kind.name)),
+            )
+            .chain(
+                program
+                    .opaque_ty_kinds
+                    .iter()
+                    .map(|(id, kind)| (id.0, &kind.name)),
+            )
+            .collect();
+        names.sort();
+
+        let mut out = String::new();
+        for (_, name) in names {
+            out.push_str(&program.lowered_item(name).unwrap());
+            out.push('\n');
+        }
+        writeln!(out, "program clauses:").unwrap();
+        for clause in &environment.program_clauses {
+            writeln!(out, "{:?}", clause).unwrap();
+        }
+        strip_atoms(&out)
+    })
+}
+
+/// Replaces the `Debug` output of the names in `text`, like
+/// `Atom('Foo' type=inline)`, with the names themselves: how a name is
+/// stored depends on its length, which is no concern of lowering.
+fn strip_atoms(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("Atom('") {
+        out.push_str(&rest[..start]);
+        rest = &rest[start + "Atom('".len()..];
+        let end = rest.find("' type=").unwrap();
+        out.push_str(&rest[..end]);
+        rest = &rest[rest[end..].find(')').unwrap() + end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[test]
+fn lowering_snapshots() {
+    let bless = std::env::var_os("CHALK_BLESS").is_some();
+    let mut paths: Vec<_> = fs::read_dir(SNAPSHOTS)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "chalk"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "no snapshots in {}", SNAPSHOTS);
+
+    let mut failures = vec![];
+    for path in &paths {
+        let rendered = render(&fs::read_to_string(path).unwrap());
+        let snapshot_path = path.with_extension("lowered");
+        if bless {
+            fs::write(&snapshot_path, &rendered).unwrap();
+            continue;
+        }
+        match fs::read_to_string(&snapshot_path) {
+            Ok(snapshot) if snapshot == rendered => {}
+            Ok(snapshot) => failures.push(mismatch(&snapshot_path, &snapshot, &rendered)),
+            Err(_) => failures.push(format!("{}: no snapshot", snapshot_path.display())),
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{}\n\nIf the changes are intended, run the tests with CHALK_BLESS=1 \
+         to update the snapshots.",
+        failures.join("\n\n")
+    );
+}
+
+/// Describes how `rendered` differs from `snapshot`: the first line that
+/// differs, with the lines of each.
+fn mismatch(path: &Path, snapshot: &str, rendered: &str) -> String {
+    let mut snapshot_lines = snapshot.lines();
+    let mut rendered_lines = rendered.lines();
+    let mut line = 1;
+    loop {
+        match (snapshot_lines.next(), rendered_lines.next()) {
+            (Some(expected), Some(actual)) if expected == actual => line += 1,
+            (expected, actual) => {
+                return format!(
+                    "{}:{}: the lowering differs from the snapshot\n\
+                     expected: {}\n  actual: {}",
+                    path.display(),
+                    line,
+                    expected.unwrap_or("<end of snapshot>"),
+                    actual.unwrap_or("<end of output>"),
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! Tests related to caching the solutions of goals across queries.
+
+use super::*;
+use chalk_solve::{AnswerCacheStats, Budget};
+
+const PROGRAM: &str = "
+    struct Vec<T> { }
+    struct Foo { }
+    trait Clone { }
+    impl Clone for Foo { }
+    impl<T> Clone for Vec<T> where T: Clone { }
+";
+
+#[test]
+fn answer_cache_hits() {
+    let goals = [
+        "Vec<Foo>: Clone",
+        "exists<T> { Vec<T>: Clone }",
+        "Vec<Foo>: Clone",
+        // The same goal as the second one, once canonicalized.
+        "exists<U> { Vec<U>: Clone }",
+        "Foo: Clone",
+    ];
+    for &solver_choice in &[SolverChoice::slg_default(), SolverChoice::recursive()] {
+        with_lowered_goals(PROGRAM, solver_choice, &goals, |db, goals| {
+            db.enable_answer_cache();
+            let solutions: Vec<_> = goals
+                .iter()
+                .map(|goal| display_solution(db.solve(goal)))
+                .collect();
+            assert_eq!(solutions[0], solutions[2]);
+            assert_eq!(solutions[1], solutions[3]);
+            assert_eq!(
+                db.answer_cache_stats(),
+                Some(AnswerCacheStats { hits: 2, misses: 3 })
+            );
+        });
+    }
+}
+
+#[test]
+fn answer_cache_disabled_by_default() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::default());
+    assert_eq!(db.answer_cache_stats(), None);
+}
+
+#[test]
+fn answer_cache_skips_stopped_goals() {
+    let goals = ["Vec<Vec<Foo>>: Clone"];
+    with_lowered_goals(PROGRAM, SolverChoice::recursive(), &goals, |db, goals| {
+        db.enable_answer_cache();
+        let goal = &goals[0];
+        let budget = Budget {
+            max_steps: Some(1),
+            ..Budget::default()
+        };
+        assert!(db.solve_with_budget(goal, budget).is_err());
+        assert_same(
+            &db.solve(goal).unwrap().display(&ChalkIr).to_string(),
+            "Unique",
+        );
+        db.solve(goal);
+        assert_eq!(
+            db.answer_cache_stats(),
+            Some(AnswerCacheStats { hits: 1, misses: 2 })
+        );
+    });
+}
+
+#[test]
+fn answer_cache_cleared_by_invalidation() {
+    let mut solver = SolverChoice::slg_default().into_solver();
+    solver.enable_answer_cache();
+    let goals = ["Foo: Clone"];
+    with_lowered_goals(PROGRAM, SolverChoice::slg_default(), &goals, |db, goals| {
+        solver.solve(db, &goals[0]);
+        solver.invalidate_traits(db, &[]);
+        solver.solve(db, &goals[0]);
+        assert_eq!(
+            solver.answer_cache_stats(),
+            Some(AnswerCacheStats { hits: 0, misses: 2 })
+        );
+    });
+}
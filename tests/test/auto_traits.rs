@@ -53,6 +53,45 @@ fn auto_semantics() {
     }
 }
 
+#[test]
+fn inductive_auto_traits() {
+    // Proving that a recursive type is `Send` needs to assume it when it
+    // comes up again, which only coinductive auto traits allow.
+    test! {
+        program {
+            #[auto] trait Send { }
+
+            struct TypeA { }
+
+            struct Ptr<T> { }
+            impl<T> Send for Ptr<T> where T: Send { }
+
+            struct List {
+                data: TypeA,
+                next: Ptr<List>
+            }
+        }
+
+        goal {
+            List: Send
+        } yields[SolverChoice::slg_default()] {
+            "Unique"
+        } yields[SolverChoice::recursive().coinductive_auto_traits(true)] {
+            "Unique"
+        } yields[SolverChoice::slg_default().coinductive_auto_traits(false)] {
+            "No possible solution"
+        } yields[SolverChoice::recursive().coinductive_auto_traits(false)] {
+            "No possible solution"
+        }
+
+        goal {
+            TypeA: Send
+        } yields[SolverChoice::recursive().coinductive_auto_traits(false)] {
+            "Unique"
+        }
+    }
+}
+
 #[test]
 fn auto_trait_without_impls() {
     test! {
@@ -0,0 +1,131 @@
+//! Tests related to batch goal files.
+
+use super::*;
+use chalk_integration::batch::{run_batch, Batch, Expectation};
+
+const PROGRAM: &str = "
+    struct Foo { }
+    struct Bar { }
+    trait Baz { }
+    impl Baz for Foo { }
+
+    goal foo_is_baz { Foo: Baz }
+";
+
+fn batch(goals: &str) -> String {
+    format!("{}\n{}", PROGRAM, goals)
+}
+
+#[test]
+fn batch_parse() {
+    let batch = Batch::parse(&batch(
+        "
+        goal { Foo: Baz } //~ UNIQUE
+        goal {
+            exists<T> { T: Baz }
+        } //~ UNIQUE [?0 := Foo]
+        // goal { Bar: Baz } //~ UNIQUE
+        goal { Bar: Baz } //~ NO SOLUTION
+        goal { exists<T> { or { T = Foo; T = Bar } } } //~ AMBIGUOUS
+        ",
+    ))
+    .unwrap();
+
+    let goals: Vec<_> = batch
+        .goals
+        .iter()
+        .map(|goal| (goal.line, &goal.text[..], goal.expected.clone()))
+        .collect();
+    assert_eq!(
+        goals,
+        vec![
+            (10, "Foo: Baz", Expectation::Unique(None)),
+            (
+                11,
+                "exists<T> { T: Baz }",
+                Expectation::Unique(Some("[?0 := Foo]".to_string()))
+            ),
+            (15, "Bar: Baz", Expectation::NoSolution),
+            (
+                16,
+                "exists<T> { or { T = Foo; T = Bar } }",
+                Expectation::Ambiguous
+            ),
+        ]
+    );
+    assert!(batch.program_text.contains("goal foo_is_baz { Foo: Baz }"));
+    assert!(!batch.program_text.contains("NO SOLUTION"));
+}
+
+#[test]
+fn batch_all_expectations_met() {
+    let report = run_batch(
+        &batch(
+            "
+            goal { Foo: Baz } //~ UNIQUE
+            goal { exists<T> { T: Baz } } //~ UNIQUE [?0 := Foo]
+            goal { Bar: Baz } //~ NO SOLUTION
+            goal { exists<T> { or { T = Foo; T = Bar } } } //~ AMBIGUOUS
+            goal { goal foo_is_baz } //~ UNIQUE
+            ",
+        ),
+        SolverChoice::default(),
+    )
+    .unwrap();
+    assert!(report.passed());
+    assert_eq!(report.mismatches, vec![]);
+    assert_eq!(report.to_string(), "5 goals, 5 passed, 0 failed");
+}
+
+#[test]
+fn batch_mismatches() {
+    let report = run_batch(
+        &batch(
+            "
+            goal { Foo: Baz } //~ NO SOLUTION
+            goal { Bar: Baz } //~ NO SOLUTION
+            goal { exists<T> { T: Baz } } //~ UNIQUE [?0 := Bar]
+            ",
+        ),
+        SolverChoice::default(),
+    )
+    .unwrap();
+    assert!(!report.passed());
+    assert_eq!(report.to_string(), "3 goals, 1 passed, 2 failed");
+    let mismatches: Vec<_> = report.mismatches.iter().map(|m| m.to_string()).collect();
+    assert_eq!(
+        mismatches,
+        vec![
+            "line 10: goal `Foo: Baz`: expected no solution, \
+             found `Unique; substitution [], lifetime constraints []`",
+            "line 12: goal `exists<T> { T: Baz }`: expected a unique solution \
+             with substitution [?0 := Bar], \
+             found `Unique; substitution [?0 := Foo], lifetime constraints []`",
+        ]
+    );
+}
+
+#[test]
+fn batch_errors() {
+    let error = Batch::parse(&batch("goal { Foo: Baz }")).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "line 9: goal is missing a `//~` expectation"
+    );
+
+    let error = Batch::parse(&batch("goal { Foo: Baz } //~ MAYBE")).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "line 9: unrecognized expectation `MAYBE`"
+    );
+
+    let error = Batch::parse(&batch("goal { Foo: Baz //~ UNIQUE")).unwrap_err();
+    assert_eq!(error.to_string(), "line 9: unterminated goal block");
+
+    let error = run_batch(
+        &batch("goal { Foo: Qux } //~ UNIQUE"),
+        SolverChoice::default(),
+    )
+    .unwrap_err();
+    assert_eq!(error.to_string(), "line 9: invalid type name `Qux`");
+}
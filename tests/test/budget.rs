@@ -0,0 +1,63 @@
+//! Tests related to limiting the work done to solve goals.
+
+use super::*;
+use chalk_solve::{Budget, BudgetExhausted};
+use std::time::Duration;
+
+const PROGRAM: &str = "
+    struct Vec<T> { }
+    struct Foo { }
+    trait Clone { }
+    trait Copy { }
+    impl Clone for Foo { }
+    impl<T> Clone for Vec<T> where T: Clone { }
+";
+
+fn steps(max_steps: usize) -> Budget {
+    Budget {
+        max_steps: Some(max_steps),
+        ..Budget::default()
+    }
+}
+
+#[test]
+fn budget_steps() {
+    let goals = ["Vec<Vec<Foo>>: Clone", "Foo: Copy"];
+    with_lowered_goals(PROGRAM, SolverChoice::recursive(), &goals, |db, goals| {
+        let solve = |goal, budget| db.solve_with_budget(goal, budget).map(display_solution);
+        let unique = Ok("Unique; substitution [], lifetime constraints []".to_string());
+        assert_eq!(solve(&goals[0], steps(2)), Err(BudgetExhausted));
+        assert_eq!(solve(&goals[0], steps(1000)), unique);
+        assert_eq!(solve(&goals[0], Budget::default()), unique);
+        assert_eq!(
+            solve(&goals[1], steps(1000)),
+            Ok("No possible solution".to_string())
+        );
+    });
+}
+
+#[test]
+fn budget_steps_slg() {
+    // There are infinitely many answers, so the SLG solver stops after
+    // each quantum of work to check the budget.
+    let goals = ["exists<T> { T: Clone }"];
+    with_lowered_goals(PROGRAM, SolverChoice::slg_default(), &goals, |db, goals| {
+        assert_eq!(
+            db.solve_with_budget(&goals[0], steps(0)),
+            Err(BudgetExhausted)
+        );
+    });
+}
+
+#[test]
+fn budget_duration() {
+    let budget = Budget {
+        max_duration: Some(Duration::from_secs(0)),
+        ..Budget::default()
+    };
+    let goals = ["Vec<Foo>: Clone"];
+    with_lowered_goals(PROGRAM, SolverChoice::recursive(), &goals, |db, goals| {
+        let solution = db.solve_with_budget(&goals[0], budget);
+        assert_eq!(solution, Err(BudgetExhausted));
+    });
+}
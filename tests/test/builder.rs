@@ -0,0 +1,72 @@
+//! Tests related to building programs with `ProgramBuilder` rather than
+//! lowering them from the surface syntax.
+
+use super::*;
+use chalk_integration::builder::ProgramBuilder;
+use chalk_integration::program::Program;
+use chalk_ir::{Goal, InEnvironment, UCanonical};
+use std::sync::Arc;
+
+const PROGRAM: &str = "
+    struct Vec<T> { }
+    struct Foo { }
+    struct Bar { }
+    trait Clone { }
+    trait Into<T> { }
+    impl Clone for Foo { }
+    impl<T> Clone for Vec<T> where T: Clone { }
+    impl<T> Into<Vec<T>> for T { }
+";
+
+fn build() -> Program {
+    ProgramBuilder::new()
+        .struct_("Vec", 1)
+        .struct_("Foo", 0)
+        .struct_("Bar", 0)
+        .trait_("Clone", 0)
+        .trait_("Into", 1)
+        .impl_("Clone", 0, |b, _| (vec![b.ty("Foo", &[])], vec![]))
+        .impl_("Clone", 1, |b, params| {
+            let vec = b.ty("Vec", &[params[0].clone()]);
+            (
+                vec![vec],
+                vec![b.implemented("Clone", &[params[0].clone()])],
+            )
+        })
+        .impl_("Into", 1, |b, params| {
+            let vec = b.ty("Vec", &[params[0].clone()]);
+            (vec![params[0].clone(), vec], vec![])
+        })
+        .build()
+}
+
+#[test]
+fn build_same_as_lowered() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::default());
+    let program = db.checked_program().unwrap();
+    assert_eq!(build(), *program);
+}
+
+#[test]
+fn solve_built_program() {
+    let program = Arc::new(build());
+    for (goal, expected) in &[
+        ("Vec<Vec<Foo>>: Clone", "Unique"),
+        ("Vec<Bar>: Clone", "No possible solution"),
+        (
+            "exists<T> { Foo: Into<T> }",
+            "Unique; substitution [?0 := Vec<Foo>]",
+        ),
+        ("forall<T> { if (T: Clone) { Vec<T>: Clone } }", "Unique"),
+    ] {
+        let goal: UCanonical<InEnvironment<Goal<ChalkIr>>> = chalk_parse::parse_goal(goal)
+            .unwrap()
+            .lower(&*program)
+            .unwrap()
+            .into_peeled_goal(&ChalkIr);
+        let solution = SolverChoice::default()
+            .into_solver()
+            .solve(&*program, &goal);
+        chalk_integration::tls::set_current_program(&program, || assert_result(solution, expected));
+    }
+}
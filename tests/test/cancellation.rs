@@ -0,0 +1,76 @@
+//! Tests related to cancelling the solving of goals.
+
+use super::*;
+use chalk_solve::trace::TraceEvent;
+use chalk_solve::CancellationToken;
+
+const PROGRAM: &str = "
+    struct Vec<T> { }
+    struct Foo { }
+    trait Clone { }
+    impl Clone for Foo { }
+    impl<T> Clone for Vec<T> where T: Clone { }
+";
+
+#[test]
+fn cancelled_before_solving() {
+    let cancelled = CancellationToken::new();
+    cancelled.cancel();
+    let goals = ["Vec<Vec<Foo>>: Clone"];
+    with_lowered_goals(PROGRAM, SolverChoice::recursive(), &goals, |db, goals| {
+        let goal = &goals[0];
+        assert_result(
+            db.solve_cancellable(goal, &cancelled),
+            "Ambiguous; no inference guidance",
+        );
+
+        // The cancelled solution is not cached.
+        assert_result(
+            db.solve_cancellable(goal, &CancellationToken::new()),
+            "Unique",
+        );
+    });
+}
+
+#[test]
+fn cancelled_while_solving() {
+    let token = CancellationToken::new();
+    let goals = ["Vec<Vec<Foo>>: Clone"];
+    with_lowered_goals(PROGRAM, SolverChoice::recursive(), &goals, |db, goals| {
+        // Cancel once the solver starts on `Vec<Foo>: Clone`, a subgoal of
+        // the goal.
+        let sink_token = token.clone();
+        db.set_trace_sink(Some(Box::new(move |event: TraceEvent<ChalkIr>| {
+            if let TraceEvent::GoalEntered { depth: 1, .. } = event {
+                sink_token.cancel();
+            }
+        })));
+        let goal = &goals[0];
+        assert_result(
+            db.solve_cancellable(goal, &token),
+            "Ambiguous; no inference guidance",
+        );
+        assert_result(
+            db.solve_cancellable(goal, &CancellationToken::new()),
+            "Unique",
+        );
+    });
+}
+
+#[test]
+fn cancelled_from_another_thread() {
+    let token = CancellationToken::new();
+    let other = token.clone();
+    std::thread::spawn(move || other.cancel()).join().unwrap();
+    assert!(token.is_cancelled());
+
+    let goals = ["exists<T> { T: Clone }"];
+    for &solver_choice in &[SolverChoice::slg_default(), SolverChoice::recursive()] {
+        with_lowered_goals(PROGRAM, solver_choice, &goals, |db, goals| {
+            assert_result(
+                db.solve_cancellable(&goals[0], &token),
+                "Ambiguous; no inference guidance",
+            );
+        });
+    }
+}
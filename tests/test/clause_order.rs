@@ -0,0 +1,178 @@
+//! Tests related to the order in which program clauses are tried.
+
+use super::*;
+use chalk_ir::DomainGoal;
+use chalk_solve::clauses::order::{
+    ByPriority, ClauseOrder, FewestVariablesFirst, PrioritizedClause,
+};
+use chalk_solve::trace::TraceEvent;
+use std::sync::{Arc, Mutex};
+
+/// Solves `goal` in `program` with the recursive solver, using
+/// `clause_order` if given, and returns the clauses tried for `goal`
+/// itself, in order.
+fn clauses_tried(
+    program: &str,
+    clause_order: Option<Arc<dyn ClauseOrder<ChalkIr>>>,
+    goal: &str,
+) -> Vec<String> {
+    with_lowered_goals(program, SolverChoice::recursive(), &[goal], |db, goals| {
+        let events = Arc::new(Mutex::new(vec![]));
+        let sink_events = events.clone();
+        db.set_trace_sink(Some(Box::new(move |event: TraceEvent<ChalkIr>| {
+            sink_events
+                .lock()
+                .unwrap()
+                .push(event.display(&ChalkIr).to_string())
+        })));
+        if let Some(clause_order) = clause_order {
+            db.set_clause_order(clause_order);
+        }
+        db.solve(&goals[0]);
+        // The first clause tried is for `goal`, before any of its
+        // subgoals.
+        let events = events.lock().unwrap();
+        let tried: Vec<_> = events
+            .iter()
+            .filter_map(|event| event.strip_prefix("clause tried: "))
+            .filter_map(|event| event.split_once(" with "))
+            .collect();
+        tried
+            .iter()
+            .filter(|(goal, _)| *goal == tried[0].0)
+            .map(|(_, clause)| clause.to_string())
+            .collect()
+    })
+}
+
+const PROGRAM: &str = "
+    struct Vec<T> { }
+    struct Foo { }
+    trait Clone { }
+    impl Clone for Foo { }
+    impl<T> Clone for Vec<T> where T: Clone { }
+    impl Clone for Vec<Foo> { }
+";
+
+const GENERIC_IMPL: &str =
+    "for<type> Implemented(Vec<^0.0>: Clone) :- ForAll<> { Implemented(^1.0: Clone) }";
+const FOO_IMPL: &str = "for<> Implemented(Vec<Foo>: Clone)";
+
+/// Tries the clauses in the reverse of the order in which they were
+/// found.
+#[derive(Debug)]
+struct Reversed;
+
+impl ClauseOrder<ChalkIr> for Reversed {
+    fn order_clauses(
+        &self,
+        _interner: &ChalkIr,
+        _goal: &DomainGoal<ChalkIr>,
+        clauses: &mut [PrioritizedClause<ChalkIr>],
+    ) {
+        clauses.reverse();
+    }
+}
+
+/// The position of each of `clauses` in `tried`.
+fn positions(tried: &[String], clauses: &[&str]) -> Vec<usize> {
+    clauses
+        .iter()
+        .map(|clause| tried.iter().position(|tried| tried == clause).unwrap())
+        .collect()
+}
+
+#[test]
+fn declaration_order_by_default() {
+    let tried = clauses_tried(PROGRAM, None, "Vec<Foo>: Clone");
+    let positions = positions(&tried, &[GENERIC_IMPL, FOO_IMPL]);
+    assert!(positions[0] < positions[1]);
+}
+
+#[test]
+fn priority_attribute() {
+    let program = "
+        struct Vec<T> { }
+        struct Foo { }
+        trait Clone { }
+        impl Clone for Foo { }
+        impl<T> Clone for Vec<T> where T: Clone { }
+        #[priority(1)] impl Clone for Vec<Foo> { }
+    ";
+    let tried = clauses_tried(program, None, "Vec<Foo>: Clone");
+    assert_eq!(tried[0], FOO_IMPL);
+}
+
+#[test]
+fn fewest_variables_first() {
+    let tried = clauses_tried(
+        PROGRAM,
+        Some(Arc::new(FewestVariablesFirst)),
+        "Vec<Foo>: Clone",
+    );
+    assert_eq!(tried[0], FOO_IMPL);
+}
+
+#[test]
+fn custom_clause_order() {
+    let tried = clauses_tried(PROGRAM, None, "Vec<Foo>: Clone");
+    let mut reversed = clauses_tried(PROGRAM, Some(Arc::new(Reversed)), "Vec<Foo>: Clone");
+    reversed.reverse();
+    assert_eq!(tried, reversed);
+}
+
+#[test]
+fn clause_order_keeps_solutions() {
+    // The impls are declared in the reverse order of `PROGRAM`, and one
+    // of them has a priority, which changes the order in which they are
+    // tried but not the solutions.
+    test! {
+        program {
+            struct Vec<T> { }
+            struct Foo { }
+            trait Clone { }
+            #[priority(1)] impl Clone for Vec<Foo> { }
+            impl<T> Clone for Vec<T> where T: Clone { }
+            impl Clone for Foo { }
+        }
+
+        goal {
+            Vec<Foo>: Clone
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            exists<T> { Vec<T>: Clone }
+        } yields {
+            "Ambiguous; no inference guidance"
+        }
+
+        goal {
+            Vec<Vec<Foo>>: Clone
+        } yields {
+            "Unique"
+        }
+    }
+}
+
+#[test]
+fn custom_clause_order_keeps_solutions() {
+    let goals = [
+        "Vec<Foo>: Clone",
+        "exists<T> { Vec<T>: Clone }",
+        "Vec<Vec<Foo>>: Clone",
+    ];
+    for &solver_choice in &[SolverChoice::slg_default(), SolverChoice::recursive()] {
+        with_lowered_goals(PROGRAM, solver_choice, &goals, |db, goals| {
+            for goal in &goals {
+                db.set_clause_order(Arc::new(ByPriority));
+                let expected = db.solve(goal);
+                db.set_clause_order(Arc::new(Reversed));
+                assert_eq!(db.solve(goal), expected);
+                db.set_clause_order(Arc::new(FewestVariablesFirst));
+                assert_eq!(db.solve(goal), expected);
+            }
+        });
+    }
+}
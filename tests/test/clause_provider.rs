@@ -0,0 +1,104 @@
+//! Tests related to how the program clauses for a goal are generated.
+
+use super::*;
+use chalk_engine::context::Floundered;
+use chalk_integration::Identifier;
+use chalk_ir::{DomainGoal, Environment, TraitId, WhereClause};
+use chalk_solve::clauses::order::PrioritizedClause;
+use chalk_solve::clauses::provider::{FromProgram, ProgramClauseProvider};
+use std::sync::{Arc, Mutex};
+
+const PROGRAM: &str = "
+    struct Vec<T> { }
+    struct Foo { }
+    trait Clone { }
+    trait Debug { }
+    impl Clone for Foo { }
+    impl<T> Clone for Vec<T> where T: Clone { }
+    impl Debug for Foo { }
+    impl<T> Debug for Vec<T> where T: Debug { }
+";
+
+/// Generates the clauses from the program, recording the goals they
+/// were generated for.
+#[derive(Debug, Default)]
+struct Recording {
+    goals: Mutex<Vec<String>>,
+}
+
+impl ProgramClauseProvider<ChalkIr> for Recording {
+    fn program_clauses(
+        &self,
+        db: &dyn RustIrDatabase<ChalkIr>,
+        environment: &Environment<ChalkIr>,
+        goal: &DomainGoal<ChalkIr>,
+        clauses: &mut Vec<PrioritizedClause<ChalkIr>>,
+    ) -> Result<(), Floundered> {
+        self.goals.lock().unwrap().push(format!("{:?}", goal));
+        FromProgram.program_clauses(db, environment, goal, clauses)
+    }
+}
+
+/// Generates the clauses from the program, except those that could
+/// prove that a type implements `trait_id`.
+#[derive(Debug)]
+struct WithoutImplsOf {
+    trait_id: TraitId<ChalkIr>,
+}
+
+impl ProgramClauseProvider<ChalkIr> for WithoutImplsOf {
+    fn program_clauses(
+        &self,
+        db: &dyn RustIrDatabase<ChalkIr>,
+        environment: &Environment<ChalkIr>,
+        goal: &DomainGoal<ChalkIr>,
+        clauses: &mut Vec<PrioritizedClause<ChalkIr>>,
+    ) -> Result<(), Floundered> {
+        match goal {
+            DomainGoal::Holds(WhereClause::Implemented(trait_ref))
+                if trait_ref.trait_id == self.trait_id =>
+            {
+                Ok(())
+            }
+            _ => FromProgram.program_clauses(db, environment, goal, clauses),
+        }
+    }
+}
+
+#[test]
+fn clauses_generated_per_goal() {
+    // Only the clauses of the goals being solved are generated, so
+    // those for `Debug` never are.
+    for &solver_choice in &[SolverChoice::slg_default(), SolverChoice::recursive()] {
+        with_lowered_goals(PROGRAM, solver_choice, &["Vec<Foo>: Clone"], |db, goals| {
+            let provider = Arc::new(Recording::default());
+            db.set_clause_provider(provider.clone());
+            assert!(db.solve(&goals[0]).unwrap().is_unique());
+
+            let goals = provider.goals.lock().unwrap();
+            assert!(goals.contains(&"Implemented(Vec<Foo>: Clone)".to_string()));
+            assert!(goals.contains(&"Implemented(Foo: Clone)".to_string()));
+            assert!(goals.iter().all(|goal| !goal.contains("Debug")));
+        });
+    }
+}
+
+#[test]
+fn custom_clause_provider() {
+    for &solver_choice in &[SolverChoice::slg_default(), SolverChoice::recursive()] {
+        with_lowered_goals(PROGRAM, solver_choice, &["Vec<Foo>: Clone"], |db, goals| {
+            let goal = &goals[0];
+            assert!(db.solve(goal).unwrap().is_unique());
+
+            // The solution found with the clauses of the program must
+            // not be reused.
+            let program = db.checked_program().unwrap();
+            let trait_id = program.trait_ids[&Identifier::from("Clone")];
+            db.set_clause_provider(Arc::new(WithoutImplsOf { trait_id }));
+            assert_eq!(db.solve(goal), None);
+
+            db.set_clause_provider(Arc::new(FromProgram));
+            assert!(db.solve(goal).unwrap().is_unique());
+        });
+    }
+}
@@ -360,3 +360,30 @@ fn local_impl_allowed_for_traits() {
         goal { forall<'a, T> { LocalImplAllowed(Upstream: UpstreamTrait<'a, Upstream, Local, T>) } } yields { "Unique" }
     }
 }
+
+#[test]
+fn compatible_modality() {
+    test! {
+        program {
+            trait Local { }
+            #[upstream] trait Upstream { }
+            struct Foo { }
+            #[upstream] struct Vec<T> { }
+
+            impl Local for Foo { }
+        }
+
+        // Without `compatible`, the solver relies on knowing every impl
+        goal { forall<T> { not { Vec<T>: Local } } } yields { "Unique" }
+        goal { not { exists<T> { T: Upstream } } } yields { "Unique" }
+        goal { not { Vec<Foo>: Upstream } } yields { "Unique" }
+
+        // With `compatible`, downstream and future upstream crates could add impls
+        goal { compatible { forall<T> { not { Vec<T>: Local } } } } yields { "Ambiguous" }
+        goal { compatible { not { exists<T> { T: Upstream } } } } yields { "Ambiguous" }
+        goal { compatible { not { Vec<Foo>: Upstream } } } yields { "Ambiguous" }
+
+        // Goals that hold in every compatible world still hold
+        goal { compatible { Foo: Local } } yields { "Unique" }
+    }
+}
@@ -0,0 +1,99 @@
+//! Tests for `impl const` and `~const` bounds, which hold in const
+//! contexts as well as at runtime.
+
+use super::*;
+
+#[test]
+fn const_impls() {
+    test! {
+        program {
+            trait Clone { }
+            struct Foo { }
+            struct Bar { }
+            struct Vec<T> { }
+
+            impl const Clone for Foo { }
+            impl Clone for Bar { }
+            impl<T> const Clone for Vec<T> where T: ~const Clone { }
+        }
+
+        goal {
+            Foo: ~const Clone
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            Bar: Clone
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            Bar: ~const Clone
+        } yields {
+            "No possible solution"
+        }
+
+        // At runtime, the `~const` bound only needs to hold at runtime.
+        goal {
+            Vec<Bar>: Clone
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            Vec<Foo>: ~const Clone
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            Vec<Bar>: ~const Clone
+        } yields {
+            "No possible solution"
+        }
+    }
+}
+
+#[test]
+fn const_bounds_in_environment() {
+    test! {
+        program {
+            trait Clone { }
+            struct Vec<T> { }
+
+            impl<T> const Clone for Vec<T> where T: ~const Clone { }
+        }
+
+        goal {
+            forall<T> {
+                if (T: ~const Clone) {
+                    Vec<T>: ~const Clone
+                }
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            forall<T> {
+                if (T: ~const Clone) {
+                    T: Clone
+                }
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            forall<T> {
+                if (T: Clone) {
+                    Vec<T>: ~const Clone
+                }
+            }
+        } yields {
+            "No possible solution"
+        }
+    }
+}
@@ -56,6 +56,37 @@ fn cycle_no_solution() {
     }
 }
 
+#[test]
+fn cycle_through_self() {
+    // The only way to prove `T: Foo` is to already know `T: Foo`, so
+    // there is no (finite) proof.
+    test! {
+        program {
+            trait Foo { }
+            struct S { }
+            impl<T> Foo for T where T: Foo { }
+        }
+
+        goal {
+            S: Foo
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            exists<T> { T: Foo }
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            forall<T> { if (T: Foo) { T: Foo } }
+        } yields {
+            "Unique"
+        }
+    }
+}
+
 #[test]
 fn cycle_many_solutions() {
     test! {
@@ -171,6 +202,165 @@ fn overflow() {
     }
 }
 
+#[test]
+fn overflow_depth() {
+    // Proving `S: A1` goes through `S: A2`, .., `S: A5`, which needs
+    // a stack of goals more than five deep. Giving up on a goal must not
+    // affect later goals, which are solved with the same solver.
+    test! {
+        program {
+            trait A1 { }
+            trait A2 { }
+            trait A3 { }
+            trait A4 { }
+            trait A5 { }
+            struct S { }
+
+            impl A5 for S { }
+            impl<T> A4 for T where T: A5 { }
+            impl<T> A3 for T where T: A4 { }
+            impl<T> A2 for T where T: A3 { }
+            impl<T> A1 for T where T: A2 { }
+        }
+
+        goal {
+            S: A1
+        } yields[SolverChoice::recursive_with_overflow_depth(6)] {
+            "Unique"
+        } yields[SolverChoice::recursive_with_overflow_depth(5)] {
+            "Ambiguous; no inference guidance"
+        }
+
+        goal {
+            S: A3
+        } yields[SolverChoice::recursive_with_overflow_depth(5)] {
+            "Unique"
+        }
+    }
+}
+
+#[test]
+fn overflow_stack() {
+    // The goals that led to the goal given up on are reported, which
+    // tells overflow apart from ambiguity in the program.
+    let program = "
+        trait A1 { }
+        trait A2 { }
+        trait A3 { }
+        struct S { }
+
+        impl A3 for S { }
+        impl<T> A2 for T where T: A3 { }
+        impl<T> A1 for T where T: A2 { }
+    ";
+    let goals = ["S: A1", "S: A3"];
+    with_lowered_goals(program, SolverChoice::default(), &goals, |db, goals| {
+        db.set_solver_choice(SolverChoice::recursive_with_overflow_depth(2));
+        assert_result(db.solve(&goals[0]), "Ambiguous; no inference guidance");
+        let stack: Vec<_> = db
+            .last_overflow()
+            .unwrap()
+            .iter()
+            .map(|goal| format!("{:?}", goal.canonical.value.goal))
+            .collect();
+        // The first goal given up on comes from the clause that proves
+        // `S: A2` from the environment, which is tried before the impl.
+        assert_eq!(
+            stack,
+            vec!["Implemented(S: A1)", "Implemented(S: A2)", "FromEnv(S: A2)"]
+        );
+
+        assert_result(db.solve(&goals[1]), "Unique");
+        assert_eq!(db.last_overflow(), None);
+    });
+}
+
+#[test]
+fn iterative_deepening() {
+    // With iterative deepening, the goals that overflow are solved again
+    // with deeper overflow depths, up to the maximum depth.
+    test! {
+        program {
+            trait A1 { }
+            trait A2 { }
+            trait A3 { }
+            trait A4 { }
+            trait A5 { }
+            struct S { }
+            struct T { }
+
+            impl A5 for S { }
+            impl<X> A4 for X where X: A5 { }
+            impl<X> A3 for X where X: A4 { }
+            impl<X> A2 for X where X: A3 { }
+            impl<X> A1 for X where X: A2 { }
+        }
+
+        goal {
+            S: A1
+        } yields[SolverChoice::recursive_with_overflow_depth(3)] {
+            "Ambiguous; no inference guidance"
+        } yields[SolverChoice::recursive_with_overflow_depth(3).iterative_deepening(Some(6))] {
+            "Unique"
+        } yields[SolverChoice::recursive_with_overflow_depth(3).iterative_deepening(Some(5))] {
+            "Ambiguous; no inference guidance"
+        }
+
+        goal {
+            T: A1
+        } yields[SolverChoice::recursive_with_overflow_depth(3).iterative_deepening(Some(6))] {
+            "No possible solution"
+        }
+
+        goal {
+            exists<X> { X: A1 }
+        } yields[SolverChoice::recursive_with_overflow_depth(3).iterative_deepening(Some(6))] {
+            "Unique; substitution [?0 := S]"
+        }
+    }
+}
+
+#[test]
+fn iterative_deepening_past_overflow_depth() {
+    // Proving `S: A0` goes through `S: A1`, .., `S: A20`, much deeper than
+    // the overflow depth the solver starts with.
+    let depth = 20;
+    let mut program = "struct S { }\n".to_string();
+    for i in 0..depth {
+        program += &format!("trait A{} {{ }}\n", i);
+        program += &format!("impl<X> A{} for X where X: A{} {{ }}\n", i, i + 1);
+    }
+    program += &format!("trait A{} {{ }}\nimpl A{} for S {{ }}\n", depth, depth);
+
+    with_lowered_goals(
+        &program,
+        SolverChoice::default(),
+        &["S: A0"],
+        |db, goals| {
+            let goal = &goals[0];
+
+            db.set_solver_choice(SolverChoice::recursive_with_overflow_depth(4));
+            assert_result(db.solve(goal), "Ambiguous; no inference guidance");
+            assert!(db.last_overflow().is_some());
+
+            db.set_solver_choice(SolverChoice::recursive_with_overflow_depth(64));
+            assert_result(db.solve(goal), "Unique");
+            let fixed = db.last_stats();
+
+            db.set_solver_choice(
+                SolverChoice::recursive_with_overflow_depth(4).iterative_deepening(Some(64)),
+            );
+            assert_result(db.solve(goal), "Unique");
+            assert_eq!(db.last_overflow(), None);
+
+            // The work done at each overflow depth is counted.
+            let deepening = db.last_stats();
+            assert!(deepening.subgoals > fixed.subgoals);
+            assert_eq!(deepening.max_depth, fixed.max_depth);
+        },
+    );
+}
+
 #[test]
 fn overflow_universe() {
     test! {
@@ -0,0 +1,208 @@
+//! Tests of the rendering of errors as diagnostics.
+
+use super::*;
+use chalk_integration::diagnostic::Diagnostic;
+use chalk_parse::ast::Span;
+
+fn render(text: &str) -> String {
+    let error = ChalkDatabase::with(text, SolverChoice::default())
+        .checked_program()
+        .unwrap_err();
+    error.render("test.chalk", text)
+}
+
+#[test]
+fn lowering_errors() {
+    let text = "struct Foo<T> { }\ntrait Bar { }\nimpl Bar for Foo<Quux> { }\n";
+    assert_eq!(
+        render(text),
+        "\
+error: invalid type name `Quux`
+ --> test.chalk:3:18
+  |
+3 | impl Bar for Foo<Quux> { }
+  |                  ^^^^ not a type in scope
+"
+    );
+
+    let text = "struct Foo<T> {\n\tfield: &T\n}\n";
+    assert_eq!(
+        render(text),
+        "\
+error: anonymous lifetimes are not allowed here
+ --> test.chalk:2:9
+  |
+2 | \tfield: &T
+  | \t       ^ lifetime needs a name
+"
+    );
+}
+
+#[test]
+fn multiple_errors() {
+    let text = "struct Foo { x: Qux }\nimpl Baz for Foo { }\n";
+    assert_eq!(
+        render(text),
+        "\
+error: invalid type name `Qux`
+ --> test.chalk:1:17
+  |
+1 | struct Foo { x: Qux }
+  |                 ^^^ not a type in scope
+
+error: invalid type name `Baz`
+ --> test.chalk:2:6
+  |
+2 | impl Baz for Foo { }
+  |      ^^^ not a type in scope
+"
+    );
+}
+
+#[test]
+fn syntax_errors() {
+    let text = "struct i32 { }\n";
+    assert_eq!(
+        render(text),
+        "\
+error: parse error: unexpected token `i32`
+ --> test.chalk:1:8
+  |
+1 | struct i32 { }
+  |        ^^^ expected identifier
+"
+    );
+
+    let text = "trait Foo { }\nimpl Foo for\n";
+    assert_eq!(
+        render(text),
+        "\
+error: parse error: unexpected end of input
+ --> test.chalk:2:13
+  |
+2 | impl Foo for
+  |             ^ expected one of identifier, path, `&` and 21 others
+"
+    );
+
+    // A syntax error in an expansion is reported in the template body, or
+    // in the argument of the `expand` that it came from.
+    let text = "template Struct<type T> { struct T { } }\nexpand Struct<i32>;\n";
+    assert_eq!(
+        render(text),
+        "\
+error: parse error: unexpected token `i32`
+ --> test.chalk:2:15
+  |
+2 | expand Struct<i32>;
+  |               ^^^ expected identifier
+"
+    );
+}
+
+/// What the `chalk` binary prints to standard error when it solves
+/// `goal` against libstd.chalk.
+fn render_goal(goal: &str) -> String {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_chalk"))
+        .arg(concat!(
+            "--program=",
+            env!("CARGO_MANIFEST_DIR"),
+            "/libstd.chalk"
+        ))
+        .arg(format!("--goal={}", goal))
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn goal_syntax_errors() {
+    assert_eq!(
+        render_goal("Vec<i32>: Clone {"),
+        "\
+error: parse error: unexpected token `{`
+ --> <goal>:1:17
+  |
+1 | Vec<i32>: Clone {
+  |                 ^ expected one of `(`, `)`, `+` and 11 others
+"
+    );
+}
+
+#[test]
+fn goal_lowering_errors() {
+    assert_eq!(
+        render_goal("Baz: Clone"),
+        "\
+error: invalid type name `Baz`
+ --> <goal>:1:1
+  |
+1 | Baz: Clone
+  | ^^^ not a type in scope
+"
+    );
+
+    assert_eq!(
+        render_goal("forall<'a> { &'b i32: Clone }"),
+        "\
+error: invalid lifetime name `'b`; the lifetime in scope is `'a`
+ --> <goal>:1:15
+  |
+1 | forall<'a> { &'b i32: Clone }
+  |               ^^ not a lifetime in scope
+"
+    );
+}
+
+#[test]
+fn errors_without_spans() {
+    let text = "trait Foo { }\nstruct Bar { }\nimpl Foo for Bar { }\nimpl Foo for Bar { }\n";
+    assert_eq!(
+        render(text),
+        "\
+error: overlapping impls of trait `Foo`
+ --> test.chalk
+"
+    );
+}
+
+#[test]
+fn spans() {
+    let text = format!("{}struct Foo {{\n    x: u32\n}}\n", "\n".repeat(9));
+    let lo = text.find("Foo").unwrap();
+    let diagnostic = Diagnostic {
+        message: "message".to_string(),
+        span: Some(Span::new(lo, text.len() - 1)),
+        label: None,
+    };
+
+    // The gutter is as wide as the line number, and a span that goes on
+    // past its line is underlined to the end of that line.
+    assert_eq!(
+        diagnostic.render("test.chalk", &text),
+        "\
+error: message
+  --> test.chalk:10:8
+   |
+10 | struct Foo {
+   |        ^^^^^
+"
+    );
+
+    // An empty span is underlined with a single caret.
+    let diagnostic = Diagnostic {
+        span: Some(Span::new(lo, lo)),
+        label: Some("here".to_string()),
+        ..diagnostic
+    };
+    assert!(diagnostic
+        .render("test.chalk", &text)
+        .ends_with("   |        ^ here\n"));
+
+    // A span that is not in the text is left out.
+    assert_eq!(
+        diagnostic.render("test.chalk", "struct Foo { }"),
+        "error: message\n --> test.chalk\n"
+    );
+}
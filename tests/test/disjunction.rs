@@ -0,0 +1,114 @@
+//! Tests related to `or { }` goals.
+
+use super::*;
+
+#[test]
+fn simple_disjunction() {
+    test! {
+        program {
+            struct Foo {}
+            struct Bar {}
+            trait Trait {}
+            trait Other {}
+
+            impl Trait for Foo {}
+        }
+
+        goal {
+            or { Foo: Trait; Bar: Trait }
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            or { Bar: Trait; Foo: Trait }
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            or { Foo: Other; Bar: Trait }
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            or { Foo: Trait; Foo: Trait; Bar: Other }
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            not { or { Foo: Other; Bar: Trait } }
+        } yields {
+            "Unique"
+        }
+    }
+}
+
+#[test]
+fn disjunction_with_substitutions() {
+    test! {
+        program {
+            struct Foo {}
+            struct Bar {}
+            trait Trait {}
+            trait Other {}
+
+            impl Trait for Foo {}
+            impl Trait for Bar {}
+            impl Other for Bar {}
+        }
+
+        goal {
+            exists<T> { or { T = Foo; T: Other } }
+        } yields {
+            "Ambiguous; no inference guidance"
+        }
+
+        goal {
+            exists<T> { or { T = Bar; T: Other } }
+        } yields {
+            "Unique; substitution [?0 := Bar]"
+        }
+
+        goal {
+            exists<T> { or { T = Bar, T: Trait; T: Other } }
+        } yields {
+            "Unique; substitution [?0 := Bar]"
+        }
+
+        goal {
+            exists<T> { or { T: Other; T = Foo, T: Other } }
+        } yields {
+            "Unique; substitution [?0 := Bar]"
+        }
+    }
+}
+
+#[test]
+fn disjunction_under_binders() {
+    test! {
+        program {
+            trait Trait {}
+        }
+
+        goal {
+            forall<T> { if (T: Trait) { or { T: Trait; T = T } } }
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            forall<T> { or { T: Trait; exists<U> { T = U } } }
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            forall<T> { or { T: Trait; exists<U> { T: Trait, U = U } } }
+        } yields {
+            "No possible solution"
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! Tests related to rendering the IR in the surface syntax.
+
+use super::*;
+
+const PROGRAM: &str = "
+    trait Clone { }
+    trait Iterator { type Item; }
+    trait Eq<T> { }
+    struct Vec<T> { }
+    struct Ref<'a, T> { }
+    predicate Reachable(type, type);
+    impl<T> Vec<T> { fn len(&self) -> usize; }
+";
+
+/// Lowers `goal` in `PROGRAM` and renders it back.
+fn render(goal: &str) -> String {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::default());
+    let program = db.checked_program().unwrap();
+    let goal = chalk_parse::parse_goal(goal)
+        .unwrap()
+        .lower(&*program)
+        .unwrap();
+    program.display(&goal).to_string()
+}
+
+/// Checks that `goal` is rendered as `expected`, and that `expected`
+/// lowers to the same goal as `goal`.
+fn assert_renders(goal: &str, expected: &str) {
+    assert_eq!(render(goal), expected);
+    assert_eq!(render(expected), expected);
+}
+
+#[test]
+fn display_types() {
+    assert_renders("Vec<u32>: Clone", "Vec<u32>: Clone");
+    assert_renders(
+        "forall<T> { Vec<T>: Clone }",
+        "forall<T0> { Vec<T0>: Clone }",
+    );
+    assert_renders(
+        "forall<'a, T> { Ref<'a, (T, &'a mut T, (bool,))>: Clone }",
+        "forall<'a0, T0> { Ref<'a0, (T0, &'a0 mut T0, (bool,))>: Clone }",
+    );
+    assert_renders(
+        "forall<T> { for<'a> fn(&'a T): Clone }",
+        "forall<T0> { for<'a0> fn(&'a0 T0): Clone }",
+    );
+    // Lowering adds the bound `Iterator` implied by `Iterator<Item = u32>`.
+    assert_eq!(
+        render("dyn Iterator<Item = u32> + Clone: Clone"),
+        "dyn Iterator + Iterator<Item = u32> + Clone: Clone",
+    );
+}
+
+#[test]
+fn display_where_clauses() {
+    assert_renders(
+        "forall<T> { T: Eq<Vec<T>> }",
+        "forall<T0> { T0: Eq<Vec<T0>> }",
+    );
+    // Lowering adds the where clause `T: Iterator` implied by
+    // `T: Iterator<Item = u32>`.
+    assert_eq!(
+        render("forall<T> { T: Iterator<Item = u32> }"),
+        "forall<T0> { T0: Iterator<Item = u32>, T0: Iterator }",
+    );
+    assert_renders(
+        "forall<T> { Normalize(<T as Iterator>::Item -> u32) }",
+        "forall<T0> { Normalize(<T0 as Iterator>::Item -> u32) }",
+    );
+    assert_renders(
+        "forall<T> { WellFormed(T: Clone), FromEnv(Vec<T>) }",
+        "forall<T0> { WellFormed(T0: Clone), FromEnv(Vec<T0>) }",
+    );
+    assert_renders("Reachable(u32, bool)", "Reachable(u32, bool)");
+}
+
+#[test]
+fn display_goals() {
+    assert_renders(
+        "forall<T> { exists<U> { U = Vec<T> } }",
+        "forall<T0> { exists<T1> { T1 = Vec<T0> } }",
+    );
+    // Lowering turns the facts of the environment into `FromEnv` facts.
+    assert_renders(
+        "forall<T> { if (T: Clone; Vec<T>: Clone) { not { T: Iterator } } }",
+        "forall<T0> { if (FromEnv(T0: Clone); FromEnv(Vec<T0>: Clone)) { not { T0: Iterator } } }",
+    );
+    assert_renders(
+        "or { u32: Clone; not { (u32: Clone, bool: Clone) } }",
+        "or { u32: Clone; not { u32: Clone, bool: Clone } }",
+    );
+    assert_renders(
+        "forall<'a, 'b> { 'a: 'b, u32: 'a }",
+        "forall<'a0, 'a1> { 'a0: 'a1, u32: 'a0 }",
+    );
+    assert_renders("forall<T> { Vec<T>.len() }", "forall<T0> { Vec<T0>.len() }");
+}
@@ -0,0 +1,71 @@
+//! Tests related to the DOT graphs of proof trees and traces.
+
+use super::*;
+use chalk_solve::dot::TraceDot;
+use chalk_solve::trace::TraceEvent;
+use std::sync::{Arc, Mutex};
+
+const PROGRAM: &str = "
+    struct Vec<T> { }
+    struct Foo { }
+    trait Clone { }
+    impl Clone for Foo { }
+    impl<T> Clone for Vec<T> where T: Clone { }
+";
+
+#[test]
+fn proof_tree_dot() {
+    let goals = ["Vec<Foo>: Clone"];
+    with_lowered_goals(PROGRAM, SolverChoice::recursive(), &goals, |db, goals| {
+        let tree = db.solve_with_proof_tree(&goals[0]).unwrap();
+        assert_same(
+            &tree.dot(&ChalkIr).to_string(),
+            r#"
+            digraph search {
+                node [style=filled];
+                n0 [label="Implemented(Vec<Foo>: Clone)\nUnique; substitution [], lifetime constraints []", shape=ellipse, fillcolor=palegreen];
+                n1 [label="for<type> Implemented(Vec<^0.0>: Clone) :- ForAll<> { Implemented(^1.0: Clone) }", shape=box, fillcolor=white];
+                n2 [label="Implemented(Foo: Clone)\nUnique; substitution [], lifetime constraints []", shape=ellipse, fillcolor=palegreen];
+                n3 [label="Implemented(Foo: Clone)", shape=box, fillcolor=white];
+                n0 -> n1;
+                n1 -> n2;
+                n2 -> n3;
+            }
+            "#,
+        );
+    });
+}
+
+#[test]
+fn trace_dot() {
+    // The second goal finds the solution of `Foo: Clone` in the cache.
+    let goals = ["Foo: Clone", "Vec<Foo>: Clone"];
+    with_lowered_goals(PROGRAM, SolverChoice::recursive(), &goals, |db, goals| {
+        let events = Arc::new(Mutex::new(vec![]));
+        let sink_events = events.clone();
+        db.set_trace_sink(Some(Box::new(move |event: TraceEvent<ChalkIr>| {
+            sink_events.lock().unwrap().push(event)
+        })));
+        for goal in &goals {
+            db.solve(goal);
+        }
+        let events = events.lock().unwrap();
+        let start = events
+            .iter()
+            .rposition(|event| match event {
+                TraceEvent::GoalEntered { depth: 0, .. } => true,
+                _ => false,
+            })
+            .unwrap();
+        let dot = TraceDot::new(&events[start..], &ChalkIr).to_string();
+        assert!(dot.starts_with("digraph search {\n"), "{}", dot);
+        assert!(dot.contains(
+            r#"n0 [label="Implemented(Vec<Foo>: Clone)\nUnique; substitution [], lifetime constraints []", shape=ellipse, fillcolor=palegreen];"#
+        ), "{}", dot);
+        assert!(dot.contains(
+            r#"[label="Implemented(Foo: Clone)\nUnique; substitution [], lifetime constraints []", shape=ellipse, fillcolor=palegreen];"#
+        ), "{}", dot);
+        assert!(dot.contains("[style=dotted];"), "{}", dot);
+        assert!(dot.ends_with("}\n"), "{}", dot);
+    });
+}
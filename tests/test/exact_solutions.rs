@@ -0,0 +1,101 @@
+//! Tests of goals whose solutions are checked exactly, with
+//! `yields_exactly`, and of how a mismatch is reported.
+
+use super::*;
+use std::panic;
+
+#[test]
+fn exact_substitution() {
+    test! {
+        program {
+            struct Foo { }
+            struct Vec<T> { }
+            trait Clone { }
+            impl Clone for Foo { }
+        }
+
+        goal {
+            exists<T> { Vec<T> = Vec<Foo> }
+        } yields_exactly {
+            "Unique; substitution [?0 := Foo], lifetime constraints []"
+        }
+
+        goal {
+            exists<T> { T: Clone }
+        } yields_exactly {
+            "Unique; substitution [?0 := Foo], lifetime constraints []"
+        }
+
+        goal {
+            Vec<Foo>: Clone
+        } yields_exactly {
+            "No possible solution"
+        }
+    }
+}
+
+#[test]
+fn exact_constraints() {
+    test! {
+        program {
+            trait Foo { }
+            struct Ref<'a, 'b> { }
+            impl<'x> Foo for Ref<'x, 'x> { }
+        }
+
+        goal {
+            forall<'a, 'b> { Ref<'a, 'b>: Foo }
+        } yields_exactly[SolverChoice::slg_default()] {
+            "Unique; substitution [], lifetime constraints [\
+            InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }, \
+            InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }\
+            ]"
+        }
+
+        goal {
+            forall<'a, 'b> { Ref<'a, 'b>: Foo }
+        } yields_exactly[SolverChoice::recursive()] {
+            "Unique; substitution [], lifetime constraints [\
+            InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }, \
+            InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }\
+            ]"
+        }
+    }
+}
+
+/// The message that `assert_matches` panics with, if it does.
+fn mismatch(result: &str, expected: &str, exact: bool) -> Option<String> {
+    panic::catch_unwind(|| assert_matches(result, expected, exact))
+        .err()
+        .map(|payload| *payload.downcast::<String>().unwrap())
+}
+
+#[test]
+fn exact_mismatch() {
+    let solution = "Unique; substitution [?0 := Foo], lifetime constraints []";
+    assert_eq!(mismatch(solution, "Unique", false), None);
+    assert_eq!(mismatch(solution, solution, true), None);
+    assert_eq!(
+        mismatch(
+            solution,
+            "Unique;  substitution [?0 := Foo],\n lifetime constraints []",
+            true
+        ),
+        None
+    );
+
+    assert_eq!(
+        mismatch(solution, "Unique", true).unwrap(),
+        "solution does not match exactly\n\
+         expected: Unique\n  \
+         actual: Unique; substitution [?0 := Foo], lifetime constraints []\n          \
+         \u{20}     ^"
+    );
+    assert_eq!(
+        mismatch(solution, "Unique; substitution [?0 := Bar]", false).unwrap(),
+        "solution does not match\n\
+         expected: Unique; substitution [?0 := Bar]\n  \
+         actual: Unique; substitution [?0 := Foo], lifetime constraints []\n          \
+         \u{20}                           ^"
+    );
+}
@@ -48,6 +48,42 @@ fn opaque_reveal() {
     }
 }
 
+#[test]
+fn opaque_type_alias() {
+    test! {
+        program {
+            struct Ty { }
+            trait Trait { }
+            impl Trait for Ty { }
+
+            trait Clone { }
+            impl Clone for Ty { }
+            type T = impl Clone;
+            hidden type T = Ty;
+        }
+
+        goal {
+            T: Clone
+        } yields {
+            "Unique; substitution []"
+        }
+
+        goal {
+            if (Reveal) {
+                T: Trait
+            }
+        } yields {
+            "Unique; substitution []"
+        }
+
+        goal {
+            T: Trait
+        } yields {
+            "No possible solution"
+        }
+    }
+}
+
 #[test]
 fn dyn_Clone_is_Clone() {
     test! {
@@ -95,6 +131,80 @@ fn dyn_Clone_Send_is_Send() {
     }
 }
 
+#[test]
+fn dyn_object_safety() {
+    test! {
+        program {
+            #[lang(sized)] trait Sized { }
+            trait Clone { }
+
+            trait Safe {
+                type Item;
+                fn by_ref(&self) -> <Self as Safe>::Item;
+                fn by_value(self, u32);
+                fn new() -> Self where Self: Sized;
+                fn generic<T>(&self, T) where Self: Sized;
+            }
+            trait AssociatedFn {
+                fn new() -> u32;
+            }
+            trait Generic {
+                fn generic<T>(&self, T);
+            }
+            trait SelfArgument {
+                fn eq(&self, Self);
+            }
+            trait SelfReturn {
+                fn clone(&self) -> Self;
+            }
+            trait SizedTrait where Self: Sized { }
+            trait SubTrait where Self: SelfReturn { }
+        }
+
+        goal {
+            dyn Safe: Safe
+        } yields {
+            "Unique; substitution []"
+        }
+
+        goal {
+            dyn AssociatedFn: AssociatedFn
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            dyn Generic: Generic
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            dyn SelfArgument: SelfArgument
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            dyn SelfReturn: SelfReturn
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            dyn SizedTrait: SizedTrait
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            dyn SubTrait: SubTrait
+        } yields {
+            "No possible solution"
+        }
+    }
+}
+
 #[test]
 fn dyn_Foo_Bar() {
     test! {
@@ -255,17 +365,13 @@ fn dyn_higher_ranked_type_arguments() {
         }
 
         goal {
-            forall<'static> {
-                dyn forall<'a> Foo<Ref<'a>>: Foo<Ref<'static>>
-            }
+            dyn forall<'a> Foo<Ref<'a>>: Foo<Ref<'static>>
         } yields {
             "Unique; substitution [], lifetime constraints []"
         }
 
         goal {
-            forall<'static> {
-                dyn forall<'a> Foo<Ref<'a>> + Bar: Foo<Ref<'static>>
-            }
+            dyn forall<'a> Foo<Ref<'a>> + Bar: Foo<Ref<'static>>
         } yields {
             "Unique; substitution [], lifetime constraints []"
         }
@@ -277,16 +383,13 @@ fn dyn_higher_ranked_type_arguments() {
         }
 
         goal {
-            forall<'static> {
-                forall<'a> {
-                    dyn Foo<Ref<'static>>: Foo<Ref<'a>>
-                }
+            forall<'a> {
+                dyn Foo<Ref<'static>>: Foo<Ref<'a>>
             }
         } yields {
-            // Note that this requires 'a == 'static, so it would be resolveable later on.
+            // Note that this requires 'a: 'static, so it would be resolveable later on.
             "Unique; substitution [], lifetime constraints [\
-            InEnvironment { environment: Env([]), goal: '!1_0: '!2_0 }, \
-            InEnvironment { environment: Env([]), goal: '!2_0: '!1_0 }\
+            InEnvironment { environment: Env([]), goal: '!1_0: 'static }\
             ]"
         }
     }
@@ -305,34 +408,24 @@ fn dyn_binders_reverse() {
             impl<A> Eq<A> for A { }
         }
 
-        // Note: these constraints are ultimately unresolveable (we
-        // have to show that 'a == 'b, basically)
+        // Note: we have to show that 'a == 'b, basically, for
+        // placeholders 'a and 'b
         goal {
             dyn forall<'a, 'b> Fn<Refs<'a, 'b>>: Eq<
                 dyn forall<'c> Fn<Refs<'c, 'c>>
             >
         } yields {
-            "Unique; substitution [], lifetime constraints [\
-            InEnvironment { environment: Env([]), goal: '!3_0: '!3_1 }, \
-            InEnvironment { environment: Env([]), goal: '!3_1: '!3_0 }, \
-            InEnvironment { environment: Env([]), goal: '!6_0: '!6_1 }, \
-            InEnvironment { environment: Env([]), goal: '!6_1: '!6_0 }\
-            ]"
+            "No possible solution"
         }
 
-        // Note: these constraints are ultimately unresolveable (we
-        // have to show that 'a == 'b, basically)
+        // Note: we have to show that 'a == 'b, basically, for
+        // placeholders 'a and 'b
         goal {
             dyn forall<'c> Fn<Refs<'c, 'c>>: Eq<
                 dyn forall<'a, 'b> Fn<Refs<'a, 'b>>
             >
         } yields {
-            "Unique; substitution [], lifetime constraints [\
-            InEnvironment { environment: Env([]), goal: '!2_0: '!2_1 }, \
-            InEnvironment { environment: Env([]), goal: '!2_1: '!2_0 }, \
-            InEnvironment { environment: Env([]), goal: '!5_0: '!5_1 }, \
-            InEnvironment { environment: Env([]), goal: '!5_1: '!5_0 }\
-            ]"
+            "No possible solution"
         }
 
         // Note: ordering of parameters is reversed here, but that's no problem
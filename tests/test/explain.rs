@@ -0,0 +1,127 @@
+//! Tests related to explaining why goals have no solution.
+
+use super::*;
+use chalk_solve::explain::{Explanation, Reason, Rejection};
+
+const PROGRAM: &str = "
+    trait Foo { }
+    trait Bar { }
+    struct S { }
+    struct T { }
+    struct U { }
+    impl Foo for S { }
+    impl Foo for U where U: Bar { }
+    impl<X> Bar for X where X: Bar { }
+";
+
+/// Explains why `goal` has no solution in `PROGRAM`, with each solver,
+/// checking that both give the same explanation.
+fn explain(goal: &str) -> Option<Explanation<ChalkIr>> {
+    let explanations: Vec<_> = [SolverChoice::slg_default(), SolverChoice::recursive()]
+        .iter()
+        .map(|&solver_choice| {
+            with_lowered_goals(PROGRAM, solver_choice, &[goal], |db, goals| {
+                db.explain_failure(&goals[0])
+            })
+        })
+        .collect();
+    assert_eq!(explanations[0], explanations[1]);
+    explanations.into_iter().next().unwrap()
+}
+
+/// Like `explain`, but returns the printed explanation.
+fn explain_display(goal: &str) -> String {
+    let explanation = explain(goal).expect("goal has a solution");
+    with_lowered_goals(PROGRAM, SolverChoice::default(), &[], |_, _| {
+        explanation.display().to_string()
+    })
+}
+
+#[test]
+fn explain_solvable_goal() {
+    assert_eq!(explain("S: Foo"), None);
+    assert_eq!(explain("exists<X> { X: Foo }"), None);
+}
+
+#[test]
+fn explain_mismatched_parameter() {
+    let explanation = explain("T: Foo").unwrap();
+    let rejected = match explanation.reason {
+        Reason::Clauses(rejected) => rejected,
+        reason => panic!("unexpected reason: {:?}", reason),
+    };
+    let mismatches: Vec<_> = rejected
+        .iter()
+        .filter_map(|rejected| match &rejected.rejection {
+            Rejection::Mismatch { index, .. } => Some(*index),
+            _ => None,
+        })
+        .collect();
+    // Both impls are rejected because of the self type.
+    assert_eq!(mismatches, vec![0, 0]);
+}
+
+#[test]
+fn explain_failed_condition() {
+    assert_eq!(
+        explain_display("U: Foo"),
+        "\
+Implemented(U: Foo) has no solution
+    rejected for<type> Implemented(^0.0: Foo) :- FromEnv(^0.0: Foo)
+        FromEnv(U: Foo) has no solution
+            no program clause could prove it
+    rejected Implemented(S: Foo)
+        parameter 0 is `U`, which does not unify with `S`
+    rejected Implemented(U: Foo) :- ForAll<> { Implemented(U: Bar) }
+        Implemented(U: Bar) has no solution
+            rejected for<type> Implemented(^0.0: Bar) :- FromEnv(^0.0: Bar)
+                FromEnv(U: Bar) has no solution
+                    no program clause could prove it
+            rejected for<type> Implemented(^0.0: Bar) :- ForAll<> { Implemented(^1.0: Bar) }
+                Implemented(U: Bar) has no solution
+                    (already being explained above)
+"
+    );
+}
+
+#[test]
+fn explain_placeholder() {
+    assert_eq!(
+        explain_display("forall<X> { X: Foo }"),
+        "\
+Implemented(!1_0: Foo) has no solution
+    rejected for<type> Implemented(^0.0: Foo) :- FromEnv(^0.0: Foo)
+        FromEnv(!1_0: Foo) has no solution
+            no program clause could prove it
+    rejected Implemented(S: Foo)
+        parameter 0 is `!1_0`, which does not unify with `S`
+    rejected Implemented(U: Foo) :- ForAll<> { Implemented(U: Bar) }
+        parameter 0 is `!1_0`, which does not unify with `U`
+"
+    );
+}
+
+#[test]
+fn explain_negation() {
+    assert_eq!(
+        explain_display("not { S: Foo }"),
+        "\
+not { Implemented(S: Foo) } has no solution
+    the goal it negates has a solution
+"
+    );
+}
+
+#[test]
+fn explain_equality() {
+    let explanation = explain("S = T").unwrap();
+    assert_eq!(explanation.reason, Reason::Inequality);
+}
+
+#[test]
+fn explain_conjunction() {
+    // Only the first conjunct that has no solution is explained.
+    let display = explain_display("S: Foo, T: Bar, T: Foo");
+    assert!(display.contains("Implemented(T: Bar) has no solution"));
+    assert!(!display.contains("Implemented(T: Foo) has no solution"));
+}
@@ -0,0 +1,57 @@
+//! Tests for `extern type` items, whose size and layout are unknown.
+
+use super::*;
+
+#[test]
+fn extern_type_is_not_sized() {
+    test! {
+        program {
+            #[lang(sized)] trait Sized { }
+            extern type Foo;
+            struct Bar { }
+            struct Tail { field: Foo }
+        }
+
+        goal {
+            Foo: Sized
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            Bar: Sized
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            Tail: Sized
+        } yields {
+            "No possible solution"
+        }
+    }
+}
+
+#[test]
+fn extern_type_auto_traits() {
+    test! {
+        program {
+            #[auto] trait Send { }
+            #[auto] trait Sync { }
+            extern type Foo;
+            impl Send for Foo { }
+        }
+
+        goal {
+            Foo: Send
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            Foo: Sync
+        } yields {
+            "No possible solution"
+        }
+    }
+}
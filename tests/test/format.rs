@@ -0,0 +1,164 @@
+//! Tests related to formatting programs.
+
+use chalk_parse::{format_program, parse_program};
+
+/// The debug output of the parsed `text`, without the spans of its
+/// nodes, which formatting changes.
+fn parsed_without_spans(text: &str) -> String {
+    let parsed = format!("{:?}", parse_program(text).unwrap());
+    let mut output = String::new();
+    let mut rest = &parsed[..];
+    while let Some(start) = rest.find("span: Span {") {
+        output.push_str(rest[..start].trim_end_matches(", "));
+        rest = &rest[start..];
+        rest = &rest[rest.find('}').unwrap() + 1..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Checks that formatting `text` gives `expected`, that it leaves the
+/// parsed program unchanged, and that the result is already formatted.
+fn assert_formats(text: &str, expected: &str) {
+    let formatted = format_program(text).unwrap();
+    assert_eq!(formatted, expected);
+    assert_eq!(parsed_without_spans(&formatted), parsed_without_spans(text));
+    assert_eq!(format_program(&formatted).unwrap(), formatted);
+}
+
+#[test]
+fn format_items() {
+    assert_formats(
+        "
+        #[upstream]   #[lang(sized)] trait   Sized{}
+        trait Iterator where Self:Sized { fn next(&mut self)->Option<Self::Item>; type Item: Sized; }
+        struct Option<T>{value:T,other:(T,),unit:(),r:&'a mut T, g: &(for<'a> fn(&'a T))}
+        trait Foo<'a, U> {   type Assoc<X>: Iterator<Item = X, Other = U> + Sized where X: Sized; }
+        impl<T> Foo<'static, T> for Option<T> where T: Iterator<Item=u32, Other=i64>, forall<'b> T: Foo<'b, T> { type Assoc<X> = T;  default type Other = &T; }
+        unsafe impl<T> const !Sized for Option<T> { }
+        impl<T> Option<T> { fn get(&self, u8) -> T; }
+        extern type Ext;
+        opaque type Opaque<T>: Sized + Iterator<Item = T> = Option<T>;
+        type Hidden = impl Sized;
+        hidden type Hidden = u32;
+        ",
+        "\
+#[upstream]
+#[lang(sized)]
+trait Sized { }
+trait Iterator where Self: Sized {
+    type Item: Sized;
+    fn next(&mut self) -> Option<Self::Item>;
+}
+struct Option<T> { value: T, other: (T,), unit: (), r: &'a mut T, g: &(for<'a> fn(&'a T)) }
+trait Foo<'a, U> {
+    type Assoc<X>: Iterator<Item = X, Other = U> + Sized where X: Sized;
+}
+impl<T> Foo<'static, T> for Option<T> where T: Iterator<Item = u32, Other = i64>, forall<'b> T: Foo<'b, T> {
+    type Assoc<X> = T;
+    default type Other = &T;
+}
+unsafe impl<T> const !Sized for Option<T> { }
+impl<T> Option<T> {
+    fn get(&self, u8) -> T;
+}
+extern type Ext;
+opaque type Opaque<T>: Sized + Iterator<Item = T> = Option<T>;
+type Hidden = impl Sized;
+hidden type Hidden = u32;
+",
+    );
+}
+
+#[test]
+fn format_goals_and_clauses() {
+    assert_formats(
+        "
+        predicate Reachable(type,lifetime);
+        forall<T> { Reachable(T, 'static) if T: Sized, (T = u32, u32: Sized) }
+        forall { Reveal }
+        goal g {
+            forall<T> {
+                if (T: Sized; forall<U> { U: Sized if U = T }) {
+                    exists<'a> { not { T: Foo<'a, T> }, or { T = u32; T: 'a, 'a: 'static } }
+                }
+            },
+            compatible { goal g }
+        }
+        goal x { WellFormed(u32: Sized), FromEnv(u32), Normalize(<u32 as Iterator>::Item -> u32), u32: ~const Sized }
+        ",
+        "\
+predicate Reachable(type, lifetime);
+forall<T> { Reachable(T, 'static) if T: Sized, (T = u32, u32: Sized) }
+forall { Reveal }
+goal g { forall<T> { if (T: Sized; forall<U> { U: Sized if U = T }) { exists<'a> { not { T: Foo<'a, T> }, or { T = u32; T: 'a, 'a: 'static } } } }, compatible { goal g } }
+goal x { WellFormed(u32: Sized), FromEnv(u32), Normalize(<u32 as Iterator>::Item -> u32), u32: ~const Sized }
+",
+    );
+}
+
+#[test]
+fn format_keeps_comments() {
+    let formatted = format_program(
+        "
+        // A comment before the first item.
+        struct Foo{}   // A trailing comment.
+
+
+        /* A block comment. */
+        mod m {
+            // A comment in a module.
+            struct Bar { }
+        }
+        struct Baz {
+            // A comment inside an item.
+        }
+        template Tmpl<type X> { impl Sized for X { } }
+        expand Tmpl<Foo>;
+        ",
+    )
+    .unwrap();
+    assert_eq!(
+        formatted,
+        "\
+// A comment before the first item.
+struct Foo { } // A trailing comment.
+
+/* A block comment. */
+mod m {
+    // A comment in a module.
+    struct Bar { }
+}
+struct Baz {
+    // A comment inside an item.
+}
+template Tmpl<type X> { impl Sized for X { } }
+expand Tmpl<Foo>;
+"
+    );
+}
+
+#[test]
+fn format_batch_goals() {
+    assert_eq!(
+        format_program(
+            "
+            struct Foo { }
+            goal {
+                exists<T> { T = Foo }
+            } //~ UNIQUE [?0 := Foo]
+            "
+        )
+        .unwrap(),
+        "\
+struct Foo { }
+goal { exists<T> { T = Foo } } //~ UNIQUE [?0 := Foo]
+"
+    );
+}
+
+#[test]
+fn format_errors() {
+    assert!(format_program("struct Foo {").is_err());
+    assert!(format_program("struct Foo { } goal { T: }").is_err());
+}
@@ -0,0 +1,46 @@
+//! Tests related to the generation of programs for fuzzing, which run
+//! the checks of the fuzz targets on a fixed set of inputs.
+
+use arbitrary::{Arbitrary, Unstructured};
+use chalk_integration::fuzz::FuzzProgram;
+
+/// The bytes of the input numbered `seed`, from a xorshift generator.
+fn input(seed: u64) -> Vec<u8> {
+    let mut state = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+    (0..512)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
+fn fuzz_program(seed: u64) -> FuzzProgram {
+    let input = input(seed);
+    FuzzProgram::arbitrary(&mut Unstructured::new(&input)).unwrap()
+}
+
+#[test]
+fn fuzz_programs_round_trip() {
+    for seed in 0..100 {
+        let text = fuzz_program(seed).text();
+        let reparsed = chalk_parse::parse_program(&text).unwrap();
+        assert_eq!(chalk_parse::print_program(&reparsed), text);
+    }
+}
+
+#[test]
+fn fuzz_programs_lower() {
+    for seed in 0..100 {
+        fuzz_program(seed).check_lowering();
+    }
+}
+
+#[test]
+fn fuzz_solvers_agree() {
+    for seed in 0..100 {
+        fuzz_program(seed).check_solvers();
+    }
+}
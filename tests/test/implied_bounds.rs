@@ -157,3 +157,63 @@ fn higher_ranked_implied_bounds() {
         }
     }
 }
+
+#[test]
+fn super_trait_from_env() {
+    test! {
+        program {
+            trait PartialOrd { }
+            trait Ord where Self: PartialOrd { }
+            struct Foo { }
+        }
+
+        // `T: Ord` in the environment gives `FromEnv(T: Ord)`, from
+        // which we get `FromEnv(T: PartialOrd)` and so `T: PartialOrd`.
+        goal {
+            forall<T> {
+                if (T: Ord) {
+                    T: PartialOrd
+                }
+            }
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            forall<T> {
+                if (FromEnv(T: Ord)) {
+                    FromEnv(T: PartialOrd)
+                }
+            }
+        } yields {
+            "Unique"
+        }
+
+        // Implied bounds go from sub trait to super trait only.
+        goal {
+            forall<T> {
+                if (T: PartialOrd) {
+                    T: Ord
+                }
+            }
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            forall<T> {
+                if (T: Ord) {
+                    FromEnv(T: Ord)
+                }
+            }
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            FromEnv(Foo: Ord)
+        } yields {
+            "No possible solution"
+        }
+    }
+}
@@ -637,5 +637,56 @@ fn clauses_in_if_goals() {
         } yields {
             "No possible solution"
         }
+
+        goal {
+            if (forall<T> { Vec<T>: Foo if T: Foo }; A: Foo) {
+                Vec<Vec<A>>: Foo
+            }
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            forall<T> {
+                if (Vec<T>: Foo if T: Foo) {
+                    Vec<T>: Foo
+                }
+            }
+        } yields {
+            "No possible solution"
+        }
+    }
+}
+
+#[test]
+fn self_in_impl() {
+    test! {
+        program {
+            trait Clone { }
+            trait Iterator { type Item; }
+            struct Vec<T> { }
+            struct Foo { }
+            impl Clone for Foo { }
+            impl<T> Iterator for Vec<T> where Self: Clone { type Item = Self; }
+            impl Clone for Vec<Foo> { }
+        }
+
+        goal {
+            Vec<Foo>: Iterator
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            Vec<u32>: Iterator
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            exists<T> { Normalize(<Vec<Foo> as Iterator>::Item -> T) }
+        } yields {
+            "Unique; substitution [?0 := Vec<Foo>]"
+        }
     }
 }
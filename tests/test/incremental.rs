@@ -0,0 +1,167 @@
+//! Tests related to adding and removing impls between solving goals.
+
+use super::*;
+use chalk_integration::Identifier;
+use chalk_ir::{ApplicationTy, ImplId, TraitId, TyData, TypeName};
+use chalk_solve::trace::TraceEvent;
+use chalk_solve::Solver;
+use std::sync::{Arc, Mutex};
+
+const PROGRAM: &str = "
+    struct Vec<T> { }
+    struct Foo { }
+    struct Bar { }
+    trait Clone { }
+    trait Copy { }
+    trait Debug { }
+    impl Clone for Foo { }
+    impl Clone for Bar { }
+    impl Copy for Bar { }
+    impl<T> Clone for Vec<T> where T: Clone { }
+    impl<T> Debug for Vec<T> where T: Clone { }
+";
+
+/// A program solved by a single solver, whose impls change between
+/// goals.
+struct Incremental {
+    db: ChalkDatabase,
+    program: Arc<Program>,
+    solver: Solver<ChalkIr>,
+    events: Arc<Mutex<Vec<String>>>,
+}
+
+impl Incremental {
+    fn new(solver_choice: SolverChoice) -> Self {
+        let db = ChalkDatabase::with(PROGRAM, solver_choice);
+        let program = db.checked_program().unwrap();
+        let events = Arc::new(Mutex::new(vec![]));
+        let sink_events = events.clone();
+        let mut solver = solver_choice.into_solver();
+        solver.set_trace_sink(Some(Box::new(move |event: TraceEvent<ChalkIr>| {
+            sink_events
+                .lock()
+                .unwrap()
+                .push(event.display(&ChalkIr).to_string())
+        })));
+        Incremental {
+            db,
+            program,
+            solver,
+            events,
+        }
+    }
+
+    fn trait_id(&self, name: &str) -> TraitId<ChalkIr> {
+        self.program.trait_ids[&Identifier::from(name)]
+    }
+
+    /// The impl of the trait `trait_name` for the struct `self_name`.
+    fn impl_id(&self, trait_name: &str, self_name: &str) -> ImplId<ChalkIr> {
+        let trait_id = self.trait_id(trait_name);
+        let struct_id = self.program.struct_ids[&Identifier::from(self_name)];
+        self.program
+            .impls_of_trait(trait_id)
+            .find(|impl_id| {
+                let impl_datum = &self.program.impl_data[impl_id];
+                let trait_ref = &impl_datum.binders.skip_binders().trait_ref;
+                match trait_ref.self_type_parameter(&ChalkIr).data(&ChalkIr) {
+                    TyData::Apply(ApplicationTy {
+                        name: TypeName::Struct(id),
+                        ..
+                    }) => *id == struct_id,
+                    _ => false,
+                }
+            })
+            .unwrap()
+    }
+
+    /// Solves `goal`, returning its printed solution and whether its
+    /// solution was cached, or for the SLG solver, whether its table was
+    /// reused.
+    fn solve(&mut self, goal: &str) -> (String, bool) {
+        let program = &self.program;
+        let solver = &mut self.solver;
+        let goal = lower_goal(&self.db, program, goal);
+        let solution = chalk_integration::tls::set_current_program(program, || {
+            display_solution(solver.solve(&**program, &goal))
+        });
+        let events: Vec<_> = self.events.lock().unwrap().drain(..).collect();
+        let cached = events.first().map_or(false, |event| {
+            event.starts_with("cache hit") || event.starts_with("table reused")
+        });
+        (solution, cached)
+    }
+}
+
+const UNIQUE: &str = "Unique; substitution [], lifetime constraints []";
+
+/// Removes the impl `Clone for Bar` and adds it back, solving goals
+/// with `solver_choice` in between.
+fn remove_and_add_impl(solver_choice: SolverChoice) {
+    let mut incremental = Incremental::new(solver_choice);
+    let clone = incremental.trait_id("Clone");
+    let impl_id = incremental.impl_id("Clone", "Bar");
+    let (impl_datum, values) = Arc::make_mut(&mut incremental.program)
+        .remove_impl(impl_id)
+        .unwrap();
+    incremental
+        .solver
+        .invalidate_traits(&*incremental.program, &[clone]);
+
+    let goals = [
+        "Vec<Bar>: Clone",
+        "Vec<Bar>: Debug",
+        "Bar: Copy",
+        "Foo: Clone",
+    ];
+    let solutions: Vec<_> = goals.iter().map(|goal| incremental.solve(goal)).collect();
+    assert_eq!(solutions[0], ("No possible solution".to_string(), false));
+    assert_eq!(solutions[1], ("No possible solution".to_string(), false));
+    assert_eq!(solutions[2], (UNIQUE.to_string(), false));
+    assert_eq!(solutions[3], (UNIQUE.to_string(), false));
+
+    let trait_id = Arc::make_mut(&mut incremental.program).add_impl(impl_id, impl_datum, values);
+    assert_eq!(trait_id, clone);
+    incremental
+        .solver
+        .invalidate_traits(&*incremental.program, &[trait_id]);
+
+    // Only the solutions that depend on the impls of `Clone` are
+    // solved again, including `Vec<Bar>: Debug` through the where
+    // clause of its impl.
+    let solutions: Vec<_> = goals.iter().map(|goal| incremental.solve(goal)).collect();
+    assert_eq!(solutions[0], (UNIQUE.to_string(), false));
+    assert_eq!(solutions[1], (UNIQUE.to_string(), false));
+    assert_eq!(solutions[2], (UNIQUE.to_string(), true));
+    assert_eq!(solutions[3], (UNIQUE.to_string(), false));
+}
+
+#[test]
+fn remove_and_add_impl_recursive() {
+    remove_and_add_impl(SolverChoice::recursive());
+}
+
+#[test]
+fn remove_and_add_impl_slg() {
+    // The table of `Bar: Copy` survives, while the tables of the goals
+    // about `Clone`, and of `Vec<Bar>: Debug`, which selected
+    // `Bar: Clone` as a subgoal, are created again.
+    remove_and_add_impl(SolverChoice::slg_default());
+}
+
+#[test]
+fn remove_impl_slg() {
+    let mut incremental = Incremental::new(SolverChoice::slg_default());
+    let clone = incremental.trait_id("Clone");
+    assert_eq!(incremental.solve("Vec<Bar>: Clone").0, UNIQUE);
+
+    let impl_id = incremental.impl_id("Clone", "Bar");
+    Arc::make_mut(&mut incremental.program).remove_impl(impl_id);
+    incremental
+        .solver
+        .invalidate_traits(&*incremental.program, &[clone]);
+    assert_eq!(
+        incremental.solve("Vec<Bar>: Clone").0,
+        "No possible solution"
+    );
+}
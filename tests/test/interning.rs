@@ -0,0 +1,86 @@
+//! Tests related to the interning of types by `ChalkIr`, and to the
+//! flags it computes for them.
+
+use super::*;
+use chalk_ir::flags::TypeFlags;
+use chalk_ir::{
+    AliasTy, ApplicationTy, BoundVar, DebruijnIndex, InferenceVar, ProjectionTy, Substitution,
+    TyData, TypeName,
+};
+
+#[test]
+fn equal_types_share_allocation() {
+    let db = ChalkDatabase::with("struct Vec<T> { } struct Foo { }", SolverChoice::default());
+    let program = db.checked_program().unwrap();
+    let interner = &ChalkIr;
+    let vec_id = program.struct_ids[&"Vec".into()];
+    let foo_id = program.struct_ids[&"Foo".into()];
+
+    let ty = |id, args: Vec<_>| {
+        TyData::Apply(ApplicationTy {
+            name: TypeName::Struct(id),
+            substitution: Substitution::from(interner, args),
+        })
+        .intern(interner)
+    };
+    let a = ty(vec_id, vec![ty(foo_id, vec![])]);
+    let b = ty(vec_id, vec![ty(foo_id, vec![])]);
+    let c = ty(vec_id, vec![ty(vec_id, vec![ty(foo_id, vec![])])]);
+
+    assert_eq!(a, b);
+    assert!(std::ptr::eq(a.data(interner), b.data(interner)));
+    assert_ne!(a, c);
+}
+
+#[test]
+fn type_flags() {
+    let db = ChalkDatabase::with(
+        "struct Vec<T> { } struct Foo { } trait Iterator { type Item; }",
+        SolverChoice::default(),
+    );
+    let program = db.checked_program().unwrap();
+    let interner = &ChalkIr;
+    let vec_id = program.struct_ids[&"Vec".into()];
+    let foo_id = program.struct_ids[&"Foo".into()];
+    let item_id = *program.associated_ty_data.keys().next().unwrap();
+
+    let vec = |arg| {
+        TyData::Apply(ApplicationTy {
+            name: TypeName::Struct(vec_id),
+            substitution: Substitution::from(interner, vec![arg]),
+        })
+        .intern(interner)
+    };
+    let foo = TyData::Apply(ApplicationTy {
+        name: TypeName::Struct(foo_id),
+        substitution: Substitution::empty(interner),
+    })
+    .intern(interner);
+    let var = TyData::InferenceVar(InferenceVar::from(0)).intern(interner);
+    let bound = TyData::BoundVar(BoundVar::new(DebruijnIndex::INNERMOST, 0)).intern(interner);
+    let item = |arg| {
+        TyData::Alias(AliasTy::Projection(ProjectionTy {
+            associated_ty_id: item_id,
+            substitution: Substitution::from(interner, vec![arg]),
+        }))
+        .intern(interner)
+    };
+
+    assert_eq!(vec(foo.clone()).flags(interner), TypeFlags::EMPTY);
+    assert_eq!(
+        vec(vec(var.clone())).flags(interner),
+        TypeFlags::HAS_INFERENCE_VARS
+    );
+    assert_eq!(
+        vec(item(bound.clone())).flags(interner),
+        TypeFlags::HAS_PROJECTIONS | TypeFlags::HAS_BOUND_VARS
+    );
+    assert_eq!(item(foo).flags(interner), TypeFlags::HAS_PROJECTIONS);
+
+    // The flags cached by `ChalkIr` are the ones computed from scratch.
+    let ty = vec(item(var));
+    assert_eq!(
+        ty.flags(interner),
+        ty.data(interner).compute_flags(interner)
+    );
+}
@@ -0,0 +1,97 @@
+//! Tests related to the token stream used for syntax highlighting.
+
+use chalk_parse::{tokenize, TokenKind};
+
+fn tokens(text: &str) -> Vec<(TokenKind, &str)> {
+    tokenize(text)
+        .into_iter()
+        .map(|token| {
+            assert_eq!(&text[token.span.lo..token.span.hi], token.text);
+            (token.kind, token.text)
+        })
+        .collect()
+}
+
+#[test]
+fn token_kinds() {
+    use TokenKind::*;
+    assert_eq!(
+        tokens("#[lang(sized)] struct Ref<'a, T> { x: &'a [u8; 3] } // ok"),
+        vec![
+            (Punctuation, "#"),
+            (Punctuation, "["),
+            (Identifier, "lang"),
+            (Punctuation, "("),
+            (Identifier, "sized"),
+            (Punctuation, ")"),
+            (Punctuation, "]"),
+            (Keyword, "struct"),
+            (Identifier, "Ref"),
+            (Punctuation, "<"),
+            (Lifetime, "'a"),
+            (Punctuation, ","),
+            (Identifier, "T"),
+            (Punctuation, ">"),
+            (Punctuation, "{"),
+            (Identifier, "x"),
+            (Punctuation, ":"),
+            (Punctuation, "&"),
+            (Lifetime, "'a"),
+            (Punctuation, "["),
+            (Keyword, "u8"),
+            (Punctuation, ";"),
+            (Number, "3"),
+            (Punctuation, "]"),
+            (Punctuation, "}"),
+            (Comment, "// ok"),
+        ]
+    );
+
+    // Words are lexed as the longest token they match, so that `forall`
+    // is a keyword, `foralls` an identifier and `std::Foo` a single path.
+    assert_eq!(
+        tokens("forall<T> { WellFormed(std::Foo: T) } foralls -> 'static"),
+        vec![
+            (Keyword, "forall"),
+            (Punctuation, "<"),
+            (Identifier, "T"),
+            (Punctuation, ">"),
+            (Punctuation, "{"),
+            (Keyword, "WellFormed"),
+            (Punctuation, "("),
+            (Identifier, "std::Foo"),
+            (Punctuation, ":"),
+            (Identifier, "T"),
+            (Punctuation, ")"),
+            (Punctuation, "}"),
+            (Identifier, "foralls"),
+            (Punctuation, "->"),
+            (Lifetime, "'static"),
+        ]
+    );
+}
+
+#[test]
+fn token_spans() {
+    use TokenKind::*;
+    let text = "trait /* a /* nested */ comment */ Föö\n  @ { } /* open";
+    let tokens = tokenize(text);
+    let spans: Vec<_> = tokens
+        .iter()
+        .map(|token| (token.kind, token.span.lo, token.span.hi))
+        .collect();
+    assert_eq!(
+        spans,
+        vec![
+            (Keyword, 0, 5),
+            (Comment, 6, 34),
+            (Identifier, 35, 36),
+            (Unknown, 36, 38),
+            (Unknown, 38, 40),
+            (Unknown, 43, 44),
+            (Punctuation, 45, 46),
+            (Punctuation, 47, 48),
+            (Comment, 49, 56),
+        ]
+    );
+}
@@ -0,0 +1,159 @@
+//! Tests related to the language server.
+
+use chalk_integration::lsp::{read_message, run, write_message, Server};
+use serde_json::{json, Value};
+
+const URI: &str = "file:///test.chalk";
+
+const PROGRAM: &str = "struct Foo { }
+trait Bar { type Item; }
+impl Bar for Foo { type Item = Foo; }
+mod m { struct Foo { } }
+";
+
+fn open(server: &mut Server, text: &str) -> Value {
+    let mut replies = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": { "textDocument": { "uri": URI, "languageId": "chalk", "version": 1, "text": text } },
+    }));
+    assert_eq!(replies.len(), 1);
+    let reply = replies.remove(0);
+    assert_eq!(reply["method"], "textDocument/publishDiagnostics");
+    reply["params"]["diagnostics"].clone()
+}
+
+/// Sends the request `method` about the position `line:character`,
+/// returning its result.
+fn request(server: &mut Server, method: &str, line: usize, character: usize) -> Value {
+    let replies = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": method,
+        "params": {
+            "textDocument": { "uri": URI },
+            "position": { "line": line, "character": character },
+        },
+    }));
+    assert_eq!(replies.len(), 1);
+    assert_eq!(replies[0]["id"], 1);
+    replies[0]["result"].clone()
+}
+
+fn range(start: (usize, usize), end: (usize, usize)) -> Value {
+    json!({
+        "start": { "line": start.0, "character": start.1 },
+        "end": { "line": end.0, "character": end.1 },
+    })
+}
+
+#[test]
+fn lsp_diagnostics() {
+    let mut server = Server::new();
+
+    let diagnostics = open(&mut server, "struct Foo {\n    x: Qux }");
+    assert_eq!(diagnostics.as_array().unwrap().len(), 1);
+    assert_eq!(diagnostics[0]["message"], "invalid type name `Qux`");
+    assert_eq!(diagnostics[0]["range"], range((1, 7), (1, 10)));
+
+    // All the errors of lowering are reported.
+    let diagnostics = open(
+        &mut server,
+        "struct Foo {\n    x: Qux }\nimpl Baz for Foo { }",
+    );
+    assert_eq!(diagnostics.as_array().unwrap().len(), 2);
+    assert_eq!(diagnostics[1]["message"], "invalid type name `Baz`");
+    assert_eq!(diagnostics[1]["range"], range((2, 5), (2, 8)));
+
+    let diagnostics = open(&mut server, "struct Foo {\n impl");
+    assert!(diagnostics[0]["message"]
+        .as_str()
+        .unwrap()
+        .starts_with("parse error: unexpected token `impl`"));
+    assert_eq!(diagnostics[0]["range"], range((1, 1), (1, 5)));
+
+    // Errors found after lowering are reported at the start.
+    let diagnostics = open(
+        &mut server,
+        "trait Foo { }\nstruct Bar { }\nimpl Foo for Bar { }\nimpl Foo for Bar { }",
+    );
+    assert_eq!(diagnostics[0]["range"], range((0, 0), (0, 0)));
+
+    assert_eq!(open(&mut server, PROGRAM), json!([]));
+}
+
+#[test]
+fn lsp_definition() {
+    let mut server = Server::new();
+    open(&mut server, PROGRAM);
+
+    // `Foo` in `impl Bar for Foo` is the top-level struct.
+    assert_eq!(
+        request(&mut server, "textDocument/definition", 2, 15),
+        json!([{ "uri": URI, "range": range((0, 7), (0, 10)) }])
+    );
+    // `Item` in the impl is the associated type of the trait.
+    assert_eq!(
+        request(&mut server, "textDocument/definition", 2, 25),
+        json!([{ "uri": URI, "range": range((1, 17), (1, 21)) }])
+    );
+    // There is no item at a brace.
+    assert_eq!(
+        request(&mut server, "textDocument/definition", 0, 11),
+        Value::Null
+    );
+}
+
+#[test]
+fn lsp_hover() {
+    let mut server = Server::new();
+    open(&mut server, PROGRAM);
+
+    let hover = request(&mut server, "textDocument/hover", 1, 7);
+    let contents = hover["contents"]["value"].as_str().unwrap();
+    assert!(contents.starts_with("```\nTypeKind {\n    sort: Trait,"));
+    assert!(contents.contains("TraitDatum {\n    id: Bar,"));
+    assert!(contents.contains("impl(Foo: Bar) => ImplDatum {"));
+    assert_eq!(hover["range"], range((1, 6), (1, 9)));
+
+    assert_eq!(
+        request(&mut server, "textDocument/hover", 1, 1),
+        Value::Null
+    );
+}
+
+#[test]
+fn lsp_run() {
+    let mut input = vec![];
+    for message in &[
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }),
+        json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} }),
+        json!({ "jsonrpc": "2.0", "id": 2, "method": "textDocument/completion", "params": {} }),
+        json!({ "jsonrpc": "2.0", "id": 3, "method": "shutdown" }),
+        json!({ "jsonrpc": "2.0", "method": "exit" }),
+        json!({ "jsonrpc": "2.0", "id": 4, "method": "shutdown" }),
+    ] {
+        write_message(&mut input, message).unwrap();
+    }
+
+    let mut output = vec![];
+    run(&input[..], &mut output).unwrap();
+
+    let mut output = &output[..];
+    let initialize = read_message(&mut output).unwrap().unwrap();
+    assert_eq!(initialize["id"], 1);
+    assert_eq!(
+        initialize["result"]["capabilities"]["definitionProvider"],
+        true
+    );
+    let completion = read_message(&mut output).unwrap().unwrap();
+    assert_eq!(completion["error"]["code"], -32601);
+    let shutdown = read_message(&mut output).unwrap().unwrap();
+    assert_eq!(
+        shutdown,
+        json!({ "jsonrpc": "2.0", "id": 3, "result": null })
+    );
+
+    // Nothing is handled after `exit`.
+    assert_eq!(read_message(&mut output).unwrap(), None);
+}
@@ -0,0 +1,260 @@
+//! Tests for method probing on top of inherent impls.
+
+use super::*;
+
+fn probe(program: &str, probe: &str) -> String {
+    let db = ChalkDatabase::with(program, SolverChoice::default());
+    match db.probe_method(probe) {
+        Ok(pick) => db.with_program(|_| pick.to_string()),
+        Err(error) => error.to_string(),
+    }
+}
+
+const PROGRAM: &str = "
+    #[lang(deref)]
+    trait Deref { type Target; }
+    trait Clone { }
+
+    struct Foo { }
+    struct Bar { }
+    struct Box<T> { }
+    struct Wrapper<T> { }
+
+    impl<T> Deref for Box<T> { type Target = T; }
+    impl Clone for Foo { }
+
+    impl Foo {
+        fn new() -> Foo;
+        fn by_ref(&self);
+        fn by_mut(&mut self, u32);
+        fn shadowed(&self);
+    }
+
+    impl Foo {
+        fn by_value(self) -> u32;
+        fn shadowed(self);
+    }
+
+    impl<T> Box<T> {
+        fn shadowed(&self);
+    }
+
+    impl<T> Wrapper<T> where T: Clone {
+        fn get(&self) -> T;
+    }
+
+    impl Bar {
+        fn ambiguous(&self);
+    }
+
+    impl Bar {
+        fn ambiguous(&self);
+    }
+";
+
+#[test]
+fn method_receivers() {
+    assert_eq!(
+        probe(PROGRAM, "Foo.by_value()"),
+        "impl for `Foo`, autoderefs: 0, autoref: none"
+    );
+    assert_eq!(
+        probe(PROGRAM, "Foo.by_ref()"),
+        "impl for `Foo`, autoderefs: 0, autoref: &"
+    );
+    assert_eq!(
+        probe(PROGRAM, "Foo.by_mut()"),
+        "impl for `Foo`, autoderefs: 0, autoref: &mut"
+    );
+}
+
+#[test]
+fn method_receiver_types() {
+    // A `&self` method is found on a reference without autoref.
+    assert_eq!(
+        probe(PROGRAM, "&Foo.by_ref()"),
+        "impl for `Foo`, autoderefs: 0, autoref: none"
+    );
+    assert_eq!(
+        probe(PROGRAM, "&'static Foo.by_ref()"),
+        "impl for `Foo`, autoderefs: 0, autoref: none"
+    );
+    assert_eq!(
+        probe(PROGRAM, "&mut Foo.by_mut()"),
+        "impl for `Foo`, autoderefs: 0, autoref: none"
+    );
+
+    // References are dereferenced without a `Deref` impl.
+    assert_eq!(
+        probe(PROGRAM, "&&Foo.by_ref()"),
+        "impl for `Foo`, autoderefs: 1, autoref: none"
+    );
+    assert_eq!(
+        probe(PROGRAM, "&Foo.by_value()"),
+        "impl for `Foo`, autoderefs: 1, autoref: none"
+    );
+    assert_eq!(
+        probe(PROGRAM, "&Foo.by_mut()"),
+        "impl for `Foo`, autoderefs: 1, autoref: &mut"
+    );
+}
+
+#[test]
+fn method_by_value_before_autoref() {
+    assert_eq!(
+        probe(PROGRAM, "Foo.shadowed()"),
+        "impl for `Foo`, autoderefs: 0, autoref: none"
+    );
+}
+
+#[test]
+fn method_autoderef() {
+    assert_eq!(
+        probe(PROGRAM, "Box<Foo>.by_ref()"),
+        "impl for `Foo`, autoderefs: 1, autoref: &"
+    );
+    assert_eq!(
+        probe(PROGRAM, "Box<Box<Foo>>.by_value()"),
+        "impl for `Foo`, autoderefs: 2, autoref: none"
+    );
+
+    // The method on `Box<T>` is found before derefencing to `Foo`.
+    assert_eq!(
+        probe(PROGRAM, "Box<Foo>.shadowed()"),
+        "impl for `Box<Foo>`, autoderefs: 0, autoref: &"
+    );
+}
+
+#[test]
+fn method_where_clauses() {
+    assert_eq!(
+        probe(PROGRAM, "Wrapper<Foo>.get()"),
+        "impl for `Wrapper<Foo>`, autoderefs: 0, autoref: &"
+    );
+    assert_eq!(
+        probe(PROGRAM, "Wrapper<Bar>.get()"),
+        "no method named `get` found for `Wrapper<Bar>`"
+    );
+}
+
+#[test]
+fn method_errors() {
+    // Associated functions cannot be called with method syntax.
+    assert_eq!(
+        probe(PROGRAM, "Foo.new()"),
+        "no method named `new` found for `Foo`"
+    );
+    assert_eq!(
+        probe(PROGRAM, "Box<Bar>.missing()"),
+        "no method named `missing` found for `Box<Bar>`"
+    );
+    assert_eq!(
+        probe(PROGRAM, "Bar.ambiguous()"),
+        "multiple applicable methods named `ambiguous` found for `Bar`"
+    );
+}
+
+#[test]
+fn method_goals() {
+    test! {
+        program {
+            #[lang(deref)]
+            trait Deref { type Target; }
+            trait Clone { }
+
+            struct Foo { }
+            struct Bar { }
+            struct Box<T> { }
+            struct Wrapper<T> { }
+
+            impl<T> Deref for Box<T> { type Target = T; }
+            impl Clone for Foo { }
+
+            impl Foo {
+                fn new() -> Foo;
+                fn by_ref(&self);
+            }
+
+            impl<T> Wrapper<T> where T: Clone {
+                fn get(&self) -> T;
+            }
+
+            impl Bar {
+                fn ambiguous(&self);
+            }
+
+            impl Bar {
+                fn ambiguous(&self);
+            }
+        }
+
+        goal {
+            Foo.by_ref()
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            Box<Box<Foo>>.by_ref()
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            &Box<&mut Foo>.by_ref()
+        } yields {
+            "Unique"
+        }
+
+        // Associated functions cannot be called with method syntax.
+        goal {
+            Foo.new()
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            Box<Bar>.missing()
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            Wrapper<Bar>.get()
+        } yields {
+            "No possible solution"
+        }
+
+        // Unlike the probe, the goal does not tell methods apart.
+        goal {
+            Bar.ambiguous()
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            exists<T> { T = Box<Wrapper<Foo>>, T.get() }
+        } yields {
+            "Unique; substitution [?0 := Box<Wrapper<Foo>>]"
+        }
+
+        // `T` could be `Foo`, `Box<Foo>` and so on.
+        goal {
+            exists<T> { Box<T>.by_ref() }
+        } yields {
+            "Ambiguous; no inference guidance"
+        }
+
+        goal {
+            forall<T> { if (T: Clone) { Box<Wrapper<T>>.get() } }
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            forall<T> { Wrapper<T>.get() }
+        } yields {
+            "No possible solution"
+        }
+    }
+}
@@ -1,6 +1,9 @@
 //! Tests that don't fit a single category
 
 use super::*;
+use chalk_solve::trace::TraceEvent;
+use chalk_solve::Guidance;
+use std::sync::{Arc, Mutex};
 
 // Regression test for rust-lang/chalk#111
 #[test]
@@ -164,6 +167,47 @@ fn only_draw_so_many_blow_up() {
     }
 }
 
+#[test]
+fn guidance_from_distinct_impls() {
+    test! {
+        program {
+            struct Vec<T> { }
+            struct Pair<T, U> { }
+            struct A { }
+            struct B { }
+
+            trait Foo { }
+            impl Foo for Vec<A> { }
+            impl Foo for Vec<B> { }
+
+            trait Bar { }
+            impl Bar for Pair<A, B> { }
+            impl Bar for Pair<B, B> { }
+        }
+
+        // The impls disagree on the type argument of `Vec`, but agree
+        // that `T` must be a `Vec`.
+        goal {
+            exists<T> { T: Foo }
+        } yields {
+            "Ambiguous; definite substitution for<?U0> { [?0 := Vec<^0.0>] }"
+        }
+
+        goal {
+            exists<T, U> { Pair<T, U>: Bar }
+        } yields {
+            "Ambiguous; definite substitution for<?U0> { [?0 := ^0.0, ?1 := B] }"
+        }
+
+        // Nothing is known about `T` beyond what the goal says.
+        goal {
+            exists<T> { Vec<T>: Foo }
+        } yields {
+            "Ambiguous; no inference guidance"
+        }
+    }
+}
+
 #[test]
 fn subgoal_cycle_uninhabited() {
     test! {
@@ -515,3 +559,232 @@ fn non_enumerable_traits_reorder() {
         }
     }
 }
+
+#[test]
+fn goal_definitions() {
+    test! {
+        program {
+            trait Foo { }
+            struct A { }
+            struct B<T> { }
+
+            impl Foo for A { }
+            impl<T> Foo for B<T> where T: Foo { }
+
+            goal a_is_foo { A: Foo }
+            goal all_foo { forall<T> { if (T: Foo) { B<T>: Foo } } }
+            goal both { goal a_is_foo, goal all_foo }
+
+            mod nested {
+                goal b_is_foo { B<A>: Foo }
+            }
+        }
+
+        goal {
+            goal a_is_foo
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            goal both
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            not { goal nested::b_is_foo }
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            forall<T> { if (T: Foo) { goal all_foo, B<B<T>>: Foo } }
+        } yields {
+            "Unique"
+        }
+    }
+}
+
+#[test]
+fn templates() {
+    test! {
+        program {
+            struct A { }
+            struct B<T> { }
+            trait Foo { }
+            trait Bar { }
+
+            template Implemented<trait Tr, type Ty> {
+                impl Tr for Ty { }
+                impl<X> Tr for B<X> where X: Tr { }
+            }
+
+            mod foo {
+                expand Implemented<Foo, A>;
+            }
+            expand Implemented<Bar, u32>;
+        }
+
+        goal {
+            B<B<A>>: Foo
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            B<u32>: Bar
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            A: Bar
+        } yields {
+            "No possible solution"
+        }
+    }
+}
+
+#[test]
+fn solve_multiple_recursive() {
+    // The recursive solver yields the answer of each program clause of a
+    // goal, and of each alternative of a disjunction, once, rather than
+    // the solution that combines them.
+    test! {
+        program {
+            struct A { }
+            struct B { }
+            struct Vec<T> { }
+            struct S<'a> { }
+            struct X { }
+            trait Foo { }
+            trait Bar { }
+            trait Baz { }
+            impl Foo for A { }
+            impl Foo for B { }
+            impl Bar for Vec<A> { }
+            impl Bar for Vec<B> { }
+
+            forall<'a> { S<'a>: Baz if 'a: 'static }
+            forall<'a> { S<'a>: Baz if X: 'a }
+        }
+
+        goal {
+            exists<T> { T: Foo }
+        } yields_all[SolverChoice::recursive()] {
+            "substitution [?0 := A], lifetime constraints []",
+            "substitution [?0 := B], lifetime constraints []"
+        }
+
+        goal {
+            exists<T> { T: Bar }
+        } yields_all[SolverChoice::recursive()] {
+            "substitution [?0 := Vec<A>], lifetime constraints []",
+            "substitution [?0 := Vec<B>], lifetime constraints []"
+        }
+
+        goal {
+            exists<T> { or { T = A; T: Foo } }
+        } yields_all[SolverChoice::recursive()] {
+            "substitution [?0 := A], lifetime constraints []",
+            "substitution [?0 := B], lifetime constraints []"
+        }
+
+        goal {
+            exists<T> { T = A, T: Foo }
+        } yields_all[SolverChoice::recursive()] {
+            "substitution [?0 := A], lifetime constraints []"
+        }
+
+        goal {
+            forall<'a> { S<'a>: Baz }
+        } yields_all[SolverChoice::recursive()] {
+            "substitution [], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: 'static }]",
+            "substitution [], lifetime constraints [InEnvironment { environment: Env([]), goal: X: '!1_0 }]"
+        }
+
+        goal {
+            forall<T> { T: Foo }
+        } yields_all[SolverChoice::recursive()] {
+        }
+    }
+}
+
+#[test]
+fn solution_flavors() {
+    // A goal has a unique solution, an ambiguous one (with or without
+    // guidance), or none, whichever the solver.
+    let program = "
+        struct A { }
+        struct B { }
+        struct Vec<T> { }
+        trait Foo { }
+        impl Foo for A { }
+        impl Foo for B { }
+        impl<T> Foo for Vec<T> where T: Foo { }
+    ";
+    let goals = [
+        "exists<T> { T = A, T: Foo }",
+        "exists<T> { Vec<T>: Foo }",
+        "forall<T> { T: Foo }",
+    ];
+    for &solver_choice in &[SolverChoice::slg_default(), SolverChoice::recursive()] {
+        with_lowered_goals(program, solver_choice, &goals, |db, goals| {
+            let solution = db.solve(&goals[0]).unwrap();
+            assert!(solution.is_unique());
+            assert!(!solution.is_ambig());
+
+            match db.solve(&goals[1]) {
+                Some(Solution::Ambig(Guidance::Unknown)) => {}
+                solution => panic!("expected an ambiguous solution, got {:?}", solution),
+            }
+
+            assert_eq!(db.solve(&goals[2]), None);
+        });
+    }
+}
+
+#[test]
+fn recursive_answers_are_streamed() {
+    // Each answer of the recursive solver is passed on as soon as the
+    // next one is found, before the clauses of the answers after it are
+    // tried, and none are tried once no more answers are wanted.
+    let program = "
+        struct A { }
+        struct B { }
+        struct C { }
+        trait Foo { }
+        impl Foo for A { }
+        impl Foo for B { }
+        impl Foo for C { }
+    ";
+    let goals = ["exists<T> { T: Foo }"];
+    with_lowered_goals(program, SolverChoice::recursive(), &goals, |db, goals| {
+        let events = Arc::new(Mutex::new(vec![]));
+        let sink_events = events.clone();
+        db.set_trace_sink(Some(Box::new(move |event: TraceEvent<ChalkIr>| {
+            if let TraceEvent::ClauseTried { clause, .. } = event {
+                sink_events.lock().unwrap().push(format!("{:?}", clause));
+            }
+        })));
+
+        let mut answers = vec![];
+        let all = db.solve_multiple(&goals[0], |answer, has_next| {
+            let answer = answer.as_ref().map(|v| v.display(&ChalkIr)).to_string();
+            answers.push((answer, has_next));
+            false
+        });
+        assert!(!all);
+        assert_eq!(
+            answers,
+            vec![(
+                "substitution [?0 := A], lifetime constraints []".to_string(),
+                true
+            )]
+        );
+        let events = events.lock().unwrap();
+        assert!(events.contains(&"for<> Implemented(B: Foo)".to_string()));
+        assert!(!events.contains(&"for<> Implemented(C: Foo)".to_string()));
+    });
+}
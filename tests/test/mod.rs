@@ -3,7 +3,9 @@
 use chalk_integration::db::ChalkDatabase;
 use chalk_integration::interner::ChalkIr;
 use chalk_integration::lowering::LowerGoal;
+use chalk_integration::program::Program;
 use chalk_integration::query::LoweringDatabase;
+use chalk_ir::{Goal, InEnvironment, UCanonical};
 use chalk_solve::ext::*;
 use chalk_solve::RustIrDatabase;
 use chalk_solve::{Solution, SolverChoice};
@@ -13,7 +15,11 @@ mod bench;
 mod coherence;
 mod wf_lowering;
 
-fn assert_result(mut result: Option<Solution<ChalkIr>>, expected: &str) {
+fn assert_result(result: Option<Solution<ChalkIr>>, expected: &str) {
+    assert_solution(result, expected, false);
+}
+
+fn assert_solution(mut result: Option<Solution<ChalkIr>>, expected: &str, exact: bool) {
     // sort constraints, since the different solvers may output them in different order
     match &mut result {
         Some(Solution::Unique(solution)) => {
@@ -24,27 +30,75 @@ fn assert_result(mut result: Option<Solution<ChalkIr>>, expected: &str) {
         }
         _ => {}
     }
-    let result = match result {
-        Some(v) => format!("{}", v.display(&ChalkIr)),
-        None => format!("No possible solution"),
-    };
+    assert_matches(&display_solution(result), expected, exact);
+}
 
-    assert_same(&result, expected);
+/// The printed `solution`, as the `test!` macro expects it.
+fn display_solution(solution: Option<Solution<ChalkIr>>) -> String {
+    match solution {
+        Some(solution) => solution.display(&ChalkIr).to_string(),
+        None => "No possible solution".to_string(),
+    }
 }
 
 fn assert_same(result: &str, expected: &str) {
-    println!("expected:\n{}", expected);
-    println!("actual:\n{}", result);
+    assert_matches(result, expected, false);
+}
 
+/// Checks that `result` starts with `expected` or, if `exact`, is
+/// `expected`, ignoring whitespace. On a mismatch, panics with both,
+/// marking where they first differ.
+fn assert_matches(result: &str, expected: &str, exact: bool) {
+    let expected = normalize(expected);
+    let result = normalize(result);
     let expected1: String = expected.chars().filter(|w| !w.is_whitespace()).collect();
     let result1: String = result.chars().filter(|w| !w.is_whitespace()).collect();
-    assert!(!expected1.is_empty() && result1.starts_with(&expected1));
+    let matches = if exact {
+        result1 == expected1
+    } else {
+        result1.starts_with(&expected1)
+    };
+    if expected1.is_empty() || !matches {
+        panic!(
+            "solution does not match{}\nexpected: {}\n  actual: {}\n          {}^",
+            if exact { " exactly" } else { "" },
+            expected,
+            result,
+            " ".repeat(first_difference(&expected, &result)),
+        );
+    }
+}
+
+/// `text` with each run of whitespace replaced by a single space.
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The number of characters of `actual` that come before the first one
+/// that differs from `expected`, ignoring whitespace.
+fn first_difference(expected: &str, actual: &str) -> usize {
+    let mut expected = expected.chars().filter(|c| !c.is_whitespace());
+    for (index, c) in actual.chars().enumerate() {
+        if c.is_whitespace() {
+            continue;
+        }
+        if expected.next() != Some(c) {
+            return index;
+        }
+    }
+    actual.chars().count()
 }
 
 // different goals
 enum TestGoal {
     // solver should produce same aggregated single solution
     Aggregated(&'static str),
+    // solver should produce exactly this aggregated single solution
+    Exact(&'static str),
+    // solver should produce same aggregated single solution, also when
+    // solving the goal in parallel
+    #[cfg(feature = "parallel")]
+    Parallel(&'static str),
     // solver should produce exactly multiple solutions
     All(Vec<&'static str>),
     // solver should produce first same multiple solutions
@@ -76,9 +130,39 @@ macro_rules! test {
               @unparsed_goals[$($unparsed_goals)*])
     };
 
+    // goal { G } yields_exactly { "Y" } -- like `yields`, but the solution
+    // must be Y, substitution and constraints included, rather than start
+    // with it
+    (@program[$program:tt] @parsed_goals[$($parsed_goals:tt)*] @unparsed_goals[
+        goal $goal:tt yields_exactly { $expected:expr }
+        $($unparsed_goals:tt)*
+    ]) => {
+        test!(@program[$program]
+              @parsed_goals[
+                  $($parsed_goals)*
+                      (stringify!($goal), SolverChoice::slg_default(), TestGoal::Exact($expected))
+                      (stringify!($goal), SolverChoice::recursive(), TestGoal::Exact($expected))
+              ]
+              @unparsed_goals[$($unparsed_goals)*])
+    };
+
+    // goal { G } yields_parallel { "Y" } -- like `yields`, but the goal is
+    // also solved in parallel, with each solver, which must yield Y too
+    (@program[$program:tt] @parsed_goals[$($parsed_goals:tt)*] @unparsed_goals[
+        goal $goal:tt yields_parallel { $expected:expr }
+        $($unparsed_goals:tt)*
+    ]) => {
+        test!(@program[$program]
+              @parsed_goals[
+                  $($parsed_goals)*
+                      (stringify!($goal), SolverChoice::slg_default(), TestGoal::Parallel($expected))
+                      (stringify!($goal), SolverChoice::recursive(), TestGoal::Parallel($expected))
+              ]
+              @unparsed_goals[$($unparsed_goals)*])
+    };
+
     // goal { G } yields_all { "Y1", "Y2", ... , "YN" } -- test that the SLG
-    // solver gets exactly N answers in this order (the recursive solver can't
-    // return multiple answers)
+    // solver gets exactly N answers in this order
     (@program[$program:tt] @parsed_goals[$($parsed_goals:tt)*] @unparsed_goals[
         goal $goal:tt yields_all { $($expected:expr),* }
         $($unparsed_goals:tt)*
@@ -150,6 +234,32 @@ macro_rules! test {
               @unparsed_goals[])
     };
 
+    // goal { G } yields_exactly[C] { "Y" } -- test that solver C yields
+    // exactly Y
+    (@program[$program:tt] @parsed_goals[$($parsed_goals:tt)*] @unparsed_goals[
+        goal $goal:tt yields_exactly[$C:expr] { $expected:expr }
+            goal $($unparsed_goals:tt)*
+    ]) => {
+        test!(@program[$program]
+              @parsed_goals[
+                  $($parsed_goals)*
+                      (stringify!($goal), $C, TestGoal::Exact($expected))
+              ]
+              @unparsed_goals[goal $($unparsed_goals)*])
+    };
+
+    // same as above, but for the final goal in the list.
+    (@program[$program:tt] @parsed_goals[$($parsed_goals:tt)*] @unparsed_goals[
+        goal $goal:tt yields_exactly[$C:expr] { $expected:expr }
+    ]) => {
+        test!(@program[$program]
+              @parsed_goals[
+                  $($parsed_goals)*
+                      (stringify!($goal), $C, TestGoal::Exact($expected))
+                ]
+              @unparsed_goals[])
+    };
+
     // goal { G } yields_all[C1] { "Y1" } yields_all[C2] { "Y2" } -- test that solver C1 yields Y1
     // and C2 yields Y2
     //
@@ -213,6 +323,41 @@ macro_rules! test {
     };
 }
 
+/// Parses the goal `text` and lowers it in `program`, the program of
+/// `db`, peeled into the canonical form that the solvers take.
+fn lower_goal(
+    db: &ChalkDatabase,
+    program: &Program,
+    text: &str,
+) -> UCanonical<InEnvironment<Goal<ChalkIr>>> {
+    chalk_parse::parse_goal(text)
+        .unwrap()
+        .lower(program)
+        .unwrap()
+        .into_peeled_goal(db.interner())
+}
+
+/// Lowers `program_text` into a database that solves goals with
+/// `solver_choice`, lowers `goals` in it, and calls `op` with both, with
+/// the program set as the current one. This is the setup of the tests
+/// that do more with a goal than checking its solution with `test!`.
+fn with_lowered_goals<R>(
+    program_text: &str,
+    solver_choice: SolverChoice,
+    goals: &[&str],
+    op: impl FnOnce(&mut ChalkDatabase, Vec<UCanonical<InEnvironment<Goal<ChalkIr>>>>) -> R,
+) -> R {
+    let mut db = ChalkDatabase::with(program_text, solver_choice);
+    let program = db.checked_program().unwrap();
+    chalk_integration::tls::set_current_program(&program, || {
+        let goals = goals
+            .iter()
+            .map(|goal| lower_goal(&db, &program, goal))
+            .collect();
+        op(&mut db, goals)
+    })
+}
+
 fn solve_goal(program_text: &str, goals: Vec<(&str, SolverChoice, TestGoal)>) {
     println!("program {}", program_text);
     assert!(program_text.starts_with("{"));
@@ -223,17 +368,12 @@ fn solve_goal(program_text: &str, goals: Vec<(&str, SolverChoice, TestGoal)>) {
         SolverChoice::default(),
     );
 
-    let program = db.checked_program().unwrap();
+    let program = db.checked_program().unwrap_or_else(|e| {
+        let text = &program_text[1..program_text.len() - 1];
+        panic!("{}", e.render("program", text))
+    });
 
     for (goal_text, solver_choice, expected) in goals {
-        match (&solver_choice, &expected) {
-            (SolverChoice::Recursive { .. }, TestGoal::All(_))
-            | (SolverChoice::Recursive { .. }, TestGoal::First(_)) => {
-                panic!("cannot test the recursive solver with yields_first or yields_all");
-            }
-            _ => {}
-        };
-
         if db.solver_choice() != solver_choice {
             db.set_solver_choice(solver_choice);
         }
@@ -243,18 +383,23 @@ fn solve_goal(program_text: &str, goals: Vec<(&str, SolverChoice, TestGoal)>) {
             println!("goal {}", goal_text);
             assert!(goal_text.starts_with("{"));
             assert!(goal_text.ends_with("}"));
-            let goal = chalk_parse::parse_goal(&goal_text[1..goal_text.len() - 1])
-                .unwrap()
-                .lower(&*program)
-                .unwrap();
-
+            let peeled_goal = lower_goal(&db, &program, &goal_text[1..goal_text.len() - 1]);
             println!("using solver: {:?}", solver_choice);
-            let peeled_goal = goal.into_peeled_goal(db.interner());
             match expected {
                 TestGoal::Aggregated(expected) => {
                     let result = db.solve(&peeled_goal);
                     assert_result(result, expected);
                 }
+                TestGoal::Exact(expected) => {
+                    let result = db.solve(&peeled_goal);
+                    assert_solution(result, expected, true);
+                }
+                #[cfg(feature = "parallel")]
+                TestGoal::Parallel(expected) => {
+                    assert_result(db.solve(&peeled_goal), expected);
+                    let result = solver_choice.solve_parallel(&*program, &peeled_goal);
+                    assert_result(result, expected);
+                }
                 TestGoal::All(expected) => {
                     let mut expected = expected.into_iter();
                     assert!(
@@ -303,18 +448,62 @@ fn solve_goal(program_text: &str, goals: Vec<(&str, SolverChoice, TestGoal)>) {
     }
 }
 
+mod answer_cache;
 mod auto_traits;
+mod batch;
+mod budget;
+mod builder;
+mod cancellation;
+mod clause_order;
+mod clause_provider;
 mod coherence_goals;
 mod coinduction;
+mod const_impls;
 mod cycle;
+mod diagnostic;
+mod disjunction;
+mod display;
+mod dot;
+mod exact_solutions;
 mod existential_types;
+mod explain;
+mod extern_types;
+mod format;
 mod functions;
+mod fuzz;
 mod implied_bounds;
 mod impls;
+mod incremental;
+mod interning;
+mod lexer;
+mod lsp;
+mod methods;
 mod misc;
+mod modules;
 mod negation;
+mod outlives;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod playground;
+mod positions;
+mod predicates;
+mod program_clauses;
+mod program_queries;
 mod projection;
+mod proof_tree;
+mod reduce;
+mod refs;
+mod report;
+mod round_trip;
 mod scalars;
+mod serialize;
+mod shift;
+mod simplify;
+mod stats;
+mod trace;
 mod tuples;
 mod unify;
+mod unions;
+mod universes;
+mod validate;
 mod wf_goals;
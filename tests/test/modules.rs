@@ -0,0 +1,73 @@
+//! Tests related to `mod` blocks and path-based name resolution.
+
+use super::*;
+
+#[test]
+fn same_name_in_different_modules() {
+    test! {
+        program {
+            trait Foo { }
+
+            mod a {
+                struct S { }
+                impl Foo for S { }
+            }
+
+            mod b {
+                struct S { }
+            }
+        }
+
+        goal {
+            a::S: Foo
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            b::S: Foo
+        } yields {
+            "No possible solution"
+        }
+    }
+}
+
+#[test]
+fn names_resolve_through_enclosing_modules() {
+    test! {
+        program {
+            struct Unit { }
+
+            mod outer {
+                trait Marker { }
+
+                mod inner {
+                    struct S<T> { }
+                    impl<T> Marker for S<T> where T: Marker { }
+                    impl Marker for Unit { }
+                }
+
+                impl<T> Marker for Vec<T> where T: Marker { }
+                struct Vec<T> { }
+            }
+        }
+
+        goal {
+            outer::inner::S<Unit>: outer::Marker
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            outer::Vec<outer::inner::S<Unit>>: outer::Marker
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            outer::Vec<outer::inner::S<outer::Vec<Unit>>>: outer::Marker
+        } yields {
+            "Unique"
+        }
+    }
+}
@@ -160,9 +160,8 @@ fn negation_free_vars() {
 
 /// Here, P and Q depend on one another through a negative loop.
 #[test]
-#[should_panic(expected = "negative cycle")]
 fn negative_loop() {
-    test! {
+    lowering_error! {
         program {
             trait P { }
             trait Q { }
@@ -171,12 +170,8 @@ fn negative_loop() {
             forall<> { Alice: P if not { Alice: Q } }
             forall<> { Alice: Q if not { Alice: P } }
         }
-
-        goal {
-            Alice: P
-        } yields_all[SolverChoice::slg(10, None)] {
-            // Negative cycle -> panic
-            ""
+        error_msg {
+            "negative cycle between predicates: `Implemented(P)` -> not `Implemented(Q)` -> not `Implemented(P)`"
         }
     }
 }
@@ -210,10 +205,9 @@ fn example_2_2_EWFS() {
 }
 
 #[test]
-#[should_panic(expected = "negative cycle")]
 #[allow(non_snake_case)]
 fn example_2_3_EWFS() {
-    test! {
+    lowering_error! {
         program {
             trait W { }
             trait M<A> { }
@@ -229,21 +223,16 @@ fn example_2_3_EWFS() {
             forall<> { c: M<b> }
             forall<> { b: P }
         }
-
-        goal {
-            a: W
-        } yields_all[SolverChoice::slg(3, None)] {
-            // Negative cycle -> panic
-            ""
+        error_msg {
+            "negative cycle between predicates: `Implemented(W)` -> not `Implemented(W)`"
         }
     }
 }
 
 #[test]
-#[should_panic(expected = "negative cycle")]
 #[allow(non_snake_case)]
 fn example_3_3_EWFS() {
-    test! {
+    lowering_error! {
         program {
             trait S { }
             trait P { }
@@ -255,12 +244,8 @@ fn example_3_3_EWFS() {
             forall<> { a: P if not { a: S }, a: Q }
             forall<> { a: Q if not { a: S }, a: P }
         }
-
-        goal {
-            a: S
-        } yields_all[SolverChoice::slg(3, None)] {
-            // Negative cycle -> panic
-            ""
+        error_msg {
+            "negative cycle between predicates: `Implemented(S)` -> not `Implemented(Q)` -> not `Implemented(S)`"
         }
     }
 }
@@ -268,30 +253,24 @@ fn example_3_3_EWFS() {
 /// Here, P is neither true nor false. If it were true, then it would
 /// be false, and so forth.
 #[test]
-#[should_panic(expected = "negative cycle")]
 fn contradiction() {
-    test! {
+    lowering_error! {
         program {
             trait P { }
             struct Alice { }
 
             forall<> { Alice: P if not { Alice: P } }
         }
-
-        goal {
-            Alice: P
-        } yields_all[SolverChoice::slg(3, None)] {
-            // Negative cycle -> panic
-            ""
+        error_msg {
+            "negative cycle between predicates: `Implemented(P)` -> not `Implemented(P)`"
         }
     }
 }
 
 /// Here, P depends on Q negatively, but Q depends only on itself.
 #[test]
-#[should_panic(expected = "negative cycle")]
 fn negative_answer_ambiguous() {
-    test! {
+    lowering_error! {
         program {
             trait P { }
             trait Q { }
@@ -300,12 +279,45 @@ fn negative_answer_ambiguous() {
             forall<> { Alice: P if not { Alice: Q } }
             forall<> { Alice: Q if not { Alice: Q } }
         }
+        error_msg {
+            "negative cycle between predicates: `Implemented(Q)` -> not `Implemented(Q)`"
+        }
+    }
+}
 
-        goal {
-            Alice: P
-        } yields_all[SolverChoice::slg(3, None)] {
-            // Negative cycle -> panic
-            ""
+/// The negative cycle goes through the where clause of an impl.
+#[test]
+fn negative_loop_through_impl() {
+    lowering_error! {
+        program {
+            trait A { }
+            trait B { }
+
+            impl<T> B for T where T: A { }
+            forall<T> { T: A if not { T: B } }
+        }
+        error_msg {
+            "negative cycle between predicates: `Implemented(A)` -> not `Implemented(B)` -> `Implemented(A)`"
+        }
+    }
+}
+
+#[test]
+fn negative_loop_between_predicates() {
+    lowering_error! {
+        program {
+            struct Zero { }
+            struct Succ<N> { }
+
+            predicate Even(type);
+            predicate Odd(type);
+
+            forall<> { Even(Zero) }
+            forall<N> { Odd(Succ<N>) if not { Odd(N) } }
+            forall<N> { Even(N) if not { Odd(N) } }
+        }
+        error_msg {
+            "negative cycle between predicates: `Predicate(Odd)` -> not `Predicate(Odd)`"
         }
     }
 }
@@ -0,0 +1,103 @@
+//! Tests related to outlives goals, `T: 'a` and `'a: 'b`.
+
+use super::*;
+
+#[test]
+fn outlives_goals_become_constraints() {
+    test! {
+        program {
+            struct Ref<'a, T> {}
+            struct Foo {}
+        }
+
+        goal {
+            forall<'a, 'b> { 'a: 'b }
+        } yields {
+            "Unique; substitution [], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }]"
+        }
+
+        goal {
+            forall<'a, T> { T: 'a }
+        } yields {
+            "Unique; substitution [], lifetime constraints [InEnvironment { environment: Env([]), goal: !1_1: '!1_0 }]"
+        }
+
+        goal {
+            forall<'a> {
+                exists<'b> {
+                    Ref<'b, Foo>: 'a,
+                    'b = 'a
+                }
+            }
+        } yields {
+            "Unique; substitution [?0 := '!1_0], lifetime constraints [InEnvironment { environment: Env([]), goal: Ref<'!1_0, Foo>: '!1_0 }]"
+        }
+    }
+}
+
+#[test]
+fn outlives_goals_in_clauses() {
+    test! {
+        program {
+            trait Foo<'a> {}
+            struct Ref<'a, T> {}
+
+            forall<'a, 'b, T> { Ref<'b, T>: Foo<'a> if T: 'a, 'b: 'a }
+        }
+
+        goal {
+            forall<'a, T> { Ref<'a, T>: Foo<'a> }
+        } yields {
+            "Unique; substitution [], lifetime constraints [InEnvironment { environment: Env([]), goal: !1_1: '!1_0 }]"
+        }
+    }
+}
+
+#[test]
+fn answers_with_fewer_constraints() {
+    test! {
+        program {
+            trait Foo {}
+            trait Bar {}
+            trait Baz {}
+            struct S<'a> {}
+            struct X {}
+
+            forall<'a> { S<'a>: Foo if 'a: 'static }
+            forall<'a> { S<'a>: Foo }
+
+            forall<'a> { S<'a>: Bar if 'a: 'static, X: 'a }
+            forall<'a> { S<'a>: Bar if 'a: 'static }
+
+            forall<'a> { S<'a>: Baz if 'a: 'static }
+            forall<'a> { S<'a>: Baz if X: 'a }
+        }
+
+        // The second clause proves the goal with no constraints, which
+        // the constraints of the first imply.
+        goal {
+            forall<'a> { S<'a>: Foo }
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            exists<'a> { S<'a>: Foo }
+        } yields {
+            "Unique; for<?U0> { substitution [?0 := '^0.0], lifetime constraints [] }"
+        }
+
+        goal {
+            forall<'a> { S<'a>: Bar }
+        } yields {
+            "Unique; substitution [], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: 'static }]"
+        }
+
+        // Either of the constraints is enough, which cannot be expressed.
+        goal {
+            forall<'a> { S<'a>: Baz }
+        } yields {
+            "Ambiguous; no inference guidance"
+        }
+    }
+}
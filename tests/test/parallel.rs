@@ -0,0 +1,109 @@
+//! Tests related to solving independent subgoals in parallel.
+
+use super::*;
+
+#[test]
+fn parallel_matches_sequential() {
+    test! {
+        program {
+            struct Vec<T> { }
+            struct Foo { }
+            struct Bar { }
+            trait Clone { }
+            trait Copy { }
+            trait Eq<T> { }
+            impl Clone for Foo { }
+            impl Copy for Bar { }
+            impl<T> Clone for Vec<T> where T: Clone { }
+            impl<T> Eq<T> for T { }
+        }
+
+        // Independent conjuncts.
+        goal {
+            exists<T, U> { T = Foo, U: Copy, Vec<T>: Clone }
+        } yields_parallel {
+            "Unique; substitution [?0 := Foo, ?1 := Bar]"
+        }
+
+        goal {
+            exists<T, U> { T: Copy, U = Vec<Foo>, U: Clone }
+        } yields_parallel {
+            "Unique; substitution [?0 := Bar, ?1 := Vec<Foo>]"
+        }
+
+        goal {
+            exists<T, U> { T = Foo, U: Clone }
+        } yields_parallel {
+            "Ambiguous; definite substitution for<?U0> { [?0 := Foo, ?1 := ^0.0] }"
+        }
+
+        goal {
+            exists<T, U> { T: Copy, U: Copy }
+        } yields_parallel {
+            "Unique; substitution [?0 := Bar, ?1 := Bar]"
+        }
+
+        goal {
+            Foo: Clone, Bar: Copy
+        } yields_parallel {
+            "Unique"
+        }
+
+        goal {
+            Foo: Clone, Bar: Clone
+        } yields_parallel {
+            "No possible solution"
+        }
+
+        // Conjuncts linked through a variable.
+        goal {
+            exists<T, U> { T: Eq<U>, U = Foo, Vec<T>: Clone }
+        } yields_parallel {
+            "Unique; substitution [?0 := Foo, ?1 := Foo]"
+        }
+
+        // Not a conjunction.
+        goal {
+            exists<T> { T: Copy }
+        } yields_parallel {
+            "Unique; substitution [?0 := Bar]"
+        }
+    }
+}
+
+#[test]
+fn parallel_components_only_bind_their_variables() {
+    test! {
+        program {
+            struct Vec<T> { }
+            struct Foo { }
+            struct Bar { }
+            trait Clone { }
+            trait Copy { }
+            impl Clone for Foo { }
+            impl Copy for Bar { }
+            impl<T> Clone for Vec<T> where T: Clone { }
+        }
+
+        // The components mention the variables out of order, and each
+        // only one of them, so their solutions must be mapped back to
+        // the variables of the goal.
+        goal {
+            exists<T, U, V> { V: Copy, T = Foo, U = Vec<T>, U: Clone }
+        } yields_parallel {
+            "Unique; substitution [?0 := Bar, ?1 := Foo, ?2 := Vec<Foo>]"
+        }
+
+        goal {
+            exists<T, U, V> { U: Copy, T = Foo, V: Clone }
+        } yields_parallel {
+            "Ambiguous; definite substitution for<?U0> { [?0 := Bar, ?1 := Foo, ?2 := ^0.0] }"
+        }
+
+        goal {
+            exists<T, U> { U: Copy, T: Copy, Foo: Clone }
+        } yields_parallel {
+            "Unique; substitution [?0 := Bar, ?1 := Bar]"
+        }
+    }
+}
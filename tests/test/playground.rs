@@ -0,0 +1,69 @@
+//! Tests related to the entry point of the playground.
+
+use chalk_integration::report::SolutionReport;
+use chalk_playground::{run, run_with, PlaygroundOptions, PlaygroundResult};
+
+const PROGRAM: &str = "
+    struct Foo { }
+    struct Vec<T> { }
+    trait Clone { }
+    impl Clone for Foo { }
+    impl<T> Clone for Vec<T> where T: Clone { }
+";
+
+#[test]
+fn playground_solves() {
+    assert_eq!(
+        run(PROGRAM, "Vec<Foo>: Clone"),
+        PlaygroundResult::Solved {
+            solution: SolutionReport::Unique {
+                substitution: vec![],
+                constraints: vec![],
+            },
+            text: "Unique; substitution [], lifetime constraints []".to_string(),
+        }
+    );
+    assert_eq!(
+        run(PROGRAM, "Vec<Vec<u32>>: Clone").to_json(),
+        r#"{"status":"solved","solution":{"kind":"no_solution"},"text":"No possible solution."}"#
+    );
+}
+
+#[test]
+fn playground_errors() {
+    assert_eq!(
+        run("struct Foo { x: Bar }", "Foo: Clone"),
+        PlaygroundResult::ProgramError {
+            message: "invalid type name `Bar`".to_string()
+        }
+    );
+    assert_eq!(
+        run(PROGRAM, "Foo: Copy"),
+        PlaygroundResult::GoalError {
+            message: "invalid type name `Copy`".to_string()
+        }
+    );
+    assert!(run(PROGRAM, "Foo:")
+        .to_json()
+        .starts_with(r#"{"status":"goal_error","message":"parse error: unexpected end of input"#));
+}
+
+#[test]
+fn playground_budget() {
+    let options = PlaygroundOptions {
+        max_steps: Some(1),
+        ..PlaygroundOptions::default()
+    };
+    assert_eq!(
+        run_with(PROGRAM, "Vec<Vec<Foo>>: Clone", &options),
+        PlaygroundResult::BudgetExhausted
+    );
+    assert_eq!(
+        run_with(
+            PROGRAM,
+            "Vec<Vec<Foo>>: Clone",
+            &PlaygroundOptions::default()
+        ),
+        run(PROGRAM, "Vec<Foo>: Clone")
+    );
+}
@@ -0,0 +1,105 @@
+//! Tests of the positions in the program text that errors are reported
+//! at.
+
+use super::*;
+use chalk_integration::report::CheckReport;
+use chalk_parse::ast::Span;
+
+fn position(text: &str) -> Option<(usize, usize)> {
+    ChalkDatabase::with(text, SolverChoice::default())
+        .checked_program()
+        .unwrap_err()
+        .position()
+}
+
+#[test]
+fn line_column() {
+    let text = "struct Foo { }\n  trait Bär { }\n";
+    assert_eq!(Span::new(0, 6).line_column(text), (1, 1));
+    assert_eq!(Span::new(7, 10).line_column(text), (1, 8));
+    assert_eq!(Span::new(17, 22).line_column(text), (2, 3));
+
+    // Columns count characters, not bytes.
+    let end = text.rfind(" { }\n").unwrap();
+    assert_eq!(Span::new(end, end).line_column(text), (2, 12));
+}
+
+#[test]
+fn lowering_errors() {
+    let text = "struct Foo { }\ntrait Bar { }\nimpl Bar for Foo<Baz> { }\n";
+    assert_eq!(position(text), Some((3, 14)));
+
+    let text = "trait Foo { }\nstruct Bar { x: Baz }\n";
+    assert_eq!(position(text), Some((2, 17)));
+
+    // An anonymous lifetime is reported at its `'_`, or at the `&` of a
+    // reference type without a lifetime.
+    let text = "struct Foo<T> {\n    field: &'_ T\n}\n";
+    assert_eq!(position(text), Some((2, 13)));
+    let text = "struct Foo<T> {\n    field: &T\n}\n";
+    assert_eq!(position(text), Some((2, 12)));
+}
+
+#[test]
+fn errors_without_positions() {
+    // The errors of the checks after lowering are not about a particular
+    // place in the program.
+    let text = "trait Foo { }\nstruct Bar { }\nimpl Foo for Bar { }\nimpl Foo for Bar { }\n";
+    assert_eq!(position(text), None);
+}
+
+#[test]
+fn template_errors() {
+    // Errors after a template expansion are still reported where they are
+    // in the program.
+    let text = "
+        template Impl<type T> { impl Foo for T { } }
+        trait Foo { }
+        struct Bar { }
+        expand Impl<Bar>;
+        struct Baz { x: Quux }
+    ";
+    assert_eq!(position(text), Some((6, 25)));
+
+    // Errors in an expansion are reported in the template body, or in the
+    // argument of the `expand` that it came from.
+    let text = "
+        template Impl<type T> { impl Foo for Quux<T> { } }
+        trait Foo { }
+        struct Bar { }
+        expand Impl<Bar>;
+    ";
+    assert_eq!(position(text), Some((2, 46)));
+    let text = "
+        template Impl<type T> { impl Foo for T { } }
+        trait Foo { }
+        expand Impl<Quux>;
+    ";
+    assert_eq!(position(text), Some((4, 21)));
+}
+
+#[test]
+fn shadowed_parameters() {
+    // Parameters that shadow others are reported at the item or where
+    // clause that introduces them.
+    let text = "trait Bar { }\nstruct Foo<T> where forall<T> T: Bar { }\n";
+    assert_eq!(position(text), Some((2, 21)));
+    let text = "trait Bar<T> {\n    type Item<T>;\n}\n";
+    assert_eq!(position(text), Some((2, 10)));
+}
+
+#[test]
+fn batch_errors() {
+    // Goal blocks are left out of the program, but the lines after them
+    // are still those of the file.
+    let text = "struct Foo { }\ntrait Bar { }\ngoal {\n    Foo: Bar\n} //~ NO SOLUTION\nimpl Bar for Quux { }\n";
+    let error = chalk_integration::batch::run_batch(text, SolverChoice::default()).unwrap_err();
+    assert_eq!(error.to_string(), "invalid type name `Quux`");
+    assert_eq!(error.position(), Some((6, 14)));
+
+    let report = CheckReport::check("test.chalk", text, SolverChoice::default());
+    assert_eq!(
+        report.to_json(),
+        r#"{"file":"test.chalk","error":"invalid type name `Quux`","line":6,"column":14}"#
+    );
+}
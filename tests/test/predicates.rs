@@ -0,0 +1,118 @@
+//! Tests related to user-declared predicates, such as `predicate Edge(type, type);`.
+
+use super::*;
+
+#[test]
+fn reachability() {
+    test! {
+        program {
+            struct A { }
+            struct B { }
+            struct C { }
+
+            predicate Edge(type, type);
+            predicate Reachable(type, type);
+
+            forall<> { Edge(A, B) }
+            forall<> { Edge(B, C) }
+
+            forall<X, Y> { Reachable(X, Y) if Edge(X, Y) }
+            forall<X, Y, Z> { Reachable(X, Z) if Edge(X, Y), Reachable(Y, Z) }
+        }
+
+        goal {
+            Reachable(A, C)
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            Reachable(C, A)
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            exists<T> { Reachable(T, A) }
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            exists<T> { Reachable(B, T) }
+        } yields {
+            "Unique; substitution [?0 := C]"
+        }
+
+        goal {
+            exists<T> { Reachable(A, T) }
+        } yields {
+            "Ambiguous"
+        }
+    }
+}
+
+#[test]
+fn predicates_in_environment() {
+    test! {
+        program {
+            struct Foo { }
+            trait Clone { }
+
+            predicate Copyable(type);
+
+            forall<T> { T: Clone if Copyable(T) }
+        }
+
+        goal {
+            Foo: Clone
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            if (Copyable(Foo)) {
+                Foo: Clone
+            }
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            forall<T> {
+                if (Copyable(T)) {
+                    T: Clone
+                }
+            }
+        } yields {
+            "Unique"
+        }
+    }
+}
+
+#[test]
+fn predicates_over_lifetimes() {
+    test! {
+        program {
+            struct Ref<'a, T> { }
+            struct Foo { }
+
+            predicate Outlives(lifetime, type);
+
+            forall<'a, T> { Outlives('a, Ref<'a, T>) if Outlives('a, T) }
+            forall<'a> { Outlives('a, Foo) }
+        }
+
+        goal {
+            forall<'a> { Outlives('a, Ref<'a, Ref<'a, Foo>>) }
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            forall<'a, T> { Outlives('a, Ref<'a, T>) }
+        } yields {
+            "No possible solution"
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! Tests of `program_clauses_for_item`, which gives the program clauses
+//! that an item contributes.
+
+use super::*;
+use chalk_integration::Identifier;
+use chalk_solve::clauses::{program_clauses_for_item, ItemId};
+
+const PROGRAM: &str = "
+    struct Foo { }
+    struct Vec<T> { }
+    trait Clone { }
+    trait Iterator { type Item; }
+    impl<T> Clone for Vec<T> where T: Clone { }
+    impl<T> Iterator for Vec<T> { type Item = T; }
+    impl !Clone for Foo { }
+";
+
+fn clauses_for(db: &ChalkDatabase, item: ItemId<ChalkIr>) -> Vec<String> {
+    db.with_program(|_| {
+        program_clauses_for_item(db, item)
+            .iter()
+            .map(|clause| format!("{:?}", clause))
+            .collect()
+    })
+}
+
+#[test]
+fn impl_clauses() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::default());
+    let program = db.checked_program().unwrap();
+    let clone = program.trait_ids[&Identifier::from("Clone")];
+    let iterator = program.trait_ids[&Identifier::from("Iterator")];
+    let clone_impls: Vec<_> = program.impls_of_trait(clone).collect();
+    let iterator_impl = program.impls_of_trait(iterator).next().unwrap();
+
+    assert_eq!(
+        clauses_for(&db, ItemId::Impl(clone_impls[0])),
+        vec!["for<type> Implemented(Vec<^0.0>: Clone) :- ForAll<> { Implemented(^1.0: Clone) }"]
+    );
+
+    // A negative impl contributes no clauses.
+    assert_eq!(
+        clauses_for(&db, ItemId::Impl(clone_impls[1])),
+        Vec::<String>::new()
+    );
+
+    // The clauses of an impl include those of its associated type
+    // values.
+    assert_eq!(
+        clauses_for(&db, ItemId::Impl(iterator_impl)),
+        vec![
+            "for<type> Implemented(Vec<^0.0>: Iterator)",
+            "for<type> Normalize(<Vec<^0.0> as Iterator>::Item -> ^0.0)",
+        ]
+    );
+}
+
+#[test]
+fn trait_and_associated_ty_clauses() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::default());
+    let program = db.checked_program().unwrap();
+    let clone = program.trait_ids[&Identifier::from("Clone")];
+    let iterator = program.trait_ids[&Identifier::from("Iterator")];
+    let item = program.trait_data[&iterator].associated_ty_ids[0];
+
+    assert!(clauses_for(&db, ItemId::Trait(clone))
+        .contains(&"for<type> Implemented(^0.0: Clone) :- FromEnv(^0.0: Clone)".to_string()));
+    assert_eq!(
+        clauses_for(&db, ItemId::AssociatedTy(item)),
+        vec![
+            "for<type> AliasEq(<^0.0 as Iterator>::Item = (Iterator::Item)<^0.0>)",
+            "for<type> WellFormed((Iterator::Item)<^0.0>) :- WellFormed(^0.0: Iterator)",
+            "for<type> FromEnv(^0.0: Iterator) :- FromEnv((Iterator::Item)<^0.0>)",
+            "for<type, type> AliasEq(<^0.0 as Iterator>::Item = ^0.1) :- \
+             Normalize(<^0.0 as Iterator>::Item -> ^0.1)",
+        ]
+    );
+}
+
+#[test]
+fn item_clauses_are_in_the_environment() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::default());
+    let program = db.checked_program().unwrap();
+    let environment = db.environment().unwrap();
+    for &impl_id in program.impl_data.keys() {
+        for clause in program_clauses_for_item(&db, ItemId::Impl(impl_id)) {
+            assert!(environment.program_clauses.contains(&clause));
+        }
+    }
+}
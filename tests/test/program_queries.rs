@@ -0,0 +1,107 @@
+//! Tests related to the queries on the items of a lowered program.
+
+use super::*;
+use chalk_integration::Identifier;
+use chalk_ir::AssocTypeId;
+use chalk_rust_ir::AssociatedTyValue;
+use chalk_solve::clauses::ItemId;
+use std::sync::Arc;
+
+const PROGRAM: &str = "
+    struct Foo { }
+    struct Bar { }
+    trait Clone { }
+    trait Iterator { type Item; }
+    impl Clone for Foo { }
+    impl Iterator for Foo { type Item = Bar; }
+    impl Clone for Bar { }
+";
+
+#[test]
+fn impls_of_trait() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::default());
+    let mut program = (*db.checked_program().unwrap()).clone();
+    let clone = program.trait_ids[&Identifier::from("Clone")];
+    let iterator = program.trait_ids[&Identifier::from("Iterator")];
+
+    let clone_impls: Vec<_> = program.impls_of_trait(clone).collect();
+    assert_eq!(clone_impls.len(), 2);
+    assert_eq!(program.impls_of_trait(iterator).count(), 1);
+    for impl_id in &clone_impls {
+        assert_eq!(program.impl_data[impl_id].trait_id(), clone);
+    }
+
+    // The index follows the impls that are removed and added back.
+    let (impl_datum, values): (_, Vec<Arc<AssociatedTyValue<ChalkIr>>>) =
+        program.remove_impl(clone_impls[0]).unwrap();
+    assert_eq!(
+        program.impls_of_trait(clone).collect::<Vec<_>>(),
+        &clone_impls[1..]
+    );
+    program.add_impl(clone_impls[0], impl_datum, values);
+    assert_eq!(
+        program.impls_of_trait(clone).collect::<Vec<_>>(),
+        clone_impls
+    );
+}
+
+#[test]
+fn item_names() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::default());
+    let program = db.checked_program().unwrap();
+
+    let foo = program.struct_ids[&Identifier::from("Foo")];
+    let iterator = program.trait_ids[&Identifier::from("Iterator")];
+    let (&item, _) = program.associated_ty_data.iter().next().unwrap();
+    let impl_id = program.impls_of_trait(iterator).next().unwrap();
+
+    assert_eq!(program.item_name(foo.0), Some(&Identifier::from("Foo")));
+    assert_eq!(
+        program.item_name(iterator.0),
+        Some(&Identifier::from("Iterator"))
+    );
+    assert_eq!(program.item_name(item.0), Some(&Identifier::from("Item")));
+    assert_eq!(program.item_name(impl_id.0), None);
+    assert_eq!(program.trait_of_assoc_ty(item), Some(iterator));
+    assert_eq!(program.trait_of_assoc_ty(AssocTypeId(foo.0)), None);
+}
+
+#[test]
+fn lowered_item() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::default());
+    db.with_program(|program| {
+        let iterator = program.lowered_item("Iterator").unwrap();
+        assert!(iterator.starts_with("TypeKind {\n    sort: Trait,"));
+        assert!(iterator.contains("TraitDatum {\n    id: Iterator,"));
+        assert!(iterator.contains("AssociatedTyDatum {"));
+        assert!(iterator.contains("impl(Foo: Iterator) => ImplDatum {"));
+        assert!(iterator.contains("value: for<> AssociatedTyValueBound {\n        ty: Bar,"));
+
+        let foo = program.lowered_item("Foo").unwrap();
+        assert!(foo.contains("StructDatum {"));
+        assert!(!foo.contains("ImplDatum"));
+
+        assert_eq!(program.lowered_item("Item"), None);
+    });
+}
+
+#[test]
+fn items_named() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::default());
+    let program = db.checked_program().unwrap();
+    let foo = program.struct_ids[&Identifier::from("Foo")];
+    let iterator = program.trait_ids[&Identifier::from("Iterator")];
+    let (&item, _) = program.associated_ty_data.iter().next().unwrap();
+    let impl_id = program.impls_of_trait(iterator).next().unwrap();
+
+    assert_eq!(program.items_named("Foo"), vec![ItemId::Struct(foo)]);
+    assert_eq!(
+        program.items_named("Iterator"),
+        vec![
+            ItemId::Trait(iterator),
+            ItemId::AssociatedTy(item),
+            ItemId::Impl(impl_id),
+        ]
+    );
+    assert_eq!(program.items_named("Item"), vec![]);
+}
@@ -91,6 +91,53 @@ fn normalize_basic() {
     }
 }
 
+#[test]
+fn normalize_vs_alias_eq() {
+    test! {
+        program {
+            trait Iterator { type Item; }
+            struct Vec<T> { }
+            impl<T> Iterator for Vec<T> {
+                type Item = T;
+            }
+        }
+
+        // Without an impl to normalize with, the projection can only be
+        // equal to its placeholder, which `Normalize` does not accept.
+        goal {
+            forall<T> {
+                if (T: Iterator) {
+                    exists<U> {
+                        Normalize(<T as Iterator>::Item -> U)
+                    }
+                }
+            }
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            forall<T> {
+                if (T: Iterator) {
+                    exists<U> {
+                        <T as Iterator>::Item = U
+                    }
+                }
+            }
+        } yields {
+            "Unique; substitution [?0 := (Iterator::Item)<!1_0>]"
+        }
+
+        goal {
+            forall<T> {
+                Normalize(<Vec<T> as Iterator>::Item -> T)
+            }
+        } yields {
+            "Unique"
+        }
+    }
+}
+
 #[test]
 fn normalize_into_iterator() {
     test! {
@@ -332,6 +379,38 @@ fn projection_equality_nested() {
     }
 }
 
+#[test]
+fn projection_equality_multiple_bindings() {
+    test! {
+        program {
+            trait IntoIterator {
+                type Item;
+                type IntoIter;
+            }
+            trait Pair<T, U> { }
+            impl<I, T, U> Pair<T, U> for I where I: IntoIterator<Item = T, IntoIter = U> { }
+
+            struct S { }
+            impl IntoIterator for S {
+                type Item = u32;
+                type IntoIter = S;
+            }
+        }
+
+        goal {
+            S: Pair<u32, S>
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            S: Pair<u32, u32>
+        } yields {
+            "No possible solution"
+        }
+    }
+}
+
 #[test]
 fn iterator_flatten() {
     test! {
@@ -675,10 +754,7 @@ fn normalize_under_binder() {
                     Ref<'a, I32>: Deref<'a, Item = U>
                 }
             }
-        } yields[SolverChoice::slg_default()] {
-            // chalk#234, I think
-            "Ambiguous"
-        } yields[SolverChoice::recursive()] {
+        } yields {
             "Unique; substitution [?0 := I32], lifetime constraints []"
         }
 
@@ -722,12 +798,7 @@ fn normalize_under_binder() {
                 }
             }
         } yields {
-            "Unique; for<?U0> { \
-             substitution [?0 := Ref<'^0.0, I32>], \
-             lifetime constraints [\
-             InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, \
-             InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }] \
-             }"
+            "No possible solution"
         }
     }
 }
@@ -755,12 +826,7 @@ fn normalize_under_binder_multi() {
                 }
             }
         } yields_all {
-            "substitution [?0 := I32], lifetime constraints []",
-            "for<?U0,?U0> { substitution [?0 := (Deref::Item)<Ref<'^0.0, I32>, '^0.1>], lifetime constraints [\
-            InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }, \
-            InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, \
-            InEnvironment { environment: Env([]), goal: '^0.1: '!1_0 }, \
-            InEnvironment { environment: Env([]), goal: '!1_0: '^0.1 }] }"
+            "substitution [?0 := I32], lifetime constraints []"
         }
 
         goal {
@@ -974,3 +1040,31 @@ fn issue_144_regression() {
         }
     }
 }
+
+#[test]
+fn shorthand_projections() {
+    test! {
+        program {
+            trait Iterator { type Item; }
+            trait Clone { }
+            struct Counter { }
+            struct Foo { }
+            impl Clone for Foo { }
+            impl Iterator for Counter { type Item = Foo; }
+            trait Cloned where Self: Iterator { }
+            impl<I> Cloned for I where I: Iterator, I::Item: Clone { }
+        }
+
+        goal {
+            Counter: Cloned
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            forall<T> { if (T: Iterator) { T: Cloned } }
+        } yields {
+            "No possible solution"
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Tests related to recording proof trees.
+
+use super::*;
+
+const PROGRAM: &str = "
+    struct Vec<T> { }
+    struct Foo { }
+    trait Clone { }
+    trait Copy { }
+    impl Clone for Foo { }
+    impl<T> Clone for Vec<T> where T: Clone { }
+";
+
+#[test]
+fn proof_tree_unique() {
+    let goals = ["exists<T> { T = Foo, Vec<Vec<T>>: Clone }"];
+    for &solver_choice in &[SolverChoice::slg_default(), SolverChoice::recursive()] {
+        with_lowered_goals(PROGRAM, solver_choice, &goals, |db, goals| {
+            let tree = db.solve_with_proof_tree(&goals[0]).unwrap();
+            assert_same(
+                &tree.display(&ChalkIr).to_string(),
+                "
+                all((^0.0 = Foo), Implemented(Vec<Vec<^0.0>>: Clone))
+                    => Unique; substitution [?0 := Foo], lifetime constraints []
+                    Implemented(Vec<Vec<Foo>>: Clone)
+                        => Unique; substitution [], lifetime constraints []
+                        by for<type> Implemented(Vec<^0.0>: Clone) :- ForAll<> { Implemented(^1.0: Clone) }
+                        Implemented(Vec<Foo>: Clone)
+                            => Unique; substitution [], lifetime constraints []
+                            by for<type> Implemented(Vec<^0.0>: Clone) :- ForAll<> { Implemented(^1.0: Clone) }
+                            Implemented(Foo: Clone)
+                                => Unique; substitution [], lifetime constraints []
+                                by Implemented(Foo: Clone)
+                ",
+            );
+        });
+    }
+}
+
+#[test]
+fn proof_tree_negation() {
+    // The goal of a `not { .. }` that holds has no solution.
+    let goals = ["not { Foo: Copy }"];
+    with_lowered_goals(PROGRAM, SolverChoice::recursive(), &goals, |db, goals| {
+        let tree = db.solve_with_proof_tree(&goals[0]).unwrap();
+        assert_same(
+            &tree.display(&ChalkIr).to_string(),
+            "
+            not { Implemented(Foo: Copy) } => Unique; substitution [], lifetime constraints []
+                Implemented(Foo: Copy) => No possible solution
+            ",
+        );
+    });
+}
+
+#[test]
+fn proof_tree_ambiguous() {
+    // Both impls apply, so no single clause is recorded for `T: Clone`.
+    let goals = ["exists<T> { Vec<T>: Clone }"];
+    with_lowered_goals(PROGRAM, SolverChoice::recursive(), &goals, |db, goals| {
+        let tree = db.solve_with_proof_tree(&goals[0]).unwrap();
+        assert_same(
+            &tree.display(&ChalkIr).to_string(),
+            "
+            Implemented(Vec<^0.0>: Clone) => Ambiguous; no inference guidance
+                by for<type> Implemented(Vec<^0.0>: Clone) :- ForAll<> { Implemented(^1.0: Clone) }
+                Implemented(^0.0: Clone) => Ambiguous; no inference guidance
+            ",
+        );
+    });
+}
+
+#[test]
+fn proof_tree_no_solution() {
+    let goals = ["Vec<Foo>: Copy"];
+    with_lowered_goals(PROGRAM, SolverChoice::recursive(), &goals, |db, goals| {
+        assert!(db.solve_with_proof_tree(&goals[0]).is_none());
+    });
+}
@@ -0,0 +1,79 @@
+//! Tests related to the reduction of failing programs.
+
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::query::LoweringDatabase;
+use chalk_integration::reduce::{reduce, reduce_failure, Failure};
+use chalk_solve::SolverChoice;
+
+fn reduced(program: &str, fails: impl Fn(&str) -> bool) -> String {
+    let program = chalk_parse::parse_program(program).unwrap();
+    let reduced = reduce(&program, |program| {
+        fails(&chalk_parse::print_program(program))
+    });
+    chalk_parse::print_program(&reduced)
+}
+
+#[test]
+fn reduce_wrong_solution() {
+    let program = "
+        struct Foo { }
+        struct Bar<T> { field: T }
+        trait Clone { }
+        trait Copy where Self: Clone { }
+        impl<T> Clone for Bar<T> where T: Clone { }
+        impl<T> Copy for Bar<T> where T: Copy { }
+        impl Clone for Foo { }
+    ";
+    let goal = "Bar<Foo>: Copy";
+    let failure = Failure::WrongSolution("Unique".to_string());
+    let solver_choice = SolverChoice::default();
+    assert!(failure.occurs(program, goal, solver_choice));
+
+    // `Foo: Copy` does not hold, so whatever else is deleted, the goal
+    // still has no solution.
+    let program = chalk_parse::parse_program(program).unwrap();
+    let reduced = reduce_failure(&program, goal, &failure, solver_choice);
+    assert_eq!(
+        chalk_parse::print_program(&reduced),
+        "\
+struct Foo { }
+struct Bar<T> { }
+trait Copy { }
+"
+    );
+}
+
+#[test]
+fn reduce_deletes_parameters() {
+    // Deleting the parameters of `Foo` also deletes its arguments, and then
+    // the parameters of the impls are unused and can be deleted too.
+    let program = "
+        struct Foo<T, U> { }
+        struct Baz { }
+        trait Bar<T> { }
+        impl<T, U> Bar<T> for Foo<T, U> where T: Bar<U> { }
+    ";
+    let fails = |program: &str| {
+        program.contains("impl")
+            && ChalkDatabase::with(program, SolverChoice::default())
+                .program_ir()
+                .is_ok()
+    };
+    assert_eq!(
+        reduced(program, fails),
+        "\
+struct Foo { }
+trait Bar { }
+impl Bar for Foo { }
+"
+    );
+}
+
+#[test]
+fn reduce_failure_must_occur() {
+    let failure = Failure::WrongSolution("No possible solution".to_string());
+    let program = "struct Foo { } trait Clone { } impl Clone for Foo { }";
+    assert!(failure.occurs(program, "Foo: Clone", SolverChoice::default()));
+    assert!(!failure.occurs(program, "Foo: Copy", SolverChoice::default()));
+    assert!(!Failure::Panic.occurs(program, "Foo: Clone", SolverChoice::default()));
+}
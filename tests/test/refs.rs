@@ -0,0 +1,89 @@
+//! Tests for reference types and the anonymous lifetimes they may carry.
+
+use super::*;
+
+#[test]
+fn elided_lifetime_in_impl_header() {
+    test! {
+        program {
+            trait Foo { }
+            impl Foo for &u32 { }
+        }
+
+        goal {
+            forall<'a> { &'a u32: Foo }
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            &u32: Foo
+        } yields {
+            "Unique; for<?U0> { substitution [?0 := '^0.0], lifetime constraints [] }"
+        }
+
+        goal {
+            &'_ mut u32: Foo
+        } yields {
+            "No possible solution"
+        }
+    }
+}
+
+#[test]
+fn anonymous_lifetimes_are_distinct() {
+    test! {
+        program {
+            trait Eq<T> { }
+            impl<T> Eq<T> for T { }
+        }
+
+        goal {
+            &'_ u32: Eq<&'_ u32>
+        } yields {
+            "Unique; for<?U0> { substitution [?0 := '^0.0, ?1 := '^0.0], lifetime constraints [] }"
+        }
+
+        goal {
+            forall<'a> { exists<T> { &'a u32: Eq<&'_ T> } }
+        } yields {
+            "Unique; substitution [?0 := '!1_0, ?1 := Uint(U32)], lifetime constraints []"
+        }
+    }
+}
+
+#[test]
+fn ref_builtin_traits() {
+    test! {
+        program {
+            #[lang(sized)] trait Sized { }
+            #[lang(copy)] trait Copy { }
+            #[lang(clone)] trait Clone { }
+            struct Foo { }
+        }
+
+        goal {
+            forall<'a> { &'a Foo: Sized }
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            forall<'a> { &'a Foo: Copy }
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            forall<'a> { &'a Foo: Clone }
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            forall<'a> { &'a mut Foo: Copy }
+        } yields {
+            "No possible solution"
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! Tests related to the machine-readable reports of goals and goal files.
+
+use super::*;
+use chalk_integration::report::{CheckReport, GoalReport, Outcome};
+
+const PROGRAM: &str = "
+    struct Foo { }
+    struct Bar { }
+    trait Baz { }
+    impl Baz for Foo { }
+    trait Quux { }
+    impl Quux for Foo { }
+    impl Quux for Bar { }
+";
+
+#[test]
+fn goal_report() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::default());
+
+    let report = GoalReport::solve(&db, "exists<T> { T: Baz }");
+    assert_eq!(
+        report.to_json(),
+        r#"{"goal":"exists<T> { T: Baz }","solution":{"kind":"unique","substitution":[{"variable":"?0","value":"Foo"}],"constraints":[]}}"#
+    );
+
+    let report = GoalReport::solve(&db, "exists<T> { T: Quux }");
+    assert_eq!(
+        report.to_json(),
+        r#"{"goal":"exists<T> { T: Quux }","solution":{"kind":"ambiguous","guidance":"unknown","substitution":[]}}"#
+    );
+
+    let report = GoalReport::solve(&db, "Bar: Baz");
+    assert_eq!(
+        report.to_json(),
+        r#"{"goal":"Bar: Baz","solution":{"kind":"no_solution"}}"#
+    );
+
+    let report = GoalReport::solve(&db, "Bar: Qux");
+    match report.outcome {
+        Outcome::Error(ref error) => assert_eq!(error, "invalid type name `Qux`"),
+        Outcome::Solution(_) => panic!("expected an error"),
+    }
+    assert_eq!(
+        report.to_json(),
+        r#"{"goal":"Bar: Qux","error":"invalid type name `Qux`"}"#
+    );
+}
+
+#[test]
+fn check_report() {
+    let text = format!(
+        "{}\n{}",
+        PROGRAM,
+        "
+        goal { Foo: Baz } //~ UNIQUE
+        goal { Bar: Baz } //~ UNIQUE
+        "
+    );
+    let report = CheckReport::check("test.chalk", &text, SolverChoice::default());
+    assert_eq!(
+        report.to_json(),
+        r#"{"file":"test.chalk","goals":2,"passed":1,"failed":1,"mismatches":[{"line":12,"goal":"Bar: Baz","expected":"a unique solution","actual":"No possible solution"}]}"#
+    );
+
+    let report = CheckReport::check("test.chalk", "struct {", SolverChoice::default());
+    match report {
+        CheckReport::Error { .. } => {}
+        CheckReport::Checked { .. } => panic!("expected an error"),
+    }
+}
@@ -0,0 +1,174 @@
+//! Round trips of programs through `Program::display_source`: a program
+//! is lowered, rendered and lowered again, which must yield the same
+//! program, so that the rendering keeps up with lowering. Generated
+//! programs are checked the same way by the fuzz tests.
+
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::fuzz::check_source_round_trip;
+use chalk_integration::query::LoweringDatabase;
+use chalk_solve::SolverChoice;
+
+fn round_trip(text: &str) {
+    let db = ChalkDatabase::with(text, SolverChoice::default());
+    let program = db.program_ir().unwrap();
+    check_source_round_trip(text, &program);
+}
+
+#[test]
+fn round_trip_structs() {
+    round_trip(
+        "
+        trait Clone { }
+        struct Foo { }
+        struct Bar<'a, T> where T: Clone { x: &'a T, y: (T, u32), z: Foo }
+        union Either<T> { left: T, right: T }
+        #[upstream] #[fundamental] struct Box<T> { }
+        #[manually_drop] struct ManuallyDrop<T> { value: T }
+        extern type Opaque;
+        ",
+    );
+}
+
+#[test]
+fn round_trip_traits() {
+    round_trip(
+        "
+        #[lang(sized)] trait Sized { }
+        #[auto] trait Send { }
+        #[marker] #[upstream] #[fundamental] #[non_enumerable] trait Marker { }
+        #[coinductive] unsafe trait Unsafe { }
+        trait Eq<T> where Self: Sized, T: Sized { }
+        trait Iterator {
+            type Item: Eq<Self> + Eq<u32> where Self: Sized;
+            type Iter<'a>: Iterator<Item = &'a Self>;
+            fn next(&mut self) -> Self::Item;
+            fn by_ref<'a>(&self, &'a u32) -> &'a Self;
+            fn new() -> Self;
+        }
+        ",
+    );
+}
+
+#[test]
+fn round_trip_impls() {
+    round_trip(
+        "
+        trait Clone { }
+        unsafe trait Send { }
+        trait Iterator { type Item; fn next(&mut self) -> Self::Item; }
+        trait Collection { type Iter<'a>; }
+        struct Vec<T> { }
+        struct Ref<'a, T> { }
+        struct Foo { }
+        impl<T> Clone for Vec<T> where T: Clone { }
+        #[upstream] #[priority(2)] impl Clone for Foo { }
+        unsafe impl<T> Send for Vec<T> where T: Send { }
+        impl const Clone for u32 { }
+        impl !Send for Foo { }
+        impl<T> Iterator for Vec<T> {
+            type Item = T;
+            fn next(&mut self) -> T;
+        }
+        impl<T> Collection for Vec<T> { type Iter<'a> = Ref<'a, T>; }
+        impl<T> Vec<T> where T: Clone { fn len(&self) -> usize; }
+        ",
+    );
+}
+
+#[test]
+fn round_trip_where_clauses() {
+    round_trip(
+        "
+        trait Clone { }
+        trait Iterator { type Item; type Rest; }
+        trait Lifetime<'a> { }
+        struct Foo<T> where T: Iterator<Item = u32> { }
+        struct Bar<T> where T: Iterator, T: Iterator<Item = u32, Rest = T> { }
+        struct Baz<T> where T: Iterator<Item = u32>, T: Iterator { }
+        struct Qux<T> where T: ~const Clone, forall<'a> T: Lifetime<'a> { }
+        struct Quux<T> where forall<'a> T: Iterator<Item = &'a u32> { }
+        ",
+    );
+}
+
+#[test]
+fn round_trip_types() {
+    round_trip(
+        "
+        trait Clone { }
+        trait Iterator { type Item; }
+        struct Types<'a, T> {
+            a: (),
+            b: (T,),
+            c: &'a mut T,
+            d: for<'b> fn(&'b T),
+            e: <T as Iterator>::Item,
+            f: dyn Iterator<Item = u32> + Clone,
+            g: dyn Iterator + Iterator<Item = u32>,
+            h: dyn forall<'b> Iterator<Item = &'b T>
+        }
+        ",
+    );
+}
+
+#[test]
+fn round_trip_opaque_types() {
+    round_trip(
+        "
+        trait Clone { }
+        trait Iterator { type Item; }
+        struct Vec<T> { }
+        opaque type Foo<T>: Clone + Iterator<Item = T> = Vec<T>;
+        type Bar = impl Clone;
+        hidden type Bar = u32;
+        struct Baz { }
+        ",
+    );
+}
+
+#[test]
+fn round_trip_clauses_and_goals() {
+    round_trip(
+        "
+        trait Clone { }
+        trait Iterator { type Item; }
+        struct Vec<T> { }
+        predicate Reachable(type, type);
+        forall<T> { Vec<T>: Clone if T: Clone, Reachable(T, u32) }
+        forall<T> { T: Iterator<Item = u32> if T: Clone }
+        goal cloned { forall<T> { if (T: Iterator<Item = u32>; T: Clone if Reachable(T, T), T: Clone) { Vec<T>: Clone } } }
+        forall { Reachable(u32, bool) }
+        goal reachable { exists<T> { Reachable(T, u32), T: Iterator<Item = u32> } }
+        struct Foo { }
+        ",
+    );
+}
+
+#[test]
+fn round_trip_modules() {
+    round_trip(
+        "
+        trait Clone { }
+        mod a {
+            struct Foo<T> { }
+            mod b {
+                trait Iterator { type Item; }
+                impl<T> Iterator for a::Foo<T> { type Item = T; }
+            }
+            impl<T> Clone for Foo<T> where T: b::Iterator { }
+        }
+        ",
+    );
+}
+
+#[test]
+fn round_trip_names_of_parameters() {
+    // The parameters are renamed so as not to shadow the items.
+    round_trip(
+        "
+        trait T0 { }
+        struct T1 { }
+        struct Foo<T> where T: T0 { x: T1 }
+        ",
+    );
+}
@@ -0,0 +1,72 @@
+//! Tests related to serializing lowered programs and goals.
+
+use super::*;
+use chalk_integration::program::Program;
+use chalk_ir::Goal;
+
+const PROGRAM: &str = "
+    #[lang(sized)]
+    trait Sized { }
+    trait Clone { }
+    trait Iterator { type Item: Clone; }
+    struct Vec<T> { }
+    impl Clone for u32 { }
+    impl<T> Clone for Vec<T> where T: Clone { }
+    impl<T> Iterator for Vec<T> where T: Clone { type Item = T; }
+    predicate Reachable(type, type);
+    forall<T> { Reachable(T, Vec<T>) }
+";
+
+/// Serializes `value` to JSON and back.
+fn round_trip<T>(value: &T) -> T
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    let json = serde_json::to_string(value).unwrap();
+    serde_json::from_str(&json).unwrap()
+}
+
+#[test]
+fn serialize_program() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::default());
+    let program = db.checked_program().unwrap();
+    let deserialized: Program = round_trip(&*program);
+    assert_eq!(deserialized, *program);
+}
+
+#[test]
+fn serialize_goal() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::default());
+    let program = db.checked_program().unwrap();
+    let goal: Goal<ChalkIr> = chalk_parse::parse_goal(
+        "forall<T> { if (T: Clone) { exists<U> { Normalize(<Vec<T> as Iterator>::Item -> U) } } }",
+    )
+    .unwrap()
+    .lower(&*program)
+    .unwrap();
+    assert_eq!(round_trip(&goal), goal);
+}
+
+#[test]
+fn solve_deserialized_program() {
+    // A deserialized program can be solved with, giving the same
+    // solutions as the original.
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::default());
+    let program = db.checked_program().unwrap();
+    let deserialized: Program = round_trip(&*program);
+
+    for goal in &[
+        "Vec<Vec<u32>>: Clone",
+        "exists<T> { Reachable(u32, T) }",
+        "forall<T> { Vec<T>: Clone }",
+    ] {
+        let goal = round_trip(&lower_goal(&db, &program, goal));
+        let expected = SolverChoice::default()
+            .into_solver()
+            .solve(&*program, &goal);
+        let solution = SolverChoice::default()
+            .into_solver()
+            .solve(&deserialized, &goal);
+        assert_eq!(solution, expected);
+    }
+}
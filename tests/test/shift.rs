@@ -0,0 +1,138 @@
+//! Tests related to shifting bound variables in and out of binders,
+//! and to substituting for them.
+
+use super::*;
+use chalk_engine::fallible::NoSolution;
+use chalk_ir::fold::shift::Shift;
+use chalk_ir::{
+    ApplicationTy, Binders, BoundVar, DebruijnIndex, ParameterKind, ParameterKinds, Substitution,
+    Ty, TyData, TypeName,
+};
+
+fn bound(depth: u32, index: usize) -> Ty<ChalkIr> {
+    TyData::BoundVar(BoundVar::new(DebruijnIndex::new(depth), index)).intern(&ChalkIr)
+}
+
+fn tuple(elements: &[Ty<ChalkIr>]) -> Ty<ChalkIr> {
+    TyData::Apply(ApplicationTy {
+        name: TypeName::Tuple(elements.len()),
+        substitution: Substitution::from(&ChalkIr, elements.iter().cloned()),
+    })
+    .intern(&ChalkIr)
+}
+
+/// `value` beneath a binder of `len` types.
+fn binders<T: chalk_ir::interner::HasInterner<Interner = ChalkIr>>(
+    len: usize,
+    value: T,
+) -> Binders<T> {
+    Binders::new(
+        ParameterKinds::from(&ChalkIr, (0..len).map(|_| ParameterKind::Ty(()))),
+        value,
+    )
+}
+
+#[test]
+fn bound_var_indices() {
+    let innermost = BoundVar::new(DebruijnIndex::INNERMOST, 0);
+    assert_eq!(innermost.shifted_in(), BoundVar::new(DebruijnIndex::ONE, 0));
+    assert_eq!(innermost.shifted_out(), None);
+    assert_eq!(innermost.shifted_in().shifted_out(), Some(innermost));
+    assert_eq!(innermost.index_if_innermost(), Some(0));
+    assert_eq!(innermost.shifted_in().index_if_innermost(), None);
+
+    // Only the variables bound outside of `outer_binder` are shifted.
+    let outer = BoundVar::new(DebruijnIndex::new(2), 1);
+    assert_eq!(
+        outer.shifted_out_to(DebruijnIndex::new(2)),
+        Some(BoundVar::new(DebruijnIndex::INNERMOST, 1))
+    );
+    assert_eq!(outer.shifted_out_to(DebruijnIndex::new(3)), None);
+    assert_eq!(
+        innermost.shifted_in_from(DebruijnIndex::new(2)),
+        BoundVar::new(DebruijnIndex::new(2), 0)
+    );
+}
+
+#[test]
+fn shift_in() {
+    let interner = &ChalkIr;
+    assert_eq!(bound(0, 0).shifted_in(interner), bound(1, 0));
+    assert_eq!(
+        tuple(&[bound(0, 0), bound(1, 1)]).shifted_in(interner),
+        tuple(&[bound(1, 0), bound(2, 1)])
+    );
+    assert_eq!(
+        bound(0, 1).shifted_in_from(interner, DebruijnIndex::new(3)),
+        bound(3, 1)
+    );
+
+    // The variables bound within the value are left alone.
+    assert_eq!(
+        binders(1, tuple(&[bound(0, 0), bound(1, 0)])).shifted_in(interner),
+        binders(1, tuple(&[bound(0, 0), bound(2, 0)]))
+    );
+}
+
+#[test]
+fn shift_out() {
+    let interner = &ChalkIr;
+    assert_eq!(bound(1, 0).shifted_out(interner), Ok(bound(0, 0)));
+    assert_eq!(
+        tuple(&[bound(2, 0), bound(1, 1)]).shifted_out(interner),
+        Ok(tuple(&[bound(1, 0), bound(0, 1)]))
+    );
+    assert_eq!(
+        bound(3, 1).shifted_out_to(interner, DebruijnIndex::new(3)),
+        Ok(bound(0, 1))
+    );
+    assert_eq!(
+        binders(1, tuple(&[bound(0, 0), bound(2, 0)])).shifted_out(interner),
+        Ok(binders(1, tuple(&[bound(0, 0), bound(1, 0)])))
+    );
+
+    // A variable bound by the binder being removed cannot be shifted
+    // out of it.
+    assert_eq!(bound(0, 0).shifted_out(interner), Err(NoSolution));
+    assert_eq!(
+        tuple(&[bound(1, 0), bound(0, 0)]).shifted_out(interner),
+        Err(NoSolution)
+    );
+    assert_eq!(
+        binders(1, bound(1, 0)).shifted_out(interner),
+        Err(NoSolution)
+    );
+}
+
+#[test]
+fn substitute() {
+    let interner = &ChalkIr;
+    let unit = tuple(&[]);
+    let pair = tuple(&[unit.clone(), unit.clone()]);
+
+    // `<X, Y> (Y, X)` with `[(), ((), ())]` is `(((), ()), ())`.
+    let value = binders(2, tuple(&[bound(0, 1), bound(0, 0)]));
+    assert_eq!(
+        value.substitute(
+            interner,
+            &Substitution::from(interner, vec![unit.clone(), pair.clone()])
+        ),
+        tuple(&[pair.clone(), unit.clone()])
+    );
+
+    // The variables bound outside of the binders are shifted out of
+    // them.
+    let value = binders(1, tuple(&[bound(0, 0), bound(1, 3)]));
+    assert_eq!(
+        value.substitute(interner, &Substitution::from(interner, vec![unit.clone()])),
+        tuple(&[unit.clone(), bound(0, 3)])
+    );
+
+    // Within inner binders, the substituted values are shifted in
+    // to remain valid there.
+    let value = binders(1, binders(1, tuple(&[bound(0, 0), bound(1, 0)])));
+    assert_eq!(
+        value.substitute(interner, &Substitution::from(interner, vec![bound(0, 5)])),
+        binders(1, tuple(&[bound(0, 0), bound(1, 5)]))
+    );
+}
@@ -0,0 +1,174 @@
+//! Tests of `Goal::simplify`, which brings goals into a normal form.
+
+use super::*;
+use chalk_ir::{Goal, GoalData, Goals};
+
+const PROGRAM: &str = "
+    struct Foo { }
+    struct Bar { }
+    struct Vec<T> { }
+    trait Clone { }
+";
+
+fn lower(program: &chalk_integration::program::Program, goal: &str) -> Goal<ChalkIr> {
+    chalk_parse::parse_goal(goal)
+        .unwrap()
+        .lower(program)
+        .unwrap()
+}
+
+#[test]
+fn simplify() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::default());
+    let program = db.checked_program().unwrap();
+    for &(goal, expected) in &[
+        // Nested conjunctions and disjunctions are flattened.
+        (
+            "Foo: Clone, (Bar: Clone, (Vec<Foo>: Clone, Vec<Bar>: Clone))",
+            "Foo: Clone, Bar: Clone, Vec<Foo>: Clone, Vec<Bar>: Clone",
+        ),
+        (
+            "or { Foo: Clone; or { Bar: Clone; Vec<Foo>: Clone } }",
+            "or { Foo: Clone; Bar: Clone; Vec<Foo>: Clone }",
+        ),
+        // Quantifiers of the same kind are merged, renumbering the
+        // variables; those of different kinds are not.
+        (
+            "forall<T> { forall<U> { if (T: Clone) { Vec<U>: Clone } } }",
+            "forall<T, U> { if (T: Clone) { Vec<U>: Clone } }",
+        ),
+        (
+            "exists<T> { exists<U> { exists<V> { T = Vec<V>, U = V } } }",
+            "exists<T, U, V> { T = Vec<V>, U = V }",
+        ),
+        (
+            "exists<T> { forall<U> { T = U } }",
+            "exists<T> { forall<U> { T = U } }",
+        ),
+        // Variables bound outside of the merged quantifiers are still
+        // found.
+        (
+            "forall<T> { exists<U> { exists<V> { T = Vec<U>, U = V } } }",
+            "forall<T> { exists<U, V> { T = Vec<U>, U = V } }",
+        ),
+        // Quantifiers are hoisted out of conjunctions, in the order of the
+        // conjuncts, and merged with the quantifiers next to them.
+        (
+            "exists<T> { T: Clone }, Foo: Clone",
+            "exists<T> { T: Clone, Foo: Clone }",
+        ),
+        (
+            "Foo: Clone, exists<T> { T: Clone }, Bar: Clone",
+            "exists<T> { Foo: Clone, T: Clone, Bar: Clone }",
+        ),
+        (
+            "exists<T> { T = Foo }, exists<U> { exists<V> { U = Vec<V> } }",
+            "exists<T, U, V> { T = Foo, U = Vec<V> }",
+        ),
+        (
+            "forall<T> { T: Clone }, exists<U> { U: Clone }",
+            "forall<T> { exists<U> { T: Clone, U: Clone } }",
+        ),
+        // Conjuncts moved into a hoisted quantifier still find the
+        // variables bound outside of it.
+        (
+            "forall<T> { exists<U> { U = Vec<T> }, T: Clone, exists<V> { V = T } }",
+            "forall<T> { exists<U, V> { U = Vec<T>, T: Clone, V = T } }",
+        ),
+        // Implications with no clauses are removed.
+        ("if () { Foo: Clone }", "Foo: Clone"),
+        (
+            "forall<T> { if () { forall<U> { T = U } } }",
+            "forall<T, U> { T = U }",
+        ),
+    ] {
+        let simplified = lower(&program, goal).simplify(&ChalkIr);
+        assert_eq!(simplified, lower(&program, expected), "{}", goal);
+        assert_eq!(simplified.simplify(&ChalkIr), simplified, "{}", goal);
+    }
+}
+
+#[test]
+fn trivially_true_goals_are_removed() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::default());
+    let program = db.checked_program().unwrap();
+    let interner = &ChalkIr;
+    let true_goal = Goal::all(interner, vec![]);
+    let foo_clone = lower(&program, "Foo: Clone");
+
+    let goal = GoalData::All(Goals::from(
+        interner,
+        vec![true_goal.clone(), foo_clone.clone(), true_goal.clone()],
+    ))
+    .intern(interner);
+    assert_eq!(goal.simplify(interner), foo_clone);
+
+    let goal = GoalData::Not(
+        GoalData::All(Goals::from(interner, vec![true_goal.clone(), true_goal])).intern(interner),
+    )
+    .intern(interner);
+    assert_eq!(
+        goal.simplify(interner),
+        GoalData::Not(Goal::all(interner, vec![])).intern(interner)
+    );
+}
+
+#[test]
+fn simplified_goals_have_the_same_solutions() {
+    let db = ChalkDatabase::with(
+        "
+        struct Foo { }
+        struct Vec<T> { }
+        trait Clone { }
+        impl Clone for Foo { }
+        impl<T> Clone for Vec<T> where T: Clone { }
+        ",
+        SolverChoice::default(),
+    );
+    let program = db.checked_program().unwrap();
+    for &goal in &[
+        "exists<T> { exists<U> { T = Vec<U>, (U: Clone, U = Foo) } }",
+        "forall<T> { forall<U> { if (T: Clone) { if (U: Clone) { Vec<T>: Clone, Vec<U>: Clone } } } }",
+        "exists<T> { or { T: Clone; or { T = Foo; T = Vec<Foo> } } }",
+    ] {
+        let goal = lower(&program, goal);
+        let solve = |goal: Goal<ChalkIr>| {
+            SolverChoice::default()
+                .into_solver()
+                .solve(&*program, &goal.into_peeled_goal(&ChalkIr))
+        };
+        assert_eq!(solve(goal.simplify(&ChalkIr)), solve(goal));
+    }
+}
+
+#[test]
+fn hoisted_goals_have_the_same_outcome() {
+    // The existentials hoisted to the top of a goal become variables of
+    // the query, so they show up in the substitution of its solution;
+    // whether the goal has a unique solution, or none, does not change.
+    let db = ChalkDatabase::with(
+        "
+        struct Foo { }
+        struct Vec<T> { }
+        trait Clone { }
+        impl Clone for Foo { }
+        impl<T> Clone for Vec<T> where T: Clone { }
+        ",
+        SolverChoice::default(),
+    );
+    let program = db.checked_program().unwrap();
+    for &goal in &[
+        "exists<T> { T = Foo }, Vec<Foo>: Clone, exists<U> { U = Vec<Foo>, U: Clone }",
+        "forall<T> { if (T: Clone) { Vec<T>: Clone } }, exists<U> { U = Foo }",
+        "exists<T> { T = Vec<Foo> }, Foo = Vec<Foo>",
+    ] {
+        let goal = lower(&program, goal);
+        let solve = |goal: Goal<ChalkIr>| {
+            SolverChoice::default()
+                .into_solver()
+                .solve(&*program, &goal.into_peeled_goal(&ChalkIr))
+                .map(|solution| solution.is_unique())
+        };
+        assert_eq!(solve(goal.simplify(&ChalkIr)), solve(goal));
+    }
+}
@@ -0,0 +1,81 @@
+//! Tests related to the statistics of the work done by the solver.
+
+use super::*;
+use chalk_solve::SolverStats;
+
+const PROGRAM: &str = "
+    struct Vec<T> { }
+    struct Foo { }
+    trait Clone { }
+    impl Clone for Foo { }
+    impl<T> Clone for Vec<T> where T: Clone { }
+";
+
+#[test]
+fn stats_recursive() {
+    let goals = ["Vec<Vec<Foo>>: Clone", "Vec<Foo>: Clone"];
+    with_lowered_goals(PROGRAM, SolverChoice::recursive(), &goals, |db, goals| {
+        // `Vec<Vec<Foo>>: Clone` needs `Vec<Foo>: Clone`, which needs
+        // `Foo: Clone`, each of which is tried with its impl.
+        db.solve(&goals[0]);
+        let first = db.last_stats();
+        assert!(first.subgoals >= 3);
+        assert!(first.unifications >= 3);
+        assert!(first.max_depth >= 3);
+        assert_eq!(first.cache_hits, 0);
+
+        // `Vec<Foo>: Clone` was cached while solving the first goal.
+        db.solve(&goals[1]);
+        assert_eq!(
+            db.last_stats(),
+            SolverStats {
+                subgoals: 0,
+                unifications: 0,
+                cache_hits: 1,
+                max_depth: 0,
+            }
+        );
+    });
+}
+
+#[test]
+fn stats_deeper_goals_do_more_work() {
+    let goals = ["Vec<Foo>: Clone", "Vec<Vec<Foo>>: Clone"];
+    let solver_choice = SolverChoice::recursive().caching(false);
+    with_lowered_goals(PROGRAM, solver_choice, &goals, |db, goals| {
+        db.solve(&goals[0]);
+        let shallow = db.last_stats();
+        db.solve(&goals[1]);
+        let deep = db.last_stats();
+        assert!(deep.subgoals > shallow.subgoals);
+        assert!(deep.unifications > shallow.unifications);
+        assert_eq!(deep.max_depth, shallow.max_depth + 1);
+    });
+}
+
+#[test]
+fn stats_slg() {
+    let goals = ["Vec<Vec<Foo>>: Clone", "Vec<Foo>: Clone"];
+    with_lowered_goals(PROGRAM, SolverChoice::slg_default(), &goals, |db, goals| {
+        // Tables are created for `Vec<Vec<Foo>>: Clone`, `Vec<Foo>: Clone`
+        // and `Foo: Clone`, among others, and the clauses of each are tried.
+        db.solve(&goals[0]);
+        let first = db.last_stats();
+        assert!(first.subgoals >= 3);
+        assert!(first.unifications >= 3);
+        assert!(first.max_depth >= 3);
+
+        // The table of `Vec<Foo>: Clone` was created while solving the first
+        // goal, and is reused.
+        db.solve(&goals[1]);
+        assert_eq!(
+            db.last_stats(),
+            SolverStats {
+                subgoals: 0,
+                unifications: 0,
+                cache_hits: 1,
+                max_depth: 0,
+            }
+        );
+    });
+}
@@ -0,0 +1,122 @@
+//! Tests related to tracing the steps of the solver.
+
+use super::*;
+use chalk_solve::trace::TraceEvent;
+use std::sync::{Arc, Mutex};
+
+const PROGRAM: &str = "
+    struct Vec<T> { }
+    struct Foo { }
+    trait Clone { }
+    impl Clone for Foo { }
+    impl<T> Clone for Vec<T> where T: Clone { }
+";
+
+/// Sets a trace sink on `db` that records the printed events, which are
+/// taken out of the returned list as the tests check them.
+fn record_events(db: &ChalkDatabase) -> Arc<Mutex<Vec<String>>> {
+    let events = Arc::new(Mutex::new(vec![]));
+    let sink_events = events.clone();
+    db.set_trace_sink(Some(Box::new(move |event: TraceEvent<ChalkIr>| {
+        sink_events
+            .lock()
+            .unwrap()
+            .push(event.display(&ChalkIr).to_string())
+    })));
+    events
+}
+
+#[test]
+fn trace_goals_and_clauses() {
+    let goals = ["Foo: Clone"];
+    with_lowered_goals(PROGRAM, SolverChoice::recursive(), &goals, |db, goals| {
+        let events = record_events(db);
+        db.solve(&goals[0]);
+        let events = events.lock().unwrap();
+        assert_eq!(
+            events[0],
+            "goal entered at depth 0: Implemented(Foo: Clone)"
+        );
+        assert!(events.contains(
+            &"clause tried: Implemented(Foo: Clone) with for<> Implemented(Foo: Clone)".to_string()
+        ));
+        assert_eq!(
+            events.last().unwrap(),
+            "goal solved: Implemented(Foo: Clone) => Unique; substitution [], lifetime constraints []"
+        );
+    });
+}
+
+#[test]
+fn trace_unification_failed() {
+    let goals = ["forall<T> { if (T: Clone) { Vec<T>: Clone } }"];
+    with_lowered_goals(PROGRAM, SolverChoice::recursive(), &goals, |db, goals| {
+        let events = record_events(db);
+        db.solve(&goals[0]);
+        assert!(events.lock().unwrap().contains(
+            &"unification failed: Implemented(!1_0: Clone) with for<> Implemented(Foo: Clone)"
+                .to_string()
+        ));
+    });
+}
+
+#[test]
+fn trace_cache_hit() {
+    let goals = ["Vec<Foo>: Clone"];
+    with_lowered_goals(PROGRAM, SolverChoice::recursive(), &goals, |db, goals| {
+        let events = record_events(db);
+        db.solve(&goals[0]);
+        assert!(events.lock().unwrap().drain(..).count() > 1);
+        db.solve(&goals[0]);
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec!["cache hit: Implemented(Vec<Foo>: Clone) => Unique; substitution [], lifetime constraints []"]
+        );
+    });
+}
+
+#[test]
+fn trace_slg_goals_and_clauses() {
+    let goals = ["Vec<Foo>: Clone"];
+    with_lowered_goals(PROGRAM, SolverChoice::slg_default(), &goals, |db, goals| {
+        let events = record_events(db);
+        db.solve(&goals[0]);
+        let events = events.lock().unwrap();
+        assert_eq!(
+            events[0],
+            "goal entered at depth 0: Implemented(Vec<Foo>: Clone)"
+        );
+        assert!(events.contains(&"goal entered at depth 2: Implemented(Foo: Clone)".to_string()));
+        assert!(events.contains(
+            &"clause tried: Implemented(Foo: Clone) with for<> Implemented(Foo: Clone)".to_string()
+        ));
+    });
+}
+
+#[test]
+fn trace_slg_unification_failed() {
+    let goals = ["forall<T> { if (T: Clone) { Vec<T>: Clone } }"];
+    with_lowered_goals(PROGRAM, SolverChoice::slg_default(), &goals, |db, goals| {
+        let events = record_events(db);
+        db.solve(&goals[0]);
+        assert!(events.lock().unwrap().contains(
+            &"unification failed: Implemented(!1_0: Clone) with for<> Implemented(Foo: Clone)"
+                .to_string()
+        ));
+    });
+}
+
+#[test]
+fn trace_slg_table_reused() {
+    let goals = ["Vec<Foo>: Clone"];
+    with_lowered_goals(PROGRAM, SolverChoice::slg_default(), &goals, |db, goals| {
+        let events = record_events(db);
+        db.solve(&goals[0]);
+        assert!(events.lock().unwrap().drain(..).count() > 1);
+        db.solve(&goals[0]);
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec!["table reused: Implemented(Vec<Foo>: Clone)"]
+        );
+    });
+}
@@ -40,6 +40,46 @@ fn region_equality() {
     }
 }
 
+/// `'static` outlives every lifetime, so equating it with a placeholder
+/// only requires the placeholder to outlive it.
+#[test]
+fn static_lifetime() {
+    test! {
+        program {
+            trait Eq<T> { }
+            impl<T> Eq<T> for T { }
+
+            struct Unit { }
+            struct Ref<'a, T> { }
+        }
+
+        goal {
+            Ref<'static, Unit>: Eq<Ref<'static, Unit>>
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            forall<'a> {
+                Ref<'a, Unit>: Eq<Ref<'static, Unit>>
+            }
+        } yields {
+            "Unique; substitution [],
+                     lifetime constraints \
+                     [InEnvironment { environment: Env([]), goal: '!1_0: 'static }]
+                     "
+        }
+
+        goal {
+            exists<'a> {
+                Ref<'a, Unit>: Eq<Ref<'static, Unit>>
+            }
+        } yields {
+            "Unique; substitution [?0 := 'static], lifetime constraints []"
+        }
+    }
+}
+
 /// Tests of region equality and "foralls" -- we generate contraints that are sometimes
 /// not solvable.
 #[test]
@@ -54,29 +94,24 @@ fn forall_equality() {
         }
 
         goal {
-            // A valid equality; we get back a series of solvable
-            // region constraints, since each region variable must
-            // refer to exactly one placeholder region, and they are
-            // all in a valid universe to do so (universe 4).
+            // A valid equality; each region variable must refer to
+            // exactly one placeholder region, and they are all in a
+            // valid universe to do so (universe 4).
             for<'a, 'b> fn(Ref<'a, Ref<'b, Unit>>): Eq<for<'c, 'd> fn(Ref<'c, Ref<'d, Unit>>)>
         } yields {
             "Unique; substitution [], lifetime constraints []"
         }
 
         goal {
-            // Note: this equality is false, but we get back successful;
-            // this is because the region constraints are unsolvable.
-            //
-            // Note that `?0` (in universe 2) must be equal to both
-            // `!1_0` and `!1_1`, which of course it cannot be.
+            // Note: this equality is false, because `?0` (in universe
+            // 2) must be equal to both `!1_0` and `!1_1`, which of course
+            // it cannot be. The region constraints that would require
+            // it relate placeholders of the binders of the `fn` types,
+            // which cannot escape the equality.
             for<'a, 'b> fn(Ref<'a, Ref<'b, Ref<'a, Unit>>>): Eq<
                 for<'c, 'd> fn(Ref<'c, Ref<'d, Ref<'d, Unit>>>)>
         } yields {
-            "Unique; substitution [], lifetime constraints [\
-            InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }, \
-            InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }, \
-            InEnvironment { environment: Env([]), goal: '!2_0: '!2_1 }, \
-            InEnvironment { environment: Env([]), goal: '!2_1: '!2_0 }]"
+            "No possible solution"
         }
     }
 }
@@ -87,9 +122,9 @@ fn unify_quantified_lifetimes() {
         program {
         }
 
-        // Check that `'a` (here, `'^0.0`) is not unified
-        // with `'!1_0`, because they belong to incompatible
-        // universes.
+        // Check that `'a` is not unified with `'b`, because they belong
+        // to incompatible universes: `'a` would have to be equal to every
+        // lifetime `'b`.
         goal {
             exists<'a> {
                 forall<'b> {
@@ -97,13 +132,7 @@ fn unify_quantified_lifetimes() {
                 }
             }
         } yields {
-            "Unique; for<?U0> { \
-             substitution [?0 := '^0.0], \
-             lifetime constraints [\
-             InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, \
-             InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }\
-             ] \
-             }"
+            "No possible solution"
         }
 
         // Similar to the previous test, but indirect.
@@ -116,23 +145,8 @@ fn unify_quantified_lifetimes() {
                     }
                 }
             }
-        } yields[SolverChoice::slg(10, None)] {
-            "Unique; for<?U0> { \
-             substitution [?0 := '^0.0, ?1 := '!1_0], \
-             lifetime constraints [\
-             InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, \
-             InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }\
-             ] \
-             }"
-        } yields[SolverChoice::recursive()] {
-            // only difference is in the value of ?1, which is equivalent
-            "Unique; for<?U0> { \
-             substitution [?0 := '^0.0, ?1 := '^0.0], \
-             lifetime constraints [\
-             InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, \
-             InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }\
-             ] \
-             }"
+        } yields {
+            "No possible solution"
         }
     }
 }
@@ -145,8 +159,9 @@ fn equality_binder() {
         }
 
         // Check that `'a` (here, `'?0`) is not unified
-        // with `'!1_0`, because they belong to incompatible
-        // universes.
+        // with `'!2_0`, because they belong to incompatible
+        // universes, and that the placeholder does not escape
+        // into the solution through region constraints.
         goal {
             forall<T> {
                 exists<'a> {
@@ -154,13 +169,7 @@ fn equality_binder() {
                 }
             }
         } yields {
-            "Unique; for<?U1> { \
-                 substitution [?0 := '^0.0], \
-                 lifetime constraints [\
-                 InEnvironment { environment: Env([]), goal: '!2_0: '^0.0 }, \
-                 InEnvironment { environment: Env([]), goal: '^0.0: '!2_0 }\
-                 ] \
-             }"
+            "No possible solution"
         }
     }
 }
@@ -172,22 +181,18 @@ fn equality_binder2() {
             struct Ref<'a, 'b> { }
         }
 
+        // Both equalities would require `'b = 'c` for placeholders
+        // `'b` and `'c`.
         goal {
             for<'b, 'c> fn(Ref<'b, 'c>) = for<'a> fn(Ref<'a, 'a>)
         } yields {
-            "Unique; substitution [], lifetime constraints [\
-             InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }, \
-             InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }\
-             ]"
+            "No possible solution"
         }
 
         goal {
             for<'a> fn(Ref<'a, 'a>) = for<'b, 'c> fn(Ref<'b, 'c>)
         } yields {
-            "Unique; substitution [], lifetime constraints [\
-             InEnvironment { environment: Env([]), goal: '!2_0: '!2_1 }, \
-             InEnvironment { environment: Env([]), goal: '!2_1: '!2_0 }\
-             ]"
+            "No possible solution"
         }
     }
 }
@@ -325,11 +330,130 @@ fn quantified_types() {
             forall<'a> { fn(fn1<'a>): Foo }
         } yields {
             // Lifetime constraints are unsatisfiable
-            "Unique; substitution [], \
-            lifetime constraints [\
-            InEnvironment { environment: Env([]), goal: '!1_0: '!3_0 }, \
-            InEnvironment { environment: Env([]), goal: '!3_0: '!1_0 }\
-            ]"
+            "No possible solution"
+        }
+    }
+}
+
+#[test]
+fn quantified_types_over_types() {
+    test! {
+        program {
+            trait Foo { }
+            struct Vec<T> { }
+            struct Unit { }
+            impl Foo for for<T> fn(Vec<T>) { }
+        }
+
+        goal {
+            for<T> fn(Vec<T>): Foo
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            for<U> fn(Vec<U>) = for<T> fn(Vec<T>)
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            for<T> fn(Vec<T>) = fn(Vec<Unit>)
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            for<T> fn(T) = for<'a> fn(Vec<Unit>)
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            exists<X> { for<T> fn(Vec<T>) = for<T> fn(X) }
+        } yields {
+            "No possible solution"
+        }
+    }
+}
+
+/// `T = U` goals invoke the unifier directly, without going through
+/// any program clauses.
+#[test]
+fn unify_types_directly() {
+    test! {
+        program {
+            struct Vec<T> { }
+            struct Foo { }
+        }
+
+        goal {
+            exists<T> { Vec<T> = Vec<i32> }
+        } yields {
+            "Unique; substitution [?0 := Int(I32)]"
+        }
+
+        goal {
+            exists<T, U> { Vec<T> = U }
+        } yields {
+            "Unique; for<?U0> { substitution [?0 := ^0.0, ?1 := Vec<^0.0>]"
+        }
+
+        goal {
+            exists<T> { Vec<T> = T }
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            Vec<Foo> = Vec<i32>
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            forall<T> { exists<U> { Vec<U> = Vec<T> } }
+        } yields {
+            "Unique; substitution [?0 := !1_0]"
+        }
+    }
+}
+
+#[test]
+fn occurs_check() {
+    test! {
+        program {
+            struct Vec<T> { }
+            struct Ref<'a, T> { }
+            struct Foo { }
+            trait Iterator { type Item; }
+            impl Iterator for Foo { type Item = Foo; }
+        }
+
+        goal {
+            exists<T, U> { T = Vec<U>, U = Vec<T> }
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            exists<T> { T = (T, T) }
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            exists<T> { T = for<'a> fn(Ref<'a, T>) }
+        } yields {
+            "No possible solution"
+        }
+
+        // An alias is not a cycle: `T` can be a type that normalizes
+        // to itself.
+        goal {
+            exists<T> { T = <T as Iterator>::Item }
+        } yields {
+            "Unique; substitution [?0 := Foo]"
         }
     }
 }
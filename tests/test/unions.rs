@@ -0,0 +1,69 @@
+//! Tests for `union` types, which behave like structs for the purposes
+//! of trait solving.
+
+use super::*;
+
+#[test]
+fn union_auto_traits() {
+    test! {
+        program {
+            #[auto] trait Send { }
+            #[manually_drop] struct ManuallyDrop<T> { value: T }
+            struct Foo { }
+            struct Bar { }
+            impl !Send for Bar { }
+
+            union U<T> {
+                a: ManuallyDrop<Foo>,
+                b: ManuallyDrop<T>
+            }
+        }
+
+        goal {
+            U<Foo>: Send
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            U<Bar>: Send
+        } yields {
+            "No possible solution"
+        }
+    }
+}
+
+#[test]
+fn union_copy() {
+    test! {
+        program {
+            #[lang(copy)] trait Copy { }
+            #[manually_drop] struct ManuallyDrop<T> { value: T }
+            struct Foo { }
+            impl Copy for u32 { }
+
+            union U {
+                a: u32,
+                b: ManuallyDrop<Foo>
+            }
+
+            union V {
+                a: u32
+            }
+
+            impl Copy for V { }
+        }
+
+        goal {
+            V: Copy
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            U: Copy
+        } yields {
+            "No possible solution"
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! Tests related to the universes of placeholders introduced by `forall`
+//! goals, which must not escape into the solutions of the goals around
+//! them.
+
+use super::*;
+
+#[test]
+fn placeholder_does_not_escape_through_equality() {
+    test! {
+        program {
+            struct Foo { }
+        }
+
+        // `'a` would have to be equal to every lifetime `'b`. The
+        // `Foo = Foo` keeps the `forall` from being peeled into the
+        // root universes of the goal.
+        goal {
+            exists<'a> {
+                Foo = Foo,
+                forall<'b> { 'a = 'b }
+            }
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            exists<'a> {
+                Foo = Foo,
+                forall<'b> {
+                    exists<'c> {
+                        'a = 'c,
+                        'c = 'b
+                    }
+                }
+            }
+        } yields {
+            "No possible solution"
+        }
+    }
+}
+
+#[test]
+fn placeholder_does_not_escape_through_outlives() {
+    test! {
+        program {
+            struct Foo { }
+            struct Ref<'a, T> { }
+        }
+
+        // Outliving every lifetime is outliving `'static`.
+        goal {
+            exists<'a> {
+                Foo = Foo,
+                forall<'b> { 'a: 'b }
+            }
+        } yields {
+            "Unique; for<?U0> { substitution [?0 := '^0.0], lifetime constraints [InEnvironment { environment: Env([]), goal: '^0.0: 'static }] }"
+        }
+
+        goal {
+            exists<T> {
+                Foo = Foo,
+                forall<'b> { T: 'b }
+            }
+        } yields {
+            "Unique; for<?U0> { substitution [?0 := ^0.0], lifetime constraints [InEnvironment { environment: Env([]), goal: ^0.0: 'static }] }"
+        }
+
+        // But no lifetime outlives every lifetime.
+        goal {
+            exists<'a> {
+                Foo = Foo,
+                forall<'b> { 'b: 'a }
+            }
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            exists<'a> {
+                Foo = Foo,
+                forall<'b> { Ref<'b, Foo>: 'a }
+            }
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            forall<'a> {
+                Foo = Foo,
+                forall<'b> { 'b: 'a }
+            }
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            Foo = Foo,
+            forall<'b> { 'b: 'b, 'static: 'b }
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+    }
+}
+
+#[test]
+fn placeholder_does_not_escape_through_impls() {
+    test! {
+        program {
+            trait Eq<T> { }
+            struct Foo { }
+            struct Ref<'a, T> { }
+            impl<T> Eq<T> for T { }
+        }
+
+        // `Ref<'a, Foo>` is not equal to `Ref<'b, Foo>` for every `'b`,
+        // even though the impl of `Eq` only produces region
+        // constraints.
+        goal {
+            exists<'a> {
+                Foo = Foo,
+                forall<'b> { Ref<'a, Foo>: Eq<Ref<'b, Foo>> }
+            }
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            forall<'a> {
+                Foo = Foo,
+                forall<'b> { Ref<'a, Foo>: Eq<Ref<'b, Foo>> }
+            }
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            forall<'a> {
+                Foo = Foo,
+                forall<'b> { Ref<'a, Foo>: Eq<Ref<'a, Foo>> }
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+    }
+}
+
+#[test]
+fn placeholder_does_not_escape_from_peeled_goals() {
+    test! {
+        program {
+            struct Ref<'a> { }
+        }
+
+        // Peeling lifts the `forall` into the universes of the goal, but
+        // the existential lifetime still cannot name its placeholder.
+        goal {
+            exists<'x> {
+                forall<'a> { 'x: 'a }
+            }
+        } yields[SolverChoice::recursive()] {
+            "Unique; for<?U0> { substitution [?0 := '^0.0], lifetime constraints [InEnvironment { environment: Env([]), goal: '^0.0: 'static }] }"
+        } yields[SolverChoice::slg_default()] {
+            "Unique; for<?U0> { substitution [?0 := '^0.0], lifetime constraints [InEnvironment { environment: Env([]), goal: '^0.0: 'static }] }"
+        }
+
+        goal {
+            exists<'b> {
+                forall<'a> { Ref<'a> = Ref<'b> }
+            }
+        } yields[SolverChoice::recursive()] {
+            "No possible solution"
+        } yields[SolverChoice::slg_default()] {
+            "No possible solution"
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Tests of `Program::validate`, which checks that programs are
+//! well-formed as IR.
+
+use chalk_integration::builder::ProgramBuilder;
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::query::LoweringDatabase;
+use chalk_integration::validate::InvalidIr;
+use chalk_integration::{Identifier, RawId};
+use chalk_ir::{BoundVar, DebruijnIndex, TyData};
+use chalk_solve::SolverChoice;
+
+#[test]
+fn lowered_program_is_valid() {
+    let db = ChalkDatabase::with(
+        "
+        struct Vec<T> { }
+        trait Iterator { type Item; }
+        trait Foo<'a> { }
+        impl<T> Iterator for Vec<T> { type Item = T; }
+        impl<'a, T> Foo<'a> for Vec<T> where <Vec<T> as Iterator>::Item: Iterator { }
+        ",
+        SolverChoice::default(),
+    );
+    let program = db.program_ir().unwrap();
+    assert_eq!(program.validate(), vec![]);
+}
+
+#[test]
+fn wrong_number_of_parameters() {
+    let program = ProgramBuilder::new()
+        .struct_("Vec", 1)
+        .trait_("Clone", 0)
+        .impl_("Clone", 0, |b, _| (vec![b.ty("Vec", &[])], vec![]))
+        .build();
+    let vec_id = program.struct_ids[&Identifier::from("Vec")].0;
+    assert_eq!(
+        program.validate(),
+        vec![InvalidIr::WrongNumberOfParameters {
+            item: "impl #2".to_string(),
+            id: vec_id,
+            expected: 1,
+            actual: 0,
+        }]
+    );
+}
+
+#[test]
+fn bound_var_out_of_range() {
+    let var = BoundVar::new(DebruijnIndex::INNERMOST, 1);
+    let program = ProgramBuilder::new()
+        .trait_("Clone", 0)
+        .impl_("Clone", 1, |_, _| {
+            (
+                vec![TyData::BoundVar(var).intern(&chalk_integration::interner::ChalkIr)],
+                vec![],
+            )
+        })
+        .build();
+    assert_eq!(
+        program.validate(),
+        vec![InvalidIr::BoundVarOutOfRange {
+            item: "impl #1".to_string(),
+            var,
+            num_params: 1,
+        }]
+    );
+}
+
+#[test]
+fn escaping_bound_var() {
+    let var = BoundVar::new(DebruijnIndex::ONE, 0);
+    let program = ProgramBuilder::new()
+        .trait_("Clone", 0)
+        .impl_("Clone", 0, |_, _| {
+            (
+                vec![TyData::BoundVar(var).intern(&chalk_integration::interner::ChalkIr)],
+                vec![],
+            )
+        })
+        .build();
+    let errors = program.validate();
+    assert_eq!(
+        errors,
+        vec![InvalidIr::EscapingBoundVar {
+            item: "impl #1".to_string(),
+            var,
+        }]
+    );
+    assert_eq!(
+        errors[0].to_string(),
+        "in impl #1: bound variable ^1.0 escapes the item"
+    );
+}
+
+#[test]
+fn unknown_id() {
+    let mut program = ProgramBuilder::new()
+        .struct_("Foo", 0)
+        .trait_("Clone", 0)
+        .impl_("Clone", 0, |b, _| (vec![b.ty("Foo", &[])], vec![]))
+        .build();
+    let foo_id = program.struct_ids[&Identifier::from("Foo")];
+    program.struct_kinds.remove(&foo_id);
+    let unknown = RawId {
+        index: foo_id.0.index,
+    };
+    assert_eq!(
+        program.validate(),
+        vec![InvalidIr::UnknownId {
+            item: "impl #2".to_string(),
+            id: unknown,
+        }]
+    );
+}
@@ -36,6 +36,61 @@ fn struct_wf() {
     }
 }
 
+#[test]
+fn trait_ref_wf() {
+    test! {
+        program {
+            trait Eq { }
+            trait Ord where Self: Eq { }
+            trait Hash<T> where T: Eq { }
+
+            struct Foo { }
+            struct Bar { }
+
+            impl Eq for Foo { }
+            impl Ord for Foo { }
+        }
+
+        goal {
+            WellFormed(Foo: Ord)
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            WellFormed(Bar: Ord)
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            WellFormed(Bar: Hash<Foo>)
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            forall<T> {
+                if (Bar: Hash<T>) {
+                    WellFormed(Bar: Hash<T>)
+                }
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            forall<T> {
+                if (T: Ord) {
+                    WellFormed(T: Ord)
+                }
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+    }
+}
+
 #[test]
 fn recursive_where_clause_on_type() {
     test! {
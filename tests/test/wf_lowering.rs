@@ -1,4 +1,7 @@
+use chalk_integration::db::ChalkDatabase;
 use chalk_integration::query::LoweringDatabase;
+use chalk_solve::wf::WfError;
+use chalk_solve::SolverChoice;
 
 #[test]
 fn well_formed_trait_decl() {
@@ -820,3 +823,212 @@ fn drop_constraints() {
         }
     }
 }
+
+#[test]
+fn check_wf_reports_every_ill_formed_impl() {
+    let db = ChalkDatabase::with(
+        "
+        trait Clone { }
+        trait Copy where Self: Clone { }
+        trait PartialEq { }
+        trait Eq where Self: PartialEq { }
+
+        struct Foo { }
+        struct Bar { }
+
+        impl Copy for Foo { }
+        impl Clone for Bar { }
+        impl Copy for Bar { }
+        impl Eq for Bar { }
+        ",
+        SolverChoice::default(),
+    );
+    let program = db.program_ir().unwrap();
+
+    let mut failing_traits: Vec<_> = program
+        .check_wf(SolverChoice::default())
+        .into_iter()
+        .map(|(_, error)| match error {
+            WfError::IllFormedTraitImpl(trait_id) => program
+                .trait_ids
+                .iter()
+                .find(|(_, &id)| id == trait_id)
+                .map(|(name, _)| name.to_string())
+                .unwrap(),
+            error => panic!("unexpected error: {}", error),
+        })
+        .collect();
+    failing_traits.sort();
+
+    assert_eq!(failing_traits, vec!["Copy", "Eq"]);
+}
+
+#[test]
+fn checked_program_reports_every_ill_formed_impl() {
+    lowering_error! {
+        program {
+            trait Clone { }
+            trait Copy where Self: Clone { }
+            trait PartialEq { }
+            trait Eq where Self: PartialEq { }
+
+            struct Foo { }
+
+            impl Copy for Foo { }
+            impl Eq for Foo { }
+        } error_msg {
+            "trait impl for `Copy` does not meet well-formedness requirements\n\
+             trait impl for `Eq` does not meet well-formedness requirements"
+        }
+    }
+}
+
+#[test]
+fn impl_method_signatures() {
+    lowering_success! {
+        program {
+            trait Clone { }
+            trait Copy where Self: Clone { }
+            struct Foo { }
+
+            trait Iterator {
+                type Item;
+                fn next(&mut self) -> <Self as Iterator>::Item;
+                fn cloned<U>(&self, U) -> U where U: Copy;
+            }
+
+            impl Iterator for Foo {
+                type Item = u32;
+                fn next(&mut self) -> u32;
+                // The impl may require less than the trait.
+                fn cloned<V>(&self, V) -> V where V: Clone;
+            }
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Clone { }
+            trait Copy where Self: Clone { }
+            struct Foo { }
+
+            trait Bar {
+                fn bar<U>(&self, U) where U: Clone;
+            }
+
+            impl Bar for Foo {
+                fn bar<U>(&self, U) where U: Copy;
+            }
+        } error_msg {
+            "method `bar` in trait impl for `Bar` does not match the trait's signature"
+        }
+    }
+
+    lowering_error! {
+        program {
+            struct Foo { }
+
+            trait Bar {
+                fn bar(&self) -> Self;
+            }
+
+            impl Bar for Foo {
+                fn bar(&self) -> u32;
+            }
+        } error_msg {
+            "method `bar` in trait impl for `Bar` does not match the trait's signature"
+        }
+    }
+
+    lowering_error! {
+        program {
+            struct Foo { }
+
+            trait Bar {
+                fn bar(&self);
+            }
+
+            impl Bar for Foo {
+                fn bar(&mut self);
+            }
+        } error_msg {
+            "method `bar` in trait impl for `Bar` does not match the trait's signature"
+        }
+    }
+
+    lowering_error! {
+        program {
+            struct Foo { }
+
+            trait Bar { }
+
+            impl Bar for Foo {
+                fn bar(&self);
+            }
+        } error_msg {
+            "method `bar` in trait impl for `Bar` does not match the trait's signature"
+        }
+    }
+}
+
+#[test]
+fn union_fields() {
+    lowering_success! {
+        program {
+            #[lang(copy)] trait Copy { }
+            #[manually_drop] struct ManuallyDrop<T> { value: T }
+            struct Foo { }
+            struct Vec<T> { }
+            impl Copy for u32 { }
+
+            union Bar<T> {
+                a: u32,
+                b: ManuallyDrop<Vec<T>>
+            }
+        }
+    }
+
+    lowering_error! {
+        program {
+            #[lang(copy)] trait Copy { }
+            struct Vec<T> { }
+
+            union Bar<T> {
+                a: u32,
+                b: Vec<T>
+            }
+        } error_msg {
+            "type declaration `Bar` does not meet well-formedness requirements"
+        }
+    }
+
+    // A union with a `ManuallyDrop` field that is not `Copy` cannot be `Copy`.
+    lowering_error! {
+        program {
+            #[lang(copy)] trait Copy { }
+            #[manually_drop] struct ManuallyDrop<T> { value: T }
+            struct Foo { }
+            impl Copy for u32 { }
+
+            union Bar {
+                a: u32,
+                b: ManuallyDrop<Foo>
+            }
+
+            impl Copy for Bar { }
+        } error_msg {
+            "trait impl for `Copy` does not meet well-formedness requirements"
+        }
+    }
+
+    // Without a `Copy` trait, only `ManuallyDrop`-like fields are allowed.
+    lowering_error! {
+        program {
+            union Bar {
+                a: u32
+            }
+        } error_msg {
+            "type declaration `Bar` does not meet well-formedness requirements"
+        }
+    }
+}
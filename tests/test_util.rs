@@ -11,7 +11,8 @@ macro_rules! lowering_success {
         )
         .checked_program();
         if let Err(ref e) = result {
-            println!("lowering error: {}", e);
+            let text = &program_text[1..program_text.len() - 1];
+            println!("{}", e.render("program", text));
         }
         assert!(result.is_ok());
     };
@@ -29,6 +30,11 @@ macro_rules! lowering_error {
         .checked_program()
         .unwrap_err();
         let expected = $expected;
-        assert_eq!(error.to_string(), expected.to_string());
+        assert_eq!(
+            error.to_string(),
+            expected.to_string(),
+            "\n{}",
+            error.render("program", &program_text[1..program_text.len() - 1])
+        );
     };
 }